@@ -0,0 +1,139 @@
+#ifndef GO_GAME_H
+#define GO_GAME_H
+
+/* Generated with cbindgen from ffi/src/lib.rs; do not edit by hand. */
+
+#include <stdarg.h>
+#include <stdbool.h>
+#include <stdint.h>
+#include <stdlib.h>
+
+typedef enum GoRules {
+  GO_RULES_JAPANESE,
+  GO_RULES_CHINESE,
+} GoRules;
+
+// What is on a point, or whose turn it is.
+typedef enum GoStone {
+  GO_STONE_EMPTY,
+  GO_STONE_BLACK,
+  GO_STONE_WHITE,
+} GoStone;
+
+// How a call went.
+typedef enum GoStatus {
+  GO_STATUS_OK,
+  GO_STATUS_OCCUPIED,
+  GO_STATUS_SUICIDE,
+  GO_STATUS_KO,
+  GO_STATUS_OFF_BOARD,
+  GO_STATUS_INVALID,
+} GoStatus;
+
+// A game from its starting position, with the moves played on it.
+typedef struct GoGame GoGame;
+
+typedef struct GoPoint {
+  uint32_t row;
+  uint32_t col;
+} GoPoint;
+
+typedef struct GoScore {
+  float black;
+  float white;
+} GoScore;
+
+#ifdef __cplusplus
+extern "C" {
+#endif // __cplusplus
+
+// A new game with the handicap stones on the usual points, or null for a size out of range.
+// Black plays first, or White in a handicap game.
+struct GoGame *go_game_new(uint32_t size, float komi, enum GoRules rules, uint32_t handicap);
+
+// Reads the main line of an SGF record, or gives null for a record that cannot be read or has
+// an illegal move.
+//
+// # Safety
+// `text` must be null or a NUL-terminated string.
+struct GoGame *go_game_from_sgf(const char *text);
+
+// # Safety
+// `game` must be null or a game from this library that has not been freed.
+void go_game_free(struct GoGame *game);
+
+// The width of the board, or 0 for a null game.
+//
+// # Safety
+// `game` must be null or a live game.
+uint32_t go_game_size(const struct GoGame *game);
+
+// Whose turn it is, or `GO_STONE_EMPTY` for a null game.
+//
+// # Safety
+// `game` must be null or a live game.
+enum GoStone go_game_to_move(const struct GoGame *game);
+
+// The number of moves played, passes included.
+//
+// # Safety
+// `game` must be null or a live game.
+uint32_t go_game_move_count(const struct GoGame *game);
+
+// What is on a point; `GO_STONE_EMPTY` off the board too.
+//
+// # Safety
+// `game` must be null or a live game.
+enum GoStone go_game_point(const struct GoGame *game, uint32_t row, uint32_t col);
+
+// Whether the player to move may play on the point.
+//
+// # Safety
+// `game` must be null or a live game.
+bool go_game_is_legal(const struct GoGame *game, uint32_t row, uint32_t col);
+
+// Plays a stone for the player to move. An illegal move leaves the game as it was and says why.
+//
+// # Safety
+// `game` must be null or a live game.
+enum GoStatus go_game_play(struct GoGame *game, uint32_t row, uint32_t col);
+
+// Passes for the player to move.
+//
+// # Safety
+// `game` must be null or a live game.
+enum GoStatus go_game_pass(struct GoGame *game);
+
+// The black stones and the white stones captured so far, written to whichever of the two
+// pointers is not null.
+//
+// # Safety
+// `game` must be null or a live game, and the others null or writable.
+enum GoStatus go_game_captures(const struct GoGame *game, uint32_t *black, uint32_t *white);
+
+// Counts the position with the `dead_count` stones at `dead` taken off, by the game's rules
+// and komi, into `score`.
+//
+// # Safety
+// `game` must be null or a live game, `dead` null or `dead_count` points, and `score` null or
+// writable.
+enum GoStatus go_game_score(const struct GoGame *game,
+                            const struct GoPoint *dead,
+                            uintptr_t dead_count,
+                            struct GoScore *score);
+
+// The record as SGF, to release with `go_string_free`; null for a null game.
+//
+// # Safety
+// `game` must be null or a live game.
+char *go_game_to_sgf(const struct GoGame *game);
+
+// # Safety
+// `text` must be null or a string from this library that has not been freed.
+void go_string_free(char *text);
+
+#ifdef __cplusplus
+}  // extern "C"
+#endif  // __cplusplus
+
+#endif  /* GO_GAME_H */
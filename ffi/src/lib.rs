@@ -0,0 +1,440 @@
+//! A C interface to the rules engine, for embedding it in programs written in other languages.
+//! `go_game.h` next to this crate is generated from it with `cbindgen --config cbindgen.toml
+//! --output go_game.h`.
+//!
+//! A game is an opaque `GoGame` made by `go_game_new` or `go_game_from_sgf` and released with
+//! `go_game_free`. Points are a row and a column counted from 0 at the top left. Strings handed
+//! out are released with `go_string_free`.
+
+use std::collections::HashSet;
+use std::ffi::{CStr, CString, c_char};
+use std::ptr;
+
+use src::board::{self, Board, Move, MoveError, Player, Stone};
+use src::game_info::{GameInfo, Rules};
+use src::{consts, scoring, sgf};
+
+/// What is on a point, or whose turn it is.
+#[repr(C)]
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum GoStone {
+    Empty,
+    Black,
+    White,
+}
+
+/// How a call went.
+#[repr(C)]
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum GoStatus {
+    Ok,
+    Occupied,
+    Suicide,
+    Ko,
+    OffBoard,
+    // A null pointer, or an argument out of range
+    Invalid,
+}
+
+#[repr(C)]
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum GoRules {
+    Japanese,
+    Chinese,
+}
+
+#[repr(C)]
+#[derive(Clone, Copy, Debug)]
+pub struct GoPoint {
+    pub row: u32,
+    pub col: u32,
+}
+
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Default)]
+pub struct GoScore {
+    pub black: f32,
+    pub white: f32,
+}
+
+/// A game from its starting position, with the moves played on it.
+pub struct GoGame {
+    start: Board,
+    start_player: Player,
+    info: GameInfo,
+    board: Board,
+    to_play: Player,
+    moves: Vec<Move>,
+    // Black stones and white stones captured so far
+    captures: (u32, u32),
+}
+
+impl GoGame {
+    fn new(start: Board, start_player: Player, info: GameInfo) -> Self {
+        GoGame {
+            board: start.clone(),
+            start,
+            start_player,
+            info,
+            to_play: start_player,
+            moves: Vec::new(),
+            captures: (0, 0),
+        }
+    }
+
+    fn point(&self, row: u32, col: u32) -> Option<(usize, usize)> {
+        let size = self.board.size();
+        let (row, col) = (row as usize, col as usize);
+        (row < size && col < size).then_some((row, col))
+    }
+
+    fn advance(&mut self, (player, point): Move) -> Result<(), MoveError> {
+        match point {
+            Some((row, col)) => {
+                self.board.check_move(row, col, player)?;
+                let captured = self.board.play(row, col, player);
+                match player {
+                    Player::Black => self.captures.1 += captured,
+                    Player::White => self.captures.0 += captured,
+                }
+            }
+            None => self.board.pass(),
+        }
+        self.moves.push((player, point));
+        self.to_play = player.other();
+        Ok(())
+    }
+
+    fn to_sgf(&self) -> String {
+        let size = self.board.size();
+        let mut root = sgf::Node::default();
+        root.set("GM", vec!["1".to_string()]);
+        root.set("FF", vec!["4".to_string()]);
+        root.set("SZ", vec![size.to_string()]);
+        self.info.write_to(&mut root);
+        for (ident, stone) in [("AB", Stone::Black), ("AW", Stone::White)] {
+            let mut points = Vec::new();
+            for row in 0..size {
+                for col in 0..size {
+                    if self.start[row][col] == stone {
+                        points.push(sgf::format_point((row, col)));
+                    }
+                }
+            }
+            root.set(ident, points);
+        }
+        if self.start_player == Player::White && self.info.handicap < 2 {
+            root.set("PL", vec!["W".to_string()]);
+        }
+        root.children = self
+            .moves
+            .iter()
+            .rev()
+            .fold(Vec::new(), |children, &(player, point)| {
+                let mut node = sgf::Node::default();
+                let ident = match player {
+                    Player::Black => "B",
+                    Player::White => "W",
+                };
+                // An empty value is a pass
+                node.set(
+                    ident,
+                    vec![point.map(sgf::format_point).unwrap_or_default()],
+                );
+                node.children = children;
+                vec![node]
+            });
+        sgf::to_string(&root)
+    }
+
+    fn from_sgf(text: &str) -> Option<Self> {
+        let trees = sgf::parse(text).ok()?;
+        let root = trees.first()?;
+        let size = sgf::parse_size(root.get("SZ").unwrap_or("19"))
+            .filter(|size| (2..=consts::MAX_BOARD_SIZE).contains(size))?;
+        let info = GameInfo::from_node(root);
+        let mut start = Board::new(size);
+        for (ident, stone) in [("AB", Stone::Black), ("AW", Stone::White)] {
+            for value in root.get_all(ident) {
+                if let Some((row, col)) = sgf::parse_point(value, size) {
+                    start[row][col] = stone;
+                }
+            }
+        }
+        let first = match root.get("PL").map(|value| value.trim().to_lowercase()) {
+            Some(value) if value == "w" || value == "white" => Player::White,
+            Some(_) => Player::Black,
+            None if info.handicap >= 2 => Player::White,
+            None => Player::Black,
+        };
+        let mut game = GoGame::new(start, first, info);
+        for node in sgf::main_line(root) {
+            for (ident, player) in [("B", Player::Black), ("W", Player::White)] {
+                if let Some(value) = node.get(ident) {
+                    game.advance((player, sgf::parse_point(value, size))).ok()?;
+                }
+            }
+        }
+        Some(game)
+    }
+}
+
+fn stone(stone: Stone) -> GoStone {
+    match stone {
+        Stone::Empty => GoStone::Empty,
+        Stone::Black => GoStone::Black,
+        Stone::White => GoStone::White,
+    }
+}
+
+fn status(result: Result<(), MoveError>) -> GoStatus {
+    match result {
+        Ok(()) => GoStatus::Ok,
+        Err(MoveError::Occupied) => GoStatus::Occupied,
+        Err(MoveError::Suicide) => GoStatus::Suicide,
+        Err(MoveError::Ko) => GoStatus::Ko,
+    }
+}
+
+/// A new game with the handicap stones on the usual points, or null for a size out of range.
+/// Black plays first, or White in a handicap game.
+#[unsafe(no_mangle)]
+pub extern "C" fn go_game_new(size: u32, komi: f32, rules: GoRules, handicap: u32) -> *mut GoGame {
+    let size = size as usize;
+    if !(2..=consts::MAX_BOARD_SIZE).contains(&size) {
+        return ptr::null_mut();
+    }
+    let mut start = Board::new(size);
+    let stones = board::handicap_points(size, handicap);
+    for &(row, col) in &stones {
+        start[row][col] = Stone::Black;
+    }
+    let info = GameInfo {
+        komi,
+        rules: match rules {
+            GoRules::Japanese => Rules::Japanese,
+            GoRules::Chinese => Rules::Chinese,
+        },
+        handicap: if stones.is_empty() { 0 } else { handicap },
+        ..GameInfo::default()
+    };
+    let first = if stones.is_empty() {
+        Player::Black
+    } else {
+        Player::White
+    };
+    Box::into_raw(Box::new(GoGame::new(start, first, info)))
+}
+
+/// Reads the main line of an SGF record, or gives null for a record that cannot be read or has
+/// an illegal move.
+///
+/// # Safety
+/// `text` must be null or a NUL-terminated string.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn go_game_from_sgf(text: *const c_char) -> *mut GoGame {
+    if text.is_null() {
+        return ptr::null_mut();
+    }
+    // SAFETY: the caller passes a NUL-terminated string
+    let text = unsafe { CStr::from_ptr(text) };
+    match text.to_str().ok().and_then(GoGame::from_sgf) {
+        Some(game) => Box::into_raw(Box::new(game)),
+        None => ptr::null_mut(),
+    }
+}
+
+/// # Safety
+/// `game` must be null or a game from this library that has not been freed.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn go_game_free(game: *mut GoGame) {
+    if !game.is_null() {
+        // SAFETY: the game came from `Box::into_raw` and is freed only once
+        drop(unsafe { Box::from_raw(game) });
+    }
+}
+
+/// The width of the board, or 0 for a null game.
+///
+/// # Safety
+/// `game` must be null or a live game.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn go_game_size(game: *const GoGame) -> u32 {
+    // SAFETY: the caller passes a live game or null
+    unsafe { game.as_ref() }.map_or(0, |game| game.board.size() as u32)
+}
+
+/// Whose turn it is, or `GO_STONE_EMPTY` for a null game.
+///
+/// # Safety
+/// `game` must be null or a live game.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn go_game_to_move(game: *const GoGame) -> GoStone {
+    // SAFETY: the caller passes a live game or null
+    unsafe { game.as_ref() }.map_or(GoStone::Empty, |game| stone(game.to_play.to_stone()))
+}
+
+/// The number of moves played, passes included.
+///
+/// # Safety
+/// `game` must be null or a live game.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn go_game_move_count(game: *const GoGame) -> u32 {
+    // SAFETY: the caller passes a live game or null
+    unsafe { game.as_ref() }.map_or(0, |game| game.moves.len() as u32)
+}
+
+/// What is on a point; `GO_STONE_EMPTY` off the board too.
+///
+/// # Safety
+/// `game` must be null or a live game.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn go_game_point(game: *const GoGame, row: u32, col: u32) -> GoStone {
+    // SAFETY: the caller passes a live game or null
+    let Some(game) = (unsafe { game.as_ref() }) else {
+        return GoStone::Empty;
+    };
+    match game.point(row, col) {
+        Some((row, col)) => stone(game.board[row][col]),
+        None => GoStone::Empty,
+    }
+}
+
+/// Whether the player to move may play on the point.
+///
+/// # Safety
+/// `game` must be null or a live game.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn go_game_is_legal(game: *const GoGame, row: u32, col: u32) -> bool {
+    // SAFETY: the caller passes a live game or null
+    let Some(game) = (unsafe { game.as_ref() }) else {
+        return false;
+    };
+    game.point(row, col)
+        .is_some_and(|(row, col)| game.board.is_legal(row, col, game.to_play))
+}
+
+/// Plays a stone for the player to move. An illegal move leaves the game as it was and says why.
+///
+/// # Safety
+/// `game` must be null or a live game.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn go_game_play(game: *mut GoGame, row: u32, col: u32) -> GoStatus {
+    // SAFETY: the caller passes a live game or null
+    let Some(game) = (unsafe { game.as_mut() }) else {
+        return GoStatus::Invalid;
+    };
+    match game.point(row, col) {
+        Some(point) => status(game.advance((game.to_play, Some(point)))),
+        None => GoStatus::OffBoard,
+    }
+}
+
+/// Passes for the player to move.
+///
+/// # Safety
+/// `game` must be null or a live game.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn go_game_pass(game: *mut GoGame) -> GoStatus {
+    // SAFETY: the caller passes a live game or null
+    let Some(game) = (unsafe { game.as_mut() }) else {
+        return GoStatus::Invalid;
+    };
+    status(game.advance((game.to_play, None)))
+}
+
+/// The black stones and the white stones captured so far, written to whichever of the two
+/// pointers is not null.
+///
+/// # Safety
+/// `game` must be null or a live game, and the others null or writable.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn go_game_captures(
+    game: *const GoGame,
+    black: *mut u32,
+    white: *mut u32,
+) -> GoStatus {
+    // SAFETY: the caller passes a live game or null
+    let Some(game) = (unsafe { game.as_ref() }) else {
+        return GoStatus::Invalid;
+    };
+    // SAFETY: the caller passes writable pointers or null
+    unsafe {
+        if let Some(black) = black.as_mut() {
+            *black = game.captures.0;
+        }
+        if let Some(white) = white.as_mut() {
+            *white = game.captures.1;
+        }
+    }
+    GoStatus::Ok
+}
+
+/// Counts the position with the `dead_count` stones at `dead` taken off, by the game's rules
+/// and komi, into `score`.
+///
+/// # Safety
+/// `game` must be null or a live game, `dead` null or `dead_count` points, and `score` null or
+/// writable.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn go_game_score(
+    game: *const GoGame,
+    dead: *const GoPoint,
+    dead_count: usize,
+    score: *mut GoScore,
+) -> GoStatus {
+    // SAFETY: the caller passes a live game or null, and a writable score or null
+    let (Some(game), Some(score)) = (unsafe { game.as_ref() }, unsafe { score.as_mut() }) else {
+        return GoStatus::Invalid;
+    };
+    let dead = if dead.is_null() || dead_count == 0 {
+        &[]
+    } else {
+        // SAFETY: the caller passes `dead_count` points at `dead`
+        unsafe { std::slice::from_raw_parts(dead, dead_count) }
+    };
+    let Some(dead) = dead
+        .iter()
+        .map(|point| game.point(point.row, point.col))
+        .collect::<Option<HashSet<_>>>()
+    else {
+        return GoStatus::OffBoard;
+    };
+    let counted = scoring::score(
+        &game.board,
+        &dead,
+        game.info.rules,
+        game.info.komi,
+        game.captures.0,
+        game.captures.1,
+    );
+    *score = GoScore {
+        black: counted.black,
+        white: counted.white,
+    };
+    GoStatus::Ok
+}
+
+/// The record as SGF, to release with `go_string_free`; null for a null game.
+///
+/// # Safety
+/// `game` must be null or a live game.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn go_game_to_sgf(game: *const GoGame) -> *mut c_char {
+    // SAFETY: the caller passes a live game or null
+    let Some(game) = (unsafe { game.as_ref() }) else {
+        return ptr::null_mut();
+    };
+    // SGF text has no NUL bytes, short of one typed into a game's information
+    CString::new(game.to_sgf()).map_or(ptr::null_mut(), CString::into_raw)
+}
+
+/// # Safety
+/// `text` must be null or a string from this library that has not been freed.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn go_string_free(text: *mut c_char) {
+    if !text.is_null() {
+        // SAFETY: the string came from `CString::into_raw` and is freed only once
+        drop(unsafe { CString::from_raw(text) });
+    }
+}
@@ -0,0 +1,380 @@
+//! Python bindings for the rules engine: a `Game` to play moves on, take them back, score and
+//! read or write as SGF, with the same rules as the app.
+//!
+//! Points are `(row, col)` pairs counted from 0 at the top left, and colors are `"B"` and `"W"`.
+
+use std::collections::HashSet;
+
+use pyo3::exceptions::PyValueError;
+use pyo3::prelude::*;
+
+use src::board::{self, Board, Move, Player, Stone};
+use src::game_info::{GameInfo, Rules};
+use src::{consts, scoring, sgf};
+
+fn color(player: Player) -> &'static str {
+    match player {
+        Player::Black => "B",
+        Player::White => "W",
+    }
+}
+
+fn parse_color(text: &str) -> PyResult<Player> {
+    match text.trim().to_lowercase().as_str() {
+        "b" | "black" => Ok(Player::Black),
+        "w" | "white" => Ok(Player::White),
+        _ => Err(PyValueError::new_err(format!(
+            "expected \"B\" or \"W\", not {:?}",
+            text
+        ))),
+    }
+}
+
+fn parse_rules(text: &str) -> PyResult<Rules> {
+    Rules::ALL
+        .into_iter()
+        .find(|rules| rules.name().eq_ignore_ascii_case(text.trim()))
+        .ok_or_else(|| {
+            PyValueError::new_err(format!(
+                "expected \"japanese\" or \"chinese\", not {:?}",
+                text
+            ))
+        })
+}
+
+/// The count of a finished position.
+#[pyclass(get_all, frozen)]
+struct Score {
+    black: f32,
+    white: f32,
+    /// As in SGF, e.g. "W+6.5"
+    result: String,
+}
+
+#[pymethods]
+impl Score {
+    fn __repr__(&self) -> String {
+        format!(
+            "Score(black={}, white={}, result={:?})",
+            self.black, self.white, self.result
+        )
+    }
+}
+
+/// A game from its starting position, with the moves played on it.
+#[pyclass]
+struct Game {
+    start: Board,
+    start_player: Player,
+    rules: Rules,
+    komi: f32,
+    handicap: u32,
+    board: Board,
+    to_play: Player,
+    moves: Vec<Move>,
+    // Black stones and white stones captured so far
+    captures: (u32, u32),
+}
+
+impl Game {
+    fn from_start(start: Board, start_player: Player, info: &GameInfo) -> Self {
+        Game {
+            board: start.clone(),
+            start,
+            start_player,
+            rules: info.rules,
+            komi: info.komi,
+            handicap: info.handicap,
+            to_play: start_player,
+            moves: Vec::new(),
+            captures: (0, 0),
+        }
+    }
+
+    fn point(&self, row: usize, col: usize) -> PyResult<(usize, usize)> {
+        let size = self.board.size();
+        if row < size && col < size {
+            Ok((row, col))
+        } else {
+            Err(PyValueError::new_err(format!(
+                "({}, {}) is off the {}x{} board",
+                row, col, size, size
+            )))
+        }
+    }
+
+    fn advance(&mut self, played: Move) -> Result<(), String> {
+        let (player, point) = played;
+        match point {
+            Some((row, col)) => {
+                self.board
+                    .check_move(row, col, player)
+                    .map_err(|err| err.to_string())?;
+                let captured = self.board.play(row, col, player);
+                match player {
+                    Player::Black => self.captures.1 += captured,
+                    Player::White => self.captures.0 += captured,
+                }
+            }
+            None => self.board.pass(),
+        }
+        self.moves.push(played);
+        self.to_play = player.other();
+        Ok(())
+    }
+}
+
+#[pymethods]
+impl Game {
+    /// A new game, with the handicap stones placed on the usual points and White to play.
+    #[new]
+    #[pyo3(signature = (size = 19, komi = consts::DEFAULT_KOMI, rules = "japanese", handicap = 0))]
+    fn new(size: usize, komi: f32, rules: &str, handicap: u32) -> PyResult<Self> {
+        if !(2..=consts::MAX_BOARD_SIZE).contains(&size) {
+            return Err(PyValueError::new_err(format!(
+                "expected a size from 2 to {}",
+                consts::MAX_BOARD_SIZE
+            )));
+        }
+        let mut start = Board::new(size);
+        let stones = board::handicap_points(size, handicap);
+        for &(row, col) in &stones {
+            start[row][col] = Stone::Black;
+        }
+        let info = GameInfo {
+            komi,
+            rules: parse_rules(rules)?,
+            handicap: if stones.is_empty() { 0 } else { handicap },
+            ..GameInfo::default()
+        };
+        let first = if stones.is_empty() {
+            Player::Black
+        } else {
+            Player::White
+        };
+        Ok(Game::from_start(start, first, &info))
+    }
+
+    /// Reads the main line of an SGF record.
+    #[staticmethod]
+    fn from_sgf(text: &str) -> PyResult<Self> {
+        let trees = sgf::parse(text).map_err(|err| PyValueError::new_err(err.to_string()))?;
+        let root = trees
+            .first()
+            .ok_or_else(|| PyValueError::new_err("the record has no game"))?;
+        let size = sgf::parse_size(root.get("SZ").unwrap_or("19"))
+            .filter(|size| (2..=consts::MAX_BOARD_SIZE).contains(size))
+            .ok_or_else(|| PyValueError::new_err("unsupported board size"))?;
+        let info = GameInfo::from_node(root);
+        let mut start = Board::new(size);
+        for (ident, stone) in [("AB", Stone::Black), ("AW", Stone::White)] {
+            for value in root.get_all(ident) {
+                if let Some((row, col)) = sgf::parse_point(value, size) {
+                    start[row][col] = stone;
+                }
+            }
+        }
+        let first = match root.get("PL") {
+            Some(value) => parse_color(value)?,
+            None if info.handicap >= 2 => Player::White,
+            None => Player::Black,
+        };
+        let mut game = Game::from_start(start, first, &info);
+        for node in sgf::main_line(root) {
+            for (ident, player) in [("B", Player::Black), ("W", Player::White)] {
+                if let Some(value) = node.get(ident) {
+                    let point = sgf::parse_point(value, size);
+                    game.advance((player, point)).map_err(|err| {
+                        PyValueError::new_err(format!("move {}: {}", game.moves.len() + 1, err))
+                    })?;
+                }
+            }
+        }
+        Ok(game)
+    }
+
+    /// The record as SGF, with the handicap stones and the moves.
+    fn to_sgf(&self) -> String {
+        let size = self.board.size();
+        let mut root = sgf::Node::default();
+        root.set("GM", vec!["1".to_string()]);
+        root.set("FF", vec!["4".to_string()]);
+        root.set("SZ", vec![size.to_string()]);
+        let info = GameInfo {
+            komi: self.komi,
+            rules: self.rules,
+            handicap: self.handicap,
+            ..GameInfo::default()
+        };
+        info.write_to(&mut root);
+        for (ident, stone) in [("AB", Stone::Black), ("AW", Stone::White)] {
+            let mut points = Vec::new();
+            for row in 0..size {
+                for col in 0..size {
+                    if self.start[row][col] == stone {
+                        points.push(sgf::format_point((row, col)));
+                    }
+                }
+            }
+            root.set(ident, points);
+        }
+        if self.start_player == Player::White && self.handicap < 2 {
+            root.set("PL", vec!["W".to_string()]);
+        }
+        let nodes = self
+            .moves
+            .iter()
+            .rev()
+            .fold(Vec::new(), |children, &(player, point)| {
+                let mut node = sgf::Node::default();
+                // An empty value is a pass
+                node.set(
+                    color(player),
+                    vec![point.map(sgf::format_point).unwrap_or_default()],
+                );
+                node.children = children;
+                vec![node]
+            });
+        root.children = nodes;
+        sgf::to_string(&root)
+    }
+
+    #[getter]
+    fn size(&self) -> usize {
+        self.board.size()
+    }
+
+    #[getter]
+    fn komi(&self) -> f32 {
+        self.komi
+    }
+
+    #[getter]
+    fn to_move(&self) -> &'static str {
+        color(self.to_play)
+    }
+
+    /// The moves played, as `(color, point)` with `None` for a pass.
+    #[getter]
+    fn moves(&self) -> Vec<(&'static str, Option<(usize, usize)>)> {
+        self.moves
+            .iter()
+            .map(|&(player, point)| (color(player), point))
+            .collect()
+    }
+
+    /// The black stones and the white stones captured so far.
+    #[getter]
+    fn captures(&self) -> (u32, u32) {
+        self.captures
+    }
+
+    /// The point the ko rule forbids right now, if any.
+    #[getter]
+    fn ko(&self) -> Option<(usize, usize)> {
+        self.board.ko_point()
+    }
+
+    /// The stone on a point: "B", "W" or `None`.
+    fn get(&self, row: usize, col: usize) -> PyResult<Option<&'static str>> {
+        let (row, col) = self.point(row, col)?;
+        Ok(match self.board[row][col] {
+            Stone::Black => Some("B"),
+            Stone::White => Some("W"),
+            Stone::Empty => None,
+        })
+    }
+
+    /// Whether the player to move, or `color`, may play on the point.
+    #[pyo3(signature = (row, col, color = None))]
+    fn is_legal(&self, row: usize, col: usize, color: Option<&str>) -> PyResult<bool> {
+        let (row, col) = self.point(row, col)?;
+        let player = color.map_or(Ok(self.to_play), parse_color)?;
+        Ok(self.board.is_legal(row, col, player))
+    }
+
+    /// Every point the player to move, or `color`, may play on.
+    #[pyo3(signature = (color = None))]
+    fn legal_moves(&self, color: Option<&str>) -> PyResult<Vec<(usize, usize)>> {
+        let player = color.map_or(Ok(self.to_play), parse_color)?;
+        let size = self.board.size();
+        Ok((0..size)
+            .flat_map(|row| (0..size).map(move |col| (row, col)))
+            .filter(|&(row, col)| self.board.is_legal(row, col, player))
+            .collect())
+    }
+
+    /// Plays a stone for the player to move and returns how many stones it captured. Raises
+    /// `ValueError` for an illegal move.
+    fn play(&mut self, row: usize, col: usize) -> PyResult<u32> {
+        let point = self.point(row, col)?;
+        let before = self.captures;
+        self.advance((self.to_play, Some(point)))
+            .map_err(PyValueError::new_err)?;
+        Ok(self.captures.0 + self.captures.1 - before.0 - before.1)
+    }
+
+    /// Passes for the player to move; `pass` itself is a Python keyword.
+    fn pass_(&mut self) {
+        let _ = self.advance((self.to_play, None));
+    }
+
+    /// Takes back the last move; returns whether there was one.
+    fn undo(&mut self) -> bool {
+        let mut moves = std::mem::take(&mut self.moves);
+        let undone = moves.pop().is_some();
+        self.board = self.start.clone();
+        self.to_play = self.start_player;
+        self.captures = (0, 0);
+        for played in moves {
+            let _ = self.advance(played);
+        }
+        undone
+    }
+
+    /// Counts the position with the `dead` stones taken off, by the game's rules and komi.
+    #[pyo3(signature = (dead = Vec::new()))]
+    fn score(&self, dead: Vec<(usize, usize)>) -> PyResult<Score> {
+        let dead: HashSet<(usize, usize)> = dead
+            .into_iter()
+            .map(|(row, col)| self.point(row, col))
+            .collect::<PyResult<_>>()?;
+        let score = scoring::score(
+            &self.board,
+            &dead,
+            self.rules,
+            self.komi,
+            self.captures.0,
+            self.captures.1,
+        );
+        Ok(Score {
+            black: score.black,
+            white: score.white,
+            result: score.result(),
+        })
+    }
+
+    /// The board in text, X for Black and O for White.
+    fn __str__(&self) -> String {
+        let size = self.board.size();
+        (0..size)
+            .map(|row| {
+                (0..size)
+                    .map(|col| match self.board[row][col] {
+                        Stone::Black => 'X',
+                        Stone::White => 'O',
+                        Stone::Empty => '.',
+                    })
+                    .collect::<String>()
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+}
+
+#[pymodule]
+fn go_game(module: &Bound<'_, PyModule>) -> PyResult<()> {
+    module.add_class::<Game>()?;
+    module.add_class::<Score>()?;
+    Ok(())
+}
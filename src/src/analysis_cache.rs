@@ -0,0 +1,154 @@
+//! The engine's scores of positions it was asked about before, kept on disk so reviewing a game
+//! again or coming back to a position does not keep the engine busy a second time. Each entry is
+//! keyed by the position, the player to move and the engine: its name, version and command line,
+//! with the komi and rules it scored under. After a line naming the format come the entries, each
+//! with Black's lead and when it was last used.
+
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::Mutex;
+
+use crate::board::{Board, Player};
+use crate::consts;
+use crate::paths;
+
+// Raised whenever the entries change, so an older file is started over
+const HEADER: &str = "go-game analysis cache 2\n";
+// A key, the lead and the generation, each little-endian
+const ENTRY_BYTES: usize = 16;
+
+struct Cache {
+    // The lead for each key with the generation it was last used in
+    entries: HashMap<u64, (f32, u32)>,
+    // One more than the newest in the file, so what this run uses outlives what it does not
+    generation: u32,
+    dirty: bool,
+}
+
+static CACHE: Mutex<Option<Cache>> = Mutex::new(None);
+
+// FNV-1a over the engine, then the position, the ko and the player to move
+fn key(engine: &str, board: &Board, next: Player) -> u64 {
+    let ko = board
+        .ko_point()
+        .map_or(0, |(row, col)| (row * board.size() + col + 1) as u64);
+    let position = [
+        board.checksum(),
+        board.size() as u64,
+        ko,
+        (next == Player::White) as u64,
+    ];
+    let bytes = engine
+        .bytes()
+        .chain(position.iter().flat_map(|word| word.to_le_bytes()));
+    bytes.fold(0xcbf2_9ce4_8422_2325, |hash, byte| {
+        (hash ^ byte as u64).wrapping_mul(0x0100_0000_01b3)
+    })
+}
+
+fn load(path: &Path) -> Cache {
+    let mut cache = Cache {
+        entries: HashMap::new(),
+        generation: 0,
+        dirty: false,
+    };
+    let Ok(bytes) = std::fs::read(path) else {
+        return cache;
+    };
+    let Some(body) = bytes.strip_prefix(HEADER.as_bytes()) else {
+        return cache;
+    };
+    for entry in body.chunks_exact(ENTRY_BYTES) {
+        let word = |at: usize| <[u8; 4]>::try_from(&entry[at..at + 4]).expect("four bytes");
+        let key = u64::from_le_bytes(entry[..8].try_into().expect("eight bytes"));
+        let generation = u32::from_le_bytes(word(12));
+        cache
+            .entries
+            .insert(key, (f32::from_le_bytes(word(8)), generation));
+        cache.generation = cache.generation.max(generation.saturating_add(1));
+    }
+    cache
+}
+
+/// Black's lead on `board` with `next` to play as `engine` scores it, from the cache or from
+/// `work_out`, whose errors are not kept.
+pub fn lead<E>(
+    engine: &str,
+    board: &Board,
+    next: Player,
+    work_out: impl FnOnce() -> Result<f32, E>,
+) -> Result<f32, E> {
+    let key = key(engine, board, next);
+    {
+        let mut cache = CACHE.lock().unwrap_or_else(|err| err.into_inner());
+        let cache = cache.get_or_insert_with(|| load(&paths::analysis_cache_path()));
+        let generation = cache.generation;
+        if let Some((lead, used)) = cache.entries.get_mut(&key) {
+            if *used != generation {
+                *used = generation;
+                cache.dirty = true;
+            }
+            return Ok(*lead);
+        }
+    }
+    // Worked out without the lock, so other games under review are not held up
+    let lead = work_out()?;
+    let mut cache = CACHE.lock().unwrap_or_else(|err| err.into_inner());
+    if let Some(cache) = cache.as_mut() {
+        cache.entries.insert(key, (lead, cache.generation));
+        cache.dirty = true;
+    }
+    Ok(lead)
+}
+
+/// Writes what changed to disk, leaving out the entries used longest ago past `megabytes`.
+pub fn save(megabytes: u32) -> std::io::Result<()> {
+    let mut cache = CACHE.lock().unwrap_or_else(|err| err.into_inner());
+    let Some(cache) = cache.as_mut().filter(|cache| cache.dirty) else {
+        return Ok(());
+    };
+    let room = (megabytes as usize * 1024 * 1024).saturating_sub(HEADER.len()) / ENTRY_BYTES;
+    if cache.entries.len() > room {
+        let mut entries: Vec<(u64, (f32, u32))> = cache.entries.drain().collect();
+        entries.sort_by_key(|&(_, (_, generation))| std::cmp::Reverse(generation));
+        entries.truncate(room);
+        cache.entries.extend(entries);
+    }
+    // Browsers keep it for the visit only
+    if !consts::DESKTOP {
+        return Ok(());
+    }
+    let mut bytes = HEADER.as_bytes().to_vec();
+    bytes.reserve(cache.entries.len() * ENTRY_BYTES);
+    for (key, (lead, generation)) in &cache.entries {
+        bytes.extend_from_slice(&key.to_le_bytes());
+        bytes.extend_from_slice(&lead.to_le_bytes());
+        bytes.extend_from_slice(&generation.to_le_bytes());
+    }
+    let path = paths::analysis_cache_path();
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    std::fs::write(path, bytes)?;
+    cache.dirty = false;
+    Ok(())
+}
+
+/// How much room the cache takes on disk, or will once saved.
+pub fn bytes() -> u64 {
+    let cache = CACHE.lock().unwrap_or_else(|err| err.into_inner());
+    match cache.as_ref() {
+        Some(cache) => (cache.entries.len() * ENTRY_BYTES) as u64,
+        None => std::fs::metadata(paths::analysis_cache_path()).map_or(0, |file| file.len()),
+    }
+}
+
+/// Forgets every score and removes the file.
+pub fn clear() -> std::io::Result<()> {
+    let mut cache = CACHE.lock().unwrap_or_else(|err| err.into_inner());
+    *cache = None;
+    match std::fs::remove_file(paths::analysis_cache_path()) {
+        Err(err) if err.kind() != std::io::ErrorKind::NotFound => Err(err),
+        _ => Ok(()),
+    }
+}
@@ -0,0 +1,188 @@
+//! A small HTTP server on this computer for other programs, such as stream overlays, home-made
+//! scoreboards and scripts: `GET /state` gives the game in front as JSON, and `POST /move` with
+//! `{"move": "D4"}` or `{"move": "pass"}` plays in it as if the move were clicked.
+
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::{Ipv4Addr, TcpListener, TcpStream};
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::sync::{Arc, Mutex};
+
+use eframe::egui;
+use serde::{Deserialize, Serialize};
+
+use crate::consts;
+
+/// The game as the API shows it.
+#[derive(Clone, Serialize, Deserialize, PartialEq)]
+pub struct State {
+    pub size: usize,
+    // "B" or "W"
+    pub to_move: String,
+    // Every move so far, such as "D4" or "pass"
+    pub moves: Vec<String>,
+    // The rows from the top, with X for Black, O for White and . for an empty point
+    pub board: Vec<String>,
+    pub black: Side,
+    pub white: Side,
+    pub komi: f32,
+    pub handicap: u32,
+    pub rules: String,
+    pub result: Option<String>,
+    pub game_over: bool,
+}
+
+#[derive(Clone, Serialize, Deserialize, PartialEq)]
+pub struct Side {
+    pub name: String,
+    pub rank: String,
+    // Stones of this color taken off the board
+    pub captured: u32,
+    // Seconds left on the clock, with none for an untimed game
+    pub time_left: Option<f32>,
+}
+
+#[derive(Deserialize)]
+struct MoveBody {
+    #[serde(rename = "move")]
+    point: String,
+}
+
+/// A move sent to the API, waiting for the game to play it.
+pub struct Request {
+    pub point: String,
+    reply: Sender<Result<String, String>>,
+}
+
+impl Request {
+    /// Answers the client with the game after the move, or why it was not played.
+    pub fn answer(self, result: Result<&State, String>) {
+        let result =
+            result.and_then(|state| serde_json::to_string(state).map_err(|err| err.to_string()));
+        let _ = self.reply.send(result);
+    }
+}
+
+pub struct Server {
+    state: Arc<Mutex<String>>,
+    published: Option<State>,
+    requests: Receiver<Request>,
+}
+
+impl Server {
+    /// Listens on `port` of this computer only, serving each client on a thread of its own.
+    pub fn start(port: u16, ctx: &egui::Context) -> std::io::Result<Self> {
+        let listener = TcpListener::bind((Ipv4Addr::LOCALHOST, port))?;
+        let state = Arc::new(Mutex::new("{}".to_string()));
+        let (sender, requests) = mpsc::channel();
+        let shared = state.clone();
+        let ctx = ctx.clone();
+        std::thread::spawn(move || {
+            for stream in listener.incoming().flatten() {
+                let (state, sender, ctx) = (shared.clone(), sender.clone(), ctx.clone());
+                std::thread::spawn(move || serve(stream, &state, &sender, &ctx));
+            }
+        });
+        Ok(Server {
+            state,
+            published: None,
+            requests,
+        })
+    }
+
+    /// Shows `state` to the clients from now on.
+    pub fn publish(&mut self, state: State) {
+        if self.published.as_ref() == Some(&state) {
+            return;
+        }
+        if let Ok(text) = serde_json::to_string(&state)
+            && let Ok(mut shared) = self.state.lock()
+        {
+            *shared = text;
+        }
+        self.published = Some(state);
+    }
+
+    /// The moves sent since the last call.
+    pub fn requests(&self) -> Vec<Request> {
+        self.requests.try_iter().collect()
+    }
+}
+
+// Answers one request and closes the connection
+fn serve(stream: TcpStream, state: &Mutex<String>, sender: &Sender<Request>, ctx: &egui::Context) {
+    let _ = stream.set_read_timeout(Some(consts::API_TIMEOUT));
+    let mut reader = BufReader::new(&stream);
+    let mut request_line = String::new();
+    if reader.read_line(&mut request_line).is_err() {
+        return;
+    }
+    let mut length = 0;
+    loop {
+        let mut header = String::new();
+        if reader.read_line(&mut header).is_err() {
+            return;
+        }
+        let header = header.trim();
+        if header.is_empty() {
+            break;
+        }
+        if let Some((name, value)) = header.split_once(':')
+            && name.trim().eq_ignore_ascii_case("content-length")
+        {
+            length = value.trim().parse().unwrap_or(0);
+        }
+    }
+    let mut parts = request_line.split_whitespace();
+    let (method, path) = (parts.next().unwrap_or(""), parts.next().unwrap_or(""));
+    let (status, body) = match (method, path) {
+        ("GET", "/state") => (
+            "200 OK",
+            state.lock().map(|state| state.clone()).unwrap_or_default(),
+        ),
+        ("POST", "/move") if length > consts::API_MAX_BODY => {
+            ("413 Payload Too Large", error("the body is too long"))
+        }
+        ("POST", "/move") => {
+            let mut body = vec![0; length];
+            match reader.read_exact(&mut body) {
+                Ok(()) => play(&body, sender, ctx),
+                Err(_) => ("400 Bad Request", error("the body is cut short")),
+            }
+        }
+        // The preflight of a page in a browser, such as an overlay, that posts a move
+        ("OPTIONS", _) => ("204 No Content", String::new()),
+        ("GET" | "POST", _) => ("404 Not Found", error("unknown path")),
+        _ => ("405 Method Not Allowed", error("unknown method")),
+    };
+    let _ = write!(
+        &stream,
+        "HTTP/1.1 {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nAccess-Control-Allow-Origin: *\r\nAccess-Control-Allow-Methods: GET, POST, OPTIONS\r\nAccess-Control-Allow-Headers: Content-Type\r\nConnection: close\r\n\r\n{}",
+        status,
+        body.len(),
+        body
+    );
+}
+
+// Hands a move to the game and waits for it to be played
+fn play(body: &[u8], sender: &Sender<Request>, ctx: &egui::Context) -> (&'static str, String) {
+    let Ok(MoveBody { point }) = serde_json::from_slice(body) else {
+        return (
+            "400 Bad Request",
+            error("expected a body such as {\"move\": \"D4\"}"),
+        );
+    };
+    let (reply, answer) = mpsc::channel();
+    if sender.send(Request { point, reply }).is_err() {
+        return ("503 Service Unavailable", error("the game has closed"));
+    }
+    ctx.request_repaint();
+    match answer.recv_timeout(consts::API_TIMEOUT) {
+        Ok(Ok(state)) => ("200 OK", state),
+        Ok(Err(reason)) => ("409 Conflict", error(&reason)),
+        Err(_) => ("503 Service Unavailable", error("the game did not answer")),
+    }
+}
+
+fn error(reason: &str) -> String {
+    serde_json::json!({ "error": reason }).to_string()
+}
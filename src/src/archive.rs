@@ -0,0 +1,290 @@
+//! Public collections of professional games kept up to date in the game database. A source is
+//! the address of an SGF file, or of a page listing SGF files such as a mirror's folder; each
+//! update downloads only the files it has not fetched before, and leaves out games whose final
+//! position is already in the database under another name.
+
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{self, Receiver, Sender};
+
+use eframe::egui;
+use serde::{Deserialize, Serialize};
+
+use crate::board::{Board, Stone};
+use crate::database::Record;
+use crate::i18n::trf;
+use crate::paths;
+use crate::sgf;
+
+#[derive(Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Source {
+    pub url: String,
+    // The files downloaded from it, so the next update skips them
+    pub fetched: Vec<String>,
+}
+
+#[derive(Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Archives {
+    pub sources: Vec<Source>,
+}
+
+impl Archives {
+    pub fn load(path: &Path) -> Self {
+        std::fs::read_to_string(path)
+            .ok()
+            .and_then(|text| serde_json::from_str(&text).ok())
+            .unwrap_or_default()
+    }
+
+    pub fn save(&self, path: &Path) -> std::io::Result<()> {
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::write(path, serde_json::to_string_pretty(self)?)
+    }
+}
+
+/// What an update sends back while it runs.
+pub enum Event {
+    // Files downloaded so far out of those found new
+    Progress { done: usize, total: usize },
+    Finished(Outcome),
+}
+
+/// What an update brought in.
+#[derive(Default)]
+pub struct Outcome {
+    pub records: Vec<Record>,
+    // The files now fetched from each source, by its address
+    pub fetched: Vec<(String, Vec<String>)>,
+    pub duplicates: usize,
+    // A line for each source or file that could not be read
+    pub errors: Vec<String>,
+}
+
+/// Updates `sources` on a thread of its own, given what the database holds already.
+pub fn update(sources: Vec<Source>, known: Vec<Record>, ctx: &egui::Context) -> Receiver<Event> {
+    let (sender, receiver) = mpsc::channel();
+    let ctx = ctx.clone();
+    std::thread::spawn(move || {
+        let outcome = run(&sources, &known, &sender, &ctx);
+        let _ = sender.send(Event::Finished(outcome));
+        ctx.request_repaint();
+    });
+    receiver
+}
+
+fn run(
+    sources: &[Source],
+    known: &[Record],
+    sender: &Sender<Event>,
+    ctx: &egui::Context,
+) -> Outcome {
+    let mut outcome = Outcome::default();
+    let mut hashes: HashSet<u64> = known.iter().map(record_hash).collect();
+    // Every source is listed before anything is downloaded, so the progress has a total
+    let mut new_files = Vec::new();
+    for source in sources {
+        match files(&source.url) {
+            Ok(files) => new_files.push(
+                files
+                    .into_iter()
+                    .filter(|file| !source.fetched.contains(file))
+                    .collect::<Vec<_>>(),
+            ),
+            Err(err) => {
+                outcome.errors.push(format!("{}: {}", source.url, err));
+                new_files.push(Vec::new());
+            }
+        }
+    }
+    let total = new_files.iter().map(Vec::len).sum();
+    let mut done = 0;
+    for (source, files) in sources.iter().zip(new_files) {
+        let folder = paths::archives_dir().join(folder_name(&source.url));
+        let mut fetched = source.fetched.clone();
+        for file in files {
+            match download(&file, &folder, &mut hashes) {
+                Ok((records, duplicates)) => {
+                    outcome.records.extend(records);
+                    outcome.duplicates += duplicates;
+                    fetched.push(file);
+                }
+                Err(err) => outcome.errors.push(format!("{}: {}", file, err)),
+            }
+            done += 1;
+            let _ = sender.send(Event::Progress { done, total });
+            ctx.request_repaint();
+        }
+        outcome.fetched.push((source.url.clone(), fetched));
+    }
+    outcome
+}
+
+fn fetch(url: &str) -> Result<String, String> {
+    let mut response = ureq::get(url).call().map_err(|err| err.to_string())?;
+    response
+        .body_mut()
+        .read_to_string()
+        .map_err(|err| err.to_string())
+}
+
+// The SGF files a source stands for: itself, or those its page links to
+fn files(url: &str) -> Result<Vec<String>, String> {
+    let url = url.trim();
+    if is_sgf(url) {
+        return Ok(vec![url.to_string()]);
+    }
+    let page = fetch(url)?;
+    let mut files = Vec::new();
+    for link in links(&page) {
+        let file = resolve(url, &link.replace("&amp;", "&"));
+        if is_sgf(&file) && !files.contains(&file) {
+            files.push(file);
+        }
+    }
+    if files.is_empty() {
+        return Err(trf("no SGF files listed at {}", &[&url]));
+    }
+    Ok(files)
+}
+
+fn is_sgf(url: &str) -> bool {
+    let path = url.split(['?', '#']).next().unwrap_or_default();
+    path.to_ascii_lowercase().ends_with(".sgf")
+}
+
+// The targets of the links in an HTML page, or the lines of a plain list
+fn links(page: &str) -> Vec<String> {
+    let mut links = Vec::new();
+    for piece in page.split("href=").skip(1) {
+        let (quote, rest) = match piece.chars().next() {
+            Some(quote @ ('"' | '\'')) => (quote, &piece[1..]),
+            _ => continue,
+        };
+        if let Some((link, _)) = rest.split_once(quote) {
+            links.push(link.to_string());
+        }
+    }
+    if links.is_empty() {
+        links = page
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty() && !line.starts_with('#'))
+            .map(str::to_string)
+            .collect();
+    }
+    links
+}
+
+// `link` as found on the page at `base`
+fn resolve(base: &str, link: &str) -> String {
+    if link.starts_with("http://") || link.starts_with("https://") {
+        return link.to_string();
+    }
+    let (scheme, rest) = base.split_once("://").unwrap_or(("https", base));
+    if let Some(link) = link.strip_prefix("//") {
+        return format!("{}://{}", scheme, link);
+    }
+    let host = rest.split('/').next().unwrap_or(rest);
+    if link.starts_with('/') {
+        return format!("{}://{}{}", scheme, host, link);
+    }
+    let page = base.split(['?', '#']).next().unwrap_or(base);
+    let folder = match page.rfind('/') {
+        Some(end) if end > scheme.len() + 2 => &page[..=end],
+        _ => return format!("{}://{}/{}", scheme, host, link),
+    };
+    format!("{}{}", folder, link.trim_start_matches("./"))
+}
+
+// Where the games of a source are kept: a folder named after its address
+fn folder_name(url: &str) -> String {
+    let rest = url.split_once("://").map_or(url, |(_, rest)| rest);
+    rest.trim_end_matches('/')
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || c == '-' || c == '.' {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect()
+}
+
+// Saves the games of the file at `url` to `folder`, each on its own, and returns the records of
+// those not known yet with how many were
+fn download(
+    url: &str,
+    folder: &Path,
+    hashes: &mut HashSet<u64>,
+) -> Result<(Vec<Record>, usize), String> {
+    let text = fetch(url)?;
+    let trees = sgf::parse(&text).map_err(|err| err.to_string())?;
+    // Named after the whole path, as mirrors often keep files of the same name in different
+    // folders
+    let page = url.split(['?', '#']).next().unwrap_or(url);
+    let path = page
+        .split_once("://")
+        .map_or(page, |(_, rest)| rest)
+        .split_once('/')
+        .map_or("game.sgf", |(_, path)| path);
+    let stem = folder_name(&path[..path.len() - ".sgf".len()]);
+    std::fs::create_dir_all(folder).map_err(|err| err.to_string())?;
+    let mut records = Vec::new();
+    let mut duplicates = 0;
+    for (index, tree) in trees.iter().enumerate() {
+        let text = sgf::to_string(tree);
+        let name = if trees.len() == 1 {
+            format!("{}.sgf", stem)
+        } else {
+            format!("{}-{}.sgf", stem, index + 1)
+        };
+        let path: PathBuf = folder.join(name);
+        let Some(record) = Record::from_sgf(path.clone(), &text) else {
+            continue;
+        };
+        if !hashes.insert(record_hash(&record)) {
+            duplicates += 1;
+            continue;
+        }
+        std::fs::write(&path, text).map_err(|err| err.to_string())?;
+        records.push(record);
+    }
+    Ok((records, duplicates))
+}
+
+/// The final position of a game with the number of moves that led to it, which two records of
+/// the same game share whatever their names and comments.
+pub fn record_hash(record: &Record) -> u64 {
+    let size = record.board_size;
+    let mut board = Board::new(size);
+    for (points, stone) in [
+        (&record.setup_black, Stone::Black),
+        (&record.setup_white, Stone::White),
+    ] {
+        for &(row, col) in points {
+            if row < size && col < size {
+                board[row][col] = stone;
+            }
+        }
+    }
+    let mut count: u64 = 0;
+    for (player, point) in record.moves() {
+        match point {
+            Some((row, col)) if row < size && col < size => {
+                if board.is_legal(row, col, player) {
+                    board.play(row, col, player);
+                } else {
+                    board[row][col] = player.to_stone();
+                }
+            }
+            _ => board.pass(),
+        }
+        count += 1;
+    }
+    board.checksum() ^ count.wrapping_mul(0x9e37_79b9_7f4a_7c15)
+}
@@ -0,0 +1,472 @@
+//! Accounts on the game server: names with salted password hashes, a Glicko-2 rating and the
+//! records of their games, kept in one JSON file. Tokens of logged in clients only live as long
+//! as the server runs. Passwords are hashed with PBKDF2, which takes a good part of a second, so
+//! the server does it away from the accounts and its async tasks.
+
+use std::collections::HashMap;
+use std::path::PathBuf;
+
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use subtle::ConstantTimeEq;
+
+use crate::protocol::{PlayedGame, Profile, RATING_PER_RANK, Session, rank_name};
+
+const START_RATING: f64 = 1500.0;
+const START_DEVIATION: f64 = 350.0;
+const START_VOLATILITY: f64 = 0.06;
+// How far the volatility may move in one game
+const VOLATILITY_CHANGE: f64 = 0.5;
+// Glicko-2 works on a scale this many times smaller than the ratings shown
+const GLICKO_SCALE: f64 = 173.7178;
+// Rounds of PBKDF2-HMAC-SHA256 a password goes through, to slow down guessing from a stolen file
+const PBKDF2_ROUNDS: u32 = 600_000;
+// Rounds of plain SHA-256 that accounts from before PBKDF2 were hashed with
+const LEGACY_ROUNDS: usize = 100_000;
+const MAX_NAME_LENGTH: usize = 20;
+const MIN_PASSWORD_LENGTH: usize = 6;
+
+#[derive(Serialize, Deserialize)]
+struct Account {
+    salt: String,
+    hash: String,
+    // Accounts saved without it were hashed before PBKDF2, and are hashed again at their next
+    // login
+    #[serde(default)]
+    scheme: Scheme,
+    #[serde(flatten)]
+    standing: Standing,
+    games: Vec<StoredGame>,
+}
+
+// A game in an account's history, with the accounts that may see it if it was private
+#[derive(Clone, Serialize, Deserialize)]
+struct StoredGame {
+    #[serde(flatten)]
+    game: PlayedGame,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    audience: Vec<String>,
+}
+
+impl StoredGame {
+    fn shown_to(&self, viewer: Option<&str>) -> bool {
+        self.audience.is_empty()
+            || viewer.is_some_and(|viewer| self.audience.iter().any(|name| name == viewer))
+    }
+}
+
+#[derive(Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+enum Scheme {
+    #[default]
+    Sha256,
+    Pbkdf2,
+}
+
+// A Glicko-2 rating: the deviation shrinks as games settle the rating, and the volatility says
+// how erratic the results have been. Accounts from before ratings had a deviation start out
+// with the deviation of a new account.
+#[derive(Clone, Copy, Serialize, Deserialize)]
+struct Standing {
+    rating: f64,
+    #[serde(default = "start_deviation")]
+    deviation: f64,
+    #[serde(default = "start_volatility")]
+    volatility: f64,
+}
+
+fn start_deviation() -> f64 {
+    START_DEVIATION
+}
+
+fn start_volatility() -> f64 {
+    START_VOLATILITY
+}
+
+impl Default for Standing {
+    fn default() -> Self {
+        Self {
+            rating: START_RATING,
+            deviation: START_DEVIATION,
+            volatility: START_VOLATILITY,
+        }
+    }
+}
+
+impl Standing {
+    // The standing after one game against `opponent`, with `score` 1 for a win and 0 for a loss;
+    // every game is a rating period of its own
+    fn after(self, opponent: Standing, score: f64) -> Standing {
+        let (mu, phi) = (
+            (self.rating - START_RATING) / GLICKO_SCALE,
+            self.deviation / GLICKO_SCALE,
+        );
+        let (opponent_mu, opponent_phi) = (
+            (opponent.rating - START_RATING) / GLICKO_SCALE,
+            opponent.deviation / GLICKO_SCALE,
+        );
+        let g = 1.0 / (1.0 + 3.0 * opponent_phi.powi(2) / std::f64::consts::PI.powi(2)).sqrt();
+        let expected = 1.0 / (1.0 + (-g * (mu - opponent_mu)).exp());
+        let variance = 1.0 / (g.powi(2) * expected * (1.0 - expected));
+        let delta = variance * g * (score - expected);
+        let volatility = new_volatility(self.volatility, phi, variance, delta);
+        let phi = 1.0 / (1.0 / (phi.powi(2) + volatility.powi(2)) + 1.0 / variance).sqrt();
+        Standing {
+            rating: START_RATING + GLICKO_SCALE * (mu + phi.powi(2) * g * (score - expected)),
+            deviation: (GLICKO_SCALE * phi).min(START_DEVIATION),
+            volatility,
+        }
+    }
+}
+
+// Solves for the volatility after a game by the Illinois method, as Glickman describes
+fn new_volatility(volatility: f64, phi: f64, variance: f64, delta: f64) -> f64 {
+    let a = volatility.powi(2).ln();
+    let f = |x: f64| {
+        let spread = phi.powi(2) + variance + x.exp();
+        x.exp() * (delta.powi(2) - spread) / (2.0 * spread.powi(2))
+            - (x - a) / VOLATILITY_CHANGE.powi(2)
+    };
+    let mut low = a;
+    let mut high = if delta.powi(2) > phi.powi(2) + variance {
+        (delta.powi(2) - phi.powi(2) - variance).ln()
+    } else {
+        let mut steps = 1.0;
+        while f(a - steps * VOLATILITY_CHANGE) < 0.0 {
+            steps += 1.0;
+        }
+        a - steps * VOLATILITY_CHANGE
+    };
+    let (mut f_low, mut f_high) = (f(low), f(high));
+    while (high - low).abs() > 1e-6 {
+        let next = low + (low - high) * f_low / (f_high - f_low);
+        let f_next = f(next);
+        if f_next * f_high <= 0.0 {
+            (low, f_low) = (high, f_high);
+        } else {
+            f_low /= 2.0;
+        }
+        (high, f_high) = (next, f_next);
+    }
+    (low / 2.0).exp()
+}
+
+pub struct Accounts {
+    path: PathBuf,
+    accounts: HashMap<String, Account>,
+    tokens: HashMap<String, String>,
+}
+
+// Hex of `count` bytes from the system's secure random source
+fn random_hex(count: usize) -> String {
+    let mut bytes = vec![0; count];
+    let random = rustls::crypto::ring::default_provider().secure_random;
+    if random.fill(&mut bytes).is_err() {
+        panic!("no secure random source");
+    }
+    hex(&bytes)
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+fn hash_password(scheme: Scheme, salt: &str, password: &str) -> String {
+    match scheme {
+        Scheme::Pbkdf2 => {
+            let mut hash = [0; 32];
+            pbkdf2::pbkdf2_hmac::<Sha256>(
+                password.as_bytes(),
+                salt.as_bytes(),
+                PBKDF2_ROUNDS,
+                &mut hash,
+            );
+            hex(&hash)
+        }
+        Scheme::Sha256 => {
+            let mut digest = Sha256::digest(format!("{}{}", salt, password));
+            for _ in 1..LEGACY_ROUNDS {
+                let mut hasher = Sha256::new();
+                hasher.update(salt);
+                hasher.update(digest);
+                digest = hasher.finalize();
+            }
+            hex(&digest)
+        }
+    }
+}
+
+/// A password hashed for a new account, or again for one hashed before PBKDF2.
+pub struct Hashed {
+    salt: String,
+    hash: String,
+}
+
+impl Hashed {
+    pub fn new(password: &str) -> Self {
+        let salt = random_hex(16);
+        Hashed {
+            hash: hash_password(Scheme::Pbkdf2, &salt, password),
+            salt,
+        }
+    }
+}
+
+/// An account's hash as it was when a login began, to check the password against without the
+/// accounts locked.
+pub struct Stored {
+    name: String,
+    salt: String,
+    hash: String,
+    scheme: Scheme,
+}
+
+impl Stored {
+    /// Whether `password` is the account's, compared in constant time.
+    pub fn matches(&self, password: &str) -> bool {
+        let hash = hash_password(self.scheme, &self.salt, password);
+        hash.as_bytes().ct_eq(self.hash.as_bytes()).into()
+    }
+
+    /// Whether the account should be hashed again once the password matched.
+    pub fn outdated(&self) -> bool {
+        self.scheme != Scheme::Pbkdf2
+    }
+}
+
+/// Works through a login as long as a real one would for a name with no account, so the time
+/// taken does not tell which names exist.
+pub fn waste_login(password: &str) {
+    let _ = hash_password(Scheme::Pbkdf2, "", password);
+}
+
+impl Accounts {
+    /// Reads the accounts saved at `path`, or starts with none if there is no file yet.
+    pub fn load(path: PathBuf) -> std::io::Result<Self> {
+        let accounts = match std::fs::read_to_string(&path) {
+            Ok(text) => serde_json::from_str(&text).map_err(std::io::Error::other)?,
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => HashMap::new(),
+            Err(err) => return Err(err),
+        };
+        Ok(Self {
+            path,
+            accounts,
+            tokens: HashMap::new(),
+        })
+    }
+
+    fn save(&self) {
+        let result = serde_json::to_string_pretty(&self.accounts)
+            .map_err(std::io::Error::other)
+            .and_then(|text| std::fs::write(&self.path, text));
+        if let Err(err) = result {
+            eprintln!("could not save {}: {}", self.path.display(), err);
+        }
+    }
+
+    fn session(&mut self, name: &str) -> Session {
+        let token = random_hex(24);
+        self.tokens.insert(token.clone(), name.to_string());
+        Session {
+            name: name.to_string(),
+            token,
+        }
+    }
+
+    /// Checks that `name` and `password` may open an account, before its password is hashed.
+    pub fn check_new(&self, name: &str, password: &str) -> Result<(), String> {
+        let name = name.trim();
+        if name.is_empty()
+            || name.chars().count() > MAX_NAME_LENGTH
+            || !name
+                .chars()
+                .all(|c| c.is_alphanumeric() || c == '_' || c == '-')
+        {
+            return Err(format!(
+                "names have 1 to {} letters, digits, - or _",
+                MAX_NAME_LENGTH
+            ));
+        }
+        if password.chars().count() < MIN_PASSWORD_LENGTH {
+            return Err(format!(
+                "passwords have at least {} characters",
+                MIN_PASSWORD_LENGTH
+            ));
+        }
+        self.check_free(name)
+    }
+
+    // Names differing only in case would be confused with one another
+    fn check_free(&self, name: &str) -> Result<(), String> {
+        match self.find(name) {
+            Some(_) => Err("that name is taken".to_string()),
+            None => Ok(()),
+        }
+    }
+
+    /// Opens a new account with the password `hashed` and logs it in, unless the name was taken
+    /// while it was hashed.
+    pub fn register(&mut self, name: &str, hashed: Hashed) -> Result<Session, String> {
+        let name = name.trim();
+        self.check_free(name)?;
+        self.accounts.insert(
+            name.to_string(),
+            Account {
+                salt: hashed.salt,
+                hash: hashed.hash,
+                scheme: Scheme::Pbkdf2,
+                standing: Standing::default(),
+                games: Vec::new(),
+            },
+        );
+        self.save();
+        Ok(self.session(name))
+    }
+
+    /// The hash to check a login to `name` against.
+    pub fn stored(&self, name: &str) -> Option<Stored> {
+        let name = self.find(name.trim())?;
+        let account = &self.accounts[name];
+        Some(Stored {
+            name: name.to_string(),
+            salt: account.salt.clone(),
+            hash: account.hash.clone(),
+            scheme: account.scheme,
+        })
+    }
+
+    /// Logs in the account whose password matched `stored`, hashing it again with `rehashed`
+    /// when it was hashed before PBKDF2.
+    pub fn log_in(&mut self, stored: &Stored, rehashed: Option<Hashed>) -> Result<Session, String> {
+        let account = self
+            .accounts
+            .get_mut(&stored.name)
+            // The password changed while it was checked
+            .filter(|account| account.hash == stored.hash)
+            .ok_or_else(|| "wrong name or password".to_string())?;
+        if let Some(rehashed) = rehashed {
+            account.salt = rehashed.salt;
+            account.hash = rehashed.hash;
+            account.scheme = Scheme::Pbkdf2;
+            self.save();
+        }
+        Ok(self.session(&stored.name))
+    }
+
+    // The account named `name`, ignoring case
+    fn find(&self, name: &str) -> Option<&str> {
+        self.accounts
+            .keys()
+            .find(|other| other.to_lowercase() == name.to_lowercase())
+            .map(String::as_str)
+    }
+
+    /// The account logged in with `token`.
+    pub fn name(&self, token: &str) -> Option<&str> {
+        self.tokens.get(token).map(String::as_str)
+    }
+
+    /// The account's name as registered, if there is one by `name` in any case.
+    pub fn canonical(&self, name: &str) -> Option<String> {
+        self.find(name.trim()).map(str::to_string)
+    }
+
+    /// The account `name` as `viewer` sees it, which leaves out the private games they were
+    /// not in.
+    pub fn profile(&self, name: &str, viewer: Option<&str>) -> Option<Profile> {
+        let name = self.find(name)?;
+        let account = &self.accounts[name];
+        Some(Profile {
+            name: name.to_string(),
+            rating: account.standing.rating,
+            deviation: account.standing.deviation,
+            games: account
+                .games
+                .iter()
+                .rev()
+                .filter(|stored| stored.shown_to(viewer))
+                .map(|stored| stored.game.clone())
+                .collect(),
+        })
+    }
+
+    /// Whether `viewer` may download the stored record `file`. A private game is in the history
+    /// of its creator, who has to be logged in to make one, so a file in no history is public.
+    pub fn may_see(&self, file: &str, viewer: Option<&str>) -> bool {
+        self.accounts
+            .values()
+            .flat_map(|account| &account.games)
+            .filter(|stored| stored.game.file == file)
+            .all(|stored| stored.shown_to(viewer))
+    }
+
+    /// The rating of the account `name`.
+    pub fn rating(&self, name: &str) -> f64 {
+        self.accounts
+            .get(name)
+            .map_or(START_RATING, |account| account.standing.rating)
+    }
+
+    /// The rank of the account `name` as shown to other players, if there is one by that name.
+    pub fn rank(&self, name: &str) -> Option<String> {
+        let standing = self.accounts.get(name)?.standing;
+        Some(rank_name(standing.rating, standing.deviation))
+    }
+
+    /// Adds a stored game to the history of each of the accounts `names`, without rating it.
+    /// Only the accounts of `audience` see a game with one.
+    pub fn add_game(&mut self, game: PlayedGame, audience: Vec<String>, names: &[&str]) {
+        let stored = StoredGame { game, audience };
+        for name in names {
+            if let Some(account) = self.accounts.get_mut(*name) {
+                account.games.push(stored.clone());
+            }
+        }
+        self.save();
+    }
+
+    /// Adds a stored game to the history of the accounts that played it, and rates it when two
+    /// different accounts played to a win. Black counts as `handicap` ranks stronger than rated.
+    pub fn record_game(
+        &mut self,
+        game: PlayedGame,
+        audience: Vec<String>,
+        black: Option<&str>,
+        white: Option<&str>,
+        handicap: u32,
+    ) {
+        let result = game.result.clone();
+        let stored = StoredGame { game, audience };
+        for name in [black, white].into_iter().flatten() {
+            if let Some(account) = self.accounts.get_mut(name) {
+                account.games.push(stored.clone());
+            }
+        }
+        if let (Some(black), Some(white)) = (black, white)
+            && black != white
+            && let Some(score) = match result.chars().next() {
+                Some('B') => Some(1.0),
+                Some('W') => Some(0.0),
+                _ => None,
+            }
+            && let (Some(black_standing), Some(white_standing)) = (
+                self.accounts.get(black).map(|account| account.standing),
+                self.accounts.get(white).map(|account| account.standing),
+            )
+        {
+            let stones = f64::from(handicap) * RATING_PER_RANK;
+            let given = Standing {
+                rating: black_standing.rating + stones,
+                ..black_standing
+            };
+            if let Some(account) = self.accounts.get_mut(black) {
+                account.standing = given.after(white_standing, score);
+                account.standing.rating -= stones;
+            }
+            if let Some(account) = self.accounts.get_mut(white) {
+                account.standing = white_standing.after(given, 1.0 - score);
+            }
+        }
+        self.save();
+    }
+}
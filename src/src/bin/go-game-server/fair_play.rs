@@ -0,0 +1,146 @@
+//! A basic check of rated games for play with outside help, from the seconds each move took:
+//! people take longer over some moves than others, and longest where groups are short of
+//! liberties, while a program relayed by hand answers at much the same pace throughout. What it
+//! finds is only a reason for the room's creator to look at the game, never proof.
+
+use crate::board::{Board, Move, Player, Stone};
+
+// Moves a player must have made before their timing says anything
+const MIN_MOVES: usize = 20;
+// Human thinking times spread more than this share of their mean
+const STEADY_SPREAD: f32 = 0.2;
+// Steady times quicker than this are just fast play
+const STEADY_MIN_MEAN: f32 = 1.0;
+// A position is complex with this many stones in groups of two liberties or fewer
+const COMPLEX_STONES: usize = 4;
+const MIN_COMPLEX_MOVES: usize = 5;
+// Complex positions answered in this many seconds on average are blitz play
+const BLITZ_SECONDS: f32 = 5.0;
+
+// One move of a player, as the check sees it
+struct Timed {
+    seconds: f32,
+    complex: bool,
+    // The reply captured some of the player's stones
+    lost: bool,
+}
+
+/// What looks suspicious about each player's timing in the game played on `start`, as lines
+/// such as "37 moves at 4.1 seconds each, give or take 9%"; a player with nothing to note
+/// is left out.
+pub fn flags(start: &Board, moves: &[(Move, f32)]) -> Vec<(Player, Vec<String>)> {
+    let mut board = start.clone();
+    let mut timed: Vec<(Player, Timed)> = Vec::new();
+    // Where in `timed` each player's last stone is, to mark it when the reply captures
+    let mut last: [Option<usize>; 2] = [None, None];
+    for &((player, point), seconds) in moves {
+        let Some((row, col)) = point else {
+            board.pass();
+            continue;
+        };
+        let complex = stones_short_of_liberties(&board) >= COMPLEX_STONES;
+        if board.play(row, col, player) > 0
+            && let Some(index) = last[seat(player.other())]
+        {
+            timed[index].1.lost = true;
+        }
+        last[seat(player)] = Some(timed.len());
+        timed.push((
+            player,
+            Timed {
+                seconds,
+                complex,
+                lost: false,
+            },
+        ));
+    }
+    [Player::Black, Player::White]
+        .into_iter()
+        .filter_map(|player| {
+            let own: Vec<&Timed> = timed
+                .iter()
+                .filter(|(by, _)| *by == player)
+                .map(|(_, timed)| timed)
+                .collect();
+            let lines = check(&own);
+            (!lines.is_empty()).then_some((player, lines))
+        })
+        .collect()
+}
+
+fn seat(player: Player) -> usize {
+    match player {
+        Player::Black => 0,
+        Player::White => 1,
+    }
+}
+
+fn check(moves: &[&Timed]) -> Vec<String> {
+    let mut lines = Vec::new();
+    if moves.len() < MIN_MOVES {
+        return lines;
+    }
+    let seconds: Vec<f32> = moves.iter().map(|timed| timed.seconds).collect();
+    let (mean, spread) = mean_and_spread(&seconds);
+    if mean >= STEADY_MIN_MEAN && spread < STEADY_SPREAD * mean {
+        lines.push(format!(
+            "{} moves at {:.1} seconds each, give or take {:.0}%",
+            moves.len(),
+            mean,
+            100.0 * spread / mean
+        ));
+    }
+    let (complex, simple): (Vec<&Timed>, Vec<&Timed>) =
+        moves.iter().partition(|timed| timed.complex);
+    if complex.len() >= MIN_COMPLEX_MOVES && !simple.is_empty() {
+        let average = |moves: &[&Timed]| {
+            moves.iter().map(|timed| timed.seconds).sum::<f32>() / moves.len() as f32
+        };
+        let (complex_mean, simple_mean) = (average(&complex), average(&simple));
+        if complex_mean <= BLITZ_SECONDS
+            && complex_mean <= simple_mean
+            && complex.iter().all(|timed| !timed.lost)
+        {
+            lines.push(format!(
+                "{} moves in fights at {:.1} seconds against {:.1} elsewhere, without losing a stone to the reply",
+                complex.len(),
+                complex_mean,
+                simple_mean
+            ));
+        }
+    }
+    lines
+}
+
+// The mean and the standard deviation
+fn mean_and_spread(values: &[f32]) -> (f32, f32) {
+    let mean = values.iter().sum::<f32>() / values.len() as f32;
+    let variance = values
+        .iter()
+        .map(|value| (value - mean).powi(2))
+        .sum::<f32>()
+        / values.len() as f32;
+    (mean, variance.sqrt())
+}
+
+fn stones_short_of_liberties(board: &Board) -> usize {
+    let size = board.size();
+    let mut seen = vec![vec![false; size]; size];
+    let mut count = 0;
+    for row in 0..size {
+        for col in 0..size {
+            let stone = board[row][col];
+            if stone == Stone::Empty || seen[row][col] {
+                continue;
+            }
+            let group = board.get_group(row, col, stone);
+            for &(row, col) in &group {
+                seen[row][col] = true;
+            }
+            if board.liberties(row, col).len() <= 2 {
+                count += group.len();
+            }
+        }
+    }
+    count
+}
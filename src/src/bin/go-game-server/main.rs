@@ -0,0 +1,1776 @@
+//! Relays games between copies of the app. A client opens a room with the record to play on and
+//! shares its code; once a second client enters, both are sent the game and every move goes
+//! through here, checked with the app's own board rules. Anyone entering a room after that
+//! watches the game. Finished games are stored as SGF. Given a certificate, it only speaks TLS.
+//! Players may log in to accounts, which keep their games and a rating. Correspondence games
+//! stay open while their players come and go, and are saved to open again after a restart.
+
+mod accounts;
+mod fair_play;
+
+use std::collections::HashMap;
+use std::hash::BuildHasher;
+use std::path::{Path as FilePath, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use axum::extract::ws::{Message as Frame, WebSocket, WebSocketUpgrade};
+use axum::extract::{Path, State};
+use axum::http::{HeaderMap, StatusCode, header};
+use axum::response::{IntoResponse, Response};
+use axum::routing::{any, get, post};
+use axum::{Json, Router};
+use axum_server::tls_rustls::RustlsConfig;
+use clap::Parser;
+use serde::{Deserialize, Serialize};
+use src::{board, protocol, sgf};
+use tokio::sync::mpsc::{self, UnboundedSender};
+
+use accounts::Accounts;
+use board::{Board, Move, Player, Stone};
+use protocol::{
+    AwayClock, Credentials, DelayUnit, Latency, Message, NewRoom, OngoingGame, Plan, PlayedGame,
+    Profile, RATING_PER_RANK, Reconnect, RoomCreated, RoomInfo, SYNC_INTERVAL, Seek, Session,
+    SpectatorDelay, Speed,
+};
+
+const DEFAULT_PORT: u16 = 7651;
+// Letters that cannot be mistaken for one another when a code is read out
+const CODE_LETTERS: &[u8; 32] = b"ABCDEFGHJKLMNPQRSTUVWXYZ23456789";
+const CODE_LENGTH: usize = 5;
+// Rating points apart two seekers may be matched at first, how fast that grows per second of
+// waiting and how far it goes
+const MATCH_WINDOW: f64 = 150.0;
+const MATCH_WIDENING: f64 = 10.0;
+const MAX_MATCH_WINDOW: f64 = 900.0;
+const MATCH_INTERVAL: Duration = Duration::from_secs(5);
+// How long matched players have to enter their room
+const MATCH_ENTRY_TIME: Duration = Duration::from_secs(30);
+const MATCH_RECONNECT: Reconnect = Reconnect {
+    seconds: 60,
+    clock: AwayClock::Stops,
+};
+const MAX_HANDICAP: u32 = 9;
+// How often the spectators of delayed rooms are sent the moves that are due, and players are
+// pinged when it is time
+const ROOM_TICK: Duration = Duration::from_secs(1);
+const DEFAULT_KOMI: &str = "6.5";
+
+#[derive(Parser)]
+#[command(
+    name = "go-game-server",
+    about = "Hosts rooms for games between go-game-rust clients"
+)]
+struct Args {
+    #[arg(long, default_value_t = DEFAULT_PORT)]
+    port: u16,
+    /// Where finished games are saved
+    #[arg(long, default_value = "server-games")]
+    games: PathBuf,
+    /// The file accounts are kept in
+    #[arg(long, default_value = "server-accounts.json")]
+    accounts: PathBuf,
+    /// The file correspondence games in progress are kept in
+    #[arg(long, default_value = "server-correspondence.json")]
+    correspondence: PathBuf,
+    /// A PEM certificate chain to serve HTTPS and WSS with, instead of plain HTTP
+    #[arg(long, requires = "key")]
+    cert: Option<PathBuf>,
+    /// The PEM private key of the certificate
+    #[arg(long, requires = "cert")]
+    key: Option<PathBuf>,
+}
+
+struct Room {
+    // The record the game starts from, as its creator sent it
+    root: sgf::Node,
+    creator: Player,
+    // The position the game started from, to replay on after taking moves back
+    start: Board,
+    start_to_move: Player,
+    board: Board,
+    to_move: Player,
+    // Every move with the seconds it took, and when it was played
+    moves: Vec<(Move, f32)>,
+    played_at: Vec<Instant>,
+    // When the player to move started thinking
+    move_started: Instant,
+    // How long a player whose connection drops has to come back, and who is away since when
+    reconnect: Option<Reconnect>,
+    away: Option<(Player, Instant)>,
+    // An undo asked for by that player and not yet answered, with the moves it takes back
+    undo_request: Option<(Player, usize)>,
+    // The connection of each color, Black first, and the account playing it if logged in
+    players: [Option<UnboundedSender<Message>>; 2],
+    names: [Option<String>; 2],
+    // Opened for two players the server paired, whose seats are kept for their accounts
+    matched: bool,
+    // Who besides the creator may enter a private game
+    invited: Vec<String>,
+    // The ranks Black is given, for rating the game
+    handicap: u32,
+    // Numbered so their chat goes to the others only
+    spectators: Vec<(usize, UnboundedSender<Message>)>,
+    next_spectator: usize,
+    started: bool,
+    // Kept while its players are away, with when the last move was played in seconds since the
+    // Unix epoch and how many of the moves each player has been sent
+    correspondence: bool,
+    last_move: u64,
+    seen: [usize; 2],
+    // The replies each player of a correspondence game planned, Black's first
+    plans: [Plan; 2],
+    // Pair go, with the second player of each side and their account, Black's first; the two
+    // players of a side take turns playing its moves, the first player starting
+    rengo: bool,
+    partners: [Option<UnboundedSender<Message>>; 2],
+    partner_names: [Option<String>; 2],
+    // One of several games its creator plays at once
+    simul: bool,
+    // How far behind the spectators are kept, and how many moves they have been sent then
+    spectator_delay: Option<SpectatorDelay>,
+    shown: usize,
+    // The round trip to each player, by seat and then by turn, which is taken off their moves'
+    // time as the server counts it
+    latency: [[Latency; 2]; 2],
+}
+
+// A correspondence room as saved between runs of the server
+#[derive(Serialize, Deserialize)]
+struct StoredRoom {
+    // The record with the moves so far, and the seconds each took
+    sgf: String,
+    seconds: Vec<f32>,
+    creator: Player,
+    names: [Option<String>; 2],
+    invited: Vec<String>,
+    handicap: u32,
+    started: bool,
+    last_move: u64,
+    seen: [usize; 2],
+    #[serde(default)]
+    plans: [Plan; 2],
+    #[serde(default)]
+    spectator_delay: Option<SpectatorDelay>,
+}
+
+fn unix_seconds() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map_or(0, |elapsed| elapsed.as_secs())
+}
+
+// Who a connection to a room is: a color and, in pair go, which of the side's two players
+#[derive(Clone, Copy)]
+enum Seat {
+    Player(Player, usize),
+    Spectator(usize),
+}
+
+impl Room {
+    // A room waiting for its players to play on `root`, with `creator` taking their color
+    fn new(
+        mut root: sgf::Node,
+        creator: Player,
+        reconnect: Option<Reconnect>,
+    ) -> Result<Room, String> {
+        // Rooms start from the position on the root, before any move
+        root.children.clear();
+        let size = sgf::parse_size(root.get("SZ").unwrap_or("19"))
+            .filter(|size| (2..=25).contains(size))
+            .ok_or_else(|| "unsupported board size".to_string())?;
+        let mut board = Board::new(size);
+        for (ident, stone) in [("AB", Stone::Black), ("AW", Stone::White)] {
+            for value in root.get_all(ident) {
+                if let Some((row, col)) = sgf::parse_point(value, size) {
+                    board[row][col] = stone;
+                }
+            }
+        }
+        let to_move = match root.get("PL") {
+            Some("W") => Player::White,
+            _ => Player::Black,
+        };
+        let handicap = root
+            .get("HA")
+            .and_then(|stones| stones.parse().ok())
+            .filter(|&stones| stones >= 2)
+            .unwrap_or(0);
+        Ok(Room {
+            root,
+            creator,
+            start: board.clone(),
+            start_to_move: to_move,
+            board,
+            to_move,
+            moves: Vec::new(),
+            played_at: Vec::new(),
+            move_started: Instant::now(),
+            undo_request: None,
+            reconnect,
+            away: None,
+            players: [None, None],
+            names: [None, None],
+            matched: false,
+            invited: Vec::new(),
+            handicap,
+            spectators: Vec::new(),
+            next_spectator: 0,
+            started: false,
+            correspondence: false,
+            last_move: unix_seconds(),
+            seen: [0, 0],
+            plans: Default::default(),
+            rengo: false,
+            partners: [None, None],
+            partner_names: [None, None],
+            simul: false,
+            spectator_delay: None,
+            shown: 0,
+            latency: Default::default(),
+        })
+    }
+
+    // Opens a saved correspondence room again, playing its moves over
+    fn restore(stored: StoredRoom) -> Result<Room, String> {
+        let mut trees = sgf::parse(&stored.sgf).map_err(|err| err.to_string())?;
+        if trees.is_empty() {
+            return Err("no game in the record".to_string());
+        }
+        let root = trees.swap_remove(0);
+        let size = sgf::parse_size(root.get("SZ").unwrap_or("19")).unwrap_or(19);
+        let moves: Vec<Move> = sgf::main_line(&root)
+            .skip(1)
+            .filter_map(|node| {
+                let (player, value) = match (node.get("B"), node.get("W")) {
+                    (Some(value), _) => (Player::Black, value),
+                    (_, Some(value)) => (Player::White, value),
+                    _ => return None,
+                };
+                Some((player, sgf::parse_point(value, size)))
+            })
+            .collect();
+        let mut room = Room::new(root, stored.creator, None)?;
+        for (index, played) in moves.into_iter().enumerate() {
+            let seconds = stored.seconds.get(index).copied().unwrap_or_default();
+            if !room.play(played, seconds) {
+                return Err("a saved move cannot be played".to_string());
+            }
+        }
+        room.names = stored.names;
+        room.invited = stored.invited;
+        room.handicap = stored.handicap;
+        room.started = stored.started;
+        room.correspondence = true;
+        room.last_move = stored.last_move;
+        room.seen = stored.seen;
+        room.plans = stored.plans;
+        room.spectator_delay = stored.spectator_delay;
+        // The player to move has been thinking since the last move, also while the server was down
+        let thinking = Duration::from_secs(unix_seconds().saturating_sub(stored.last_move));
+        room.move_started = Instant::now()
+            .checked_sub(thinking)
+            .unwrap_or_else(Instant::now);
+        Ok(room)
+    }
+
+    fn stored(&self) -> StoredRoom {
+        StoredRoom {
+            sgf: sgf::to_string(&self.record(None)),
+            seconds: self.moves.iter().map(|&(_, seconds)| seconds).collect(),
+            creator: self.creator,
+            names: self.names.clone(),
+            invited: self.invited.clone(),
+            handicap: self.handicap,
+            started: self.started,
+            last_move: self.last_move,
+            seen: self.seen,
+            plans: self.plans.clone(),
+            spectator_delay: self.spectator_delay,
+        }
+    }
+
+    fn seat(color: Player) -> usize {
+        match color {
+            Player::Black => 0,
+            Player::White => 1,
+        }
+    }
+
+    fn info(&self, code: &str, accounts: &Accounts) -> RoomInfo {
+        let host = self.names[Room::seat(self.creator)]
+            .clone()
+            .unwrap_or_default();
+        let time = [self.root.get("TM"), self.root.get("OT")]
+            .into_iter()
+            .flatten()
+            .collect::<Vec<_>>()
+            .join(" + ");
+        RoomInfo {
+            code: code.to_string(),
+            board_size: self.board.size(),
+            komi: self.root.get("KM").unwrap_or("0").to_string(),
+            time,
+            open_color: self.creator.other(),
+            playing: self.started,
+            host_rank: accounts.rank(&host).unwrap_or_default(),
+            host,
+            private: !self.invited.is_empty(),
+            correspondence: self.correspondence,
+            rengo: self.rengo,
+            simul: self.simul,
+            spectator_delay: self.spectator_delay,
+        }
+    }
+
+    // The correspondence game as `name` sees it, if they play in it; it is their turn to move, or
+    // to see the game ended by passes
+    fn ongoing(&self, code: &str, name: &str) -> Option<OngoingGame> {
+        let color = [Player::Black, Player::White]
+            .into_iter()
+            .find(|&color| self.names[Room::seat(color)].as_deref() == Some(name))?;
+        let your_turn = self.started
+            && if self.ended_by_passes() {
+                self.seen[Room::seat(color)] < self.moves.len()
+            } else {
+                self.to_move == color
+            };
+        Some(OngoingGame {
+            code: code.to_string(),
+            color,
+            opponent: self.names[Room::seat(color.other())]
+                .clone()
+                .unwrap_or_default(),
+            board_size: self.board.size(),
+            moves: self.moves.len(),
+            your_turn,
+            last_move: self.last_move,
+        })
+    }
+
+    // Notes that the players connected now have been sent every move
+    fn mark_seen(&mut self) {
+        for seat in 0..2 {
+            if self.players[seat].is_some() {
+                self.seen[seat] = self.moves.len();
+            }
+        }
+    }
+
+    // Whether the account `name` may enter; anyone may enter a game that was not made private
+    fn admits(&self, name: Option<&str>) -> bool {
+        self.invited.is_empty()
+            || name.is_some_and(|name| {
+                self.names[Room::seat(self.creator)].as_deref() == Some(name)
+                    || self.invited.iter().any(|invited| invited == name)
+            })
+    }
+
+    // Those who may see the stored game of a private room, none for one open to all
+    fn audience(&self) -> Vec<String> {
+        if self.invited.is_empty() {
+            return Vec::new();
+        }
+        let mut audience: Vec<String> = self
+            .names
+            .iter()
+            .chain(&self.partner_names)
+            .flatten()
+            .chain(&self.invited)
+            .cloned()
+            .collect();
+        audience.sort();
+        audience.dedup();
+        audience
+    }
+
+    // The connection of the first or second player of `color`
+    fn connection(&self, color: Player, turn: usize) -> Option<&UnboundedSender<Message>> {
+        let players = if turn == 0 {
+            &self.players
+        } else {
+            &self.partners
+        };
+        players[Room::seat(color)].as_ref()
+    }
+
+    // Sends to the player of `color`, or both players of the side in pair go
+    fn send(&self, color: Player, message: &Message) {
+        for turn in 0..2 {
+            if let Some(player) = self.connection(color, turn) {
+                let _ = player.send(message.clone());
+            }
+        }
+    }
+
+    // Sends what one player of a pair go side did to the other
+    fn send_partner(&self, color: Player, turn: usize, message: &Message) {
+        if let Some(partner) = self.connection(color, 1 - turn) {
+            let _ = partner.send(message.clone());
+        }
+    }
+
+    // Which of the two players of `color` plays its next move in pair go
+    fn turn(&self, color: Player) -> usize {
+        self.moves
+            .iter()
+            .filter(|((player, _), _)| *player == color)
+            .count()
+            % 2
+    }
+
+    // The players of `color` who have accounts, as the record names them
+    fn team_name(&self, color: Player) -> Option<String> {
+        let seat = Room::seat(color);
+        let names: Vec<&str> = [&self.names[seat], &self.partner_names[seat]]
+            .into_iter()
+            .flatten()
+            .map(String::as_str)
+            .collect();
+        (!names.is_empty()).then(|| names.join(" & "))
+    }
+
+    // Every connection to the room: players, partners and spectators
+    fn everyone(&self) -> impl Iterator<Item = &UnboundedSender<Message>> {
+        let spectators = self.spectators.iter().map(|(_, spectator)| spectator);
+        self.players
+            .iter()
+            .chain(&self.partners)
+            .flatten()
+            .chain(spectators)
+    }
+
+    // Sends what `from` did to the other player and every spectator
+    fn relay(&self, from: Player, message: &Message) {
+        self.send(from.other(), message);
+        for (_, spectator) in &self.spectators {
+            let _ = spectator.send(message.clone());
+        }
+    }
+
+    // Like `relay` for a move, which the spectators of a delayed room only see later
+    fn relay_move(&self, from: Player, message: &Message) {
+        if self.spectator_delay.is_some() {
+            self.send(from.other(), message);
+        } else {
+            self.relay(from, message);
+        }
+    }
+
+    // How many of the moves spectators may see by now
+    fn visible(&self) -> usize {
+        match self.spectator_delay {
+            None => self.moves.len(),
+            Some(SpectatorDelay {
+                amount,
+                unit: DelayUnit::Moves,
+            }) => self.moves.len().saturating_sub(amount as usize),
+            Some(SpectatorDelay {
+                amount,
+                unit: DelayUnit::Minutes,
+            }) => {
+                let delay = Duration::from_secs(u64::from(amount) * 60);
+                self.played_at.partition_point(|at| at.elapsed() >= delay)
+            }
+        }
+    }
+
+    // The moves every spectator has been sent
+    fn shown(&self) -> usize {
+        if self.spectator_delay.is_some() {
+            self.shown
+        } else {
+            self.moves.len()
+        }
+    }
+
+    // Sends the spectators of a delayed room the moves up to `count` they have not seen yet
+    fn reveal(&mut self, count: usize) {
+        if self.spectator_delay.is_none() {
+            return;
+        }
+        let count = count.min(self.moves.len());
+        for &(played, seconds) in self.moves.get(self.shown..count).unwrap_or_default() {
+            let message = Message::Play { played, seconds };
+            for (_, spectator) in &self.spectators {
+                let _ = spectator.send(message.clone());
+            }
+        }
+        self.shown = self.shown.max(count);
+    }
+
+    // Takes back from the spectators of a delayed room the moves they saw that are gone
+    fn hide_taken_back(&mut self) {
+        let count = self.shown.saturating_sub(self.moves.len());
+        if count > 0 {
+            let message = Message::UndoAccept(count);
+            for (_, spectator) in &self.spectators {
+                let _ = spectator.send(message.clone());
+            }
+            self.shown = self.moves.len();
+        }
+    }
+
+    fn send_spectators(&self) {
+        let message = Message::Spectators(self.spectators.len());
+        for connection in self.everyone() {
+            let _ = connection.send(message.clone());
+        }
+    }
+
+    // Sends `first`, which carries the record, then the first `count` moves, and the time on
+    // the move once that is every move so far
+    fn catch_up(&self, to: &UnboundedSender<Message>, first: Message, count: usize) {
+        let _ = to.send(first);
+        for &(played, seconds) in &self.moves[..count] {
+            let _ = to.send(Message::Play { played, seconds });
+        }
+        if count == self.moves.len() {
+            let thinking = self.move_started.elapsed().as_secs_f32();
+            let _ = to.send(Message::Thinking(thinking));
+        }
+    }
+
+    // Catches a spectator up with the game as far as they may see it
+    fn catch_up_spectator(&self, spectator: &UnboundedSender<Message>) {
+        let sgf = sgf::to_string(&self.root);
+        self.catch_up(spectator, Message::Watch { sgf }, self.shown());
+        if let Some(delay) = self.spectator_delay {
+            let _ = spectator.send(Message::Delay(delay));
+        }
+    }
+
+    // Catches a new spectator up with the game so far, and numbers them
+    fn watch(&mut self, spectator: UnboundedSender<Message>) -> usize {
+        self.catch_up_spectator(&spectator);
+        let id = self.next_spectator;
+        self.next_spectator += 1;
+        self.spectators.push((id, spectator));
+        self.send_spectators();
+        id
+    }
+
+    // Sends the player of `color` the game as it is now, with the terms it is played on; `turn`
+    // says which of the side's players they are in pair go
+    fn resume(&self, color: Player, turn: usize, player: &UnboundedSender<Message>) {
+        let sgf = sgf::to_string(&self.root);
+        self.catch_up(player, Message::Resume { color, sgf }, self.moves.len());
+        if let Some(terms) = self.reconnect {
+            let _ = player.send(Message::Reconnect(terms));
+        }
+        if self.correspondence {
+            let _ = player.send(Message::Correspondence);
+            let plan = &self.plans[Room::seat(color)];
+            if !plan.lines.is_empty() {
+                let _ = player.send(Message::Plan(plan.clone()));
+            }
+        }
+        if self.rengo {
+            let _ = player.send(Message::Rengo(turn));
+        }
+    }
+
+    // Every few moves, sends everyone the position so far, so that a client whose copy of the
+    // game went out of step finds out and asks for it again
+    fn send_sync(&self) {
+        let moves = self.moves.len();
+        if moves == 0 || !moves.is_multiple_of(SYNC_INTERVAL) {
+            return;
+        }
+        let message = Message::Sync {
+            moves,
+            checksum: protocol::checksum(self.start.clone(), self.to_move, &self.moves),
+        };
+        // Delayed spectators are behind the position, and would only ask for it again
+        let spectators = self
+            .spectators
+            .iter()
+            .map(|(_, spectator)| spectator)
+            .filter(|_| self.spectator_delay.is_none());
+        for connection in self
+            .players
+            .iter()
+            .chain(&self.partners)
+            .flatten()
+            .chain(spectators)
+        {
+            let _ = connection.send(message.clone());
+        }
+    }
+
+    // Seats a player who lost their connection again and sends them the game as it is now
+    fn come_back(&mut self, color: Player, player: UnboundedSender<Message>) {
+        if let Some((_, since)) = self.away.take()
+            && self
+                .reconnect
+                .is_some_and(|terms| terms.clock == AwayClock::Stops)
+        {
+            self.move_started += since.elapsed();
+        }
+        self.resume(color, 0, &player);
+        self.players[Room::seat(color)] = Some(player);
+        self.latency[Room::seat(color)][0] = Latency::default();
+        self.relay(color, &Message::Back(color));
+        self.send_spectators();
+    }
+
+    // Measures the round trip to each player now and then
+    fn ping(&mut self) {
+        for (seat, latency) in self.latency.iter_mut().enumerate() {
+            for (turn, players) in [&self.players, &self.partners].into_iter().enumerate() {
+                if let Some(player) = &players[seat]
+                    && let Some(ping) = latency[turn].ping()
+                {
+                    let _ = player.send(ping);
+                }
+            }
+        }
+    }
+
+    fn ended_by_passes(&self) -> bool {
+        self.moves.len() >= 2
+            && self.moves[self.moves.len() - 2..]
+                .iter()
+                .all(|((_, point), _)| point.is_none())
+    }
+
+    // Checks a move against the turn and the rules, and plays it
+    fn play(&mut self, (player, point): Move, seconds: f32) -> bool {
+        if player != self.to_move {
+            return false;
+        }
+        match point {
+            Some((row, col)) => {
+                let size = self.board.size();
+                if row >= size || col >= size || !self.board.is_legal(row, col, player) {
+                    return false;
+                }
+                self.board.play(row, col, player);
+            }
+            None => self.board.pass(),
+        }
+        self.moves.push(((player, point), seconds));
+        self.played_at.push(Instant::now());
+        self.move_started = Instant::now();
+        self.last_move = unix_seconds();
+        // Playing on instead of answering declines an undo
+        self.undo_request = None;
+        self.to_move = player.other();
+        true
+    }
+
+    // Whether `player` may ask to take back `count` moves: its own last move, and the reply to
+    // it if there is one
+    fn can_undo(&self, player: Player, count: usize) -> bool {
+        (1..=2).contains(&count)
+            && count <= self.moves.len()
+            && self.moves[self.moves.len() - count].0.0 == player
+    }
+
+    // Plays the reply `color` planned to the move just made, if there is one, and sends it to
+    // both players and the spectators; any other move drops the plan
+    fn follow_plan(&mut self, color: Player) -> bool {
+        let plan = std::mem::take(&mut self.plans[Room::seat(color)]);
+        let Some(&((_, Some(point)), _)) = self.moves.last() else {
+            return false;
+        };
+        let Some((reply, then)) = plan.answer(point) else {
+            return false;
+        };
+        let played = (color, Some(reply));
+        if !self.play(played, 0.0) {
+            return false;
+        }
+        self.plans[Room::seat(color)] = then;
+        let message = Message::Play {
+            played,
+            seconds: 0.0,
+        };
+        self.send(color, &message);
+        self.relay_move(color, &message);
+        self.send_sync();
+        true
+    }
+
+    fn take_back(&mut self, count: usize) {
+        self.plans = Default::default();
+        self.moves.truncate(self.moves.len() - count);
+        self.played_at.truncate(self.moves.len());
+        self.board = self.start.clone();
+        self.to_move = self.start_to_move;
+        for &((player, point), _) in &self.moves {
+            match point {
+                Some((row, col)) => {
+                    self.board.play(row, col, player);
+                }
+                None => self.board.pass(),
+            }
+            self.to_move = player.other();
+        }
+        self.move_started = Instant::now();
+    }
+
+    // The record with every move played and `result` if the game has one
+    fn record(&self, result: Option<&str>) -> sgf::Node {
+        let mut root = self.root.clone();
+        if let Some(result) = result {
+            root.set("RE", vec![result.to_string()]);
+        }
+        let mut nodes: Vec<sgf::Node> = self
+            .moves
+            .iter()
+            .map(|&((player, point), _)| {
+                let mut node = sgf::Node::default();
+                let ident = match player {
+                    Player::Black => "B",
+                    Player::White => "W",
+                };
+                node.set(
+                    ident,
+                    vec![point.map(sgf::format_point).unwrap_or_default()],
+                );
+                node
+            })
+            .collect();
+        root.children = Vec::new();
+        while let Some(mut last) = nodes.pop() {
+            if let Some(previous) = nodes.last_mut() {
+                previous.children = vec![std::mem::take(&mut last)];
+            } else {
+                root.children = vec![last];
+            }
+        }
+        root
+    }
+}
+
+// A player waiting in the automatch queue
+struct Seeker {
+    id: usize,
+    name: String,
+    rating: f64,
+    seek: Seek,
+    since: Instant,
+    sender: UnboundedSender<Message>,
+}
+
+#[derive(Default)]
+struct Queue {
+    seekers: Vec<Seeker>,
+    next_seeker: usize,
+}
+
+// The first size and speed of `first` that `second` accepts too
+fn common_terms(first: &Seek, second: &Seek) -> Option<(usize, Speed)> {
+    let size = first
+        .sizes
+        .iter()
+        .find(|size| second.sizes.contains(size))?;
+    let speed = first
+        .speeds
+        .iter()
+        .find(|speed| second.speeds.contains(speed))?;
+    Some((*size, *speed))
+}
+
+// The record of an automatch game between `weaker` and `stronger`: weaker takes Black, with a
+// handicap stone for every rank between them beyond the first, which komi alone makes up for.
+// Also gives the ranks handed to Black.
+fn match_record(
+    size: usize,
+    speed: Speed,
+    weaker: &str,
+    stronger: &str,
+    ranks: u32,
+) -> (sgf::Node, u32) {
+    let mut root = sgf::Node::default();
+    root.set("GM", vec!["1".to_string()]);
+    root.set("FF", vec!["4".to_string()]);
+    root.set("SZ", vec![size.to_string()]);
+    let (main_time, overtime) = speed.clock();
+    root.set("TM", vec![main_time.to_string()]);
+    root.set("OT", vec![overtime.to_string()]);
+    root.set("PB", vec![weaker.to_string()]);
+    root.set("PW", vec![stronger.to_string()]);
+    let stones = board::handicap_points(size, ranks.min(MAX_HANDICAP));
+    let (komi, ranks) = match (ranks, stones.len()) {
+        (0, _) => (DEFAULT_KOMI, 0),
+        (_, 0) => ("0.5", 1),
+        (_, count) => {
+            root.set("HA", vec![count.to_string()]);
+            root.set("AB", stones.into_iter().map(sgf::format_point).collect());
+            root.set("PL", vec!["W".to_string()]);
+            ("0.5", count as u32)
+        }
+    };
+    root.set("KM", vec![komi.to_string()]);
+    (root, ranks)
+}
+
+struct Server {
+    rooms: Mutex<HashMap<String, Room>>,
+    games: PathBuf,
+    accounts: Mutex<Accounts>,
+    queue: Mutex<Queue>,
+    correspondence: PathBuf,
+}
+
+// The correspondence rooms saved at `path`, or none if there is no file yet; a room that cannot
+// be read or opened again is left out, so one bad room does not keep the server from starting
+fn load_correspondence(path: &FilePath) -> std::io::Result<HashMap<String, Room>> {
+    let stored: HashMap<String, serde_json::Value> = match std::fs::read_to_string(path) {
+        Ok(text) => serde_json::from_str(&text).map_err(std::io::Error::other)?,
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => HashMap::new(),
+        Err(err) => return Err(err),
+    };
+    let mut rooms = HashMap::new();
+    for (code, stored) in stored {
+        let restored = serde_json::from_value::<StoredRoom>(stored)
+            .map_err(|err| err.to_string())
+            .and_then(Room::restore);
+        match restored {
+            Ok(room) => {
+                rooms.insert(code, room);
+            }
+            Err(err) => eprintln!("could not open room {} again: {}", code, err),
+        }
+    }
+    Ok(rooms)
+}
+
+impl Server {
+    fn new_code(rooms: &HashMap<String, Room>) -> String {
+        let state = std::collections::hash_map::RandomState::new();
+        (0u64..)
+            .map(|attempt| {
+                let mut hash = state.hash_one(attempt);
+                (0..CODE_LENGTH)
+                    .map(|_| {
+                        let letter = CODE_LETTERS[(hash % 32) as usize] as char;
+                        hash /= 32;
+                        letter
+                    })
+                    .collect::<String>()
+            })
+            .find(|code| !rooms.contains_key(code))
+            .unwrap_or_default()
+    }
+
+    // Pairs the players in the queue who accept the same size and speed and are rated close
+    // enough, a gap that widens the longer the earlier of them has waited. Each pair is sent the
+    // code of a room opened for them.
+    fn pair_seekers(self: &Arc<Self>) {
+        let mut queue = self.queue.lock().unwrap();
+        let mut index = 0;
+        while index < queue.seekers.len() {
+            let first = &queue.seekers[index];
+            let window = MATCH_WINDOW + MATCH_WIDENING * first.since.elapsed().as_secs_f64();
+            let partner = queue.seekers[index + 1..]
+                .iter()
+                .position(|second| {
+                    second.name != first.name
+                        && (second.rating - first.rating).abs() <= window.min(MAX_MATCH_WINDOW)
+                        && common_terms(&first.seek, &second.seek).is_some()
+                })
+                .map(|offset| index + 1 + offset);
+            let Some(partner) = partner else {
+                index += 1;
+                continue;
+            };
+            let second = queue.seekers.remove(partner);
+            let first = queue.seekers.remove(index);
+            self.open_match(first, second);
+        }
+    }
+
+    fn open_match(self: &Arc<Self>, first: Seeker, second: Seeker) {
+        let Some((size, speed)) = common_terms(&first.seek, &second.seek) else {
+            return;
+        };
+        let (weaker, stronger) = if first.rating <= second.rating {
+            (&first, &second)
+        } else {
+            (&second, &first)
+        };
+        let ranks = ((stronger.rating - weaker.rating) / RATING_PER_RANK).round() as u32;
+        let (root, handicap) = match_record(size, speed, &weaker.name, &stronger.name, ranks);
+        let Ok(mut room) = Room::new(root, Player::Black, Some(MATCH_RECONNECT)) else {
+            return;
+        };
+        room.names = [Some(weaker.name.clone()), Some(stronger.name.clone())];
+        room.matched = true;
+        room.handicap = handicap;
+        let mut rooms = self.rooms.lock().unwrap();
+        let code = Server::new_code(&rooms);
+        rooms.insert(code.clone(), room);
+        drop(rooms);
+        println!(
+            "room {} opened for {} and {}",
+            code, weaker.name, stronger.name
+        );
+        for seeker in [&first, &second] {
+            let _ = seeker.sender.send(Message::Matched(code.clone()));
+        }
+        // A room whose players do not both come is closed again
+        let server = self.clone();
+        tokio::spawn(async move {
+            tokio::time::sleep(MATCH_ENTRY_TIME).await;
+            let mut rooms = server.rooms.lock().unwrap();
+            if rooms.get(&code).is_some_and(|room| !room.started) {
+                rooms.remove(&code);
+                println!("room {} closed", code);
+            }
+        });
+    }
+
+    // The account a request is logged in with, from its bearer token; tokens are forgotten
+    // when the server restarts, which has the client log in again
+    fn account(&self, headers: &HeaderMap) -> Result<Option<String>, (StatusCode, String)> {
+        let Some(value) = headers.get(header::AUTHORIZATION) else {
+            return Ok(None);
+        };
+        let token = value
+            .to_str()
+            .ok()
+            .and_then(|value| value.strip_prefix("Bearer "))
+            .unwrap_or_default();
+        match self.accounts.lock().unwrap().name(token.trim()) {
+            Some(name) => Ok(Some(name.to_string())),
+            None => Err((
+                StatusCode::UNAUTHORIZED,
+                "logged out, log in again".to_string(),
+            )),
+        }
+    }
+
+    // Writes out every correspondence room, for the server to open them again after a restart
+    fn save_correspondence(&self, rooms: &HashMap<String, Room>) {
+        let stored: HashMap<&String, StoredRoom> = rooms
+            .iter()
+            .filter(|(_, room)| room.correspondence)
+            .map(|(code, room)| (code, room.stored()))
+            .collect();
+        let result = serde_json::to_string_pretty(&stored)
+            .map_err(std::io::Error::other)
+            .and_then(|text| std::fs::write(&self.correspondence, text));
+        if let Err(err) = result {
+            eprintln!("could not save {}: {}", self.correspondence.display(), err);
+        }
+    }
+
+    // Saves the game and closes the room, which disconnects both players once they have been
+    // sent what is queued for them; the accounts that played it get it in their history
+    fn finish(&self, rooms: &mut HashMap<String, Room>, code: &str, result: Option<&str>) {
+        let Some(mut room) = rooms.remove(code) else {
+            return;
+        };
+        // The game is over, so spectators may see all of it
+        room.reveal(room.moves.len());
+        if room.correspondence {
+            self.save_correspondence(rooms);
+        }
+        if room.moves.is_empty() {
+            return;
+        }
+        let file = format!("{}-{}.sgf", code, unix_seconds());
+        let path = self.games.join(&file);
+        let text = sgf::to_string(&room.record(result));
+        if let Err(err) =
+            std::fs::create_dir_all(&self.games).and_then(|()| std::fs::write(&path, text))
+        {
+            eprintln!("could not save {}: {}", path.display(), err);
+            return;
+        }
+        let game = PlayedGame {
+            file,
+            black: room.team_name(Player::Black).unwrap_or_default(),
+            white: room.team_name(Player::White).unwrap_or_default(),
+            result: result.unwrap_or_default().to_string(),
+        };
+        // Pair go is not rated, as a result says little about each player of a side
+        if room.rengo {
+            let names: Vec<&str> = room
+                .names
+                .iter()
+                .chain(&room.partner_names)
+                .flatten()
+                .map(String::as_str)
+                .collect();
+            self.accounts
+                .lock()
+                .unwrap()
+                .add_game(game, room.audience(), &names);
+            return;
+        }
+        let [black, white] = &room.names;
+        // Only live rated games are checked, as a correspondence move's time is mostly away
+        if let (Some(black), Some(white)) = (black, white)
+            && black != white
+            && !room.correspondence
+        {
+            for (player, lines) in fair_play::flags(&room.start, &room.moves) {
+                let note = lines.join("; ");
+                println!("room {} fair play {}: {}", code, letter(player), note);
+                if player != room.creator {
+                    room.send(room.creator, &Message::FairPlay { player, note });
+                }
+            }
+        }
+        if black.is_some() || white.is_some() {
+            self.accounts.lock().unwrap().record_game(
+                game,
+                room.audience(),
+                black.as_deref(),
+                white.as_deref(),
+                room.handicap,
+            );
+        }
+    }
+}
+
+async fn register(
+    State(server): State<Arc<Server>>,
+    Json(credentials): Json<Credentials>,
+) -> Result<Json<Session>, (StatusCode, String)> {
+    let refused = |message| (StatusCode::BAD_REQUEST, message);
+    server
+        .accounts
+        .lock()
+        .unwrap()
+        .check_new(&credentials.name, &credentials.password)
+        .map_err(refused)?;
+    let password = credentials.password;
+    let hashed = tokio::task::spawn_blocking(move || accounts::Hashed::new(&password))
+        .await
+        .map_err(|err| (StatusCode::INTERNAL_SERVER_ERROR, err.to_string()))?;
+    let session = server
+        .accounts
+        .lock()
+        .unwrap()
+        .register(&credentials.name, hashed)
+        .map_err(refused)?;
+    println!("account {} registered", session.name);
+    Ok(Json(session))
+}
+
+async fn log_in(
+    State(server): State<Arc<Server>>,
+    Json(credentials): Json<Credentials>,
+) -> Result<Json<Session>, (StatusCode, String)> {
+    let wrong = || {
+        (
+            StatusCode::UNAUTHORIZED,
+            "wrong name or password".to_string(),
+        )
+    };
+    let stored = server.accounts.lock().unwrap().stored(&credentials.name);
+    let password = credentials.password;
+    // Hashing takes a good part of a second, on a thread of its own with the accounts unlocked
+    let checked = tokio::task::spawn_blocking(move || {
+        let Some(stored) = stored else {
+            accounts::waste_login(&password);
+            return None;
+        };
+        if !stored.matches(&password) {
+            return None;
+        }
+        let rehashed = stored.outdated().then(|| accounts::Hashed::new(&password));
+        Some((stored, rehashed))
+    })
+    .await
+    .map_err(|err| (StatusCode::INTERNAL_SERVER_ERROR, err.to_string()))?;
+    let (stored, rehashed) = checked.ok_or_else(wrong)?;
+    let session = server
+        .accounts
+        .lock()
+        .unwrap()
+        .log_in(&stored, rehashed)
+        .map_err(|message| (StatusCode::UNAUTHORIZED, message))?;
+    Ok(Json(session))
+}
+
+// The games of private rooms are only listed to those who could enter them
+async fn profile(
+    State(server): State<Arc<Server>>,
+    Path(name): Path<String>,
+    headers: HeaderMap,
+) -> Result<Json<Profile>, (StatusCode, String)> {
+    let viewer = server.account(&headers)?;
+    let profile = server
+        .accounts
+        .lock()
+        .unwrap()
+        .profile(&name, viewer.as_deref());
+    profile
+        .map(Json)
+        .ok_or((StatusCode::NOT_FOUND, "no such account".to_string()))
+}
+
+// A stored record, by the file name an account's history gives; a private game's looks the
+// same as a missing one to those who were not in it
+async fn game_record(
+    State(server): State<Arc<Server>>,
+    Path(file): Path<String>,
+    headers: HeaderMap,
+) -> Result<String, (StatusCode, String)> {
+    let not_found = (StatusCode::NOT_FOUND, "no such game".to_string());
+    if !file
+        .chars()
+        .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '.')
+        || file.starts_with('.')
+    {
+        return Err(not_found);
+    }
+    let viewer = server.account(&headers)?;
+    if !server
+        .accounts
+        .lock()
+        .unwrap()
+        .may_see(&file, viewer.as_deref())
+    {
+        return Err(not_found);
+    }
+    std::fs::read_to_string(server.games.join(file)).map_err(|_| not_found)
+}
+
+async fn list_rooms(
+    State(server): State<Arc<Server>>,
+    headers: HeaderMap,
+) -> Result<Json<Vec<RoomInfo>>, (StatusCode, String)> {
+    let name = server.account(&headers)?;
+    let rooms = server.rooms.lock().unwrap();
+    let accounts = server.accounts.lock().unwrap();
+    // Private games are only shown to those who may enter them
+    let mut open: Vec<RoomInfo> = rooms
+        .iter()
+        .filter(|(_, room)| room.admits(name.as_deref()))
+        .map(|(code, room)| room.info(code, &accounts))
+        .collect();
+    // Rooms waiting for a player come first
+    open.sort_by(|a, b| (a.playing, &a.code).cmp(&(b.playing, &b.code)));
+    Ok(Json(open))
+}
+
+async fn create_room(
+    State(server): State<Arc<Server>>,
+    headers: HeaderMap,
+    Json(new_room): Json<NewRoom>,
+) -> Result<Json<RoomCreated>, (StatusCode, String)> {
+    let bad_request = |message: String| (StatusCode::BAD_REQUEST, message);
+    let name = server.account(&headers)?;
+    if new_room.correspondence && name.is_none() {
+        return Err((
+            StatusCode::UNAUTHORIZED,
+            "log in to open a correspondence game".to_string(),
+        ));
+    }
+    let mut invited = Vec::new();
+    if !new_room.invited.is_empty() {
+        if name.is_none() {
+            return Err((
+                StatusCode::UNAUTHORIZED,
+                "log in to open a private game".to_string(),
+            ));
+        }
+        let accounts = server.accounts.lock().unwrap();
+        for invitee in &new_room.invited {
+            invited.push(
+                accounts
+                    .canonical(invitee)
+                    .ok_or_else(|| bad_request(format!("no account {}", invitee.trim())))?,
+            );
+        }
+    }
+    let mut names = [None, None];
+    names[Room::seat(new_room.color)] = name;
+    let mut trees = sgf::parse(&new_room.sgf).map_err(|err| bad_request(err.to_string()))?;
+    if trees.is_empty() {
+        return Err(bad_request("no game in the record".to_string()));
+    }
+    if new_room.rengo && new_room.correspondence {
+        return Err(bad_request(
+            "pair go cannot be played by correspondence".to_string(),
+        ));
+    }
+    // Players of a correspondence game leave whenever they like, and one of a pair go side
+    // leaving loses the game for it
+    let reconnect = new_room
+        .reconnect
+        .filter(|_| !new_room.correspondence && !new_room.rengo);
+    let mut room =
+        Room::new(trees.swap_remove(0), new_room.color, reconnect).map_err(bad_request)?;
+    room.names = names;
+    room.invited = invited;
+    room.correspondence = new_room.correspondence;
+    room.rengo = new_room.rengo;
+    room.simul = new_room.simul;
+    room.spectator_delay = new_room.spectator_delay.filter(|delay| delay.amount > 0);
+    let mut rooms = server.rooms.lock().unwrap();
+    let code = Server::new_code(&rooms);
+    rooms.insert(code.clone(), room);
+    if new_room.correspondence {
+        server.save_correspondence(&rooms);
+    }
+    println!("room {} opened", code);
+    Ok(Json(RoomCreated { code }))
+}
+
+// The correspondence games of the account logged in, those waiting for it first
+async fn correspondence_games(
+    State(server): State<Arc<Server>>,
+    headers: HeaderMap,
+) -> Result<Json<Vec<OngoingGame>>, (StatusCode, String)> {
+    let Some(name) = server.account(&headers)? else {
+        return Err((
+            StatusCode::UNAUTHORIZED,
+            "log in to see your correspondence games".to_string(),
+        ));
+    };
+    let rooms = server.rooms.lock().unwrap();
+    let mut games: Vec<OngoingGame> = rooms
+        .iter()
+        .filter(|(_, room)| room.correspondence)
+        .filter_map(|(code, room)| room.ongoing(code, &name))
+        .collect();
+    games.sort_by_key(|game| (!game.your_turn, game.last_move));
+    Ok(Json(games))
+}
+
+async fn automatch(
+    State(server): State<Arc<Server>>,
+    headers: HeaderMap,
+    upgrade: WebSocketUpgrade,
+) -> Response {
+    match server.account(&headers) {
+        Ok(Some(name)) => upgrade.on_upgrade(move |socket| seek(server, name, socket)),
+        Ok(None) => (StatusCode::UNAUTHORIZED, "log in to find an opponent").into_response(),
+        Err(refused) => refused.into_response(),
+    }
+}
+
+// Keeps the player in the queue from their `Seek` until they are matched, when they are sent
+// the room's code, or until they leave
+async fn seek(server: Arc<Server>, name: String, mut socket: WebSocket) {
+    let seek = loop {
+        match socket.recv().await {
+            Some(Ok(Frame::Binary(bytes))) => {
+                if let Some(Message::Seek(seek)) = Message::decode(&bytes)
+                    && !seek.sizes.is_empty()
+                    && !seek.speeds.is_empty()
+                {
+                    break seek;
+                }
+                return;
+            }
+            Some(Ok(Frame::Ping(_) | Frame::Pong(_))) => {}
+            _ => return,
+        }
+    };
+    let (sender, mut outbox) = mpsc::unbounded_channel::<Message>();
+    let id = {
+        let rating = server.accounts.lock().unwrap().rating(&name);
+        let mut queue = server.queue.lock().unwrap();
+        let id = queue.next_seeker;
+        queue.next_seeker += 1;
+        queue.seekers.push(Seeker {
+            id,
+            name: name.clone(),
+            rating,
+            seek,
+            since: Instant::now(),
+            sender,
+        });
+        id
+    };
+    println!("{} is looking for a game", name);
+    server.pair_seekers();
+    loop {
+        tokio::select! {
+            message = outbox.recv() => match message {
+                Some(message) => {
+                    if socket.send(Frame::Binary(message.encode().into())).await.is_err() {
+                        break;
+                    }
+                }
+                // Matched, with the code sent
+                None => break,
+            },
+            frame = socket.recv() => match frame {
+                Some(Ok(Frame::Close(_))) | Some(Err(_)) | None => break,
+                Some(Ok(_)) => {}
+            },
+        }
+    }
+    server
+        .queue
+        .lock()
+        .unwrap()
+        .seekers
+        .retain(|seeker| seeker.id != id);
+}
+
+async fn enter_room(
+    State(server): State<Arc<Server>>,
+    Path(code): Path<String>,
+    headers: HeaderMap,
+    upgrade: WebSocketUpgrade,
+) -> Response {
+    let code = code.to_uppercase();
+    let name = match server.account(&headers) {
+        Ok(name) => name,
+        Err(refused) => return refused.into_response(),
+    };
+    match server.rooms.lock().unwrap().get(&code) {
+        None => return (StatusCode::NOT_FOUND, "no such room").into_response(),
+        Some(room) if !room.admits(name.as_deref()) => {
+            return (StatusCode::FORBIDDEN, "this game is private").into_response();
+        }
+        Some(room) if room.correspondence && !room.started && name.is_none() => {
+            return (
+                StatusCode::UNAUTHORIZED,
+                "log in to play a correspondence game",
+            )
+                .into_response();
+        }
+        Some(_) => {}
+    }
+    upgrade.on_upgrade(move |socket| play(server, code, name, socket))
+}
+
+// Seats the player, hands out the game once both are in, and relays until either side leaves.
+// Once the game has started, whoever enters watches it.
+async fn play(server: Arc<Server>, code: String, name: Option<String>, mut socket: WebSocket) {
+    let (sender, mut outbox) = mpsc::unbounded_channel::<Message>();
+    let seat = {
+        let mut rooms = server.rooms.lock().unwrap();
+        let Some(room) = rooms.get_mut(&code) else {
+            return;
+        };
+        // A seat kept for an account only takes that account, also when coming back, and only
+        // accounts play correspondence games
+        let free = [room.creator, room.creator.other()]
+            .into_iter()
+            .find(|&color| {
+                let seat = Room::seat(color);
+                room.players[seat].is_none()
+                    && (room.names[seat].is_none() || room.names[seat] == name)
+                    && (!room.correspondence || name.is_some())
+            })
+            .map(|color| (color, 0));
+        // The second players of a pair go side sit down once both first players are in
+        let free = free.or_else(|| {
+            [room.creator, room.creator.other()]
+                .into_iter()
+                .find(|&color| {
+                    room.rengo && !room.started && room.partners[Room::seat(color)].is_none()
+                })
+                .map(|color| (color, 1))
+        });
+        let seat = match free {
+            Some((color, turn)) if !room.started => {
+                let seat = Room::seat(color);
+                if turn == 0 {
+                    room.players[seat] = Some(sender);
+                    if room.names[seat].is_none() {
+                        room.names[seat] = name;
+                    }
+                } else {
+                    room.partners[seat] = Some(sender);
+                    room.partner_names[seat] = name;
+                }
+                // A correspondence game starts once it has both players, who need not be in
+                let ready = if room.correspondence {
+                    room.names.iter().all(Option::is_some)
+                } else {
+                    room.players.iter().all(Option::is_some)
+                        && (!room.rengo || room.partners.iter().all(Option::is_some))
+                };
+                if ready {
+                    room.started = true;
+                    room.move_started = Instant::now();
+                    room.last_move = unix_seconds();
+                    // The record says who played, for both clients and the stored game
+                    for (ident, color) in [("PB", Player::Black), ("PW", Player::White)] {
+                        if let Some(name) = room.team_name(color) {
+                            room.root.set(ident, vec![name]);
+                        }
+                    }
+                    let sgf = sgf::to_string(&room.root);
+                    for color in [Player::Black, Player::White] {
+                        let game = Message::Game {
+                            color,
+                            sgf: sgf.clone(),
+                        };
+                        room.send(color, &game);
+                        if let Some(terms) = room.reconnect {
+                            room.send(color, &Message::Reconnect(terms));
+                        }
+                        if room.correspondence {
+                            room.send(color, &Message::Correspondence);
+                        }
+                        for turn in 0..2 {
+                            if room.rengo
+                                && let Some(player) = room.connection(color, turn)
+                            {
+                                let _ = player.send(Message::Rengo(turn));
+                            }
+                        }
+                    }
+                    println!("room {} started", code);
+                }
+                Seat::Player(color, turn)
+            }
+            Some((color, _))
+                if room.correspondence || room.away.is_some_and(|(away, _)| away == color) =>
+            {
+                room.come_back(color, sender);
+                println!("room {} back {}", code, letter(color));
+                Seat::Player(color, 0)
+            }
+            _ => Seat::Spectator(room.watch(sender)),
+        };
+        if room.correspondence {
+            room.mark_seen();
+            server.save_correspondence(&rooms);
+        }
+        seat
+    };
+    'connected: loop {
+        tokio::select! {
+            message = outbox.recv() => match message {
+                Some(message) => {
+                    if socket.send(Frame::Binary(message.encode().into())).await.is_err() {
+                        break;
+                    }
+                }
+                None => break,
+            },
+            frame = socket.recv() => match frame {
+                // Messages this version does not know are skipped
+                Some(Ok(Frame::Binary(bytes))) => {
+                    let Some(message) = Message::decode(&bytes) else {
+                        continue;
+                    };
+                    let open = match seat {
+                        Seat::Player(color, turn) => {
+                            receive(&server, &code, color, turn, message)
+                        }
+                        Seat::Spectator(id) => receive_spectator(&server, &code, id, message),
+                    };
+                    if !open {
+                        break 'connected;
+                    }
+                }
+                Some(Ok(Frame::Close(_))) | Some(Err(_)) | None => break,
+                Some(Ok(_)) => {}
+            },
+        }
+    }
+    match seat {
+        Seat::Player(color, turn) => leave(&server, &code, color, turn),
+        Seat::Spectator(id) => stop_watching(&server, &code, id),
+    }
+}
+
+// Handles a message from the player of `color`, the first or second of the side by `turn`;
+// `false` drops a client that broke the rules
+fn receive(server: &Server, code: &str, color: Player, turn: usize, message: Message) -> bool {
+    let mut rooms = server.rooms.lock().unwrap();
+    let Some(room) = rooms.get_mut(code) else {
+        return false;
+    };
+    if !room.started {
+        return true;
+    }
+    let correspondence = room.correspondence;
+    let finished = match message.clone() {
+        Message::Play { played, seconds } => {
+            if played.0 != color || (room.rengo && room.turn(color) != turn) {
+                return false;
+            }
+            // The server keeps the clocks, and holds a player to the time it saw them take but
+            // for their round trip; correspondence players come and go between moves
+            let claimed = seconds;
+            let seconds = if correspondence {
+                claimed
+            } else {
+                let elapsed = room.move_started.elapsed().as_secs_f32();
+                room.latency[Room::seat(color)][turn].charge(claimed, elapsed)
+            };
+            if !room.play(played, seconds) {
+                return false;
+            }
+            let message = Message::Play { played, seconds };
+            if protocol::tenths(seconds) != protocol::tenths(claimed)
+                && let Some(player) = room.connection(color, turn)
+            {
+                let _ = player.send(Message::Charged(seconds));
+            }
+            room.relay_move(color, &message);
+            room.send_partner(color, turn, &message);
+            room.send_sync();
+            // Planned replies may answer one another
+            room.plans[Room::seat(color)] = Plan::default();
+            let mut next = color.other();
+            while room.follow_plan(next) {
+                next = next.other();
+            }
+            None
+        }
+        Message::Plan(plan) => {
+            if room.correspondence
+                && room.to_move == color.other()
+                && room.connection(color, turn).is_some()
+                && plan.fits()
+            {
+                room.plans[Room::seat(color)] = plan;
+            }
+            None
+        }
+        Message::Resync => {
+            if let Some(player) = room.connection(color, turn) {
+                room.resume(color, turn, player);
+            }
+            None
+        }
+        Message::Pong(id) => {
+            room.latency[Room::seat(color)][turn].pong(id);
+            None
+        }
+        Message::Resign(player) if player == color => {
+            room.reveal(room.moves.len());
+            room.relay(color, &message);
+            room.send_partner(color, turn, &message);
+            Some(format!("{}+R", letter(color.other())))
+        }
+        Message::Timeout(player) if player == color => {
+            room.reveal(room.moves.len());
+            room.relay(color, &message);
+            room.send_partner(color, turn, &message);
+            Some(format!("{}+T", letter(color.other())))
+        }
+        // Taking moves back would need all four players to agree
+        Message::UndoRequest(_) | Message::UndoAccept(_) | Message::UndoDecline if room.rengo => {
+            None
+        }
+        Message::TeamChat(_) => {
+            room.send_partner(color, turn, &message);
+            None
+        }
+        Message::UndoRequest(count) => {
+            if room.undo_request.is_some() || !room.can_undo(color, count) {
+                return false;
+            }
+            room.undo_request = Some((color, count));
+            room.send(color.other(), &message);
+            None
+        }
+        Message::UndoAccept(count) => {
+            if room.undo_request != Some((color.other(), count)) {
+                return false;
+            }
+            room.undo_request = None;
+            room.take_back(count);
+            if room.spectator_delay.is_some() {
+                room.send(color.other(), &message);
+                room.hide_taken_back();
+            } else {
+                room.relay(color, &message);
+            }
+            None
+        }
+        Message::UndoDecline => {
+            if room.undo_request.take().is_some() {
+                room.send(color.other(), &message);
+            }
+            None
+        }
+        Message::Chat { from, .. } if from == Some(color) => {
+            room.relay(color, &message);
+            room.send_partner(color, turn, &message);
+            None
+        }
+        _ => None,
+    };
+    if let Some(result) = finished {
+        println!("room {} ended {}", code, result);
+        server.finish(&mut rooms, code, Some(&result));
+    } else if correspondence {
+        room.mark_seen();
+        server.save_correspondence(&rooms);
+    }
+    true
+}
+
+// A player leaving a game that is still going forfeits it, unless they come back in the time
+// the room allows for that
+fn leave(server: &Arc<Server>, code: &str, color: Player, turn: usize) {
+    let mut rooms = server.rooms.lock().unwrap();
+    let Some(room) = rooms.get_mut(code) else {
+        return;
+    };
+    if turn == 0 {
+        room.players[Room::seat(color)] = None;
+    } else {
+        room.partners[Room::seat(color)] = None;
+    }
+    // A correspondence game waits for its players, until both have seen it end by passes
+    let seen_end = room.started
+        && room.ended_by_passes()
+        && room.seen.iter().all(|&seen| seen == room.moves.len());
+    if room.correspondence && !seen_end {
+        println!("room {} left for now by {}", code, letter(color));
+        return;
+    }
+    // A room still waiting for its players closes when its creator leaves, or the last of them;
+    // anyone else only frees their seat
+    if !room.started {
+        let empty = room
+            .players
+            .iter()
+            .chain(&room.partners)
+            .all(Option::is_none);
+        if (color == room.creator && turn == 0) || empty {
+            rooms.remove(code);
+            println!("room {} closed", code);
+        } else {
+            let seat = Room::seat(color);
+            if turn != 0 {
+                room.partner_names[seat] = None;
+            } else if !room.matched {
+                room.names[seat] = None;
+            }
+            println!("room {} seat of {} free again", code, letter(color));
+        }
+        return;
+    }
+    if !room.ended_by_passes()
+        && room.away.is_none()
+        && let Some(terms) = room.reconnect
+    {
+        let since = Instant::now();
+        room.away = Some((color, since));
+        room.relay(color, &Message::Away(color));
+        println!("room {} waiting for {}", code, letter(color));
+        let server = server.clone();
+        let code = code.to_string();
+        tokio::spawn(async move {
+            tokio::time::sleep(Duration::from_secs(terms.seconds.into())).await;
+            let mut rooms = server.rooms.lock().unwrap();
+            // Still away since the same drop
+            if let Some(room) = rooms.get_mut(&code)
+                && room.away == Some((color, since))
+            {
+                room.reveal(room.moves.len());
+                room.relay(color, &Message::Forfeit(color));
+                println!("room {} forfeited by {}", code, letter(color));
+                let result = format!("{}+F", letter(color.other()));
+                server.finish(&mut rooms, &code, Some(&result));
+            }
+        });
+        return;
+    }
+    let result = (!room.ended_by_passes()).then(|| format!("{}+F", letter(color.other())));
+    println!("room {} left by {}", code, letter(color));
+    server.finish(&mut rooms, code, result.as_deref());
+}
+
+// Spectators only talk among themselves, so as not to help the players
+fn receive_spectator(server: &Server, code: &str, id: usize, message: Message) -> bool {
+    let rooms = server.rooms.lock().unwrap();
+    let Some(room) = rooms.get(code) else {
+        return false;
+    };
+    match message {
+        Message::Chat { from: None, .. } => {
+            for (_, spectator) in room.spectators.iter().filter(|(other, _)| *other != id) {
+                let _ = spectator.send(message.clone());
+            }
+        }
+        Message::Resync => {
+            if let Some((_, spectator)) = room.spectators.iter().find(|(other, _)| *other == id) {
+                room.catch_up_spectator(spectator);
+            }
+        }
+        _ => {}
+    }
+    true
+}
+
+fn stop_watching(server: &Server, code: &str, id: usize) {
+    let mut rooms = server.rooms.lock().unwrap();
+    if let Some(room) = rooms.get_mut(code) {
+        room.spectators.retain(|&(other, _)| other != id);
+        room.send_spectators();
+    }
+}
+
+fn letter(player: Player) -> &'static str {
+    match player {
+        Player::Black => "B",
+        Player::White => "W",
+    }
+}
+
+#[tokio::main]
+async fn main() -> std::io::Result<()> {
+    let args = Args::parse();
+    let rooms = load_correspondence(&args.correspondence)?;
+    if !rooms.is_empty() {
+        println!("{} correspondence games open again", rooms.len());
+    }
+    let server = Arc::new(Server {
+        rooms: Mutex::new(rooms),
+        games: args.games,
+        accounts: Mutex::new(Accounts::load(args.accounts)?),
+        queue: Mutex::new(Queue::default()),
+        correspondence: args.correspondence,
+    });
+    // Waiting players are paired again as their rating gap widens
+    let pairing = server.clone();
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(MATCH_INTERVAL);
+        loop {
+            interval.tick().await;
+            pairing.pair_seekers();
+        }
+    });
+    // Spectators of delayed rooms see each move once its delay is up, and players are pinged
+    let delayed = server.clone();
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(ROOM_TICK);
+        loop {
+            interval.tick().await;
+            for room in delayed.rooms.lock().unwrap().values_mut() {
+                let count = room.visible();
+                room.reveal(count);
+                if room.started && !room.correspondence {
+                    room.ping();
+                }
+            }
+        }
+    });
+    let app = Router::new()
+        .route("/register", post(register))
+        .route("/login", post(log_in))
+        .route("/users/{name}", get(profile))
+        .route("/games/{file}", get(game_record))
+        .route("/rooms", get(list_rooms).post(create_room))
+        .route("/rooms/{code}", any(enter_room))
+        .route("/correspondence", get(correspondence_games))
+        .route("/automatch", any(automatch))
+        .with_state(server);
+    if let (Some(cert), Some(key)) = (args.cert, args.key) {
+        let config = RustlsConfig::from_pem_file(cert, key).await?;
+        println!("listening on port {} with TLS", args.port);
+        return axum_server::bind_rustls(
+            std::net::SocketAddr::from(([0, 0, 0, 0], args.port)),
+            config,
+        )
+        .serve(app.into_make_service())
+        .await;
+    }
+    let listener = tokio::net::TcpListener::bind(("0.0.0.0", args.port)).await?;
+    println!("listening on port {}", args.port);
+    axum::serve(listener, app).await
+}
@@ -0,0 +1,388 @@
+use std::collections::HashSet;
+use std::fmt;
+use std::ops::{Index, IndexMut};
+
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Stone {
+    Black,
+    White,
+    Empty,
+}
+
+#[derive(Clone, Copy, PartialEq, Debug, Serialize, Deserialize)]
+pub enum Player {
+    Black,
+    White,
+}
+
+impl Player {
+    pub fn other(self) -> Player {
+        match self {
+            Player::Black => Player::White,
+            Player::White => Player::Black,
+        }
+    }
+    pub fn to_stone(self) -> Stone {
+        match self {
+            Player::Black => Stone::Black,
+            Player::White => Stone::White,
+        }
+    }
+}
+
+// A played move; `None` is a pass
+pub type Move = (Player, Option<(usize, usize)>);
+
+/// Returns the traditional fixed handicap points for `count` stones, or an empty list
+/// when the board is too small for them.
+pub fn handicap_points(size: usize, count: u32) -> Vec<(usize, usize)> {
+    if size < 7 || count < 2 {
+        return Vec::new();
+    }
+    let low = if size < 13 { 2 } else { 3 };
+    let high = size - 1 - low;
+    let mid = size / 2;
+    let mut points = vec![(low, high), (high, low), (high, high), (low, low)];
+    points.truncate(count.min(4) as usize);
+    if count >= 6 {
+        points.extend([(mid, low), (mid, high)]);
+    }
+    if count >= 8 {
+        points.extend([(low, mid), (high, mid)]);
+    }
+    // Odd counts from five upwards include the center point
+    if count >= 5 && count % 2 == 1 && size % 2 == 1 {
+        points.push((mid, mid));
+    }
+    points
+}
+
+// Why a move may not be played
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum MoveError {
+    Occupied,
+    Suicide,
+    Ko,
+}
+
+impl fmt::Display for MoveError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            MoveError::Occupied => write!(f, "occupied"),
+            MoveError::Suicide => write!(f, "suicide"),
+            MoveError::Ko => write!(f, "ko — recapture next turn"),
+        }
+    }
+}
+
+// The rules engine: stones on an intersection grid, without any game or UI state
+#[derive(Clone, PartialEq, Debug)]
+pub struct Board {
+    size: usize,
+    grid: Vec<Vec<Stone>>,
+    // The point that may not be played next because it would retake a ko immediately
+    ko: Option<(usize, usize)>,
+    // Toroidal Go: each edge joins the opposite one, so there are no edges or corners
+    wraps: bool,
+}
+
+impl Index<usize> for Board {
+    type Output = Vec<Stone>;
+
+    fn index(&self, row: usize) -> &Vec<Stone> {
+        &self.grid[row]
+    }
+}
+
+impl IndexMut<usize> for Board {
+    fn index_mut(&mut self, row: usize) -> &mut Vec<Stone> {
+        &mut self.grid[row]
+    }
+}
+
+impl Board {
+    pub fn new(size: usize) -> Self {
+        Self {
+            size,
+            grid: vec![vec![Stone::Empty; size]; size],
+            ko: None,
+            wraps: false,
+        }
+    }
+
+    pub fn toroidal(size: usize) -> Self {
+        Self {
+            wraps: true,
+            ..Self::new(size)
+        }
+    }
+
+    pub fn size(&self) -> usize {
+        self.size
+    }
+
+    pub fn wraps(&self) -> bool {
+        self.wraps
+    }
+
+    /// A digest of the stones, for two copies of a game to check they see the same position.
+    pub fn checksum(&self) -> u64 {
+        // FNV-1a over the points row by row
+        let mut hash: u64 = 0xcbf2_9ce4_8422_2325;
+        for stone in self.grid.iter().flatten() {
+            hash ^= match stone {
+                Stone::Empty => 0,
+                Stone::Black => 1,
+                Stone::White => 2,
+            };
+            hash = hash.wrapping_mul(0x0100_0000_01b3);
+        }
+        hash
+    }
+
+    pub fn get_neighbors(&self, row: usize, col: usize) -> Vec<(usize, usize)> {
+        let mut neighbors = Vec::new();
+        let directions = [(-1, 0), (1, 0), (0, -1), (0, 1)];
+        for (dr, dc) in directions.iter() {
+            let new_row = row as i32 + dr;
+            let new_col = col as i32 + dc;
+            if self.wraps {
+                let size = self.size as i32;
+                let point = (
+                    new_row.rem_euclid(size) as usize,
+                    new_col.rem_euclid(size) as usize,
+                );
+                // On the smallest boards two directions can lead to the same point
+                if point != (row, col) && !neighbors.contains(&point) {
+                    neighbors.push(point);
+                }
+            } else if new_row >= 0
+                && new_row < self.size as i32
+                && new_col >= 0
+                && new_col < self.size as i32
+            {
+                neighbors.push((new_row as usize, new_col as usize));
+            }
+        }
+        neighbors
+    }
+
+    pub fn get_group(&self, row: usize, col: usize, stone: Stone) -> HashSet<(usize, usize)> {
+        let mut group = HashSet::new();
+        let mut stack = vec![(row, col)];
+        while let Some((r, c)) = stack.pop() {
+            if group.contains(&(r, c)) || self.grid[r][c] != stone {
+                continue;
+            }
+            group.insert((r, c));
+            for (nr, nc) in self.get_neighbors(r, c) {
+                if !group.contains(&(nr, nc)) && self.grid[nr][nc] == stone {
+                    stack.push((nr, nc));
+                }
+            }
+        }
+        group
+    }
+
+    pub fn has_liberties(&self, row: usize, col: usize) -> bool {
+        let stone = self.grid[row][col];
+        if stone == Stone::Empty {
+            return true;
+        }
+        let group = self.get_group(row, col, stone);
+        for &(r, c) in &group {
+            for (nr, nc) in self.get_neighbors(r, c) {
+                if self.grid[nr][nc] == Stone::Empty {
+                    return true;
+                }
+            }
+        }
+        false
+    }
+
+    // The empty points next to the group at `(row, col)`
+    pub fn liberties(&self, row: usize, col: usize) -> HashSet<(usize, usize)> {
+        let group = self.get_group(row, col, self.grid[row][col]);
+        group
+            .iter()
+            .flat_map(|&(r, c)| self.get_neighbors(r, c))
+            .filter(|&(r, c)| self.grid[r][c] == Stone::Empty)
+            .collect()
+    }
+
+    pub fn capture_stones(&mut self, opponent: Stone) -> u32 {
+        let mut captured = 0;
+        let mut to_remove = Vec::new();
+        for row in 0..self.size {
+            for col in 0..self.size {
+                if self.grid[row][col] == opponent && !self.has_liberties(row, col) {
+                    let group = self.get_group(row, col, opponent);
+                    for &(r, c) in &group {
+                        to_remove.push((r, c));
+                    }
+                    captured += group.len() as u32;
+                }
+            }
+        }
+        for (r, c) in to_remove {
+            self.grid[r][c] = Stone::Empty;
+        }
+        captured
+    }
+
+    pub fn would_capture_opponent(&self, row: usize, col: usize, player: Player) -> bool {
+        let opponent_stone = player.other().to_stone();
+        for (nr, nc) in self.get_neighbors(row, col) {
+            if self.grid[nr][nc] == opponent_stone {
+                // Check if this opponent group would have no liberties after our move
+                if self.would_group_be_captured(nr, nc, opponent_stone, row, col) {
+                    return true;
+                }
+            }
+        }
+        false
+    }
+
+    fn would_group_be_captured(
+        &self,
+        group_row: usize,
+        group_col: usize,
+        group_stone: Stone,
+        new_stone_row: usize,
+        new_stone_col: usize,
+    ) -> bool {
+        let group = self.get_group(group_row, group_col, group_stone);
+        for &(r, c) in &group {
+            for (nr, nc) in self.get_neighbors(r, c) {
+                // If there's an empty liberty that's not where we're placing our stone
+                if self.grid[nr][nc] == Stone::Empty
+                    && !(nr == new_stone_row && nc == new_stone_col)
+                {
+                    return false;
+                }
+            }
+        }
+        true
+    }
+
+    pub fn would_be_suicide(&self, row: usize, col: usize, player: Player) -> bool {
+        let player_stone = player.to_stone();
+        // Check if placing the stone would create a group with no liberties
+        // First, check direct liberties (empty adjacent spots)
+        for (nr, nc) in self.get_neighbors(row, col) {
+            if self.grid[nr][nc] == Stone::Empty {
+                return false; // Has at least one liberty
+            }
+        }
+        // Check if we can connect to a friendly group that has liberties
+        for (nr, nc) in self.get_neighbors(row, col) {
+            if self.grid[nr][nc] == player_stone {
+                // Check if this friendly group would still have liberties after our move
+                if self.would_friendly_group_have_liberties(nr, nc, player_stone, row, col) {
+                    return false;
+                }
+            }
+        }
+        true
+    }
+
+    fn would_friendly_group_have_liberties(
+        &self,
+        group_row: usize,
+        group_col: usize,
+        group_stone: Stone,
+        new_row: usize,
+        new_col: usize,
+    ) -> bool {
+        let group = self.get_group(group_row, group_col, group_stone);
+
+        // Check for empty spots (but not where we're placing the new stone)
+        for &(r, c) in &group {
+            for (nr, nc) in self.get_neighbors(r, c) {
+                if self.grid[nr][nc] == Stone::Empty && !(nr == new_row && nc == new_col) {
+                    return true;
+                }
+            }
+        }
+
+        // Check the new stone's position for additional liberties
+        for (nr, nc) in self.get_neighbors(new_row, new_col) {
+            if self.grid[nr][nc] == Stone::Empty {
+                return true;
+            }
+        }
+        false
+    }
+
+    pub fn ko_point(&self) -> Option<(usize, usize)> {
+        self.ko
+    }
+
+    pub fn check_move(&self, row: usize, col: usize, player: Player) -> Result<(), MoveError> {
+        if self.grid[row][col] != Stone::Empty {
+            return Err(MoveError::Occupied);
+        }
+        if self.ko == Some((row, col)) {
+            return Err(MoveError::Ko);
+        }
+
+        // Check if the move would capture opponent stones
+        let would_capture = self.would_capture_opponent(row, col, player);
+
+        // If we wouldn't capture anything, check if it would be suicide
+        if !would_capture && self.would_be_suicide(row, col, player) {
+            return Err(MoveError::Suicide);
+        }
+        Ok(())
+    }
+
+    pub fn is_legal(&self, row: usize, col: usize, player: Player) -> bool {
+        self.check_move(row, col, player).is_ok()
+    }
+
+    // Places a stone without checking legality and returns the number of captured stones
+    pub fn play(&mut self, row: usize, col: usize, player: Player) -> u32 {
+        self.grid[row][col] = player.to_stone();
+        let captured = self.capture_stones(player.other().to_stone());
+
+        // A lone stone that captured one stone and is left with a single liberty can be retaken
+        self.ko = None;
+        let neighbors = self.get_neighbors(row, col);
+        let empty: Vec<_> = neighbors
+            .iter()
+            .copied()
+            .filter(|&(r, c)| self.grid[r][c] == Stone::Empty)
+            .collect();
+        let alone = neighbors
+            .iter()
+            .all(|&(r, c)| self.grid[r][c] != player.to_stone());
+        if captured == 1 && alone && empty.len() == 1 {
+            self.ko = Some(empty[0]);
+        }
+        captured
+    }
+
+    // The opponent stones that playing at `(row, col)` would remove
+    pub fn captured_by(&self, row: usize, col: usize, player: Player) -> Vec<(usize, usize)> {
+        let mut board = self.clone();
+        board.play(row, col, player);
+        let opponent = player.other().to_stone();
+        (0..self.size)
+            .flat_map(|r| (0..self.size).map(move |c| (r, c)))
+            .filter(|&(r, c)| self.grid[r][c] == opponent && board.grid[r][c] == Stone::Empty)
+            .collect()
+    }
+
+    // Whether a move that captures nothing would leave its own group with a single liberty
+    pub fn would_be_self_atari(&self, row: usize, col: usize, player: Player) -> bool {
+        let mut board = self.clone();
+        board.play(row, col, player) == 0 && board.liberties(row, col).len() == 1
+    }
+
+    // A pass lifts any ko prohibition
+    pub fn pass(&mut self) {
+        self.ko = None;
+    }
+}
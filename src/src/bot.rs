@@ -0,0 +1,64 @@
+//! A weak player built on the score estimate, for the commands that need an opponent without an
+//! engine: it tries every move and keeps one of those the estimate likes best.
+
+use crate::board::{Board, Player, Stone};
+use crate::consts;
+use crate::estimate;
+
+/// The move `player` makes on `board`, with `None` for a pass once no move looks better than
+/// passing. Among moves within `consts::BOT_MARGIN` of the best, `seed` picks one, so games
+/// between two bots differ. It never fills its own eyes.
+pub fn choose(board: &Board, player: Player, komi: f32, seed: u64) -> Option<(usize, usize)> {
+    let (passing, candidates) = candidates(board, player, komi);
+    let best = candidates
+        .first()
+        .map_or(f32::NEG_INFINITY, |&(_, lead)| lead);
+    if best <= passing {
+        return None;
+    }
+    let close: Vec<(usize, usize)> = candidates
+        .into_iter()
+        .filter(|&(_, lead)| lead >= best - consts::BOT_MARGIN)
+        .map(|(point, _)| point)
+        .collect();
+    let seed = seed.wrapping_mul(0x9e37_79b9_7f4a_7c15) >> 32;
+    close.get(seed as usize % close.len()).copied()
+}
+
+/// A move and the estimated lead of the player after it.
+pub type Candidate = ((usize, usize), f32);
+
+/// The estimated lead of `player` after passing, and after each move it would consider, best
+/// first.
+pub fn candidates(board: &Board, player: Player, komi: f32) -> (f32, Vec<Candidate>) {
+    let lead_for = |board: &Board| {
+        let lead = estimate::estimate(board).lead(komi);
+        match player {
+            Player::Black => lead,
+            Player::White => -lead,
+        }
+    };
+    let passing = lead_for(board);
+    let mut candidates = Vec::new();
+    for row in 0..board.size() {
+        for col in 0..board.size() {
+            if board.is_legal(row, col, player) && !is_own_eye(board, (row, col), player) {
+                let mut after = board.clone();
+                after.play(row, col, player);
+                candidates.push(((row, col), lead_for(&after)));
+            }
+        }
+    }
+    // Stable, so equal moves stay in reading order
+    candidates.sort_by(|a, b| b.1.total_cmp(&a.1));
+    (passing, candidates)
+}
+
+/// Whether every neighbor of the empty `point` is a stone of `player`.
+pub fn is_own_eye(board: &Board, (row, col): (usize, usize), player: Player) -> bool {
+    board[row][col] == Stone::Empty
+        && board
+            .get_neighbors(row, col)
+            .into_iter()
+            .all(|(row, col)| board[row][col] == player.to_stone())
+}
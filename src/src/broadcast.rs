@@ -0,0 +1,199 @@
+//! A read-only web page of one game for the audience: `GET /` gives the page, which follows the
+//! board, clocks and moves over a websocket at `/ws`. Unlike the API it listens on every network
+//! the computer is on, so phones on the venue Wi-Fi can reach it.
+
+use std::io::{BufRead, BufReader, Write};
+use std::net::{Ipv4Addr, TcpListener, TcpStream, UdpSocket};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, RecvTimeoutError, Sender};
+use std::sync::{Arc, Mutex};
+
+use tungstenite::protocol::Role;
+use tungstenite::{Message, WebSocket};
+
+use crate::api;
+use crate::consts;
+
+const PAGE: &str = include_str!("broadcast.html");
+
+// What the client threads share with the game
+#[derive(Default)]
+struct Shared {
+    // The state last published, sent first to a new viewer
+    latest: String,
+    viewers: Vec<Sender<String>>,
+}
+
+pub struct Server {
+    port: u16,
+    // Where the audience opens the page
+    pub address: String,
+    shared: Arc<Mutex<Shared>>,
+    stop: Arc<AtomicBool>,
+    // The moves and the time of the last state sent, to send clock changes less often
+    sent: Option<(usize, web_time::Instant)>,
+}
+
+impl Server {
+    pub fn start(port: u16) -> std::io::Result<Self> {
+        let listener = TcpListener::bind((Ipv4Addr::UNSPECIFIED, port))?;
+        let shared = Arc::new(Mutex::new(Shared::default()));
+        let stop = Arc::new(AtomicBool::new(false));
+        let (threads_shared, threads_stop) = (shared.clone(), stop.clone());
+        std::thread::spawn(move || {
+            for stream in listener.incoming().flatten() {
+                if threads_stop.load(Ordering::Relaxed) {
+                    break;
+                }
+                let shared = threads_shared.clone();
+                std::thread::spawn(move || serve(stream, &shared));
+            }
+        });
+        Ok(Server {
+            port,
+            address: address(port),
+            shared,
+            stop,
+            sent: None,
+        })
+    }
+
+    pub fn viewers(&self) -> usize {
+        self.shared.lock().map_or(0, |shared| shared.viewers.len())
+    }
+
+    /// Sends `state` to the viewers when it changed. A change of the clocks alone waits for
+    /// `consts::BROADCAST_INTERVAL`, as they change every frame.
+    pub fn publish(&mut self, state: &api::State) {
+        let Ok(text) = serde_json::to_string(state) else {
+            return;
+        };
+        let Ok(mut shared) = self.shared.lock() else {
+            return;
+        };
+        if shared.latest == text {
+            return;
+        }
+        if let Some((moves, at)) = self.sent
+            && moves == state.moves.len()
+            && state.result.is_none()
+            && at.elapsed() < consts::BROADCAST_INTERVAL
+        {
+            return;
+        }
+        shared
+            .viewers
+            .retain(|viewer| viewer.send(text.clone()).is_ok());
+        shared.latest = text;
+        self.sent = Some((state.moves.len(), web_time::Instant::now()));
+    }
+}
+
+impl Drop for Server {
+    fn drop(&mut self) {
+        self.stop.store(true, Ordering::Relaxed);
+        // Wakes the listener so it sees the stop and frees the port
+        let _ = TcpStream::connect((Ipv4Addr::LOCALHOST, self.port));
+        if let Ok(mut shared) = self.shared.lock() {
+            shared.viewers.clear();
+        }
+    }
+}
+
+// The page on the network this computer reaches others by
+fn address(port: u16) -> String {
+    // Connecting a UDP socket sends nothing; it only picks the outgoing interface
+    let ip = UdpSocket::bind((Ipv4Addr::UNSPECIFIED, 0))
+        .and_then(|socket| {
+            socket.connect((Ipv4Addr::new(192, 0, 2, 1), 9))?;
+            socket.local_addr()
+        })
+        .map(|address| address.ip().to_string())
+        .unwrap_or_else(|_| Ipv4Addr::LOCALHOST.to_string());
+    format!("http://{}:{}/", ip, port)
+}
+
+// Serves the page, or keeps a viewer's websocket up to date until it closes
+fn serve(stream: TcpStream, shared: &Mutex<Shared>) {
+    let _ = stream.set_read_timeout(Some(consts::API_TIMEOUT));
+    let _ = stream.set_write_timeout(Some(consts::API_TIMEOUT));
+    let mut reader = BufReader::new(&stream);
+    let mut request_line = String::new();
+    if reader.read_line(&mut request_line).is_err() {
+        return;
+    }
+    let mut key = None;
+    loop {
+        let mut header = String::new();
+        if reader.read_line(&mut header).is_err() {
+            return;
+        }
+        let header = header.trim();
+        if header.is_empty() {
+            break;
+        }
+        if let Some((name, value)) = header.split_once(':')
+            && name.trim().eq_ignore_ascii_case("sec-websocket-key")
+        {
+            key = Some(value.trim().to_string());
+        }
+    }
+    let mut parts = request_line.split_whitespace();
+    let (method, path) = (parts.next().unwrap_or(""), parts.next().unwrap_or(""));
+    match (method, path, key) {
+        ("GET", "/ws", Some(key)) => {
+            let accept = tungstenite::handshake::derive_accept_key(key.as_bytes());
+            let handshake = write!(
+                &stream,
+                "HTTP/1.1 101 Switching Protocols\r\nUpgrade: websocket\r\nConnection: Upgrade\r\nSec-WebSocket-Accept: {}\r\n\r\n",
+                accept
+            );
+            if handshake.is_ok() {
+                watch(
+                    WebSocket::from_raw_socket(stream, Role::Server, None),
+                    shared,
+                );
+            }
+        }
+        ("GET", "/" | "/index.html", _) => {
+            respond(&stream, "200 OK", "text/html; charset=utf-8", PAGE)
+        }
+        _ => respond(&stream, "404 Not Found", "text/plain", "Not found"),
+    }
+}
+
+fn respond(mut stream: &TcpStream, status: &str, content_type: &str, body: &str) {
+    let _ = write!(
+        stream,
+        "HTTP/1.1 {}\r\nContent-Type: {}\r\nContent-Length: {}\r\nCache-Control: no-store\r\nConnection: close\r\n\r\n{}",
+        status,
+        content_type,
+        body.len(),
+        body
+    );
+}
+
+fn watch(mut socket: WebSocket<TcpStream>, shared: &Mutex<Shared>) {
+    let (sender, receiver) = mpsc::channel();
+    let Ok(latest) = shared.lock().map(|mut shared| {
+        shared.viewers.push(sender);
+        shared.latest.clone()
+    }) else {
+        return;
+    };
+    if !latest.is_empty() && socket.send(Message::text(latest)).is_err() {
+        return;
+    }
+    loop {
+        let sent = match receiver.recv_timeout(consts::BROADCAST_PING) {
+            Ok(text) => socket.send(Message::text(text)),
+            // Tells a dead connection apart from a quiet game
+            Err(RecvTimeoutError::Timeout) => socket.send(Message::Ping(Default::default())),
+            Err(RecvTimeoutError::Disconnected) => break,
+        };
+        if sent.is_err() {
+            break;
+        }
+    }
+    let _ = socket.close(None);
+}
@@ -0,0 +1,204 @@
+use std::path::PathBuf;
+
+use clap::{Parser, Subcommand};
+
+use crate::consts;
+use crate::game_info::Rules;
+
+/// Command-line options: a subcommand, or with none the options of the window.
+#[derive(Parser)]
+#[command(
+    version,
+    about = "A Go board for playing, reviewing and studying games",
+    args_conflicts_with_subcommands = true
+)]
+pub struct Args {
+    #[command(subcommand)]
+    pub command: Option<Command>,
+    #[command(flatten)]
+    pub game: GameArgs,
+}
+
+#[derive(Subcommand)]
+pub enum Command {
+    /// Open the window; the same as giving no subcommand
+    Gui(GameArgs),
+    /// Play as a GTP engine on stdin and stdout, choosing moves by the score estimate
+    Gtp(GtpArgs),
+    /// Play games between two engines, or the built-in player, and save them as SGF
+    Selfplay(SelfplayArgs),
+    /// Time the board rules, the score estimate and the built-in player
+    Bench(BenchArgs),
+    /// Rewrite an SGF record, or turn it into a review report in HTML or Markdown
+    Convert(ConvertArgs),
+    /// Check that SGF records can be read and that every move in them is legal
+    Validate(ValidateArgs),
+    /// Run the game server, the go-game-server program built next to this one
+    Serve(ServeArgs),
+    /// Turn SGF records with a result into training data for neural networks
+    Dataset(DatasetArgs),
+    /// Run a Rhai script's hooks over SGF records, or its run function on an empty board
+    Script(ScriptArgs),
+}
+
+/// The options of the window. Anything left out falls back to the saved settings.
+#[derive(clap::Args)]
+pub struct GameArgs {
+    /// Board size, 2 to 25
+    #[arg(long, value_parser = parse_size)]
+    pub size: Option<usize>,
+    /// Japanese or Chinese
+    #[arg(long, value_parser = parse_rules)]
+    pub rules: Option<Rules>,
+    #[arg(long)]
+    pub komi: Option<f32>,
+    #[arg(long, value_parser = clap::value_parser!(u32).range(0..=9))]
+    pub handicap: Option<u32>,
+    /// Command line of a GTP engine to play against
+    #[arg(long)]
+    pub engine: Option<String>,
+    /// SGF file to open
+    #[arg(long)]
+    pub sgf: Option<PathBuf>,
+    /// Print the position and score instead of opening a window; no engine is started
+    #[arg(long)]
+    pub headless: bool,
+    /// Play in the terminal by typing coordinates; no engine is started
+    #[arg(long)]
+    pub tui: bool,
+    /// Serve the game in front as JSON on this port of localhost, with moves taken by POST
+    #[arg(long)]
+    pub api_port: Option<u16>,
+    /// Show the game on a web page for the audience, on the port set in the settings
+    #[arg(long)]
+    pub broadcast: bool,
+}
+
+impl GameArgs {
+    /// Whether to skip the new game form and go straight to the board.
+    pub fn starts_game(&self) -> bool {
+        self.size.is_some()
+            || self.rules.is_some()
+            || self.komi.is_some()
+            || self.handicap.is_some()
+            || self.engine.is_some()
+            || self.sgf.is_some()
+            || self.tui
+    }
+}
+
+#[derive(clap::Args)]
+pub struct GtpArgs {
+    #[arg(long, default_value_t = consts::DEFAULT_BOARD_SIZE, value_parser = parse_size)]
+    pub size: usize,
+    #[arg(long, default_value_t = consts::DEFAULT_KOMI)]
+    pub komi: f32,
+    /// Rhai script whose genmove function chooses the moves, instead of the built-in player
+    #[arg(long)]
+    pub script: Option<PathBuf>,
+    /// Print the board to stderr after each move, to follow the game in a terminal
+    #[arg(long)]
+    pub show_board: bool,
+}
+
+#[derive(clap::Args)]
+pub struct SelfplayArgs {
+    /// Command line of the GTP engine playing Black; the built-in player if left out
+    #[arg(long)]
+    pub black: Option<String>,
+    /// Command line of the GTP engine playing White; the built-in player if left out
+    #[arg(long)]
+    pub white: Option<String>,
+    /// Rhai script whose genmove function plays Black
+    #[arg(long, conflicts_with = "black")]
+    pub black_script: Option<PathBuf>,
+    /// Rhai script whose genmove function plays White
+    #[arg(long, conflicts_with = "white")]
+    pub white_script: Option<PathBuf>,
+    #[arg(long, default_value_t = 1)]
+    pub games: u32,
+    #[arg(long, default_value_t = 9, value_parser = parse_size)]
+    pub size: usize,
+    #[arg(long, default_value_t = consts::DEFAULT_KOMI)]
+    pub komi: f32,
+    /// Japanese or Chinese
+    #[arg(long, default_value = "japanese", value_parser = parse_rules)]
+    pub rules: Rules,
+    /// Folder the games are saved in, as game1.sgf, game2.sgf and so on
+    #[arg(long, default_value = "selfplay")]
+    pub out: PathBuf,
+    /// Print the board after each move, to follow the games in a terminal
+    #[arg(long)]
+    pub show_board: bool,
+}
+
+#[derive(clap::Args)]
+pub struct BenchArgs {
+    #[arg(long, default_value_t = consts::DEFAULT_BOARD_SIZE, value_parser = parse_size)]
+    pub size: usize,
+    /// Random games played to time the board rules
+    #[arg(long, default_value_t = 100)]
+    pub games: u32,
+    /// Starts the random games, so runs can be compared
+    #[arg(long, default_value_t = 1)]
+    pub seed: u64,
+}
+
+#[derive(clap::Args)]
+pub struct ConvertArgs {
+    pub input: PathBuf,
+    /// Written as the extension says: .sgf, .html or .md
+    pub output: PathBuf,
+}
+
+#[derive(clap::Args)]
+pub struct ValidateArgs {
+    #[arg(required = true)]
+    pub files: Vec<PathBuf>,
+}
+
+#[derive(clap::Args)]
+pub struct ServeArgs {
+    /// Passed on to go-game-server; see `go-game-server --help`
+    #[arg(trailing_var_arg = true, allow_hyphen_values = true)]
+    pub args: Vec<String>,
+}
+
+#[derive(clap::Args)]
+pub struct ScriptArgs {
+    pub script: PathBuf,
+    /// Records replayed through the script's on_move and on_game_end functions, then given to run
+    pub records: Vec<PathBuf>,
+    /// Folder the records are saved in with the comments from on_move added
+    #[arg(long)]
+    pub out: Option<PathBuf>,
+}
+
+#[derive(clap::Args)]
+pub struct DatasetArgs {
+    /// Records to read; those of another board size or without a result are left out
+    #[arg(required = true)]
+    pub records: Vec<PathBuf>,
+    #[arg(long, default_value = "dataset.bin")]
+    pub out: PathBuf,
+    #[arg(long, default_value_t = consts::DEFAULT_BOARD_SIZE, value_parser = parse_size)]
+    pub size: usize,
+}
+
+fn parse_size(value: &str) -> Result<usize, String> {
+    value
+        .parse()
+        .ok()
+        .filter(|size| (2..=consts::MAX_BOARD_SIZE).contains(size))
+        .ok_or_else(|| format!("expected a size from 2 to {}", consts::MAX_BOARD_SIZE))
+}
+
+fn parse_rules(value: &str) -> Result<Rules, String> {
+    Rules::ALL
+        .into_iter()
+        .find(|rules| rules.name().eq_ignore_ascii_case(value.trim()))
+        .ok_or_else(|| {
+            let names = Rules::ALL.map(|rules| rules.name().to_lowercase());
+            format!("expected one of: {}", names.join(", "))
+        })
+}
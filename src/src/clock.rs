@@ -0,0 +1,302 @@
+use serde::{Deserialize, Serialize};
+
+use crate::i18n::{tr, trf};
+
+/// Extra time a player gets once the main time has run out.
+#[derive(Clone, Copy, PartialEq, Debug, Serialize, Deserialize)]
+pub enum Overtime {
+    // Sudden death: the game is lost when the main time is up
+    None,
+    // Japanese byo-yomi: a period is only used up by thinking past its end, and every move inside
+    // it starts the period afresh
+    ByoYomi { periods: u32, seconds: f32 },
+    // Canadian: `stones` moves have to be played within `seconds`, after which the next block
+    // starts
+    Canadian { stones: u32, seconds: f32 },
+    // Fischer: every move adds `increment` to the main time, and the game is lost when it is up
+    Fischer { increment: f32 },
+    // A fixed time for every move and no main time; running out passes instead of losing when
+    // `auto_pass` is set
+    PerMove { seconds: f32, auto_pass: bool },
+}
+
+impl Overtime {
+    /// Every kind of overtime, with typical amounts.
+    pub const ALL: [Overtime; 5] = [
+        Overtime::None,
+        Overtime::ByoYomi {
+            periods: 5,
+            seconds: 30.0,
+        },
+        Overtime::Canadian {
+            stones: 25,
+            seconds: 300.0,
+        },
+        Overtime::Fischer { increment: 10.0 },
+        Overtime::PerMove {
+            seconds: 30.0,
+            auto_pass: false,
+        },
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Overtime::None => "None",
+            Overtime::ByoYomi { .. } => "Byo-yomi",
+            Overtime::Canadian { .. } => "Canadian",
+            Overtime::Fischer { .. } => "Fischer",
+            Overtime::PerMove { .. } => "Per move",
+        }
+    }
+
+    /// Whether `other` is the same kind of overtime, whatever its amounts.
+    pub fn same_kind(self, other: Overtime) -> bool {
+        std::mem::discriminant(&self) == std::mem::discriminant(&other)
+    }
+
+    /// Reads an SGF `OT` value such as `5x30 byo-yomi`, `25/300 canadian`, `10 fischer` or
+    /// `30 per move`; anything else has no overtime.
+    pub fn from_sgf(value: &str) -> Overtime {
+        let value = value.trim().to_lowercase();
+        let amounts = value.split_whitespace().next().unwrap_or_default();
+        if value.contains("byo")
+            && let Some((periods, seconds)) = amounts.split_once('x')
+            && let (Ok(periods), Ok(seconds)) = (periods.parse(), seconds.parse())
+        {
+            return Overtime::ByoYomi { periods, seconds };
+        }
+        if value.contains("canadian")
+            && let Some((stones, seconds)) = amounts.split_once('/')
+            && let (Ok(stones), Ok(seconds)) = (stones.parse(), seconds.parse())
+        {
+            return Overtime::Canadian { stones, seconds };
+        }
+        if value.contains("fischer")
+            && let Ok(increment) = amounts.trim_start_matches('+').parse()
+        {
+            return Overtime::Fischer { increment };
+        }
+        if value.contains("per move")
+            && let Ok(seconds) = amounts.parse()
+        {
+            return Overtime::PerMove {
+                seconds,
+                auto_pass: value.contains("pass"),
+            };
+        }
+        Overtime::None
+    }
+
+    pub fn to_sgf(self) -> Option<String> {
+        match self {
+            Overtime::None => None,
+            Overtime::ByoYomi { periods, seconds } => {
+                Some(format!("{}x{} byo-yomi", periods, seconds))
+            }
+            Overtime::Canadian { stones, seconds } => {
+                Some(format!("{}/{} canadian", stones, seconds))
+            }
+            Overtime::Fischer { increment } => Some(format!("{} fischer", increment)),
+            Overtime::PerMove { seconds, auto_pass } => Some(format!(
+                "{} per move{}",
+                seconds,
+                if auto_pass { ", auto-pass" } else { "" }
+            )),
+        }
+    }
+}
+
+/// The time one player has left.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct Clock {
+    overtime: Overtime,
+    // Main time left; only negative once it ran out with no overtime to follow
+    main: f32,
+    // Byo-yomi periods left counting the current one, or stones still to play in the Canadian block
+    count: u32,
+    // Time left in the current period or block; negative once it ran out for good
+    period_left: f32,
+}
+
+impl Clock {
+    pub fn new(main_time: f32, overtime: Overtime) -> Self {
+        // Thinking per move starts at once, without main time
+        let main_time = match overtime {
+            Overtime::PerMove { .. } => 0.0,
+            _ => main_time,
+        };
+        let (count, period_left) = match overtime {
+            Overtime::None | Overtime::Fischer { .. } => (0, 0.0),
+            Overtime::ByoYomi { periods, seconds } => (periods, seconds),
+            Overtime::Canadian { stones, seconds } => (stones, seconds),
+            Overtime::PerMove { seconds, .. } => (1, seconds),
+        };
+        Self {
+            overtime,
+            main: main_time,
+            count,
+            period_left,
+        }
+    }
+
+    pub fn in_overtime(&self) -> bool {
+        !matches!(self.overtime, Overtime::None | Overtime::Fischer { .. }) && self.main <= 0.0
+    }
+
+    /// Takes `seconds` of thinking off the clock.
+    pub fn spend(&mut self, seconds: f32) {
+        let from_main = seconds.min(self.main.max(0.0));
+        self.main -= from_main;
+        let mut seconds = seconds - from_main;
+        match self.overtime {
+            Overtime::None | Overtime::Fischer { .. } => self.main -= seconds,
+            Overtime::ByoYomi {
+                seconds: period, ..
+            } => {
+                while seconds > 0.0 && self.count > 0 {
+                    if seconds < self.period_left {
+                        self.period_left -= seconds;
+                        seconds = 0.0;
+                    } else {
+                        seconds -= self.period_left;
+                        self.count -= 1;
+                        self.period_left = if self.count > 0 { period } else { 0.0 };
+                    }
+                }
+                if self.count == 0 {
+                    self.period_left -= seconds;
+                }
+            }
+            Overtime::Canadian { .. } | Overtime::PerMove { .. } => self.period_left -= seconds,
+        }
+    }
+
+    /// Playing a move inside a byo-yomi period starts it afresh; in Canadian overtime it counts
+    /// towards the block, and the last stone in time starts the next one. A Fischer clock gains
+    /// its increment unless the flag has already fallen, and any move gets the full time per move.
+    pub fn end_move(&mut self) {
+        match self.overtime {
+            Overtime::None => {}
+            Overtime::Fischer { increment } => {
+                if self.main > 0.0 {
+                    self.main += increment;
+                }
+            }
+            Overtime::ByoYomi { seconds, .. } => {
+                if self.count > 0 {
+                    self.period_left = seconds;
+                }
+            }
+            Overtime::PerMove { seconds, .. } => self.period_left = seconds,
+            Overtime::Canadian { stones, seconds } => {
+                if self.in_overtime() && self.period_left > 0.0 {
+                    self.count = self.count.saturating_sub(1);
+                    if self.count == 0 {
+                        self.count = stones;
+                        self.period_left = seconds;
+                    }
+                }
+            }
+        }
+    }
+
+    /// Seconds left in the main time, or in the current period once in overtime.
+    pub fn left(&self) -> f32 {
+        if self.in_overtime() {
+            self.period_left
+        } else {
+            self.main
+        }
+    }
+
+    /// Byo-yomi periods or Canadian stones left, once overtime has started.
+    pub fn count(&self) -> Option<u32> {
+        self.in_overtime().then_some(self.count)
+    }
+
+    pub fn is_out(&self) -> bool {
+        self.left() <= 0.0
+    }
+
+    /// How long the move took that left the clock at `left` with `count` periods or stones
+    /// remaining when it was played, as written in SGF BL/WL and OB/OW.
+    pub fn elapsed_until(&self, left: f32, count: Option<u32>) -> f32 {
+        let period_before = if self.in_overtime() {
+            self.period_left
+        } else {
+            match self.overtime {
+                Overtime::None | Overtime::Fischer { .. } => 0.0,
+                Overtime::ByoYomi { seconds, .. }
+                | Overtime::Canadian { seconds, .. }
+                | Overtime::PerMove { seconds, .. } => seconds,
+            }
+        };
+        let elapsed = match (self.overtime, count) {
+            (Overtime::ByoYomi { seconds, .. }, Some(periods)) => {
+                let periods_before = self.count;
+                self.main.max(0.0)
+                    + periods_before.saturating_sub(periods) as f32 * seconds
+                    + (period_before - left)
+            }
+            (Overtime::Canadian { .. }, Some(_)) => self.main.max(0.0) + (period_before - left),
+            (Overtime::PerMove { .. }, _) => period_before - left,
+            _ => self.main - left,
+        };
+        elapsed.max(0.0)
+    }
+
+    /// The time left, followed by the periods or stones left in overtime.
+    pub fn label(&self) -> String {
+        match (self.overtime, self.count()) {
+            (Overtime::Canadian { .. }, Some(stones)) => {
+                trf("{} for {} stones", &[&format(self.left()), &stones])
+            }
+            (Overtime::PerMove { .. }, _) => format(self.left()),
+            (_, Some(periods)) => format!("{} ({})", format(self.left()), periods),
+            _ => format(self.left()),
+        }
+    }
+}
+
+/// The words the last seconds of overtime are read out in.
+#[derive(Clone, Copy, PartialEq, Debug, Serialize, Deserialize)]
+pub enum CountdownLanguage {
+    // The interface language, through its catalog
+    Interface,
+    // The count heard in Japanese tournaments, written out for any speech program
+    Japanese,
+}
+
+impl CountdownLanguage {
+    pub const ALL: [CountdownLanguage; 2] =
+        [CountdownLanguage::Interface, CountdownLanguage::Japanese];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            CountdownLanguage::Interface => "Interface language",
+            CountdownLanguage::Japanese => "Japanese",
+        }
+    }
+
+    /// How `second`, from ten down to one, is read out.
+    pub fn word(self, second: u32) -> String {
+        const ENGLISH: [&str; 10] = [
+            "one", "two", "three", "four", "five", "six", "seven", "eight", "nine", "ten",
+        ];
+        const JAPANESE: [&str; 10] = [
+            "ichi", "ni", "san", "yon", "go", "roku", "nana", "hachi", "kyuu", "juu",
+        ];
+        let index = second.clamp(1, 10) as usize - 1;
+        match self {
+            CountdownLanguage::Interface => tr(ENGLISH[index]),
+            CountdownLanguage::Japanese => JAPANESE[index].to_string(),
+        }
+    }
+}
+
+// Minutes and seconds, negative once the time has run out
+pub fn format(seconds: f32) -> String {
+    let sign = if seconds < 0.0 { "-" } else { "" };
+    let total = seconds.abs() as u32;
+    format!("{}{}:{:02}", sign, total / 60, total % 60)
+}
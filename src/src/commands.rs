@@ -0,0 +1,858 @@
+//! The subcommands that run without a window: a GTP engine, self-play, a benchmark and the game
+//! server. Each prints what it does and returns a message for the terminal when it fails.
+
+use std::collections::HashSet;
+use std::io::{BufRead, Write};
+use std::path::Path;
+
+use crate::board::{self, Board, Move, Player, Stone};
+use crate::bot;
+use crate::cli;
+use crate::consts;
+use crate::estimate;
+use crate::game_info::{GameInfo, Rules};
+use crate::gtp;
+use crate::notation;
+use crate::scoring;
+use crate::script::{self, Choice, Script};
+use crate::sgf;
+
+// A game without a window, from its starting position
+struct Position {
+    start: Board,
+    board: Board,
+    moves: Vec<Move>,
+    // Black stones and white stones captured so far
+    captures: (u32, u32),
+}
+
+impl Position {
+    fn new(board: Board) -> Self {
+        Self {
+            start: board.clone(),
+            board,
+            moves: Vec::new(),
+            captures: (0, 0),
+        }
+    }
+
+    fn play(&mut self, (player, point): Move) -> Result<(), board::MoveError> {
+        match point {
+            Some((row, col)) => {
+                self.board.check_move(row, col, player)?;
+                let captured = self.board.play(row, col, player);
+                match player {
+                    Player::Black => self.captures.1 += captured,
+                    Player::White => self.captures.0 += captured,
+                }
+            }
+            None => self.board.pass(),
+        }
+        self.moves.push((player, point));
+        Ok(())
+    }
+
+    // Replays everything but the last move
+    fn undo(&mut self) -> bool {
+        let mut moves = std::mem::take(&mut self.moves);
+        if moves.pop().is_none() {
+            return false;
+        }
+        self.board = self.start.clone();
+        self.captures = (0, 0);
+        for played in moves {
+            let _ = self.play(played);
+        }
+        true
+    }
+
+    fn ended(&self) -> bool {
+        self.moves.len() >= 2
+            && self
+                .moves
+                .iter()
+                .rev()
+                .take(2)
+                .all(|(_, point)| point.is_none())
+    }
+
+    fn score(&self, dead: &HashSet<(usize, usize)>, rules: Rules, komi: f32) -> scoring::Score {
+        scoring::score(
+            &self.board,
+            dead,
+            rules,
+            komi,
+            self.captures.0,
+            self.captures.1,
+        )
+    }
+
+    fn to_move(&self) -> Player {
+        match self.moves.last() {
+            Some(&(player, _)) => player.other(),
+            // White moves first after handicap stones
+            None if self.start != Board::new(self.start.size()) => Player::White,
+            None => Player::Black,
+        }
+    }
+
+    fn game(&self, player: Player, komi: f32) -> script::Game {
+        script::Game::new(
+            self.board.clone(),
+            player,
+            self.moves.clone(),
+            self.captures,
+            komi,
+            Rules::Chinese,
+        )
+    }
+
+    fn to_sgf(&self, info: &GameInfo) -> sgf::Node {
+        let size = self.board.size();
+        let mut root = sgf::Node::default();
+        root.set("GM", vec!["1".to_string()]);
+        root.set("FF", vec!["4".to_string()]);
+        root.set("CA", vec!["UTF-8".to_string()]);
+        root.set("AP", vec![consts::SGF_APPLICATION.to_string()]);
+        root.set("SZ", vec![size.to_string()]);
+        info.write_to(&mut root);
+        let stones: Vec<String> = (0..size)
+            .flat_map(|row| (0..size).map(move |col| (row, col)))
+            .filter(|&(row, col)| self.start[row][col] == Stone::Black)
+            .map(sgf::format_point)
+            .collect();
+        root.set("AB", stones);
+        root.children = self
+            .moves
+            .iter()
+            .rev()
+            .fold(Vec::new(), |children, &(player, point)| {
+                let mut node = sgf::Node::default();
+                // An empty value is a pass
+                let value = point.map(sgf::format_point).unwrap_or_default();
+                node.set(gtp::color(player), vec![value]);
+                node.children = children;
+                vec![node]
+            });
+        root
+    }
+}
+
+// A seed that differs between runs and between moves
+fn seed(moves: usize) -> u64 {
+    let nanos = web_time::SystemTime::now()
+        .duration_since(web_time::UNIX_EPOCH)
+        .map(|elapsed| elapsed.subsec_nanos())
+        .unwrap_or_default();
+    u64::from(nanos) ^ moves as u64
+}
+
+fn vertex(point: Option<(usize, usize)>, size: usize) -> String {
+    point.map_or("pass".to_string(), |point| {
+        notation::format_point(point, size)
+    })
+}
+
+fn parse_color(text: &str) -> Option<Player> {
+    match text.to_ascii_lowercase().as_str() {
+        "b" | "black" => Some(Player::Black),
+        "w" | "white" => Some(Player::White),
+        _ => None,
+    }
+}
+
+const GTP_COMMANDS: [&str; 24] = [
+    "protocol_version",
+    "name",
+    "version",
+    "known_command",
+    "list_commands",
+    "quit",
+    "boardsize",
+    "clear_board",
+    "komi",
+    "fixed_handicap",
+    "place_free_handicap",
+    "set_free_handicap",
+    "time_settings",
+    "time_left",
+    "play",
+    "genmove",
+    "undo",
+    "loadsgf",
+    "showboard",
+    "final_score",
+    "final_status_list",
+    "lz-analyze",
+    "candidates",
+    "gogui-analyze_commands",
+];
+
+// Black stones on the handicap points, with nothing played yet
+fn handicapped(size: usize, points: &[(usize, usize)]) -> Position {
+    let mut start = Board::new(size);
+    for &(row, col) in points {
+        start[row][col] = Stone::Black;
+    }
+    Position::new(start)
+}
+
+fn vertices(points: &[(usize, usize)], size: usize) -> String {
+    points
+        .iter()
+        .map(|&point| vertex(Some(point), size))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+// The groups of `stones`, a line each as final_status_list gives them
+fn group_lines(board: &Board, stones: &HashSet<(usize, usize)>) -> String {
+    let mut seen = HashSet::new();
+    let mut lines = Vec::new();
+    let size = board.size();
+    for row in 0..size {
+        for col in 0..size {
+            if !stones.contains(&(row, col)) || seen.contains(&(row, col)) {
+                continue;
+            }
+            let mut group: Vec<_> = board
+                .get_group(row, col, board[row][col])
+                .into_iter()
+                .collect();
+            group.sort_unstable();
+            seen.extend(group.iter().copied());
+            lines.push(vertices(&group, size));
+        }
+    }
+    lines.join("\n")
+}
+
+// The position a record reaches before move `number`, or at its end, with the record's komi
+fn load_position(path: &str, number: Option<usize>) -> Result<(Position, Option<f32>), String> {
+    let text = std::fs::read_to_string(path).map_err(|err| err.to_string())?;
+    let trees = sgf::parse(&text).map_err(|err| err.to_string())?;
+    let root = trees.first().ok_or("no game in the file")?;
+    let size = sgf::parse_size(root.get("SZ").unwrap_or("19"))
+        .filter(|size| (2..=consts::MAX_BOARD_SIZE).contains(size))
+        .ok_or("unsupported board size")?;
+    let komi = root.get("KM").and_then(|komi| komi.trim().parse().ok());
+    let mut start = Board::new(size);
+    let mut moves = Vec::new();
+    for node in sgf::main_line(root) {
+        for (ident, stone) in [
+            ("AB", Stone::Black),
+            ("AW", Stone::White),
+            ("AE", Stone::Empty),
+        ] {
+            let points = node.get_all(ident);
+            // Undo replays from the start, which cannot hold stones set up later in the game
+            if !points.is_empty() && !moves.is_empty() {
+                return Err("stones set up after the first move".to_string());
+            }
+            for (row, col) in points
+                .iter()
+                .filter_map(|value| sgf::parse_point(value, size))
+            {
+                start[row][col] = stone;
+            }
+        }
+        for (ident, player) in [("B", Player::Black), ("W", Player::White)] {
+            if let Some(value) = node.get(ident) {
+                moves.push((player, sgf::parse_point(value, size)));
+            }
+        }
+    }
+    let mut position = Position::new(start);
+    let played = number.map_or(moves.len(), |number| number.saturating_sub(1));
+    for (index, &played) in moves.iter().take(played).enumerate() {
+        position
+            .play(played)
+            .map_err(|err| format!("move {}: {}", index + 1, err))?;
+    }
+    Ok((position, komi))
+}
+
+// The candidates of the built-in player for `player`, best first, with its lead after each
+fn candidates(position: &Position, player: Player, komi: f32) -> Vec<bot::Candidate> {
+    let (_, mut candidates) = bot::candidates(&position.board, player, komi);
+    candidates.truncate(consts::GTP_CANDIDATES);
+    candidates
+}
+
+// Leela Zero's analysis line, which Sabaki reads; the lead stands in for a winning rate out of
+// 10000, and every move counts as one visit as nothing is searched
+fn lz_analysis(position: &Position, player: Player, komi: f32) -> String {
+    let size = position.board.size();
+    candidates(position, player, komi)
+        .into_iter()
+        .enumerate()
+        .map(|(order, (point, lead))| {
+            let winrate = 10000.0 / (1.0 + (-lead / consts::GTP_WINRATE_SCALE).exp());
+            let vertex = vertex(Some(point), size);
+            format!(
+                "info move {} visits 1 winrate {} prior 0 lcb {} order {} pv {}",
+                vertex, winrate as u32, winrate as u32, order, vertex
+            )
+        })
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Speaks GTP on stdin and stdout until `quit` or the end of the input, playing with the
+/// built-in player.
+pub fn gtp(args: &cli::GtpArgs) -> Result<(), String> {
+    let mut size = args.size;
+    let mut komi = args.komi;
+    let mut position = Position::new(Board::new(size));
+    let script = args.script.as_deref().map(Script::load).transpose()?;
+    let stdout = std::io::stdout();
+    for line in std::io::stdin().lock().lines() {
+        let line = line.map_err(|err| err.to_string())?;
+        let line = line.split('#').next().unwrap_or("").trim();
+        let mut words = line.split_whitespace().peekable();
+        // A command may start with a number for the reply to repeat
+        let id = words
+            .next_if(|word| word.chars().all(|c| c.is_ascii_digit()))
+            .unwrap_or("");
+        let Some(command) = words.next() else {
+            continue;
+        };
+        let words: Vec<&str> = words.collect();
+        let played = position.moves.len();
+        let reply: Result<String, String> = match (command, words.as_slice()) {
+            ("protocol_version", _) => Ok("2".to_string()),
+            ("name", _) => Ok("go-game-rust".to_string()),
+            ("version", _) => Ok(env!("CARGO_PKG_VERSION").to_string()),
+            ("known_command", [name, ..]) => Ok(GTP_COMMANDS.contains(name).to_string()),
+            ("list_commands", _) => Ok(GTP_COMMANDS.join("\n")),
+            ("quit", _) => {
+                let _ = write!(stdout.lock(), "={}\n\n", id);
+                return Ok(());
+            }
+            ("boardsize", [value]) => match value.parse() {
+                Ok(value) if (2..=consts::MAX_BOARD_SIZE).contains(&value) => {
+                    size = value;
+                    position = Position::new(Board::new(size));
+                    Ok(String::new())
+                }
+                _ => Err("unacceptable size".to_string()),
+            },
+            ("clear_board", _) => {
+                position = Position::new(Board::new(size));
+                Ok(String::new())
+            }
+            ("komi", [value]) => value
+                .parse()
+                .map(|value| komi = value)
+                .map(|()| String::new())
+                .map_err(|_| "syntax error".to_string()),
+            ("fixed_handicap", [count]) => {
+                let points = count
+                    .parse()
+                    .map(|count| board::handicap_points(size, count))
+                    .unwrap_or_default();
+                if points.is_empty() || !position.moves.is_empty() {
+                    Err("invalid number of stones".to_string())
+                } else {
+                    position = handicapped(size, &points);
+                    Ok(vertices(&points, size))
+                }
+            }
+            ("place_free_handicap", [count]) => match count.parse::<usize>() {
+                Ok(count) if (2..size * size).contains(&count) && position.moves.is_empty() => {
+                    // The usual points first, then wherever the built-in player likes best
+                    let mut points = board::handicap_points(size, count as u32);
+                    points.truncate(count);
+                    let mut board = Board::new(size);
+                    for &(row, col) in &points {
+                        board[row][col] = Stone::Black;
+                    }
+                    while points.len() < count {
+                        let (_, candidates) = bot::candidates(&board, Player::Black, komi);
+                        let Some(&((row, col), _)) = candidates.first() else {
+                            break;
+                        };
+                        board[row][col] = Stone::Black;
+                        points.push((row, col));
+                    }
+                    position = handicapped(size, &points);
+                    Ok(vertices(&points, size))
+                }
+                _ => Err("invalid number of stones".to_string()),
+            },
+            ("set_free_handicap", list) => {
+                let points: Option<Vec<(usize, usize)>> = list
+                    .iter()
+                    .map(|word| notation::parse_point(word, size).flatten())
+                    .collect();
+                match points {
+                    Some(mut points) if points.len() >= 2 && position.moves.is_empty() => {
+                        points.sort_unstable();
+                        points.dedup();
+                        if points.len() == list.len() {
+                            position = handicapped(size, &points);
+                            Ok(String::new())
+                        } else {
+                            Err("bad vertex list".to_string())
+                        }
+                    }
+                    _ => Err("bad vertex list".to_string()),
+                }
+            }
+            // Accepted for the controller's sake; the built-in player moves at once either way
+            ("time_settings", [main, overtime, stones]) => {
+                match (
+                    main.parse::<f32>(),
+                    overtime.parse::<f32>(),
+                    stones.parse::<u32>(),
+                ) {
+                    (Ok(_), Ok(_), Ok(_)) => Ok(String::new()),
+                    _ => Err("syntax error".to_string()),
+                }
+            }
+            ("time_left", [color, left, stones]) => {
+                match (
+                    parse_color(color),
+                    left.parse::<f32>(),
+                    stones.parse::<u32>(),
+                ) {
+                    (Some(_), Ok(_), Ok(_)) => Ok(String::new()),
+                    _ => Err("syntax error".to_string()),
+                }
+            }
+            ("play", [color, point]) => {
+                match (parse_color(color), notation::parse_point(point, size)) {
+                    (Some(player), Some(point)) => position
+                        .play((player, point))
+                        .map(|()| String::new())
+                        .map_err(|_| "illegal move".to_string()),
+                    _ => Err("syntax error".to_string()),
+                }
+            }
+            ("genmove", [color]) => match parse_color(color) {
+                Some(player) => {
+                    let choice = match &script {
+                        Some(script) => script.genmove(&position.game(player, komi)),
+                        None => Ok(Choice::Play(bot::choose(
+                            &position.board,
+                            player,
+                            komi,
+                            seed(position.moves.len()),
+                        ))),
+                    };
+                    match choice {
+                        Ok(Choice::Play(point)) => position
+                            .play((player, point))
+                            .map(|()| vertex(point, size))
+                            .map_err(|err| err.to_string()),
+                        Ok(Choice::Resign) => Ok("resign".to_string()),
+                        Err(err) => Err(err),
+                    }
+                }
+                None => Err("syntax error".to_string()),
+            },
+            ("undo", _) => match position.undo() {
+                true => Ok(String::new()),
+                false => Err("cannot undo".to_string()),
+            },
+            ("loadsgf", [path, number @ ..]) => {
+                let number = match number {
+                    [] => Ok(None),
+                    [number] => number
+                        .parse()
+                        .map(Some)
+                        .map_err(|_| "syntax error".to_string()),
+                    _ => Err("syntax error".to_string()),
+                };
+                number.and_then(|number| {
+                    let (loaded, record_komi) = load_position(path, number)
+                        .map_err(|err| format!("cannot load file: {}", err))?;
+                    size = loaded.board.size();
+                    komi = record_komi.unwrap_or(komi);
+                    position = loaded;
+                    Ok(String::new())
+                })
+            }
+            ("showboard", _) => Ok(format!("\n{}", diagram(&position))),
+            ("final_score", _) => {
+                let dead = estimate::dead_stones(&position.board);
+                Ok(position.score(&dead, Rules::Chinese, komi).result())
+            }
+            ("final_status_list", [status]) => {
+                let dead = estimate::dead_stones(&position.board);
+                match *status {
+                    "dead" => Ok(group_lines(&position.board, &dead)),
+                    "alive" => {
+                        let alive = (0..size)
+                            .flat_map(|row| (0..size).map(move |col| (row, col)))
+                            .filter(|&(row, col)| {
+                                position.board[row][col] != Stone::Empty
+                                    && !dead.contains(&(row, col))
+                            })
+                            .collect();
+                        Ok(group_lines(&position.board, &alive))
+                    }
+                    // Told apart from dead stones by nothing here, so there is never any
+                    "seki" => Ok(String::new()),
+                    _ => Err("syntax error".to_string()),
+                }
+            }
+            // The color is optional, and the interval and other options are ignored as the
+            // candidates come all at once
+            ("lz-analyze", options) => {
+                let player = options
+                    .first()
+                    .and_then(|word| parse_color(word))
+                    .unwrap_or_else(|| position.to_move());
+                Ok(format!("\n{}", lz_analysis(&position, player, komi)))
+            }
+            ("candidates", options) => {
+                let player = options
+                    .first()
+                    .and_then(|word| parse_color(word))
+                    .unwrap_or_else(|| position.to_move());
+                let pairs: Vec<String> = candidates(&position, player, komi)
+                    .into_iter()
+                    .map(|(point, lead)| format!("{} {:+.1}", vertex(Some(point), size), lead))
+                    .collect();
+                Ok(pairs.join(" "))
+            }
+            ("gogui-analyze_commands", _) => {
+                Ok("pspairs/Candidate Moves/candidates %c".to_string())
+            }
+            _ if GTP_COMMANDS.contains(&command) => Err("syntax error".to_string()),
+            _ => Err("unknown command".to_string()),
+        };
+        // Stdout is for the controller, so the board goes to stderr, which controllers show as
+        // the engine's log
+        if args.show_board && position.moves.len() > played {
+            eprint!("{}", show_move(&position, "Move"));
+        }
+        let mut out = stdout.lock();
+        let written = match reply {
+            Ok(text) => write!(out, "={} {}\n\n", id, text),
+            Err(text) => write!(out, "?{} {}\n\n", id, text),
+        };
+        written
+            .and_then(|()| out.flush())
+            .map_err(|err| err.to_string())?;
+    }
+    Ok(())
+}
+
+// The board as text in the way of GTP engines' showboard, with X for Black, O for White and
+// the last stone played in brackets, framed by coordinates and followed by the captures
+fn diagram(position: &Position) -> String {
+    let board = &position.board;
+    let size = board.size();
+    let style = notation::CoordinateStyle::Western;
+    let last = position.moves.last().and_then(|&(_, point)| point);
+    let columns: String = (0..size)
+        .map(|col| format!(" {}", style.column_label(col, size)))
+        .collect();
+    let mut lines = vec![format!("  {}", columns)];
+    for row in 0..size {
+        let label = style.row_label(row, size);
+        let mut line = format!("{:>2} ", label);
+        for col in 0..size {
+            let stone = match board[row][col] {
+                Stone::Black => 'X',
+                Stone::White => 'O',
+                Stone::Empty => '.',
+            };
+            // The brackets take the place of the spaces on either side of the stone
+            if last == Some((row, col)) {
+                line.pop();
+                line.push('(');
+                line.push(stone);
+                line.push(')');
+            } else {
+                line.push(stone);
+                line.push(' ');
+            }
+        }
+        line.push_str(&label);
+        lines.push(line);
+    }
+    lines.push(format!("  {}", columns));
+    lines.push(format!(
+        "Black (X) has captured {} stones, White (O) has captured {}",
+        position.captures.1, position.captures.0
+    ));
+    lines.join("\n")
+}
+
+// The move just played and the board after it, for following a run in the terminal
+fn show_move(position: &Position, heading: &str) -> String {
+    let played = position
+        .moves
+        .last()
+        .map(|&(player, point)| {
+            format!(
+                "{} {}",
+                gtp::color(player),
+                vertex(point, position.board.size())
+            )
+        })
+        .unwrap_or_default();
+    format!(
+        "{} {}: {}\n{}\n",
+        heading,
+        position.moves.len(),
+        played,
+        diagram(position)
+    )
+}
+
+// One side of a self-play game
+enum Side {
+    Bot,
+    Engine(gtp::Engine, String),
+    Script(Box<Script>),
+}
+
+impl Side {
+    fn start(command: Option<&str>, script: Option<&Path>) -> Result<Self, String> {
+        if let Some(script) = script {
+            return Script::load(script).map(|script| Side::Script(Box::new(script)));
+        }
+        let Some(command) = command else {
+            return Ok(Side::Bot);
+        };
+        let mut engine = gtp::Engine::start(command).map_err(|err| err.to_string())?;
+        let name = engine.send("name").unwrap_or_else(|_| command.to_string());
+        Ok(Side::Engine(engine, name))
+    }
+
+    fn name(&self) -> &str {
+        match self {
+            Side::Bot => "go-game-rust",
+            Side::Engine(_, name) => name,
+            Side::Script(script) => &script.name,
+        }
+    }
+
+    fn new_game(&mut self, size: usize, komi: f32) -> Result<(), gtp::Error> {
+        if let Side::Engine(engine, _) = self {
+            engine.send(&format!("boardsize {}", size))?;
+            engine.send("clear_board")?;
+            engine.send(&format!("komi {}", komi))?;
+        }
+        Ok(())
+    }
+
+    // `None` for a resignation
+    fn genmove(
+        &mut self,
+        position: &Position,
+        player: Player,
+        komi: f32,
+    ) -> Result<Option<Move>, String> {
+        match self {
+            Side::Bot => {
+                let point = bot::choose(&position.board, player, komi, seed(position.moves.len()));
+                Ok(Some((player, point)))
+            }
+            Side::Engine(engine, _) => engine
+                .genmove(player, position.board.size())
+                .map_err(|err| err.to_string()),
+            Side::Script(script) => match script.genmove(&position.game(player, komi))? {
+                Choice::Play(point) => Ok(Some((player, point))),
+                Choice::Resign => Ok(None),
+            },
+        }
+    }
+
+    fn tell(&mut self, played: Move, size: usize) -> Result<(), gtp::Error> {
+        match self {
+            Side::Bot | Side::Script(_) => Ok(()),
+            Side::Engine(engine, _) => engine.play(played, size),
+        }
+    }
+}
+
+/// Plays the games one after another and saves each as it ends.
+pub fn selfplay(args: &cli::SelfplayArgs) -> Result<(), String> {
+    std::fs::create_dir_all(&args.out).map_err(|err| err.to_string())?;
+    let mut sides = [
+        Side::start(args.black.as_deref(), args.black_script.as_deref())?,
+        Side::start(args.white.as_deref(), args.white_script.as_deref())?,
+    ];
+    let (mut black_wins, mut white_wins) = (0, 0);
+    for number in 1..=args.games {
+        let (result, moves, position) = selfplay_game(&mut sides, number, args)?;
+        let info = GameInfo {
+            black_name: sides[0].name().to_string(),
+            white_name: sides[1].name().to_string(),
+            komi: args.komi,
+            rules: args.rules,
+            result: result.clone(),
+            ..GameInfo::default()
+        };
+        let path = args.out.join(format!("game{}.sgf", number));
+        std::fs::write(&path, sgf::to_string(&position.to_sgf(&info)))
+            .map_err(|err| format!("{}: {}", path.display(), err))?;
+        if result.starts_with("B+") {
+            black_wins += 1;
+        } else if result.starts_with("W+") {
+            white_wins += 1;
+        }
+        println!(
+            "Game {}: {} in {} moves, saved to {}",
+            number,
+            result,
+            moves,
+            path.display()
+        );
+    }
+    println!(
+        "Black ({}) won {}, White ({}) won {}",
+        sides[0].name(),
+        black_wins,
+        sides[1].name(),
+        white_wins
+    );
+    Ok(())
+}
+
+// Plays one game to its end, returning the result and the number of moves
+fn selfplay_game(
+    sides: &mut [Side; 2],
+    number: u32,
+    args: &cli::SelfplayArgs,
+) -> Result<(String, usize, Position), String> {
+    let size = args.size;
+    for side in sides.iter_mut() {
+        side.new_game(size, args.komi)
+            .map_err(|err| err.to_string())?;
+    }
+    let mut position = Position::new(Board::new(size));
+    let mut player = Player::Black;
+    // Long enough for any real game, short enough to stop two players that never pass
+    let limit = size * size * consts::SELFPLAY_MOVE_FACTOR;
+    while !position.ended() && position.moves.len() < limit {
+        let (mover, other) = match player {
+            Player::Black => (0, 1),
+            Player::White => (1, 0),
+        };
+        let reply = sides[mover].genmove(&position, player, args.komi)?;
+        let Some(played) = reply else {
+            let result = format!("{}+R", gtp::color(player.other()));
+            return Ok((result, position.moves.len(), position));
+        };
+        if position.play(played).is_err() {
+            // An illegal move loses the game by forfeit
+            let result = format!("{}+F", gtp::color(player.other()));
+            return Ok((result, position.moves.len(), position));
+        }
+        sides[other]
+            .tell(played, size)
+            .map_err(|err| err.to_string())?;
+        if args.show_board {
+            println!(
+                "{}",
+                show_move(&position, &format!("Game {}, move", number))
+            );
+        }
+        player = player.other();
+    }
+    // Counted with every stone alive, as nobody is there to mark the dead ones
+    let result = position
+        .score(&HashSet::new(), args.rules, args.komi)
+        .result();
+    Ok((result, position.moves.len(), position))
+}
+
+/// Plays random games to time the board rules, then times the estimate and the built-in player
+/// on the positions they end in.
+pub fn bench(args: &cli::BenchArgs) -> Result<(), String> {
+    let size = args.size;
+    let mut rng = args.seed.max(1);
+    let mut next = move || {
+        // xorshift, which is plenty for picking moves
+        rng ^= rng << 13;
+        rng ^= rng >> 7;
+        rng ^= rng << 17;
+        rng
+    };
+    let started = web_time::Instant::now();
+    let mut finals = Vec::new();
+    let mut moves = 0;
+    for _ in 0..args.games {
+        let mut board = Board::new(size);
+        let mut player = Player::Black;
+        let mut passes = 0;
+        let limit = size * size * consts::SELFPLAY_MOVE_FACTOR;
+        for _ in 0..limit {
+            let legal: Vec<(usize, usize)> = (0..size)
+                .flat_map(|row| (0..size).map(move |col| (row, col)))
+                .filter(|&(row, col)| {
+                    board.is_legal(row, col, player) && !bot::is_own_eye(&board, (row, col), player)
+                })
+                .collect();
+            if legal.is_empty() {
+                board.pass();
+                passes += 1;
+                if passes == 2 {
+                    break;
+                }
+            } else {
+                let (row, col) = legal[next() as usize % legal.len()];
+                board.play(row, col, player);
+                passes = 0;
+            }
+            moves += 1;
+            player = player.other();
+        }
+        finals.push(board);
+    }
+    let seconds = started.elapsed().as_secs_f64();
+    println!(
+        "Board: {} random {}x{} games, {} moves in {:.2} s, {:.0} moves a second",
+        args.games,
+        size,
+        size,
+        moves,
+        seconds,
+        moves as f64 / seconds.max(f64::EPSILON)
+    );
+    if finals.is_empty() {
+        return Ok(());
+    }
+    let started = web_time::Instant::now();
+    for board in &finals {
+        std::hint::black_box(estimate::estimate(board));
+    }
+    let per_position = started.elapsed().as_secs_f64() * 1000.0 / finals.len() as f64;
+    println!("Estimate: {:.3} ms a position", per_position);
+    // The opening is where the built-in player has the most moves to weigh
+    let started = web_time::Instant::now();
+    let empty = Board::new(size);
+    std::hint::black_box(bot::choose(&empty, Player::Black, consts::DEFAULT_KOMI, 0));
+    println!(
+        "Built-in player: {:.1} ms for the first move",
+        started.elapsed().as_secs_f64() * 1000.0
+    );
+    Ok(())
+}
+
+/// Runs go-game-server from the folder this program is in, passing `args` on.
+pub fn serve(args: &[String]) -> Result<(), String> {
+    let exe = std::env::current_exe().map_err(|err| err.to_string())?;
+    let server = exe.with_file_name(format!("go-game-server{}", std::env::consts::EXE_SUFFIX));
+    if !Path::new(&server).exists() {
+        return Err(format!(
+            "{} was not found; build it with `cargo build --features server`",
+            server.display()
+        ));
+    }
+    let status = std::process::Command::new(&server)
+        .args(args)
+        .status()
+        .map_err(|err| format!("{}: {}", server.display(), err))?;
+    if status.success() {
+        Ok(())
+    } else {
+        Err(format!("go-game-server stopped: {}", status))
+    }
+}
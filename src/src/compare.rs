@@ -0,0 +1,122 @@
+//! Two positions side by side with the points where they differ picked out, for seeing where a
+//! joseki went another way, or that two move orders came to the same shape.
+
+use eframe::egui;
+
+use crate::board::{Board, Stone};
+use crate::i18n::{tr, trf};
+use crate::notation::CoordinateStyle;
+use crate::theme::BoardTheme;
+use crate::view;
+
+/// A position kept to compare others with, from this game or another tab.
+#[derive(Clone)]
+pub struct Marked {
+    pub label: String,
+    pub board: Board,
+    // How many moves led to it, to go to the same move of another game
+    pub moves: usize,
+}
+
+/// The points where `first` and `second` have different stones, or none when their sizes differ.
+pub fn differences(first: &Board, second: &Board) -> Vec<(usize, usize)> {
+    let size = first.size();
+    if second.size() != size {
+        return Vec::new();
+    }
+    (0..size)
+        .flat_map(|row| (0..size).map(move |col| (row, col)))
+        .filter(|&(row, col)| first[row][col] != second[row][col])
+        .collect()
+}
+
+fn stone_name(stone: Stone) -> String {
+    tr(match stone {
+        Stone::Black => "Black",
+        Stone::White => "White",
+        Stone::Empty => "empty",
+    })
+}
+
+/// A line for each point of `points`, such as "D4: Black, then empty".
+pub fn describe(
+    first: &Board,
+    second: &Board,
+    points: &[(usize, usize)],
+    style: CoordinateStyle,
+) -> Vec<String> {
+    points
+        .iter()
+        .map(|&(row, col)| {
+            trf(
+                "{}: {}, then {}",
+                &[
+                    &style.format_point((row, col), first.size()),
+                    &stone_name(first[row][col]),
+                    &stone_name(second[row][col]),
+                ],
+            )
+        })
+        .collect()
+}
+
+/// Draws `board` `side` points across, with a frame around each point of `picked` and the last
+/// move, when given, ringed.
+pub fn paint(
+    ui: &mut egui::Ui,
+    board: &Board,
+    picked: &[(usize, usize)],
+    last_move: Option<(usize, usize)>,
+    theme: BoardTheme,
+    side: f32,
+) {
+    let (response, painter) = ui.allocate_painter(egui::Vec2::splat(side), egui::Sense::hover());
+    let rect = response.rect;
+    let size = board.size();
+    painter.rect_filled(rect, 2.0, theme.background());
+    let step = side / size as f32;
+    let at = |(row, col): (usize, usize)| {
+        rect.min + egui::vec2(col as f32 + 0.5, row as f32 + 0.5) * step
+    };
+    let last = size - 1;
+    for index in 0..size {
+        let stroke = egui::Stroke::new(1.0, theme.lines());
+        painter.line_segment([at((index, 0)), at((index, last))], stroke);
+        painter.line_segment([at((0, index)), at((last, index))], stroke);
+    }
+    for &point in view::star_points(size) {
+        painter.circle_filled(at(point), step * 0.1, theme.lines());
+    }
+    let radius = step * 0.47;
+    for row in 0..size {
+        for col in 0..size {
+            let color = match board[row][col] {
+                Stone::Black => egui::Color32::BLACK,
+                Stone::White => egui::Color32::WHITE,
+                Stone::Empty => continue,
+            };
+            painter.circle_filled(at((row, col)), radius, color);
+            painter.circle_stroke(
+                at((row, col)),
+                radius,
+                egui::Stroke::new(1.0, egui::Color32::DARK_GRAY),
+            );
+        }
+    }
+    if let Some(point) = last_move {
+        painter.circle_stroke(
+            at(point),
+            radius * 0.5,
+            egui::Stroke::new((step * 0.08).max(1.5), egui::Color32::RED),
+        );
+    }
+    let frame = egui::Stroke::new((step * 0.1).max(2.0), egui::Color32::from_rgb(255, 140, 0));
+    for &point in picked {
+        painter.rect_stroke(
+            egui::Rect::from_center_size(at(point), egui::Vec2::splat(step * 0.9)),
+            1.0,
+            frame,
+            egui::StrokeKind::Middle,
+        );
+    }
+}
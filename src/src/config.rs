@@ -0,0 +1,109 @@
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+use crate::consts;
+use crate::new_game::Settings;
+
+// Where the browser build keeps the config in local storage
+#[cfg(target_arch = "wasm32")]
+pub const STORAGE_KEY: &str = "config";
+
+#[derive(Clone, Copy, PartialEq, Debug, Serialize, Deserialize)]
+pub struct WindowGeometry {
+    // Not every platform reports where the window is
+    pub position: Option<[f32; 2]>,
+    pub size: [f32; 2],
+    #[serde(default)]
+    pub maximized: bool,
+}
+
+/// Which panels are shown and the sizes they were dragged to.
+#[derive(Clone, Copy, PartialEq, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Layout {
+    pub show_coordinates: bool,
+    pub show_info: bool,
+    pub show_tree: bool,
+    // In points; `None` leaves the panel at its default size
+    pub prisoners_width: Option<f32>,
+    pub history_width: Option<f32>,
+    pub chat_width: Option<f32>,
+    pub tree_height: Option<f32>,
+}
+
+impl Default for Layout {
+    fn default() -> Self {
+        Self {
+            show_coordinates: true,
+            show_info: false,
+            show_tree: false,
+            prisoners_width: None,
+            history_width: None,
+            chat_width: None,
+            tree_height: None,
+        }
+    }
+}
+
+/// Preferences kept between runs in a TOML file under the config directory.
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Config {
+    pub board_size: usize,
+    pub layout: Layout,
+    // Most recently opened or saved first
+    pub recent_files: Vec<PathBuf>,
+    pub settings: Settings,
+    pub window: Option<WindowGeometry>,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            board_size: consts::DEFAULT_BOARD_SIZE,
+            layout: Layout::default(),
+            recent_files: Vec::new(),
+            settings: Settings::default(),
+            window: None,
+        }
+    }
+}
+
+impl Config {
+    /// Reads a config written by `to_text`; anything unreadable gives the defaults.
+    pub fn parse(text: &str) -> Self {
+        toml::from_str(text).unwrap_or_default()
+    }
+
+    pub fn to_text(&self) -> Result<String, toml::ser::Error> {
+        toml::to_string(self)
+    }
+
+    /// Reads the config at `path`; a missing or unreadable file gives the defaults.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn load(path: &Path) -> Self {
+        std::fs::read_to_string(path)
+            .map(|text| Self::parse(&text))
+            .unwrap_or_default()
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn save(&self, path: &Path) -> std::io::Result<()> {
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        let text = self.to_text().map_err(std::io::Error::other)?;
+        std::fs::write(path, text)
+    }
+
+    /// Moves `path` to the front of the recent files, dropping the oldest beyond the limit.
+    pub fn add_recent(&mut self, path: &Path) {
+        if self.recent_files.first().is_some_and(|first| first == path) {
+            return;
+        }
+        self.recent_files.retain(|recent| recent != path);
+        self.recent_files.insert(0, path.to_path_buf());
+        self.recent_files.truncate(consts::MAX_RECENT_FILES);
+    }
+}
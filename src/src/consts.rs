@@ -13,7 +13,152 @@ pub const STAR_POINTS_19X19: &[(usize, usize)] = &[
     (15, 15),
 ];
 pub const DEFAULT_BOARD_SIZE: usize = 19;
-pub const CELL_SIZE: f32 = 30.0;
-pub const STONE_RADIUS: f32 = 12.0;
+// The board scales with the window; this keeps it usable when the window is tiny
+pub const MIN_CELL_SIZE: f32 = 12.0;
+pub const STONE_RADIUS_RATIO: f32 = 0.4;
+pub const MAX_ZOOM: f32 = 4.0;
+pub const TOUCH_TARGET_SIZE: f32 = 40.0;
+pub const PRESENTATION_SCALE: f32 = 1.5;
+pub const UI_SCALE_RANGE: std::ops::RangeInclusive<f32> = 0.5..=3.0;
+pub const CJK_FONT_PATHS: &[&str] = &[
+    "/usr/share/fonts/opentype/noto/NotoSansCJK-Regular.ttc",
+    "/usr/share/fonts/noto-cjk/NotoSansCJK-Regular.ttc",
+    "/usr/share/fonts/truetype/droid/DroidSansFallbackFull.ttf",
+    "/System/Library/Fonts/Hiragino Sans GB.ttc",
+    "C:\\Windows\\Fonts\\msgothic.ttc",
+];
+pub const ANALYSIS_WINDOW_SIZE: [f32; 2] = [420.0, 560.0];
+// Seconds left below which the clock turns red, flashes and ticks
+pub const LOW_TIME: f32 = 10.0;
+pub const MAX_MARKED_MOVES: usize = 20;
+pub const BOWL_SIZE: f32 = 120.0;
+pub const BOWL_CAPACITY: u32 = 100;
+pub const TIME_CHART_HEIGHT: f32 = 60.0;
+// Across each board of the position comparison
+pub const COMPARE_BOARD_SIZE: f32 = 280.0;
+pub const COUNTING_BOARD_SIZE: f32 = 360.0;
+// Room under each board of the split view for its buttons and comment
+pub const STUDY_TEXT_HEIGHT: f32 = 140.0;
+pub const STUDY_MIN_BOARD_SIZE: f32 = 160.0;
+// Across the QR code of a shared game, big enough for a phone to read from across a table
+pub const SHARE_CODE_SIZE: f32 = 400.0;
+pub const GRAPH_HEIGHT: f32 = 160.0;
+pub const TREE_NODE_SPACING: f32 = 24.0;
+pub const TREE_NODE_RADIUS: f32 = 7.0;
 pub const TITLE: &str = "Go Game";
-pub const WINDOW_SIZE: [f32; 2] = [800.0, 850.0];
+#[cfg(not(target_arch = "wasm32"))]
+pub const WINDOW_SIZE: [f32; 2] = [1200.0, 850.0];
+pub const APP_DIR_NAME: &str = "go-game";
+#[cfg(not(target_arch = "wasm32"))]
+pub const OGS_DEFAULT_GAME_COUNT: usize = 10;
+pub const MAX_RECENT_FILES: usize = 10;
+pub const MAX_BOARD_SIZE: usize = 25;
+pub const MAX_CAPTURE_GOAL: u32 = 20;
+pub const MAX_HIDDEN_STONES: u32 = 5;
+// How many moves ahead a shape drill reads to see whether stones are caught
+pub const SHAPE_READING_DEPTH: usize = 12;
+// How far from an endgame play its follow-ups are looked for
+pub const YOSE_REACH: usize = 2;
+// How many plays an endgame line is read for before it is counted
+pub const YOSE_DEPTH: usize = 5;
+// How far from an endgame play the points counted for it reach
+pub const YOSE_COUNTED: usize = 5;
+// How many points by the estimate a move has to lose to make a drill
+pub const MISTAKE_POINTS: f32 = 5.0;
+// The better moves a drill from a mistake accepts: up to this many, this close to the best
+pub const MISTAKE_ANSWERS: usize = 3;
+pub const MISTAKE_ANSWER_MARGIN: f32 = 1.0;
+// A review report draws this many of the biggest mistakes, and its text graph is at most this
+// wide, with the lead written out every so many moves below it
+pub const REPORT_MISTAKES: usize = 6;
+pub const REPORT_GRAPH_WIDTH: usize = 60;
+pub const REPORT_LEAD_STEP: usize = 25;
+// Openings are named for this many moves, and corner patterns within this many lines of the corner
+pub const FUSEKI_MOVES: usize = 30;
+pub const JOSEKI_CORNER: usize = 6;
+// Elo ratings of the profiles: where a new player starts, how far a game moves a rating while it
+// is still provisional and after, and what a handicap stone is taken to be worth
+pub const START_RATING: f32 = 1500.0;
+pub const PROVISIONAL_GAMES: u32 = 10;
+pub const PROVISIONAL_K: f32 = 40.0;
+pub const RATING_K: f32 = 20.0;
+pub const STONE_RATING: f32 = 100.0;
+// A handicap game gives White this komi, and reverse komi takes this many points off it for each
+// rank between the players
+pub const HANDICAP_KOMI: f32 = 0.5;
+pub const RANK_POINTS: f32 = 14.0;
+pub const MAX_HANDICAP: u32 = 9;
+// Results listed in the head-to-head record of two profiles
+pub const HEAD_TO_HEAD_SHOWN: usize = 5;
+// Entries kept in the log of one game
+pub const LOG_ENTRIES: usize = 5000;
+// Lines of an engine's stderr kept for the game log after each move
+pub const ENGINE_NOTES: usize = 20;
+// How long the last move's stone shows in Blind Go
+pub const BLIND_FLASH: std::time::Duration = std::time::Duration::from_millis(1500);
+// Between the steps of the count as it plays
+pub const COUNTING_STEP: std::time::Duration = std::time::Duration::from_millis(1200);
+pub const DEFAULT_KOMI: f32 = 6.5;
+// Room for about four million positions
+pub const ANALYSIS_CACHE_MEGABYTES: u32 = 64;
+// Points of estimated lead within which the built-in player counts moves as equally good
+#[cfg(not(target_arch = "wasm32"))]
+pub const BOT_MARGIN: f32 = 0.5;
+// Steps a script may take in one call before it is stopped
+#[cfg(not(target_arch = "wasm32"))]
+pub const SCRIPT_MAX_OPERATIONS: u64 = 50_000_000;
+// Candidate moves the GTP analysis commands list
+#[cfg(not(target_arch = "wasm32"))]
+pub const GTP_CANDIDATES: usize = 10;
+// Points of lead that make a winning rate of about 73% in the GTP analysis of Sabaki
+#[cfg(not(target_arch = "wasm32"))]
+pub const GTP_WINRATE_SCALE: f32 = 10.0;
+// Self-play and bench games stop after this many moves per point of the board
+#[cfg(not(target_arch = "wasm32"))]
+pub const SELFPLAY_MOVE_FACTOR: usize = 3;
+pub const SGF_APPLICATION: &str = "go-game-rust:0.1.0";
+pub const GUESS_NEARBY_DISTANCE: usize = 2;
+pub const DEFAULT_ENGINE_COMMAND: &str = "gnugo --mode gtp";
+pub const LAN_PORT: u16 = 7650;
+pub const DEFAULT_SERVER: &str = "localhost:7651";
+pub const IGS_SERVER: &str = "igs.joyjoy.net:6969";
+pub const RECONNECT_SECONDS: u32 = 60;
+// Boards one player may open for a simultaneous game
+pub const MAX_SIMUL_BOARDS: u32 = 20;
+// Board sizes the automatch queue of the game server offers
+#[cfg(not(target_arch = "wasm32"))]
+pub const MATCH_SIZES: [usize; 3] = [9, 13, 19];
+// Games listed for an account of the game server
+#[cfg(not(target_arch = "wasm32"))]
+pub const PROFILE_GAMES: usize = 10;
+// Between tries to get a dropped network game back
+pub const RECONNECT_RETRY: std::time::Duration = std::time::Duration::from_secs(2);
+// How long the API waits for a client to send its request, and for the game to play a move
+#[cfg(not(target_arch = "wasm32"))]
+pub const API_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(5);
+#[cfg(not(target_arch = "wasm32"))]
+pub const API_MAX_BODY: usize = 4096;
+pub const BROADCAST_PORT: u16 = 7652;
+// The shortest wait between broadcasts that only move the clocks, which count down on the page
+#[cfg(not(target_arch = "wasm32"))]
+pub const BROADCAST_INTERVAL: std::time::Duration = std::time::Duration::from_secs(1);
+// How long a quiet broadcast waits before checking that a viewer is still there
+#[cfg(not(target_arch = "wasm32"))]
+pub const BROADCAST_PING: std::time::Duration = std::time::Duration::from_secs(15);
+// Rounds a new tournament plans, and the McMahon bar it starts with
+#[cfg(not(target_arch = "wasm32"))]
+pub const TOURNAMENT_ROUNDS: u32 = 5;
+#[cfg(not(target_arch = "wasm32"))]
+pub const MCMAHON_BAR: &str = "1d";
+// How often the wall of boards redraws for the clocks, and how long a broadcast it follows
+// waits for news before checking it is still wanted
+#[cfg(not(target_arch = "wasm32"))]
+pub const WALL_REFRESH: std::time::Duration = std::time::Duration::from_millis(500);
+#[cfg(not(target_arch = "wasm32"))]
+pub const WALL_POLL: std::time::Duration = std::time::Duration::from_millis(500);
+// Engines, the speech program and the games folder need a desktop; the browser build leaves them out
+pub const DESKTOP: bool = cfg!(not(target_arch = "wasm32"));
+#[cfg(target_os = "macos")]
+pub const DEFAULT_SPEECH_COMMAND: &str = "say";
+#[cfg(not(target_os = "macos"))]
+pub const DEFAULT_SPEECH_COMMAND: &str = "espeak";
@@ -0,0 +1,83 @@
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+
+use crate::board::Player;
+use crate::protocol::OngoingGame;
+
+/// A correspondence game on a game server, with its record as last seen here.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Game {
+    pub server: String,
+    pub code: String,
+    pub color: Player,
+    // Empty while the room waits for an opponent
+    pub opponent: String,
+    // Empty for a game not opened here yet
+    pub sgf: String,
+    pub your_turn: bool,
+}
+
+#[derive(Default, Serialize, Deserialize)]
+pub struct Games {
+    pub games: Vec<Game>,
+}
+
+impl Games {
+    pub fn load(path: &Path) -> Self {
+        std::fs::read_to_string(path)
+            .ok()
+            .and_then(|text| serde_json::from_str(&text).ok())
+            .unwrap_or_default()
+    }
+
+    pub fn save(&self, path: &Path) -> std::io::Result<()> {
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::write(path, serde_json::to_string(self)?)
+    }
+
+    /// Keeps `game` in place of what was kept of it before.
+    pub fn remember(&mut self, game: Game) {
+        match self
+            .games
+            .iter_mut()
+            .find(|known| known.server == game.server && known.code == game.code)
+        {
+            Some(known) => *known = game,
+            None => self.games.push(game),
+        }
+    }
+
+    pub fn forget(&mut self, server: &str, code: &str) {
+        self.games
+            .retain(|known| known.server != server || known.code != code);
+    }
+
+    /// Takes the list the account on `server` has there: games missing from it are over, and
+    /// games begun elsewhere are added without a record.
+    pub fn update(&mut self, server: &str, ongoing: Vec<OngoingGame>) {
+        self.games.retain(|known| {
+            known.server != server || ongoing.iter().any(|game| game.code == known.code)
+        });
+        for game in ongoing {
+            let sgf = self
+                .games
+                .iter()
+                .find(|known| known.server == server && known.code == game.code)
+                .map(|known| known.sgf.clone())
+                .unwrap_or_default();
+            self.remember(Game {
+                server: server.to_string(),
+                code: game.code,
+                color: game.color,
+                opponent: game.opponent,
+                sgf,
+                your_turn: game.your_turn,
+            });
+        }
+        // Those waiting for a move here come first
+        self.games.sort_by_key(|game| !game.your_turn);
+    }
+}
@@ -0,0 +1,375 @@
+//! The count of a game under Japanese rules played out step by step, the way it is done over a
+//! real board: the dead stones come off, the prisoners fill the other side's territory, and the
+//! stones are moved about until each territory makes rectangles of ten.
+
+use std::collections::HashSet;
+
+use eframe::egui;
+
+use crate::board::{Board, Stone};
+use crate::i18n::{self, tr, trf};
+use crate::theme::{self, BoardTheme};
+use crate::view;
+
+/// One picture of the count, with what happened to get there.
+pub struct Frame {
+    pub board: Board,
+    // Who owns each empty point, for shading
+    pub territory: Vec<Vec<Stone>>,
+    // Stones to point out, such as the ones just put down
+    pub picked: Vec<(usize, usize)>,
+    // Opposite corners of each rectangle of ten
+    pub tens: Vec<((usize, usize), (usize, usize))>,
+    pub caption: String,
+}
+
+/// The count being shown, stepped through by hand or played.
+pub struct Counting {
+    pub frames: Vec<Frame>,
+    pub step: usize,
+    pub playing: bool,
+    // When the step last changed while playing
+    pub stepped: web_time::Instant,
+}
+
+impl Counting {
+    pub fn new(
+        board: &Board,
+        dead: &HashSet<(usize, usize)>,
+        territory: &[Vec<Stone>],
+        captured: (u32, u32),
+        komi: f32,
+    ) -> Self {
+        Counting {
+            frames: frames(board, dead, territory, captured, komi),
+            step: 0,
+            playing: true,
+            stepped: web_time::Instant::now(),
+        }
+    }
+
+    pub fn frame(&self) -> &Frame {
+        &self.frames[self.step]
+    }
+
+    pub fn last(&self) -> bool {
+        self.step + 1 == self.frames.len()
+    }
+}
+
+// At most this many pictures of prisoners going in, so the step stays short on a big board
+const PRISONER_FRAMES: usize = 8;
+
+// `captured` is the black stones and the white stones captured during the game
+fn frames(
+    board: &Board,
+    dead: &HashSet<(usize, usize)>,
+    territory: &[Vec<Stone>],
+    (captured_black, captured_white): (u32, u32),
+    komi: f32,
+) -> Vec<Frame> {
+    let size = board.size();
+    let points = || (0..size).flat_map(move |row| (0..size).map(move |col| (row, col)));
+    let owned = |owner: Stone| {
+        points()
+            .filter(|&(row, col)| territory[row][col] == owner)
+            .count() as i32
+    };
+    let (black_area, white_area) = (owned(Stone::Black), owned(Stone::White));
+    let mut dead_points: Vec<_> = dead.iter().copied().collect();
+    dead_points.sort_unstable();
+    let mut frames = vec![Frame {
+        board: board.clone(),
+        territory: territory.to_vec(),
+        picked: dead_points.clone(),
+        tens: Vec::new(),
+        caption: trf(
+            "The game is over and the dead stones are marked. Black's territory is {} points, White's {}.",
+            &[&black_area, &white_area],
+        ),
+    }];
+
+    // Black's prisoners are the white stones Black took, in the game or as dead stones
+    let dead_of = |stone: Stone| {
+        dead.iter()
+            .filter(|&&(row, col)| board[row][col] == stone)
+            .count()
+    };
+    let black_prisoners = captured_white as usize + dead_of(Stone::White);
+    let white_prisoners = captured_black as usize + dead_of(Stone::Black);
+    let mut cleared = board.clone();
+    for &(row, col) in &dead_points {
+        cleared[row][col] = Stone::Empty;
+    }
+    frames.push(Frame {
+        board: cleared.clone(),
+        territory: territory.to_vec(),
+        picked: Vec::new(),
+        tens: Vec::new(),
+        caption: trf(
+            "The dead stones are taken off and join the prisoners: Black has {} white stones, White has {} black stones.",
+            &[&black_prisoners, &white_prisoners],
+        ),
+    });
+
+    // The prisoners go into the territory of their own color, a few at a time
+    let fill = |owner: Stone| -> Vec<(usize, usize)> {
+        points()
+            .filter(|&(row, col)| territory[row][col] == owner)
+            .collect()
+    };
+    let white_fill: Vec<_> = fill(Stone::White)
+        .into_iter()
+        .take(black_prisoners)
+        .collect();
+    let black_fill: Vec<_> = fill(Stone::Black)
+        .into_iter()
+        .take(white_prisoners)
+        .collect();
+    let caption = trf(
+        "Black's {} prisoners fill in White's territory, and White's {} fill in Black's.",
+        &[&black_prisoners, &white_prisoners],
+    );
+    let longest = white_fill.len().max(black_fill.len());
+    let per_frame = longest.div_ceil(PRISONER_FRAMES).max(1);
+    let mut filled = cleared;
+    let mut owners = territory.to_vec();
+    for start in (0..longest).step_by(per_frame) {
+        let mut picked = Vec::new();
+        for (points, stone) in [(&white_fill, Stone::White), (&black_fill, Stone::Black)] {
+            for &(row, col) in points.iter().skip(start).take(per_frame) {
+                filled[row][col] = stone;
+                owners[row][col] = Stone::Empty;
+                picked.push((row, col));
+            }
+        }
+        frames.push(Frame {
+            board: filled.clone(),
+            territory: owners.clone(),
+            picked,
+            tens: Vec::new(),
+            caption: caption.clone(),
+        });
+    }
+
+    // What is left of each territory, less than nothing when the prisoners did not fit
+    let black_left = black_area - white_prisoners as i32;
+    let white_left = white_area - black_prisoners as i32;
+    let tally = |left: i32| {
+        if left < 0 {
+            trf("{} short", &[&-left])
+        } else {
+            format!("{} × 10 + {} = {}", left / 10, left % 10, left)
+        }
+    };
+    match arrange(size, black_left.max(0) as usize, white_left.max(0) as usize) {
+        Some(arranged) => frames.push(Frame {
+            caption: trf(
+                "The stones are moved about so each territory makes rectangles of ten, which are quick to count. Black: {}. White: {}.",
+                &[&tally(black_left), &tally(white_left)],
+            ),
+            ..arranged
+        }),
+        None => frames.push(Frame {
+            board: filled,
+            territory: owners,
+            picked: Vec::new(),
+            tens: Vec::new(),
+            caption: trf(
+                "The territories are too big to lay out in rectangles on this board, so they are counted where they are. Black: {}. White: {}.",
+                &[&tally(black_left), &tally(white_left)],
+            ),
+        }),
+    }
+
+    let white_total = white_left as f32 + komi;
+    let lead = black_left as f32 - white_total;
+    let result = if lead > 0.0 {
+        trf("Black wins by {}", &[&i18n::number(lead)])
+    } else if lead < 0.0 {
+        trf("White wins by {}", &[&i18n::number(-lead)])
+    } else {
+        tr("The game is a draw")
+    };
+    let last = frames.last().expect("the count has frames");
+    frames.push(Frame {
+        board: last.board.clone(),
+        territory: last.territory.clone(),
+        picked: Vec::new(),
+        tens: last.tens.clone(),
+        caption: trf(
+            "White adds the komi of {}: Black {}, White {}. {}.",
+            &[
+                &i18n::number(komi),
+                &black_left,
+                &i18n::number(white_total),
+                &result,
+            ],
+        ),
+    });
+    frames
+}
+
+// A board with Black's territory in rows from the bottom left corner and White's from the top,
+// each walled in by its own stones, or none when they do not fit
+fn arrange(size: usize, black: usize, white: usize) -> Option<Frame> {
+    // A row of ten where it fits, otherwise two rows of five make a ten; White's corner is
+    // tried on the right first, which leaves the most room
+    for width in [10, 5].into_iter().filter(|&width| width <= size) {
+        for white_right in [true, false] {
+            if let Some(frame) = lay_out(size, width, black, white, white_right) {
+                return Some(frame);
+            }
+        }
+    }
+    None
+}
+
+fn lay_out(
+    size: usize,
+    width: usize,
+    black: usize,
+    white: usize,
+    white_right: bool,
+) -> Option<Frame> {
+    if black.div_ceil(width) > size || white.div_ceil(width) > size {
+        return None;
+    }
+    let mut territory = vec![vec![Stone::Empty; size]; size];
+    let mut tens = Vec::new();
+    for (count, stone) in [(black, Stone::Black), (white, Stone::White)] {
+        let at = |index: usize| {
+            let (row, col) = (index / width, index % width);
+            match stone {
+                Stone::Black => (size - 1 - row, col),
+                _ if white_right => (row, size - 1 - col),
+                _ => (row, col),
+            }
+        };
+        for index in 0..count {
+            let (row, col) = at(index);
+            if territory[row][col] != Stone::Empty {
+                return None;
+            }
+            territory[row][col] = stone;
+        }
+        for ten in 0..count / 10 {
+            let first = at(ten * 10);
+            let last = at(ten * 10 + 9);
+            tens.push((
+                (first.0.min(last.0), first.1.min(last.1)),
+                (first.0.max(last.0), first.1.max(last.1)),
+            ));
+        }
+    }
+    // Each territory may only touch stones of its owner, so no point can border both
+    let mut board = Board::new(size);
+    let mut walls = vec![vec![Stone::Empty; size]; size];
+    for row in 0..size {
+        for col in 0..size {
+            let owner = territory[row][col];
+            if owner == Stone::Empty {
+                continue;
+            }
+            for (next_row, next_col) in board.get_neighbors(row, col) {
+                match territory[next_row][next_col] {
+                    Stone::Empty if walls[next_row][next_col] == Stone::Empty => {
+                        walls[next_row][next_col] = owner;
+                    }
+                    Stone::Empty if walls[next_row][next_col] == owner => {}
+                    other if other == owner => {}
+                    _ => return None,
+                }
+            }
+        }
+    }
+    // The stones away from both go to the side of the board they are on
+    for row in 0..size {
+        for col in 0..size {
+            if territory[row][col] != Stone::Empty {
+                continue;
+            }
+            board[row][col] = match walls[row][col] {
+                Stone::Empty if 2 * row < size => Stone::White,
+                Stone::Empty => Stone::Black,
+                wall => wall,
+            };
+        }
+    }
+    Some(Frame {
+        board,
+        territory,
+        picked: Vec::new(),
+        tens,
+        caption: String::new(),
+    })
+}
+
+/// Draws `frame` `side` points across, with the territory shaded, the picked stones ringed and
+/// each ten framed.
+pub fn paint(ui: &mut egui::Ui, frame: &Frame, theme: BoardTheme, high_contrast: bool, side: f32) {
+    let (response, painter) = ui.allocate_painter(egui::Vec2::splat(side), egui::Sense::hover());
+    let rect = response.rect;
+    let board = &frame.board;
+    let size = board.size();
+    painter.rect_filled(rect, 2.0, theme.background());
+    let step = side / size as f32;
+    let at = |(row, col): (usize, usize)| {
+        rect.min + egui::vec2(col as f32 + 0.5, row as f32 + 0.5) * step
+    };
+    let last = size - 1;
+    for index in 0..size {
+        let stroke = egui::Stroke::new(1.0, theme.lines());
+        painter.line_segment([at((index, 0)), at((index, last))], stroke);
+        painter.line_segment([at((0, index)), at((last, index))], stroke);
+    }
+    for &point in view::star_points(size) {
+        painter.circle_filled(at(point), step * 0.1, theme.lines());
+    }
+    let radius = step * 0.47;
+    for row in 0..size {
+        for col in 0..size {
+            let color = match board[row][col] {
+                Stone::Black => egui::Color32::BLACK,
+                Stone::White => egui::Color32::WHITE,
+                Stone::Empty => {
+                    if let Some(color) =
+                        theme::territory_color(frame.territory[row][col], high_contrast)
+                    {
+                        painter.rect_filled(
+                            egui::Rect::from_center_size(
+                                at((row, col)),
+                                egui::Vec2::splat(step * 0.5),
+                            ),
+                            0.0,
+                            color,
+                        );
+                    }
+                    continue;
+                }
+            };
+            painter.circle_filled(at((row, col)), radius, color);
+            painter.circle_stroke(
+                at((row, col)),
+                radius,
+                egui::Stroke::new(1.0, egui::Color32::DARK_GRAY),
+            );
+        }
+    }
+    let highlight = egui::Color32::from_rgb(255, 140, 0);
+    for &point in &frame.picked {
+        painter.circle_stroke(
+            at(point),
+            radius * 0.6,
+            egui::Stroke::new((step * 0.08).max(1.5), highlight),
+        );
+    }
+    for &(first, last) in &frame.tens {
+        painter.rect_stroke(
+            egui::Rect::from_two_pos(at(first), at(last)).expand(step * 0.4),
+            2.0,
+            egui::Stroke::new((step * 0.08).max(1.5), highlight),
+            egui::StrokeKind::Middle,
+        );
+    }
+}
@@ -0,0 +1,64 @@
+//! The puzzle of the day: a shape drill picked by the date alone, so everyone gets the same one
+//! on the same day, and how many days in a row its first try has been right.
+
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+
+use crate::shapes::Drill;
+
+/// The drill of `day`, counted in days since the Unix epoch.
+pub fn puzzle(day: u32) -> Drill {
+    Drill::generate(None, u64::from(day))
+}
+
+#[derive(Default, Serialize, Deserialize)]
+pub struct Streak {
+    // The last day whose puzzle was tried
+    last: Option<u32>,
+    days: u32,
+    pub best: u32,
+}
+
+impl Streak {
+    pub fn load(path: &Path) -> Self {
+        std::fs::read_to_string(path)
+            .ok()
+            .and_then(|text| serde_json::from_str(&text).ok())
+            .unwrap_or_default()
+    }
+
+    pub fn save(&self, path: &Path) -> std::io::Result<()> {
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::write(path, serde_json::to_string(self)?)
+    }
+
+    /// The days in a row solved, as long as yesterday's or today's puzzle was among them.
+    pub fn current(&self, today: u32) -> u32 {
+        match self.last {
+            Some(last) if last + 1 >= today => self.days,
+            _ => 0,
+        }
+    }
+
+    pub fn tried(&self, today: u32) -> bool {
+        self.last == Some(today)
+    }
+
+    /// Keeps how the first try at the puzzle of `day` went; later tries that day do not count.
+    pub fn record(&mut self, day: u32, solved: bool) -> bool {
+        if self.last.is_some_and(|last| last >= day) {
+            return false;
+        }
+        self.days = match (solved, self.last) {
+            (false, _) => 0,
+            (true, Some(last)) if last + 1 == day => self.days + 1,
+            (true, _) => 1,
+        };
+        self.best = self.best.max(self.days);
+        self.last = Some(day);
+        true
+    }
+}
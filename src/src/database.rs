@@ -0,0 +1,182 @@
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+use crate::board::{Move, Player};
+use crate::game_info::GameInfo;
+use crate::sgf;
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Record {
+    pub path: PathBuf,
+    pub black: String,
+    pub white: String,
+    pub event: String,
+    pub date: String,
+    pub result: String,
+    pub board_size: usize,
+    // Main line as three-character tokens such as "Bpd", with ".." marking a pass
+    pub moves: String,
+    pub setup_black: Vec<(usize, usize)>,
+    pub setup_white: Vec<(usize, usize)>,
+}
+
+impl Record {
+    pub fn from_sgf(path: PathBuf, text: &str) -> Option<Self> {
+        let trees = sgf::parse(text).ok()?;
+        let root = &trees[0];
+        let board_size = sgf::parse_size(root.get("SZ").unwrap_or("19"))?;
+        let info = GameInfo::from_node(root);
+        let points = |ident| {
+            root.get_all(ident)
+                .iter()
+                .filter_map(|value| sgf::parse_point(value, board_size))
+                .collect()
+        };
+        let mut moves = String::new();
+        for node in sgf::main_line(root) {
+            for (ident, player) in [("B", Player::Black), ("W", Player::White)] {
+                if let Some(value) = node.get(ident) {
+                    push_move(&mut moves, (player, sgf::parse_point(value, board_size)));
+                }
+            }
+        }
+        Some(Self {
+            black: info.black_label(),
+            white: info.white_label(),
+            event: info.event,
+            date: info.date,
+            result: info.result,
+            board_size,
+            moves,
+            setup_black: points("AB"),
+            setup_white: points("AW"),
+            path,
+        })
+    }
+
+    pub fn year(&self) -> &str {
+        self.date.get(..4).unwrap_or_default()
+    }
+
+    pub fn moves(&self) -> impl Iterator<Item = Move> + '_ {
+        self.moves.as_bytes().chunks(3).map(|token| {
+            let player = if token[0] == b'B' {
+                Player::Black
+            } else {
+                Player::White
+            };
+            let point = std::str::from_utf8(&token[1..])
+                .ok()
+                .and_then(|value| sgf::parse_point(value, self.board_size));
+            (player, point)
+        })
+    }
+}
+
+fn push_move(out: &mut String, (player, point): Move) {
+    out.push(match player {
+        Player::Black => 'B',
+        Player::White => 'W',
+    });
+    match point {
+        Some(point) => out.push_str(&sgf::format_point(point)),
+        None => out.push_str(".."),
+    }
+}
+
+/// Encodes moves in the same compact form as [`Record::moves`], for prefix comparisons.
+pub fn encode_moves(moves: &[Move]) -> String {
+    let mut out = String::new();
+    for &played in moves {
+        push_move(&mut out, played);
+    }
+    out
+}
+
+#[derive(Default)]
+pub struct Query {
+    pub player: String,
+    pub event: String,
+    pub year: String,
+    pub result: String,
+    pub board_size: Option<usize>,
+}
+
+impl Query {
+    fn matches(&self, record: &Record) -> bool {
+        let contains = |haystack: &str, needle: &str| {
+            haystack
+                .to_lowercase()
+                .contains(&needle.trim().to_lowercase())
+        };
+        (contains(&record.black, &self.player) || contains(&record.white, &self.player))
+            && contains(&record.event, &self.event)
+            && record.year().starts_with(self.year.trim())
+            && contains(&record.result, &self.result)
+            && self.board_size.is_none_or(|size| size == record.board_size)
+    }
+}
+
+#[derive(Default, Serialize, Deserialize)]
+pub struct Database {
+    pub records: Vec<Record>,
+}
+
+impl Database {
+    pub fn load(path: &Path) -> Self {
+        std::fs::read_to_string(path)
+            .ok()
+            .and_then(|text| serde_json::from_str(&text).ok())
+            .unwrap_or_default()
+    }
+
+    pub fn save(&self, path: &Path) -> std::io::Result<()> {
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::write(path, serde_json::to_string(self)?)
+    }
+
+    /// Adds records for files not indexed yet and returns how many were added.
+    pub fn merge(&mut self, records: Vec<Record>) -> usize {
+        let before = self.records.len();
+        for record in records {
+            if !self.records.iter().any(|known| known.path == record.path) {
+                self.records.push(record);
+            }
+        }
+        self.records.len() - before
+    }
+
+    pub fn search(&self, query: &Query) -> Vec<usize> {
+        (0..self.records.len())
+            .filter(|&index| query.matches(&self.records[index]))
+            .collect()
+    }
+}
+
+/// Parses every SGF file below `dir`; this is the slow part of an import and runs off the UI thread.
+pub fn scan(dir: &Path) -> Vec<Record> {
+    let mut records = Vec::new();
+    let mut pending = vec![dir.to_path_buf()];
+    while let Some(dir) = pending.pop() {
+        let Ok(read_dir) = std::fs::read_dir(&dir) else {
+            continue;
+        };
+        for entry in read_dir.flatten() {
+            let path = entry.path();
+            if path.is_dir() {
+                pending.push(path);
+            } else if path
+                .extension()
+                .is_some_and(|ext| ext.eq_ignore_ascii_case("sgf"))
+                && let Ok(text) = std::fs::read_to_string(&path)
+                && let Some(record) = Record::from_sgf(path, &text)
+            {
+                records.push(record);
+            }
+        }
+    }
+    records
+}
@@ -0,0 +1,167 @@
+//! Training data for neural networks: each move of a game becomes a record of the position before
+//! it as feature planes, the move played and who went on to win. The layout, little-endian
+//! throughout:
+//!
+//! - header: the magic `GOTD`, then one byte each for the version (1), the board size and the
+//!   number of planes;
+//! - records, as many as fit to the end of the file: the planes, each `size * size` bits packed
+//!   row by row from the top left, lowest bit first, and padded to a whole byte; a byte for the
+//!   color to move, 0 for Black and 1 for White; the komi as an `f32`; the move played as a `u16`,
+//!   `row * size + col` or `size * size` for a pass; and the outcome as an `i8`, 1 when the
+//!   player to move won, -1 when they lost and 0 for a draw.
+//!
+//! The planes are seen from the player to move, in the order of [`Plane::ALL`].
+
+use std::io::{self, Write};
+
+use crate::board::{Board, Move, Player, Stone};
+
+pub const MAGIC: &[u8; 4] = b"GOTD";
+pub const VERSION: u8 = 1;
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Plane {
+    Own,
+    Opponent,
+    Empty,
+    // Stones in groups with one liberty, then with two
+    OwnAtari,
+    OpponentAtari,
+    OwnTwoLiberties,
+    OpponentTwoLiberties,
+    // The point that may not be taken back at once
+    Ko,
+    LastMove,
+    Legal,
+}
+
+impl Plane {
+    pub const ALL: [Plane; 10] = [
+        Plane::Own,
+        Plane::Opponent,
+        Plane::Empty,
+        Plane::OwnAtari,
+        Plane::OpponentAtari,
+        Plane::OwnTwoLiberties,
+        Plane::OpponentTwoLiberties,
+        Plane::Ko,
+        Plane::LastMove,
+        Plane::Legal,
+    ];
+
+    fn contains(
+        self,
+        board: &Board,
+        (row, col): (usize, usize),
+        player: Player,
+        last: Option<(usize, usize)>,
+    ) -> bool {
+        let stone = board[row][col];
+        let own = stone == player.to_stone();
+        let opponent = stone == player.other().to_stone();
+        let liberties = || board.liberties(row, col).len();
+        match self {
+            Plane::Own => own,
+            Plane::Opponent => opponent,
+            Plane::Empty => stone == Stone::Empty,
+            Plane::OwnAtari => own && liberties() == 1,
+            Plane::OpponentAtari => opponent && liberties() == 1,
+            Plane::OwnTwoLiberties => own && liberties() == 2,
+            Plane::OpponentTwoLiberties => opponent && liberties() == 2,
+            Plane::Ko => board.ko_point() == Some((row, col)),
+            Plane::LastMove => last == Some((row, col)),
+            Plane::Legal => board.is_legal(row, col, player),
+        }
+    }
+}
+
+/// The planes of `board` for `player`, packed as in the file.
+pub fn planes(board: &Board, player: Player, last: Option<(usize, usize)>) -> Vec<u8> {
+    let size = board.size();
+    let bytes = (size * size).div_ceil(8);
+    let mut packed = Vec::with_capacity(bytes * Plane::ALL.len());
+    for plane in Plane::ALL {
+        let mut bits = vec![0; bytes];
+        for row in 0..size {
+            for col in 0..size {
+                if plane.contains(board, (row, col), player, last) {
+                    let index = row * size + col;
+                    bits[index / 8] |= 1 << (index % 8);
+                }
+            }
+        }
+        packed.extend(bits);
+    }
+    packed
+}
+
+pub struct Writer<W: Write> {
+    out: W,
+    size: usize,
+    pub records: usize,
+}
+
+impl<W: Write> Writer<W> {
+    /// Writes the header for boards of `size`.
+    pub fn new(mut out: W, size: usize) -> io::Result<Self> {
+        out.write_all(MAGIC)?;
+        out.write_all(&[VERSION, size as u8, Plane::ALL.len() as u8])?;
+        Ok(Writer {
+            out,
+            size,
+            records: 0,
+        })
+    }
+
+    /// Writes a record for each move of a game played from `start`. `black_score` is 1 for a win
+    /// by Black, 0 for a win by White and 0.5 for a draw. Stops at the first illegal move, which
+    /// a checked record does not have.
+    pub fn write_game(
+        &mut self,
+        start: Board,
+        moves: &[Move],
+        komi: f32,
+        black_score: f32,
+    ) -> io::Result<()> {
+        let mut board = start;
+        let mut last = None;
+        let black_outcome: i8 = match black_score {
+            score if score > 0.5 => 1,
+            score if score < 0.5 => -1,
+            _ => 0,
+        };
+        for &(player, point) in moves {
+            if let Some((row, col)) = point
+                && !board.is_legal(row, col, player)
+            {
+                break;
+            }
+            let outcome = match player {
+                Player::Black => black_outcome,
+                Player::White => -black_outcome,
+            };
+            let index = match point {
+                Some((row, col)) => row * self.size + col,
+                None => self.size * self.size,
+            };
+            self.out.write_all(&planes(&board, player, last))?;
+            self.out.write_all(&[u8::from(player == Player::White)])?;
+            self.out.write_all(&komi.to_le_bytes())?;
+            self.out.write_all(&(index as u16).to_le_bytes())?;
+            self.out.write_all(&outcome.to_le_bytes())?;
+            self.records += 1;
+            match point {
+                Some((row, col)) => {
+                    board.play(row, col, player);
+                }
+                None => board.pass(),
+            }
+            last = point;
+        }
+        Ok(())
+    }
+
+    pub fn finish(mut self) -> io::Result<()> {
+        self.out.flush()
+    }
+}
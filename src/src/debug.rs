@@ -0,0 +1,103 @@
+//! An overlay of what the rules see, to make a rule bug visible on the board instead of in
+//! printouts: the number and liberties of each group, the position hash, the ko point and the
+//! passes in a row.
+
+use eframe::egui;
+
+use crate::board::{Board, Move, Stone};
+use crate::i18n::{tr, trf};
+use crate::notation;
+
+/// A chain of stones as the rules see it, numbered in reading order from the top left.
+pub struct Group {
+    pub stone: Stone,
+    pub points: Vec<(usize, usize)>,
+    pub liberties: usize,
+}
+
+pub fn groups(board: &Board) -> Vec<Group> {
+    let size = board.size();
+    let mut seen = vec![vec![false; size]; size];
+    let mut groups = Vec::new();
+    for row in 0..size {
+        for col in 0..size {
+            let stone = board[row][col];
+            if stone == Stone::Empty || seen[row][col] {
+                continue;
+            }
+            let mut points: Vec<_> = board.get_group(row, col, stone).into_iter().collect();
+            points.sort_unstable();
+            for &(row, col) in &points {
+                seen[row][col] = true;
+            }
+            groups.push(Group {
+                stone,
+                points,
+                liberties: board.liberties(row, col).len(),
+            });
+        }
+    }
+    groups
+}
+
+/// The state behind the board, a line each.
+pub fn facts(board: &Board, moves: &[Move], groups: &[Group]) -> Vec<String> {
+    let passes = moves
+        .iter()
+        .rev()
+        .take_while(|(_, point)| point.is_none())
+        .count();
+    let ko = board.ko_point().map_or_else(
+        || tr("none"),
+        |point| notation::format_point(point, board.size()),
+    );
+    vec![
+        trf("Hash: {}", &[&format!("{:016x}", board.checksum())]),
+        trf("Ko point: {}", &[&ko]),
+        trf("Passes in a row: {}", &[&passes]),
+        trf("Moves: {}", &[&moves.len()]),
+        trf("Groups: {}", &[&groups.len()]),
+    ]
+}
+
+/// Writes "group:liberties" on every stone, in red for a group in atari, and the facts in the
+/// top left corner of `rect`.
+pub fn paint(
+    painter: &egui::Painter,
+    rect: egui::Rect,
+    groups: &[Group],
+    facts: &[String],
+    at: impl Fn((usize, usize)) -> egui::Pos2,
+    radius: f32,
+) {
+    let font = egui::FontId::monospace((radius * 0.6).max(8.0));
+    for (index, group) in groups.iter().enumerate() {
+        let color = match (group.liberties, group.stone) {
+            (0 | 1, _) => egui::Color32::RED,
+            (_, Stone::Black) => egui::Color32::WHITE,
+            _ => egui::Color32::BLACK,
+        };
+        let label = format!("{}:{}", index + 1, group.liberties);
+        for &point in &group.points {
+            painter.text(
+                at(point),
+                egui::Align2::CENTER_CENTER,
+                &label,
+                font.clone(),
+                color,
+            );
+        }
+    }
+    let galley = painter.layout_no_wrap(
+        facts.join("\n"),
+        egui::FontId::monospace(12.0),
+        egui::Color32::WHITE,
+    );
+    let corner = rect.left_top() + egui::vec2(6.0, 6.0);
+    painter.rect_filled(
+        egui::Rect::from_min_size(corner, galley.size()).expand(4.0),
+        3.0,
+        egui::Color32::from_black_alpha(190),
+    );
+    painter.galley(corner, galley, egui::Color32::WHITE);
+}
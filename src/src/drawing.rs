@@ -0,0 +1,73 @@
+use eframe::egui;
+
+/// How a drag on the board draws while the drawing layer is on.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Tool {
+    Pen,
+    Arrow,
+}
+
+impl Tool {
+    pub const ALL: [Tool; 2] = [Tool::Pen, Tool::Arrow];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Tool::Pen => "Pen",
+            Tool::Arrow => "Arrow",
+        }
+    }
+}
+
+/// One line drawn by hand over the board. Points are in grid cells from the top left
+/// intersection as shown on screen, so strokes keep their place when the board is zoomed or
+/// the window resized.
+#[derive(Clone, Debug)]
+pub struct Stroke {
+    pub tool: Tool,
+    pub color: egui::Color32,
+    pub points: Vec<egui::Pos2>,
+}
+
+impl Stroke {
+    pub fn new(tool: Tool, color: egui::Color32, start: egui::Pos2) -> Self {
+        Self {
+            tool,
+            color,
+            points: vec![start],
+        }
+    }
+
+    /// Continues the stroke to `point`; an arrow only moves its head.
+    pub fn extend(&mut self, point: egui::Pos2) {
+        match self.tool {
+            Tool::Pen => self.points.push(point),
+            Tool::Arrow => {
+                self.points.truncate(1);
+                self.points.push(point);
+            }
+        }
+    }
+
+    /// Paints the stroke on a board whose top left intersection is at `top_left`.
+    pub fn paint(&self, painter: &egui::Painter, top_left: egui::Pos2, cell: f32) {
+        let stroke = egui::Stroke::new(cell * 0.12, self.color);
+        let points: Vec<egui::Pos2> = self
+            .points
+            .iter()
+            .map(|point| top_left + point.to_vec2() * cell)
+            .collect();
+        painter.add(egui::Shape::line(points.clone(), stroke));
+        if self.tool == Tool::Arrow
+            && let [start, end] = points[..]
+            && start != end
+        {
+            let back = (start - end).normalized() * cell * 0.45;
+            let side = back.rot90() * 0.6;
+            painter.add(egui::Shape::convex_polygon(
+                vec![end, end + back + side, end + back - side],
+                self.color,
+                egui::Stroke::NONE,
+            ));
+        }
+    }
+}
@@ -0,0 +1,188 @@
+use crate::board::{Board, Move, Stone};
+use crate::i18n::{self, tr};
+
+// Bouzy's 5/21 setting: enough dilation to spread into moyos, enough erosion to keep it honest
+const DILATIONS: usize = 5;
+const EROSIONS: usize = 21;
+const STONE_INFLUENCE: i32 = 128;
+
+pub struct Estimate {
+    // The expected owner of every point; `Empty` for neutral points
+    pub owner: Vec<Vec<Stone>>,
+    // Stones plus owned empty points
+    pub black: u32,
+    pub white: u32,
+}
+
+impl Estimate {
+    /// Black's lead after komi; negative when White is ahead.
+    pub fn lead(&self, komi: f32) -> f32 {
+        self.black as f32 - self.white as f32 - komi
+    }
+}
+
+/// A lead as a result, e.g. "B+3.5", to a tenth of a point.
+pub fn format_lead(lead: f32) -> String {
+    if lead > 0.0 {
+        format!("B+{}", i18n::number((lead * 10.0).round() / 10.0))
+    } else if lead < 0.0 {
+        format!("W+{}", i18n::number((-lead * 10.0).round() / 10.0))
+    } else {
+        tr("Even")
+    }
+}
+
+fn neighbor_values(values: &[Vec<i32>], row: usize, col: usize) -> impl Iterator<Item = i32> {
+    let size = values.len();
+    [(0, -1), (0, 1), (-1, 0), (1, 0)]
+        .into_iter()
+        .filter_map(move |(dr, dc)| {
+            let r = row.checked_add_signed(dr).filter(|&r| r < size)?;
+            let c = col.checked_add_signed(dc).filter(|&c| c < size)?;
+            Some(values[r][c])
+        })
+}
+
+// Grows each side's influence into points not touched by the other side
+fn dilate(values: &[Vec<i32>]) -> Vec<Vec<i32>> {
+    let mut result = values.to_vec();
+    for (row, line) in values.iter().enumerate() {
+        for (col, &value) in line.iter().enumerate() {
+            let neighbors: Vec<i32> = neighbor_values(values, row, col).collect();
+            if value >= 0 && neighbors.iter().all(|&n| n >= 0) {
+                result[row][col] += neighbors.iter().filter(|&&n| n > 0).count() as i32;
+            }
+            if value <= 0 && neighbors.iter().all(|&n| n <= 0) {
+                result[row][col] -= neighbors.iter().filter(|&&n| n < 0).count() as i32;
+            }
+        }
+    }
+    result
+}
+
+// Wears influence down where it borders neutral or opposing points, never past zero
+fn erode(values: &[Vec<i32>]) -> Vec<Vec<i32>> {
+    let mut result = values.to_vec();
+    for (row, line) in values.iter().enumerate() {
+        for (col, &value) in line.iter().enumerate() {
+            let neighbors = neighbor_values(values, row, col);
+            if value > 0 {
+                let opposing = neighbors.filter(|&n| n <= 0).count() as i32;
+                result[row][col] = (value - opposing).max(0);
+            } else if value < 0 {
+                let opposing = neighbors.filter(|&n| n >= 0).count() as i32;
+                result[row][col] = (value + opposing).min(0);
+            }
+        }
+    }
+    result
+}
+
+/// Bouzy's dilation/erosion influence; positive values belong to Black, negative to White.
+pub fn bouzy(board: &Board, dilations: usize, erosions: usize) -> Vec<Vec<i32>> {
+    let size = board.size();
+    let mut values: Vec<Vec<i32>> = (0..size)
+        .map(|row| {
+            (0..size)
+                .map(|col| match board[row][col] {
+                    Stone::Black => STONE_INFLUENCE,
+                    Stone::White => -STONE_INFLUENCE,
+                    Stone::Empty => 0,
+                })
+                .collect()
+        })
+        .collect();
+    for _ in 0..dilations {
+        values = dilate(&values);
+    }
+    for _ in 0..erosions {
+        values = erode(&values);
+    }
+    values
+}
+
+pub fn influence(board: &Board) -> Vec<Vec<i32>> {
+    bouzy(board, DILATIONS, EROSIONS)
+}
+
+/// A quick ownership estimate from Bouzy influence; every stone on the board counts as alive.
+pub fn estimate(board: &Board) -> Estimate {
+    let size = board.size();
+    let influence = influence(board);
+    let mut estimate = Estimate {
+        owner: vec![vec![Stone::Empty; size]; size],
+        black: 0,
+        white: 0,
+    };
+    for row in 0..size {
+        for col in 0..size {
+            let owner = match board[row][col] {
+                Stone::Empty if influence[row][col] > 0 => Stone::Black,
+                Stone::Empty if influence[row][col] < 0 => Stone::White,
+                stone => stone,
+            };
+            match owner {
+                Stone::Black => estimate.black += 1,
+                Stone::White => estimate.white += 1,
+                Stone::Empty => {}
+            }
+            estimate.owner[row][col] = owner;
+        }
+    }
+    estimate
+}
+
+/// Black's estimated lead after komi at the start and after every move.
+pub fn analyze(mut board: Board, moves: &[Move], komi: f32) -> Vec<f32> {
+    let mut leads = vec![estimate(&board).lead(komi)];
+    for &(player, point) in moves {
+        match point {
+            Some((row, col)) => {
+                board.play(row, col, player);
+            }
+            None => board.pass(),
+        }
+        leads.push(estimate(&board).lead(komi));
+    }
+    leads
+}
+
+/// The stones the estimate takes for dead: groups with no liberty in their own area that leave
+/// all their points to the other side once taken off. Seki looks dead to it.
+#[cfg(not(target_arch = "wasm32"))]
+pub fn dead_stones(board: &Board) -> std::collections::HashSet<(usize, usize)> {
+    let owner = estimate(board).owner;
+    let size = board.size();
+    let mut dead = std::collections::HashSet::new();
+    let mut seen = std::collections::HashSet::new();
+    for row in 0..size {
+        for col in 0..size {
+            let stone = board[row][col];
+            if stone == Stone::Empty || seen.contains(&(row, col)) {
+                continue;
+            }
+            let group = board.get_group(row, col, stone);
+            seen.extend(group.iter().copied());
+            if board
+                .liberties(row, col)
+                .iter()
+                .any(|&(row, col)| owner[row][col] == stone)
+            {
+                continue;
+            }
+            let mut without = board.clone();
+            for &(row, col) in &group {
+                without[row][col] = Stone::Empty;
+            }
+            let after = estimate(&without).owner;
+            let other = match stone {
+                Stone::Black => Stone::White,
+                _ => Stone::Black,
+            };
+            if group.iter().all(|&(row, col)| after[row][col] == other) {
+                dead.extend(group);
+            }
+        }
+    }
+    dead
+}
@@ -0,0 +1,53 @@
+use std::collections::HashMap;
+
+use crate::board::{Move, Player};
+use crate::database::{self, Record};
+
+pub struct Candidate {
+    // `None` for a pass
+    pub point: Option<(usize, usize)>,
+    pub games: usize,
+    pub wins: usize,
+}
+
+#[derive(Default)]
+pub struct Statistics {
+    pub games: usize,
+    pub candidates: Vec<Candidate>,
+}
+
+/// Counts how often each move followed `moves` among games that started from an empty board.
+pub fn explore(records: &[Record], board_size: usize, moves: &[Move]) -> Statistics {
+    let prefix = database::encode_moves(moves);
+    let mut stats = Statistics::default();
+    let mut candidates: HashMap<Option<(usize, usize)>, Candidate> = HashMap::new();
+    for record in records.iter().filter(|record| {
+        record.board_size == board_size
+            && record.setup_black.is_empty()
+            && record.setup_white.is_empty()
+            && record.moves.starts_with(&prefix)
+    }) {
+        stats.games += 1;
+        let Some((player, point)) = record.moves().nth(moves.len()) else {
+            continue;
+        };
+        let candidate = candidates.entry(point).or_insert(Candidate {
+            point,
+            games: 0,
+            wins: 0,
+        });
+        candidate.games += 1;
+        let winner_prefix = match player {
+            Player::Black => "B+",
+            Player::White => "W+",
+        };
+        if record.result.starts_with(winner_prefix) {
+            candidate.wins += 1;
+        }
+    }
+    stats.candidates = candidates.into_values().collect();
+    stats
+        .candidates
+        .sort_by_key(|candidate| std::cmp::Reverse(candidate.games));
+    stats
+}
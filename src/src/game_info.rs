@@ -0,0 +1,158 @@
+use serde::{Deserialize, Serialize};
+
+use crate::clock::Overtime;
+use crate::consts;
+use crate::sgf;
+
+#[derive(Clone, Copy, PartialEq, Debug, Serialize, Deserialize)]
+pub enum Rules {
+    Japanese,
+    Chinese,
+}
+
+impl Rules {
+    pub const ALL: [Rules; 2] = [Rules::Japanese, Rules::Chinese];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Rules::Japanese => "Japanese",
+            Rules::Chinese => "Chinese",
+        }
+    }
+
+    // Unknown rule sets fall back to territory counting
+    fn from_sgf(value: &str) -> Rules {
+        match value.trim().to_lowercase().as_str() {
+            "chinese" | "cn" | "aga" | "nz" => Rules::Chinese,
+            _ => Rules::Japanese,
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct GameInfo {
+    pub black_name: String,
+    pub black_rank: String,
+    pub white_name: String,
+    pub white_rank: String,
+    pub event: String,
+    pub date: String,
+    pub place: String,
+    pub komi: f32,
+    pub handicap: u32,
+    pub rules: Rules,
+    pub result: String,
+    pub copyright: String,
+    // Main time in seconds for each player, 0 when untimed
+    pub time_limit: f32,
+    pub overtime: Overtime,
+}
+
+impl Default for GameInfo {
+    fn default() -> Self {
+        Self {
+            black_name: String::new(),
+            black_rank: String::new(),
+            white_name: String::new(),
+            white_rank: String::new(),
+            event: String::new(),
+            date: String::new(),
+            place: String::new(),
+            komi: consts::DEFAULT_KOMI,
+            handicap: 0,
+            rules: Rules::Japanese,
+            result: String::new(),
+            copyright: String::new(),
+            time_limit: 0.0,
+            overtime: Overtime::None,
+        }
+    }
+}
+
+impl GameInfo {
+    pub fn from_node(node: &sgf::Node) -> Self {
+        let text = |ident| node.get(ident).unwrap_or_default().to_string();
+        Self {
+            black_name: text("PB"),
+            black_rank: text("BR"),
+            white_name: text("PW"),
+            white_rank: text("WR"),
+            event: text("EV"),
+            date: text("DT"),
+            place: text("PC"),
+            komi: node
+                .get("KM")
+                .and_then(|km| km.trim().parse().ok())
+                .unwrap_or(0.0),
+            handicap: node
+                .get("HA")
+                .and_then(|ha| ha.trim().parse().ok())
+                .unwrap_or(0),
+            rules: Rules::from_sgf(node.get("RU").unwrap_or_default()),
+            result: text("RE"),
+            copyright: text("CP"),
+            time_limit: node
+                .get("TM")
+                .and_then(|tm| tm.trim().parse().ok())
+                .unwrap_or(0.0),
+            overtime: Overtime::from_sgf(node.get("OT").unwrap_or_default()),
+        }
+    }
+
+    pub fn write_to(&self, node: &mut sgf::Node) {
+        for (ident, value) in [
+            ("PB", &self.black_name),
+            ("BR", &self.black_rank),
+            ("PW", &self.white_name),
+            ("WR", &self.white_rank),
+            ("EV", &self.event),
+            ("DT", &self.date),
+            ("PC", &self.place),
+            ("RE", &self.result),
+            ("CP", &self.copyright),
+        ] {
+            let values = if value.trim().is_empty() {
+                Vec::new()
+            } else {
+                vec![value.trim().to_string()]
+            };
+            node.set(ident, values);
+        }
+        node.set("KM", vec![self.komi.to_string()]);
+        node.set("RU", vec![self.rules.name().to_string()]);
+        let handicap = if self.handicap > 1 {
+            vec![self.handicap.to_string()]
+        } else {
+            Vec::new()
+        };
+        node.set("HA", handicap);
+        let time_limit = if self.time_limit > 0.0 {
+            vec![self.time_limit.to_string()]
+        } else {
+            Vec::new()
+        };
+        node.set("TM", time_limit);
+        node.set("OT", self.overtime.to_sgf().into_iter().collect());
+    }
+
+    /// Whether the game is played with a clock.
+    pub fn is_timed(&self) -> bool {
+        self.time_limit > 0.0 || self.overtime != Overtime::None
+    }
+
+    fn player_label(name: &str, rank: &str) -> String {
+        match (name.is_empty(), rank.is_empty()) {
+            (true, _) => "?".to_string(),
+            (false, true) => name.to_string(),
+            (false, false) => format!("{} [{}]", name, rank),
+        }
+    }
+
+    pub fn black_label(&self) -> String {
+        Self::player_label(&self.black_name, &self.black_rank)
+    }
+
+    pub fn white_label(&self) -> String {
+        Self::player_label(&self.white_name, &self.white_rank)
+    }
+}
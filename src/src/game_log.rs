@@ -0,0 +1,109 @@
+//! What happened in a game as it happened: the moves and their times, the clocks, the engine
+//! and the network. Every entry also goes out as a `tracing` event, which the desktop build
+//! writes to a log file for all games together.
+
+use serde::Serialize;
+
+use crate::consts;
+
+#[derive(Clone, Copy, PartialEq, Debug, Serialize)]
+pub enum Kind {
+    Game,
+    Move,
+    Clock,
+    Engine,
+    Network,
+}
+
+impl Kind {
+    pub const ALL: [Kind; 5] = [
+        Kind::Game,
+        Kind::Move,
+        Kind::Clock,
+        Kind::Engine,
+        Kind::Network,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Kind::Game => "Game",
+            Kind::Move => "Move",
+            Kind::Clock => "Clock",
+            Kind::Engine => "Engine",
+            Kind::Network => "Network",
+        }
+    }
+}
+
+#[derive(Serialize)]
+pub struct Entry {
+    // Since the log was started
+    pub seconds: f32,
+    pub kind: Kind,
+    pub text: String,
+}
+
+pub struct Log {
+    started: web_time::Instant,
+    // Oldest first, with the oldest dropped past `consts::LOG_ENTRIES`
+    pub entries: Vec<Entry>,
+}
+
+impl Default for Log {
+    fn default() -> Self {
+        Self {
+            started: web_time::Instant::now(),
+            entries: Vec::new(),
+        }
+    }
+}
+
+impl Log {
+    pub fn record(&mut self, kind: Kind, text: String) {
+        let seconds = self.started.elapsed().as_secs_f32();
+        tracing::info!(target: "game", kind = kind.name(), seconds, "{}", text);
+        if self.entries.len() >= consts::LOG_ENTRIES {
+            self.entries.remove(0);
+        }
+        self.entries.push(Entry {
+            seconds,
+            kind,
+            text,
+        });
+    }
+
+    /// The entries as JSON, one object to a line.
+    pub fn to_json_lines(&self) -> String {
+        self.entries
+            .iter()
+            .filter_map(|entry| serde_json::to_string(entry).ok())
+            .map(|line| line + "\n")
+            .collect()
+    }
+}
+
+/// Sends the `tracing` events of the whole app to `path`, keeping the log of the run before as
+/// `.old`. `GO_GAME_LOG` sets how much is written, from `error` to `trace`, with `info` when it
+/// is not set; `debug` adds the GTP traffic and every network message.
+#[cfg(not(target_arch = "wasm32"))]
+pub fn install(path: &std::path::Path) -> std::io::Result<()> {
+    use tracing_subscriber::filter::LevelFilter;
+
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    if path.exists() {
+        std::fs::rename(path, path.with_extension("old"))?;
+    }
+    let file = std::fs::File::create(path)?;
+    let level = std::env::var("GO_GAME_LOG")
+        .ok()
+        .and_then(|level| level.parse::<LevelFilter>().ok())
+        .unwrap_or(LevelFilter::INFO);
+    tracing_subscriber::fmt()
+        .json()
+        .with_max_level(level)
+        .with_writer(std::sync::Mutex::new(file))
+        .init();
+    Ok(())
+}
@@ -0,0 +1,283 @@
+use crate::board::{Move, Player};
+use crate::markup::Mark;
+
+#[derive(Clone)]
+pub struct Node {
+    // `None` only for the root, which stands for the starting position
+    pub played: Option<Move>,
+    pub parent: Option<usize>,
+    pub children: Vec<usize>,
+    // Time spent thinking on the move, when known
+    pub seconds: Option<f32>,
+    pub markup: Vec<((usize, usize), Mark)>,
+    pub comment: String,
+    // How the author of a problem judged the line, saved as TE or BM
+    pub verdict: Option<Verdict>,
+    // The name of a branch shown while teaching, saved as N
+    pub name: String,
+    // A label to find the position by again, saved as BOOKMARK
+    pub bookmark: String,
+    // The child that redo and "next move" follow; the first child is the main line
+    selected: usize,
+}
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum Verdict {
+    Correct,
+    Wrong,
+}
+
+/// All moves and variations of a game. Nodes live in one vector and refer to each other by index.
+#[derive(Clone)]
+pub struct GameTree {
+    nodes: Vec<Node>,
+    current: usize,
+}
+
+impl Default for GameTree {
+    fn default() -> Self {
+        Self {
+            nodes: vec![Node {
+                played: None,
+                parent: None,
+                children: Vec::new(),
+                seconds: None,
+                markup: Vec::new(),
+                comment: String::new(),
+                verdict: None,
+                name: String::new(),
+                bookmark: String::new(),
+                selected: 0,
+            }],
+            current: 0,
+        }
+    }
+}
+
+impl GameTree {
+    pub const ROOT: usize = 0;
+
+    pub fn current(&self) -> usize {
+        self.current
+    }
+
+    pub fn node(&self, id: usize) -> &Node {
+        &self.nodes[id]
+    }
+
+    /// The child that redo follows from `id`.
+    pub fn next(&self, id: usize) -> Option<usize> {
+        let node = &self.nodes[id];
+        node.children.get(node.selected).copied()
+    }
+
+    /// Node ids from the root down to and including `id`.
+    pub fn path(&self, id: usize) -> Vec<usize> {
+        let mut path: Vec<usize> =
+            std::iter::successors(Some(id), |&node| self.nodes[node].parent).collect();
+        path.reverse();
+        path
+    }
+
+    pub fn moves_to(&self, id: usize) -> Vec<Move> {
+        self.path(id)
+            .into_iter()
+            .filter_map(|node| self.nodes[node].played)
+            .collect()
+    }
+
+    /// The path to the current node followed by the selected continuation; index `n` is the node after move `n`.
+    pub fn line(&self) -> Vec<usize> {
+        let mut line = self.path(self.current);
+        let mut node = self.current;
+        while let Some(next) = self.next(node) {
+            line.push(next);
+            node = next;
+        }
+        line
+    }
+
+    pub fn main_line_end(&self) -> usize {
+        let mut node = Self::ROOT;
+        while let Some(&next) = self.nodes[node].children.first() {
+            node = next;
+        }
+        node
+    }
+
+    /// Adds `played` below `parent` unless that move already exists there, and returns the child.
+    pub fn add_child(&mut self, parent: usize, played: Move) -> usize {
+        if let Some(&existing) = self.nodes[parent]
+            .children
+            .iter()
+            .find(|&&child| self.nodes[child].played == Some(played))
+        {
+            return existing;
+        }
+        let id = self.nodes.len();
+        self.nodes.push(Node {
+            played: Some(played),
+            parent: Some(parent),
+            children: Vec::new(),
+            seconds: None,
+            markup: Vec::new(),
+            comment: String::new(),
+            verdict: None,
+            name: String::new(),
+            bookmark: String::new(),
+            selected: 0,
+        });
+        self.nodes[parent].children.push(id);
+        id
+    }
+
+    pub fn set_seconds(&mut self, id: usize, seconds: f32) {
+        self.nodes[id].seconds = Some(seconds);
+    }
+
+    /// Adds `mark` at `point` of node `id`, replacing any mark there; a mark of the same kind is
+    /// removed instead.
+    pub fn toggle_mark(&mut self, id: usize, point: (usize, usize), mark: Mark) {
+        let markup = &mut self.nodes[id].markup;
+        let previous = markup
+            .iter()
+            .position(|&(marked, _)| marked == point)
+            .map(|index| markup.remove(index).1);
+        if previous.is_none_or(|previous| !previous.same_kind(&mark)) {
+            markup.push((point, mark));
+        }
+    }
+
+    pub fn clear_markup(&mut self, id: usize) {
+        self.nodes[id].markup.clear();
+    }
+
+    /// Adds marks read from a record to node `id`, keeping the ones it already has elsewhere.
+    pub fn add_markup(&mut self, id: usize, markup: Vec<((usize, usize), Mark)>) {
+        let existing = &mut self.nodes[id].markup;
+        existing.retain(|(point, _)| markup.iter().all(|(marked, _)| marked != point));
+        existing.extend(markup);
+    }
+
+    pub fn set_verdict(&mut self, id: usize, verdict: Option<Verdict>) {
+        self.nodes[id].verdict = verdict;
+    }
+
+    pub fn set_name(&mut self, id: usize, name: String) {
+        self.nodes[id].name = name;
+    }
+
+    pub fn set_bookmark(&mut self, id: usize, bookmark: String) {
+        self.nodes[id].bookmark = bookmark;
+    }
+
+    /// The bookmarked nodes in the order of the record, each branch after the line it leaves.
+    pub fn bookmarks(&self) -> Vec<usize> {
+        let mut bookmarks = Vec::new();
+        let mut stack = vec![Self::ROOT];
+        while let Some(id) = stack.pop() {
+            if !self.nodes[id].bookmark.is_empty() {
+                bookmarks.push(id);
+            }
+            stack.extend(self.nodes[id].children.iter().rev());
+        }
+        bookmarks
+    }
+
+    pub fn set_comment(&mut self, id: usize, comment: String) {
+        self.nodes[id].comment = comment;
+    }
+
+    /// Appends a comment read from a record, after a blank line if the node already has one.
+    pub fn add_comment(&mut self, id: usize, comment: &str) {
+        let existing = &mut self.nodes[id].comment;
+        if !existing.is_empty() {
+            existing.push_str("\n\n");
+        }
+        existing.push_str(comment);
+    }
+
+    /// Total thinking time recorded anywhere in the tree for Black and for White.
+    pub fn total_seconds(&self) -> (f32, f32) {
+        self.nodes.iter().fold((0.0, 0.0), |(black, white), node| {
+            match (node.played, node.seconds) {
+                (Some((Player::Black, _)), Some(seconds)) => (black + seconds, white),
+                (Some((Player::White, _)), Some(seconds)) => (black, white + seconds),
+                _ => (black, white),
+            }
+        })
+    }
+
+    /// Plays from the current node, creating a variation if the move is new. A new move goes
+    /// ahead of the named branches, so a game goes on in the main line past the alternatives
+    /// shown at its end.
+    pub fn play(&mut self, played: Move) -> usize {
+        let child = self.add_child(self.current, played);
+        let nodes = &self.nodes;
+        let children = &nodes[self.current].children;
+        if let Some(named) = children
+            .iter()
+            .position(|&other| !nodes[other].name.is_empty())
+            && children[named..].ends_with(&[child])
+            && nodes[child].name.is_empty()
+        {
+            let children = &mut self.nodes[self.current].children;
+            children.pop();
+            children.insert(named, child);
+        }
+        self.go_to(child);
+        child
+    }
+
+    /// Makes `id` current and selects its branch at every ancestor, so redo leads back to it.
+    pub fn go_to(&mut self, id: usize) {
+        let path = self.path(id);
+        for pair in path.windows(2) {
+            let (parent, child) = (pair[0], pair[1]);
+            if let Some(index) = self.nodes[parent].children.iter().position(|&c| c == child) {
+                self.nodes[parent].selected = index;
+            }
+        }
+        self.current = id;
+    }
+
+    /// Moves the branch containing `id` to the front at every level, making it the main line.
+    pub fn promote(&mut self, id: usize) {
+        let path = self.path(id);
+        for pair in path.windows(2) {
+            let (parent, child) = (pair[0], pair[1]);
+            let children = &mut self.nodes[parent].children;
+            if let Some(index) = children.iter().position(|&c| c == child) {
+                children.remove(index);
+                children.insert(0, child);
+                self.nodes[parent].selected = 0;
+            }
+        }
+    }
+
+    /// Grid position `(column, row)` of every node for drawing; the column is the move number.
+    pub fn layout(&self) -> Vec<(usize, usize)> {
+        let mut positions = vec![(0, 0); self.nodes.len()];
+        let mut rows = 0;
+        // First children continue their parent's row, every other branch opens a new one
+        let mut stack = vec![(Self::ROOT, 0, Some(0))];
+        while let Some((id, column, row)) = stack.pop() {
+            let row = row.unwrap_or_else(|| {
+                rows += 1;
+                rows
+            });
+            positions[id] = (column, row);
+            for (index, &child) in self.nodes[id].children.iter().enumerate().rev() {
+                stack.push((child, column + 1, (index == 0).then_some(row)));
+            }
+        }
+        positions
+    }
+
+    pub fn siblings(&self, id: usize) -> &[usize] {
+        match self.nodes[id].parent {
+            Some(parent) => &self.nodes[parent].children,
+            None => &[],
+        }
+    }
+}
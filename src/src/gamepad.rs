@@ -0,0 +1,68 @@
+use eframe::egui;
+
+/// What a controller button asks the board to do.
+#[derive(Clone, Copy, PartialEq, Debug)]
+#[cfg_attr(not(feature = "gamepad"), allow(dead_code))]
+pub enum Action {
+    Up,
+    Down,
+    Left,
+    Right,
+    Place,
+    Pass,
+    Back,
+    Forward,
+}
+
+/// Game controllers, opened on the first poll. Does nothing when built without the `gamepad`
+/// feature or when the platform has no controller support.
+#[derive(Default)]
+pub struct Gamepads {
+    #[cfg(feature = "gamepad")]
+    gilrs: Option<gilrs::Gilrs>,
+    #[cfg(feature = "gamepad")]
+    opened: bool,
+}
+
+impl Gamepads {
+    /// The button presses since the last poll. Keeps the UI polling while a controller is connected,
+    /// since its events do not wake the window.
+    #[cfg(feature = "gamepad")]
+    pub fn poll(&mut self, ctx: &egui::Context) -> Vec<Action> {
+        use gilrs::{Button, EventType};
+
+        if !self.opened {
+            self.opened = true;
+            self.gilrs = gilrs::Gilrs::new().ok();
+        }
+        let Some(gilrs) = &mut self.gilrs else {
+            return Vec::new();
+        };
+        let mut actions = Vec::new();
+        while let Some(event) = gilrs.next_event() {
+            let EventType::ButtonPressed(button, _) = event.event else {
+                continue;
+            };
+            actions.extend(match button {
+                Button::DPadUp => Some(Action::Up),
+                Button::DPadDown => Some(Action::Down),
+                Button::DPadLeft => Some(Action::Left),
+                Button::DPadRight => Some(Action::Right),
+                Button::South => Some(Action::Place),
+                Button::East => Some(Action::Pass),
+                Button::LeftTrigger => Some(Action::Back),
+                Button::RightTrigger => Some(Action::Forward),
+                _ => None,
+            });
+        }
+        if gilrs.gamepads().next().is_some() {
+            ctx.request_repaint_after(std::time::Duration::from_millis(50));
+        }
+        actions
+    }
+
+    #[cfg(not(feature = "gamepad"))]
+    pub fn poll(&mut self, _ctx: &egui::Context) -> Vec<Action> {
+        Vec::new()
+    }
+}
@@ -0,0 +1,96 @@
+//! The lines and star points of the board, which change only when the board is resized, moved or
+//! given another theme. They are made into a mesh once and drawn from it again every frame, so a
+//! frame with only a stone or a clock changed does not lay out hundreds of lines anew.
+
+use std::sync::Arc;
+
+use eframe::egui;
+
+// What the mesh was made for, so it is made again once any of it changes
+#[derive(PartialEq)]
+struct Key {
+    top_left: egui::Pos2,
+    cell: f32,
+    size: usize,
+    stars: Vec<egui::Pos2>,
+    color: egui::Color32,
+    pixels_per_point: f32,
+}
+
+#[derive(Default)]
+pub struct Grid {
+    made: Option<(Key, Arc<egui::Mesh>)>,
+}
+
+impl Grid {
+    /// Draws `size` lines each way `cell` apart from `top_left`, with a dot at each of `stars`.
+    pub fn paint(
+        &mut self,
+        painter: &egui::Painter,
+        top_left: egui::Pos2,
+        cell: f32,
+        size: usize,
+        stars: Vec<egui::Pos2>,
+        color: egui::Color32,
+    ) {
+        let ctx = painter.ctx();
+        let key = Key {
+            top_left,
+            cell,
+            size,
+            stars,
+            color,
+            pixels_per_point: ctx.pixels_per_point(),
+        };
+        let mesh = match &self.made {
+            Some((made, mesh)) if *made == key => mesh.clone(),
+            _ => {
+                let mesh = Arc::new(tessellate(ctx, &key));
+                self.made = Some((key, mesh.clone()));
+                mesh
+            }
+        };
+        painter.add(egui::Shape::Mesh(mesh));
+    }
+}
+
+fn tessellate(ctx: &egui::Context, key: &Key) -> egui::Mesh {
+    let stroke = egui::Stroke::new(1.0, key.color);
+    let far = (key.size - 1) as f32 * key.cell;
+    let mut shapes = Vec::new();
+    for i in 0..key.size {
+        let offset = i as f32 * key.cell;
+        shapes.push(egui::Shape::line_segment(
+            [
+                key.top_left + egui::vec2(0.0, offset),
+                key.top_left + egui::vec2(far, offset),
+            ],
+            stroke,
+        ));
+        shapes.push(egui::Shape::line_segment(
+            [
+                key.top_left + egui::vec2(offset, 0.0),
+                key.top_left + egui::vec2(offset, far),
+            ],
+            stroke,
+        ));
+    }
+    for &pos in &key.stars {
+        shapes.push(egui::Shape::circle_filled(pos, 3.0, key.color));
+    }
+    // The same options and discs as the frame's own shapes get, so the grid looks no different
+    let (font_size, discs) = ctx.fonts(|fonts| {
+        (
+            fonts.font_image_size(),
+            fonts.texture_atlas().lock().prepared_discs(),
+        )
+    });
+    let options = ctx.tessellation_options(|options| *options);
+    let mut tessellator =
+        egui::epaint::Tessellator::new(key.pixels_per_point, options, font_size, discs);
+    let mut mesh = egui::Mesh::default();
+    for shape in shapes {
+        tessellator.tessellate_shape(shape, &mut mesh);
+    }
+    mesh
+}
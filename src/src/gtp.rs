@@ -0,0 +1,193 @@
+use std::fmt;
+use std::io::{BufRead, BufReader, Write};
+use std::process::{Child, ChildStdin, ChildStdout, Command, Stdio};
+use std::sync::mpsc::{self, Receiver};
+
+use crate::board::{Move, Player};
+use crate::clock::{Clock, Overtime};
+use crate::consts;
+use crate::notation;
+
+#[derive(Debug)]
+pub enum Error {
+    Io(std::io::Error),
+    Engine(String),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Error::Io(err) => write!(f, "engine I/O failed: {}", err),
+            Error::Engine(message) => write!(f, "engine error: {}", message),
+        }
+    }
+}
+
+impl From<std::io::Error> for Error {
+    fn from(err: std::io::Error) -> Self {
+        Error::Io(err)
+    }
+}
+
+/// An external engine speaking the Go Text Protocol over stdin/stdout.
+pub struct Engine {
+    child: Child,
+    stdin: ChildStdin,
+    stdout: BufReader<ChildStdout>,
+    // Lines the engine printed on stderr, where many engines explain their moves
+    notes: Receiver<String>,
+}
+
+impl Drop for Engine {
+    fn drop(&mut self) {
+        let _ = writeln!(self.stdin, "quit");
+        let _ = self.child.wait();
+    }
+}
+
+pub fn color(player: Player) -> &'static str {
+    match player {
+        Player::Black => "B",
+        Player::White => "W",
+    }
+}
+
+impl Engine {
+    pub fn start(command_line: &str) -> Result<Self, Error> {
+        let mut parts = command_line.split_whitespace();
+        let program = parts
+            .next()
+            .ok_or_else(|| Error::Engine("no engine command given".to_string()))?;
+        let mut child = Command::new(program)
+            .args(parts)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()?;
+        let stdin = child.stdin.take().expect("stdin is piped");
+        let stdout = BufReader::new(child.stdout.take().expect("stdout is piped"));
+        let stderr = BufReader::new(child.stderr.take().expect("stderr is piped"));
+        let (sender, notes) = mpsc::channel();
+        // Read to the end even with nobody listening, so the engine never blocks on a full pipe
+        std::thread::spawn(move || {
+            for line in stderr.lines().map_while(Result::ok) {
+                tracing::debug!(target: "gtp", "stderr: {}", line);
+                let _ = sender.send(line);
+            }
+        });
+        Ok(Self {
+            child,
+            stdin,
+            stdout,
+            notes,
+        })
+    }
+
+    /// What the engine printed on stderr since the last call, up to `consts::ENGINE_NOTES` lines.
+    pub fn notes(&self) -> Vec<String> {
+        let mut notes: Vec<String> = self.notes.try_iter().collect();
+        let extra = notes.len().saturating_sub(consts::ENGINE_NOTES);
+        notes.drain(..extra);
+        notes
+    }
+
+    /// Sends one command and returns the response text without the leading `=`.
+    pub fn send(&mut self, command: &str) -> Result<String, Error> {
+        tracing::debug!(target: "gtp", "sent {}", command);
+        writeln!(self.stdin, "{}", command)?;
+        self.stdin.flush()?;
+        let mut response = String::new();
+        loop {
+            let mut line = String::new();
+            if self.stdout.read_line(&mut line)? == 0 {
+                return Err(Error::Engine("engine exited".to_string()));
+            }
+            // A response ends with an empty line
+            if line.trim().is_empty() && !response.is_empty() {
+                break;
+            }
+            response.push_str(&line);
+        }
+        let response = response.trim();
+        tracing::debug!(target: "gtp", "received {}", response);
+        match response.strip_prefix('=') {
+            Some(rest) => Ok(rest.trim().to_string()),
+            None => Err(Error::Engine(
+                response.trim_start_matches('?').trim().to_string(),
+            )),
+        }
+    }
+
+    pub fn play(&mut self, (player, point): Move, board_size: usize) -> Result<(), Error> {
+        let vertex = point.map_or("pass".to_string(), |point| {
+            notation::format_point(point, board_size)
+        });
+        self.send(&format!("play {} {}", color(player), vertex))?;
+        Ok(())
+    }
+
+    /// Sets the time controls; byo-yomi needs the KGS extension of the protocol, while the
+    /// standard command is Canadian overtime. The protocol has no increments, so a Fischer
+    /// clock is sent as sudden death and the engine learns of each increment from `time_left`,
+    /// while time per move is Canadian overtime of one stone.
+    pub fn time_settings(&mut self, main_time: f32, overtime: Overtime) -> Result<(), Error> {
+        let command = match overtime {
+            Overtime::None | Overtime::Fischer { .. } => {
+                format!("time_settings {} 0 0", main_time as u32)
+            }
+            Overtime::ByoYomi { periods, seconds } => format!(
+                "kgs-time_settings byoyomi {} {} {}",
+                main_time as u32, seconds as u32, periods
+            ),
+            Overtime::Canadian { stones, seconds } => format!(
+                "time_settings {} {} {}",
+                main_time as u32, seconds as u32, stones
+            ),
+            Overtime::PerMove { seconds, .. } => format!("time_settings 0 {} 1", seconds as u32),
+        };
+        self.send(&command)?;
+        Ok(())
+    }
+
+    /// Reports the time `player` has left, with the periods or stones left once in overtime.
+    pub fn time_left(&mut self, player: Player, clock: &Clock) -> Result<(), Error> {
+        self.send(&format!(
+            "time_left {} {} {}",
+            color(player),
+            clock.left().max(0.0) as u32,
+            clock.count().unwrap_or(0)
+        ))?;
+        Ok(())
+    }
+
+    /// The engine's name and version, as it reports them.
+    pub fn identity(&mut self) -> String {
+        let name = self.send("name").unwrap_or_default();
+        let version = self.send("version").unwrap_or_default();
+        format!("{} {}", name, version)
+    }
+
+    /// Black's lead as the engine scores the position; engines add words after the result.
+    pub fn final_score(&mut self) -> Result<f32, Error> {
+        let reply = self.send("final_score")?;
+        let result = reply.split_whitespace().next().unwrap_or_default();
+        let lead = match result.split_once('+') {
+            None if result == "0" => Some(0.0),
+            Some(("B", points)) => points.parse().ok(),
+            Some(("W", points)) => points.parse::<f32>().ok().map(|points| -points),
+            _ => None,
+        };
+        lead.ok_or_else(|| Error::Engine(format!("unexpected score \"{}\"", reply)))
+    }
+
+    /// Asks for a move; `Ok(None)` means the engine resigned.
+    pub fn genmove(&mut self, player: Player, board_size: usize) -> Result<Option<Move>, Error> {
+        let reply = self.send(&format!("genmove {}", color(player)))?;
+        if reply.eq_ignore_ascii_case("resign") {
+            return Ok(None);
+        }
+        notation::parse_point(&reply, board_size)
+            .map(|point| Some((player, point)))
+            .ok_or_else(|| Error::Engine(format!("unexpected move \"{}\"", reply)))
+    }
+}
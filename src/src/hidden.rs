@@ -0,0 +1,83 @@
+//! Hidden-move Go: before the first move each player secretly places stones, which play their
+//! part on the board from the start but stay off the display until they matter. A stone comes
+//! to light when the other player tries to play on it, or when a capture takes it or is made
+//! with it.
+
+use crate::board::{Board, Player};
+
+pub struct Hidden {
+    per_player: u32,
+    // Whose stones are being placed, until both players have placed theirs
+    placing: Option<Player>,
+    placed: u32,
+    stones: Vec<(Player, (usize, usize))>,
+}
+
+impl Hidden {
+    pub fn new(per_player: u32) -> Self {
+        Self {
+            per_player,
+            placing: Some(Player::Black),
+            placed: 0,
+            stones: Vec::new(),
+        }
+    }
+
+    /// The player to place a hidden stone, and how many they have left to place.
+    pub fn placing(&self) -> Option<(Player, u32)> {
+        self.placing
+            .map(|player| (player, self.per_player - self.placed))
+    }
+
+    pub fn place(&mut self, point: (usize, usize)) {
+        let Some(player) = self.placing else {
+            return;
+        };
+        self.stones.push((player, point));
+        self.placed += 1;
+        if self.placed == self.per_player {
+            self.placed = 0;
+            self.placing = match player {
+                Player::Black => Some(Player::White),
+                Player::White => None,
+            };
+        }
+    }
+
+    pub fn is_hidden(&self, point: (usize, usize)) -> bool {
+        self.stones.iter().any(|&(_, hidden)| hidden == point)
+    }
+
+    /// Brings the stone at `point` to light, and says whose it was.
+    pub fn reveal(&mut self, point: (usize, usize)) -> Option<Player> {
+        let index = self
+            .stones
+            .iter()
+            .position(|&(_, hidden)| hidden == point)?;
+        Some(self.stones.remove(index).0)
+    }
+
+    /// Brings to light the stones among `captured` or next to them, and returns them.
+    pub fn reveal_around(
+        &mut self,
+        board: &Board,
+        captured: &[(usize, usize)],
+    ) -> Vec<(usize, usize)> {
+        let involved: Vec<(usize, usize)> = self
+            .stones
+            .iter()
+            .map(|&(_, point)| point)
+            .filter(|&point| {
+                captured.contains(&point)
+                    || board
+                        .get_neighbors(point.0, point.1)
+                        .iter()
+                        .any(|neighbor| captured.contains(neighbor))
+            })
+            .collect();
+        for &point in &involved {
+            self.reveal(point);
+        }
+        involved
+    }
+}
@@ -0,0 +1,117 @@
+use std::collections::HashMap;
+use std::fmt::Display;
+use std::sync::RwLock;
+
+use crate::paths;
+
+/// English is built in: the source strings are the English text.
+pub const ENGLISH: &str = "en";
+
+// The language in use and its messages keyed by the English text
+struct Catalog {
+    language: String,
+    messages: HashMap<String, String>,
+    decimal_separator: char,
+}
+
+static CATALOG: RwLock<Option<Catalog>> = RwLock::new(None);
+
+/// Reads a catalog file: `English text = Translation` per line, `#` starts a comment and
+/// `@decimal ,` sets the decimal separator.
+fn parse(text: &str) -> (HashMap<String, String>, char) {
+    let mut messages = HashMap::new();
+    let mut decimal_separator = '.';
+    for line in text.lines() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        if let Some(separator) = line.strip_prefix("@decimal") {
+            decimal_separator = separator.trim().chars().next().unwrap_or('.');
+        } else if let Some((source, translation)) = line.split_once(" = ")
+            && !translation.trim().is_empty()
+        {
+            messages.insert(
+                source.trim().replace("\\n", "\n"),
+                translation.trim().replace("\\n", "\n"),
+            );
+        }
+    }
+    (messages, decimal_separator)
+}
+
+/// Language codes with a catalog in the locales folder, English first.
+pub fn languages() -> Vec<String> {
+    let mut languages = vec![ENGLISH.to_string()];
+    if let Ok(entries) = std::fs::read_dir(paths::locales_dir()) {
+        let mut found: Vec<String> = entries
+            .filter_map(Result::ok)
+            .filter_map(|entry| {
+                let path = entry.path();
+                (path.extension()? == "txt")
+                    .then(|| path.file_stem()?.to_str().map(str::to_string))?
+            })
+            .filter(|code| code != ENGLISH)
+            .collect();
+        found.sort();
+        languages.extend(found);
+    }
+    languages
+}
+
+/// Switches to `language`, loading its catalog unless it is already in use. Unknown languages fall
+/// back to English.
+pub fn set_language(language: &str) {
+    if CATALOG
+        .read()
+        .is_ok_and(|catalog| catalog.as_ref().is_some_and(|c| c.language == language))
+    {
+        return;
+    }
+    let (messages, decimal_separator) = if language == ENGLISH {
+        (HashMap::new(), '.')
+    } else {
+        let path = paths::locales_dir().join(format!("{}.txt", language));
+        parse(&std::fs::read_to_string(path).unwrap_or_default())
+    };
+    if let Ok(mut catalog) = CATALOG.write() {
+        *catalog = Some(Catalog {
+            language: language.to_string(),
+            messages,
+            decimal_separator,
+        });
+    }
+}
+
+/// The translation of `text`, or `text` itself when there is none.
+pub fn tr(text: &str) -> String {
+    CATALOG
+        .read()
+        .ok()
+        .and_then(|catalog| catalog.as_ref()?.messages.get(text).cloned())
+        .unwrap_or_else(|| text.to_string())
+}
+
+/// Translates `template` and replaces each `{}` in it with the next argument.
+pub fn trf(template: &str, args: &[&dyn Display]) -> String {
+    let translated = tr(template);
+    let mut parts = translated.split("{}");
+    let mut result = parts.next().unwrap_or_default().to_string();
+    for (index, part) in parts.enumerate() {
+        if let Some(arg) = args.get(index) {
+            result += &arg.to_string();
+        }
+        result += part;
+    }
+    result
+}
+
+/// Formats a number such as a komi or score with the language's decimal separator.
+pub fn number(value: f32) -> String {
+    let separator = CATALOG
+        .read()
+        .ok()
+        .and_then(|catalog| Some(catalog.as_ref()?.decimal_separator))
+        .unwrap_or('.');
+    value.to_string().replace('.', &separator.to_string())
+}
@@ -0,0 +1,671 @@
+use std::io::{Read, Write};
+use std::net::TcpStream;
+use std::sync::mpsc;
+use std::time::{Duration, Instant};
+
+use eframe::egui;
+
+use crate::board::{self, Move, Player};
+use crate::lan;
+use crate::notation;
+use crate::protocol::Message;
+use crate::sgf;
+
+const DEFAULT_PORT: u16 = 6969;
+// How long the server may take to answer while logging in and setting up the game
+const ANSWER_TIMEOUT: Duration = Duration::from_secs(15);
+// Reads give up after this, so the connection's thread also gets to send what is queued
+const POLL_INTERVAL: Duration = Duration::from_millis(50);
+// IGS logs out those who stay quiet for an hour
+const KEEPALIVE_INTERVAL: Duration = Duration::from_secs(300);
+// The stones of every byo-yomi period, which IGS plays as Canadian overtime
+const BYOYOMI_STONES: u32 = 25;
+
+/// A match to ask another player for, always in the colour, size and time chosen here.
+pub struct Challenge {
+    pub opponent: String,
+    pub color: Player,
+    pub board_size: usize,
+    pub minutes: u32,
+    pub byoyomi_minutes: u32,
+}
+
+/// What to do once logged in.
+pub enum Goal {
+    Watch(u32),
+    Challenge(Challenge),
+    // The first match request that arrives is accepted
+    Accept,
+}
+
+fn letter(player: Player) -> &'static str {
+    match player {
+        Player::Black => "B",
+        Player::White => "W",
+    }
+}
+
+fn index(player: Player) -> usize {
+    match player {
+        Player::Black => 0,
+        Player::White => 1,
+    }
+}
+
+fn failed(text: impl Into<String>) -> std::io::Error {
+    std::io::Error::other(text.into())
+}
+
+// The telnet session, in lines once logged in
+struct Telnet {
+    stream: TcpStream,
+    pending: Vec<u8>,
+}
+
+impl Telnet {
+    fn send(&mut self, command: &str) -> std::io::Result<()> {
+        write!(self.stream, "{}\r\n", command)?;
+        self.stream.flush()
+    }
+
+    // Reads what has arrived into `pending`, returning false when a read gave up
+    fn fill(&mut self) -> std::io::Result<bool> {
+        let mut buffer = [0; 4096];
+        match self.stream.read(&mut buffer) {
+            Ok(0) => Err(std::io::ErrorKind::UnexpectedEof.into()),
+            Ok(count) => {
+                self.pending.extend_from_slice(&buffer[..count]);
+                Ok(true)
+            }
+            Err(err)
+                if matches!(
+                    err.kind(),
+                    std::io::ErrorKind::WouldBlock | std::io::ErrorKind::TimedOut
+                ) =>
+            {
+                Ok(false)
+            }
+            Err(err) => Err(err),
+        }
+    }
+
+    // The next whole line, or none when nothing more has arrived for now
+    fn line(&mut self) -> std::io::Result<Option<String>> {
+        loop {
+            if let Some(end) = self.pending.iter().position(|&byte| byte == b'\n') {
+                let line: Vec<u8> = self.pending.drain(..=end).collect();
+                return Ok(Some(String::from_utf8_lossy(&line).trim().to_string()));
+            }
+            if !self.fill()? {
+                return Ok(None);
+            }
+        }
+    }
+
+    // Waits for the first of `words`, such as the `Login:` prompt that ends no line, and drops
+    // everything up to it
+    fn wait_for(&mut self, words: &[&str]) -> std::io::Result<usize> {
+        let started = Instant::now();
+        loop {
+            let text = String::from_utf8_lossy(&self.pending).into_owned();
+            if let Some((found, at)) = words
+                .iter()
+                .enumerate()
+                .filter_map(|(found, word)| Some((found, text.find(word)? + word.len())))
+                .min_by_key(|&(_, at)| at)
+            {
+                self.pending.drain(..at.min(self.pending.len()));
+                return Ok(found);
+            }
+            if started.elapsed() > ANSWER_TIMEOUT {
+                return Err(failed("IGS did not answer"));
+            }
+            self.fill()?;
+        }
+    }
+
+    // The lines up to the prompt that ends the answer to a command, which has a line that is
+    // `wanted`; prompts can also come on their own before it
+    fn answer(&mut self, wanted: impl Fn(&str) -> bool) -> std::io::Result<Vec<String>> {
+        let started = Instant::now();
+        let mut lines: Vec<String> = Vec::new();
+        loop {
+            match self.line()? {
+                Some(line) if line.starts_with("1 ") && lines.iter().any(|line| wanted(line)) => {
+                    return Ok(lines);
+                }
+                Some(line) if line.starts_with("1 ") => {}
+                Some(line) => lines.push(line),
+                None if started.elapsed() > ANSWER_TIMEOUT => {
+                    return Err(failed("IGS did not answer"));
+                }
+                None => {}
+            }
+        }
+    }
+}
+
+// An entry of the `games` list: `7 [45]  bob [ 3k*] vs.  alice [ 2k*] (  0   19  0  6.5 10  I) ( 0)`
+struct GameEntry {
+    white: (String, String),
+    black: (String, String),
+    board_size: usize,
+    handicap: u32,
+    komi: f32,
+    byoyomi_minutes: u32,
+}
+
+fn parse_game_entry(line: &str, game: u32) -> Option<GameEntry> {
+    let (number, rest) = line.strip_prefix("7 [")?.split_once(']')?;
+    if number.trim().parse() != Ok(game) {
+        return None;
+    }
+    let player = |text: &str| -> Option<(String, String)> {
+        let (name, rank) = text.split_once('[')?;
+        let rank = rank.split_once(']')?.0.trim().trim_end_matches('*');
+        Some((name.trim().to_string(), rank.to_string()))
+    };
+    let (white, rest) = rest.split_once(" vs. ")?;
+    let (black, terms) = rest.split_once('(')?;
+    let terms: Vec<&str> = terms.split_whitespace().collect();
+    Some(GameEntry {
+        white: player(white)?,
+        black: player(black)?,
+        board_size: terms.get(1)?.parse().ok()?,
+        handicap: terms.get(2)?.parse().ok()?,
+        komi: terms.get(3)?.parse().ok()?,
+        byoyomi_minutes: terms.get(4)?.parse().ok()?,
+    })
+}
+
+// The game number in `Creating match [45] with bob.`
+fn created_game(line: &str) -> Option<u32> {
+    let (_, rest) = line.split_once("Creating match [")?;
+    rest.split_once(']')?.0.trim().parse().ok()
+}
+
+/// Logs in to an IGS server such as Pandanet, finds the game `goal` asks for, and opens it as a
+/// network game: the record so far comes first, and then the moves, chat, counting and result.
+/// Waiting for a match may take as long as the other player does.
+pub fn play(
+    server: &str,
+    name: &str,
+    password: &str,
+    goal: Goal,
+    ctx: egui::Context,
+) -> std::io::Result<lan::Connection> {
+    let server = server.trim();
+    let address = if server.contains(':') {
+        server.to_string()
+    } else {
+        format!("{}:{}", server, DEFAULT_PORT)
+    };
+    let stream = TcpStream::connect(address)?;
+    stream.set_read_timeout(Some(POLL_INTERVAL))?;
+    let mut telnet = Telnet {
+        stream,
+        pending: Vec::new(),
+    };
+    let name = if name.trim().is_empty() {
+        "guest"
+    } else {
+        name.trim()
+    };
+    telnet.wait_for(&["Login:"])?;
+    telnet.send(name)?;
+    // A client mode kept from an earlier session prompts with `1 5` instead of `#>`
+    if telnet.wait_for(&["Password:", "#>", "\n1 "])? == 0 {
+        telnet.send(password)?;
+        if telnet.wait_for(&["Invalid password", "#>", "\n1 "])? == 0 {
+            return Err(failed("wrong IGS name or password"));
+        }
+    }
+    telnet.send("toggle client true")?;
+    telnet.answer(|line| line.starts_with("9 ") || line.is_empty())?;
+
+    let game = match goal {
+        Goal::Watch(game) => game,
+        Goal::Challenge(challenge) => {
+            telnet.send(&format!(
+                "match {} {} {} {} {}",
+                challenge.opponent,
+                letter(challenge.color),
+                challenge.board_size,
+                challenge.minutes,
+                challenge.byoyomi_minutes
+            ))?;
+            wait_for_match(&mut telnet, false)?
+        }
+        Goal::Accept => wait_for_match(&mut telnet, true)?,
+    };
+
+    telnet.send(&format!("games {}", game))?;
+    let entry = telnet
+        .answer(|line| parse_game_entry(line, game).is_some())?
+        .iter()
+        .find_map(|line| parse_game_entry(line, game))
+        .ok_or_else(|| failed(format!("IGS has no game {}", game)))?;
+    let color = if entry.black.0.eq_ignore_ascii_case(name) {
+        Some(Player::Black)
+    } else if entry.white.0.eq_ignore_ascii_case(name) {
+        Some(Player::White)
+    } else {
+        None
+    };
+    // Observing sends the moves so far, and the new ones after them
+    telnet.send(&if color.is_some() {
+        format!("moves {}", game)
+    } else {
+        format!("observe {}", game)
+    })?;
+    let record = telnet.answer(|line| parse_header(line, game).is_some())?;
+
+    let mut live = Live {
+        game,
+        board_size: entry.board_size,
+        color,
+        following: false,
+        next: 0,
+        times: None,
+        heard: None,
+        byoyomi_seconds: entry.byoyomi_minutes as f32 * 60.0,
+        removed: Vec::new(),
+        kibitzer: None,
+        finished: false,
+    };
+    let sgf = sgf::to_string(&record_root(game, &entry, &live, &record));
+    let mut opening = vec![match color {
+        Some(color) => Message::Resume { color, sgf },
+        None => Message::Watch { sgf },
+    }];
+    for line in &record {
+        opening.extend(live.incoming(line));
+    }
+    opening.push(Message::Thinking(0.0));
+
+    let (outgoing, queued) = mpsc::channel::<Message>();
+    let (sender, incoming) = mpsc::channel();
+    std::thread::spawn(move || {
+        for message in opening {
+            if sender.send(message).is_err() {
+                return;
+            }
+        }
+        ctx.request_repaint();
+        let mut kept_alive = Instant::now();
+        'connected: loop {
+            loop {
+                match queued.try_recv() {
+                    Ok(message) => {
+                        let mut replies = Vec::new();
+                        for command in live.outgoing(message, &mut replies) {
+                            if telnet.send(&command).is_err() {
+                                break 'connected;
+                            }
+                        }
+                        for reply in replies {
+                            let _ = sender.send(reply);
+                            ctx.request_repaint();
+                        }
+                    }
+                    Err(mpsc::TryRecvError::Empty) => break,
+                    // The game was closed here; IGS adjourns a game its player leaves
+                    Err(mpsc::TryRecvError::Disconnected) => {
+                        if live.color.is_none() {
+                            let _ = telnet.send(&format!("unobserve {}", live.game));
+                        }
+                        let _ = telnet.send("quit");
+                        return;
+                    }
+                }
+            }
+            if kept_alive.elapsed() >= KEEPALIVE_INTERVAL {
+                kept_alive = Instant::now();
+                if telnet.send("ayt").is_err() {
+                    break;
+                }
+            }
+            loop {
+                match telnet.line() {
+                    Ok(Some(line)) => {
+                        for message in live.incoming(&line) {
+                            if sender.send(message).is_err() {
+                                return;
+                            }
+                            ctx.request_repaint();
+                        }
+                    }
+                    Ok(None) => break,
+                    Err(_) => break 'connected,
+                }
+            }
+        }
+        let _ = sender.send(Message::Bye);
+        ctx.request_repaint();
+    });
+    Ok(lan::Connection::relayed(outgoing, incoming))
+}
+
+// Waits until the server creates the game asked for, or with `accept` until another player asks
+// for one, which is then agreed to on their terms
+fn wait_for_match(telnet: &mut Telnet, accept: bool) -> std::io::Result<u32> {
+    loop {
+        let Some(line) = telnet.line()? else {
+            continue;
+        };
+        if let Some(game) = created_game(&line) {
+            return Ok(game);
+        }
+        if accept
+            && line.starts_with("9 Use <match")
+            && let Some((_, rest)) = line.split_once('<')
+            && let Some((command, _)) = rest.split_once('>')
+        {
+            telnet.send(command)?;
+        } else if !accept && (line.starts_with("5 ") || line.contains("declines")) {
+            return Err(failed(
+                line.trim_start_matches(|c: char| c.is_ascii_digit()).trim(),
+            ));
+        }
+    }
+}
+
+// The record to load before the moves: the players, komi, handicap and clock of the game
+fn record_root(game: u32, entry: &GameEntry, live: &Live, record: &[String]) -> sgf::Node {
+    let mut root = sgf::Node::default();
+    root.set("GM", vec!["1".to_string()]);
+    root.set("FF", vec!["4".to_string()]);
+    root.set("CA", vec!["UTF-8".to_string()]);
+    root.set("SZ", vec![entry.board_size.to_string()]);
+    root.set("KM", vec![entry.komi.to_string()]);
+    root.set("RU", vec!["Japanese".to_string()]);
+    root.set("PC", vec![format!("IGS game {}", game)]);
+    for (ident, value) in [
+        ("PB", &entry.black.0),
+        ("BR", &entry.black.1),
+        ("PW", &entry.white.0),
+        ("WR", &entry.white.1),
+    ] {
+        if !value.is_empty() && value != "NR" {
+            root.set(ident, vec![value.clone()]);
+        }
+    }
+    // The clocks as the last header of the record shows them, the fuller one for both
+    let main_time = record
+        .iter()
+        .rev()
+        .find_map(|line| parse_header(line, game))
+        .map_or(0.0, |times| {
+            times
+                .iter()
+                .filter(|&&(_, stones)| stones < 0)
+                .map(|&(left, _)| left)
+                .fold(0.0, f32::max)
+        });
+    if main_time > 0.0 {
+        root.set("TM", vec![main_time.to_string()]);
+    }
+    if live.byoyomi_seconds > 0.0 {
+        root.set(
+            "OT",
+            vec![format!(
+                "{}/{} canadian",
+                BYOYOMI_STONES, live.byoyomi_seconds
+            )],
+        );
+    }
+    // IGS places the handicap on the usual points as move 0
+    if entry.handicap > 1 {
+        root.set("HA", vec![entry.handicap.to_string()]);
+        root.set(
+            "AB",
+            board::handicap_points(entry.board_size, entry.handicap)
+                .into_iter()
+                .map(sgf::format_point)
+                .collect(),
+        );
+        root.set("PL", vec!["W".to_string()]);
+    }
+    root
+}
+
+// The time left and the byo-yomi stones still to play, or -1 in main time, for Black and White
+// from a header such as `15 Game 45 I: bob (0 596 -1) vs alice (0 600 -1)`, where White comes
+// first
+fn parse_header(line: &str, game: u32) -> Option<[(f32, i32); 2]> {
+    let (number, rest) = line.strip_prefix("15 Game ")?.split_once(' ')?;
+    if number.parse() != Ok(game) {
+        return None;
+    }
+    let (_, players) = rest.split_once(": ")?;
+    let (white, black) = players.split_once(" vs ")?;
+    let times = |text: &str| -> Option<(f32, i32)> {
+        let (_, terms) = text.split_once('(')?;
+        let terms: Vec<&str> = terms.trim_end_matches(')').split_whitespace().collect();
+        Some((terms.get(1)?.parse().ok()?, terms.get(2)?.parse().ok()?))
+    };
+    Some([times(black)?, times(white)?])
+}
+
+// A move line such as `15  12(W): Q16` or `15   0(B): Handicap 3`, with the captures after it
+fn parse_move(line: &str) -> Option<(usize, Player, &str)> {
+    let rest = line.strip_prefix("15 ")?.trim_start();
+    let (number, rest) = rest.split_once('(')?;
+    let (color, rest) = rest.split_once("): ")?;
+    let player = match color {
+        "B" => Player::Black,
+        "W" => Player::White,
+        _ => return None,
+    };
+    Some((number.trim().parse().ok()?, player, rest.trim()))
+}
+
+// How a finished game is announced, such as `{Game 45: bob vs alice : Black resigns.}`
+fn parse_end(line: &str, game: u32) -> Option<Option<String>> {
+    let (_, rest) = line.split_once("{Game ")?;
+    let (number, rest) = rest.split_once(':')?;
+    if number.trim().parse() != Ok(game) {
+        return None;
+    }
+    let how = rest.rsplit_once(" : ")?.1.trim_end_matches('}').trim();
+    let words: Vec<&str> = how.split_whitespace().collect();
+    if let ["W", white, "B", black] = &words[..] {
+        return Some(Some(score_result(white.parse().ok()?, black.parse().ok()?)));
+    }
+    let loser = match words.first() {
+        Some(&"Black") => Player::Black,
+        Some(&"White") => Player::White,
+        _ => return Some(None),
+    };
+    // Adjourned, or anything else without a result, has none
+    Some(if how.contains("resigns") {
+        Some(format!("{}+R", letter(loser.other())))
+    } else if how.contains("time") {
+        Some(format!("{}+T", letter(loser.other())))
+    } else {
+        None
+    })
+}
+
+fn score_result(white: f32, black: f32) -> String {
+    let margin = black - white;
+    if margin > 0.0 {
+        format!("B+{}", margin)
+    } else if margin < 0.0 {
+        format!("W+{}", -margin)
+    } else {
+        "0".to_string()
+    }
+}
+
+// A count such as `20 bob (W:O): 65.5 to alice (B:#): 70.0`
+fn parse_score(line: &str) -> Option<String> {
+    let (first, second) = line.strip_prefix("20 ")?.split_once(" to ")?;
+    let score = |text: &str| -> Option<(bool, f32)> {
+        let (_, rest) = text.split_once('(')?;
+        Some((
+            rest.starts_with('W'),
+            rest.rsplit_once(": ")?.1.trim().parse().ok()?,
+        ))
+    };
+    match (score(first)?, score(second)?) {
+        ((true, white), (false, black)) | ((false, black), (true, white)) => {
+            Some(score_result(white, black))
+        }
+        _ => None,
+    }
+}
+
+// What goes on between this app and the connection's one IGS game, translated both ways
+struct Live {
+    game: u32,
+    board_size: usize,
+    // The color played here, none when watching
+    color: Option<Player>,
+    // Whether the last header was this game's, which the move lines after it belong to
+    following: bool,
+    // The number of the next move; a handicap is move 0
+    next: usize,
+    // The clocks before the last move and as last heard, for the time the move took
+    times: Option<[(f32, i32); 2]>,
+    heard: Option<[(f32, i32); 2]>,
+    byoyomi_seconds: f32,
+    removed: Vec<(usize, usize)>,
+    // Who sent the kibitz whose text comes on the next line
+    kibitzer: Option<String>,
+    finished: bool,
+}
+
+impl Live {
+    // Seconds `player` spent on the move just heard, from its clock before and after
+    fn spent(&self, player: Player) -> f32 {
+        let (Some(before), Some(after)) = (self.times, self.heard) else {
+            return 0.0;
+        };
+        let ((left, stones), (now_left, now_stones)) =
+            (before[index(player)], after[index(player)]);
+        let spent = match (stones < 0, now_stones < 0) {
+            (true, true) => left - now_left,
+            (true, false) => left + self.byoyomi_seconds - now_left,
+            (false, false) if now_stones < stones => left - now_left,
+            _ => 0.0,
+        };
+        spent.max(0.0)
+    }
+
+    fn incoming(&mut self, line: &str) -> Vec<Message> {
+        if line.starts_with("15 Game ") {
+            self.heard = parse_header(line, self.game);
+            self.following = self.heard.is_some();
+            if self.times.is_none() {
+                self.times = self.heard;
+            }
+            return Vec::new();
+        }
+        if let Some((number, player, text)) = parse_move(line) {
+            // Moves sent from here come back too
+            if !self.following || number < self.next {
+                return Vec::new();
+            }
+            self.next = number + 1;
+            let seconds = self.spent(player);
+            self.times = self.heard;
+            if text.starts_with("Handicap") {
+                return Vec::new();
+            }
+            let word = text.split_whitespace().next().unwrap_or_default();
+            let Some(point) = notation::parse_point(word, self.board_size) else {
+                return Vec::new();
+            };
+            let played: Move = (player, point);
+            return vec![Message::Play { played, seconds }];
+        }
+        if let Some(result) = parse_end(line, self.game) {
+            return match result {
+                Some(result) if !std::mem::replace(&mut self.finished, true) => {
+                    vec![Message::Result(result)]
+                }
+                Some(_) => Vec::new(),
+                None => vec![Message::Bye],
+            };
+        }
+        if self.color.is_some()
+            && let Some(result) = parse_score(line)
+        {
+            self.finished = true;
+            return vec![Message::Result(result)];
+        }
+        // Counting starts after both passed, and each stone removed takes its group with it
+        if line.starts_with("9 ") && line.contains("type 'done' when finished") {
+            return vec![Message::Dead(self.removed.clone())];
+        }
+        if let Some(rest) = line.strip_prefix(&format!("49 Game {} ", self.game)) {
+            if let Some((_, point)) = rest.rsplit_once("@ ")
+                && let Some(Some(point)) = notation::parse_point(point, self.board_size)
+            {
+                self.removed.push(point);
+                return vec![Message::Dead(self.removed.clone())];
+            }
+            return Vec::new();
+        }
+        if let Some(rest) = line.strip_prefix("19 *")
+            && let Some(color) = self.color
+            && let Some((_, text)) = rest.split_once("*: ")
+        {
+            return vec![Message::Chat {
+                from: Some(color.other()),
+                text: text.to_string(),
+            }];
+        }
+        if let Some(rest) = line.strip_prefix("11 ") {
+            if let Some(header) = rest.strip_prefix("Kibitz ") {
+                self.kibitzer = header.split_whitespace().next().map(str::to_string);
+                return Vec::new();
+            }
+            if let Some(name) = self.kibitzer.take() {
+                return vec![Message::Chat {
+                    from: None,
+                    text: format!("{}: {}", name, rest.trim()),
+                }];
+            }
+        }
+        Vec::new()
+    }
+
+    // A message of this app as commands for the server, with any answer it gets at once in
+    // `replies`
+    fn outgoing(&mut self, message: Message, replies: &mut Vec<Message>) -> Vec<String> {
+        match message {
+            Message::Play {
+                played: (_, point), ..
+            } => {
+                self.next += 1;
+                vec![point.map_or_else(
+                    || "pass".to_string(),
+                    |point| notation::format_point(point, self.board_size),
+                )]
+            }
+            Message::Resign(_) => vec!["resign".to_string()],
+            Message::Chat { text, .. } => vec![match self.color {
+                Some(_) => format!("say {}", text),
+                None => format!("kibitz {} {}", self.game, text),
+            }],
+            // IGS games take back nothing once agreed
+            Message::UndoRequest(_) => {
+                replies.push(Message::UndoDecline);
+                Vec::new()
+            }
+            // Removing a stone takes its group, so only the first of a group does anything
+            Message::Dead(stones) => {
+                let mut commands: Vec<String> = stones
+                    .iter()
+                    .filter(|point| !self.removed.contains(point))
+                    .map(|&point| notation::format_point(point, self.board_size))
+                    .collect();
+                commands.push("done".to_string());
+                commands
+            }
+            _ => Vec::new(),
+        }
+    }
+}
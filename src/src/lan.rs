@@ -0,0 +1,638 @@
+use std::io::{Read, Write};
+use std::net::{Shutdown, TcpListener, TcpStream};
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::time::Duration;
+
+use eframe::egui;
+
+use crate::protocol::{self, Message};
+#[cfg(not(target_arch = "wasm32"))]
+use crate::protocol::{
+    Credentials, NewRoom, OngoingGame, Profile, RoomCreated, RoomInfo, Seek, Session,
+};
+
+enum Link {
+    Tcp(TcpStream),
+    // Messages for the thread that owns the WebSocket of a server room or a TLS stream, or that
+    // passes them on to OGS
+    #[cfg(not(target_arch = "wasm32"))]
+    Queued(Sender<Message>),
+}
+
+// A TLS stream, either end of the handshake
+#[cfg(not(target_arch = "wasm32"))]
+trait Duplex: Read + Write + Send {}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl<T: Read + Write + Send> Duplex for T {}
+
+// The name in the certificate of a directly hosted game
+#[cfg(not(target_arch = "wasm32"))]
+const TLS_NAME: &str = "go-game";
+#[cfg(not(target_arch = "wasm32"))]
+const HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(10);
+#[cfg(not(target_arch = "wasm32"))]
+const POLL_INTERVAL: Duration = Duration::from_millis(50);
+
+/// Where the connection of a network game goes, to open it again after it drops.
+#[derive(Clone, PartialEq, Debug)]
+pub enum Peer {
+    Host {
+        port: u16,
+        tls: bool,
+    },
+    Join {
+        address: String,
+        port: u16,
+        tls: bool,
+    },
+    // With no code yet until the room is opened
+    Room {
+        server: String,
+        code: String,
+    },
+    // The queue of the game server, until it opens a room
+    Automatch {
+        server: String,
+    },
+    // A game on online-go.com
+    Ogs {
+        game: u64,
+    },
+    // A game on an IGS server, found as the new game form says
+    Igs {
+        server: String,
+        target: String,
+    },
+}
+
+/// A connection to the other player, directly or through a room of the game server, read on a
+/// background thread that wakes the UI for every message.
+pub struct Connection {
+    link: Link,
+    incoming: Receiver<Message>,
+    /// The code of the server room, for the other player to enter.
+    pub room: Option<String>,
+    /// The start of the host's certificate digest on an encrypted direct connection, for the
+    /// players to compare.
+    pub fingerprint: Option<String>,
+}
+
+// The first bytes of the SHA-256 digest of `certificate`, as hex pairs
+#[cfg(not(target_arch = "wasm32"))]
+fn fingerprint(certificate: &[u8]) -> String {
+    use sha2::Digest;
+
+    sha2::Sha256::digest(certificate)[..8]
+        .iter()
+        .map(|byte| format!("{:02X}", byte))
+        .collect::<Vec<_>>()
+        .join(":")
+}
+
+// A direct connection has no authority to vouch for the host's certificate, which is made up
+// for each game; the players compare fingerprints instead
+#[cfg(not(target_arch = "wasm32"))]
+#[derive(Debug)]
+struct AnyCertificate(std::sync::Arc<rustls::crypto::CryptoProvider>);
+
+#[cfg(not(target_arch = "wasm32"))]
+impl rustls::client::danger::ServerCertVerifier for AnyCertificate {
+    fn verify_server_cert(
+        &self,
+        _end_entity: &rustls::pki_types::CertificateDer<'_>,
+        _intermediates: &[rustls::pki_types::CertificateDer<'_>],
+        _server_name: &rustls::pki_types::ServerName<'_>,
+        _ocsp_response: &[u8],
+        _now: rustls::pki_types::UnixTime,
+    ) -> Result<rustls::client::danger::ServerCertVerified, rustls::Error> {
+        Ok(rustls::client::danger::ServerCertVerified::assertion())
+    }
+
+    fn verify_tls12_signature(
+        &self,
+        message: &[u8],
+        cert: &rustls::pki_types::CertificateDer<'_>,
+        dss: &rustls::DigitallySignedStruct,
+    ) -> Result<rustls::client::danger::HandshakeSignatureValid, rustls::Error> {
+        rustls::crypto::verify_tls12_signature(
+            message,
+            cert,
+            dss,
+            &self.0.signature_verification_algorithms,
+        )
+    }
+
+    fn verify_tls13_signature(
+        &self,
+        message: &[u8],
+        cert: &rustls::pki_types::CertificateDer<'_>,
+        dss: &rustls::DigitallySignedStruct,
+    ) -> Result<rustls::client::danger::HandshakeSignatureValid, rustls::Error> {
+        rustls::crypto::verify_tls13_signature(
+            message,
+            cert,
+            dss,
+            &self.0.signature_verification_algorithms,
+        )
+    }
+
+    fn supported_verify_schemes(&self) -> Vec<rustls::SignatureScheme> {
+        self.0.signature_verification_algorithms.supported_schemes()
+    }
+}
+
+// The server address as typed, without a trailing slash
+#[cfg(not(target_arch = "wasm32"))]
+fn base_url(server: &str) -> String {
+    let server = server.trim().trim_end_matches('/');
+    if server.contains("://") {
+        server.to_string()
+    } else {
+        format!("http://{}", server)
+    }
+}
+
+// Requests carry the token of the account logged in with, if any, and hand back the server's
+// own message when it refuses
+#[cfg(not(target_arch = "wasm32"))]
+fn request<B>(builder: ureq::RequestBuilder<B>, token: &str) -> ureq::RequestBuilder<B> {
+    let builder = builder.config().http_status_as_error(false).build();
+    if token.is_empty() {
+        builder
+    } else {
+        builder.header("Authorization", format!("Bearer {}", token))
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn checked(
+    response: Result<ureq::http::Response<ureq::Body>, ureq::Error>,
+) -> std::io::Result<ureq::http::Response<ureq::Body>> {
+    let mut response = response.map_err(std::io::Error::other)?;
+    if response.status().is_success() {
+        return Ok(response);
+    }
+    let message = response.body_mut().read_to_string().unwrap_or_default();
+    Err(std::io::Error::other(if message.is_empty() {
+        response.status().to_string()
+    } else {
+        message
+    }))
+}
+
+/// The rooms on the game server at `server` still waiting for a second player, and the private
+/// ones the account of `token` may enter.
+#[cfg(not(target_arch = "wasm32"))]
+pub fn open_rooms(server: &str, token: &str) -> std::io::Result<Vec<RoomInfo>> {
+    checked(request(ureq::get(format!("{}/rooms", base_url(server))), token).call())?
+        .body_mut()
+        .read_json()
+        .map_err(std::io::Error::other)
+}
+
+/// The correspondence games of the account of `token`, those waiting for its move first.
+#[cfg(not(target_arch = "wasm32"))]
+pub fn correspondence_games(server: &str, token: &str) -> std::io::Result<Vec<OngoingGame>> {
+    let url = format!("{}/correspondence", base_url(server));
+    checked(request(ureq::get(url), token).call())?
+        .body_mut()
+        .read_json()
+        .map_err(std::io::Error::other)
+}
+
+/// Opens an account on the game server and logs in to it.
+#[cfg(not(target_arch = "wasm32"))]
+pub fn register(server: &str, name: &str, password: &str) -> std::io::Result<Session> {
+    send_credentials(server, "register", name, password)
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+pub fn log_in(server: &str, name: &str, password: &str) -> std::io::Result<Session> {
+    send_credentials(server, "login", name, password)
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn send_credentials(
+    server: &str,
+    path: &str,
+    name: &str,
+    password: &str,
+) -> std::io::Result<Session> {
+    let credentials = Credentials {
+        name: name.trim().to_string(),
+        password: password.to_string(),
+    };
+    let url = format!("{}/{}", base_url(server), path);
+    checked(request(ureq::post(url), "").send_json(credentials))?
+        .body_mut()
+        .read_json()
+        .map_err(std::io::Error::other)
+}
+
+/// The rating and games of the account `name`, with the private games the account of `token`
+/// was in.
+#[cfg(not(target_arch = "wasm32"))]
+pub fn profile(server: &str, name: &str, token: &str) -> std::io::Result<Profile> {
+    let url = format!("{}/users/{}", base_url(server), name);
+    checked(request(ureq::get(url), token).call())?
+        .body_mut()
+        .read_json()
+        .map_err(std::io::Error::other)
+}
+
+/// The SGF of a game the server stored, by the file name in a profile.
+#[cfg(not(target_arch = "wasm32"))]
+pub fn game_record(server: &str, file: &str, token: &str) -> std::io::Result<String> {
+    let url = format!("{}/games/{}", base_url(server), file);
+    checked(request(ureq::get(url), token).call())?
+        .body_mut()
+        .read_to_string()
+        .map_err(std::io::Error::other)
+}
+
+impl Connection {
+    /// Waits for the other player to connect on `port`, for at most `wait` if given, over TLS
+    /// if `tls`.
+    pub fn host(
+        port: u16,
+        wait: Option<Duration>,
+        tls: bool,
+        ctx: egui::Context,
+    ) -> std::io::Result<Self> {
+        let listener = TcpListener::bind(("0.0.0.0", port))?;
+        let Some(wait) = wait else {
+            let (stream, _) = listener.accept()?;
+            return Self::accepted(stream, tls, ctx);
+        };
+        let deadline = web_time::Instant::now() + wait;
+        listener.set_nonblocking(true)?;
+        loop {
+            match listener.accept() {
+                Ok((stream, _)) => {
+                    stream.set_nonblocking(false)?;
+                    return Self::accepted(stream, tls, ctx);
+                }
+                Err(err) if err.kind() == std::io::ErrorKind::WouldBlock => {
+                    if web_time::Instant::now() >= deadline {
+                        return Err(std::io::ErrorKind::TimedOut.into());
+                    }
+                    std::thread::sleep(Duration::from_millis(100));
+                }
+                Err(err) => return Err(err),
+            }
+        }
+    }
+
+    /// Connects to a game hosted at `address`, over TLS if `tls`.
+    pub fn join(address: &str, port: u16, tls: bool, ctx: egui::Context) -> std::io::Result<Self> {
+        let stream = TcpStream::connect((address.trim(), port))?;
+        #[cfg(not(target_arch = "wasm32"))]
+        if tls {
+            let provider = std::sync::Arc::new(rustls::crypto::ring::default_provider());
+            let config = rustls::ClientConfig::builder_with_provider(provider.clone())
+                .with_safe_default_protocol_versions()
+                .map_err(std::io::Error::other)?
+                .dangerous()
+                .with_custom_certificate_verifier(std::sync::Arc::new(AnyCertificate(provider)))
+                .with_no_client_auth();
+            let name =
+                rustls::pki_types::ServerName::try_from(TLS_NAME).map_err(std::io::Error::other)?;
+            let mut connection = rustls::ClientConnection::new(std::sync::Arc::new(config), name)
+                .map_err(std::io::Error::other)?;
+            let mut stream = stream;
+            stream.set_read_timeout(Some(HANDSHAKE_TIMEOUT))?;
+            while connection.is_handshaking() {
+                connection.complete_io(&mut stream)?;
+            }
+            stream.set_read_timeout(Some(POLL_INTERVAL))?;
+            let fingerprint = connection
+                .peer_certificates()
+                .and_then(|certificates| certificates.first())
+                .map(|certificate| fingerprint(certificate));
+            return Self::start_tls(
+                Box::new(rustls::StreamOwned::new(connection, stream)),
+                fingerprint,
+                ctx,
+            );
+        }
+        #[cfg(target_arch = "wasm32")]
+        let _ = tls;
+        Self::start(stream, ctx)
+    }
+
+    // Starts the connection the host accepted, making up a certificate for it if `tls`
+    fn accepted(stream: TcpStream, tls: bool, ctx: egui::Context) -> std::io::Result<Self> {
+        #[cfg(not(target_arch = "wasm32"))]
+        if tls {
+            let certified = rcgen::generate_simple_self_signed([TLS_NAME.to_string()])
+                .map_err(std::io::Error::other)?;
+            let certificate = certified.cert.der().clone();
+            let key = rustls::pki_types::PrivateKeyDer::Pkcs8(
+                certified.signing_key.serialize_der().into(),
+            );
+            let fingerprint = fingerprint(&certificate);
+            let provider = std::sync::Arc::new(rustls::crypto::ring::default_provider());
+            let config = rustls::ServerConfig::builder_with_provider(provider)
+                .with_safe_default_protocol_versions()
+                .map_err(std::io::Error::other)?
+                .with_no_client_auth()
+                .with_single_cert(vec![certificate], key)
+                .map_err(std::io::Error::other)?;
+            let mut connection = rustls::ServerConnection::new(std::sync::Arc::new(config))
+                .map_err(std::io::Error::other)?;
+            let mut stream = stream;
+            stream.set_read_timeout(Some(HANDSHAKE_TIMEOUT))?;
+            while connection.is_handshaking() {
+                connection.complete_io(&mut stream)?;
+            }
+            stream.set_read_timeout(Some(POLL_INTERVAL))?;
+            return Self::start_tls(
+                Box::new(rustls::StreamOwned::new(connection, stream)),
+                Some(fingerprint),
+                ctx,
+            );
+        }
+        #[cfg(target_arch = "wasm32")]
+        let _ = tls;
+        Self::start(stream, ctx)
+    }
+
+    /// Opens a room on the game server for the game and terms of `new_room`, and waits in it.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn create_room(
+        server: &str,
+        token: &str,
+        new_room: NewRoom,
+        ctx: egui::Context,
+    ) -> std::io::Result<Self> {
+        let url = format!("{}/rooms", base_url(server));
+        let created: RoomCreated = checked(request(ureq::post(url), token).send_json(new_room))?
+            .body_mut()
+            .read_json()
+            .map_err(std::io::Error::other)?;
+        Self::enter_room(server, token, &created.code, ctx)
+    }
+
+    /// Enters the room `code` of the game server, which sends the game once both players are in;
+    /// `token` plays or watches as the account logged in with.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn enter_room(
+        server: &str,
+        token: &str,
+        code: &str,
+        ctx: egui::Context,
+    ) -> std::io::Result<Self> {
+        let code = code.trim().to_uppercase();
+        let mut connection = Self::open_socket(server, token, &format!("rooms/{}", code), ctx)?;
+        connection.room = Some(code);
+        Ok(connection)
+    }
+
+    /// Waits in the automatch queue of the game server for an opponent who accepts one of the
+    /// sizes and speeds of `seek`, until the server sends the room opened for the two.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn automatch(
+        server: &str,
+        token: &str,
+        seek: Seek,
+        ctx: egui::Context,
+    ) -> std::io::Result<Self> {
+        let mut connection = Self::open_socket(server, token, "automatch", ctx)?;
+        connection.send(&Message::Seek(seek))?;
+        Ok(connection)
+    }
+
+    // Opens the WebSocket at `path` of the game server, owned by a thread of its own
+    #[cfg(not(target_arch = "wasm32"))]
+    fn open_socket(
+        server: &str,
+        token: &str,
+        path: &str,
+        ctx: egui::Context,
+    ) -> std::io::Result<Self> {
+        use tungstenite::Message as Frame;
+        use tungstenite::client::IntoClientRequest;
+
+        let url = format!("{}/{}", base_url(server).replacen("http", "ws", 1), path);
+        let mut request = url.into_client_request().map_err(std::io::Error::other)?;
+        if !token.is_empty() {
+            let value = format!("Bearer {}", token)
+                .parse()
+                .map_err(std::io::Error::other)?;
+            request.headers_mut().insert("Authorization", value);
+        }
+        // A refusal carries the server's reason, such as a private game
+        let (mut socket, _) = tungstenite::connect(request).map_err(|err| match err {
+            tungstenite::Error::Http(response) => std::io::Error::other(
+                response
+                    .body()
+                    .as_deref()
+                    .map(String::from_utf8_lossy)
+                    .map_or_else(|| response.status().to_string(), |text| text.into_owned()),
+            ),
+            err => std::io::Error::other(err),
+        })?;
+        // Reads give up after a moment so the thread also gets to send what is queued
+        match socket.get_ref() {
+            tungstenite::stream::MaybeTlsStream::Plain(stream) => {
+                stream.set_read_timeout(Some(POLL_INTERVAL))?
+            }
+            tungstenite::stream::MaybeTlsStream::Rustls(stream) => {
+                stream.get_ref().set_read_timeout(Some(POLL_INTERVAL))?
+            }
+            _ => {}
+        }
+        let (outgoing, queued) = mpsc::channel::<Message>();
+        let (sender, incoming) = mpsc::channel();
+        std::thread::spawn(move || {
+            'connected: loop {
+                loop {
+                    match queued.try_recv() {
+                        Ok(message) => {
+                            if socket.send(Frame::binary(message.encode())).is_err() {
+                                break 'connected;
+                            }
+                        }
+                        Err(mpsc::TryRecvError::Empty) => break,
+                        // The connection was dropped here
+                        Err(mpsc::TryRecvError::Disconnected) => {
+                            let _ = socket.close(None);
+                            let _ = socket.flush();
+                            return;
+                        }
+                    }
+                }
+                match socket.read() {
+                    // Messages this version does not know are skipped
+                    Ok(Frame::Binary(bytes)) => {
+                        if let Some(message) = Message::decode(&bytes) {
+                            if sender.send(message).is_err() {
+                                return;
+                            }
+                            ctx.request_repaint();
+                        }
+                    }
+                    Ok(Frame::Close(_)) => break,
+                    Ok(_) => {}
+                    Err(tungstenite::Error::Io(err))
+                        if matches!(
+                            err.kind(),
+                            std::io::ErrorKind::WouldBlock | std::io::ErrorKind::TimedOut
+                        ) => {}
+                    Err(_) => break,
+                }
+            }
+            let _ = sender.send(Message::Bye);
+            ctx.request_repaint();
+        });
+        Ok(Self {
+            link: Link::Queued(outgoing),
+            incoming,
+            room: None,
+            fingerprint: None,
+        })
+    }
+
+    // Like a room, a thread owns the TLS stream and takes turns reading and sending, since
+    // neither end can be used on its own
+    #[cfg(not(target_arch = "wasm32"))]
+    fn start_tls(
+        mut stream: Box<dyn Duplex>,
+        fingerprint: Option<String>,
+        ctx: egui::Context,
+    ) -> std::io::Result<Self> {
+        let (outgoing, queued) = mpsc::channel::<Message>();
+        let (sender, incoming) = mpsc::channel();
+        std::thread::spawn(move || {
+            let mut buffer = Vec::new();
+            'connected: loop {
+                loop {
+                    match queued.try_recv() {
+                        Ok(message) => {
+                            if stream
+                                .write_all(&message.frame())
+                                .and_then(|_| stream.flush())
+                                .is_err()
+                            {
+                                break 'connected;
+                            }
+                        }
+                        Err(mpsc::TryRecvError::Empty) => break,
+                        // The connection was dropped here, which closes the socket
+                        Err(mpsc::TryRecvError::Disconnected) => return,
+                    }
+                }
+                if !read_messages(&mut stream, &mut buffer, &sender, &ctx) {
+                    break;
+                }
+            }
+            let _ = sender.send(Message::Bye);
+            ctx.request_repaint();
+        });
+        Ok(Self {
+            link: Link::Queued(outgoing),
+            incoming,
+            room: None,
+            fingerprint,
+        })
+    }
+
+    /// A connection whose messages another thread turns into those of a different protocol, and
+    /// whose answers it turns back into messages.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn relayed(outgoing: Sender<Message>, incoming: Receiver<Message>) -> Self {
+        Self {
+            link: Link::Queued(outgoing),
+            incoming,
+            room: None,
+            fingerprint: None,
+        }
+    }
+
+    fn start(stream: TcpStream, ctx: egui::Context) -> std::io::Result<Self> {
+        let mut reader = stream.try_clone()?;
+        let (sender, incoming) = mpsc::channel();
+        std::thread::spawn(move || {
+            let mut buffer = Vec::new();
+            while read_messages(&mut reader, &mut buffer, &sender, &ctx) {}
+            let _ = sender.send(Message::Bye);
+            ctx.request_repaint();
+        });
+        Ok(Self {
+            link: Link::Tcp(stream),
+            incoming,
+            room: None,
+            fingerprint: None,
+        })
+    }
+
+    pub fn send(&mut self, message: &Message) -> std::io::Result<()> {
+        match &mut self.link {
+            Link::Tcp(stream) => stream.write_all(&message.frame()),
+            #[cfg(not(target_arch = "wasm32"))]
+            Link::Queued(outgoing) => outgoing
+                .send(message.clone())
+                .map_err(|_| std::io::ErrorKind::BrokenPipe.into()),
+        }
+    }
+
+    /// The next message that has arrived, if any.
+    pub fn receive(&self) -> Option<Message> {
+        self.incoming.try_recv().ok()
+    }
+}
+
+// Reads what came on `stream` and passes on each whole message, keeping the start of the next in
+// `buffer`; `false` once the stream is closed or broken, or nobody listens any more
+fn read_messages(
+    stream: &mut impl Read,
+    buffer: &mut Vec<u8>,
+    sender: &Sender<Message>,
+    ctx: &egui::Context,
+) -> bool {
+    let mut chunk = [0; 4096];
+    match stream.read(&mut chunk) {
+        Ok(0) => return false,
+        Ok(read) => buffer.extend_from_slice(&chunk[..read]),
+        // A read gave up so the thread can send what is queued
+        Err(err)
+            if matches!(
+                err.kind(),
+                std::io::ErrorKind::WouldBlock | std::io::ErrorKind::TimedOut
+            ) =>
+        {
+            return true;
+        }
+        Err(_) => return false,
+    }
+    loop {
+        match protocol::take_frame(buffer) {
+            Ok(Some(bytes)) => {
+                // Messages this version does not know are skipped
+                if let Some(message) = Message::decode(&bytes) {
+                    if sender.send(message).is_err() {
+                        return false;
+                    }
+                    ctx.request_repaint();
+                }
+            }
+            Ok(None) => return true,
+            Err(_) => return false,
+        }
+    }
+}
+
+impl Drop for Connection {
+    // Also ends the reading thread, which holds a handle on the same socket; the thread of a
+    // room or TLS stream notices the dropped sender by itself
+    fn drop(&mut self) {
+        match &self.link {
+            Link::Tcp(stream) => {
+                let _ = stream.shutdown(Shutdown::Both);
+            }
+            #[cfg(not(target_arch = "wasm32"))]
+            Link::Queued(_) => {}
+        }
+    }
+}
@@ -0,0 +1,14 @@
+//! The rules engine on its own, without the window: boards and moves, SGF records, game
+//! information, scoring and the protocol copies of the app speak to each other. The app, the game
+//! server and the bindings to other languages all build on this one copy.
+
+pub mod board;
+pub mod clock;
+pub mod consts;
+pub mod game_info;
+pub mod i18n;
+pub mod notation;
+pub mod paths;
+pub mod protocol;
+pub mod scoring;
+pub mod sgf;
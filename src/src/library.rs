@@ -0,0 +1,46 @@
+use std::path::{Path, PathBuf};
+
+use crate::game_info::GameInfo;
+use crate::sgf;
+
+pub struct Entry {
+    pub path: PathBuf,
+    pub info: GameInfo,
+    pub board_size: String,
+}
+
+/// Collects every readable SGF file below `dir`, sorted by path.
+pub fn scan(dir: &Path) -> Vec<Entry> {
+    let mut entries = Vec::new();
+    collect(dir, &mut entries);
+    entries.sort_by(|a, b| a.path.cmp(&b.path));
+    entries
+}
+
+fn collect(dir: &Path, entries: &mut Vec<Entry>) {
+    let Ok(read_dir) = std::fs::read_dir(dir) else {
+        return;
+    };
+    for dir_entry in read_dir.flatten() {
+        let path = dir_entry.path();
+        if path.is_dir() {
+            collect(&path, entries);
+        } else if path
+            .extension()
+            .is_some_and(|ext| ext.eq_ignore_ascii_case("sgf"))
+        {
+            let Ok(text) = std::fs::read_to_string(&path) else {
+                continue;
+            };
+            let Ok(trees) = sgf::parse(&text) else {
+                continue;
+            };
+            let root = &trees[0];
+            entries.push(Entry {
+                info: GameInfo::from_node(root),
+                board_size: root.get("SZ").unwrap_or("19").to_string(),
+                path,
+            });
+        }
+    }
+}
@@ -1,62 +1,910 @@
+#[cfg(not(target_arch = "wasm32"))]
+use clap::Parser;
 use eframe::egui;
-use std::collections::HashSet;
-mod consts;
+use std::collections::{HashMap, HashSet};
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::mpsc::Receiver;
+mod analysis_cache;
+#[cfg(not(target_arch = "wasm32"))]
+mod api;
+#[cfg(not(target_arch = "wasm32"))]
+mod archive;
+#[cfg(not(target_arch = "wasm32"))]
+mod bot;
+#[cfg(not(target_arch = "wasm32"))]
+mod broadcast;
+#[cfg(not(target_arch = "wasm32"))]
+mod cli;
+#[cfg(not(target_arch = "wasm32"))]
+mod commands;
+mod compare;
+mod config;
+#[cfg(not(target_arch = "wasm32"))]
+mod correspondence;
+mod counting;
+mod daily;
+mod database;
+#[cfg(not(target_arch = "wasm32"))]
+mod dataset;
+mod debug;
+mod drawing;
+mod estimate;
+mod fuseki;
+mod game_log;
+mod game_tree;
+mod gamepad;
+mod grid;
+mod gtp;
+mod hidden;
+#[cfg(not(target_arch = "wasm32"))]
+mod igs;
+mod lan;
+mod library;
+mod markup;
+mod mistakes;
+mod new_game;
+mod notifications;
+#[cfg(not(target_arch = "wasm32"))]
+mod ogs;
+mod openings;
+mod pattern;
+#[cfg(not(target_arch = "wasm32"))]
+mod pdf;
+mod problems;
+mod profiles;
+mod report;
+#[cfg(not(target_arch = "wasm32"))]
+mod script;
+mod shapes;
+mod share;
+mod skin;
+mod sound;
+mod speech;
+mod study;
+#[cfg(not(target_arch = "wasm32"))]
+mod sync_folder;
+mod task;
+mod theme;
+#[cfg(not(target_arch = "wasm32"))]
+mod tournament;
+mod training;
+#[cfg(not(target_arch = "wasm32"))]
+mod tui;
+mod tutorial;
+mod view;
+#[cfg(not(target_arch = "wasm32"))]
+mod voice;
+#[cfg(not(target_arch = "wasm32"))]
+mod wall;
+mod yose;
 
-#[derive(Clone, Copy, PartialEq, Debug)]
-enum Stone {
-    Black,
-    White,
-    Empty,
-}
+use src::{board, clock, consts, game_info, i18n, notation, paths, protocol, scoring, sgf};
 
-#[derive(Clone, Copy, PartialEq, Debug)]
-enum Player {
-    Black,
-    White,
-}
+use board::{Board, Move, Player, Stone};
+use clock::Clock;
+use game_info::{GameInfo, Rules};
+use game_log::Kind;
+use game_tree::{GameTree, Verdict};
+use i18n::{tr, trf};
+use new_game::Opponent;
+use notation::CoordinateStyle;
+use sound::Sound;
+use theme::{BoardTheme, MarkerShape, UiTheme};
 
 enum AppState {
     Options,
     Game,
+    Database,
+    Problems,
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum MoveNumbers {
+    Off,
+    All,
+    LastTen,
+    FromFigure,
 }
 
-impl Player {
-    fn other(&self) -> Player {
+impl MoveNumbers {
+    const ALL: [MoveNumbers; 4] = [Self::Off, Self::All, Self::LastTen, Self::FromFigure];
+
+    fn name(self) -> &'static str {
         match self {
-            Player::Black => Player::White,
-            Player::White => Player::Black,
+            Self::Off => "No numbers",
+            Self::All => "All moves",
+            Self::LastTen => "Last 10 moves",
+            Self::FromFigure => "From figure",
         }
     }
-    fn to_stone(&self) -> Stone {
+}
+
+#[derive(Clone, Copy)]
+enum Command {
+    NewGame,
+    SaveSgf,
+    Undo,
+    Redo,
+    Pass,
+    CountScore,
+    Rematch,
+    GameInfo,
+    EditPosition,
+    AnalyzeGame,
+    GameTree,
+    TryItOut,
+    Estimate,
+    Influence,
+    AtariWarnings,
+    AreaCount,
+    SelectRegion,
+    AttachEngine,
+    Fullscreen,
+    Coordinates,
+    Preferences,
+    RotateBoard,
+    MirrorBoard,
+    Presentation,
+    Markup,
+    Drawing,
+    ExportImage,
+    PauseClocks,
+    Adjourn,
+    GameLog,
+    Scripts,
+    Broadcast,
+    Tournament,
+    Wall,
+    Compare,
+    Debug,
+    Share,
+    Bookmark,
+    Study,
+}
+
+impl Command {
+    const ALL: [Command; 39] = [
+        Self::NewGame,
+        Self::SaveSgf,
+        Self::Undo,
+        Self::Redo,
+        Self::Pass,
+        Self::CountScore,
+        Self::Rematch,
+        Self::GameInfo,
+        Self::EditPosition,
+        Self::AnalyzeGame,
+        Self::GameTree,
+        Self::TryItOut,
+        Self::Estimate,
+        Self::Influence,
+        Self::AtariWarnings,
+        Self::AreaCount,
+        Self::SelectRegion,
+        Self::AttachEngine,
+        Self::Fullscreen,
+        Self::Coordinates,
+        Self::Preferences,
+        Self::RotateBoard,
+        Self::MirrorBoard,
+        Self::Presentation,
+        Self::Markup,
+        Self::Drawing,
+        Self::ExportImage,
+        Self::PauseClocks,
+        Self::Adjourn,
+        Self::GameLog,
+        Self::Scripts,
+        Self::Broadcast,
+        Self::Tournament,
+        Self::Wall,
+        Self::Compare,
+        Self::Debug,
+        Self::Share,
+        Self::Bookmark,
+        Self::Study,
+    ];
+
+    fn name(self) -> &'static str {
         match self {
-            Player::Black => Stone::Black,
-            Player::White => Stone::White,
+            Self::NewGame => "New game",
+            Self::SaveSgf => "Save SGF",
+            Self::Undo => "Undo",
+            Self::Redo => "Redo",
+            Self::Pass => "Pass",
+            Self::CountScore => "Count score",
+            Self::Rematch => "Rematch",
+            Self::GameInfo => "Toggle game info",
+            Self::EditPosition => "Toggle position editing",
+            Self::AnalyzeGame => "Analyze game",
+            Self::GameTree => "Toggle game tree",
+            Self::TryItOut => "Toggle try it out",
+            Self::Estimate => "Toggle score estimate",
+            Self::Influence => "Toggle influence map",
+            Self::AtariWarnings => "Toggle atari warnings",
+            Self::AreaCount => "Toggle area count",
+            Self::SelectRegion => "Select region",
+            Self::AttachEngine => "Attach engine",
+            Self::Fullscreen => "Toggle fullscreen",
+            Self::Coordinates => "Toggle coordinates",
+            Self::Preferences => "Settings",
+            Self::RotateBoard => "Rotate board",
+            Self::MirrorBoard => "Mirror board",
+            Self::Presentation => "Toggle presentation mode",
+            Self::Markup => "Toggle markup tools",
+            Self::Drawing => "Toggle drawing",
+            Self::ExportImage => "Export board image",
+            Self::PauseClocks => "Pause or resume the clocks",
+            Self::Adjourn => "Adjourn game",
+            Self::GameLog => "Toggle game log",
+            Self::Scripts => "Toggle scripts",
+            Self::Broadcast => "Toggle broadcast",
+            Self::Tournament => "Toggle tournament director",
+            Self::Wall => "Show the wall of boards",
+            Self::Compare => "Toggle position comparison",
+            Self::Debug => "Toggle debug overlay",
+            Self::Share => "Share game link",
+            Self::Bookmark => "Bookmark this position",
+            Self::Study => "Study two games side by side",
         }
     }
 }
 
+/// Stones put down freely while presenting, outside the rules and the game record.
+#[derive(Clone, Copy)]
+struct Presentation {
+    // The color of the next stone placed
+    color: Player,
+    // Whether the color flips after every stone
+    alternate: bool,
+}
+
+/// A chat message of a network game, `from` a player or, with `None`, a spectator.
+struct ChatLine {
+    from: Option<Player>,
+    // Between the two players of a pair go side
+    team: bool,
+    text: String,
+    // Seconds of game time when it was sent, both clocks counted
+    at: f32,
+}
+
 struct GoBoard {
     state: AppState,
     board_size: usize,
-    board: Vec<Vec<Stone>>,
+    board: Board,
     current_player: Player,
     captured_black: u32,
     captured_white: u32,
+    // Captures that win the game being played, 0 when it is counted
+    capture_goal: u32,
+    // Blind Go: the stones are hidden until the game ends, unless shown on demand
+    blind: bool,
+    blind_revealed: bool,
+    toroidal: bool,
+    hidden: Option<hidden::Hidden>,
+    // The four players of pair go at this computer, and who says they are at the board, with
+    // the move it was said at
+    pair: Option<[String; 4]>,
+    pair_seat: Option<(usize, usize)>,
     game_over: bool,
     last_move: Option<(usize, usize)>,
+    #[cfg(not(target_arch = "wasm32"))]
+    ogs_game_input: String,
+    #[cfg(not(target_arch = "wasm32"))]
+    ogs_player_input: String,
+    #[cfg(not(target_arch = "wasm32"))]
+    ogs_player_count: usize,
+    #[cfg(not(target_arch = "wasm32"))]
+    ogs_status: Option<String>,
+    #[cfg(not(target_arch = "wasm32"))]
+    ogs_pending: Option<Receiver<ogs::Response>>,
+    // The OGS account logged in to for live play, with its games and challenges as last fetched;
+    // the password is never saved
+    #[cfg(not(target_arch = "wasm32"))]
+    ogs_password: String,
+    #[cfg(not(target_arch = "wasm32"))]
+    ogs_session: Option<ogs::Session>,
+    #[cfg(not(target_arch = "wasm32"))]
+    ogs_session_pending: Option<Receiver<Result<(ogs::Session, ogs::Lobby), ogs::Error>>>,
+    #[cfg(not(target_arch = "wasm32"))]
+    ogs_lobby: ogs::Lobby,
+    #[cfg(not(target_arch = "wasm32"))]
+    ogs_lobby_pending: Option<Receiver<ogs::Answered>>,
+    // Games of others to watch, the top ones or a player's, as last fetched; no login needed
+    #[cfg(not(target_arch = "wasm32"))]
+    ogs_watch_player: String,
+    #[cfg(not(target_arch = "wasm32"))]
+    ogs_watchable: Vec<ogs::LiveGame>,
+    #[cfg(not(target_arch = "wasm32"))]
+    ogs_watchable_pending: Option<Receiver<Result<Vec<ogs::LiveGame>, ogs::Error>>>,
+    // The OGS game to open
+    ogs_game: Option<u64>,
+    // Typed to log in to IGS and never saved
+    #[cfg(not(target_arch = "wasm32"))]
+    igs_password: String,
+    info: GameInfo,
+    setup: Vec<(Stone, (usize, usize))>,
+    // Prisoners already taken in the setup position, black stones first
+    setup_captures: (u32, u32),
+    // The stone placed by clicks while editing the position
+    edit_stone: Option<Stone>,
+    problem_editor: bool,
+    // The mark placed by clicks while marking up the current node
+    markup_tool: Option<markup::Tool>,
+    // What dragging draws while the drawing layer is on, and in which color
+    drawing_tool: Option<drawing::Tool>,
+    drawing_color: egui::Color32,
+    // Lines drawn by hand over the current node, which is remembered so moving on clears them
+    drawings: Vec<drawing::Stroke>,
+    drawings_node: usize,
+    export_drawings: bool,
+    // Set from asking for a screenshot until the board image has been cut from it
+    export_requested: bool,
+    // Where the board was last drawn, for cutting it out of screenshots
+    board_rect: egui::Rect,
+    moves: Vec<Move>,
+    file_path: Option<PathBuf>,
+    file_status: Option<String>,
+    // The record as last saved or loaded; `None` before a game is set up
+    saved_sgf: Option<String>,
+    library: Option<Vec<library::Entry>>,
+    database: Option<database::Database>,
+    db_query: database::Query,
+    db_results: Vec<usize>,
+    db_import_input: String,
+    db_status: Option<String>,
+    db_pending: Option<Receiver<Vec<database::Record>>>,
+    // The public archives the database is kept up to date from, loaded with the database, and
+    // an update running with how far it has got
+    #[cfg(not(target_arch = "wasm32"))]
+    archives: Option<archive::Archives>,
+    #[cfg(not(target_arch = "wasm32"))]
+    archive_input: String,
+    #[cfg(not(target_arch = "wasm32"))]
+    archive_pending: Option<Receiver<archive::Event>>,
+    #[cfg(not(target_arch = "wasm32"))]
+    archive_progress: (usize, usize),
+    selecting_region: bool,
+    region_start: Option<(usize, usize)>,
+    region: Option<((usize, usize), (usize, usize))>,
+    pattern_pending: Option<Receiver<pattern::SearchResult>>,
+    show_graph: bool,
+    auto_replay: bool,
+    replay_interval: f32,
+    // When auto-replay last stepped, in egui time
+    replay_step_at: f64,
+    // Estimated score lead for Black after each move of the analysed line
+    analysis: Option<Vec<f32>>,
+    analysis_pending: Option<Receiver<Result<Vec<f32>, String>>>,
+    // Whose mistakes in the analysed line become drills
+    mistake_side: Player,
+    mistakes_pending: Option<Receiver<Vec<mistakes::Mistake>>>,
+    mistakes_status: Option<String>,
+    // A review report of the analysed line being written, and the file it goes to
+    report_format: report::Format,
+    report_pending: Option<(PathBuf, Receiver<String>)>,
+    pattern_result: Option<pattern::SearchResult>,
+    // The endgame plays in the selected region, for the position with this checksum
+    yose: Option<(u64, Vec<yose::Play>)>,
+    yose_pending: Option<Receiver<(u64, Vec<yose::Play>)>>,
+    guess_record: Option<Vec<Move>>,
+    guess_session: training::Session,
+    guess_feedback: Option<String>,
+    problem: Option<training::Problem>,
+    problem_feedback: Option<String>,
+    problem_sets: Option<problems::Sets>,
+    problem_sets_input: String,
+    problem_sets_status: Option<String>,
+    // The set the open problem is reviewed from, to go on with its next due problem
+    reviewing: Option<usize>,
+    // Whether the open problem's result is kept already; only the first try counts
+    problem_recorded: bool,
+    // The open problem as a lesson of the rules tutorial
+    lesson: Option<usize>,
+    // The open problem as a shape drill, the shape drilled if only one, and how many first
+    // tries at drills were right out of how many
+    drill: Option<shapes::Drill>,
+    drill_shape: Option<shapes::Shape>,
+    drill_score: (u32, u32),
+    drills_started: u64,
+    // The day of the puzzle of the day, when that is the open drill
+    daily: Option<u32>,
+    streak: Option<daily::Streak>,
+    show_fuseki: bool,
+    // `Some` with the filter text while the command palette is open
+    palette_query: Option<String>,
+    // The intersection under the mouse, shown in the status bar
+    hovered_point: Option<(usize, usize)>,
+    // The intersection picked with the arrow keys, while playing from the keyboard
+    board_cursor: Option<(usize, usize)>,
+    // The group last long-pressed on a touch screen, whose liberties stay shown until the next tap
+    group_info: Option<(usize, usize)>,
+    // Rotation and mirroring of the board on screen
+    view: view::BoardView,
+    // `Some` while showing positions to a room instead of playing
+    presentation: Option<Presentation>,
+    // Set by a long press so that lifting the finger afterwards is not taken as a tap
+    long_pressed: bool,
+    // Which panels are shown and how large, kept between runs
+    layout: config::Layout,
+    // Stones removed by the last move, for the screen reader description
+    last_captured: u32,
+    // Board magnification over the fitted size, and the offset of its center while zoomed in
+    zoom: f32,
+    pan: egui::Vec2,
+    // Textures of the skin chosen in the settings, loaded on first use
+    skin: Option<skin::Skin>,
+    // The lines and star points as last drawn, drawn from again while the board stays put
+    grid: grid::Grid,
+    // Sounds caused this frame, played by the window if this tab is in front
+    sounds: Vec<Sound>,
+    // What happened this frame for a desktop notification, shown by the window while it is out
+    // of sight
+    notices: Vec<String>,
+    // Tells the tab apart from the others, wherever it is among them, for a click on its
+    // notification
+    tab_id: u64,
+    // The latest move to read aloud, likewise
+    announcement: Option<String>,
+    fuseki_stats: Option<(Vec<Move>, fuseki::Statistics)>,
+    new_game: new_game::Settings,
+    // The copy edited in the settings window while it is open
+    preferences: Option<new_game::Settings>,
+    // Set when the settings window applied changes that still have to be saved
+    preferences_applied: bool,
+    start_player: Player,
+    engine: Option<gtp::Engine>,
+    engine_color: Option<Player>,
+    engine_synced: usize,
+    engine_pending: Option<Receiver<EngineReply>>,
+    // A game against another copy of the app over the network, with the color played over
+    // there once it is agreed and the moves it has been sent
+    lan: Option<lan::Connection>,
+    lan_opponent: Option<Player>,
+    lan_synced: usize,
+    // How many moves there were when a host last looked at whether to send a `Sync`
+    lan_checked: usize,
+    // The round trip to the other side, which a host measures to keep the clocks fair
+    lan_latency: protocol::Latency,
+    // Following a server room's game without playing in it, and how many are doing so
+    lan_watching: bool,
+    lan_spectators: usize,
+    // Where the connection goes and the agreed terms for getting it back once it drops
+    lan_peer: Option<lan::Peer>,
+    lan_reconnect: Option<protocol::Reconnect>,
+    // The player whose connection dropped and since when, while waiting for them, or for this
+    // side to get through again no sooner than `lan_retry`
+    lan_away: Option<(Player, web_time::Instant)>,
+    lan_retry: Option<web_time::Instant>,
+    // Moves of both sides are taken while coming back, until the time on the move arrives
+    lan_catching_up: bool,
+    // The room the automatch queue opened, to enter next
+    lan_matched: Option<String>,
+    chat: Vec<ChatLine>,
+    chat_draft: String,
+    // Spectators read the players' chat or their own
+    chat_spectators: bool,
+    chat_team: bool,
+    // The moves this side asked to take back, and those the other side asks to
+    undo_asked: Option<usize>,
+    undo_offer: Option<usize>,
+    // Set by starting a network game until the connection is opened, which needs the UI context
+    lan_requested: bool,
+    lan_pending: Option<Receiver<std::io::Result<lan::Connection>>>,
+    // The game server's lobby as last fetched
+    rooms: Vec<protocol::RoomInfo>,
+    rooms_pending: Option<Receiver<std::io::Result<Vec<protocol::RoomInfo>>>>,
+    // Typed to log in to the server and never saved
+    server_password: String,
+    session_pending: Option<Receiver<std::io::Result<protocol::Session>>>,
+    // The account's rating and games as last fetched, and a record of them being opened
+    profile: Option<protocol::Profile>,
+    profile_pending: Option<Receiver<std::io::Result<protocol::Profile>>>,
+    record_pending: Option<Receiver<std::io::Result<String>>>,
+    // The server keeps the game between visits, and its moves and end were last copied here
+    lan_correspondence: bool,
+    // In a pair go game, which of its side's two players plays here: 0 for the side's first move
+    // and every other one after it, 1 for the rest
+    lan_rengo: Option<usize>,
+    // One board of a simultaneous game played here against several opponents, and how many
+    // more boards the tabs are still to open for it
+    lan_simul: bool,
+    simul_extra: u32,
+    // The replies it plays for this side when the opponent's move matches
+    lan_plan: protocol::Plan,
+    #[cfg(not(target_arch = "wasm32"))]
+    lan_remembered: Option<(usize, bool)>,
+    // The game's name in the sync folder, for one started here or opened from there, and how far
+    // it was when last written there
+    #[cfg(not(target_arch = "wasm32"))]
+    sync_id: Option<String>,
+    #[cfg(not(target_arch = "wasm32"))]
+    sync_written: Option<(usize, bool)>,
+    // The correspondence games played here, brought up to date by asking the server once
+    #[cfg(not(target_arch = "wasm32"))]
+    correspondence: correspondence::Games,
+    #[cfg(not(target_arch = "wasm32"))]
+    correspondence_pending: Option<Receiver<std::io::Result<Vec<protocol::OngoingGame>>>>,
+    #[cfg(not(target_arch = "wasm32"))]
+    correspondence_checked: bool,
+    profiles: profiles::Profiles,
+    // Shown under the profiles after they could not be written
+    profiles_status: Option<String>,
+    // `Some` for a game that moves the players' ratings once it has a result, with the color of
+    // the engine when it plays one side
+    rated: Option<Option<Player>>,
+    // A game between two profiles here, kept for their head-to-head record once it has a result
+    head_to_head: bool,
+    log: game_log::Log,
+    show_log: bool,
+    // The kinds of entry the log window leaves out
+    log_hidden: Vec<Kind>,
+    show_scripts: bool,
+    // The web page of this game for an audience
+    #[cfg(not(target_arch = "wasm32"))]
+    broadcast: Option<broadcast::Server>,
+    // Rhai scripts from the scripts folder, read when the scripts window first opens
+    #[cfg(not(target_arch = "wasm32"))]
+    scripts: Option<Vec<script::Script>>,
+    #[cfg(not(target_arch = "wasm32"))]
+    script_errors: Vec<String>,
+    // The scripts whose on_move and on_game_end run as the game is played, by name
+    #[cfg(not(target_arch = "wasm32"))]
+    script_hooks: Vec<String>,
+    // What the last script run or annotation gave
+    #[cfg(not(target_arch = "wasm32"))]
+    script_output: String,
+    // Whether on_game_end has run for this game
+    #[cfg(not(target_arch = "wasm32"))]
+    script_ended: bool,
+    show_tournament: bool,
+    // The tournament open in the director window, and the file it is kept in
+    #[cfg(not(target_arch = "wasm32"))]
+    tournament: Option<(PathBuf, tournament::Tournament)>,
+    // What the director is typing: the name of a new tournament, and a player to enter
+    #[cfg(not(target_arch = "wasm32"))]
+    tournament_name: String,
+    #[cfg(not(target_arch = "wasm32"))]
+    tournament_entrant: tournament::Entrant,
+    // The round shown, counted from 0
+    #[cfg(not(target_arch = "wasm32"))]
+    tournament_round: usize,
+    #[cfg(not(target_arch = "wasm32"))]
+    tournament_status: Option<String>,
+    // Boards of the tournament for the tabs to open, each with its game started
+    #[cfg(not(target_arch = "wasm32"))]
+    tournament_boards: Vec<tournament::Link>,
+    // The board of a tournament this game is played on
+    #[cfg(not(target_arch = "wasm32"))]
+    tournament_game: Option<tournament::Link>,
+    // Asks the tabs to put up the wall of boards
+    wall_requested: bool,
+    // Asks the tabs to put this game and another side by side
+    study_requested: bool,
+    show_compare: bool,
+    // The position the one on the board is compared with, and whether it was just marked here for
+    // the tabs to hand to the others
+    compare_mark: Option<compare::Marked>,
+    compare_shared: bool,
+    // The group numbers, liberties and other state of the rules over the board
+    show_debug: bool,
+    // The count played out the way it is done over a real board
+    counting: Option<counting::Counting>,
+    // The link and QR code of the game, while the dialog showing them is open
+    share: Option<share::Shared>,
+    tree: GameTree,
+    move_numbers: MoveNumbers,
+    show_atari: bool,
+    show_estimate: bool,
+    show_influence: bool,
+    show_area: bool,
+    // Moves between recounts of the live area count
+    area_interval: usize,
+    // The area count and the move number it was taken at
+    area_count: Option<(usize, u32, u32)>,
+    // How many of the latest moves get a marker, the newest most visible
+    marked_moves: usize,
+    // Moves before this one are left unnumbered in a figure
+    figure_start: usize,
+    // The real game tree while moves are only being tried out
+    trial: Option<GameTree>,
+    // What the branch being tried out is to be called when it is kept
+    branch_name: String,
+    // The label of the next bookmark, when it is given one
+    bookmark_label: String,
+    analysis_detached: bool,
+    staged_move: Option<(usize, usize)>,
+    move_input: String,
+    // When the player to move started thinking
+    move_started: web_time::Instant,
+    // How long the player to move had been thinking when the clocks were paused
+    paused: Option<f32>,
+    // The node and whole second last ticked for in the low time warning
+    time_warned: Option<(usize, u32)>,
+    // A second of overtime waiting to be read aloud
+    countdown: Option<u32>,
+    // Stones marked dead while counting; `None` outside the counting phase
+    dead_stones: Option<HashSet<(usize, usize)>>,
+    // Empty points given to a side, or to neither, by hand while counting
+    territory_marks: HashMap<(usize, usize), Stone>,
+    // A self-atari waiting for the player to confirm it
+    self_atari_prompt: Option<(usize, usize)>,
+    // The node the tree panel last scrolled to
+    tree_scrolled_to: Option<usize>,
 }
 
+const UNDO_SHORTCUT: egui::KeyboardShortcut =
+    egui::KeyboardShortcut::new(egui::Modifiers::COMMAND, egui::Key::Z);
+const REDO_SHORTCUT: egui::KeyboardShortcut =
+    egui::KeyboardShortcut::new(egui::Modifiers::COMMAND, egui::Key::Y);
+const PALETTE_SHORTCUT: egui::KeyboardShortcut =
+    egui::KeyboardShortcut::new(egui::Modifiers::COMMAND, egui::Key::P);
+const PREFERENCES_SHORTCUT: egui::KeyboardShortcut =
+    egui::KeyboardShortcut::new(egui::Modifiers::COMMAND, egui::Key::Comma);
+const DEBUG_SHORTCUT: egui::KeyboardShortcut = egui::KeyboardShortcut::new(
+    egui::Modifiers::COMMAND.plus(egui::Modifiers::SHIFT),
+    egui::Key::D,
+);
+
+type EngineReply = (gtp::Engine, Result<Option<Move>, gtp::Error>);
+
+static NEXT_TAB_ID: AtomicU64 = AtomicU64::new(0);
+
 impl Default for GoBoard {
     fn default() -> Self {
         Self {
             state: AppState::Options,
             board_size: consts::DEFAULT_BOARD_SIZE,
-            board: vec![vec![Stone::Empty; consts::DEFAULT_BOARD_SIZE]; consts::DEFAULT_BOARD_SIZE],
+            board: Board::new(consts::DEFAULT_BOARD_SIZE),
             current_player: Player::Black,
             captured_black: 0,
             captured_white: 0,
+            capture_goal: 0,
+            blind: false,
+            blind_revealed: false,
+            toroidal: false,
+            hidden: None,
+            pair: None,
+            pair_seat: None,
             game_over: false,
             last_move: None,
+            #[cfg(not(target_arch = "wasm32"))]
+            ogs_game_input: String::new(),
+            #[cfg(not(target_arch = "wasm32"))]
+            ogs_player_input: String::new(),
+            #[cfg(not(target_arch = "wasm32"))]
+            ogs_player_count: consts::OGS_DEFAULT_GAME_COUNT,
+            #[cfg(not(target_arch = "wasm32"))]
+            ogs_status: None,
+            #[cfg(not(target_arch = "wasm32"))]
+            ogs_pending: None,
+            #[cfg(not(target_arch = "wasm32"))]
+            ogs_password: String::new(),
+            #[cfg(not(target_arch = "wasm32"))]
+            ogs_session: None,
+            #[cfg(not(target_arch = "wasm32"))]
+            ogs_session_pending: None,
+            #[cfg(not(target_arch = "wasm32"))]
+            ogs_lobby: ogs::Lobby::default(),
+            #[cfg(not(target_arch = "wasm32"))]
+            ogs_lobby_pending: None,
+            #[cfg(not(target_arch = "wasm32"))]
+            ogs_watch_player: String::new(),
+            #[cfg(not(target_arch = "wasm32"))]
+            ogs_watchable: Vec::new(),
+            #[cfg(not(target_arch = "wasm32"))]
+            ogs_watchable_pending: None,
+            ogs_game: None,
+            #[cfg(not(target_arch = "wasm32"))]
+            igs_password: String::new(),
+            info: GameInfo::default(),
+            setup: Vec::new(),
+            setup_captures: (0, 0),
+            edit_stone: None,
+            problem_editor: false,
+            moves: Vec::new(),
+            file_path: None,
+            file_status: None,
+            saved_sgf: None,
+            library: None,
+            database: None,
+            db_query: database::Query::default(),
+            db_results: Vec::new(),
+            db_import_input: String::new(),
+            db_status: None,
+            db_pending: None,
+            #[cfg(not(target_arch = "wasm32"))]
+            archives: None,
+            #[cfg(not(target_arch = "wasm32"))]
+            archive_input: String::new(),
+            #[cfg(not(target_arch = "wasm32"))]
+            archive_pending: None,
+            #[cfg(not(target_arch = "wasm32"))]
+            archive_progress: (0, 0),
+            selecting_region: false,
+            region_start: None,
+            region: None,
+            pattern_pending: None,
+            show_graph: false,
+            auto_replay: false,
+            replay_interval: 1.0,
+            replay_step_at: 0.0,
+            analysis: None,
+            analysis_pending: None,
+            mistake_side: Player::Black,
+            mistakes_pending: None,
+            mistakes_status: None,
+            report_format: report::Format::Html,
+            report_pending: None,
+            pattern_result: None,
+            yose: None,
+            yose_pending: None,
+            guess_record: None,
+            guess_session: training::Session::default(),
+            guess_feedback: None,
+            problem: None,
+            problem_feedback: None,
+            problem_sets: None,
+            problem_sets_input: String::new(),
+            problem_sets_status: None,
+            reviewing: None,
+            problem_recorded: false,
+            lesson: None,
+            drill: None,
+            drill_shape: None,
+            drill_score: (0, 0),
+            drills_started: 0,
+            daily: None,
+            streak: None,
+            show_fuseki: false,
+            palette_query: None,
+            hovered_point: None,
+            board_cursor: None,
+            group_info: None,
+            view: view::BoardView::default(),
+            long_pressed: false,
+            presentation: None,
+            markup_tool: None,
+            drawing_tool: None,
+            drawing_color: egui::Color32::from_rgb(230, 120, 0),
+            drawings: Vec::new(),
+            drawings_node: GameTree::ROOT,
+            export_drawings: true,
+            export_requested: false,
+            board_rect: egui::Rect::NOTHING,
+            layout: config::Layout::default(),
+            last_captured: 0,
+            zoom: 1.0,
+            pan: egui::Vec2::ZERO,
+            skin: None,
+            grid: grid::Grid::default(),
+            sounds: Vec::new(),
+            notices: Vec::new(),
+            tab_id: NEXT_TAB_ID.fetch_add(1, Ordering::Relaxed),
+            announcement: None,
+            fuseki_stats: None,
+            new_game: new_game::Settings::default(),
+            preferences: None,
+            preferences_applied: false,
+            start_player: Player::Black,
+            engine: None,
+            engine_color: None,
+            engine_synced: 0,
+            engine_pending: None,
+            lan: None,
+            lan_opponent: None,
+            lan_synced: 0,
+            lan_checked: 0,
+            lan_latency: protocol::Latency::default(),
+            lan_watching: false,
+            lan_spectators: 0,
+            lan_peer: None,
+            lan_reconnect: None,
+            lan_away: None,
+            lan_retry: None,
+            lan_catching_up: false,
+            lan_matched: None,
+            chat: Vec::new(),
+            chat_draft: String::new(),
+            chat_spectators: false,
+            chat_team: false,
+            undo_asked: None,
+            undo_offer: None,
+            lan_requested: false,
+            lan_pending: None,
+            rooms: Vec::new(),
+            rooms_pending: None,
+            server_password: String::new(),
+            session_pending: None,
+            profile: None,
+            profile_pending: None,
+            record_pending: None,
+            lan_correspondence: false,
+            lan_rengo: None,
+            lan_simul: false,
+            simul_extra: 0,
+            lan_plan: protocol::Plan::default(),
+            #[cfg(not(target_arch = "wasm32"))]
+            lan_remembered: None,
+            #[cfg(not(target_arch = "wasm32"))]
+            sync_id: None,
+            #[cfg(not(target_arch = "wasm32"))]
+            sync_written: None,
+            #[cfg(not(target_arch = "wasm32"))]
+            correspondence: correspondence::Games::load(&paths::correspondence_path()),
+            #[cfg(not(target_arch = "wasm32"))]
+            correspondence_pending: None,
+            #[cfg(not(target_arch = "wasm32"))]
+            correspondence_checked: false,
+            profiles: profiles::Profiles::load(&paths::profiles_path()),
+            profiles_status: None,
+            rated: None,
+            head_to_head: false,
+            log: game_log::Log::default(),
+            show_log: false,
+            log_hidden: Vec::new(),
+            show_scripts: false,
+            #[cfg(not(target_arch = "wasm32"))]
+            broadcast: None,
+            #[cfg(not(target_arch = "wasm32"))]
+            scripts: None,
+            #[cfg(not(target_arch = "wasm32"))]
+            script_errors: Vec::new(),
+            #[cfg(not(target_arch = "wasm32"))]
+            script_hooks: Vec::new(),
+            #[cfg(not(target_arch = "wasm32"))]
+            script_output: String::new(),
+            #[cfg(not(target_arch = "wasm32"))]
+            script_ended: false,
+            show_tournament: false,
+            #[cfg(not(target_arch = "wasm32"))]
+            tournament: None,
+            #[cfg(not(target_arch = "wasm32"))]
+            tournament_name: String::new(),
+            #[cfg(not(target_arch = "wasm32"))]
+            tournament_entrant: tournament::Entrant::default(),
+            #[cfg(not(target_arch = "wasm32"))]
+            tournament_round: 0,
+            #[cfg(not(target_arch = "wasm32"))]
+            tournament_status: None,
+            #[cfg(not(target_arch = "wasm32"))]
+            tournament_boards: Vec::new(),
+            #[cfg(not(target_arch = "wasm32"))]
+            tournament_game: None,
+            wall_requested: false,
+            study_requested: false,
+            show_compare: false,
+            compare_mark: None,
+            compare_shared: false,
+            show_debug: false,
+            counting: None,
+            share: None,
+            tree: GameTree::default(),
+            move_numbers: MoveNumbers::Off,
+            show_atari: false,
+            show_estimate: false,
+            show_influence: false,
+            show_area: false,
+            area_interval: 1,
+            area_count: None,
+            marked_moves: 1,
+            figure_start: 0,
+            trial: None,
+            branch_name: String::new(),
+            bookmark_label: String::new(),
+            analysis_detached: false,
+            staged_move: None,
+            move_input: String::new(),
+            move_started: web_time::Instant::now(),
+            paused: None,
+            time_warned: None,
+            countdown: None,
+            dead_stones: None,
+            territory_marks: HashMap::new(),
+            self_atari_prompt: None,
+            tree_scrolled_to: None,
         }
     }
 }
@@ -66,440 +914,10977 @@ impl GoBoard {
         Self::default()
     }
 
-    fn _with_size(board_size_param: usize) -> Self {
+    fn with_size(board_size_param: usize) -> Self {
         GoBoard {
-            state: AppState::Options,
             board_size: board_size_param,
-            board: vec![vec![Stone::Empty; board_size_param]; board_size_param],
-            current_player: Player::Black,
-            captured_black: 0,
-            captured_white: 0,
-            game_over: false,
-            last_move: None,
+            board: Board::new(board_size_param),
+            ..Self::default()
+        }
+    }
+
+    fn from_config(config: &config::Config) -> Self {
+        GoBoard {
+            new_game: config.settings.clone(),
+            layout: config.layout,
+            ..Self::with_size(config.board_size)
+        }
+    }
+
+    // Applies the command-line choices on top of the saved settings
+    #[cfg(not(target_arch = "wasm32"))]
+    fn apply_args(&mut self, args: &cli::GameArgs) -> Result<(), String> {
+        if let Some(size) = args.size {
+            self.board_size = size;
+            self.board = Board::new(size);
+        }
+        let settings = &mut self.new_game;
+        if let Some(rules) = args.rules {
+            settings.rules = rules;
+        }
+        if let Some(komi) = args.komi {
+            settings.komi = komi;
         }
+        if let Some(handicap) = args.handicap {
+            settings.handicap = handicap;
+        }
+        if args.headless || args.tui {
+            settings.opponent = Opponent::Human;
+        } else if let Some(engine) = &args.engine {
+            settings.engine_command = engine.clone();
+            settings.opponent = Opponent::Engine;
+        }
+        if let Some(path) = &args.sgf {
+            self.open_file(path.clone())?;
+        } else if args.starts_game() {
+            self.start_game();
+        }
+        if args.broadcast {
+            self.toggle_broadcast();
+        }
+        Ok(())
     }
 
     fn reset(&mut self) {
+        // The guessing score covers the whole session, not a single game
+        let guess_session = std::mem::take(&mut self.guess_session);
+        let new_game = std::mem::take(&mut self.new_game);
+        let layout = self.layout;
+        #[cfg(not(target_arch = "wasm32"))]
+        let scripts = (self.scripts.take(), std::mem::take(&mut self.script_hooks));
+        // The director goes on with the tournament between games
+        #[cfg(not(target_arch = "wasm32"))]
+        let tournament = (self.show_tournament, self.tournament.take());
         *self = Self::default();
+        self.guess_session = guess_session;
+        self.new_game = new_game;
+        self.layout = layout;
+        #[cfg(not(target_arch = "wasm32"))]
+        {
+            (self.scripts, self.script_hooks) = scripts;
+            (self.show_tournament, self.tournament) = tournament;
+        }
     }
 
-    fn get_neighbors(&self, row: usize, col: usize) -> Vec<(usize, usize)> {
-        let mut neighbors = Vec::new();
-        let directions = [(-1, 0), (1, 0), (0, -1), (0, 1)];
-        for (dr, dc) in directions.iter() {
-            let new_row = row as i32 + dr;
-            let new_col = col as i32 + dc;
-            if new_row >= 0
-                && new_row < self.board_size as i32
-                && new_col >= 0
-                && new_col < self.board_size as i32
-            {
-                neighbors.push((new_row as usize, new_col as usize));
-            }
+    fn is_valid_move(&self, row: usize, col: usize) -> bool {
+        !self.game_over && self.board.is_legal(row, col, self.current_player)
+    }
+
+    fn make_move(&mut self, row: usize, col: usize) -> bool {
+        if !self.is_valid_move(row, col) {
+            return false;
+        }
+        self.last_move = Some((row, col));
+        self.moves.push((self.current_player, Some((row, col))));
+
+        // Place the stone and capture opponent stones
+        let captured = self.board.play(row, col, self.current_player);
+        self.last_captured = captured;
+        match self.current_player {
+            Player::Black => self.captured_white += captured,
+            Player::White => self.captured_black += captured,
         }
-        neighbors
+        self.current_player = self.current_player.other();
+        true
     }
 
-    fn get_group(&self, row: usize, col: usize, stone: Stone) -> HashSet<(usize, usize)> {
-        let mut group = HashSet::new();
-        let mut stack = vec![(row, col)];
-        while let Some((r, c)) = stack.pop() {
-            if group.contains(&(r, c)) || self.board[r][c] != stone {
-                continue;
-            }
-            group.insert((r, c));
-            for (nr, nc) in self.get_neighbors(r, c) {
-                if !group.contains(&(nr, nc)) && self.board[nr][nc] == stone {
-                    stack.push((nr, nc));
-                }
-            }
+    fn pass_turn(&mut self) {
+        self.last_captured = 0;
+        self.board.pass();
+        self.moves.push((self.current_player, None));
+        self.current_player = self.current_player.other();
+    }
+
+    fn empty_board(&self) -> Board {
+        if self.toroidal {
+            Board::toroidal(self.board_size)
+        } else {
+            Board::new(self.board_size)
         }
-        group
     }
 
-    fn has_liberties(&self, row: usize, col: usize) -> bool {
-        let stone = self.board[row][col];
-        if stone == Stone::Empty {
-            return true;
+    fn setup_board(&self) -> Board {
+        let mut board = self.empty_board();
+        for &(stone, (row, col)) in &self.setup {
+            board[row][col] = stone;
         }
-        let group = self.get_group(row, col, stone);
-        for &(r, c) in &group {
-            for (nr, nc) in self.get_neighbors(r, c) {
-                if self.board[nr][nc] == Stone::Empty {
-                    return true;
+        board
+    }
+
+    // Every move of the game with the seconds it took
+    fn timed_moves(&self) -> Vec<(Move, f32)> {
+        let nodes: Vec<usize> = self
+            .tree
+            .path(self.tree.current())
+            .into_iter()
+            .filter(|&id| self.tree.node(id).played.is_some())
+            .collect();
+        self.moves
+            .iter()
+            .enumerate()
+            .map(|(index, &played)| {
+                let seconds = nodes.get(index).and_then(|&id| self.tree.node(id).seconds);
+                (played, seconds.unwrap_or(0.0))
+            })
+            .collect()
+    }
+
+    // What a `Sync` of the first `count` moves carries, as this copy of the game has them
+    fn sync_checksum(&self, count: usize) -> u64 {
+        let to_move = self
+            .moves
+            .get(count)
+            .map_or(self.current_player, |&(player, _)| player);
+        protocol::checksum(self.setup_board(), to_move, &self.timed_moves()[..count])
+    }
+
+    // Returns to the setup position and replays `moves` on top of it
+    fn rebuild(&mut self, moves: &[Move]) {
+        self.staged_move = None;
+        self.restart_thinking();
+        self.dead_stones = None;
+        self.territory_marks.clear();
+        self.board = self.setup_board();
+        self.moves.clear();
+        (self.captured_black, self.captured_white) = self.setup_captures;
+        self.last_move = None;
+        self.last_captured = 0;
+        self.current_player = moves
+            .first()
+            .map_or(self.start_player, |&(player, _)| player);
+        for &(player, point) in moves {
+            self.current_player = player;
+            match point {
+                Some((row, col)) => {
+                    self.make_move(row, col);
                 }
+                None => self.pass_turn(),
             }
         }
-        false
     }
 
-    fn capture_stones(&mut self, opponent: Stone) -> u32 {
-        let mut captured = 0;
-        let mut to_remove = Vec::new();
-        for row in 0..self.board_size {
-            for col in 0..self.board_size {
-                if self.board[row][col] == opponent && !self.has_liberties(row, col) {
-                    let group = self.get_group(row, col, opponent);
-                    for &(r, c) in &group {
-                        to_remove.push((r, c));
-                    }
-                    captured += group.len() as u32;
+    // Plays a move on the board and records it in the game tree
+    fn advance(&mut self, (player, point): Move) -> bool {
+        self.current_player = player;
+        let was_ended = self.game_ended();
+        let captures_before = self.captured_black + self.captured_white;
+        let taken = match (&self.hidden, point) {
+            (Some(_), Some((row, col))) => self.board.captured_by(row, col, player),
+            _ => Vec::new(),
+        };
+        let played = match point {
+            Some((row, col)) => self.make_move(row, col),
+            None => {
+                self.pass_turn();
+                true
+            }
+        };
+        if played {
+            // Scripts comment on a move when it is first played, not when it is played again
+            #[cfg(not(target_arch = "wasm32"))]
+            let fresh = !self
+                .tree
+                .node(self.tree.current())
+                .children
+                .iter()
+                .any(|&child| self.tree.node(child).played == Some((player, point)));
+            let node = self.tree.play((player, point));
+            let seconds = self.thinking_seconds();
+            // Moves replayed from the record keep the time they were first played with; every move
+            // of a network game is played live, also the same one again after an undo
+            if (self.tree.node(node).seconds.is_none() || self.lan.is_some())
+                && self.guess_record.is_none()
+            {
+                self.tree.set_seconds(node, (seconds * 10.0).round() / 10.0);
+            }
+            self.restart_thinking();
+            self.staged_move = None;
+
+            let captured = self.captured_black + self.captured_white - captures_before;
+            let vertex = match point {
+                Some(point) if !self.is_secret(point) => {
+                    notation::format_point(point, self.board_size)
                 }
+                Some(_) => "a hidden stone".to_string(),
+                None => "pass".to_string(),
+            };
+            self.log.record(
+                Kind::Move,
+                format!(
+                    "{} {}: {} after {:.1} s, {} captured",
+                    self.moves.len(),
+                    gtp::color(player),
+                    vertex,
+                    seconds,
+                    captured
+                ),
+            );
+            #[cfg(not(target_arch = "wasm32"))]
+            if fresh {
+                self.run_move_hooks(node);
+            }
+            if let Some(hidden) = &mut self.hidden
+                && !hidden.reveal_around(&self.board, &taken).is_empty()
+            {
+                self.file_status = Some(tr("The capture brought a hidden stone to light"));
             }
+            self.check_capture_goal(player);
+            if !was_ended && self.game_ended() {
+                self.sounds.push(Sound::GameEnd);
+            } else if captured >= 2 {
+                self.sounds.push(Sound::Capture);
+            } else if point.is_some() {
+                self.sounds.push(Sound::Stone);
+            }
+            self.announcement = Some(self.describe_last_move());
         }
-        for (r, c) in to_remove {
-            self.board[r][c] = Stone::Empty;
+        played
+    }
+
+    // Ends a game of Capture Go once `player` has taken enough stones
+    fn check_capture_goal(&mut self, player: Player) {
+        let captures = match player {
+            Player::Black => self.captured_white,
+            Player::White => self.captured_black,
+        };
+        if self.capture_goal == 0 || self.game_over || captures < self.capture_goal {
+            return;
         }
-        captured
+        self.info.result = format!("{}+", gtp::color(player));
+        self.game_over = true;
+        self.file_status = Some(if captures == 1 {
+            trf("{} captured a stone and wins", &[&player_name(player)])
+        } else {
+            trf(
+                "{} captured {} stones and wins",
+                &[&player_name(player), &captures],
+            )
+        });
     }
 
-    fn would_capture_opponent(&self, row: usize, col: usize, player: Player) -> bool {
-        let opponent_stone = player.other().to_stone();
-        for (nr, nc) in self.get_neighbors(row, col) {
-            if self.board[nr][nc] == opponent_stone {
-                // Check if this opponent group would have no liberties after our move
-                if self.would_group_be_captured(nr, nc, opponent_stone, row, col) {
-                    return true;
-                }
-            }
+    // Whether the stones are hidden; the last move still shows for a moment after it is played
+    fn stones_hidden(&self) -> bool {
+        self.blind && !self.blind_revealed && !self.game_ended()
+    }
+
+    // The overlay would give away stones the players are not to see
+    fn can_debug(&self) -> bool {
+        !self.stones_hidden() && (self.hidden.is_none() || self.game_ended())
+    }
+
+    // A finished game, or one on the game server that others may want to follow
+    fn can_share(&self) -> bool {
+        !self.moves.is_empty()
+            && (self.game_ended() || matches!(self.lan_peer, Some(lan::Peer::Room { .. })))
+            && !self.stones_hidden()
+            && (self.hidden.is_none() || self.game_ended())
+    }
+
+    // The page shared links open: the one in the settings, or on the web this page itself
+    fn share_viewer(&self) -> String {
+        let viewer = self.new_game.share_viewer.trim();
+        #[cfg(target_arch = "wasm32")]
+        if viewer.is_empty() {
+            return web_sys::window()
+                .and_then(|window| window.location().href().ok())
+                .unwrap_or_default();
         }
-        false
+        viewer.to_string()
     }
 
-    fn would_group_be_captured(
-        &self,
-        group_row: usize,
-        group_col: usize,
-        group_stone: Stone,
-        new_stone_row: usize,
-        new_stone_col: usize,
-    ) -> bool {
-        let group = self.get_group(group_row, group_col, group_stone);
-        for &(r, c) in &group {
-            for (nr, nc) in self.get_neighbors(r, c) {
-                // If there's an empty liberty that's not where we're placing our stone
-                if self.board[nr][nc] == Stone::Empty
-                    && !(nr == new_stone_row && nc == new_stone_col)
-                {
-                    return false;
-                }
-            }
+    fn open_share(&mut self) {
+        self.share = Some(share::Shared::new(&self.share_viewer(), &self.to_sgf()));
+    }
+
+    // A stone of Hidden-move Go that has not come to light yet
+    fn is_secret(&self, point: (usize, usize)) -> bool {
+        !self.game_ended()
+            && self
+                .hidden
+                .as_ref()
+                .is_some_and(|hidden| hidden.is_hidden(point))
+    }
+
+    fn placing_hidden(&self) -> bool {
+        self.hidden
+            .as_ref()
+            .is_some_and(|hidden| hidden.placing().is_some())
+    }
+
+    fn announce_hidden_placing(&mut self) {
+        if let Some((player, left)) = self.hidden.as_ref().and_then(hidden::Hidden::placing) {
+            self.file_status = Some(trf(
+                "{} places a hidden stone ({} to go) while {} looks away",
+                &[&player_name(player), &left, &player_name(player.other())],
+            ));
+        } else if self.hidden.is_some() {
+            self.file_status = Some(tr("The hidden stones are placed"));
         }
-        true
     }
 
-    fn would_be_suicide(&self, row: usize, col: usize, player: Player) -> bool {
-        let player_stone = player.to_stone();
-        // Check if placing the stone would create a group with no liberties
-        // First, check direct liberties (empty adjacent spots)
-        for (nr, nc) in self.get_neighbors(row, col) {
-            if self.board[nr][nc] == Stone::Empty {
-                return false; // Has at least one liberty
-            }
+    // Puts down a hidden stone before the game, as a setup stone nobody sees
+    fn place_hidden(&mut self, point: (usize, usize)) {
+        let Some((player, _)) = self.hidden.as_ref().and_then(hidden::Hidden::placing) else {
+            return;
+        };
+        if self.board[point.0][point.1] != Stone::Empty {
+            self.file_status = Some(tr("Choose another point"));
+            return;
         }
-        // Check if we can connect to a friendly group that has liberties
-        for (nr, nc) in self.get_neighbors(row, col) {
-            if self.board[nr][nc] == player_stone {
-                // Check if this friendly group would still have liberties after our move
-                if self.would_friendly_group_have_liberties(nr, nc, player_stone, row, col) {
-                    return false;
-                }
-            }
+        self.setup.push((player.to_stone(), point));
+        if let Some(hidden) = &mut self.hidden {
+            hidden.place(point);
         }
-        true
+        self.rebuild(&[]);
+        self.announce_hidden_placing();
     }
 
-    fn would_friendly_group_have_liberties(
-        &self,
-        group_row: usize,
-        group_col: usize,
-        group_stone: Stone,
-        new_row: usize,
-        new_col: usize,
-    ) -> bool {
-        let group = self.get_group(group_row, group_col, group_stone);
+    // A move onto a hidden stone, which comes to light; the player to move plays again
+    fn run_into_hidden(&mut self, point: (usize, usize)) {
+        if let Some(owner) = self.hidden.as_mut().and_then(|hidden| hidden.reveal(point)) {
+            self.file_status = Some(trf(
+                "{} ran into a hidden {} stone and plays again",
+                &[&player_name(owner.other()), &player_name(owner)],
+            ));
+        }
+    }
 
-        // Check for empty spots (but not where we're placing the new stone)
-        for &(r, c) in &group {
-            for (nr, nc) in self.get_neighbors(r, c) {
-                if self.board[nr][nc] == Stone::Empty && !(nr == new_row && nc == new_col) {
-                    return true;
-                }
+    // Whether a move fails for want of liberties taken by hidden stones next to it
+    fn hidden_blocks(&self, (row, col): (usize, usize)) -> bool {
+        self.board[row][col] == Stone::Empty
+            && self.board.would_be_suicide(row, col, self.current_player)
+            && self
+                .board
+                .get_neighbors(row, col)
+                .into_iter()
+                .any(|point| self.is_secret(point))
+    }
+
+    fn reveal_blocking(&mut self, (row, col): (usize, usize)) {
+        let neighbors = self.board.get_neighbors(row, col);
+        if let Some(hidden) = &mut self.hidden {
+            for point in neighbors {
+                hidden.reveal(point);
             }
         }
+        self.file_status = Some(tr("Hidden stones next to the point came to light"));
+    }
 
-        // Check the new stone's position for additional liberties
-        for (nr, nc) in self.get_neighbors(new_row, new_col) {
-            if self.board[nr][nc] == Stone::Empty {
-                return true;
-            }
+    fn start_trial(&mut self) {
+        self.trial = Some(self.tree.clone());
+    }
+
+    // Drops every move tried out and returns to where the trial started
+    fn end_trial(&mut self) {
+        if let Some(tree) = self.trial.take() {
+            self.tree = tree;
+            self.go_to_node(self.tree.current());
         }
-        false
     }
 
-    fn is_valid_move(&self, row: usize, col: usize) -> bool {
-        if self.game_over || self.board[row][col] != Stone::Empty {
-            return false;
+    // Keeps the moves tried out as a branch named after `branch_name`, with their comments and
+    // markup, and returns to where the trial started
+    fn keep_trial(&mut self) {
+        let Some(tree) = self.trial.take() else {
+            return;
+        };
+        let start = tree.current();
+        let game_line = self.tree.path(start);
+        if let Some(branch) = self
+            .tree
+            .path(self.tree.current())
+            .into_iter()
+            .find(|id| !game_line.contains(id))
+        {
+            let name = std::mem::take(&mut self.branch_name);
+            self.tree.set_name(branch, name.trim().to_string());
         }
+        self.go_to_node(start);
+    }
 
-        // Check if the move would capture opponent stones
-        let would_capture = self.would_capture_opponent(row, col, self.current_player);
+    fn score(&self) -> Option<scoring::Score> {
+        let dead = self.dead_stones.as_ref()?;
+        let mut score = scoring::score(
+            &self.board,
+            dead,
+            self.info.rules,
+            self.info.komi,
+            self.captured_black,
+            self.captured_white,
+        );
+        score.reassign(&self.territory_marks);
+        Some(score)
+    }
 
-        // If we wouldn't capture anything, check if it would be suicide
-        if !would_capture && self.would_be_suicide(row, col, self.current_player) {
-            return false;
-        }
-        true
+    // Starts counting the game, with no stones marked dead yet
+    fn start_count(&mut self) {
+        self.dead_stones = Some(HashSet::new());
+        self.territory_marks.clear();
     }
 
-    fn make_move(&mut self, row: usize, col: usize) -> bool {
-        if !self.is_valid_move(row, col) {
-            return false;
+    // Points can be given by hand only off the network, as the other side would not see it
+    fn can_mark_territory(&self) -> bool {
+        self.lan.is_none()
+    }
+
+    // Gives an empty point to the next of Black, White and neither, after the side it has
+    fn cycle_territory(&mut self, (row, col): (usize, usize)) {
+        if self.board[row][col] != Stone::Empty || !self.can_mark_territory() {
+            return;
         }
-        self.board[row][col] = self.current_player.to_stone();
-        self.last_move = Some((row, col));
+        let Some(score) = self.score() else {
+            return;
+        };
+        let owner = match score.territory[row][col] {
+            Stone::Black => Stone::White,
+            Stone::White => Stone::Empty,
+            Stone::Empty => Stone::Black,
+        };
+        self.territory_marks.insert((row, col), owner);
+    }
 
-        // Capture opponent stones
-        let opponent_stone = self.current_player.other().to_stone();
-        let captured = self.capture_stones(opponent_stone);
-        match self.current_player {
-            Player::Black => self.captured_white += captured,
-            Player::White => self.captured_black += captured,
+    // Marks or unmarks the whole group at `point` as dead
+    fn toggle_dead(&mut self, (row, col): (usize, usize)) {
+        let stone = self.board[row][col];
+        let Some(dead) = &mut self.dead_stones else {
+            return;
+        };
+        if stone == Stone::Empty {
+            return;
+        }
+        let group = self.board.get_group(row, col, stone);
+        if dead.contains(&(row, col)) {
+            dead.retain(|point| !group.contains(point));
+        } else {
+            dead.extend(group);
         }
-        self.current_player = self.current_player.other();
-        true
     }
 
-    fn pass_turn(&mut self) {
-        self.current_player = self.current_player.other();
+    // Recounts stones plus surrounded points once the game has moved on far enough
+    fn update_area_count(&mut self) {
+        let current = self.moves.len();
+        let stale = self
+            .area_count
+            .is_none_or(|(counted_at, _, _)| counted_at.abs_diff(current) >= self.area_interval);
+        if stale {
+            let estimate = estimate::estimate(&self.board);
+            self.area_count = Some((current, estimate.black, estimate.white));
+        }
     }
 
-    // Actual GUI functions
-    fn show_options(&mut self, ctx: &egui::Context) {
-        egui::CentralPanel::default().show(ctx, |ui| {
-            ui.heading("Go Game");
+    fn can_edit_position(&self) -> bool {
+        self.tree.node(GameTree::ROOT).children.is_empty()
+            && self.guess_record.is_none()
+            && self.problem.is_none()
+            && self.trial.is_none()
+    }
 
-            egui::ComboBox::from_label("Board Size")
-                .selected_text(format!("{} x {}", &self.board_size, &self.board_size))
-                .show_ui(ui, |ui| {
-                    for &selected_size in consts::VALID_BOARD_SIZES {
-                        let is_selected: bool = self.board_size == selected_size;
-                        let label = format!("{} x {}", selected_size, selected_size);
+    // Puts `stone` on the setup position, or clears the point if it already holds that stone
+    fn edit_point(&mut self, point: (usize, usize), stone: Stone) {
+        let (row, col) = point;
+        let stone = if self.board[row][col] == stone {
+            Stone::Empty
+        } else {
+            stone
+        };
+        self.setup.retain(|&(_, setup_point)| setup_point != point);
+        if stone != Stone::Empty {
+            self.setup.push((stone, point));
+        }
+        self.rebuild(&[]);
+        self.resync_engine();
+    }
 
-                        if ui.selectable_label(is_selected, label).clicked() {
-                            self.board_size = selected_size;
-                        }
-                    }
-                });
+    // Puts the presenter's stone on the board, or takes away the stone already there; captures
+    // happen but nothing is checked or recorded
+    fn present_point(&mut self, (row, col): (usize, usize)) {
+        let Some(presentation) = &mut self.presentation else {
+            return;
+        };
+        if self.board[row][col] != Stone::Empty {
+            self.board[row][col] = Stone::Empty;
+            return;
+        }
+        let player = presentation.color;
+        self.board.play(row, col, player);
+        if presentation.alternate {
+            presentation.color = player.other();
+        }
+    }
 
-            ui.separator();
+    fn toggle_presentation(&mut self) {
+        if self.presentation.take().is_some() {
+            // Put back the position the record is at
+            self.go_to_node(self.tree.current());
+        } else {
+            self.staged_move = None;
+            self.presentation = Some(Presentation {
+                color: self.current_player,
+                alternate: true,
+            });
+        }
+    }
 
-            if ui.button("Start Game").clicked() {
-                self.state = AppState::Game;
-            }
-        });
+    fn can_present(&self) -> bool {
+        self.presentation.is_some()
+            || (self.edit_stone.is_none()
+                && self.markup_tool.is_none()
+                && self.dead_stones.is_none()
+                && self.guess_record.is_none())
     }
 
-    fn show_game(&mut self, ctx: &egui::Context) {
-        egui::CentralPanel::default().show(ctx, |ui| {
-            ui.heading("Go Game");
-            ui.horizontal(|ui| {
-                ui.label(format!("Current Player: {:?}", self.current_player));
-                ui.separator();
-                ui.label(format!(
-                    "Captured - Black: {}, White: {}",
-                    self.captured_black, self.captured_white
-                ));
-                if ui.button("Pass").clicked() {
-                    self.pass_turn();
-                }
-                if ui.button("Reset Game").clicked() {
-                    self.reset();
-                }
-            });
-            ui.separator();
+    fn can_mark_up(&self) -> bool {
+        self.markup_tool.is_some()
+            || (self.edit_stone.is_none()
+                && self.dead_stones.is_none()
+                && self.guess_record.is_none())
+    }
 
-            // Calculate board dimensions
-            let board_size = consts::CELL_SIZE * (self.board_size as f32 + 1.0);
-            let (response, painter) =
-                ui.allocate_painter(egui::Vec2::splat(board_size), egui::Sense::click());
-            let board_rect = response.rect;
-            let top_left = board_rect.min + egui::Vec2::splat(consts::CELL_SIZE * 0.5);
+    fn mark_point(&mut self, point: (usize, usize), tool: markup::Tool) {
+        let current = self.tree.current();
+        let mark = tool.next_mark(&self.tree.node(current).markup);
+        self.tree.toggle_mark(current, point, mark);
+    }
 
-            // Draw grid lines
-            let line_color = egui::Color32::from_rgb(101, 67, 33);
-            for i in 0..self.board_size {
-                let offset = i as f32 * consts::CELL_SIZE;
-                // Horizontal lines
-                painter.line_segment(
-                    [
-                        top_left + egui::Vec2::new(0.0, offset),
-                        top_left
-                            + egui::Vec2::new(
-                                (self.board_size - 1) as f32 * consts::CELL_SIZE,
-                                offset,
-                            ),
-                    ],
-                    egui::Stroke::new(1.0, line_color),
-                );
-                // Vertical lines
-                painter.line_segment(
-                    [
-                        top_left + egui::Vec2::new(offset, 0.0),
-                        top_left
-                            + egui::Vec2::new(
-                                offset,
-                                (self.board_size - 1) as f32 * consts::CELL_SIZE,
-                            ),
-                    ],
-                    egui::Stroke::new(1.0, line_color),
-                );
-            }
+    fn pass_clicked(&mut self) {
+        self.staged_move = None;
+        if self.capture_goal > 0 && !self.game_over {
+            self.file_status = Some(tr("There is no passing in Capture Go"));
+        } else if self.placing_hidden() {
+            self.announce_hidden_placing();
+        } else if self.guess_record.is_some() {
+            self.guess_move(None);
+        } else if self.problem.is_some() {
+            self.solve_move(None);
+        } else {
+            self.play(None);
+        }
+    }
 
-            // Draw star points (handicap points)
-            let star_points: &[(usize, usize)];
-            if self.board_size == consts::VALID_BOARD_SIZES[0] {
-                star_points = consts::STAR_POINTS_9X9;
-            } else if self.board_size == consts::VALID_BOARD_SIZES[1] {
-                star_points = consts::STAR_POINTS_13X13;
-            } else {
-                star_points = consts::STAR_POINTS_19X19;
-            }
+    // Plays a move typed as a coordinate such as Q16 or "pass"
+    fn enter_typed_move(&mut self) {
+        let text = std::mem::take(&mut self.move_input);
+        self.play_entered(&text);
+    }
 
-            for &(row, col) in star_points {
-                let pos = top_left
-                    + egui::Vec2::new(
-                        col as f32 * consts::CELL_SIZE,
-                        row as f32 * consts::CELL_SIZE,
-                    );
-                painter.circle_filled(pos, 3.0, line_color);
+    fn play_entered(&mut self, text: &str) {
+        if !self.human_to_move() || self.edit_stone.is_some() || self.dead_stones.is_some() {
+            return;
+        }
+        match notation::parse_point(text, self.board_size) {
+            Some(Some(point)) if self.is_valid_move(point.0, point.1) || self.is_secret(point) => {
+                self.click_point(point)
             }
+            Some(None) => self.pass_clicked(),
+            _ => self.file_status = Some(trf("Cannot play \"{}\"", &[&text.trim()])),
+        }
+    }
 
-            // Draw stones
-            for row in 0..self.board_size {
-                for col in 0..self.board_size {
-                    let stone = self.board[row][col];
-                    if stone != Stone::Empty {
-                        let pos = top_left
-                            + egui::Vec2::new(
-                                col as f32 * consts::CELL_SIZE,
-                                row as f32 * consts::CELL_SIZE,
-                            );
-                        let stone_color = match stone {
-                            Stone::Black => egui::Color32::BLACK,
-                            Stone::White => egui::Color32::WHITE,
-                            Stone::Empty => continue,
-                        };
-                        // Draw stone shadow
-                        painter.circle_filled(
-                            pos + egui::Vec2::new(1.0, 1.0),
-                            consts::STONE_RADIUS,
-                            egui::Color32::from_rgba_premultiplied(0, 0, 0, 100),
-                        );
-                        // Draw stone
-                        painter.circle_filled(pos, consts::STONE_RADIUS, stone_color);
-                        // Draw stone border
-                        painter.circle_stroke(
-                            pos,
-                            consts::STONE_RADIUS,
-                            egui::Stroke::new(1.0, egui::Color32::DARK_GRAY),
-                        );
-                        // Highlight last move
-                        if let Some((last_row, last_col)) = self.last_move {
-                            if row == last_row && col == last_col {
-                                painter.circle_stroke(
-                                    pos,
-                                    consts::STONE_RADIUS + 3.0,
-                                    egui::Stroke::new(2.0, egui::Color32::RED),
-                                );
-                            }
-                        }
+    // Carries out a phrase the speech recognizer heard. While presenting, a point puts down the
+    // next stone there.
+    #[cfg(not(target_arch = "wasm32"))]
+    fn voice_command(&mut self, phrase: &str) {
+        match voice::parse(phrase, self.board_size) {
+            Some(voice::Said::Move(player, vertex)) => {
+                let point = notation::parse_point(&vertex, self.board_size);
+                if self.presentation.is_some() {
+                    if let Some(Some(point)) = point {
+                        self.present_point(point);
                     }
+                } else if player.is_some_and(|player| player != self.current_player) {
+                    self.file_status = Some(trf(
+                        "Heard \"{}\", but it is {}'s turn",
+                        &[&phrase.trim(), &player_name(self.current_player)],
+                    ));
+                } else {
+                    self.play_entered(&vertex);
                 }
             }
-
-            // Handle clicks
-            if response.clicked() {
-                if let Some(pos) = response.interact_pointer_pos() {
-                    let rel_pos = pos - top_left;
-                    let col = ((rel_pos.x + consts::CELL_SIZE * 0.5) / consts::CELL_SIZE) as usize;
-                    let row = ((rel_pos.y + consts::CELL_SIZE * 0.5) / consts::CELL_SIZE) as usize;
-                    if row < self.board_size && col < self.board_size {
-                        self.make_move(row, col);
-                    }
-                }
+            Some(voice::Said::Undo) if self.can_undo() => self.undo(),
+            Some(voice::Said::Back) => self.go_to_move(self.moves.len().saturating_sub(1)),
+            Some(voice::Said::Forward) => self.go_to_move(self.moves.len() + 1),
+            Some(voice::Said::Undo) | None => {
+                self.file_status = Some(trf("Did not understand \"{}\"", &[&phrase.trim()]))
             }
+        }
+    }
 
-            // Show move validity hint
-            if let Some(hover_pos) = response.hover_pos() {
-                let rel_pos = hover_pos - top_left;
-                let col = ((rel_pos.x + consts::CELL_SIZE * 0.5) / consts::CELL_SIZE) as usize;
-                let row = ((rel_pos.y + consts::CELL_SIZE * 0.5) / consts::CELL_SIZE) as usize;
-                if row < self.board_size
-                    && col < self.board_size
-                    && self.board[row][col] == Stone::Empty
-                {
-                    let pos = top_left
-                        + egui::Vec2::new(
-                            col as f32 * consts::CELL_SIZE,
-                            row as f32 * consts::CELL_SIZE,
-                        );
-                    let is_valid = self.is_valid_move(row, col);
-                    let preview_color = match self.current_player {
-                        Player::Black => egui::Color32::from_rgba_premultiplied(0, 0, 0, 100),
-                        Player::White => egui::Color32::from_rgba_premultiplied(255, 255, 255, 150),
-                    };
-                    if is_valid {
-                        painter.circle_filled(pos, consts::STONE_RADIUS * 0.7, preview_color);
-                    }
-                }
-            }
-        });
+    // A board click that is not just staging a move
+    fn click_point(&mut self, point: (usize, usize)) {
+        self.staged_move = None;
+        let (row, col) = point;
+        if self.placing_hidden() {
+            self.place_hidden(point);
+        } else if self.is_secret(point) {
+            self.run_into_hidden(point);
+        } else if self.hidden_blocks(point) {
+            self.reveal_blocking(point);
+        } else if self.stones_hidden() && self.board[row][col] != Stone::Empty {
+            self.file_status = Some(tr("There is already a stone there"));
+        } else if self.guess_record.is_some() {
+            self.guess_move(Some(point));
+        } else if self.problem.is_some() {
+            self.solve_move(Some(point));
+        } else if self.new_game.warn_self_atari
+            && self.hidden.is_none()
+            && self.is_valid_move(row, col)
+            && self
+                .board
+                .would_be_self_atari(row, col, self.current_player)
+        {
+            self.self_atari_prompt = Some(point);
+        } else {
+            self.play(Some(point));
+        }
     }
-}
 
-impl eframe::App for GoBoard {
-    fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
-        match self.state {
-            AppState::Options => self.show_options(ctx),
-            AppState::Game => self.show_game(ctx),
+    // A move chosen by the user; away from the end of the line this starts a variation
+    fn play(&mut self, point: Option<(usize, usize)>) {
+        if self.paused.is_some() && self.trial.is_none() {
+            self.file_status = Some(tr("Resume the clocks to play"));
+            return;
+        }
+        if let Some(seat) = self.wrong_partner() {
+            self.file_status = Some(self.wrong_partner_warning(seat));
+            return;
         }
+        self.advance((self.current_player, point));
     }
 
-    fn save(&mut self, _storage: &mut dyn eframe::Storage) {}
+    // The seat in pair go at this computer whose turn it is: 0 and 2 for Black, 1 and 3 for White
+    fn pair_turn(&self) -> usize {
+        let side = match self.current_player {
+            Player::Black => 0,
+            Player::White => 1,
+        };
+        side + 2 * self.rengo_turn()
+    }
 
-    fn on_exit(&mut self, _gl: Option<&eframe::glow::Context>) {}
+    // The partner who says they are at the board when it is the other one's turn
+    fn wrong_partner(&self) -> Option<usize> {
+        let (moves, seat) = self.pair_seat?;
+        (self.pair.is_some()
+            && self.trial.is_none()
+            && moves == self.moves.len()
+            && seat != self.pair_turn())
+        .then_some(seat)
+    }
 
-    fn auto_save_interval(&self) -> std::time::Duration {
-        std::time::Duration::from_secs(30)
+    fn wrong_partner_warning(&self, seat: usize) -> String {
+        let pair = self.pair.as_ref().map_or(&[][..], |pair| &pair[..]);
+        let name = |seat: usize| pair.get(seat).cloned().unwrap_or_default();
+        trf(
+            "It is {}'s move, not {}'s",
+            &[&name(self.pair_turn()), &name(seat)],
+        )
     }
 
-    fn clear_color(&self, _visuals: &egui::Visuals) -> [f32; 4] {
-        // NOTE: a bright gray makes the shadows of the windows look weird.
-        // We use a bit of transparency so that if the user switches on the
-        // `transparent()` option they get immediate results.
-        egui::Color32::from_rgba_unmultiplied(12, 12, 12, 180).to_normalized_gamma_f32()
+    fn can_navigate(&self) -> bool {
+        self.guess_record.is_none()
+            && self.problem.is_none()
+            && self.engine_pending.is_none()
+            && ((self.lan.is_none() && self.lan_away.is_none()) || self.game_over)
+    }
 
-        // _visuals.window_fill() would also be a natural choice
+    fn can_undo(&self) -> bool {
+        !self.moves.is_empty() && !self.game_over && self.can_navigate()
     }
 
-    fn persist_egui_memory(&self) -> bool {
-        true
+    fn can_redo(&self) -> bool {
+        self.tree.next(self.tree.current()).is_some() && self.can_navigate()
     }
 
-    fn raw_input_hook(&mut self, _ctx: &egui::Context, _raw_input: &mut egui::RawInput) {}
-}
+    fn undo(&mut self) {
+        if !self.can_undo() {
+            return;
+        }
+        let mut target = self.tree.current();
+        // Against an engine, take back its reply together with the player's own move
+        while let (Some((player, _)), Some(parent)) =
+            (self.tree.node(target).played, self.tree.node(target).parent)
+        {
+            target = parent;
+            if self.engine_color != Some(player) || self.trial.is_some() {
+                break;
+            }
+        }
+        self.go_to_node(target);
+    }
 
-fn main() -> Result<(), eframe::Error> {
-    let options = eframe::NativeOptions {
-        viewport: egui::ViewportBuilder::default()
-            .with_inner_size(consts::WINDOW_SIZE)
-            .with_title(consts::TITLE),
-        ..Default::default()
-    };
-    eframe::run_native(
-        consts::TITLE,
-        options,
-        Box::new(|_cc| Ok(Box::new(GoBoard::new()))),
-    )
+    fn redo(&mut self) {
+        if !self.can_redo() {
+            return;
+        }
+        while let Some(next) = self.tree.next(self.tree.current()) {
+            let Some(played) = self.tree.node(next).played else {
+                break;
+            };
+            self.advance(played);
+            if self.human_to_move() {
+                break;
+            }
+        }
+    }
+
+    fn go_to_node(&mut self, id: usize) {
+        self.tree.go_to(id);
+        let moves = self.tree.moves_to(id);
+        self.rebuild(&moves);
+        self.resync_engine();
+    }
+
+    // Bookmarks belong to the game record, so not to moves only tried out or a game being guessed
+    fn can_bookmark(&self) -> bool {
+        self.trial.is_none() && self.guess_record.is_none()
+    }
+
+    // Bookmarks the position on the board under `bookmark_label`, or its move number
+    fn bookmark(&mut self) {
+        if !self.can_bookmark() {
+            return;
+        }
+        let label = std::mem::take(&mut self.bookmark_label);
+        let label = match label.trim() {
+            "" => trf("Move {}", &[&self.moves.len()]),
+            label => label.to_string(),
+        };
+        self.tree.set_bookmark(self.tree.current(), label);
+    }
+
+    fn go_to_move(&mut self, move_number: usize) {
+        if !self.can_navigate() {
+            return;
+        }
+        let line = self.tree.line();
+        self.go_to_node(line[move_number.min(line.len() - 1)]);
+    }
+
+    // Switches to the previous or next variation of the current move
+    fn switch_variation(&mut self, forward: bool) {
+        let current = self.tree.current();
+        let siblings = self.tree.siblings(current);
+        let Some(index) = siblings.iter().position(|&id| id == current) else {
+            return;
+        };
+        let target = if forward {
+            siblings.get(index + 1)
+        } else {
+            index.checked_sub(1).and_then(|index| siblings.get(index))
+        };
+        if let Some(&target) = target
+            && self.can_navigate()
+        {
+            self.go_to_node(target);
+        }
+    }
+
+    // An empty board of the selected size without handicap or opponent
+    fn clear_game(&mut self) {
+        self.rated = None;
+        self.head_to_head = false;
+        self.log = game_log::Log::default();
+        #[cfg(not(target_arch = "wasm32"))]
+        {
+            self.script_ended = false;
+        }
+        self.capture_goal = 0;
+        self.blind = false;
+        self.blind_revealed = false;
+        self.toroidal = false;
+        self.hidden = None;
+        self.pair = None;
+        self.pair_seat = None;
+        self.area_count = None;
+        self.board_cursor = None;
+        self.analysis = None;
+        self.figure_start = 0;
+        self.trial = None;
+        self.info = GameInfo::default();
+        self.setup.clear();
+        self.setup_captures = (0, 0);
+        self.edit_stone = None;
+        self.start_player = Player::Black;
+        self.game_over = false;
+        self.file_path = None;
+        self.file_status = None;
+        self.guess_record = None;
+        self.problem = None;
+        self.engine = None;
+        self.engine_color = None;
+        self.engine_pending = None;
+        self.lan = None;
+        self.lan_opponent = None;
+        self.lan_watching = false;
+        self.lan_spectators = 0;
+        self.lan_peer = None;
+        self.lan_reconnect = None;
+        self.lan_away = None;
+        self.lan_retry = None;
+        self.lan_catching_up = false;
+        self.lan_matched = None;
+        self.lan_correspondence = false;
+        self.lan_rengo = None;
+        self.lan_simul = false;
+        self.lan_plan = protocol::Plan::default();
+        #[cfg(not(target_arch = "wasm32"))]
+        {
+            self.lan_remembered = None;
+            self.sync_id = Some(sync_folder::new_game_id());
+            self.sync_written = None;
+        }
+        self.chat.clear();
+        self.chat_draft.clear();
+        self.chat_spectators = false;
+        self.chat_team = false;
+        self.undo_asked = None;
+        self.undo_offer = None;
+        self.lan_requested = false;
+        self.lan_pending = None;
+        self.paused = None;
+        self.tree = GameTree::default();
+        self.rebuild(&[]);
+        self.state = AppState::Game;
+        self.mark_saved();
+    }
+
+    fn start_game(&mut self) {
+        if self.new_game.opponent == Opponent::Ogs && self.ogs_game.is_none() {
+            self.file_status = Some(tr("Choose an OGS game first"));
+            return;
+        }
+        self.clear_game();
+        let settings = &self.new_game;
+        self.info.komi = settings.komi;
+        self.info.handicap = settings.handicap;
+        self.info.rules = settings.rules;
+        for player in [Player::Black, Player::White] {
+            let name = match (settings.opponent, player) {
+                (Opponent::Human, Player::Black) => &settings.black_profile,
+                (Opponent::Human, Player::White) => &settings.white_profile,
+                (Opponent::Engine | Opponent::Host, _) if player == settings.human_color => {
+                    &settings.profile
+                }
+                _ => continue,
+            };
+            if let Some(profile) = self.profiles.find(name) {
+                let (name, rank) = match player {
+                    Player::Black => (&mut self.info.black_name, &mut self.info.black_rank),
+                    Player::White => (&mut self.info.white_name, &mut self.info.white_rank),
+                };
+                *name = profile.name.trim().to_string();
+                *rank = profile.rank.trim().to_string();
+            }
+        }
+        // The other side of a network game would not know to stop at a capture
+        if matches!(settings.opponent, Opponent::Human | Opponent::Engine) {
+            self.capture_goal = settings.capture_goal;
+        }
+        self.blind = settings.blind;
+        // Engines and the other side of a network game only know the flat board
+        self.toroidal = settings.toroidal && settings.opponent == Opponent::Human;
+        let hidden_stones = settings.hidden_stones;
+        if hidden_stones > 0 && settings.opponent == Opponent::Human {
+            self.hidden = Some(hidden::Hidden::new(hidden_stones));
+        }
+        if settings.rengo && settings.opponent == Opponent::Human {
+            let pair = std::array::from_fn(|seat| {
+                let name = settings.pair_names[seat].trim();
+                if name.is_empty() {
+                    seat_name(seat)
+                } else {
+                    name.to_string()
+                }
+            });
+            self.info.black_name = format!("{} & {}", pair[0], pair[2]);
+            self.info.white_name = format!("{} & {}", pair[1], pair[3]);
+            self.pair = Some(pair);
+        }
+        self.info.time_limit = settings.main_time;
+        self.info.overtime = settings.overtime;
+        self.setup = board::handicap_points(self.board_size, settings.handicap)
+            .into_iter()
+            .map(|point| (Stone::Black, point))
+            .collect();
+        if !self.setup.is_empty() {
+            self.start_player = Player::White;
+        }
+        self.rebuild(&[]);
+        self.announce_hidden_placing();
+        match self.new_game.opponent {
+            Opponent::Human => {}
+            Opponent::Engine => {
+                if let Err(err) = self.start_engine() {
+                    self.file_status = Some(err.to_string());
+                }
+            }
+            Opponent::Host => {
+                self.lan_opponent = Some(self.new_game.human_color.other());
+                self.lan_requested = true;
+            }
+            Opponent::Join | Opponent::Room | Opponent::Ogs | Opponent::Igs => {
+                self.lan_requested = true
+            }
+        }
+        let settings = &self.new_game;
+        self.lan_simul = settings.opponent == Opponent::Room
+            && !settings.automatch
+            && settings.room_code.trim().is_empty()
+            && !settings.rengo
+            && settings.simul_boards > 1;
+        // Only normal games between known players count
+        let known = |name: &str| self.profiles.find(name).is_some();
+        let normal = self.capture_goal == 0
+            && !self.toroidal
+            && self.hidden.is_none()
+            && self.pair.is_none();
+        let profiles_here = settings.opponent == Opponent::Human
+            && known(&self.info.black_name)
+            && known(&self.info.white_name)
+            && self.info.black_name != self.info.white_name;
+        self.head_to_head = normal && profiles_here;
+        if self.head_to_head {
+            let record = self
+                .profiles
+                .head_to_head(&self.info.black_name, &self.info.white_name);
+            if !record.meetings.is_empty() {
+                self.file_status = Some(head_to_head_summary(
+                    &self.info.black_name,
+                    &self.info.white_name,
+                    &record,
+                ));
+            }
+        }
+        self.rated = match (settings.opponent, self.engine_color) {
+            (Opponent::Human, _) if profiles_here => Some(None),
+            (Opponent::Engine, Some(engine))
+                if known(self.name_of(engine.other()))
+                    && !self.name_of(engine).trim().is_empty() =>
+            {
+                Some(Some(engine))
+            }
+            _ => None,
+        }
+        .filter(|_| normal && settings.rated);
+        self.log.record(
+            Kind::Game,
+            format!(
+                "New {}x{} game, {} rules, komi {}, handicap {}: Black {} against White {}",
+                self.board_size,
+                self.board_size,
+                self.info.rules.name(),
+                self.info.komi,
+                self.info.handicap,
+                self.info.black_label(),
+                self.info.white_label()
+            ),
+        );
+        self.mark_saved();
+    }
+
+    // The name the record gives `player`
+    fn name_of(&self, player: Player) -> &str {
+        match player {
+            Player::Black => &self.info.black_name,
+            Player::White => &self.info.white_name,
+        }
+    }
+
+    // Moves the players' ratings and adds to their head-to-head record once the game has a
+    // result
+    fn keep_result(&mut self) {
+        if self.rated.is_none() && !self.head_to_head {
+            return;
+        }
+        let Some(score) = profiles::black_score(&self.info.result).filter(|_| self.game_over)
+        else {
+            return;
+        };
+        // Another tab may have finished a game since these were read
+        let path = paths::profiles_path();
+        let mut profiles = profiles::Profiles::load(&path);
+        let (black, white) = (self.info.black_name.trim(), self.info.white_name.trim());
+        if std::mem::take(&mut self.head_to_head) {
+            profiles.meetings.push(profiles::Meeting {
+                black: black.to_string(),
+                white: white.to_string(),
+                result: self.info.result.clone(),
+                handicap: self.info.handicap,
+                komi: self.info.komi,
+                day: problems::today(),
+            });
+        }
+        let mut kept = tr("Result kept for the head-to-head record");
+        if let Some(engine) = self.rated.take()
+            && profiles.rate((black, white), engine, self.info.handicap, score)
+        {
+            let shown: Vec<String> = [Player::Black, Player::White]
+                .into_iter()
+                .filter(|&player| Some(player) != engine)
+                .filter_map(|player| profiles.find(self.name_of(player)))
+                .map(|profile| format!("{} {:.0}", profile.name.trim(), profile.rating.value))
+                .collect();
+            kept = trf("Ratings now {}", &[&shown.join(", ")]);
+        }
+        let rated = match profiles.save(&path) {
+            Ok(()) => kept,
+            Err(err) => trf("Could not write the profiles: {}", &[&err]),
+        };
+        // After what ended the game
+        self.file_status = Some(match self.file_status.take() {
+            Some(status) => format!("{} — {}", status, rated),
+            None => rated,
+        });
+        self.profiles = profiles;
+    }
+
+    fn game_ended(&self) -> bool {
+        self.game_over
+            || self.moves.len() >= 2
+                && self
+                    .moves
+                    .iter()
+                    .rev()
+                    .take(2)
+                    .all(|&(_, point)| point.is_none())
+    }
+
+    // The game as the API serves it, with the stones of Hidden-move Go left out
+    #[cfg(not(target_arch = "wasm32"))]
+    fn api_state(&self) -> api::State {
+        let size = self.board_size;
+        let side = |player: Player| {
+            let (name, rank, captured) = match player {
+                Player::Black => (
+                    &self.info.black_name,
+                    &self.info.black_rank,
+                    self.captured_black,
+                ),
+                Player::White => (
+                    &self.info.white_name,
+                    &self.info.white_rank,
+                    self.captured_white,
+                ),
+            };
+            api::Side {
+                name: name.clone(),
+                rank: rank.clone(),
+                captured,
+                time_left: self.clock(player).map(|clock| clock.left()),
+            }
+        };
+        api::State {
+            size,
+            to_move: gtp::color(self.current_player).to_string(),
+            moves: self
+                .moves
+                .iter()
+                .map(|&(_, point)| match point {
+                    Some(point) => notation::format_point(point, size),
+                    None => "pass".to_string(),
+                })
+                .collect(),
+            board: (0..size)
+                .map(|row| {
+                    (0..size)
+                        .map(|col| match self.board[row][col] {
+                            _ if self.is_secret((row, col)) => '.',
+                            Stone::Black => 'X',
+                            Stone::White => 'O',
+                            Stone::Empty => '.',
+                        })
+                        .collect()
+                })
+                .collect(),
+            black: side(Player::Black),
+            white: side(Player::White),
+            komi: self.info.komi,
+            handicap: self.info.handicap,
+            rules: self.info.rules.name().to_string(),
+            result: Some(self.info.result.clone()).filter(|result| !result.is_empty()),
+            game_over: self.game_over,
+        }
+    }
+
+    // Plays a move sent through the API as a click on the board, taking the warning about
+    // self-atari as confirmed
+    #[cfg(not(target_arch = "wasm32"))]
+    fn api_move(&mut self, text: &str) -> Result<(), String> {
+        if !matches!(self.state, AppState::Game) || self.game_over {
+            return Err(tr("No game is being played"));
+        }
+        if !self.human_to_move() {
+            return Err(tr("It is not the turn of a player at this computer"));
+        }
+        let Some(point) = notation::parse_point(text, self.board_size) else {
+            return Err(trf("Cannot play \"{}\"", &[&text.trim()]));
+        };
+        if let Some((row, col)) = point
+            && !self.is_secret((row, col))
+            && let Err(err) = self.board.check_move(row, col, self.current_player)
+        {
+            return Err(err.to_string());
+        }
+        let (node, status) = (self.tree.current(), self.file_status.clone());
+        match point {
+            Some(point) => {
+                self.click_point(point);
+                if self.self_atari_prompt == Some(point) {
+                    self.self_atari_prompt = None;
+                    self.play(Some(point));
+                }
+            }
+            None => self.pass_clicked(),
+        }
+        if self.tree.current() == node {
+            return Err(self
+                .file_status
+                .clone()
+                .filter(|text| Some(text) != status.as_ref())
+                .unwrap_or_else(|| tr("The move was not played")));
+        }
+        Ok(())
+    }
+
+    // Starts another game with the same settings and the colors swapped
+    fn rematch(&mut self) {
+        let black = (self.info.black_name.clone(), self.info.black_rank.clone());
+        let white = (self.info.white_name.clone(), self.info.white_rank.clone());
+        self.new_game.human_color = self.new_game.human_color.other();
+        let settings = &mut self.new_game;
+        std::mem::swap(&mut settings.black_profile, &mut settings.white_profile);
+        self.start_game();
+        // The engine fills in its own name, the players keep theirs
+        if self.info.black_name.is_empty() {
+            (self.info.black_name, self.info.black_rank) = white;
+        }
+        if self.info.white_name.is_empty() {
+            (self.info.white_name, self.info.white_rank) = black;
+        }
+        self.mark_saved();
+    }
+
+    // Sets up the engine's board from scratch so that it matches the current position
+    fn send_position(&self, engine: &mut gtp::Engine) -> Result<(), gtp::Error> {
+        send_setup(engine, self.board_size, self.info.komi, &self.setup)?;
+        for &played in &self.moves {
+            engine.play(played, self.board_size)?;
+        }
+        Ok(())
+    }
+
+    fn resync_engine(&mut self) {
+        let Some(mut engine) = self.engine.take() else {
+            return;
+        };
+        match self.send_position(&mut engine) {
+            Ok(()) => {
+                self.engine = Some(engine);
+                self.engine_synced = self.moves.len();
+            }
+            Err(err) => {
+                self.file_status = Some(err.to_string());
+                self.engine_color = None;
+            }
+        }
+    }
+
+    fn start_engine(&mut self) -> Result<(), gtp::Error> {
+        let mut engine = gtp::Engine::start(&self.new_game.engine_command)?;
+        self.send_position(&mut engine)?;
+        let engine_color = self.new_game.human_color.other();
+        // Engines without time management refuse this, and simply play at their own pace
+        if self.info.is_timed() {
+            let _ = engine.time_settings(self.info.time_limit, self.info.overtime);
+        }
+        let name = engine.send("name").unwrap_or_default();
+        self.log.record(
+            Kind::Engine,
+            format!(
+                "Started \"{}\" ({}) as {}",
+                self.new_game.engine_command,
+                name,
+                gtp::color(engine_color)
+            ),
+        );
+        match engine_color {
+            Player::Black => self.info.black_name = name,
+            Player::White => self.info.white_name = name,
+        }
+        self.engine = Some(engine);
+        self.engine_color = Some(engine_color);
+        self.engine_synced = 0;
+        Ok(())
+    }
+
+    // While trying out moves the user plays both sides
+    fn human_to_move(&self) -> bool {
+        self.trial.is_some()
+            || (self.engine_color != Some(self.current_player)
+                && self.lan_opponent != Some(self.current_player)
+                && self.lan_rengo.is_none_or(|turn| self.rengo_turn() == turn)
+                && !self.lan_watching
+                && !self.lan_waiting())
+    }
+
+    // A board of a simultaneous game where the opponent waits for a move here
+    fn simul_waiting(&self) -> bool {
+        self.lan_simul
+            && self.lan.is_some()
+            && self.lan_opponent.is_some()
+            && !self.game_over
+            && self.human_to_move()
+    }
+
+    // Which of the two players of the side to move in pair go plays this move
+    fn rengo_turn(&self) -> usize {
+        self.moves
+            .iter()
+            .filter(|&&(player, _)| player == self.current_player)
+            .count()
+            % 2
+    }
+
+    // Whether a network game has been started but the two sides have not agreed on it yet, or
+    // a player's connection dropped and the game waits for them
+    fn lan_waiting(&self) -> bool {
+        self.lan_requested
+            || self.lan_pending.is_some()
+            || self.lan_away.is_some()
+            || (self.lan.is_some() && self.lan_opponent.is_none() && !self.lan_watching)
+    }
+
+    // Opens the connection of a network game once it is started, sends the moves played here
+    // and plays the ones that arrive
+    fn drive_lan(&mut self, ctx: &egui::Context) {
+        self.receive_account_replies();
+        if let Some(receiver) = &self.rooms_pending
+            && let Ok(result) = receiver.try_recv()
+        {
+            self.rooms_pending = None;
+            match result {
+                Ok(rooms) => self.rooms = rooms,
+                Err(err) => self.file_status = Some(trf("Could not list the rooms: {}", &[&err])),
+            }
+        }
+        if std::mem::take(&mut self.lan_requested) {
+            let settings = &self.new_game;
+            self.lan_peer = Some(match settings.opponent {
+                Opponent::Host => lan::Peer::Host {
+                    port: settings.lan_port,
+                    tls: settings.lan_tls,
+                },
+                Opponent::Room if settings.automatch => lan::Peer::Automatch {
+                    server: settings.server.clone(),
+                },
+                Opponent::Room => lan::Peer::Room {
+                    server: settings.server.clone(),
+                    code: settings.room_code.trim().to_string(),
+                },
+                Opponent::Ogs => lan::Peer::Ogs {
+                    game: self.ogs_game.unwrap_or_default(),
+                },
+                Opponent::Igs => lan::Peer::Igs {
+                    server: settings.igs_server.clone(),
+                    target: settings.igs_target.trim().to_string(),
+                },
+                _ => lan::Peer::Join {
+                    address: settings.lan_address.clone(),
+                    port: settings.lan_port,
+                    tls: settings.lan_tls,
+                },
+            });
+            self.connect(ctx);
+        }
+        if let Some((away, since)) = self.lan_away
+            && self.lan.is_none()
+            && self.lan_pending.is_none()
+        {
+            let seconds = self.lan_reconnect.map_or(0, |terms| terms.seconds);
+            let now = web_time::Instant::now();
+            if now.duration_since(since).as_secs() >= seconds.into() {
+                self.give_up_reconnecting(away);
+            } else if self.lan_retry.is_none_or(|retry| now >= retry) {
+                self.connect(ctx);
+            } else {
+                ctx.request_repaint_after(consts::RECONNECT_RETRY);
+            }
+        }
+        if let Some(receiver) = &self.lan_pending
+            && let Ok(result) = receiver.try_recv()
+        {
+            self.lan_pending = None;
+            match result {
+                Ok(connection) => {
+                    if let (Some(lan::Peer::Room { code, .. }), Some(room)) =
+                        (&mut self.lan_peer, &connection.room)
+                    {
+                        code.clone_from(room);
+                    }
+                    let connection_room = connection.room.clone();
+                    let fingerprint = connection.fingerprint.clone();
+                    self.log.record(Kind::Network, "Connected".to_string());
+                    self.lan = Some(connection);
+                    self.lan_latency = protocol::Latency::default();
+                    let hosting = matches!(self.lan_peer, Some(lan::Peer::Host { .. }));
+                    // The host sends the game, and both clocks start once it is agreed
+                    if hosting && self.lan_away.is_some() {
+                        self.resume_opponent();
+                    } else if hosting && let Some(opponent) = self.lan_opponent {
+                        let sgf = sgf::to_string(&self.to_sgf());
+                        self.send_lan(&protocol::Message::Game {
+                            color: opponent,
+                            sgf,
+                        });
+                        self.lan_reconnect =
+                            (self.new_game.reconnect_seconds > 0).then_some(protocol::Reconnect {
+                                seconds: self.new_game.reconnect_seconds,
+                                clock: self.new_game.away_clock,
+                            });
+                        if let Some(terms) = self.lan_reconnect {
+                            self.send_lan(&protocol::Message::Reconnect(terms));
+                        }
+                        self.lan_synced = self.moves.len();
+                        self.restart_thinking();
+                        self.file_status = Some(tr("The opponent has connected"));
+                        self.notices.push(tr("The opponent has connected"));
+                    } else if self.lan_away.is_some() {
+                        self.file_status = Some(tr("Connected again, catching up"));
+                    } else if matches!(self.lan_peer, Some(lan::Peer::Automatch { .. })) {
+                        self.file_status = Some(tr("Looking for an opponent"));
+                    } else if let Some(code) = &connection_room {
+                        self.file_status =
+                            Some(trf("In room {}, waiting for the other player", &[code]));
+                    } else {
+                        self.file_status = Some(tr("Connected, waiting for the game"));
+                    }
+                    // For the players to check that nobody sits in between
+                    if let Some(fingerprint) = fingerprint
+                        && let Some(status) = &self.file_status
+                    {
+                        self.file_status =
+                            Some(trf("{} (certificate {})", &[status, &fingerprint]));
+                    }
+                }
+                // Coming back is tried again until the time for it is up
+                Err(_) if self.lan_away.is_some() => {
+                    self.lan_retry = Some(web_time::Instant::now() + consts::RECONNECT_RETRY);
+                }
+                Err(err) => {
+                    self.log
+                        .record(Kind::Network, format!("Could not connect: {}", err));
+                    self.lan_opponent = None;
+                    self.file_status = Some(trf("Could not connect: {}", &[&err]));
+                }
+            }
+        }
+        if self.lan.is_none()
+            || self.trial.is_some()
+            || self.edit_stone.is_some()
+            || self.presentation.is_some()
+        {
+            return;
+        }
+        if self.lan_opponent.is_some() && self.lan_synced < self.moves.len() {
+            self.send_moves();
+            self.lan_plan = protocol::Plan::default();
+            // Playing on instead of answering declines an undo
+            self.undo_offer = None;
+        }
+        while let Some(message) = self.lan.as_ref().and_then(lan::Connection::receive) {
+            self.receive_lan(message);
+        }
+        // The host keeps the game, and every few moves lets the other side check its copy
+        if matches!(self.lan_peer, Some(lan::Peer::Host { .. })) && self.lan_opponent.is_some() {
+            let moves = self.moves.len();
+            if moves / protocol::SYNC_INTERVAL > self.lan_checked / protocol::SYNC_INTERVAL {
+                self.send_lan(&protocol::Message::Sync {
+                    moves,
+                    checksum: self.sync_checksum(moves),
+                });
+            }
+            self.lan_checked = moves;
+            if self.info.is_timed()
+                && let Some(ping) = self.lan_latency.ping()
+            {
+                self.send_lan(&ping);
+            }
+        }
+        #[cfg(not(target_arch = "wasm32"))]
+        self.remember_correspondence();
+        if let Some(code) = self.lan_matched.take()
+            && let Some(lan::Peer::Automatch { server }) = self.lan_peer.take()
+        {
+            self.lan_peer = Some(lan::Peer::Room { server, code });
+            self.connect(ctx);
+        }
+    }
+
+    // Writes the game to the sync folder whenever it moves on, or once it is over
+    #[cfg(not(target_arch = "wasm32"))]
+    fn sync_to_folder(&mut self) {
+        let folder = self.new_game.sync_folder.trim();
+        let seen = (
+            self.moves.len(),
+            self.game_over || !self.info.result.is_empty(),
+        );
+        let Some(id) = &self.sync_id else {
+            return;
+        };
+        if folder.is_empty()
+            || self.moves.is_empty()
+            || self.sync_written == Some(seen)
+            || self.trial.is_some()
+            || self.edit_stone.is_some()
+            || self.presentation.is_some()
+        {
+            return;
+        }
+        self.sync_written = Some(seen);
+        let sgf = sgf::to_string(&self.to_sgf());
+        if let Err(err) = sync_folder::write(std::path::Path::new(folder), id, &sgf) {
+            self.file_status = Some(trf(
+                "Could not write the game to the sync folder: {}",
+                &[&err],
+            ));
+        }
+    }
+
+    // Keeps a copy of a correspondence game here whenever it moves on, and forgets it once over
+    #[cfg(not(target_arch = "wasm32"))]
+    fn remember_correspondence(&mut self) {
+        let (Some(lan::Peer::Room { server, code }), Some(opponent)) =
+            (self.lan_peer.clone(), self.lan_opponent)
+        else {
+            return;
+        };
+        let seen = (self.moves.len(), self.game_over);
+        if !self.lan_correspondence || code.is_empty() || self.lan_remembered == Some(seen) {
+            return;
+        }
+        self.lan_remembered = Some(seen);
+        let path = paths::correspondence_path();
+        let mut games = correspondence::Games::load(&path);
+        let server = server.trim();
+        if self.game_over {
+            games.forget(server, &code);
+        } else {
+            let color = opponent.other();
+            games.remember(correspondence::Game {
+                server: server.to_string(),
+                code,
+                color,
+                opponent: match opponent {
+                    Player::Black => self.info.black_name.clone(),
+                    Player::White => self.info.white_name.clone(),
+                },
+                sgf: sgf::to_string(&self.to_sgf()),
+                your_turn: !self.game_ended() && self.current_player == color,
+            });
+        }
+        if let Err(err) = games.save(&path) {
+            self.file_status = Some(trf("Could not save the correspondence games: {}", &[&err]));
+        }
+        self.correspondence = games;
+    }
+
+    // Asks the game server once for the account's correspondence games, and takes its answer
+    #[cfg(not(target_arch = "wasm32"))]
+    fn poll_correspondence(&mut self, ctx: &egui::Context) {
+        if !std::mem::replace(&mut self.correspondence_checked, true)
+            && !self.new_game.server_token.is_empty()
+        {
+            let server = self.new_game.server.clone();
+            let token = self.new_game.server_token.clone();
+            self.correspondence_pending = Some(task::spawn(ctx, move || {
+                lan::correspondence_games(&server, &token)
+            }));
+        }
+        if let Some(receiver) = &self.correspondence_pending
+            && let Ok(result) = receiver.try_recv()
+        {
+            self.correspondence_pending = None;
+            match result {
+                Ok(ongoing) => {
+                    let path = paths::correspondence_path();
+                    let mut games = correspondence::Games::load(&path);
+                    // Games that came to this side since the last look, which may have been
+                    // another tab's
+                    let waiting: Vec<(String, String)> = games
+                        .games
+                        .iter()
+                        .filter(|game| game.your_turn)
+                        .map(|game| (game.server.clone(), game.code.clone()))
+                        .collect();
+                    games.update(self.new_game.server.trim(), ongoing);
+                    for game in games.games.iter().filter(|game| game.your_turn) {
+                        if !waiting
+                            .iter()
+                            .any(|(server, code)| *server == game.server && *code == game.code)
+                        {
+                            self.notices.push(trf(
+                                "Your move in the correspondence game against {}",
+                                &[&game.opponent],
+                            ));
+                        }
+                    }
+                    if let Err(err) = games.save(&path) {
+                        self.file_status =
+                            Some(trf("Could not save the correspondence games: {}", &[&err]));
+                    }
+                    self.correspondence = games;
+                }
+                Err(err) => {
+                    self.file_status = Some(trf(
+                        "Could not look up the correspondence games: {}",
+                        &[&err],
+                    ))
+                }
+            }
+        }
+    }
+
+    // Takes the answers of the game server to logging in and looking up the account's games
+    fn receive_account_replies(&mut self) {
+        if let Some(receiver) = &self.session_pending
+            && let Ok(result) = receiver.try_recv()
+        {
+            self.session_pending = None;
+            match result {
+                Ok(session) => {
+                    self.file_status = Some(trf("Logged in as {}", &[&session.name]));
+                    #[cfg(not(target_arch = "wasm32"))]
+                    {
+                        self.correspondence_checked = false;
+                    }
+                    self.new_game.server_account = session.name;
+                    self.new_game.server_token = session.token;
+                    self.server_password.clear();
+                }
+                Err(err) => self.file_status = Some(trf("Could not log in: {}", &[&err])),
+            }
+        }
+        if let Some(receiver) = &self.profile_pending
+            && let Ok(result) = receiver.try_recv()
+        {
+            self.profile_pending = None;
+            match result {
+                Ok(profile) => self.profile = Some(profile),
+                Err(err) => {
+                    self.file_status = Some(trf("Could not look up the account: {}", &[&err]))
+                }
+            }
+        }
+        if let Some(receiver) = &self.record_pending
+            && let Ok(result) = receiver.try_recv()
+        {
+            self.record_pending = None;
+            match result.and_then(|text| self.load_sgf(&text).map_err(std::io::Error::other)) {
+                Ok(()) => self.state = AppState::Game,
+                Err(err) => self.file_status = Some(trf("Could not open the game: {}", &[&err])),
+            }
+        }
+    }
+
+    // Opens the connection to `lan_peer` in the background; after a drop the host waits for
+    // the opponent only as long as they have left to come back
+    fn connect(&mut self, ctx: &egui::Context) {
+        let Some(peer) = self.lan_peer.clone() else {
+            return;
+        };
+        let repaint = ctx.clone();
+        let coming_back = self.lan_away.is_some();
+        let again = if coming_back { " again" } else { "" };
+        self.log
+            .record(Kind::Network, format!("Connecting{} to {:?}", again, peer));
+        self.lan_pending = Some(match peer {
+            lan::Peer::Host { port, tls } => {
+                let wait = self
+                    .lan_away
+                    .zip(self.lan_reconnect)
+                    .map(|((_, since), terms)| {
+                        std::time::Duration::from_secs(terms.seconds.into())
+                            .saturating_sub(since.elapsed())
+                    });
+                if !coming_back {
+                    self.file_status = Some(trf("Waiting for an opponent on port {}", &[&port]));
+                }
+                task::spawn(ctx, move || lan::Connection::host(port, wait, tls, repaint))
+            }
+            #[cfg(not(target_arch = "wasm32"))]
+            lan::Peer::Room { server, code } => {
+                if code.is_empty() {
+                    let sgf = sgf::to_string(&self.to_sgf());
+                    let color = self.new_game.human_color;
+                    let reconnect = (self.new_game.reconnect_seconds > 0 && !self.new_game.rengo)
+                        .then_some(protocol::Reconnect {
+                            seconds: self.new_game.reconnect_seconds,
+                            clock: self.new_game.away_clock,
+                        });
+                    let token = self.new_game.server_token.clone();
+                    let invited = self
+                        .new_game
+                        .invited
+                        .split(',')
+                        .map(str::trim)
+                        .filter(|name| !name.is_empty())
+                        .map(str::to_string)
+                        .collect();
+                    let new_room = protocol::NewRoom {
+                        sgf,
+                        color,
+                        reconnect,
+                        invited,
+                        correspondence: self.new_game.correspondence
+                            && !self.new_game.rengo
+                            && !token.is_empty(),
+                        rengo: self.new_game.rengo,
+                        simul: self.lan_simul,
+                        spectator_delay: (self.new_game.spectator_delay > 0).then_some(
+                            protocol::SpectatorDelay {
+                                amount: self.new_game.spectator_delay,
+                                unit: self.new_game.spectator_delay_unit,
+                            },
+                        ),
+                    };
+                    self.file_status = Some(trf("Opening a room on {}", &[&server.trim()]));
+                    task::spawn(ctx, move || {
+                        lan::Connection::create_room(&server, &token, new_room, repaint)
+                    })
+                } else {
+                    if !coming_back {
+                        self.file_status = Some(trf("Entering room {}", &[&code]));
+                    }
+                    let token = self.new_game.server_token.clone();
+                    task::spawn(ctx, move || {
+                        lan::Connection::enter_room(&server, &token, &code, repaint)
+                    })
+                }
+            }
+            #[cfg(not(target_arch = "wasm32"))]
+            lan::Peer::Automatch { server } => {
+                let token = self.new_game.server_token.clone();
+                let seek = protocol::Seek {
+                    sizes: self.new_game.match_sizes.clone(),
+                    speeds: self.new_game.match_speeds.clone(),
+                };
+                self.file_status = Some(trf("Joining the queue on {}", &[&server.trim()]));
+                task::spawn(ctx, move || {
+                    lan::Connection::automatch(&server, &token, seek, repaint)
+                })
+            }
+            #[cfg(not(target_arch = "wasm32"))]
+            lan::Peer::Ogs { game } => {
+                // Without logging in, games are only watched
+                let session = self.ogs_session.clone();
+                self.file_status = Some(trf("Opening OGS game {}", &[&game]));
+                task::spawn(ctx, move || ogs::play(session.as_ref(), game, repaint))
+            }
+            #[cfg(not(target_arch = "wasm32"))]
+            lan::Peer::Igs { server, target } => {
+                let settings = &self.new_game;
+                let goal = if let Ok(game) = target.parse() {
+                    self.file_status = Some(trf("Connecting to {}", &[&server.trim()]));
+                    igs::Goal::Watch(game)
+                } else if target.is_empty() {
+                    self.file_status =
+                        Some(trf("Waiting for a challenge on {}", &[&server.trim()]));
+                    igs::Goal::Accept
+                } else {
+                    self.file_status = Some(trf("Asking {} for a game", &[&target]));
+                    igs::Goal::Challenge(igs::Challenge {
+                        opponent: target,
+                        color: settings.human_color,
+                        board_size: self.board_size,
+                        minutes: (settings.main_time / 60.0).round() as u32,
+                        byoyomi_minutes: match settings.overtime {
+                            clock::Overtime::ByoYomi { seconds, .. }
+                            | clock::Overtime::Canadian { seconds, .. } => {
+                                (seconds / 60.0).round() as u32
+                            }
+                            _ => 0,
+                        },
+                    })
+                };
+                let account = settings.igs_account.clone();
+                let password = self.igs_password.clone();
+                task::spawn(ctx, move || {
+                    igs::play(&server, &account, &password, goal, repaint)
+                })
+            }
+            #[cfg(target_arch = "wasm32")]
+            lan::Peer::Room { .. }
+            | lan::Peer::Automatch { .. }
+            | lan::Peer::Ogs { .. }
+            | lan::Peer::Igs { .. } => {
+                return;
+            }
+            lan::Peer::Join { address, port, tls } => {
+                if !coming_back {
+                    self.file_status = Some(trf("Connecting to {}", &[&address.trim()]));
+                }
+                task::spawn(ctx, move || {
+                    lan::Connection::join(&address, port, tls, repaint)
+                })
+            }
+        });
+    }
+
+    // Keeps the game open for a while when its connection drops, if the terms allow coming
+    // back: the host waits for the other side, which tries to get through again
+    fn lan_dropped(&mut self, status: String) {
+        self.log.record(Kind::Network, status.clone());
+        self.lan = None;
+        let terms = self.lan_reconnect.filter(|terms| terms.seconds > 0);
+        if let (Some(opponent), Some(terms)) = (self.lan_opponent, terms)
+            && !self.game_over
+            && self.info.result.is_empty()
+        {
+            let hosting = matches!(self.lan_peer, Some(lan::Peer::Host { .. }));
+            let away = if hosting { opponent } else { opponent.other() };
+            self.lan_away = Some((away, web_time::Instant::now()));
+            self.lan_retry = None;
+            if terms.clock == protocol::AwayClock::Stops && self.paused.is_none() {
+                self.toggle_pause();
+            }
+            self.file_status = Some(if hosting {
+                trf(
+                    "{} lost the connection, waiting for them to come back",
+                    &[&player_name(opponent)],
+                )
+            } else {
+                tr("Lost the connection, trying to get back")
+            });
+            return;
+        }
+        self.lan_opponent = None;
+        self.lan_watching = false;
+        self.file_status = Some(status);
+    }
+
+    // Ends the wait for a dropped connection; a host whose opponent did not come back wins
+    fn give_up_reconnecting(&mut self, away: Player) {
+        self.lan_away = None;
+        self.lan_pending = None;
+        if self.paused.is_some() {
+            self.toggle_pause();
+        }
+        let hosting = matches!(self.lan_peer, Some(lan::Peer::Host { .. }));
+        if hosting && self.lan_opponent == Some(away) {
+            self.info.result = format!("{}+F", gtp::color(away.other()));
+            self.game_over = true;
+            self.sounds.push(Sound::GameEnd);
+            self.file_status = Some(trf("{} did not come back in time", &[&player_name(away)]));
+        } else {
+            self.file_status = Some(tr("Could not get back to the game"));
+        }
+        self.lan_opponent = None;
+    }
+
+    // Sends the opponent who came back the game as it stands, as the server does
+    fn resume_opponent(&mut self) {
+        let Some(opponent) = self.lan_opponent else {
+            return;
+        };
+        self.lan_away = None;
+        if self.paused.is_some() {
+            self.toggle_pause();
+        }
+        let mut root = self.to_sgf();
+        root.children.clear();
+        self.send_lan(&protocol::Message::Resume {
+            color: opponent,
+            sgf: sgf::to_string(&root),
+        });
+        self.lan_synced = 0;
+        self.send_moves();
+        self.send_lan(&protocol::Message::Thinking(self.thinking_seconds()));
+        if let Some(terms) = self.lan_reconnect {
+            self.send_lan(&protocol::Message::Reconnect(terms));
+        }
+        self.file_status = Some(trf("{} is back", &[&player_name(opponent)]));
+    }
+
+    fn receive_lan(&mut self, message: protocol::Message) {
+        tracing::debug!(target: "network", "received {:?}", message);
+        match message {
+            protocol::Message::Game { color, sgf } => match self.load_sgf(&sgf) {
+                Ok(()) => {
+                    self.lan_opponent = Some(color.other());
+                    self.lan_synced = self.moves.len();
+                    self.state = AppState::Game;
+                    self.file_status =
+                        Some(trf("Playing {} over the network", &[&player_name(color)]));
+                    self.notices.push(trf(
+                        "The game has started, you play {}",
+                        &[&player_name(color)],
+                    ));
+                }
+                Err(err) => {
+                    self.lan = None;
+                    self.file_status = Some(trf("Could not read the game sent: {}", &[&err]));
+                }
+            },
+            protocol::Message::Resume { color, sgf } => match self.load_sgf(&sgf) {
+                Ok(()) => {
+                    self.lan_opponent = Some(color.other());
+                    // The server sends what is left of the plan after the moves
+                    self.lan_plan = protocol::Plan::default();
+                    self.lan_away = None;
+                    self.lan_catching_up = true;
+                    self.paused = None;
+                    self.undo_asked = None;
+                    self.undo_offer = None;
+                    self.state = AppState::Game;
+                    self.file_status = Some(match self.lan_peer {
+                        Some(lan::Peer::Ogs { .. }) => {
+                            trf("Playing {} on OGS", &[&player_name(color)])
+                        }
+                        Some(lan::Peer::Igs { .. }) => {
+                            trf("Playing {} on IGS", &[&player_name(color)])
+                        }
+                        _ => tr("Back in the game"),
+                    });
+                }
+                Err(err) => {
+                    self.lan = None;
+                    self.file_status = Some(trf("Could not read the game sent: {}", &[&err]));
+                }
+            },
+            protocol::Message::Reconnect(terms) => self.lan_reconnect = Some(terms),
+            protocol::Message::Delay(delay) => {
+                let amount = delay.amount.to_string();
+                self.file_status = Some(match delay.unit {
+                    protocol::DelayUnit::Moves => {
+                        trf("Watching the game, {} moves behind", &[&amount])
+                    }
+                    protocol::DelayUnit::Minutes => {
+                        trf("Watching the game, {} minutes behind", &[&amount])
+                    }
+                });
+            }
+            protocol::Message::Correspondence => self.lan_correspondence = true,
+            protocol::Message::Rengo(turn) => {
+                self.lan_rengo = Some(turn);
+                if let Some(opponent) = self.lan_opponent {
+                    let color = player_name(opponent.other());
+                    self.file_status = Some(if turn == 0 {
+                        trf(
+                            "Playing {} in pair go, with the first move of the side",
+                            &[&color],
+                        )
+                    } else {
+                        trf(
+                            "Playing {} in pair go, with the second move of the side",
+                            &[&color],
+                        )
+                    });
+                }
+            }
+            protocol::Message::TeamChat(text) => {
+                if self.lan_rengo.is_some() {
+                    self.add_chat(self.lan_opponent.map(Player::other), text, true);
+                }
+            }
+            protocol::Message::Plan(plan) => self.lan_plan = plan,
+            protocol::Message::Ping(id) => self.send_lan(&protocol::Message::Pong(id)),
+            protocol::Message::Pong(id) => self.lan_latency.pong(id),
+            // The side that keeps the clocks counted other seconds for the last move played here
+            protocol::Message::Charged(seconds) => {
+                if let Some(own) = self.lan_opponent.map(Player::other)
+                    && let Some(id) =
+                        self.tree
+                            .path(self.tree.current())
+                            .into_iter()
+                            .rev()
+                            .find(|&id| {
+                                self.tree
+                                    .node(id)
+                                    .played
+                                    .is_some_and(|(player, _)| player == own)
+                            })
+                {
+                    self.tree.set_seconds(id, seconds);
+                }
+            }
+            protocol::Message::Sync { moves, checksum } => {
+                // A move that went missing on the way leaves too few
+                if moves > self.moves.len() || self.sync_checksum(moves) != checksum {
+                    self.send_lan(&protocol::Message::Resync);
+                    self.file_status = Some(tr("The game got out of step, fetching it again"));
+                }
+            }
+            // Only the side that keeps the game answers, with all of it again
+            protocol::Message::Resync => {
+                if matches!(self.lan_peer, Some(lan::Peer::Host { .. })) {
+                    let status = self.file_status.take();
+                    self.resume_opponent();
+                    self.file_status = status;
+                }
+            }
+            // The queue is done with, and the game is played in the room
+            protocol::Message::Matched(code) => {
+                self.lan = None;
+                self.lan_matched = Some(code);
+            }
+            protocol::Message::Seek(_) => {}
+            protocol::Message::Away(player) if self.lan_away.is_none() && !self.game_over => {
+                self.lan_away = Some((player, web_time::Instant::now()));
+                if self
+                    .lan_reconnect
+                    .is_some_and(|terms| terms.clock == protocol::AwayClock::Stops)
+                    && self.paused.is_none()
+                {
+                    self.toggle_pause();
+                }
+                self.file_status = Some(trf(
+                    "{} lost the connection, waiting for them to come back",
+                    &[&player_name(player)],
+                ));
+            }
+            protocol::Message::Away(_) => {}
+            protocol::Message::Back(player) => {
+                self.lan_away = None;
+                if self.paused.is_some() {
+                    self.toggle_pause();
+                }
+                self.file_status = Some(trf("{} is back", &[&player_name(player)]));
+            }
+            protocol::Message::Watch { sgf } => match self.load_sgf(&sgf) {
+                Ok(()) => {
+                    self.lan_watching = true;
+                    self.state = AppState::Game;
+                    self.file_status = Some(tr("Watching the game"));
+                }
+                Err(err) => {
+                    self.lan = None;
+                    self.file_status = Some(trf("Could not read the game sent: {}", &[&err]));
+                }
+            },
+            protocol::Message::Play { played, seconds } => {
+                let elapsed = self.thinking_seconds();
+                let after_plan = self.plan_after(played);
+                // In pair go the partner plays every other move of this side
+                if !(self.lan_watching
+                    || self.lan_catching_up
+                    || self.lan_opponent == Some(played.0)
+                    || self.lan_rengo.is_some_and(|turn| self.rengo_turn() != turn)
+                    || after_plan.is_some())
+                    || played.0 != self.current_player
+                    || !self.advance(played)
+                {
+                    self.lan = None;
+                    self.lan_opponent = None;
+                    self.lan_watching = false;
+                    self.file_status = Some(tr("The opponent sent a move that cannot be played"));
+                    return;
+                }
+                // The host keeps the clocks, as the server does, and holds the other side to the
+                // time it saw them take but for their round trip
+                let seconds = if matches!(self.lan_peer, Some(lan::Peer::Host { .. })) {
+                    let charged = self.lan_latency.charge(seconds, elapsed);
+                    if protocol::tenths(charged) != protocol::tenths(seconds) {
+                        self.send_lan(&protocol::Message::Charged(charged));
+                    }
+                    charged
+                } else {
+                    seconds
+                };
+                self.tree.set_seconds(self.tree.current(), seconds);
+                self.lan_synced = self.moves.len();
+                self.undo_asked = None;
+                if self.lan_opponent == Some(played.0) && !self.lan_catching_up && !self.game_over {
+                    let vertex = played.1.map_or(tr("pass"), |point| {
+                        self.new_game
+                            .coordinates
+                            .format_point(point, self.board_size)
+                    });
+                    self.notices.push(trf(
+                        "{} played {}, your move",
+                        &[&player_name(played.0), &vertex],
+                    ));
+                }
+                // A move the plan does not answer drops it, as on the server
+                if let Some(then) = after_plan {
+                    self.lan_plan = then;
+                } else if played
+                    .1
+                    .is_none_or(|point| self.lan_plan.answer(point).is_none())
+                {
+                    self.lan_plan = protocol::Plan::default();
+                }
+            }
+            protocol::Message::UndoRequest(count) => {
+                if let Some(opponent) = self.lan_opponent
+                    && self.can_take_back(opponent) == Some(count)
+                {
+                    self.undo_offer = Some(count);
+                }
+            }
+            protocol::Message::UndoAccept(count)
+                if self.lan_watching || self.undo_asked == Some(count) =>
+            {
+                self.undo_asked = None;
+                // Spectators see it from the side whose move is taken back
+                if let Some(&(requester, _)) = self
+                    .moves
+                    .len()
+                    .checked_sub(count)
+                    .and_then(|index| self.moves.get(index))
+                {
+                    self.take_back(count, requester);
+                }
+            }
+            protocol::Message::UndoAccept(_) => {}
+            protocol::Message::UndoDecline => {
+                if let (Some(count), Some(opponent)) = (self.undo_asked.take(), self.lan_opponent) {
+                    let text = trf(
+                        "{} asked to take back {}, declined by {}",
+                        &[
+                            &player_name(opponent.other()),
+                            &undo_moves(count),
+                            &player_name(opponent),
+                        ],
+                    );
+                    self.log_comment(&text);
+                    self.file_status = Some(text);
+                }
+            }
+            protocol::Message::Thinking(seconds)
+                if self.lan_watching || std::mem::take(&mut self.lan_catching_up) =>
+            {
+                let now = web_time::Instant::now();
+                self.move_started = now
+                    .checked_sub(std::time::Duration::from_secs_f32(seconds))
+                    .unwrap_or(now);
+            }
+            protocol::Message::Thinking(_) => {}
+            protocol::Message::Spectators(count) => self.lan_spectators = count,
+            protocol::Message::Chat { from, text } => {
+                // A player only hears the other players
+                if self.lan_watching
+                    || (from.is_some() && (from == self.lan_opponent || self.lan_rengo.is_some()))
+                {
+                    self.add_chat(from, text, false);
+                }
+            }
+            protocol::Message::Resign(player)
+            | protocol::Message::Timeout(player)
+            | protocol::Message::Forfeit(player)
+                if (self.lan_watching
+                    || self.lan_opponent == Some(player)
+                    || self.lan_rengo.is_some())
+                    && !self.game_over =>
+            {
+                let (reason, status) = match message {
+                    protocol::Message::Resign(_) => {
+                        ("R", trf("{} resigned", &[&player_name(player)]))
+                    }
+                    protocol::Message::Timeout(_) => {
+                        ("T", trf("{} ran out of time", &[&player_name(player)]))
+                    }
+                    _ => (
+                        "F",
+                        trf("{} did not come back in time", &[&player_name(player)]),
+                    ),
+                };
+                self.lan_away = None;
+                self.info.result = format!("{}+{}", gtp::color(player.other()), reason);
+                self.game_over = true;
+                self.sounds.push(Sound::GameEnd);
+                self.file_status = Some(status);
+            }
+            protocol::Message::Resign(_)
+            | protocol::Message::Timeout(_)
+            | protocol::Message::Forfeit(_) => {}
+            protocol::Message::Dead(stones) => {
+                if self.game_ended() && !self.game_over {
+                    // Only stones on the board can be dead, whatever the peer sent
+                    let size = self.board.size();
+                    let stones = stones.into_iter().filter(|&(row, col)| {
+                        row < size && col < size && self.board[row][col] != Stone::Empty
+                    });
+                    self.dead_stones = Some(stones.collect());
+                    self.file_status = Some(tr("Mark the dead stones, then accept the count"));
+                }
+            }
+            // The server's word is final, even over a count or a flag fallen here
+            protocol::Message::Result(result) => {
+                if !self.game_over {
+                    self.sounds.push(Sound::GameEnd);
+                }
+                self.lan_away = None;
+                self.dead_stones = None;
+                self.game_over = true;
+                self.file_status = Some(trf("The game ended: {}", &[&result]));
+                self.info.result = result;
+            }
+            // Only for this player's eyes, so it stays out of the record and the chat
+            protocol::Message::FairPlay { player, note } => {
+                tracing::warn!(target: "network", "fair play check on {}: {}", gtp::color(player), note);
+                self.file_status = Some(trf(
+                    "The server's fair play check flagged {}: {}",
+                    &[&player_name(player), &note],
+                ));
+            }
+            protocol::Message::Bye => {
+                self.lan_dropped(if matches!(self.lan_peer, Some(lan::Peer::Ogs { .. })) {
+                    tr("Lost the connection to OGS, where the game goes on")
+                } else if matches!(self.lan_peer, Some(lan::Peer::Igs { .. })) {
+                    tr("The IGS game has closed or was adjourned")
+                } else if self.lan_correspondence && !self.game_over {
+                    tr("Lost the connection to the server, which keeps the game")
+                } else if self.lan_watching {
+                    tr("The game has closed")
+                } else {
+                    tr("The opponent left the game")
+                })
+            }
+        }
+    }
+
+    // The plan left after `played`, if it is the reply planned here to the opponent's last move
+    // and so played by the server
+    fn plan_after(&self, played: Move) -> Option<protocol::Plan> {
+        let (player, Some(reply)) = played else {
+            return None;
+        };
+        let &(_, Some(theirs)) = self.moves.last()? else {
+            return None;
+        };
+        if self.lan_opponent != Some(player.other()) {
+            return None;
+        }
+        let (planned, then) = self.lan_plan.answer(theirs)?;
+        (planned == reply).then_some(then)
+    }
+
+    // The first reply tried out to each move of the opponent after `node`, and so on down, as
+    // one line for each way down
+    fn tried_plan(&self, node: usize) -> protocol::Plan {
+        let lines = self
+            .tree
+            .node(node)
+            .children
+            .iter()
+            .filter_map(|&theirs| {
+                let reply = *self.tree.node(theirs).children.first()?;
+                let (Some((_, Some(theirs))), Some((_, Some(reply_point)))) =
+                    (self.tree.node(theirs).played, self.tree.node(reply).played)
+                else {
+                    return None;
+                };
+                Some((
+                    protocol::Exchange {
+                        theirs,
+                        reply: reply_point,
+                    },
+                    reply,
+                ))
+            })
+            .flat_map(|(exchange, reply)| {
+                let mut lines = self.tried_plan(reply).lines;
+                if lines.is_empty() {
+                    lines.push(Vec::new());
+                }
+                for line in &mut lines {
+                    line.insert(0, exchange);
+                }
+                lines
+            })
+            .collect();
+        protocol::Plan { lines }
+    }
+
+    // Sends the replies tried out from `start`, the position of the game, as the plan for the
+    // opponent's next moves, and goes back to the game
+    fn send_plan(&mut self, start: usize) {
+        let plan = self.tried_plan(start);
+        if !plan.fits() {
+            self.file_status = Some(tr("Too many replies to plan"));
+            return;
+        }
+        self.end_trial();
+        self.send_lan(&protocol::Message::Plan(plan.clone()));
+        self.file_status = Some(trf("{} replies planned", &[&plan.replies()]));
+        self.lan_plan = plan;
+    }
+
+    fn send_lan(&mut self, message: &protocol::Message) {
+        if self.lan.is_some() {
+            tracing::debug!(target: "network", "sent {:?}", message);
+        }
+        if let Some(connection) = &mut self.lan
+            && let Err(err) = connection.send(message)
+        {
+            self.lan_dropped(trf("Lost the connection: {}", &[&err]));
+        }
+    }
+
+    // Sends the moves from `lan_synced` on with the time each took
+    fn send_moves(&mut self) {
+        for (played, seconds) in self.timed_moves().split_off(self.lan_synced) {
+            self.send_lan(&protocol::Message::Play { played, seconds });
+        }
+        self.lan_synced = self.moves.len();
+    }
+
+    // Adds a line to the comment of the move on the board
+    fn log_comment(&mut self, line: &str) {
+        let node = self.tree.current();
+        let mut comment = self.tree.node(node).comment.clone();
+        if !comment.is_empty() {
+            comment.push('\n');
+        }
+        comment.push_str(line);
+        self.tree.set_comment(node, comment);
+    }
+
+    // Keeps a chat message, and writes it into the record at the move on the board if asked to
+    fn add_chat(&mut self, from: Option<Player>, text: String, team: bool) {
+        let (black, white) = self.tree.total_seconds();
+        let at = black + white + self.thinking_seconds();
+        // What a side said among itself stays out of a record the other side may see
+        if self.new_game.chat_in_record && !team {
+            let name = from.map_or_else(|| tr("Spectator"), player_name);
+            self.log_comment(&format!("[{}] {}: {}", clock::format(at), name, text));
+        }
+        self.chat.push(ChatLine {
+            from,
+            team,
+            text,
+            at,
+        });
+    }
+
+    // How many moves `player` would take back in a network game: its own last move, and the
+    // reply to it if there is one
+    fn can_take_back(&self, player: Player) -> Option<usize> {
+        (1..=2.min(self.moves.len()))
+            .find(|&count| self.moves[self.moves.len() - count].0 == player)
+    }
+
+    fn request_undo(&mut self) {
+        let Some(opponent) = self.lan_opponent else {
+            return;
+        };
+        let Some(count) = self.can_take_back(opponent.other()) else {
+            return;
+        };
+        self.undo_asked = Some(count);
+        self.file_status = Some(trf("Asked to take back {}", &[&undo_moves(count)]));
+        self.send_lan(&protocol::Message::UndoRequest(count));
+    }
+
+    // Steps back `count` moves on agreement, leaving them in the record as a variation with
+    // a note of who asked
+    fn take_back(&mut self, count: usize, requester: Player) {
+        let path = self.tree.path(self.tree.current());
+        let Some(&target) = path
+            .len()
+            .checked_sub(count + 1)
+            .and_then(|index| path.get(index))
+        else {
+            return;
+        };
+        self.go_to_node(target);
+        self.lan_synced = self.moves.len();
+        let text = trf(
+            "{} took back {}, agreed by {}",
+            &[
+                &player_name(requester),
+                &undo_moves(count),
+                &player_name(requester.other()),
+            ],
+        );
+        self.log_comment(&text);
+        self.file_status = Some(text);
+    }
+
+    // Sends the draft as the color played here, or to the other spectators
+    fn send_chat(&mut self) {
+        let text = self.chat_draft.trim().to_string();
+        self.chat_draft.clear();
+        if text.is_empty() {
+            return;
+        }
+        let from = if self.lan_watching {
+            None
+        } else {
+            self.lan_opponent.map(Player::other)
+        };
+        let team = self.lan_rengo.is_some() && self.chat_team;
+        if team {
+            self.send_lan(&protocol::Message::TeamChat(text.clone()));
+        } else {
+            self.send_lan(&protocol::Message::Chat {
+                from,
+                text: text.clone(),
+            });
+        }
+        self.add_chat(from, text, team);
+    }
+
+    // Gives up the network game for the color played here
+    fn resign(&mut self) {
+        let Some(opponent) = self.lan_opponent else {
+            return;
+        };
+        self.info.result = format!("{}+R", gtp::color(opponent));
+        self.game_over = true;
+        self.sounds.push(Sound::GameEnd);
+        self.file_status = Some(trf("{} resigned", &[&player_name(opponent.other())]));
+        self.send_lan(&protocol::Message::Resign(opponent.other()));
+    }
+
+    // Sends the moves the engine has not seen yet and asks it for a reply in the background
+    fn drive_engine(&mut self, ctx: &egui::Context) {
+        if let Some(receiver) = &self.engine_pending
+            && let Ok((engine, reply)) = receiver.try_recv()
+        {
+            self.engine_pending = None;
+            // What the engine printed while thinking, such as its evaluation
+            for note in engine.notes() {
+                self.log.record(Kind::Engine, note);
+            }
+            self.engine = Some(engine);
+            // A move that comes after the engine's flag fell is not played, and one it was already
+            // passed for leaves the engine to catch up with the pass
+            if !self.game_over {
+                if matches!(reply, Ok(Some((player, _))) if player != self.current_player) {
+                    self.resync_engine();
+                } else {
+                    self.apply_engine_reply(reply);
+                }
+            }
+        }
+        if self.game_over
+            || self.human_to_move()
+            || self.engine_pending.is_some()
+            || self.edit_stone.is_some()
+            || self.presentation.is_some()
+            || self.paused.is_some()
+        {
+            return;
+        }
+        let Some(mut engine) = self.engine.take() else {
+            return;
+        };
+        let board_size = self.board_size;
+        let player = self.current_player;
+        let unsynced = self.moves[self.engine_synced..].to_vec();
+        let clock = self.clock(player);
+        self.engine_pending = Some(task::spawn(ctx, move || {
+            let reply = unsynced
+                .iter()
+                .try_for_each(|&played| engine.play(played, board_size))
+                .and_then(|()| {
+                    if let Some(clock) = clock {
+                        let _ = engine.time_left(player, &clock);
+                    }
+                    engine.genmove(player, board_size)
+                });
+            (engine, reply)
+        }));
+    }
+
+    fn apply_engine_reply(&mut self, reply: Result<Option<Move>, gtp::Error>) {
+        match reply {
+            Ok(Some(played)) => {
+                if !self.advance(played) {
+                    // Only a stone can be illegal
+                    let vertex = played
+                        .1
+                        .map(|point| notation::format_point(point, self.board_size))
+                        .unwrap_or_default();
+                    self.log
+                        .record(Kind::Engine, format!("Illegal move {}", vertex));
+                    self.file_status = Some(tr("The engine played an illegal move"));
+                    self.engine_color = None;
+                }
+                self.engine_synced = self.moves.len();
+            }
+            Ok(None) => {
+                let winner = self.current_player.other();
+                self.info.result = format!("{}+R", gtp::color(winner));
+                self.game_over = true;
+                self.sounds.push(Sound::GameEnd);
+                self.log.record(Kind::Engine, "Resigned".to_string());
+                self.file_status = Some(tr("The engine resigned"));
+            }
+            Err(err) => {
+                self.log.record(Kind::Engine, err.to_string());
+                self.file_status = Some(err.to_string());
+                self.engine_color = None;
+            }
+        }
+    }
+
+    fn to_sgf(&self) -> sgf::Node {
+        let mut root = sgf::Node::default();
+        root.set("GM", vec!["1".to_string()]);
+        root.set("FF", vec!["4".to_string()]);
+        root.set("CA", vec!["UTF-8".to_string()]);
+        root.set("AP", vec![consts::SGF_APPLICATION.to_string()]);
+        root.set("SZ", vec![self.board_size.to_string()]);
+        // Not a standard property; other programs ignore it and see a flat board
+        if self.toroidal {
+            root.set("TORUS", vec!["1".to_string()]);
+        }
+        // Also not standard: the teams are in PB and PW, each player and partner joined by " & "
+        if self.pair.is_some() {
+            root.set("RENGO", vec!["1".to_string()]);
+        }
+        self.info.write_to(&mut root);
+        // Thinking times are stored as BL/WL time left, so untimed games get a limit covering them all
+        let (black_total, white_total) = self.tree.total_seconds();
+        let clock = if self.info.is_timed() {
+            Some(Clock::new(self.info.time_limit, self.info.overtime))
+        } else if black_total + white_total > 0.0 {
+            let time_limit = black_total.max(white_total).ceil();
+            root.set("TM", vec![time_limit.to_string()]);
+            Some(Clock::new(time_limit, clock::Overtime::None))
+        } else {
+            None
+        };
+        for (ident, stone) in [
+            ("AB", Stone::Black),
+            ("AW", Stone::White),
+            ("AE", Stone::Empty),
+        ] {
+            let points = self
+                .setup
+                .iter()
+                .filter(|(setup_stone, _)| *setup_stone == stone)
+                .map(|&(_, point)| sgf::format_point(point))
+                .collect();
+            root.set(ident, points);
+        }
+        if !self.setup.is_empty() {
+            root.set("PL", vec![gtp::color(self.start_player).to_string()]);
+        }
+        let start = self.tree.node(GameTree::ROOT);
+        markup::write(&start.markup, &mut root);
+        if !start.comment.is_empty() {
+            root.set("C", vec![start.comment.clone()]);
+        }
+        // Not standard either, so bookmarks only come back in this program
+        if !start.bookmark.is_empty() {
+            root.set("BOOKMARK", vec![start.bookmark.clone()]);
+        }
+        if self.tree.current() == GameTree::ROOT {
+            self.write_paused_clock(&mut root);
+        }
+
+        root.children = self.sgf_children(GameTree::ROOT, clock.map(|clock| (clock, clock)));
+        root
+    }
+
+    // `clocks` are Black's and White's before the children are played, `None` when no times are
+    // written. The nodes wait on a stack rather than in recursion, as records may go deep.
+    fn sgf_children(&self, id: usize, clocks: Option<(Clock, Clock)>) -> Vec<sgf::Node> {
+        // The nodes still open, each with the clocks after its move and its children so far
+        let mut open = vec![(id, clocks, sgf::Node::default())];
+        loop {
+            let (id, clocks, node) = open.last().unwrap();
+            if let Some(&child) = self.tree.node(*id).children.get(node.children.len()) {
+                let (node, clocks) = self.sgf_node(child, *clocks);
+                open.push((child, clocks, node));
+                continue;
+            }
+            let (_, _, mut node) = open.pop().unwrap();
+            match open.last_mut() {
+                Some((_, _, parent)) => parent.children.push(node),
+                None => return std::mem::take(&mut node.children),
+            }
+        }
+    }
+
+    // The node of `child` without its children, and the clocks after its move
+    fn sgf_node(
+        &self,
+        child: usize,
+        mut clocks: Option<(Clock, Clock)>,
+    ) -> (sgf::Node, Option<(Clock, Clock)>) {
+        let mut node = sgf::Node::default();
+        let child_node = self.tree.node(child);
+        if let Some((player, point)) = child_node.played {
+            node.set(
+                gtp::color(player),
+                vec![point.map(sgf::format_point).unwrap_or_default()],
+            );
+            if let Some(seconds) = child_node.seconds
+                && let Some((black, white)) = &mut clocks
+            {
+                let (ident, count_ident) = time_idents(player);
+                let clock = match player {
+                    Player::Black => black,
+                    Player::White => white,
+                };
+                clock.spend(seconds);
+                node.set(ident, vec![format!("{:.1}", clock.left())]);
+                // Stones are counted after the move, as SGF has it
+                clock.end_move();
+                if let Some(count) = clock.count() {
+                    node.set(count_ident, vec![count.to_string()]);
+                }
+            }
+        }
+        markup::write(&child_node.markup, &mut node);
+        if !child_node.comment.is_empty() {
+            node.set("C", vec![child_node.comment.clone()]);
+        }
+        if !child_node.name.is_empty() {
+            node.set("N", vec![child_node.name.clone()]);
+        }
+        if !child_node.bookmark.is_empty() {
+            node.set("BOOKMARK", vec![child_node.bookmark.clone()]);
+        }
+        match child_node.verdict {
+            Some(Verdict::Correct) => node.set("TE", vec!["1".to_string()]),
+            Some(Verdict::Wrong) => node.set("BM", vec!["1".to_string()]),
+            None => {}
+        }
+        if child == self.tree.current() {
+            self.write_paused_clock(&mut node);
+        }
+        (node, clocks)
+    }
+
+    // While the clocks are paused the time left of the player to move is written on the current
+    // node, so an adjourned game resumes exactly where it stopped
+    fn write_paused_clock(&self, node: &mut sgf::Node) {
+        let player = self.current_player;
+        let (ident, count_ident) = time_idents(player);
+        if self.paused.is_some()
+            && node.get(ident).is_none()
+            && let Some(clock) = self.clock(player)
+        {
+            node.set(ident, vec![format!("{:.1}", clock.left())]);
+            if let Some(count) = clock.count() {
+                node.set(count_ident, vec![count.to_string()]);
+            }
+        }
+    }
+
+    // The record as a problem for other programs: the position, the variations and what is said
+    // about them, with the correct lines also called RIGHT in their comments
+    fn to_problem_sgf(&self) -> sgf::Node {
+        let mut full = self.to_sgf();
+        let mut root = sgf::Node::default();
+        for (ident, values) in std::mem::take(&mut full.properties) {
+            if [
+                "GM", "FF", "CA", "AP", "SZ", "AB", "AW", "AE", "C", "TR", "SQ", "CR", "MA", "LB",
+            ]
+            .contains(&ident.as_str())
+            {
+                root.set(&ident, values);
+            }
+        }
+        root.set("PL", vec![gtp::color(self.start_player).to_string()]);
+        root.children = self.sgf_children(GameTree::ROOT, None);
+        fn note_correct(node: &mut sgf::Node) {
+            let comment = node.get("C").unwrap_or_default();
+            if node.get("TE").is_some() && !comment.to_lowercase().contains("right") {
+                let comment = if comment.is_empty() {
+                    "RIGHT".to_string()
+                } else {
+                    format!("{}\n\nRIGHT", comment)
+                };
+                node.set("C", vec![comment]);
+            }
+            node.children.iter_mut().for_each(note_correct);
+        }
+        root.children.iter_mut().for_each(note_correct);
+        root
+    }
+
+    fn export_problem(&mut self) {
+        let path = paths::problems_dir().join(format!("problem-{}.sgf", timestamp()));
+        let result = std::fs::create_dir_all(paths::problems_dir())
+            .and_then(|()| std::fs::write(&path, sgf::to_string(&self.to_problem_sgf())));
+        self.file_status = Some(match result {
+            Ok(()) => trf("Exported the problem to {}", &[&path.display()]),
+            Err(err) => trf("Could not save problem: {}", &[&err]),
+        });
+    }
+
+    fn mark_saved(&mut self) {
+        self.saved_sgf = Some(sgf::to_string(&self.to_sgf()));
+    }
+
+    // Whether the record differs from what was last saved or loaded
+    fn is_dirty(&self) -> bool {
+        self.saved_sgf
+            .as_ref()
+            .is_some_and(|saved| *saved != sgf::to_string(&self.to_sgf()))
+    }
+
+    fn save_game(&mut self) {
+        let path = self
+            .file_path
+            .clone()
+            .unwrap_or_else(|| paths::games_dir().join(format!("game-{}.sgf", timestamp())));
+        let result = path
+            .parent()
+            .map_or(Ok(()), std::fs::create_dir_all)
+            .and_then(|()| std::fs::write(&path, sgf::to_string(&self.to_sgf())));
+        self.file_status = Some(match result {
+            Ok(()) => {
+                self.mark_saved();
+                trf("Saved to {}", &[&path.display()])
+            }
+            Err(err) => trf("Could not save game: {}", &[&err]),
+        });
+        self.file_path = Some(path);
+        self.library = None;
+    }
+
+    fn request_export(&mut self, ctx: &egui::Context) {
+        self.export_requested = true;
+        ctx.send_viewport_cmd(egui::ViewportCommand::Screenshot(egui::UserData::default()));
+    }
+
+    // Saves the board out of a screenshot of the window as a PNG next to the game file
+    fn export_image(&mut self, screenshot: &egui::ColorImage, pixels_per_point: f32) {
+        let image = screenshot.region(&self.board_rect, Some(pixels_per_point));
+        let path = match &self.file_path {
+            Some(path) => path.with_extension("png"),
+            None => paths::games_dir().join(format!("board-{}.png", timestamp())),
+        };
+        let [width, height] = image.size;
+        let bytes: Vec<u8> = image
+            .pixels
+            .iter()
+            .flat_map(|pixel| pixel.to_array())
+            .collect();
+        let result = path
+            .parent()
+            .map_or(Ok(()), std::fs::create_dir_all)
+            .map_err(|err| err.to_string())
+            .and_then(|()| {
+                image::save_buffer(
+                    &path,
+                    &bytes,
+                    width as u32,
+                    height as u32,
+                    image::ColorType::Rgba8,
+                )
+                .map_err(|err| err.to_string())
+            });
+        self.file_status = Some(match result {
+            Ok(()) => trf("Saved image to {}", &[&path.display()]),
+            Err(err) => trf("Could not save image: {}", &[&err]),
+        });
+    }
+
+    fn star_points(&self) -> &'static [(usize, usize)] {
+        view::star_points(self.board_size)
+    }
+
+    // Replays the main line of an SGF record, leaving the board at its final position
+    fn load_sgf(&mut self, text: &str) -> Result<(), String> {
+        let trees = sgf::parse(text).map_err(|err| err.to_string())?;
+        let root = &trees[0];
+        let size_value = root.get("SZ").unwrap_or("19");
+        let board_size = sgf::parse_size(size_value)
+            .filter(|size| (2..=consts::MAX_BOARD_SIZE).contains(size))
+            .ok_or_else(|| format!("unsupported board size {}", size_value))?;
+
+        self.board_size = board_size;
+        self.toroidal = root.get("TORUS").is_some();
+        self.board = self.empty_board();
+        self.info = GameInfo::from_node(root);
+        self.pair = root.get("RENGO").and_then(|_| {
+            let (black, black_partner) = self.info.black_name.split_once(" & ")?;
+            let (white, white_partner) = self.info.white_name.split_once(" & ")?;
+            Some([black, white, black_partner, white_partner].map(str::to_string))
+        });
+        self.pair_seat = None;
+        self.setup.clear();
+        self.moves.clear();
+        self.file_path = None;
+        self.file_status = None;
+        self.guess_record = None;
+        self.problem = None;
+        self.capture_goal = 0;
+        self.blind = false;
+        self.hidden = None;
+        self.rated = None;
+        self.head_to_head = false;
+        self.log = game_log::Log::default();
+        self.engine = None;
+        self.engine_color = None;
+        self.engine_pending = None;
+        self.tree = GameTree::default();
+        self.area_count = None;
+        self.analysis = None;
+        self.figure_start = 0;
+        self.trial = None;
+        self.edit_stone = None;
+        self.setup_captures = (0, 0);
+        self.current_player = Player::Black;
+        self.captured_black = 0;
+        self.captured_white = 0;
+        self.game_over = false;
+        self.last_move = None;
+
+        for current in sgf::main_line(root) {
+            for (ident, stone) in [
+                ("AB", Stone::Black),
+                ("AW", Stone::White),
+                ("AE", Stone::Empty),
+            ] {
+                for value in current.get_all(ident) {
+                    if let Some((row, col)) = sgf::parse_point(value, board_size) {
+                        self.board[row][col] = stone;
+                        self.setup.push((stone, (row, col)));
+                    }
+                }
+            }
+            if let Some(player) = current.get("PL") {
+                self.current_player = if player == "W" {
+                    Player::White
+                } else {
+                    Player::Black
+                };
+            }
+        }
+        self.start_player = self.current_player;
+        let clock = self
+            .info
+            .is_timed()
+            .then(|| Clock::new(self.info.time_limit, self.info.overtime));
+        self.read_variations(root, GameTree::ROOT, self.board.clone(), 0, (clock, clock))?;
+        let main_line = self.tree.moves_to(self.tree.main_line_end());
+        self.tree.go_to(self.tree.main_line_end());
+        self.rebuild(&main_line);
+        self.paused = None;
+        // An adjourned game has the time left of the player to move on its last node
+        let (ident, count_ident) = time_idents(self.current_player);
+        if self.info.result.is_empty()
+            && let Some(last) = sgf::main_line(root).last()
+            && last.get(gtp::color(self.current_player)).is_none()
+            && let Some(left) = last.get(ident).and_then(|left| left.trim().parse().ok())
+        {
+            self.paused = Some(0.0);
+            let count = last
+                .get(count_ident)
+                .and_then(|count| count.trim().parse().ok());
+            self.paused = self
+                .clock(self.current_player)
+                .map(|clock| clock.elapsed_until(left, count));
+        }
+        self.log.record(
+            Kind::Game,
+            format!(
+                "Opened a {}x{} game of {} moves: Black {} against White {}",
+                board_size,
+                board_size,
+                self.moves.len(),
+                self.info.black_label(),
+                self.info.white_label()
+            ),
+        );
+        self.mark_saved();
+        Ok(())
+    }
+
+    // Adds the moves of `node` and its variations below `parent`, checking each against `board`.
+    // Thinking times come from the drop in each player's BL/WL time left, and OB/OW periods left.
+    // Without time settings a player's first BL/WL starts their clock.
+    // The nodes wait on a stack rather than in recursion, as records may go deep.
+    fn read_variations(
+        &mut self,
+        node: &sgf::Node,
+        parent: usize,
+        board: Board,
+        move_number: usize,
+        clocks: (Option<Clock>, Option<Clock>),
+    ) -> Result<(), String> {
+        let mut pending = vec![(node, parent, board, move_number, clocks)];
+        while let Some((node, mut parent, mut board, mut move_number, mut clocks)) = pending.pop() {
+            for (ident, player) in [("B", Player::Black), ("W", Player::White)] {
+                if let Some(value) = node.get(ident) {
+                    move_number += 1;
+                    let point = sgf::parse_point(value, self.board_size);
+                    if let Some((row, col)) = point {
+                        if !board.is_legal(row, col, player) {
+                            return Err(format!("illegal move {} in record", move_number));
+                        }
+                        board.play(row, col, player);
+                    } else {
+                        board.pass();
+                    }
+                    parent = self.tree.add_child(parent, (player, point));
+                    if node.get("TE").is_some() {
+                        self.tree.set_verdict(parent, Some(Verdict::Correct));
+                    } else if node.get("BM").is_some() {
+                        self.tree.set_verdict(parent, Some(Verdict::Wrong));
+                    }
+                    let (ident, count_ident) = time_idents(player);
+                    let clock = match player {
+                        Player::Black => &mut clocks.0,
+                        Player::White => &mut clocks.1,
+                    };
+                    if let Some(left) = node
+                        .get(ident)
+                        .and_then(|left| left.trim().parse::<f32>().ok())
+                    {
+                        let count = node
+                            .get(count_ident)
+                            .and_then(|count| count.trim().parse().ok());
+                        match clock {
+                            Some(clock) => {
+                                let seconds = clock.elapsed_until(left, count);
+                                self.tree.set_seconds(parent, seconds);
+                                clock.spend(seconds);
+                                clock.end_move();
+                            }
+                            None => *clock = Some(Clock::new(left, clock::Overtime::None)),
+                        }
+                    }
+                }
+            }
+            // Markup and comments on nodes without a move belong to the position before them
+            let marks = markup::read(node, self.board_size);
+            if !marks.is_empty() {
+                self.tree.add_markup(parent, marks);
+            }
+            if let Some(comment) = node.get("C") {
+                self.tree.add_comment(parent, comment);
+            }
+            if parent != GameTree::ROOT
+                && let Some(name) = node.get("N")
+            {
+                self.tree.set_name(parent, name.to_string());
+            }
+            if let Some(bookmark) = node.get("BOOKMARK") {
+                self.tree.set_bookmark(parent, bookmark.to_string());
+            }
+            // Pushed last to first so the first variation is read first, as the tree keeps them
+            for child in node.children.iter().rev() {
+                pending.push((child, parent, board.clone(), move_number, clocks));
+            }
+        }
+        Ok(())
+    }
+
+    fn open_file(&mut self, path: PathBuf) -> Result<(), String> {
+        let text = std::fs::read_to_string(&path).map_err(|err| err.to_string())?;
+        self.load_sgf(&text)?;
+        // A game from the sync folder is played on under the same name
+        #[cfg(not(target_arch = "wasm32"))]
+        {
+            let folder = self.new_game.sync_folder.trim();
+            self.sync_id = (!folder.is_empty() && path.starts_with(folder))
+                .then(|| sync_folder::game_id(&path))
+                .flatten();
+            self.sync_written = Some((self.moves.len(), self.game_over));
+        }
+        self.file_path = Some(path);
+        self.state = AppState::Game;
+        Ok(())
+    }
+
+    fn start_guessing(&mut self, path: PathBuf) -> Result<(), String> {
+        self.open_file(path)?;
+        #[cfg(not(target_arch = "wasm32"))]
+        {
+            self.sync_id = None;
+        }
+        let record = std::mem::take(&mut self.moves);
+        self.tree = GameTree::default();
+        self.rebuild(&[]);
+        self.guess_record = Some(record);
+        self.guess_feedback = None;
+        self.skip_passes();
+        Ok(())
+    }
+
+    // Opens a problem with its solution in the variations, for the solver to find
+    fn start_problem(&mut self, path: PathBuf) -> Result<(), String> {
+        let text = std::fs::read_to_string(&path).map_err(|err| err.to_string())?;
+        let root = sgf::parse(&text)
+            .map_err(|err| err.to_string())?
+            .into_iter()
+            .next()
+            .unwrap_or_default();
+        self.open_file(path)?;
+        #[cfg(not(target_arch = "wasm32"))]
+        {
+            self.sync_id = None;
+        }
+        self.begin_problem(root)
+    }
+
+    // Goes on from a problem's record just loaded to trying to solve it
+    fn begin_problem(&mut self, root: sgf::Node) -> Result<(), String> {
+        let problem = training::Problem::new(root, self.board_size)
+            .ok_or_else(|| tr("The file has no solution to try"))?;
+        self.start_player = problem.solver;
+        self.problem = Some(problem);
+        self.problem_recorded = false;
+        self.reviewing = None;
+        self.lesson = None;
+        self.drill = None;
+        self.daily = None;
+        self.restart_problem();
+        Ok(())
+    }
+
+    fn start_drill(&mut self) {
+        self.drills_started += 1;
+        self.open_drill(shapes::Drill::generate(
+            self.drill_shape,
+            timestamp() + self.drills_started,
+        ));
+    }
+
+    fn start_daily(&mut self) {
+        let today = problems::today();
+        self.open_drill(daily::puzzle(today));
+        if self.drill.is_some() {
+            self.daily = Some(today);
+        }
+    }
+
+    fn open_drill(&mut self, drill: shapes::Drill) {
+        let root = drill.problem();
+        let started = self
+            .load_sgf(&sgf::to_string(&root))
+            .and_then(|()| self.begin_problem(root));
+        if let Err(err) = started {
+            self.file_status = Some(err);
+            return;
+        }
+        if let Some(problem) = &mut self.problem {
+            problem.description = tr(drill.shape.task());
+        }
+        self.drill = Some(drill);
+        self.state = AppState::Game;
+    }
+
+    fn start_lesson(&mut self, index: usize) {
+        let lesson = &tutorial::LESSONS[index];
+        let root = sgf::parse(lesson.sgf)
+            .ok()
+            .and_then(|trees| trees.into_iter().next())
+            .unwrap_or_default();
+        let started = self
+            .load_sgf(lesson.sgf)
+            .and_then(|()| self.begin_problem(root));
+        if let Err(err) = started {
+            self.file_status = Some(err);
+            return;
+        }
+        if let Some(problem) = &mut self.problem {
+            problem.description = tr(lesson.text);
+        }
+        self.lesson = Some(index);
+        self.state = AppState::Game;
+    }
+
+    // Opens the problem of the set that is due for review first
+    fn review_next(&mut self, set: usize) {
+        let next = self
+            .problem_sets
+            .as_ref()
+            .and_then(|sets| sets.sets.get(set))
+            .and_then(|set| set.next_due(problems::today()))
+            .map(|path| path.to_path_buf());
+        let Some(path) = next else {
+            self.problem_sets_status = Some(tr("No more problems due today in this set"));
+            self.problem_feedback = self.problem_sets_status.clone();
+            return;
+        };
+        match self.start_problem(path) {
+            Ok(()) => self.reviewing = Some(set),
+            Err(err) => self.problem_sets_status = Some(err),
+        }
+    }
+
+    // Keeps how the first try at a problem of a set went
+    fn record_problem(&mut self, solved: bool) {
+        if std::mem::replace(&mut self.problem_recorded, true) {
+            return;
+        }
+        if let Some(day) = self.daily {
+            let streak = self
+                .streak
+                .get_or_insert_with(|| daily::Streak::load(&paths::daily_path()));
+            if streak.record(day, solved)
+                && let Err(err) = streak.save(&paths::daily_path())
+            {
+                self.file_status = Some(trf("Could not write the streak: {}", &[&err]));
+            }
+            return;
+        }
+        if self.drill.is_some() {
+            self.drill_score.0 += u32::from(solved);
+            self.drill_score.1 += 1;
+            return;
+        }
+        let Some(path) = self.file_path.clone() else {
+            return;
+        };
+        let sets = self
+            .problem_sets
+            .get_or_insert_with(|| problems::Sets::load(&paths::problem_sets_path()));
+        if sets.record(&path, solved)
+            && let Err(err) = sets.save(&paths::problem_sets_path())
+        {
+            self.file_status = Some(trf("Could not write problem sets: {}", &[&err]));
+        }
+    }
+
+    fn restart_problem(&mut self) {
+        if let Some(problem) = &mut self.problem {
+            problem.restart();
+        }
+        self.problem_feedback = None;
+        // The marks on the problem's position stay, such as those on the stones to capture
+        let markup = self.tree.node(GameTree::ROOT).markup.clone();
+        self.tree = GameTree::default();
+        self.tree.add_markup(GameTree::ROOT, markup);
+        self.rebuild(&[]);
+    }
+
+    // The solver's move, answered from the problem's variations
+    fn solve_move(&mut self, point: Option<(usize, usize)>) {
+        if point.is_some_and(|(row, col)| !self.is_valid_move(row, col)) {
+            return;
+        }
+        let Some(problem) = &mut self.problem else {
+            return;
+        };
+        if problem.solved.is_some() {
+            return;
+        }
+        let solver = problem.solver;
+        // A drill's first move is read out on the board, so another move that works counts too
+        if let Some(drill) = &self.drill
+            && let Some(point) = point
+            && self.moves.is_empty()
+            && point != drill.answer()
+            && drill.works(&self.board, point)
+        {
+            let answer = self
+                .new_game
+                .coordinates
+                .format_point(drill.answer(), self.board_size);
+            problem.solved = Some(true);
+            self.advance((solver, Some(point)));
+            self.record_problem(true);
+            self.problem_feedback = Some(trf("That works too - the shape is at {}", &[&answer]));
+            return;
+        }
+        let reply = problem.attempt(point);
+        let solved = problem.solved;
+        self.advance((solver, point));
+        if let Some(reply) = reply {
+            self.advance(reply);
+        }
+        if let Some(solved) = solved {
+            self.record_problem(solved);
+        }
+        self.problem_feedback = solved.map(|solved| {
+            if solved {
+                tr("Correct!")
+            } else {
+                trf("Wrong - the solution is {}", &[&self.solution_label()])
+            }
+        });
+    }
+
+    fn show_solution(&mut self) {
+        let Some(problem) = &self.problem else {
+            return;
+        };
+        let solution = problem.solution();
+        self.restart_problem();
+        for &played in &solution {
+            self.advance(played);
+        }
+        if let Some(problem) = &mut self.problem {
+            problem.solved = Some(false);
+        }
+        self.record_problem(false);
+        self.problem_feedback = Some(trf("The solution is {}", &[&self.solution_label()]));
+    }
+
+    // The solution written out, such as "B C2, W D1"
+    fn solution_label(&self) -> String {
+        let Some(problem) = &self.problem else {
+            return String::new();
+        };
+        let moves: Vec<String> = problem
+            .solution()
+            .into_iter()
+            .map(|(player, point)| {
+                let point = point.map_or(tr("pass"), |point| {
+                    self.new_game
+                        .coordinates
+                        .format_point(point, self.board_size)
+                });
+                let color = match player {
+                    Player::Black => "B",
+                    Player::White => "W",
+                };
+                format!("{} {}", color, point)
+            })
+            .collect();
+        if moves.is_empty() {
+            tr("not in the file")
+        } else {
+            moves.join(", ")
+        }
+    }
+
+    fn next_recorded_move(&self) -> Option<Move> {
+        self.guess_record.as_ref()?.get(self.moves.len()).copied()
+    }
+
+    // Passes in the record are played automatically instead of being guessed
+    fn skip_passes(&mut self) {
+        while let Some((player, None)) = self.next_recorded_move() {
+            self.advance((player, None));
+        }
+        if let Some((player, _)) = self.next_recorded_move() {
+            self.current_player = player;
+        }
+    }
+
+    fn guess_move(&mut self, guess: Option<(usize, usize)>) {
+        let move_number = self.moves.len() + 1;
+        let Some((player, actual)) = self.next_recorded_move() else {
+            self.guess_feedback = Some(tr("End of the game"));
+            return;
+        };
+        let result = training::grade(guess, actual);
+        self.guess_session.record(result);
+        let actual_label = actual.map_or(tr("pass"), |point| {
+            self.new_game
+                .coordinates
+                .format_point(point, self.board_size)
+        });
+        self.guess_feedback = Some(match result {
+            training::Guess::Exact => trf("Move {}: exact!", &[&move_number]),
+            training::Guess::Nearby => {
+                trf("Move {}: close, it was {}", &[&move_number, &actual_label])
+            }
+            training::Guess::Wrong => trf("Move {}: it was {}", &[&move_number, &actual_label]),
+        });
+        self.advance((player, actual));
+        self.skip_passes();
+    }
+
+    fn poll_database_import(&mut self) {
+        let Some(receiver) = &self.db_pending else {
+            return;
+        };
+        let Ok(records) = receiver.try_recv() else {
+            return;
+        };
+        self.db_pending = None;
+        let database = self
+            .database
+            .get_or_insert_with(|| database::Database::load(&paths::database_path()));
+        let added = database.merge(records);
+        self.db_status = Some(match database.save(&paths::database_path()) {
+            Ok(()) => trf("Imported {} new games", &[&added]),
+            Err(err) => trf("Could not write database: {}", &[&err]),
+        });
+        self.db_results = database.search(&self.db_query);
+    }
+
+    // Downloads what is new in every archive, deduplicated against the database
+    #[cfg(not(target_arch = "wasm32"))]
+    fn update_archives(&mut self, ctx: &egui::Context) {
+        let sources = self
+            .archives
+            .get_or_insert_with(|| archive::Archives::load(&paths::archives_path()))
+            .sources
+            .clone();
+        let known = self
+            .database
+            .get_or_insert_with(|| database::Database::load(&paths::database_path()))
+            .records
+            .clone();
+        self.archive_progress = (0, 0);
+        self.db_status = Some(tr("Looking for new games in the archives..."));
+        self.archive_pending = Some(archive::update(sources, known, ctx));
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    fn poll_archive_update(&mut self) {
+        let Some(receiver) = &self.archive_pending else {
+            return;
+        };
+        let mut finished = None;
+        for event in receiver.try_iter() {
+            match event {
+                archive::Event::Progress { done, total } => self.archive_progress = (done, total),
+                archive::Event::Finished(outcome) => finished = Some(outcome),
+            }
+        }
+        let Some(outcome) = finished else {
+            return;
+        };
+        self.archive_pending = None;
+        for error in &outcome.errors {
+            tracing::warn!(target: "archive", "{}", error);
+        }
+        let archives = self
+            .archives
+            .get_or_insert_with(|| archive::Archives::load(&paths::archives_path()));
+        for (url, fetched) in outcome.fetched {
+            if let Some(source) = archives.sources.iter_mut().find(|source| source.url == url) {
+                source.fetched = fetched;
+            }
+        }
+        let database = self
+            .database
+            .get_or_insert_with(|| database::Database::load(&paths::database_path()));
+        let added = database.merge(outcome.records);
+        let saved = archives
+            .save(&paths::archives_path())
+            .and_then(|()| database.save(&paths::database_path()));
+        self.db_status = Some(match (saved, outcome.errors.first()) {
+            (Err(err), _) => trf("Could not write database: {}", &[&err]),
+            (Ok(()), Some(error)) => trf(
+                "Added {} games, {} were already in the database; {} could not be read, the first: {}",
+                &[&added, &outcome.duplicates, &outcome.errors.len(), error],
+            ),
+            (Ok(()), None) => trf(
+                "Added {} games, {} were already in the database",
+                &[&added, &outcome.duplicates],
+            ),
+        });
+        self.db_results = database.search(&self.db_query);
+    }
+
+    // The archives to update from, with their progress
+    #[cfg(not(target_arch = "wasm32"))]
+    fn show_archives(&mut self, ui: &mut egui::Ui) {
+        let archives = self
+            .archives
+            .get_or_insert_with(|| archive::Archives::load(&paths::archives_path()));
+        let mut changed = false;
+        let mut update = false;
+        egui::CollapsingHeader::new(trf("Archives ({})", &[&archives.sources.len()])).show(
+            ui,
+            |ui| {
+                let mut removed = None;
+                for (index, source) in archives.sources.iter().enumerate() {
+                    ui.horizontal(|ui| {
+                        ui.label(&source.url);
+                        ui.weak(trf("{} files fetched", &[&source.fetched.len()]));
+                        if ui
+                            .add_enabled(
+                                self.archive_pending.is_none(),
+                                egui::Button::new(tr("Remove")),
+                            )
+                            .on_hover_text(tr(
+                                "Stop updating from this archive; its games stay in the database",
+                            ))
+                            .clicked()
+                        {
+                            removed = Some(index);
+                        }
+                    });
+                }
+                if let Some(index) = removed {
+                    archives.sources.remove(index);
+                    changed = true;
+                }
+                ui.horizontal(|ui| {
+                    ui.add(
+                        egui::TextEdit::singleline(&mut self.archive_input)
+                            .hint_text(tr("Address of an SGF file or a page listing them")),
+                    );
+                    let url = self.archive_input.trim();
+                    if ui
+                        .add_enabled(
+                            !url.is_empty()
+                                && !archives.sources.iter().any(|source| source.url == url),
+                            egui::Button::new(tr("Add Archive")),
+                        )
+                        .clicked()
+                    {
+                        archives.sources.push(archive::Source {
+                            url: url.to_string(),
+                            fetched: Vec::new(),
+                        });
+                        self.archive_input.clear();
+                        changed = true;
+                    }
+                    update = ui
+                        .add_enabled(
+                            self.archive_pending.is_none() && !archives.sources.is_empty(),
+                            egui::Button::new(tr("Update Archives")),
+                        )
+                        .on_hover_text(tr("Download the games added since the last update"))
+                        .clicked();
+                });
+                if self.archive_pending.is_some() {
+                    let (done, total) = self.archive_progress;
+                    let fraction = if total == 0 {
+                        0.0
+                    } else {
+                        done as f32 / total as f32
+                    };
+                    ui.add(
+                        egui::ProgressBar::new(fraction)
+                            .text(trf("{} of {} files", &[&done, &total]))
+                            .animate(total == 0),
+                    );
+                }
+            },
+        );
+        if changed && let Err(err) = archives.save(&paths::archives_path()) {
+            self.db_status = Some(trf("Could not write database: {}", &[&err]));
+        }
+        if update {
+            self.update_archives(ui.ctx());
+        }
+    }
+
+    fn search_pattern(&mut self, ctx: &egui::Context) {
+        let Some((from, to)) = self.region else {
+            return;
+        };
+        let Some(pattern) = pattern::Pattern::from_region(&self.board, from, to) else {
+            self.file_status = Some(tr("The selected region contains no stones"));
+            return;
+        };
+        let records = self
+            .database
+            .get_or_insert_with(|| database::Database::load(&paths::database_path()))
+            .records
+            .clone();
+        self.file_status = Some(tr("Searching..."));
+        self.pattern_pending = Some(task::spawn(ctx, move || {
+            let mut games = database::Database { records };
+            games.merge(database::scan(&paths::games_dir()));
+            pattern.search(&games.records)
+        }));
+    }
+
+    fn count_endgame(&mut self, ctx: &egui::Context) {
+        let Some((from, to)) = self.region else {
+            return;
+        };
+        let board = self.board.clone();
+        let rules = self.info.rules;
+        self.file_status = Some(tr("Counting..."));
+        self.yose_pending = Some(task::spawn(ctx, move || {
+            (board.checksum(), yose::plays(&board, rules, from, to))
+        }));
+    }
+
+    fn analyze_game(&mut self, ctx: &egui::Context) {
+        let line = self.tree.line();
+        let moves = self.tree.moves_to(line[line.len() - 1]);
+        let board = self.setup_board();
+        let komi = self.info.komi;
+        self.show_graph = true;
+        if !(consts::DESKTOP && self.new_game.review_engine) {
+            self.analysis_pending = Some(task::spawn(ctx, move || {
+                Ok(estimate::analyze(board, &moves, komi))
+            }));
+            return;
+        }
+        let command = self.new_game.engine_command.clone();
+        let megabytes = self.new_game.analysis_cache_megabytes;
+        let rules = self.info.rules;
+        let setup = self.setup.clone();
+        let start_player = self.start_player;
+        self.analysis_pending = Some(task::spawn(ctx, move || {
+            let leads = engine_analysis(&command, komi, rules, &setup, board, start_player, &moves);
+            save_analysis_cache(megabytes);
+            leads.map_err(|err| err.to_string())
+        }));
+    }
+
+    fn find_mistakes(&mut self, ctx: &egui::Context) {
+        let line = self.tree.line();
+        let moves = self.tree.moves_to(line[line.len() - 1]);
+        let board = self.setup_board();
+        let side = self.mistake_side;
+        let komi = self.info.komi;
+        self.mistakes_status = Some(tr("Looking for mistakes..."));
+        self.mistakes_pending = Some(task::spawn(ctx, move || {
+            mistakes::find(board, &moves, side, komi)
+        }));
+    }
+
+    // Writes a report of the review next to the game file
+    fn export_report(&mut self, ctx: &egui::Context) {
+        let line = self.tree.line();
+        let moves = self.tree.moves_to(line[line.len() - 1]);
+        let board = self.setup_board();
+        let info = self.info.clone();
+        let format = self.report_format;
+        let coordinates = self.new_game.coordinates;
+        let path = match &self.file_path {
+            Some(path) => path.with_extension(format.extension()),
+            None => {
+                paths::games_dir().join(format!("report-{}.{}", timestamp(), format.extension()))
+            }
+        };
+        self.mistakes_status = Some(tr("Writing the report..."));
+        self.report_pending = Some((
+            path,
+            task::spawn(ctx, move || {
+                report::Review::new(board, &moves, info).render(format, coordinates)
+            }),
+        ));
+    }
+
+    fn poll_report(&mut self) {
+        let Some((path, receiver)) = &self.report_pending else {
+            return;
+        };
+        let Ok(text) = receiver.try_recv() else {
+            return;
+        };
+        let result = path
+            .parent()
+            .map_or(Ok(()), std::fs::create_dir_all)
+            .and_then(|()| std::fs::write(path, text));
+        self.mistakes_status = Some(match result {
+            Ok(()) => trf("Saved the report to {}", &[&path.display()]),
+            Err(err) => trf("Could not save the report: {}", &[&err]),
+        });
+        self.report_pending = None;
+    }
+
+    // Turns the mistakes found into problems in the set kept in the `mistakes` problem folder
+    fn poll_mistakes(&mut self) {
+        let Some(receiver) = &self.mistakes_pending else {
+            return;
+        };
+        let Ok(found) = receiver.try_recv() else {
+            return;
+        };
+        self.mistakes_pending = None;
+        if found.is_empty() {
+            self.mistakes_status = Some(tr("No mistakes found"));
+            return;
+        }
+        let problems: Vec<(usize, sgf::Node)> = found
+            .iter()
+            .map(|mistake| {
+                let comment = trf(
+                    "{} vs {}, move {}: the move played lost about {} points. Find a better one.",
+                    &[
+                        &self.info.black_name,
+                        &self.info.white_name,
+                        &(mistake.move_number + 1),
+                        &mistake.loss.round(),
+                    ],
+                );
+                (mistake.move_number, mistake.problem(comment))
+            })
+            .collect();
+        let game = self
+            .file_path
+            .as_ref()
+            .and_then(|path| path.file_stem())
+            .map(|stem| stem.to_string_lossy().into_owned())
+            .unwrap_or_else(|| format!("game-{}", timestamp()));
+        let folder = paths::problems_dir().join("mistakes");
+        if let Err(err) = mistakes::write(&folder, &game, &problems) {
+            self.mistakes_status = Some(trf("Could not save problem: {}", &[&err]));
+            return;
+        }
+        let sets = self
+            .problem_sets
+            .get_or_insert_with(|| problems::Sets::load(&paths::problem_sets_path()));
+        match sets.sets.iter_mut().find(|set| set.folder == folder) {
+            Some(set) => set.refresh(),
+            None => {
+                sets.add(folder);
+            }
+        }
+        self.mistakes_status = Some(match sets.save(&paths::problem_sets_path()) {
+            Ok(()) => trf(
+                "Made {} problems from the mistakes, in the set \"mistakes\"",
+                &[&problems.len()],
+            ),
+            Err(err) => trf("Could not write problem sets: {}", &[&err]),
+        });
+    }
+
+    fn poll_analysis(&mut self) {
+        let Some(receiver) = &self.analysis_pending else {
+            return;
+        };
+        let Ok(result) = receiver.try_recv() else {
+            return;
+        };
+        self.analysis_pending = None;
+        match result {
+            Ok(leads) => self.analysis = Some(leads),
+            Err(err) => self.file_status = Some(trf("Could not analyze the game: {}", &[&err])),
+        }
+    }
+
+    fn poll_pattern_search(&mut self) {
+        let Some(receiver) = &self.pattern_pending else {
+            return;
+        };
+        let Ok(result) = receiver.try_recv() else {
+            return;
+        };
+        self.pattern_pending = None;
+        self.file_status = None;
+        self.pattern_result = Some(result);
+    }
+
+    fn poll_yose(&mut self) {
+        let Some(receiver) = &self.yose_pending else {
+            return;
+        };
+        let Ok(result) = receiver.try_recv() else {
+            return;
+        };
+        self.yose_pending = None;
+        self.file_status = None;
+        self.yose = Some(result);
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    fn poll_ogs(&mut self) {
+        if let Some(receiver) = &self.ogs_session_pending
+            && let Ok(result) = receiver.try_recv()
+        {
+            self.ogs_session_pending = None;
+            match result {
+                Ok((session, lobby)) => {
+                    self.file_status = Some(trf("Logged in to OGS as {}", &[&session.username]));
+                    self.new_game.ogs_username = session.username.clone();
+                    self.ogs_session = Some(session);
+                    self.ogs_lobby = lobby;
+                    self.ogs_password.clear();
+                }
+                Err(err) => self.file_status = Some(trf("Could not log in: {}", &[&err])),
+            }
+        }
+        if let Some(receiver) = &self.ogs_lobby_pending
+            && let Ok(result) = receiver.try_recv()
+        {
+            self.ogs_lobby_pending = None;
+            match result {
+                Ok((game, lobby)) => {
+                    if game.is_some() {
+                        self.ogs_game = game;
+                    }
+                    self.ogs_lobby = lobby;
+                }
+                Err(err) => self.file_status = Some(trf("Could not reach OGS: {}", &[&err])),
+            }
+        }
+        if let Some(receiver) = &self.ogs_watchable_pending
+            && let Ok(result) = receiver.try_recv()
+        {
+            self.ogs_watchable_pending = None;
+            match result {
+                Ok(games) => {
+                    if games.is_empty() {
+                        self.file_status = Some(tr("No games to watch there right now"));
+                    }
+                    self.ogs_watchable = games;
+                }
+                Err(err) => self.file_status = Some(trf("Could not reach OGS: {}", &[&err])),
+            }
+        }
+        let Some(receiver) = &self.ogs_pending else {
+            return;
+        };
+        let Ok(response) = receiver.try_recv() else {
+            return;
+        };
+        self.ogs_pending = None;
+        self.ogs_status = Some(match response {
+            ogs::Response::Game(Ok(text)) => match self.load_sgf(&text) {
+                Ok(()) => {
+                    self.state = AppState::Game;
+                    return;
+                }
+                Err(err) => err,
+            },
+            ogs::Response::PlayerGames(Ok(paths)) => trf(
+                "Downloaded {} games to {}",
+                &[&paths.len(), &self.ogs_download_dir().display()],
+            ),
+            ogs::Response::Game(Err(err)) | ogs::Response::PlayerGames(Err(err)) => err.to_string(),
+        });
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    fn ogs_download_dir(&self) -> PathBuf {
+        paths::games_dir()
+            .join("ogs")
+            .join(self.ogs_player_input.trim())
+    }
+
+    // Actual GUI functions
+    fn show_options(&mut self, ctx: &egui::Context, recent_files: &[PathBuf]) {
+        egui::CentralPanel::default().show(ctx, |ui| {
+            ui.heading(tr("Go Game"));
+            #[cfg(not(target_arch = "wasm32"))]
+            self.show_correspondence(ui);
+
+            egui::Grid::new("new_game").num_columns(2).show(ui, |ui| {
+                ui.label(tr("Board Size"));
+                egui::ComboBox::from_id_salt("board_size")
+                    .selected_text(format!("{} x {}", &self.board_size, &self.board_size))
+                    .show_ui(ui, |ui| {
+                        for &selected_size in consts::VALID_BOARD_SIZES {
+                            let is_selected: bool = self.board_size == selected_size;
+                            let label = format!("{} x {}", selected_size, selected_size);
+
+                            if ui.selectable_label(is_selected, label).clicked() {
+                                self.board_size = selected_size;
+                            }
+                        }
+                    });
+                ui.end_row();
+
+                let settings = &mut self.new_game;
+                ui.label(tr("Handicap"));
+                ui.add(egui::DragValue::new(&mut settings.handicap).range(0..=9));
+                ui.end_row();
+
+                ui.label(tr("Komi"));
+                ui.add(egui::DragValue::new(&mut settings.komi).speed(0.5));
+                ui.end_row();
+
+                ui.label(tr("Main time (s)"));
+                ui.add(egui::DragValue::new(&mut settings.main_time).range(0.0..=f32::MAX))
+                    .on_hover_text(tr("0 plays without a clock"));
+                ui.end_row();
+
+                ui.label(tr("Overtime"));
+                overtime_editor(ui, &mut settings.overtime);
+                ui.end_row();
+
+                ui.label(tr("Rules"));
+                egui::ComboBox::from_id_salt("rules")
+                    .selected_text(tr(settings.rules.name()))
+                    .show_ui(ui, |ui| {
+                        for rules in Rules::ALL {
+                            ui.selectable_value(&mut settings.rules, rules, tr(rules.name()));
+                        }
+                    });
+                ui.end_row();
+
+                ui.label(tr("Blind Go"));
+                ui.checkbox(&mut settings.blind, tr("Hide the stones"))
+                    .on_hover_text(tr("Only the last move shows, for a moment; the position appears at the end of the game or while \"Show Stones\" is held down"));
+                ui.end_row();
+
+                ui.label(tr("Capture Go"));
+                ui.add_enabled_ui(
+                    matches!(settings.opponent, Opponent::Human | Opponent::Engine),
+                    |ui| {
+                        ui.horizontal(|ui| {
+                            let mut capture_go = settings.capture_goal > 0;
+                            if ui
+                                .checkbox(&mut capture_go, tr("First to capture wins"))
+                                .on_hover_text(tr("The beginners' game: no territory and no counting, whoever takes the stones first wins"))
+                                .changed()
+                            {
+                                settings.capture_goal = u32::from(capture_go);
+                            }
+                            if capture_go {
+                                ui.add(
+                                    egui::DragValue::new(&mut settings.capture_goal)
+                                        .range(1..=consts::MAX_CAPTURE_GOAL),
+                                );
+                                ui.label(tr("stones"));
+                            }
+                        })
+                    },
+                )
+                .response
+                .on_disabled_hover_text(tr("Only for games on this computer"));
+                ui.end_row();
+
+                ui.label(tr("Hidden-move Go"));
+                ui.add_enabled_ui(settings.opponent == Opponent::Human, |ui| {
+                    ui.horizontal(|ui| {
+                        ui.add(
+                            egui::DragValue::new(&mut settings.hidden_stones)
+                                .range(0..=consts::MAX_HIDDEN_STONES),
+                        );
+                        ui.label(tr("hidden stones each"));
+                    })
+                })
+                .response
+                .on_hover_text(tr("Before the first move each player secretly places stones, which count from the start but show only when the other player tries to play on one or a capture involves them. 0 plays a normal game"))
+                .on_disabled_hover_text(tr("Only for two players on this computer"));
+                ui.end_row();
+
+                ui.label(tr("Toroidal Go"));
+                ui.add_enabled(
+                    settings.opponent == Opponent::Human,
+                    egui::Checkbox::new(&mut settings.toroidal, tr("Join the edges")),
+                )
+                .on_hover_text(tr("Each edge of the board joins the opposite one, as on a doughnut, so there are no corners or sides"))
+                .on_disabled_hover_text(tr("Only for two players on this computer"));
+                ui.end_row();
+
+                if settings.opponent == Opponent::Human
+                    && !settings.rengo
+                    && !self.profiles.profiles.is_empty()
+                {
+                    ui.label(tr("Players"));
+                    ui.horizontal(|ui| {
+                        ui.label(tr("Black"));
+                        profile_picker(ui, "black_profile", &mut settings.black_profile, &self.profiles);
+                        ui.label(tr("White"));
+                        profile_picker(ui, "white_profile", &mut settings.white_profile, &self.profiles);
+                    });
+                    ui.end_row();
+
+                    ui.label(tr("Rated"));
+                    ui.checkbox(&mut settings.rated, tr("Update the ratings"))
+                        .on_hover_text(tr("The result moves the ratings of both profiles"));
+                    ui.end_row();
+
+                    let record = self
+                        .profiles
+                        .head_to_head(&settings.black_profile, &settings.white_profile);
+                    if !record.meetings.is_empty() {
+                        ui.label(tr("Head to head"));
+                        ui.label(head_to_head_summary(
+                            &settings.black_profile,
+                            &settings.white_profile,
+                            &record,
+                        ));
+                        ui.end_row();
+                    }
+
+                    if let (Some(black), Some(white)) = (
+                        self.profiles.find(&settings.black_profile),
+                        self.profiles.find(&settings.white_profile),
+                    ) && let Some(gap) = profiles::stones_stronger(black, white)
+                    {
+                        // The weaker player takes Black
+                        let (weaker, swap) = if gap > 0 { (white, true) } else { (black, false) };
+                        let stones = gap.unsigned_abs();
+                        let (handicap, komi) = profiles::handicap(stones, consts::DEFAULT_KOMI);
+                        let mut apply = None;
+                        ui.label(tr("Suggested"));
+                        ui.horizontal_wrapped(|ui| {
+                            ui.label(match handicap {
+                                _ if stones == 0 => trf("Even game, komi {}", &[&komi]),
+                                0 => trf("{} takes Black, komi {}", &[&weaker.name, &komi]),
+                                _ => trf(
+                                    "{} takes Black with {} stones, komi {}",
+                                    &[&weaker.name, &handicap, &komi],
+                                ),
+                            });
+                            if ui.button(tr("Apply")).clicked() {
+                                apply = Some((handicap, komi));
+                            }
+                            if stones > 0 {
+                                let reverse = profiles::reverse_komi(stones, consts::DEFAULT_KOMI);
+                                ui.label(trf("or even with reverse komi {}", &[&reverse]))
+                                    .on_hover_text(tr("No stones: White gives Black points instead, for the ranks between them"));
+                                if ui.button(tr("Apply")).clicked() {
+                                    apply = Some((0, reverse));
+                                }
+                            }
+                        })
+                        .response
+                        .on_hover_text(tr("From the ratings once both have settled, otherwise from the ranks: a stone for each rank between them"));
+                        ui.end_row();
+                        if let Some((handicap, komi)) = apply {
+                            settings.handicap = handicap;
+                            settings.komi = komi;
+                            if swap {
+                                std::mem::swap(&mut settings.black_profile, &mut settings.white_profile);
+                            }
+                        }
+                    }
+                }
+
+                if settings.opponent == Opponent::Human {
+                    ui.label(tr("Pair go"));
+                    ui.checkbox(&mut settings.rengo, tr("Two players a side, taking turns"))
+                        .on_hover_text(tr("Four players at this computer play in turn: Black, White, Black's partner, White's partner"));
+                    ui.end_row();
+
+                    if settings.rengo {
+                        ui.label(tr("Players"));
+                        ui.vertical(|ui| {
+                            for (seat, name) in settings.pair_names.iter_mut().enumerate() {
+                                ui.add(
+                                    egui::TextEdit::singleline(name)
+                                        .hint_text(seat_name(seat))
+                                        .desired_width(160.0),
+                                );
+                            }
+                        });
+                        ui.end_row();
+                    }
+                }
+
+                ui.label(tr("Opponent"));
+                ui.horizontal(|ui| {
+                    ui.radio_value(&mut settings.opponent, Opponent::Human, tr("Human"));
+                    if consts::DESKTOP {
+                        ui.radio_value(&mut settings.opponent, Opponent::Engine, tr("GTP engine"))
+                            .on_hover_text(&settings.engine_command);
+                        ui.radio_value(
+                            &mut settings.opponent,
+                            Opponent::Host,
+                            tr("Host on network"),
+                        )
+                        .on_hover_text(tr(
+                            "Wait for another player to join over the local network",
+                        ));
+                        ui.radio_value(
+                            &mut settings.opponent,
+                            Opponent::Join,
+                            tr("Join over network"),
+                        );
+                        ui.radio_value(&mut settings.opponent, Opponent::Room, tr("Game server"));
+                        ui.radio_value(&mut settings.opponent, Opponent::Ogs, "OGS")
+                            .on_hover_text(tr("Play your games on online-go.com"));
+                        ui.radio_value(&mut settings.opponent, Opponent::Igs, "IGS")
+                            .on_hover_text(tr("Play or watch games on Pandanet and other IGS servers"));
+                    }
+                });
+                ui.end_row();
+
+                if settings.opponent == Opponent::Join {
+                    ui.label(tr("Address"));
+                    ui.text_edit_singleline(&mut settings.lan_address)
+                        .on_hover_text(tr("The IP address or name of the host's computer"));
+                    ui.end_row();
+                }
+                if matches!(settings.opponent, Opponent::Host | Opponent::Join) {
+                    ui.label(tr("Port"));
+                    ui.horizontal(|ui| {
+                        ui.add(egui::DragValue::new(&mut settings.lan_port).range(1024..=u16::MAX));
+                        ui.checkbox(&mut settings.lan_tls, tr("Encrypted"))
+                            .on_hover_text(tr("Both players need the same choice; compare the certificate shown once connected to be sure nobody listens in"));
+                    });
+                    ui.end_row();
+                }
+                #[cfg(not(target_arch = "wasm32"))]
+                if settings.opponent == Opponent::Room {
+                    ui.label(tr("Server"));
+                    ui.text_edit_singleline(&mut settings.server);
+                    ui.end_row();
+
+                    ui.label(tr("Account"));
+                    ui.horizontal(|ui| {
+                        if settings.server_token.is_empty() {
+                            ui.add(
+                                egui::TextEdit::singleline(&mut settings.server_account)
+                                    .hint_text(tr("name"))
+                                    .desired_width(100.0),
+                            );
+                            ui.add(
+                                egui::TextEdit::singleline(&mut self.server_password)
+                                    .password(true)
+                                    .hint_text(tr("password"))
+                                    .desired_width(100.0),
+                            );
+                            let ready = self.session_pending.is_none()
+                                && !settings.server_account.trim().is_empty()
+                                && !self.server_password.is_empty();
+                            let log_in = ui.add_enabled(ready, egui::Button::new(tr("Log In")));
+                            let register =
+                                ui.add_enabled(ready, egui::Button::new(tr("Register")));
+                            if log_in.clicked() || register.clicked() {
+                                let server = settings.server.clone();
+                                let name = settings.server_account.clone();
+                                let password = self.server_password.clone();
+                                let register = register.clicked();
+                                self.session_pending = Some(task::spawn(ctx, move || {
+                                    if register {
+                                        lan::register(&server, &name, &password)
+                                    } else {
+                                        lan::log_in(&server, &name, &password)
+                                    }
+                                }));
+                            }
+                        } else {
+                            ui.label(trf("Logged in as {}", &[&settings.server_account]));
+                            if ui
+                                .add_enabled(
+                                    self.profile_pending.is_none(),
+                                    egui::Button::new(tr("My Games")),
+                                )
+                                .clicked()
+                            {
+                                let server = settings.server.clone();
+                                let name = settings.server_account.clone();
+                                let token = settings.server_token.clone();
+                                self.profile_pending = Some(task::spawn(ctx, move || {
+                                    lan::profile(&server, &name, &token)
+                                }));
+                            }
+                            if ui.button(tr("Log Out")).clicked() {
+                                settings.server_token.clear();
+                                self.profile = None;
+                            }
+                        }
+                    });
+                    ui.end_row();
+
+                    if let Some(profile) = &self.profile {
+                        ui.label("");
+                        ui.label(trf(
+                            "Rating {} ({})",
+                            &[
+                                &format!("{:.0} ± {:.0}", profile.rating, profile.deviation),
+                                &protocol::rank_name(profile.rating, profile.deviation),
+                            ],
+                        ));
+                        ui.end_row();
+                        for game in profile.games.iter().take(consts::PROFILE_GAMES) {
+                            ui.label("");
+                            let name = |name: &str| {
+                                if name.is_empty() {
+                                    tr("guest")
+                                } else {
+                                    name.to_string()
+                                }
+                            };
+                            let text = trf(
+                                "{} against {}, {}",
+                                &[&name(&game.black), &name(&game.white), &game.result],
+                            );
+                            if ui
+                                .add_enabled(
+                                    self.record_pending.is_none(),
+                                    egui::Button::new(text).frame(false),
+                                )
+                                .on_hover_text(tr("Open the record"))
+                                .clicked()
+                            {
+                                let server = settings.server.clone();
+                                let file = game.file.clone();
+                                let token = settings.server_token.clone();
+                                self.record_pending = Some(task::spawn(ctx, move || {
+                                    lan::game_record(&server, &file, &token)
+                                }));
+                            }
+                            ui.end_row();
+                        }
+                    }
+
+                    ui.label(tr("Automatch"));
+                    ui.checkbox(&mut settings.automatch, tr("Find me an opponent"))
+                        .on_hover_text(tr(
+                            "Needs an account. The server pairs you with a player of a similar rating, giving the weaker player Black and a handicap for the difference",
+                        ));
+                    ui.end_row();
+                    if settings.automatch {
+                        ui.label(tr("Board sizes"));
+                        ui.horizontal(|ui| {
+                            for size in consts::MATCH_SIZES {
+                                let mut on = settings.match_sizes.contains(&size);
+                                let last = on && settings.match_sizes.len() == 1;
+                                let label = format!("{} x {}", size, size);
+                                if ui
+                                    .add_enabled(!last, egui::Checkbox::new(&mut on, label))
+                                    .changed()
+                                {
+                                    settings.match_sizes.retain(|&other| other != size);
+                                    if on {
+                                        settings.match_sizes.push(size);
+                                        settings.match_sizes.sort_unstable();
+                                    }
+                                }
+                            }
+                        });
+                        ui.end_row();
+
+                        ui.label(tr("Speeds"));
+                        ui.horizontal(|ui| {
+                            for speed in protocol::Speed::ALL {
+                                let mut on = settings.match_speeds.contains(&speed);
+                                let last = on && settings.match_speeds.len() == 1;
+                                let (main_time, overtime) = speed.clock();
+                                if ui
+                                    .add_enabled(
+                                        !last,
+                                        egui::Checkbox::new(&mut on, tr(speed.name())),
+                                    )
+                                    .on_hover_text(trf(
+                                        "{} min + {}",
+                                        &[&(main_time / 60), &overtime],
+                                    ))
+                                    .changed()
+                                {
+                                    let chosen = settings.match_speeds.clone();
+                                    settings.match_speeds = protocol::Speed::ALL
+                                        .into_iter()
+                                        .filter(|&other| {
+                                            if other == speed {
+                                                on
+                                            } else {
+                                                chosen.contains(&other)
+                                            }
+                                        })
+                                        .collect();
+                                }
+                            }
+                        });
+                        ui.end_row();
+                    }
+
+                    if !settings.automatch {
+                        ui.label(tr("Room code"));
+                        ui.horizontal(|ui| {
+                            ui.text_edit_singleline(&mut settings.room_code)
+                                .on_hover_text(tr(
+                                    "Leave empty to open a new room, or enter one whose game has started to watch it",
+                                ));
+                            if ui
+                                .add_enabled(
+                                    self.rooms_pending.is_none(),
+                                    egui::Button::new(tr("Find Rooms")),
+                                )
+                                .clicked()
+                            {
+                                let server = settings.server.clone();
+                                let token = settings.server_token.clone();
+                                self.rooms_pending = Some(task::spawn(ctx, move || {
+                                    lan::open_rooms(&server, &token)
+                                }));
+                            }
+                        });
+                        ui.end_row();
+
+                        for room in &self.rooms {
+                            ui.label("");
+                            let seat = if room.playing {
+                                tr("watch")
+                            } else {
+                                trf("play {}", &[&player_name(room.open_color)])
+                            };
+                            let mut text = trf(
+                                "{}: {} x {}, komi {}, {}",
+                                &[
+                                    &room.code,
+                                    &room.board_size,
+                                    &room.board_size,
+                                    &room.komi,
+                                    &seat,
+                                ],
+                            );
+                            if !room.time.is_empty() {
+                                text = format!("{} ({})", text, room.time);
+                            }
+                            if !room.host_rank.is_empty() {
+                                let host = format!("{} [{}]", room.host, room.host_rank);
+                                text = trf("{}, by {}", &[&text, &host]);
+                            } else if !room.host.is_empty() {
+                                text = trf("{}, by {}", &[&text, &room.host]);
+                            }
+                            if room.private {
+                                text = trf("{}, private", &[&text]);
+                            }
+                            if room.rengo {
+                                text = trf("{}, pair go", &[&text]);
+                            }
+                            if room.simul {
+                                text = trf("{}, simul", &[&text]);
+                            }
+                            if let Some(delay) = room.spectator_delay {
+                                let amount = delay.amount.to_string();
+                                text = match delay.unit {
+                                    protocol::DelayUnit::Moves => {
+                                        trf("{}, watched {} moves behind", &[&text, &amount])
+                                    }
+                                    protocol::DelayUnit::Minutes => {
+                                        trf("{}, watched {} minutes behind", &[&text, &amount])
+                                    }
+                                };
+                            }
+                            if ui
+                                .selectable_label(settings.room_code == room.code, text)
+                                .clicked()
+                            {
+                                settings.room_code = room.code.clone();
+                            }
+                            ui.end_row();
+                        }
+                    }
+                }
+
+                #[cfg(not(target_arch = "wasm32"))]
+                if settings.opponent == Opponent::Ogs {
+                    let idle =
+                        self.ogs_session_pending.is_none() && self.ogs_lobby_pending.is_none();
+                    ui.label(tr("OGS account"));
+                    ui.horizontal(|ui| {
+                        if let Some(session) = &self.ogs_session {
+                            ui.label(trf("Logged in as {}", &[&session.username]));
+                            if ui
+                                .add_enabled(idle, egui::Button::new(tr("Refresh")))
+                                .clicked()
+                            {
+                                let session = session.clone();
+                                self.ogs_lobby_pending = Some(task::spawn(ctx, move || {
+                                    ogs::lobby(&session).map(|lobby| (None, lobby))
+                                }));
+                            }
+                            if ui.button(tr("Log Out")).clicked() {
+                                self.ogs_session = None;
+                                self.ogs_lobby = ogs::Lobby::default();
+                                self.ogs_game = None;
+                            }
+                        } else {
+                            ui.add(
+                                egui::TextEdit::singleline(&mut settings.ogs_username)
+                                    .hint_text(tr("username"))
+                                    .desired_width(100.0),
+                            );
+                            ui.add(
+                                egui::TextEdit::singleline(&mut self.ogs_password)
+                                    .password(true)
+                                    .hint_text(tr("password"))
+                                    .desired_width(100.0),
+                            );
+                            let ready = idle
+                                && !settings.ogs_username.trim().is_empty()
+                                && !self.ogs_password.is_empty();
+                            if ui
+                                .add_enabled(ready, egui::Button::new(tr("Log In")))
+                                .clicked()
+                            {
+                                let username = settings.ogs_username.trim().to_string();
+                                let password = self.ogs_password.clone();
+                                self.ogs_session_pending = Some(task::spawn(ctx, move || {
+                                    ogs::log_in(&username, &password)
+                                }));
+                            }
+                        }
+                    });
+                    ui.end_row();
+
+                    ui.label(tr("Watch"));
+                    ui.horizontal(|ui| {
+                        let idle = self.ogs_watchable_pending.is_none();
+                        if ui
+                            .add_enabled(idle, egui::Button::new(tr("Top Games")))
+                            .on_hover_text(tr("The live games with the strongest players"))
+                            .clicked()
+                        {
+                            self.ogs_watchable_pending = Some(task::spawn(ctx, ogs::top_games));
+                        }
+                        ui.add(
+                            egui::TextEdit::singleline(&mut self.ogs_watch_player)
+                                .hint_text(tr("player"))
+                                .desired_width(100.0),
+                        );
+                        let player = self.ogs_watch_player.trim().to_string();
+                        if ui
+                            .add_enabled(
+                                idle && !player.is_empty(),
+                                egui::Button::new(tr("Their Games")),
+                            )
+                            .clicked()
+                        {
+                            self.ogs_watchable_pending =
+                                Some(task::spawn(ctx, move || ogs::player_games(&player)));
+                        }
+                    });
+                    ui.end_row();
+
+                    let mut chosen = None;
+                    for (heading, games) in [
+                        (tr("Your games"), &self.ogs_lobby.games),
+                        (tr("To watch"), &self.ogs_watchable),
+                    ] {
+                        for (index, game) in games.iter().enumerate() {
+                            ui.label(if index == 0 {
+                                heading.clone()
+                            } else {
+                                String::new()
+                            });
+                            let mut text = trf(
+                                "{} against {}, {} x {}",
+                                &[&game.black, &game.white, &game.board_size, &game.board_size],
+                            );
+                            if !game.name.is_empty() {
+                                text = format!("{}: {}", game.name, text);
+                            }
+                            if ui
+                                .selectable_label(self.ogs_game == Some(game.id), text)
+                                .clicked()
+                            {
+                                chosen = Some(game.id);
+                            }
+                            ui.end_row();
+                        }
+                    }
+                    if chosen.is_some() {
+                        self.ogs_game = chosen;
+                    }
+                    let mut answer = None;
+                    for (index, challenge) in self.ogs_lobby.challenges.iter().enumerate() {
+                        ui.label(if index == 0 {
+                            tr("Challenges")
+                        } else {
+                            String::new()
+                        });
+                        ui.horizontal(|ui| {
+                            let ranked = if challenge.ranked {
+                                tr("ranked")
+                            } else {
+                                tr("unranked")
+                            };
+                            ui.label(trf(
+                                "{}: {} x {}, {}",
+                                &[
+                                    &challenge.challenger,
+                                    &challenge.board_size,
+                                    &challenge.board_size,
+                                    &ranked,
+                                ],
+                            ));
+                            if ui
+                                .add_enabled(idle, egui::Button::new(tr("Accept")))
+                                .clicked()
+                            {
+                                answer = Some((challenge.id, true));
+                            }
+                            if ui
+                                .add_enabled(idle, egui::Button::new(tr("Decline")))
+                                .clicked()
+                            {
+                                answer = Some((challenge.id, false));
+                            }
+                        });
+                        ui.end_row();
+                    }
+                    if let Some((challenge, accept)) = answer
+                        && let Some(session) = self.ogs_session.clone()
+                    {
+                        self.ogs_lobby_pending = Some(task::spawn(ctx, move || {
+                            if accept {
+                                ogs::accept(&session, challenge)
+                            } else {
+                                ogs::decline(&session, challenge)
+                            }
+                        }));
+                    }
+                }
+
+                #[cfg(not(target_arch = "wasm32"))]
+                if settings.opponent == Opponent::Igs {
+                    ui.label(tr("Server"));
+                    ui.text_edit_singleline(&mut settings.igs_server);
+                    ui.end_row();
+
+                    ui.label(tr("Account"));
+                    ui.horizontal(|ui| {
+                        ui.add(
+                            egui::TextEdit::singleline(&mut settings.igs_account)
+                                .hint_text(tr("guest"))
+                                .desired_width(100.0),
+                        );
+                        ui.add(
+                            egui::TextEdit::singleline(&mut self.igs_password)
+                                .password(true)
+                                .hint_text(tr("password"))
+                                .desired_width(100.0),
+                        );
+                    });
+                    ui.end_row();
+
+                    ui.label(tr("Game or player"));
+                    ui.text_edit_singleline(&mut settings.igs_target)
+                        .on_hover_text(tr("A game number to watch, or a player to challenge to a game with the size, color and clock chosen here; leave empty to accept the first challenge that arrives"));
+                    ui.end_row();
+                }
+
+                let creates_room = settings.opponent == Opponent::Room
+                    && !settings.automatch
+                    && settings.room_code.trim().is_empty();
+                let challenges = settings.opponent == Opponent::Igs
+                    && !settings.igs_target.trim().is_empty()
+                    && settings.igs_target.trim().parse::<u32>().is_err();
+                if matches!(settings.opponent, Opponent::Engine | Opponent::Host)
+                    || creates_room
+                    || challenges
+                {
+                    if matches!(settings.opponent, Opponent::Engine | Opponent::Host)
+                        && !self.profiles.profiles.is_empty()
+                    {
+                        ui.label(tr("Your profile"));
+                        if let Some(color) =
+                            profile_picker(ui, "profile", &mut settings.profile, &self.profiles)
+                                .and_then(|profile| profile.color)
+                        {
+                            settings.human_color = color;
+                        }
+                        ui.end_row();
+                    }
+                    if settings.opponent == Opponent::Engine && !self.profiles.profiles.is_empty() {
+                        ui.label(tr("Rated"));
+                        ui.checkbox(&mut settings.rated, tr("Update the ratings"))
+                            .on_hover_text(tr("The result moves the rating of your profile and the engine's"));
+                        ui.end_row();
+                    }
+                    ui.label(tr("Your color"));
+                    ui.horizontal(|ui| {
+                        ui.radio_value(&mut settings.human_color, Player::Black, tr("Black"));
+                        ui.radio_value(&mut settings.human_color, Player::White, tr("White"));
+                    });
+                    ui.end_row();
+                }
+                if creates_room {
+                    ui.label(tr("Pair go"));
+                    ui.checkbox(&mut settings.rengo, tr("Two players a side, taking turns"))
+                        .on_hover_text(tr("The room waits for four players. A player who leaves loses the game for their side"));
+                    ui.end_row();
+
+                    if !settings.rengo {
+                        ui.label(tr("Simultaneous games"));
+                        ui.horizontal(|ui| {
+                            ui.add(
+                                egui::DragValue::new(&mut settings.simul_boards)
+                                    .range(1..=consts::MAX_SIMUL_BOARDS),
+                            )
+                            .on_hover_text(tr("Play several opponents at once: each board gets a room of its own and a tab here, and each opponent only sees their own game"));
+                            ui.label(tr("boards"));
+                        });
+                        ui.end_row();
+                    }
+
+                    ui.label(tr("Spectator delay"));
+                    ui.horizontal(|ui| {
+                        ui.add(
+                            egui::DragValue::new(&mut settings.spectator_delay).range(0..=120),
+                        )
+                        .on_hover_text(tr("Spectators see each move only this much later, so nobody can pass hints to the players; 0 shows the game as it is played"));
+                        egui::ComboBox::from_id_salt("spectator_delay_unit")
+                            .selected_text(tr(settings.spectator_delay_unit.name()))
+                            .show_ui(ui, |ui| {
+                                for unit in protocol::DelayUnit::ALL {
+                                    ui.selectable_value(
+                                        &mut settings.spectator_delay_unit,
+                                        unit,
+                                        tr(unit.name()),
+                                    );
+                                }
+                            });
+                    });
+                    ui.end_row();
+                }
+                if creates_room && !settings.server_token.is_empty() {
+                    ui.label(tr("Invite"));
+                    ui.add(
+                        egui::TextEdit::singleline(&mut settings.invited)
+                            .hint_text(tr("names, separated by commas")),
+                    )
+                    .on_hover_text(tr(
+                        "Only these accounts may play or watch; leave empty for a game open to all",
+                    ));
+                    ui.end_row();
+
+                    if !settings.rengo {
+                        ui.label(tr("Correspondence"));
+                        ui.checkbox(&mut settings.correspondence, tr("Keep the game on the server"))
+                            .on_hover_text(tr("For a slow game: leave whenever you like and come back to play your move, even days later. Both players need accounts"));
+                        ui.end_row();
+                    }
+                }
+                let correspondence = creates_room
+                    && settings.correspondence
+                    && !settings.rengo
+                    && !settings.server_token.is_empty();
+                if settings.opponent == Opponent::Host
+                    || (creates_room && !correspondence && !settings.rengo)
+                {
+                    ui.label(tr("Dropped connection"));
+                    ui.horizontal(|ui| {
+                        ui.add(
+                            egui::DragValue::new(&mut settings.reconnect_seconds)
+                                .range(0..=600)
+                                .suffix(" s"),
+                        )
+                        .on_hover_text(tr(
+                            "How long a player whose connection drops has to come back; 0 ends the game at once",
+                        ));
+                        ui.label(tr("clocks"));
+                        egui::ComboBox::from_id_salt("away_clock")
+                            .selected_text(tr(settings.away_clock.name()))
+                            .show_ui(ui, |ui| {
+                                for clock in protocol::AwayClock::ALL {
+                                    ui.selectable_value(
+                                        &mut settings.away_clock,
+                                        clock,
+                                        tr(clock.name()),
+                                    );
+                                }
+                            });
+                    });
+                    ui.end_row();
+                }
+            });
+
+            if consts::DESKTOP {
+                self.show_profiles(ui);
+            }
+
+            ui.separator();
+
+            ui.horizontal(|ui| {
+                if ui.button(tr("Start Game")).clicked() {
+                    self.start_game();
+                    // The tabs open a room for each further board
+                    if self.lan_simul {
+                        self.simul_extra = self.new_game.simul_boards - 1;
+                    }
+                }
+                if ui.button(tr("Settings")).clicked() {
+                    self.preferences = Some(self.new_game.clone());
+                }
+                if consts::DESKTOP && ui.button(tr("Game Database")).clicked() {
+                    self.state = AppState::Database;
+                }
+                if consts::DESKTOP && ui.button(tr("Problem Sets")).clicked() {
+                    self.state = AppState::Problems;
+                }
+                if ui.button(tr("Fuseki Explorer")).clicked() {
+                    self.clear_game();
+                    self.show_fuseki = true;
+                }
+                if ui
+                    .button(tr("Learn to Play"))
+                    .on_hover_text(tr("The rules in eight short lessons, each with a move to find"))
+                    .clicked()
+                {
+                    self.start_lesson(0);
+                }
+                if ui
+                    .button(tr("Shape Drills"))
+                    .on_hover_text(tr("Short problems on the tiger's mouth, the bamboo joint, the net and the snapback"))
+                    .clicked()
+                {
+                    self.start_drill();
+                }
+                let today = problems::today();
+                let streak = self
+                    .streak
+                    .get_or_insert_with(|| daily::Streak::load(&paths::daily_path()));
+                let (days, tried) = (streak.current(today), streak.tried(today));
+                if ui
+                    .button(tr("Puzzle of the Day"))
+                    .on_hover_text(tr("A new shape drill every day, the same for everyone"))
+                    .clicked()
+                {
+                    self.start_daily();
+                }
+                if days > 0 {
+                    ui.label(trf("{} days in a row", &[&days]));
+                } else if tried {
+                    ui.label(tr("Tried today"));
+                }
+            });
+
+            if consts::DESKTOP && !recent_files.is_empty() {
+                let mut open = None;
+                ui.horizontal_wrapped(|ui| {
+                    ui.label(tr("Recent"));
+                    for path in recent_files {
+                        let name = path.file_name().unwrap_or(path.as_os_str());
+                        if ui
+                            .button(name.to_string_lossy())
+                            .on_hover_text(path.display().to_string())
+                            .clicked()
+                        {
+                            open = Some(path.clone());
+                        }
+                    }
+                });
+                if let Some(path) = open
+                    && let Err(err) = self.open_file(path)
+                {
+                    self.file_status = Some(err);
+                }
+            }
+            if let Some(status) = &self.file_status {
+                ui.label(status);
+            }
+
+            #[cfg(not(target_arch = "wasm32"))]
+            {
+                ui.separator();
+                self.show_ogs_download(ui);
+            }
+
+            if consts::DESKTOP {
+                ui.separator();
+                self.show_library(ui);
+            }
+        });
+    }
+
+    // Edits a copy of the settings, which only takes effect on Apply
+    fn show_preferences(&mut self, ctx: &egui::Context) {
+        let Some(settings) = &mut self.preferences else {
+            return;
+        };
+        let mut open = true;
+        let mut apply = false;
+        egui::Window::new(tr("Settings"))
+            .open(&mut open)
+            .resizable(false)
+            .show(ctx, |ui| {
+                egui::Grid::new("preferences").num_columns(2).show(ui, |ui| {
+                    if consts::DESKTOP {
+                        ui.label(tr("Engine command"));
+                        ui.text_edit_singleline(&mut settings.engine_command);
+                        ui.end_row();
+                    }
+
+                    ui.label(tr("Confirm moves"));
+                    ui.checkbox(&mut settings.confirm_moves, tr("Click twice to play"));
+                    ui.end_row();
+
+                    ui.label(tr("Self-atari"));
+                    ui.checkbox(
+                        &mut settings.warn_self_atari,
+                        tr("Ask before playing into atari"),
+                    );
+                    ui.end_row();
+
+                    ui.label(tr("Chat"));
+                    ui.checkbox(
+                        &mut settings.chat_in_record,
+                        tr("Save network chat in the record as comments"),
+                    );
+                    ui.end_row();
+
+                    ui.label(tr("Language"));
+                    egui::ComboBox::from_id_salt("language")
+                        .selected_text(&settings.language)
+                        .show_ui(ui, |ui| {
+                            for language in i18n::languages() {
+                                let label = language.clone();
+                                ui.selectable_value(&mut settings.language, language, label);
+                            }
+                        })
+                        .response
+                        .on_hover_text(trf(
+                            "Translations are read from {}",
+                            &[&paths::locales_dir().display()],
+                        ));
+                    ui.end_row();
+
+                    ui.label(tr("Coordinates"));
+                    egui::ComboBox::from_id_salt("coordinates")
+                        .selected_text(tr(settings.coordinates.name()))
+                        .show_ui(ui, |ui| {
+                            for style in CoordinateStyle::ALL {
+                                ui.selectable_value(&mut settings.coordinates, style, tr(style.name()));
+                            }
+                        });
+                    ui.end_row();
+
+                    ui.label(tr("Theme"));
+                    egui::ComboBox::from_id_salt("ui_theme")
+                        .selected_text(tr(settings.ui_theme.name()))
+                        .show_ui(ui, |ui| {
+                            for theme in UiTheme::ALL {
+                                ui.selectable_value(&mut settings.ui_theme, theme, tr(theme.name()));
+                            }
+                        });
+                    ui.end_row();
+
+                    ui.label(tr("Interface scale"));
+                    ui.horizontal(|ui| {
+                        ui.add(
+                            egui::Slider::new(&mut settings.ui_scale, consts::UI_SCALE_RANGE)
+                                .step_by(0.05),
+                        )
+                        .on_hover_text(tr("Also changed with Ctrl+Plus and Ctrl+Minus"));
+                        ui.checkbox(&mut settings.follow_system_scale, tr("Follow system scaling"))
+                            .on_hover_text(tr(
+                                "Turn off to use the scale as pixels per point whatever the display reports",
+                            ));
+                    });
+                    ui.end_row();
+
+                    ui.label(tr("Board"));
+                    egui::ComboBox::from_id_salt("board_theme")
+                        .selected_text(tr(settings.board_theme.name()))
+                        .show_ui(ui, |ui| {
+                            for theme in BoardTheme::ALL {
+                                ui.selectable_value(&mut settings.board_theme, theme, tr(theme.name()));
+                            }
+                        });
+                    ui.end_row();
+
+                    ui.label(tr("Skin"));
+                    egui::ComboBox::from_id_salt("skin")
+                        .selected_text(settings.skin.clone().unwrap_or_else(|| tr("None")))
+                        .show_ui(ui, |ui| {
+                            ui.selectable_value(&mut settings.skin, None, tr("None"));
+                            for name in skin::available() {
+                                let label = name.clone();
+                                ui.selectable_value(&mut settings.skin, Some(name), label);
+                            }
+                        })
+                        .response
+                        .on_hover_text(trf(
+                            "board.png, black.png and white.png from a folder in {}",
+                            &[&paths::skins_dir().display()],
+                        ));
+                    ui.end_row();
+
+                    ui.label(tr("Markers"));
+                    ui.horizontal(|ui| {
+                        egui::ComboBox::from_id_salt("marker_shape")
+                            .selected_text(tr(settings.marker_shape.name()))
+                            .show_ui(ui, |ui| {
+                                for shape in MarkerShape::ALL {
+                                    ui.selectable_value(
+                                        &mut settings.marker_shape,
+                                        shape,
+                                        tr(shape.name()),
+                                    );
+                                }
+                            });
+                        ui.checkbox(&mut settings.high_contrast, tr("High contrast"));
+                    });
+                    ui.end_row();
+
+                    if consts::DESKTOP {
+                        ui.label(tr("Speech"));
+                        ui.horizontal(|ui| {
+                            ui.checkbox(&mut settings.speak_moves, tr("Read moves aloud"));
+                            if settings.speak_moves || settings.speak_countdown {
+                                ui.label(tr("with"));
+                                ui.text_edit_singleline(&mut settings.speech_command);
+                            }
+                        });
+                        ui.end_row();
+
+                        ui.label(tr("Voice"));
+                        ui.horizontal(|ui| {
+                            ui.checkbox(&mut settings.voice_moves, tr("Take moves by voice"))
+                                .on_hover_text(tr("Play what a speech recognizer hears, such as \"black Q sixteen\", \"pass\", \"undo\", \"back\" or \"next\""));
+                            if settings.voice_moves {
+                                ui.label(tr("from"));
+                                ui.add(
+                                    egui::TextEdit::singleline(&mut settings.voice_command)
+                                        .hint_text(tr("a program printing each phrase it hears")),
+                                );
+                            }
+                        });
+                        ui.end_row();
+
+                        ui.label(tr("Notifications"));
+                        ui.checkbox(&mut settings.notify_when_away, tr("Notify when away"))
+                            .on_hover_text(tr("While the window is minimized or behind others, tell about network games: your move, an opponent arriving, a low clock and the end of the game. Clicking the notification goes to the game's tab"));
+                        ui.end_row();
+
+                        if consts::DESKTOP {
+                            ui.label(tr("Review"));
+                            ui.checkbox(&mut settings.review_engine, tr("Score with the engine"))
+                                .on_hover_text(tr("\"Analyze\" asks the engine command for the final_score of every position instead of using the quick estimate"));
+                            ui.end_row();
+
+                            ui.label(tr("Analysis cache"));
+                            ui.horizontal(|ui| {
+                                ui.add(
+                                    egui::DragValue::new(&mut settings.analysis_cache_megabytes)
+                                        .range(1..=4096)
+                                        .suffix(" MB"),
+                                )
+                                .on_hover_text(tr("The engine's scores are kept for each position, engine version, command line, komi and rules, and used again instead of asking it twice; past this size the ones used longest ago are dropped"));
+                                let used = analysis_cache::bytes() as f32 / (1024.0 * 1024.0);
+                                ui.label(trf("{} MB used", &[&i18n::number((used * 10.0).round() / 10.0)]));
+                                if ui.button(tr("Clear")).clicked()
+                                    && let Err(err) = analysis_cache::clear()
+                                {
+                                    tracing::warn!(target: "analysis", "could not clear the cache: {}", err);
+                                }
+                            });
+                            ui.end_row();
+                        }
+
+                        ui.label(tr("Sync folder"));
+                        ui.add(
+                            egui::TextEdit::singleline(&mut settings.sync_folder)
+                                .hint_text(tr("none")),
+                        )
+                        .on_hover_text(tr("Games played here are kept up to date in this folder, under names of this computer's own. Point it at a folder Dropbox or Syncthing shares, and the library on your other computers lists the newest copy of each game to play on"));
+                        ui.end_row();
+
+                        ui.label(tr("Broadcast port"));
+                        ui.add(egui::DragValue::new(&mut settings.broadcast_port).range(1024..=65535))
+                            .on_hover_text(tr("\"Toggle broadcast\" in the command palette shows the game on a web page at this port, for phones on the same network"));
+                        ui.end_row();
+
+                        ui.label(tr("Countdown"));
+                        ui.horizontal(|ui| {
+                            ui.checkbox(&mut settings.speak_countdown, tr("Count overtime aloud"))
+                                .on_hover_text(tr("Read the last ten seconds of each period"));
+                            if settings.speak_countdown {
+                                egui::ComboBox::from_id_salt("countdown_language")
+                                    .selected_text(tr(settings.countdown_language.name()))
+                                    .show_ui(ui, |ui| {
+                                        for language in clock::CountdownLanguage::ALL {
+                                            ui.selectable_value(
+                                                &mut settings.countdown_language,
+                                                language,
+                                                tr(language.name()),
+                                            );
+                                        }
+                                    });
+                            }
+                        });
+                        ui.end_row();
+                    }
+
+                    ui.label(tr("Sounds"));
+                    ui.horizontal(|ui| {
+                        egui::ComboBox::from_id_salt("sound_pack")
+                            .selected_text(settings.sound_pack.clone().unwrap_or_else(|| tr("Off")))
+                            .show_ui(ui, |ui| {
+                                ui.selectable_value(&mut settings.sound_pack, None, tr("Off"));
+                                for name in sound::packs() {
+                                    let label = name.clone();
+                                    ui.selectable_value(&mut settings.sound_pack, Some(name), label);
+                                }
+                            })
+                            .response
+                            .on_hover_text(trf(
+                                "stone, capture, game_end and time_warning (.wav or .ogg) from a folder in {}",
+                                &[&paths::sounds_dir().display()],
+                            ));
+                        if settings.sound_pack.is_some() {
+                            ui.checkbox(&mut settings.stone_sound, tr("Stones"));
+                            ui.checkbox(&mut settings.capture_sound, tr("Captures"));
+                            ui.checkbox(&mut settings.game_end_sound, tr("Game end"));
+                            ui.checkbox(&mut settings.time_warning_sound, tr("Low time"));
+                            if settings.time_warning_sound {
+                                ui.add(
+                                    egui::Slider::new(&mut settings.time_warning_volume, 0.0..=1.0)
+                                        .text(tr("volume")),
+                                );
+                            }
+                        }
+                    });
+                    ui.end_row();
+                });
+                ui.separator();
+                ui.horizontal(|ui| {
+                    if ui.button(tr("Apply")).clicked() {
+                        apply = true;
+                    }
+                    if ui.button(tr("Reset")).clicked() {
+                        let chosen = std::mem::take(settings);
+                        settings.keep_game_choices(&chosen);
+                    }
+                });
+            });
+        if apply {
+            let mut applied = settings.clone();
+            applied.keep_game_choices(&self.new_game);
+            self.new_game = applied;
+            self.preferences_applied = true;
+        }
+        if !open {
+            self.preferences = None;
+        }
+    }
+
+    fn show_database(&mut self, ctx: &egui::Context) {
+        egui::CentralPanel::default().show(ctx, |ui| {
+            ui.horizontal(|ui| {
+                if ui.button(tr("Back")).clicked() {
+                    self.state = AppState::Options;
+                }
+                ui.heading(tr("Game Database"));
+            });
+
+            ui.horizontal(|ui| {
+                ui.add(
+                    egui::TextEdit::singleline(&mut self.db_import_input)
+                        .hint_text(tr("Folder of SGF files")),
+                );
+                let can_import =
+                    self.db_pending.is_none() && !self.db_import_input.trim().is_empty();
+                if ui
+                    .add_enabled(can_import, egui::Button::new(tr("Import")))
+                    .clicked()
+                {
+                    let dir = PathBuf::from(self.db_import_input.trim());
+                    self.db_status = Some(trf("Importing {}...", &[&dir.display()]));
+                    self.db_pending = Some(task::spawn(ui.ctx(), move || database::scan(&dir)));
+                }
+            });
+            #[cfg(not(target_arch = "wasm32"))]
+            self.show_archives(ui);
+            if let Some(status) = &self.db_status {
+                ui.label(status);
+            }
+            ui.separator();
+
+            let database = self.database.get_or_insert_with(|| {
+                let database = database::Database::load(&paths::database_path());
+                self.db_results = database.search(&self.db_query);
+                database
+            });
+            let query = &mut self.db_query;
+            let mut search = false;
+            ui.horizontal(|ui| {
+                for (hint, value) in [
+                    ("Player", &mut query.player),
+                    ("Event", &mut query.event),
+                    ("Year", &mut query.year),
+                    ("Result", &mut query.result),
+                ] {
+                    let response = ui.add(
+                        egui::TextEdit::singleline(value)
+                            .hint_text(tr(hint))
+                            .desired_width(100.0),
+                    );
+                    search |=
+                        response.lost_focus() && ui.input(|i| i.key_pressed(egui::Key::Enter));
+                }
+                let size_label = query
+                    .board_size
+                    .map_or(tr("Any size"), |size| format!("{} x {}", size, size));
+                egui::ComboBox::from_id_salt("db_size")
+                    .selected_text(size_label)
+                    .show_ui(ui, |ui| {
+                        ui.selectable_value(&mut query.board_size, None, tr("Any size"));
+                        for &size in consts::VALID_BOARD_SIZES {
+                            ui.selectable_value(
+                                &mut query.board_size,
+                                Some(size),
+                                format!("{} x {}", size, size),
+                            );
+                        }
+                    });
+                search |= ui.button(tr("Search")).clicked();
+            });
+            if search {
+                self.db_results = database.search(query);
+            }
+            ui.label(trf(
+                "{} of {} games",
+                &[&self.db_results.len(), &database.records.len()],
+            ));
+
+            let mut open = None;
+            let row_height = ui.spacing().interact_size.y;
+            egui::ScrollArea::vertical().show_rows(
+                ui,
+                row_height,
+                self.db_results.len(),
+                |ui, rows| {
+                    egui::Grid::new("db_results")
+                        .striped(true)
+                        .num_columns(7)
+                        .show(ui, |ui| {
+                            for &index in &self.db_results[rows] {
+                                let record = &database.records[index];
+                                ui.label(&record.black);
+                                ui.label(&record.white);
+                                ui.label(&record.date);
+                                ui.label(&record.event);
+                                ui.label(&record.result);
+                                ui.label(trf("{} moves", &[&record.moves().count()]));
+                                ui.horizontal(|ui| {
+                                    if ui.button(tr("Open")).clicked() {
+                                        open = Some((record.path.clone(), Opening::Review));
+                                    }
+                                    if ui.button(tr("Guess Moves")).clicked() {
+                                        open = Some((record.path.clone(), Opening::Guess));
+                                    }
+                                    if ui.button(tr("Solve")).clicked() {
+                                        open = Some((record.path.clone(), Opening::Solve));
+                                    }
+                                });
+                                ui.end_row();
+                            }
+                        });
+                },
+            );
+            if let Some((path, opening)) = open {
+                let result = match opening {
+                    Opening::Review => self.open_file(path),
+                    Opening::Guess => self.start_guessing(path),
+                    Opening::Solve => self.start_problem(path),
+                };
+                if let Err(err) = result {
+                    self.db_status = Some(err);
+                }
+            }
+        });
+    }
+
+    // The players kept for the new game form, edited in place and written at every change
+    fn show_profiles(&mut self, ui: &mut egui::Ui) {
+        egui::CollapsingHeader::new(tr("Player Profiles")).show(ui, |ui| {
+            let mut changed = false;
+            let mut removed = None;
+            egui::Grid::new("profiles").num_columns(6).show(ui, |ui| {
+                for (index, profile) in self.profiles.profiles.iter_mut().enumerate() {
+                    changed |= ui
+                        .add(
+                            egui::TextEdit::singleline(&mut profile.avatar)
+                                .hint_text("🙂")
+                                .desired_width(30.0),
+                        )
+                        .on_hover_text(tr("Shown before the name, such as an emoji"))
+                        .changed();
+                    changed |= ui
+                        .add(
+                            egui::TextEdit::singleline(&mut profile.name)
+                                .hint_text(tr("name"))
+                                .desired_width(120.0),
+                        )
+                        .changed();
+                    changed |= ui
+                        .add(
+                            egui::TextEdit::singleline(&mut profile.rank)
+                                .hint_text(tr("rank"))
+                                .desired_width(40.0),
+                        )
+                        .changed();
+                    let rating = profile.rating;
+                    let text = if rating.provisional() {
+                        format!("{:.0}?", rating.value)
+                    } else {
+                        format!("{:.0}", rating.value)
+                    };
+                    ui.label(text).on_hover_text(trf(
+                        "Rating from {} rated games here; marked ? until it has settled",
+                        &[&rating.games],
+                    ));
+                    let color = match profile.color {
+                        Some(player) => player_name(player),
+                        None => tr("Either color"),
+                    };
+                    egui::ComboBox::from_id_salt(("profile_color", index))
+                        .selected_text(color)
+                        .show_ui(ui, |ui| {
+                            for color in [None, Some(Player::Black), Some(Player::White)] {
+                                let text = color.map_or_else(|| tr("Either color"), player_name);
+                                changed |= ui
+                                    .selectable_value(&mut profile.color, color, text)
+                                    .changed();
+                            }
+                        })
+                        .response
+                        .on_hover_text(tr(
+                            "Taken when playing an engine or hosting a network game",
+                        ));
+                    if ui.button(tr("Remove")).clicked() {
+                        removed = Some(index);
+                    }
+                    ui.end_row();
+                }
+            });
+            if let Some(index) = removed {
+                self.profiles.profiles.remove(index);
+                changed = true;
+            }
+            if !self.profiles.engines.is_empty() {
+                let engines: Vec<String> = self
+                    .profiles
+                    .engines
+                    .iter()
+                    .map(|(name, rating)| format!("{} {:.0}", name, rating.value))
+                    .collect();
+                ui.label(trf("Engines: {}", &[&engines.join(", ")]));
+            }
+            ui.horizontal(|ui| {
+                if ui.button(tr("Add Profile")).clicked() {
+                    self.profiles.profiles.push(profiles::Profile::default());
+                    changed = true;
+                }
+                if let Some(status) = &self.profiles_status {
+                    ui.label(status);
+                }
+            });
+            if changed {
+                self.profiles_status = self
+                    .profiles
+                    .save(&paths::profiles_path())
+                    .err()
+                    .map(|err| trf("Could not write the profiles: {}", &[&err]));
+            }
+        });
+    }
+
+    // The correspondence games played here, to go back to one or to look at its record as last
+    // seen while the server is out of reach
+    #[cfg(not(target_arch = "wasm32"))]
+    fn show_correspondence(&mut self, ui: &mut egui::Ui) {
+        if self.correspondence.games.is_empty() {
+            return;
+        }
+        ui.horizontal(|ui| {
+            ui.label(tr("Correspondence games"));
+            let idle =
+                self.correspondence_pending.is_none() && !self.new_game.server_token.is_empty();
+            if ui
+                .add_enabled(idle, egui::Button::new(tr("Refresh")))
+                .clicked()
+            {
+                self.correspondence_checked = false;
+            }
+        });
+        let mut play = None;
+        let mut view = None;
+        for (index, game) in self.correspondence.games.iter().enumerate() {
+            ui.horizontal(|ui| {
+                let color = player_name(game.color);
+                let text = if game.opponent.is_empty() {
+                    trf(
+                        "{} in room {}, waiting for an opponent",
+                        &[&color, &game.code],
+                    )
+                } else if game.your_turn {
+                    trf("{} against {}: your turn", &[&color, &game.opponent])
+                } else {
+                    trf("{} against {}: their turn", &[&color, &game.opponent])
+                };
+                if game.your_turn {
+                    ui.strong(text)
+                } else {
+                    ui.label(text)
+                }
+                .on_hover_text(&game.server);
+                if ui.button(tr("Play")).clicked() {
+                    play = Some(index);
+                }
+                if ui
+                    .add_enabled(!game.sgf.is_empty(), egui::Button::new(tr("View")))
+                    .on_hover_text(tr("The record as last seen here"))
+                    .clicked()
+                {
+                    view = Some(index);
+                }
+            });
+        }
+        if let Some(index) = play {
+            let game = self.correspondence.games[index].clone();
+            self.new_game.opponent = Opponent::Room;
+            self.new_game.automatch = false;
+            self.new_game.server = game.server;
+            self.new_game.room_code = game.code;
+            self.start_game();
+        }
+        if let Some(index) = view {
+            let sgf = self.correspondence.games[index].sgf.clone();
+            match self.load_sgf(&sgf) {
+                Ok(()) => self.state = AppState::Game,
+                Err(err) => self.file_status = Some(err),
+            }
+        }
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    fn show_ogs_download(&mut self, ui: &mut egui::Ui) {
+        ui.label(tr("Open from online-go.com"));
+        let idle = self.ogs_pending.is_none();
+        ui.horizontal(|ui| {
+            ui.add(
+                egui::TextEdit::singleline(&mut self.ogs_game_input)
+                    .hint_text(tr("Game ID or URL")),
+            );
+            if ui
+                .add_enabled(idle, egui::Button::new(tr("Open")))
+                .clicked()
+            {
+                match ogs::parse_game_id(&self.ogs_game_input) {
+                    Some(id) => {
+                        self.ogs_status = Some(trf("Downloading game {}...", &[&id]));
+                        self.ogs_pending = Some(task::spawn(ui.ctx(), move || {
+                            ogs::Response::Game(ogs::fetch_sgf(id))
+                        }));
+                    }
+                    None => self.ogs_status = Some(tr("Not an OGS game ID or URL")),
+                }
+            }
+        });
+        ui.horizontal(|ui| {
+            ui.add(
+                egui::TextEdit::singleline(&mut self.ogs_player_input).hint_text(tr("Username")),
+            );
+            ui.add(egui::DragValue::new(&mut self.ogs_player_count).range(1..=100));
+            let can_download = idle && !self.ogs_player_input.trim().is_empty();
+            if ui
+                .add_enabled(can_download, egui::Button::new(tr("Download recent games")))
+                .clicked()
+            {
+                let username = self.ogs_player_input.trim().to_string();
+                let count = self.ogs_player_count;
+                let dir = self.ogs_download_dir();
+                self.ogs_status = Some(trf("Downloading games of {}...", &[&username]));
+                self.ogs_pending = Some(task::spawn(ui.ctx(), move || {
+                    ogs::Response::PlayerGames(ogs::fetch_player_games(&username, count, &dir))
+                }));
+            }
+        });
+        if let Some(status) = &self.ogs_status {
+            ui.label(status);
+        }
+    }
+
+    fn show_problem_editor(&mut self, ui: &mut egui::Ui) {
+        let current = self.tree.current();
+        ui.horizontal(|ui| {
+            ui.add_enabled_ui(self.can_edit_position(), |ui| {
+                for player in [Player::Black, Player::White] {
+                    if ui
+                        .selectable_label(
+                            self.start_player == player,
+                            trf("{} to play", &[&player_name(player)]),
+                        )
+                        .clicked()
+                    {
+                        self.start_player = player;
+                        self.rebuild(&[]);
+                    }
+                }
+            });
+            ui.separator();
+            let verdict = self.tree.node(current).verdict;
+            for (label, hint, mark) in [
+                (
+                    "Correct",
+                    "The line ending with this move solves the problem",
+                    Verdict::Correct,
+                ),
+                ("Wrong", "This move fails", Verdict::Wrong),
+            ] {
+                if ui
+                    .add_enabled(
+                        current != GameTree::ROOT,
+                        egui::Button::new(tr(label)).selected(verdict == Some(mark)),
+                    )
+                    .on_hover_text(tr(hint))
+                    .clicked()
+                {
+                    let verdict = (verdict != Some(mark)).then_some(mark);
+                    self.tree.set_verdict(current, verdict);
+                }
+            }
+            ui.separator();
+            if ui
+                .add_enabled(
+                    !self.tree.node(GameTree::ROOT).children.is_empty(),
+                    egui::Button::new(tr("Export Problem")),
+                )
+                .on_hover_text(tr(
+                    "Save the problem to its own file, for the problem sets and other programs",
+                ))
+                .clicked()
+            {
+                self.export_problem();
+            }
+        });
+        if current == GameTree::ROOT {
+            ui.label(tr(
+                "The comment on the starting position is the problem's description",
+            ));
+        }
+    }
+
+    fn show_problem_sets(&mut self, ctx: &egui::Context) {
+        egui::CentralPanel::default().show(ctx, |ui| {
+            ui.horizontal(|ui| {
+                if ui.button(tr("Back")).clicked() {
+                    self.state = AppState::Options;
+                }
+                ui.heading(tr("Problem Sets"));
+            });
+
+            let sets = self
+                .problem_sets
+                .get_or_insert_with(|| problems::Sets::load(&paths::problem_sets_path()));
+            let mut changed = false;
+            ui.horizontal(|ui| {
+                ui.add(
+                    egui::TextEdit::singleline(&mut self.problem_sets_input)
+                        .hint_text(tr("Folder of SGF problems")),
+                );
+                if ui
+                    .add_enabled(
+                        !self.problem_sets_input.trim().is_empty(),
+                        egui::Button::new(tr("Add Set")),
+                    )
+                    .clicked()
+                {
+                    let folder = PathBuf::from(self.problem_sets_input.trim());
+                    let count = sets.add(folder);
+                    self.problem_sets_status = Some(trf("Added a set of {} problems", &[&count]));
+                    self.problem_sets_input.clear();
+                    changed = true;
+                }
+            });
+            if let Some(status) = &self.problem_sets_status {
+                ui.label(status);
+            }
+            ui.separator();
+
+            let today = problems::today();
+            let mut review = None;
+            let mut solve = None;
+            let mut remove = None;
+            egui::ScrollArea::vertical().show(ui, |ui| {
+                for (index, set) in sets.sets.iter_mut().enumerate() {
+                    ui.horizontal(|ui| {
+                        ui.strong(&set.name);
+                        ui.label(trf(
+                            "{} of {} solved, {} due today",
+                            &[&set.solved(), &set.problems.len(), &set.due(today)],
+                        ));
+                        if ui
+                            .add_enabled(set.due(today) > 0, egui::Button::new(tr("Review")))
+                            .clicked()
+                        {
+                            review = Some(index);
+                        }
+                        if ui
+                            .button(tr("Refresh"))
+                            .on_hover_text(tr("Pick up the problems added to the folder"))
+                            .clicked()
+                        {
+                            set.refresh();
+                            changed = true;
+                        }
+                        if ui.button(tr("Remove")).clicked() {
+                            remove = Some(index);
+                        }
+                    });
+                    egui::CollapsingHeader::new(tr("Problems"))
+                        .id_salt(("problem_set", index))
+                        .show(ui, |ui| {
+                            egui::Grid::new(("problems", index))
+                                .striped(true)
+                                .num_columns(4)
+                                .show(ui, |ui| {
+                                    for problem in &set.problems {
+                                        let name = problem
+                                            .path
+                                            .file_name()
+                                            .unwrap_or(problem.path.as_os_str());
+                                        ui.label(name.to_string_lossy());
+                                        // Solved and missed tries, oldest first
+                                        let history: String = problem
+                                            .history
+                                            .iter()
+                                            .map(|attempt| if attempt.solved { '✔' } else { '✘' })
+                                            .collect();
+                                        ui.label(history);
+                                        ui.label(match problem.days_until_due(today) {
+                                            0 => tr("due"),
+                                            days => trf("due in {} days", &[&days]),
+                                        });
+                                        if ui.button(tr("Solve")).clicked() {
+                                            solve = Some((index, problem.path.clone()));
+                                        }
+                                        ui.end_row();
+                                    }
+                                });
+                        });
+                    ui.separator();
+                }
+            });
+            if let Some(index) = remove {
+                sets.sets.remove(index);
+                changed = true;
+            }
+            if changed && let Err(err) = sets.save(&paths::problem_sets_path()) {
+                self.problem_sets_status = Some(trf("Could not write problem sets: {}", &[&err]));
+            }
+            if let Some(set) = review {
+                self.review_next(set);
+            }
+            if let Some((set, path)) = solve {
+                match self.start_problem(path) {
+                    Ok(()) => self.reviewing = Some(set),
+                    Err(err) => self.problem_sets_status = Some(err),
+                }
+            }
+        });
+    }
+
+    fn show_library(&mut self, ui: &mut egui::Ui) {
+        ui.horizontal(|ui| {
+            ui.label(tr("Library"));
+            if ui.button(tr("Refresh")).clicked() {
+                self.library = None;
+            }
+        });
+        #[cfg(not(target_arch = "wasm32"))]
+        let folder = self.new_game.sync_folder.trim().to_string();
+        let entries = self.library.get_or_insert_with(|| {
+            // With the newest copy of each game kept in the sync folder
+            #[cfg(not(target_arch = "wasm32"))]
+            if !folder.is_empty() {
+                let mut entries = library::scan(&paths::games_dir());
+                entries.extend(sync_folder::games(std::path::Path::new(&folder)));
+                return entries;
+            }
+            library::scan(&paths::games_dir())
+        });
+        let mut open = None;
+        egui::ScrollArea::vertical().show(ui, |ui| {
+            egui::Grid::new("library")
+                .striped(true)
+                .num_columns(7)
+                .show(ui, |ui| {
+                    for heading in ["Black", "White", "Date", "Event", "Size", "Result", ""] {
+                        ui.strong(tr(heading));
+                    }
+                    ui.end_row();
+                    for entry in entries.iter() {
+                        ui.label(entry.info.black_label());
+                        ui.label(entry.info.white_label());
+                        ui.label(&entry.info.date);
+                        ui.label(&entry.info.event);
+                        ui.label(&entry.board_size);
+                        ui.label(&entry.info.result);
+                        ui.horizontal(|ui| {
+                            if ui.button(tr("Open")).clicked() {
+                                open = Some((entry.path.clone(), Opening::Review));
+                            }
+                            if ui.button(tr("Guess Moves")).clicked() {
+                                open = Some((entry.path.clone(), Opening::Guess));
+                            }
+                            if ui
+                                .button(tr("Solve"))
+                                .on_hover_text(tr(
+                                    "Try a problem whose variations hold its solution",
+                                ))
+                                .clicked()
+                            {
+                                open = Some((entry.path.clone(), Opening::Solve));
+                            }
+                        });
+                        ui.end_row();
+                    }
+                });
+        });
+        if let Some((path, opening)) = open {
+            let result = match opening {
+                Opening::Review => self.open_file(path),
+                Opening::Guess => self.start_guessing(path),
+                Opening::Solve => self.start_problem(path),
+            };
+            if let Err(err) = result {
+                self.file_status = Some(err);
+            }
+        }
+    }
+
+    fn show_game_info(&mut self, ctx: &egui::Context) {
+        let mut open = self.layout.show_info;
+        egui::Window::new(tr("Game Info"))
+            .open(&mut open)
+            .resizable(false)
+            .show(ctx, |ui| {
+                let info = &mut self.info;
+                egui::Grid::new("game_info").num_columns(2).show(ui, |ui| {
+                    for (label, value) in [
+                        ("Black", &mut info.black_name),
+                        ("Black rank", &mut info.black_rank),
+                        ("White", &mut info.white_name),
+                        ("White rank", &mut info.white_rank),
+                        ("Event", &mut info.event),
+                        ("Date", &mut info.date),
+                        ("Place", &mut info.place),
+                    ] {
+                        ui.label(tr(label));
+                        ui.text_edit_singleline(value);
+                        ui.end_row();
+                    }
+                    ui.label(tr("Komi"));
+                    ui.add(egui::DragValue::new(&mut info.komi).speed(0.5));
+                    ui.end_row();
+                    ui.label(tr("Handicap"));
+                    ui.add(egui::DragValue::new(&mut info.handicap).range(0..=9));
+                    ui.end_row();
+                    ui.label(tr("Main time (s)"));
+                    ui.add(egui::DragValue::new(&mut info.time_limit).range(0.0..=f32::MAX));
+                    ui.end_row();
+                    ui.label(tr("Overtime"));
+                    overtime_editor(ui, &mut info.overtime);
+                    ui.end_row();
+                    ui.label(tr("Result"));
+                    ui.text_edit_singleline(&mut info.result);
+                    ui.end_row();
+                    ui.label(tr("Copyright"));
+                    ui.text_edit_singleline(&mut info.copyright);
+                    ui.end_row();
+                });
+            });
+        self.layout.show_info = open;
+    }
+
+    fn show_pattern_result(&mut self, ctx: &egui::Context) {
+        let Some(result) = &self.pattern_result else {
+            return;
+        };
+        let mut open = true;
+        let mut open_path = None;
+        show_analysis_window(
+            ctx,
+            &tr("Pattern Search"),
+            &mut self.analysis_detached,
+            &mut open,
+            |ui| {
+                ui.label(trf(
+                    "Found in {} of {} games",
+                    &[&result.matches.len(), &result.games_searched],
+                ));
+                ui.separator();
+                egui::Grid::new("continuations")
+                    .striped(true)
+                    .num_columns(3)
+                    .show(ui, |ui| {
+                        ui.strong(tr("Next move"));
+                        ui.strong(tr("Games"));
+                        ui.strong(tr("Win %"));
+                        ui.end_row();
+                        for continuation in &result.continuations {
+                            let label = continuation.point.map_or(tr("Elsewhere"), |point| {
+                                self.new_game
+                                    .coordinates
+                                    .format_point(point, self.board_size)
+                            });
+                            ui.label(label);
+                            ui.label(continuation.count.to_string());
+                            ui.label(format!(
+                                "{:.0}",
+                                100.0 * continuation.wins as f32 / continuation.count as f32
+                            ));
+                            ui.end_row();
+                        }
+                    });
+                ui.separator();
+                egui::ScrollArea::vertical().show(ui, |ui| {
+                    for game in &result.matches {
+                        ui.horizontal(|ui| {
+                            ui.label(trf(
+                                "{} vs {}, move {}",
+                                &[&game.black, &game.white, &game.move_number],
+                            ));
+                            if ui.button(tr("Open")).clicked() {
+                                open_path = Some(game.path.clone());
+                            }
+                        });
+                    }
+                });
+            },
+        );
+        if !open {
+            self.pattern_result = None;
+        }
+        if let Some(path) = open_path
+            && let Err(err) = self.open_file(path)
+        {
+            self.file_status = Some(err);
+        }
+    }
+
+    fn show_yose(&mut self, ctx: &egui::Context) {
+        // The values are for the position they were counted on
+        if self
+            .yose
+            .as_ref()
+            .is_some_and(|(checksum, _)| *checksum != self.board.checksum())
+        {
+            self.yose = None;
+        }
+        let Some((_, plays)) = &self.yose else {
+            return;
+        };
+        let mut open = true;
+        show_analysis_window(
+            ctx,
+            &tr("Endgame Values"),
+            &mut self.analysis_detached,
+            &mut open,
+            |ui| {
+                if plays.is_empty() {
+                    ui.label(tr("No endgame plays left in the region"));
+                    return;
+                }
+                ui.label(trf(
+                    "Counted under {} rules, biggest first",
+                    &[&tr(self.info.rules.name())],
+                ));
+                ui.separator();
+                egui::Grid::new("yose")
+                    .striped(true)
+                    .num_columns(3)
+                    .show(ui, |ui| {
+                        ui.strong(tr("Move"));
+                        ui.strong(tr("Value"));
+                        ui.strong(tr("Status"));
+                        ui.end_row();
+                        for play in plays {
+                            ui.label(
+                                self.new_game
+                                    .coordinates
+                                    .format_point(play.point, self.board_size),
+                            );
+                            ui.label(i18n::number(play.value));
+                            ui.label(tr(play.status.name()));
+                            ui.end_row();
+                        }
+                    });
+            },
+        );
+        if !open {
+            self.yose = None;
+        }
+    }
+
+    // Prisoners taken by Black and by White after every move, starting from the setup position
+    fn prisoner_history(&self) -> Vec<(u32, u32)> {
+        let mut board = self.setup_board();
+        let (mut black, mut white) = (self.setup_captures.1, self.setup_captures.0);
+        let mut history = vec![(black, white)];
+        for &(player, point) in &self.moves {
+            if let Some((row, col)) = point {
+                let captured = board.play(row, col, player);
+                match player {
+                    Player::Black => black += captured,
+                    Player::White => white += captured,
+                }
+            }
+            history.push((black, white));
+        }
+        history
+    }
+
+    fn show_prisoners(&mut self, ctx: &egui::Context) {
+        let mut panel = egui::SidePanel::left("prisoners");
+        if let Some(width) = self.layout.prisoners_width {
+            panel = panel.default_width(width);
+        }
+        let response = panel.show(ctx, |ui| {
+            ui.heading(tr("Prisoners"));
+            // Each bowl holds the opponent stones that side has captured
+            for (label, player, count, stone_color) in [
+                (
+                    "Black",
+                    Player::Black,
+                    self.captured_white,
+                    egui::Color32::WHITE,
+                ),
+                (
+                    "White",
+                    Player::White,
+                    self.captured_black,
+                    egui::Color32::BLACK,
+                ),
+            ] {
+                let name = match player {
+                    Player::Black => (&self.info.black_name, &self.info.black_rank),
+                    Player::White => (&self.info.white_name, &self.info.white_rank),
+                };
+                let shown = match self.profiles.find(name.0) {
+                    Some(profile) => profile.label(),
+                    None => format!("{} {}", name.0, name.1).trim().to_string(),
+                };
+                if shown.is_empty() {
+                    ui.label(trf("{}: {}", &[&tr(label), &count]));
+                } else {
+                    ui.label(trf("{} ({}): {}", &[&shown, &tr(label), &count]));
+                }
+                // The running clock is shown strong, and red in its last seconds, when it also
+                // flashes every second
+                if let Some(clock) = self.clock(player) {
+                    let mut text = egui::RichText::new(clock.label()).monospace();
+                    let running =
+                        player == self.current_player && !self.game_over && self.paused.is_none();
+                    if running {
+                        text = text.strong();
+                    }
+                    let left = clock.left();
+                    if left < consts::LOW_TIME && !(running && left > 0.0 && left.fract() < 0.5) {
+                        text = text.color(egui::Color32::RED);
+                    }
+                    ui.label(text.size(20.0));
+                }
+                let (response, painter) =
+                    ui.allocate_painter(egui::Vec2::splat(consts::BOWL_SIZE), egui::Sense::hover());
+                let center = response.rect.center();
+                painter.circle_filled(
+                    center,
+                    consts::BOWL_SIZE * 0.5,
+                    egui::Color32::from_rgb(150, 100, 50),
+                );
+                let radius = 4.0;
+                let shown = count.min(consts::BOWL_CAPACITY);
+                for index in 0..shown {
+                    // Spiral the stones outward from the middle of the bowl
+                    let angle = index as f32 * 2.4;
+                    let distance = 5.0 * (index as f32).sqrt();
+                    let pos = center + egui::Vec2::angled(angle) * distance;
+                    painter.circle_filled(pos, radius, stone_color);
+                    painter.circle_stroke(
+                        pos,
+                        radius,
+                        egui::Stroke::new(0.5, egui::Color32::DARK_GRAY),
+                    );
+                }
+            }
+
+            ui.separator();
+            ui.label(tr("Over the game"));
+            let history = self.prisoner_history();
+            let size = egui::Vec2::new(consts::BOWL_SIZE, consts::BOWL_SIZE);
+            let (response, painter) = ui.allocate_painter(size, egui::Sense::hover());
+            let rect = response.rect;
+            painter.rect_filled(rect, 0.0, egui::Color32::from_gray(30));
+            let most = history
+                .iter()
+                .map(|&(black, white)| black.max(white))
+                .max()
+                .unwrap_or(0)
+                .max(1) as f32;
+            let last = (history.len() - 1).max(1) as f32;
+            for (side, color) in [(0, egui::Color32::from_gray(20)), (1, egui::Color32::WHITE)] {
+                let points: Vec<egui::Pos2> = history
+                    .iter()
+                    .enumerate()
+                    .map(|(index, &(black, white))| {
+                        let taken = if side == 0 { black } else { white };
+                        egui::Pos2::new(
+                            rect.left() + rect.width() * index as f32 / last,
+                            rect.bottom() - rect.height() * taken as f32 / most,
+                        )
+                    })
+                    .collect();
+                // Black's line gets a light outline so it shows on the dark background
+                if side == 0 {
+                    painter.add(egui::Shape::line(
+                        points.clone(),
+                        egui::Stroke::new(4.0, egui::Color32::GRAY),
+                    ));
+                }
+                painter.add(egui::Shape::line(points, egui::Stroke::new(2.0, color)));
+            }
+        });
+        self.layout.prisoners_width = Some(response.response.rect.width());
+    }
+
+    fn show_score_graph(&mut self, ctx: &egui::Context) {
+        if !self.show_graph {
+            return;
+        }
+        let mut open = true;
+        let mut jump = None;
+        let mut find_mistakes = false;
+        let mut export_report = false;
+        let current = self.moves.len();
+        let pending = self.analysis_pending.is_some();
+        show_analysis_window(
+            ctx,
+            &tr("Score Graph"),
+            &mut self.analysis_detached,
+            &mut open,
+            |ui| {
+                let Some(leads) = &self.analysis else {
+                    ui.label(tr(if pending {
+                        "Analyzing..."
+                    } else {
+                        "No analysis yet"
+                    }));
+                    return;
+                };
+                ui.label(tr("Estimated lead for Black by move, click to jump"));
+                let size = egui::Vec2::new(ui.available_width().max(200.0), consts::GRAPH_HEIGHT);
+                let (response, painter) = ui.allocate_painter(size, egui::Sense::click());
+                let rect = response.rect;
+                painter.rect_filled(rect, 0.0, egui::Color32::from_gray(30));
+                let scale = leads.iter().fold(10.0_f32, |max, lead| max.max(lead.abs()));
+                let last = (leads.len() - 1).max(1) as f32;
+                let point = |index: usize, lead: f32| {
+                    egui::Pos2::new(
+                        rect.left() + rect.width() * index as f32 / last,
+                        rect.center().y - rect.height() * 0.5 * lead / scale,
+                    )
+                };
+                painter.line_segment(
+                    [
+                        egui::Pos2::new(rect.left(), rect.center().y),
+                        egui::Pos2::new(rect.right(), rect.center().y),
+                    ],
+                    egui::Stroke::new(1.0, egui::Color32::GRAY),
+                );
+                let points: Vec<egui::Pos2> = leads
+                    .iter()
+                    .enumerate()
+                    .map(|(index, &lead)| point(index, lead))
+                    .collect();
+                painter.add(egui::Shape::line(
+                    points,
+                    egui::Stroke::new(2.0, egui::Color32::from_rgb(30, 120, 220)),
+                ));
+                let x = rect.left() + rect.width() * current as f32 / last;
+                painter.line_segment(
+                    [
+                        egui::Pos2::new(x, rect.top()),
+                        egui::Pos2::new(x, rect.bottom()),
+                    ],
+                    egui::Stroke::new(1.0, egui::Color32::RED),
+                );
+                if let Some(&lead) = leads.get(current) {
+                    ui.label(trf(
+                        "Move {}: {}",
+                        &[&current, &estimate::format_lead(lead)],
+                    ));
+                }
+                if consts::DESKTOP {
+                    ui.separator();
+                    ui.horizontal(|ui| {
+                        ui.label(tr("Mistakes by"));
+                        ui.selectable_value(&mut self.mistake_side, Player::Black, tr("Black"));
+                        ui.selectable_value(&mut self.mistake_side, Player::White, tr("White"));
+                        if ui
+                            .add_enabled(
+                                self.mistakes_pending.is_none(),
+                                egui::Button::new(tr("Make Drills")),
+                            )
+                            .on_hover_text(tr(
+                                "Save the positions before the moves that lost points as problems",
+                            ))
+                            .clicked()
+                        {
+                            find_mistakes = true;
+                        }
+                    });
+                    ui.horizontal(|ui| {
+                        ui.label(tr("Report"));
+                        for format in report::Format::ALL {
+                            ui.selectable_value(&mut self.report_format, format, tr(format.name()));
+                        }
+                        if ui
+                            .add_enabled(
+                                self.report_pending.is_none(),
+                                egui::Button::new(tr("Export Report")),
+                            )
+                            .on_hover_text(tr("Save the graph, the biggest mistakes of both sides drawn with better moves, and a summary, in one file to share"))
+                            .clicked()
+                        {
+                            export_report = true;
+                        }
+                    });
+                    if let Some(status) = &self.mistakes_status {
+                        ui.label(status);
+                    }
+                }
+                if response.clicked()
+                    && let Some(pos) = response.interact_pointer_pos()
+                {
+                    let fraction = ((pos.x - rect.left()) / rect.width()).clamp(0.0, 1.0);
+                    jump = Some((fraction * last).round() as usize);
+                }
+            },
+        );
+        self.show_graph = open;
+        if let Some(move_number) = jump {
+            self.go_to_move(move_number);
+        }
+        if find_mistakes {
+            self.find_mistakes(ctx);
+        }
+        if export_report {
+            self.export_report(ctx);
+        }
+    }
+
+    // The log of this game, for telling what went wrong with an engine or a network game
+    fn show_game_log(&mut self, ctx: &egui::Context) {
+        if !self.show_log {
+            return;
+        }
+        let mut open = true;
+        egui::Window::new(tr("Game Log"))
+            .open(&mut open)
+            .default_width(420.0)
+            .show(ctx, |ui| {
+                ui.horizontal_wrapped(|ui| {
+                    for kind in Kind::ALL {
+                        let mut shown = !self.log_hidden.contains(&kind);
+                        if ui.checkbox(&mut shown, tr(kind.name())).changed() {
+                            self.log_hidden.retain(|&hidden| hidden != kind);
+                            if !shown {
+                                self.log_hidden.push(kind);
+                            }
+                        }
+                    }
+                });
+                ui.horizontal(|ui| {
+                    if ui
+                        .button(tr("Copy"))
+                        .on_hover_text(tr("Copy the log as JSON, one entry to a line"))
+                        .clicked()
+                    {
+                        ctx.copy_text(self.log.to_json_lines());
+                    }
+                    #[cfg(not(target_arch = "wasm32"))]
+                    ui.label(
+                        egui::RichText::new(trf(
+                            "All games are also logged to {}",
+                            &[&paths::log_path().display()],
+                        ))
+                        .weak(),
+                    );
+                });
+                ui.separator();
+                egui::ScrollArea::vertical()
+                    .max_height(360.0)
+                    .stick_to_bottom(true)
+                    .show(ui, |ui| {
+                        egui::Grid::new("game_log")
+                            .striped(true)
+                            .num_columns(3)
+                            .show(ui, |ui| {
+                                for entry in &self.log.entries {
+                                    if self.log_hidden.contains(&entry.kind) {
+                                        continue;
+                                    }
+                                    ui.label(clock::format(entry.seconds));
+                                    ui.label(tr(entry.kind.name()));
+                                    ui.label(&entry.text);
+                                    ui.end_row();
+                                }
+                            });
+                    });
+            });
+        self.show_log = open;
+    }
+
+    // Starts or stops showing this game on a web page for the audience
+    #[cfg(not(target_arch = "wasm32"))]
+    fn toggle_broadcast(&mut self) {
+        if self.broadcast.take().is_some() {
+            self.file_status = Some(tr("Broadcast stopped"));
+            return;
+        }
+        let port = self.new_game.broadcast_port;
+        let status = match broadcast::Server::start(port) {
+            Ok(server) => {
+                let status = trf("Broadcasting at {}", &[&server.address]);
+                self.broadcast = Some(server);
+                status
+            }
+            Err(err) => trf("Could not start the broadcast: {}", &[&err]),
+        };
+        self.log.record(Kind::Network, status.clone());
+        self.file_status = Some(status);
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    fn drive_broadcast(&mut self) {
+        if self.broadcast.is_none() {
+            return;
+        }
+        let state = self.api_state();
+        if let Some(broadcast) = &mut self.broadcast {
+            broadcast.publish(&state);
+        }
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    fn script_game(&self) -> script::Game {
+        script::Game::new(
+            self.board.clone(),
+            self.current_player,
+            self.moves.clone(),
+            (self.captured_black, self.captured_white),
+            self.info.komi,
+            self.info.rules,
+        )
+    }
+
+    // Adds what the scripts with hooks on say about the move at `node` to its comment
+    #[cfg(not(target_arch = "wasm32"))]
+    fn run_move_hooks(&mut self, node: usize) {
+        // Hidden stones stay hidden from scripts too
+        if self.script_hooks.is_empty() || self.hidden.is_some() {
+            return;
+        }
+        let Some(scripts) = &self.scripts else {
+            return;
+        };
+        let game = self.script_game();
+        for script in scripts
+            .iter()
+            .filter(|script| self.script_hooks.contains(&script.name))
+        {
+            match script.on_move(&game) {
+                Ok(Some(comment)) => self.tree.add_comment(node, &comment),
+                Ok(None) => {}
+                Err(err) => {
+                    self.log.record(Kind::Game, err.clone());
+                    self.file_status = Some(err);
+                }
+            }
+        }
+    }
+
+    // Shows what the scripts with hooks on say once the game has a result
+    #[cfg(not(target_arch = "wasm32"))]
+    fn run_end_hooks(&mut self) {
+        if self.script_ended || !self.game_over || self.info.result.is_empty() {
+            return;
+        }
+        self.script_ended = true;
+        let Some(scripts) = &self.scripts else {
+            return;
+        };
+        let game = self.script_game();
+        let mut said = Vec::new();
+        for script in scripts
+            .iter()
+            .filter(|script| self.script_hooks.contains(&script.name))
+        {
+            match script.on_game_end(&game, &self.info.result) {
+                Ok(text) => said.extend(text),
+                Err(err) => said.push(err),
+            }
+            said.extend(script.take_printed());
+        }
+        if !said.is_empty() {
+            for text in &said {
+                self.log.record(Kind::Game, text.clone());
+            }
+            self.script_output = said.join("\n");
+            self.show_scripts = true;
+        }
+    }
+
+    /// Replays the line shown through `script`'s on_move, adding what it returns to the comment
+    /// of each move. Returns the number of moves commented on.
+    #[cfg(not(target_arch = "wasm32"))]
+    fn annotate_with(&mut self, script: &script::Script) -> Result<usize, String> {
+        let line = self.tree.line();
+        let mut game = script::Game::new(
+            self.setup_board(),
+            self.start_player,
+            Vec::new(),
+            self.setup_captures,
+            self.info.komi,
+            self.info.rules,
+        );
+        let mut commented = 0;
+        for &node in &line[1..] {
+            let Some(played) = self.tree.node(node).played else {
+                continue;
+            };
+            game = game.after(played)?;
+            if let Some(comment) = script.on_move(&game)? {
+                self.tree.add_comment(node, &comment);
+                commented += 1;
+            }
+        }
+        Ok(commented)
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    fn load_scripts(&mut self) {
+        let (scripts, errors): (Vec<_>, Vec<_>) =
+            script::load_all().into_iter().partition(Result::is_ok);
+        let scripts: Vec<script::Script> = scripts.into_iter().flatten().collect();
+        self.script_hooks
+            .retain(|name| scripts.iter().any(|script| &script.name == name));
+        self.script_errors = errors.into_iter().filter_map(Result::err).collect();
+        self.scripts = Some(scripts);
+    }
+
+    // The scripts in the scripts folder, each with its hooks switch and the functions it offers
+    #[cfg(not(target_arch = "wasm32"))]
+    fn show_scripts_window(&mut self, ctx: &egui::Context) {
+        if !self.show_scripts {
+            return;
+        }
+        if self.scripts.is_none() {
+            self.load_scripts();
+        }
+        let mut open = true;
+        let mut reload = false;
+        let mut run = None;
+        let mut annotate = None;
+        egui::Window::new(tr("Scripts"))
+            .open(&mut open)
+            .default_width(380.0)
+            .show(ctx, |ui| {
+                let scripts = self.scripts.as_deref().unwrap_or_default();
+                if scripts.is_empty() && self.script_errors.is_empty() {
+                    ui.label(tr(
+                        "No scripts yet. Put Rhai scripts ending in .rhai in the scripts folder.",
+                    ));
+                }
+                egui::Grid::new("scripts").num_columns(4).show(ui, |ui| {
+                    for (index, script) in scripts.iter().enumerate() {
+                        ui.label(&script.name);
+                        let mut hooked = self.script_hooks.contains(&script.name);
+                        let hooks =
+                            script.defines("on_move", 2) || script.defines("on_game_end", 2);
+                        if ui
+                            .add_enabled(hooks, egui::Checkbox::new(&mut hooked, tr("Hooks")))
+                            .on_hover_text(tr(
+                                "Run on_move after each move and on_game_end once the game is over",
+                            ))
+                            .changed()
+                        {
+                            self.script_hooks.retain(|name| name != &script.name);
+                            if hooked {
+                                self.script_hooks.push(script.name.clone());
+                            }
+                        }
+                        if ui
+                            .add_enabled(script.defines("run", 1), egui::Button::new(tr("Run")))
+                            .on_hover_text(tr("Call run with the position on the board"))
+                            .clicked()
+                        {
+                            run = Some(index);
+                        }
+                        if ui
+                            .add_enabled(
+                                script.defines("on_move", 2),
+                                egui::Button::new(tr("Annotate")),
+                            )
+                            .on_hover_text(tr(
+                                "Add what on_move says about each move to its comment",
+                            ))
+                            .clicked()
+                        {
+                            annotate = Some(index);
+                        }
+                        ui.end_row();
+                    }
+                });
+                for err in &self.script_errors {
+                    ui.colored_label(ui.visuals().error_fg_color, err);
+                }
+                ui.horizontal(|ui| {
+                    reload = ui.button(tr("Reload")).clicked();
+                    ui.label(
+                        egui::RichText::new(trf(
+                            "Scripts are read from {}",
+                            &[&paths::scripts_dir().display()],
+                        ))
+                        .weak(),
+                    );
+                });
+                if !self.script_output.is_empty() {
+                    ui.separator();
+                    egui::ScrollArea::vertical()
+                        .max_height(240.0)
+                        .show(ui, |ui| {
+                            ui.add(
+                                egui::TextEdit::multiline(&mut self.script_output.as_str())
+                                    .desired_width(f32::INFINITY),
+                            );
+                        });
+                }
+            });
+        self.show_scripts = open;
+        if reload {
+            self.load_scripts();
+        }
+        let Some(scripts) = self.scripts.take() else {
+            return;
+        };
+        if let Some(index) = run {
+            let script = &scripts[index];
+            self.script_output = match script.run(&self.script_game()) {
+                Ok(output) if output.is_empty() => trf("{} finished", &[&script.name]),
+                Ok(output) => output,
+                Err(err) => err,
+            };
+        }
+        if let Some(index) = annotate {
+            let script = &scripts[index];
+            self.script_output = match self.annotate_with(script) {
+                Ok(count) => trf("{} commented on {} moves", &[&script.name, &count]),
+                Err(err) => err,
+            };
+            self.script_output.extend(
+                script
+                    .take_printed()
+                    .iter()
+                    .map(|line| format!("\n{}", line)),
+            );
+        }
+        self.scripts = Some(scripts);
+    }
+
+    // Starts the game of a tournament board, with the players, handicap and komi of its pairing
+    #[cfg(not(target_arch = "wasm32"))]
+    fn start_tournament_game(&mut self, event: &tournament::Tournament, link: tournament::Link) {
+        let pairing = &event.played[link.round].pairings[link.board];
+        let (black, white) = (&event.players[pairing.black], &event.players[pairing.white]);
+        self.board_size = event.board_size;
+        self.board = Board::new(event.board_size);
+        let settings = &mut self.new_game;
+        settings.opponent = Opponent::Human;
+        settings.handicap = pairing.handicap;
+        settings.komi = pairing.komi;
+        settings.rules = event.rules;
+        settings.black_profile = black.name.trim().to_string();
+        settings.white_profile = white.name.trim().to_string();
+        // A tournament game is played on the plain board
+        settings.capture_goal = 0;
+        settings.blind = false;
+        settings.toroidal = false;
+        settings.hidden_stones = 0;
+        settings.rengo = false;
+        self.start_game();
+        self.info.black_name = black.name.trim().to_string();
+        self.info.black_rank = black.rank.trim().to_string();
+        self.info.white_name = white.name.trim().to_string();
+        self.info.white_rank = white.rank.trim().to_string();
+        self.info.event = trf("{}, round {}", &[&event.name, &(link.round + 1)]);
+        self.log.record(
+            Kind::Game,
+            format!(
+                "Board {} of round {} of {}: Black {} against White {}",
+                link.board + 1,
+                link.round + 1,
+                event.name,
+                self.info.black_label(),
+                self.info.white_label()
+            ),
+        );
+        self.mark_saved();
+        self.tournament_game = Some(link);
+    }
+
+    // Once a tournament game is over, saves its record and gives its round the result. Returns
+    // the tournament file written, for the director windows to read again.
+    #[cfg(not(target_arch = "wasm32"))]
+    fn keep_tournament_result(&mut self) -> Option<PathBuf> {
+        let outcome = tournament::Outcome::from_result(&self.info.result);
+        if !self.game_over || outcome == tournament::Outcome::Pending {
+            return None;
+        }
+        let link = self.tournament_game.take()?;
+        self.save_game();
+        let saved = self.file_status.take();
+        let recorded = tournament::Tournament::load(&link.path).and_then(|mut event| {
+            event.record(&link, outcome, self.file_path.clone())?;
+            event.save(&link.path).map_err(|err| err.to_string())
+        });
+        let kept = match recorded {
+            Ok(()) => trf(
+                "Result kept for board {} of round {}",
+                &[&(link.board + 1), &(link.round + 1)],
+            ),
+            Err(err) => trf("Could not keep the tournament result: {}", &[&err]),
+        };
+        self.file_status = Some(match saved {
+            Some(status) => format!("{} — {}", status, kept),
+            None => kept,
+        });
+        Some(link.path)
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    fn reload_tournament(&mut self, path: &std::path::Path) {
+        if let Some((open, event)) = &mut self.tournament
+            && open == path
+        {
+            match tournament::Tournament::load(path) {
+                Ok(loaded) => *event = loaded,
+                Err(err) => {
+                    self.tournament_status = Some(trf("Could not read the tournament: {}", &[&err]))
+                }
+            }
+        }
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    fn open_tournament(&mut self, path: PathBuf) {
+        match tournament::Tournament::load(&path) {
+            Ok(event) => {
+                self.tournament_round = event.played.len().saturating_sub(1);
+                self.tournament_status = None;
+                self.tournament = Some((path, event));
+            }
+            Err(err) => {
+                self.tournament_status = Some(trf("Could not read the tournament: {}", &[&err]))
+            }
+        }
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    fn create_tournament(&mut self) {
+        let name = std::mem::take(&mut self.tournament_name).trim().to_string();
+        let path = tournament::path_for(&name);
+        if path.exists() {
+            self.tournament_status = Some(trf(
+                "There already is a tournament at {}",
+                &[&path.display()],
+            ));
+            return;
+        }
+        let event = tournament::Tournament {
+            name,
+            board_size: self.board_size,
+            komi: self.new_game.komi,
+            rules: self.new_game.rules,
+            ..Default::default()
+        };
+        self.tournament_round = 0;
+        self.tournament_status = None;
+        self.tournament = Some((path, event));
+        self.save_tournament();
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    fn save_tournament(&mut self) {
+        if let Some((path, event)) = &self.tournament
+            && let Err(err) = event.save(path)
+        {
+            self.tournament_status = Some(trf("Could not save the tournament: {}", &[&err]));
+        }
+    }
+
+    // Writes the standings, or the pairings of a round, next to the tournament file
+    #[cfg(not(target_arch = "wasm32"))]
+    fn export_tournament(&mut self, round: Option<usize>) {
+        let Some((path, event)) = &self.tournament else {
+            return;
+        };
+        let format = self.report_format;
+        let stem = path
+            .file_stem()
+            .map(|stem| stem.to_string_lossy().into_owned())
+            .unwrap_or_default();
+        let (name, text) = match round {
+            Some(round) => (
+                format!("{}-round-{}.{}", stem, round + 1, format.extension()),
+                event.pairing_list(round, format),
+            ),
+            None => (
+                format!("{}-standings.{}", stem, format.extension()),
+                event.wall_list(format),
+            ),
+        };
+        let out = path.with_file_name(name);
+        self.tournament_status = Some(match std::fs::write(&out, text) {
+            Ok(()) => trf("Written to {}", &[&out.display()]),
+            Err(err) => trf("Could not write {}: {}", &[&out.display(), &err]),
+        });
+    }
+
+    // Writes a sheet to print as a PDF next to the tournament file
+    #[cfg(not(target_arch = "wasm32"))]
+    fn print_tournament(&mut self, sheet: tournament::Sheet) {
+        let Some((path, event)) = &self.tournament else {
+            return;
+        };
+        let stem = path
+            .file_stem()
+            .map(|stem| stem.to_string_lossy().into_owned())
+            .unwrap_or_default();
+        let name = match sheet {
+            tournament::Sheet::WallList => format!("{}-standings.pdf", stem),
+            tournament::Sheet::Pairings(round) => format!("{}-round-{}.pdf", stem, round + 1),
+            tournament::Sheet::ResultSlips(round) => {
+                format!("{}-round-{}-slips.pdf", stem, round + 1)
+            }
+        };
+        let out = path.with_file_name(name);
+        self.tournament_status = Some(match std::fs::write(&out, event.print(sheet)) {
+            Ok(()) => trf("Written to {}", &[&out.display()]),
+            Err(err) => trf("Could not write {}: {}", &[&out.display(), &err]),
+        });
+    }
+
+    // The players, rounds and standings of a tournament at a real event, saved on every change
+    #[cfg(not(target_arch = "wasm32"))]
+    fn show_tournament_window(&mut self, ctx: &egui::Context) {
+        if !self.show_tournament {
+            return;
+        }
+        let mut open = true;
+        let mut opened = None;
+        let mut create = false;
+        let mut close = false;
+        let mut changed = false;
+        let mut add = false;
+        let mut remove = None;
+        let mut pair = false;
+        let mut unpair = false;
+        let mut play = None;
+        let mut export = None;
+        let mut print = None;
+        egui::Window::new(tr("Tournament Director"))
+            .open(&mut open)
+            .default_width(560.0)
+            .show(ctx, |ui| {
+                let Some((_, event)) = &mut self.tournament else {
+                    let files = tournament::list();
+                    if files.is_empty() {
+                        ui.label(tr("No tournaments yet."));
+                    }
+                    for file in files {
+                        let name = file
+                            .file_stem()
+                            .map(|stem| stem.to_string_lossy().into_owned())
+                            .unwrap_or_default();
+                        if ui.button(name).clicked() {
+                            opened = Some(file);
+                        }
+                    }
+                    ui.separator();
+                    ui.horizontal(|ui| {
+                        ui.label(tr("New tournament"));
+                        ui.text_edit_singleline(&mut self.tournament_name);
+                        create = ui
+                            .add_enabled(
+                                !self.tournament_name.trim().is_empty(),
+                                egui::Button::new(tr("Create")),
+                            )
+                            .clicked();
+                    });
+                    if let Some(status) = &self.tournament_status {
+                        ui.label(status);
+                    }
+                    return;
+                };
+                ui.horizontal(|ui| {
+                    ui.heading(&event.name);
+                    close = ui
+                        .button(tr("Close"))
+                        .on_hover_text(tr("Open another tournament"))
+                        .clicked();
+                });
+                egui::CollapsingHeader::new(tr("Settings")).show(ui, |ui| {
+                    egui::Grid::new("tournament_settings")
+                        .num_columns(2)
+                        .show(ui, |ui| {
+                            ui.label(tr("Pairing"));
+                            egui::ComboBox::from_id_salt("tournament_system")
+                                .selected_text(tr(event.system.name()))
+                                .show_ui(ui, |ui| {
+                                    for system in tournament::System::ALL {
+                                        changed |= ui
+                                            .selectable_value(
+                                                &mut event.system,
+                                                system,
+                                                tr(system.name()),
+                                            )
+                                            .changed();
+                                    }
+                                });
+                            ui.end_row();
+
+                            ui.label(tr("Rounds"));
+                            let paired = event.played.len().max(1) as u32;
+                            changed |= ui
+                                .add(egui::DragValue::new(&mut event.rounds).range(paired..=99))
+                                .changed();
+                            ui.end_row();
+
+                            if event.system == tournament::System::McMahon {
+                                ui.label(tr("McMahon bar"));
+                                changed |= ui
+                                    .text_edit_singleline(&mut event.bar)
+                                    .on_hover_text(tr("Players of this rank and stronger all start on the top score"))
+                                    .changed();
+                                ui.end_row();
+                            }
+
+                            ui.label(tr("Handicap"));
+                            changed |= ui
+                                .checkbox(&mut event.handicap, tr("A stone for each rank between the players"))
+                                .changed();
+                            ui.end_row();
+
+                            ui.label(tr("Board Size"));
+                            egui::ComboBox::from_id_salt("tournament_size")
+                                .selected_text(format!("{} x {}", event.board_size, event.board_size))
+                                .show_ui(ui, |ui| {
+                                    for &size in consts::VALID_BOARD_SIZES {
+                                        changed |= ui
+                                            .selectable_value(
+                                                &mut event.board_size,
+                                                size,
+                                                format!("{} x {}", size, size),
+                                            )
+                                            .changed();
+                                    }
+                                });
+                            ui.end_row();
+
+                            ui.label(tr("Komi"));
+                            changed |= ui
+                                .add(egui::DragValue::new(&mut event.komi).speed(0.5))
+                                .changed();
+                            ui.end_row();
+
+                            ui.label(tr("Rules"));
+                            egui::ComboBox::from_id_salt("tournament_rules")
+                                .selected_text(tr(event.rules.name()))
+                                .show_ui(ui, |ui| {
+                                    for rules in Rules::ALL {
+                                        changed |= ui
+                                            .selectable_value(&mut event.rules, rules, tr(rules.name()))
+                                            .changed();
+                                    }
+                                });
+                            ui.end_row();
+                        });
+                });
+                egui::CollapsingHeader::new(trf("Players ({})", &[&event.players.len()]))
+                    .id_salt("tournament_players")
+                    .default_open(event.played.is_empty())
+                    .show(ui, |ui| {
+                        egui::Grid::new("tournament_entrants")
+                            .num_columns(4)
+                            .show(ui, |ui| {
+                                ui.strong(tr("Name"));
+                                ui.strong(tr("Rank"));
+                                ui.strong(tr("Club"));
+                                ui.end_row();
+                                for (index, entrant) in event.players.iter_mut().enumerate() {
+                                    for (text, width) in [
+                                        (&mut entrant.name, 140.0),
+                                        (&mut entrant.rank, 50.0),
+                                        (&mut entrant.club, 100.0),
+                                    ] {
+                                        changed |= ui
+                                            .add(egui::TextEdit::singleline(text).desired_width(width))
+                                            .changed();
+                                    }
+                                    ui.horizontal(|ui| {
+                                        changed |= ui
+                                            .checkbox(&mut entrant.withdrawn, tr("Withdrawn"))
+                                            .on_hover_text(tr("Leave the player out of the rounds paired from now on"))
+                                            .changed();
+                                        // Games refer to the players by their place in the list
+                                        if event.played.is_empty() && ui.small_button("x").clicked() {
+                                            remove = Some(index);
+                                        }
+                                    });
+                                    ui.end_row();
+                                }
+                                let entrant = &mut self.tournament_entrant;
+                                for (text, width) in [
+                                    (&mut entrant.name, 140.0),
+                                    (&mut entrant.rank, 50.0),
+                                    (&mut entrant.club, 100.0),
+                                ] {
+                                    ui.add(egui::TextEdit::singleline(text).desired_width(width));
+                                }
+                                add = ui
+                                    .add_enabled(
+                                        !entrant.name.trim().is_empty(),
+                                        egui::Button::new(tr("Add")),
+                                    )
+                                    .clicked();
+                                ui.end_row();
+                            });
+                    });
+                ui.separator();
+                ui.horizontal_wrapped(|ui| {
+                    for round in 0..event.played.len() {
+                        ui.selectable_value(
+                            &mut self.tournament_round,
+                            round,
+                            trf("Round {}", &[&(round + 1)]),
+                        );
+                    }
+                    let next = event.played.len() < event.rounds as usize;
+                    pair = ui
+                        .add_enabled(
+                            next && event.round_finished(),
+                            egui::Button::new(trf("Pair round {}", &[&(event.played.len() + 1)])),
+                        )
+                        .on_disabled_hover_text(if next {
+                            tr("Every game of the last round needs a result first")
+                        } else {
+                            tr("All the rounds planned are paired")
+                        })
+                        .clicked();
+                });
+                let round = self.tournament_round;
+                if let Some(played) = event.played.get(round) {
+                    let mut outcomes: Vec<tournament::Outcome> =
+                        played.pairings.iter().map(|pairing| pairing.outcome).collect();
+                    egui::Grid::new("tournament_pairings")
+                        .striped(true)
+                        .num_columns(6)
+                        .show(ui, |ui| {
+                            for (board, pairing) in played.pairings.iter().enumerate() {
+                                ui.label((board + 1).to_string());
+                                ui.label(trf("Black: {}", &[&event.label(pairing.black)]));
+                                ui.label(trf("White: {}", &[&event.label(pairing.white)]));
+                                ui.label(match pairing.handicap {
+                                    0 => trf("komi {}", &[&pairing.komi]),
+                                    stones => trf("{} stones", &[&stones]),
+                                });
+                                egui::ComboBox::from_id_salt(("tournament_outcome", board))
+                                    .selected_text(tr(outcomes[board].name()))
+                                    .show_ui(ui, |ui| {
+                                        for outcome in tournament::Outcome::ALL {
+                                            ui.selectable_value(
+                                                &mut outcomes[board],
+                                                outcome,
+                                                tr(outcome.name()),
+                                            );
+                                        }
+                                    });
+                                let button = ui.add_enabled(
+                                    pairing.outcome == tournament::Outcome::Pending,
+                                    egui::Button::new(tr("Play")),
+                                );
+                                let button = match &pairing.game {
+                                    Some(game) => button.on_disabled_hover_text(trf(
+                                        "Played here, saved to {}",
+                                        &[&game.display()],
+                                    )),
+                                    None => button,
+                                };
+                                if button
+                                    .on_hover_text(tr("Play the game in a new tab, which gives the round its result once over"))
+                                    .clicked()
+                                {
+                                    play = Some(board);
+                                }
+                                ui.end_row();
+                            }
+                            if let Some(player) = played.bye {
+                                ui.label("");
+                                ui.label(event.label(player));
+                                ui.label(tr("Bye"));
+                                ui.end_row();
+                            }
+                        });
+                    let finished = outcomes
+                        .iter()
+                        .any(|&outcome| outcome != tournament::Outcome::Pending);
+                    if round + 1 == event.played.len()
+                        && !finished
+                        && ui
+                            .button(tr("Undo the pairing"))
+                            .on_hover_text(tr("Take the round back to pair it again, such as after a late arrival"))
+                            .clicked()
+                    {
+                        unpair = true;
+                    }
+                    for (pairing, outcome) in event.played[round].pairings.iter_mut().zip(outcomes) {
+                        if pairing.outcome != outcome {
+                            pairing.outcome = outcome;
+                            changed = true;
+                        }
+                    }
+                }
+                egui::CollapsingHeader::new(tr("Standings")).show(ui, |ui| {
+                    egui::Grid::new("tournament_standings")
+                        .striped(true)
+                        .num_columns(5)
+                        .show(ui, |ui| {
+                            for heading in ["Place", "Name", "Score", "SOS", "SODOS"] {
+                                ui.strong(tr(heading));
+                            }
+                            ui.end_row();
+                            for standing in event.standings() {
+                                ui.label(standing.place.to_string());
+                                ui.label(event.label(standing.player));
+                                ui.label(standing.score.to_string());
+                                ui.label(standing.sos.to_string());
+                                ui.label(standing.sodos.to_string());
+                                ui.end_row();
+                            }
+                        });
+                });
+                ui.horizontal(|ui| {
+                    egui::ComboBox::from_id_salt("tournament_format")
+                        .selected_text(tr(self.report_format.name()))
+                        .show_ui(ui, |ui| {
+                            for format in report::Format::ALL {
+                                ui.selectable_value(&mut self.report_format, format, tr(format.name()));
+                            }
+                        });
+                    if ui
+                        .button(tr("Export Wall List"))
+                        .on_hover_text(tr("The standings with every player's games, to hang up"))
+                        .clicked()
+                    {
+                        export = Some(None);
+                    }
+                    if ui
+                        .add_enabled(round < event.played.len(), egui::Button::new(tr("Export Pairings")))
+                        .on_hover_text(tr("The boards of the round shown, to hang up before it starts"))
+                        .clicked()
+                    {
+                        export = Some(Some(round));
+                    }
+                });
+                ui.horizontal(|ui| {
+                    let paired = round < event.played.len();
+                    ui.label(tr("Print"));
+                    if ui
+                        .button(tr("Wall List"))
+                        .on_hover_text(tr("The standings as a PDF to print"))
+                        .clicked()
+                    {
+                        print = Some(tournament::Sheet::WallList);
+                    }
+                    if ui
+                        .add_enabled(paired, egui::Button::new(tr("Pairings")))
+                        .on_hover_text(tr("The boards of the round shown as a PDF to print"))
+                        .clicked()
+                    {
+                        print = Some(tournament::Sheet::Pairings(round));
+                    }
+                    if ui
+                        .add_enabled(paired, egui::Button::new(tr("Result Slips")))
+                        .on_hover_text(tr("A slip for each board of the round shown, for the players to tick the result on and sign"))
+                        .clicked()
+                    {
+                        print = Some(tournament::Sheet::ResultSlips(round));
+                    }
+                });
+                if let Some(status) = &self.tournament_status {
+                    ui.label(status);
+                }
+            });
+        self.show_tournament = open;
+        if let Some(path) = opened {
+            self.open_tournament(path);
+        }
+        if create {
+            self.create_tournament();
+        }
+        if close {
+            self.tournament = None;
+            self.tournament_status = None;
+            return;
+        }
+        let Some((path, event)) = &mut self.tournament else {
+            return;
+        };
+        if add {
+            event
+                .players
+                .push(std::mem::take(&mut self.tournament_entrant));
+            changed = true;
+        }
+        if let Some(index) = remove {
+            event.players.remove(index);
+            changed = true;
+        }
+        if pair {
+            match event.pair_round() {
+                Ok(()) => {
+                    self.tournament_round = event.played.len() - 1;
+                    self.tournament_status = None;
+                    changed = true;
+                }
+                Err(err) => self.tournament_status = Some(err),
+            }
+        }
+        if unpair {
+            event.played.pop();
+            self.tournament_round = event.played.len().saturating_sub(1);
+            changed = true;
+        }
+        if let Some(board) = play {
+            self.tournament_boards
+                .push(event.link(path, self.tournament_round, board));
+            self.tournament_status = Some(trf("Board {} opened in a new tab", &[&(board + 1)]));
+        }
+        if changed {
+            self.save_tournament();
+        }
+        if let Some(sheet) = print {
+            self.print_tournament(sheet);
+        }
+        if let Some(round) = export {
+            self.export_tournament(round);
+        }
+    }
+
+    // The link and QR code of the game, while the share window is open
+    fn show_share(&mut self, ctx: &egui::Context) {
+        if self.share.is_none() {
+            return;
+        }
+        let viewer = self.share_viewer();
+        let mut open = true;
+        let mut copied = false;
+        egui::Window::new(tr("Share Game"))
+            .open(&mut open)
+            .default_width(consts::SHARE_CODE_SIZE + 20.0)
+            .show(ctx, |ui| {
+                ui.horizontal(|ui| {
+                    ui.label(tr("Web viewer"));
+                    ui.add(
+                        egui::TextEdit::singleline(&mut self.new_game.share_viewer)
+                            .hint_text("https://…"),
+                    )
+                    .on_hover_text(tr("Where the web version of the app is served, such as a club site the links open on"));
+                });
+                let Some(shared) = &self.share else {
+                    return;
+                };
+                if viewer.is_empty() {
+                    ui.label(tr("Enter the address of the web version of the app to make a link."));
+                    return;
+                }
+                match &shared.code {
+                    Some(code) => share::paint(ui, code, consts::SHARE_CODE_SIZE),
+                    None => {
+                        ui.label(tr("The game is too long for a QR code, but the link still works."));
+                    }
+                }
+                let mut link = shared.link.as_str();
+                ui.add(
+                    egui::TextEdit::multiline(&mut link)
+                        .desired_rows(3)
+                        .desired_width(consts::SHARE_CODE_SIZE),
+                );
+                if ui.button(tr("Copy Link")).clicked() {
+                    ctx.copy_text(shared.link.clone());
+                    copied = true;
+                }
+            });
+        if copied {
+            self.file_status = Some(tr("Copied the link to the game"));
+        }
+        if !open {
+            self.share = None;
+        } else if self
+            .share
+            .as_ref()
+            .is_some_and(|shared| shared.viewer != viewer)
+        {
+            self.open_share();
+        }
+    }
+
+    // The steps of the count, played one after another or gone through by hand
+    fn show_counting(&mut self, ctx: &egui::Context) {
+        let Some(counting) = &mut self.counting else {
+            return;
+        };
+        if counting.playing {
+            if counting.last() {
+                counting.playing = false;
+            } else if counting.stepped.elapsed() >= consts::COUNTING_STEP {
+                counting.step += 1;
+                counting.stepped = web_time::Instant::now();
+            } else {
+                ctx.request_repaint_after(consts::COUNTING_STEP);
+            }
+        }
+        let mut open = true;
+        let (theme, high_contrast) = (self.new_game.board_theme, self.new_game.high_contrast);
+        egui::Window::new(tr("Counting"))
+            .open(&mut open)
+            .default_width(consts::COUNTING_BOARD_SIZE + 20.0)
+            .show(ctx, |ui| {
+                counting::paint(
+                    ui,
+                    counting.frame(),
+                    theme,
+                    high_contrast,
+                    consts::COUNTING_BOARD_SIZE,
+                );
+                ui.add(egui::Label::new(&counting.frame().caption).wrap());
+                ui.horizontal(|ui| {
+                    if ui
+                        .add_enabled(counting.step > 0, egui::Button::new(tr("Back")))
+                        .clicked()
+                    {
+                        counting.step -= 1;
+                        counting.playing = false;
+                    }
+                    let label = if counting.playing {
+                        tr("Pause")
+                    } else {
+                        tr("Play")
+                    };
+                    if ui.button(label).clicked() {
+                        if !counting.playing && counting.last() {
+                            counting.step = 0;
+                        }
+                        counting.playing = !counting.playing;
+                        counting.stepped = web_time::Instant::now();
+                    }
+                    if ui
+                        .add_enabled(!counting.last(), egui::Button::new(tr("Next")))
+                        .clicked()
+                    {
+                        counting.step += 1;
+                        counting.playing = false;
+                    }
+                    ui.label(trf(
+                        "Step {} of {}",
+                        &[&(counting.step + 1), &counting.frames.len()],
+                    ));
+                });
+            });
+        if !open {
+            self.counting = None;
+        }
+    }
+
+    // The marked position beside the one on the board, with the points where they differ framed
+    fn show_compare_window(&mut self, ctx: &egui::Context) {
+        if !self.show_compare {
+            return;
+        }
+        let mut open = true;
+        let mut mark = false;
+        let mut same_move = None;
+        let theme = self.new_game.board_theme;
+        let style = self.new_game.coordinates;
+        egui::Window::new(tr("Compare Positions"))
+            .open(&mut open)
+            .default_width(2.0 * consts::COMPARE_BOARD_SIZE + 40.0)
+            .show(ctx, |ui| {
+                ui.horizontal(|ui| {
+                    mark = ui
+                        .button(tr("Mark This Position"))
+                        .on_hover_text(tr("Keep the position on the board to compare others with, in this tab or another"))
+                        .clicked();
+                    if let Some(marked) = &self.compare_mark
+                        && ui
+                            .add_enabled(self.can_navigate(), egui::Button::new(tr("Same Move Here")))
+                            .on_hover_text(tr("Go to the move of this game with the number of the marked position"))
+                            .clicked()
+                    {
+                        same_move = Some(marked.moves);
+                    }
+                });
+                let Some(marked) = &self.compare_mark else {
+                    ui.label(tr("Mark a position, then go to another move of the game tree, or to another game's tab, to see how they differ."));
+                    return;
+                };
+                if marked.board.size() != self.board.size() {
+                    ui.label(tr("The positions are on boards of different sizes."));
+                    return;
+                }
+                let points = compare::differences(&marked.board, &self.board);
+                ui.horizontal_top(|ui| {
+                    ui.vertical(|ui| {
+                        ui.label(&marked.label);
+                        compare::paint(ui, &marked.board, &points, None, theme, consts::COMPARE_BOARD_SIZE);
+                    });
+                    ui.vertical(|ui| {
+                        ui.label(trf("Now: move {} of {}", &[&self.moves.len(), &tab_title(self)]));
+                        compare::paint(ui, &self.board, &points, None, theme, consts::COMPARE_BOARD_SIZE);
+                    });
+                });
+                if points.is_empty() {
+                    ui.label(tr("The stones are the same in both."));
+                    return;
+                }
+                ui.label(trf("{} points differ", &[&points.len()]));
+                egui::ScrollArea::vertical()
+                    .max_height(120.0)
+                    .show(ui, |ui| {
+                        for line in compare::describe(&marked.board, &self.board, &points, style) {
+                            ui.label(line);
+                        }
+                    });
+            });
+        self.show_compare = open;
+        if mark {
+            self.compare_mark = Some(compare::Marked {
+                label: trf("Move {} of {}", &[&self.moves.len(), &tab_title(self)]),
+                board: self.board.clone(),
+                moves: self.moves.len(),
+            });
+            self.compare_shared = true;
+        }
+        if let Some(count) = same_move {
+            self.go_to_move(count);
+        }
+    }
+
+    fn show_fuseki_explorer(&mut self, ctx: &egui::Context) {
+        if !self.show_fuseki {
+            return;
+        }
+        let outdated = self
+            .fuseki_stats
+            .as_ref()
+            .is_none_or(|(moves, _)| *moves != self.moves);
+        if outdated {
+            let database = self
+                .database
+                .get_or_insert_with(|| database::Database::load(&paths::database_path()));
+            let stats = fuseki::explore(&database.records, self.board_size, &self.moves);
+            self.fuseki_stats = Some((self.moves.clone(), stats));
+        }
+        let Some((_, stats)) = &self.fuseki_stats else {
+            return;
+        };
+
+        let mut open = true;
+        let mut play = None;
+        let mut rewind = None;
+        show_analysis_window(
+            ctx,
+            &tr("Fuseki Explorer"),
+            &mut self.analysis_detached,
+            &mut open,
+            |ui| {
+                ui.horizontal(|ui| {
+                    if ui.button(tr("Start")).clicked() {
+                        rewind = Some(0);
+                    }
+                    if ui.button(tr("Back")).clicked() {
+                        rewind = Some(self.moves.len().saturating_sub(1));
+                    }
+                    ui.label(trf("{} games reach this position", &[&stats.games]));
+                });
+                ui.separator();
+                egui::Grid::new("fuseki")
+                    .striped(true)
+                    .num_columns(3)
+                    .show(ui, |ui| {
+                        ui.strong(tr("Move"));
+                        ui.strong(tr("Games"));
+                        ui.strong(tr("Win %"));
+                        ui.end_row();
+                        for candidate in &stats.candidates {
+                            let label = candidate.point.map_or(tr("Pass"), |point| {
+                                self.new_game
+                                    .coordinates
+                                    .format_point(point, self.board_size)
+                            });
+                            if ui.button(label).clicked() {
+                                play = Some(candidate.point);
+                            }
+                            ui.label(candidate.games.to_string());
+                            ui.label(format!(
+                                "{:.0}",
+                                100.0 * candidate.wins as f32 / candidate.games as f32
+                            ));
+                            ui.end_row();
+                        }
+                    });
+            },
+        );
+        self.show_fuseki = open;
+        if let Some(move_number) = rewind {
+            self.go_to_move(move_number);
+        }
+        if let Some(point) = play {
+            self.play(point);
+        }
+    }
+
+    // The clock of `player` at the current node, counting the move being thought about
+    fn clock(&self, player: Player) -> Option<Clock> {
+        if !self.info.is_timed() {
+            return None;
+        }
+        let mut clock = Clock::new(self.info.time_limit, self.info.overtime);
+        for id in self.tree.path(self.tree.current()) {
+            let node = self.tree.node(id);
+            if let (Some((mover, _)), Some(seconds)) = (node.played, node.seconds)
+                && mover == player
+            {
+                clock.spend(seconds);
+                clock.end_move();
+            }
+        }
+        if self.current_player == player && !self.game_over {
+            clock.spend(self.thinking_seconds());
+        }
+        Some(clock)
+    }
+
+    // Starts timing the player to move from zero, keeping the clocks paused if they were
+    fn restart_thinking(&mut self) {
+        self.move_started = web_time::Instant::now();
+        if self.paused.is_some() {
+            self.paused = Some(0.0);
+        }
+    }
+
+    // How long the player to move has been thinking, not counting time with the clocks paused
+    fn thinking_seconds(&self) -> f32 {
+        self.paused
+            .unwrap_or_else(|| self.move_started.elapsed().as_secs_f32())
+    }
+
+    fn can_pause(&self) -> bool {
+        self.info.is_timed() && !self.game_over && self.info.result.is_empty() && self.lan.is_none()
+    }
+
+    // Freezes both clocks, or starts the player to move's again from where it stopped
+    fn toggle_pause(&mut self) {
+        match self.paused.take() {
+            Some(seconds) => {
+                let now = web_time::Instant::now();
+                self.move_started = now
+                    .checked_sub(std::time::Duration::from_secs_f32(seconds))
+                    .unwrap_or(now);
+                self.log.record(Kind::Clock, "Clocks resumed".to_string());
+            }
+            None => {
+                self.paused = Some(self.thinking_seconds());
+                self.log.record(Kind::Clock, "Clocks paused".to_string());
+            }
+        }
+    }
+
+    // Stops the game with its clocks for another day
+    fn adjourn(&mut self) {
+        if self.paused.is_none() {
+            self.toggle_pause();
+        }
+        self.save_game();
+    }
+
+    // Ends the game once the player to move has no time left, or passes for them when the time
+    // per move allows it, unless the position is only being reviewed, tried out or edited
+    fn check_flag(&mut self, ctx: &egui::Context) {
+        // The branches kept while teaching do not count as moves after the current one
+        let moves_after = self
+            .tree
+            .node(self.tree.current())
+            .children
+            .iter()
+            .any(|&child| self.tree.node(child).name.is_empty());
+        if self.game_over
+            || !self.info.result.is_empty()
+            // A network game stays at its last move, even with moves taken back after it
+            || (moves_after && self.lan.is_none())
+            || self.trial.is_some()
+            || self.guess_record.is_some()
+            || self.problem.is_some()
+            || self.edit_stone.is_some()
+            || self.presentation.is_some()
+            || self.paused.is_some()
+            || self.lan_waiting()
+        {
+            return;
+        }
+        let player = self.current_player;
+        let Some(clock) = self.clock(player) else {
+            return;
+        };
+        // Wake up in time to see the flag fall, even with the status bar hidden, and often enough
+        // to flash the clock in its last seconds
+        if !clock.is_out() {
+            let left = clock.left();
+            let wait = if left < consts::LOW_TIME { 0.25 } else { 1.0 };
+            ctx.request_repaint_after(std::time::Duration::from_secs_f32(left.min(wait)));
+            let second = left.ceil() as u32;
+            let warned = (self.tree.current(), second);
+            if left < consts::LOW_TIME && self.time_warned != Some(warned) {
+                if self.time_warned.is_none_or(|(node, _)| node != warned.0) {
+                    self.log.record(
+                        Kind::Clock,
+                        format!("{} is low on time: {}", gtp::color(player), clock.label()),
+                    );
+                    if self.lan.is_some() && self.lan_opponent == Some(player.other()) {
+                        self.notices
+                            .push(trf("Your clock is low: {}", &[&clock.label()]));
+                    }
+                }
+                self.time_warned = Some(warned);
+                if clock.count().is_some() && self.new_game.speak_countdown {
+                    self.countdown = Some(second);
+                } else {
+                    self.sounds.push(Sound::TimeWarning);
+                }
+            }
+        } else if self.lan_opponent == Some(player) || self.lan_watching {
+            // The other side of a network game tells when its own time is up
+            ctx.request_repaint_after(std::time::Duration::from_secs(1));
+        } else if let clock::Overtime::PerMove {
+            auto_pass: true, ..
+        } = self.info.overtime
+        {
+            self.log.record(
+                Kind::Clock,
+                format!("{} passed on time", gtp::color(player)),
+            );
+            self.advance((player, None));
+            self.file_status = Some(trf("{} passed on time", &[&player_name(player)]));
+        } else {
+            self.info.result = format!("{}+T", gtp::color(player.other()));
+            self.log.record(
+                Kind::Clock,
+                format!(
+                    "{} ran out of time: {}",
+                    gtp::color(player),
+                    self.info.result
+                ),
+            );
+            self.game_over = true;
+            self.sounds.push(Sound::GameEnd);
+            self.file_status = Some(trf("{} ran out of time", &[&player_name(player)]));
+            self.send_lan(&protocol::Message::Timeout(player));
+        }
+    }
+
+    fn to_move_text(&self) -> String {
+        if self.game_over {
+            tr("Game over")
+        } else {
+            trf("{} to move", &[&player_name(self.current_player)])
+        }
+    }
+
+    fn describe_last_move(&self) -> String {
+        match self.moves.last() {
+            Some(&(player, Some(point))) => {
+                let mut text = format!(
+                    "{} {}",
+                    player_name(player),
+                    self.new_game
+                        .coordinates
+                        .format_point(point, self.board_size)
+                );
+                let captured = player_name(player.other());
+                match self.last_captured {
+                    0 => {}
+                    1 => text += &trf(", 1 {} stone captured", &[&captured]),
+                    count => text += &trf(", {} {} stones captured", &[&count, &captured]),
+                }
+                text
+            }
+            Some(&(player, None)) => trf("{} passed", &[&player_name(player)]),
+            None => tr("No moves yet"),
+        }
+    }
+
+    // What the board reads as to screen readers: the last move and who is to move
+    fn describe_position(&self) -> String {
+        format!("{}. {}", self.describe_last_move(), self.to_move_text())
+    }
+
+    fn describe_point(&self, (row, col): (usize, usize)) -> String {
+        let name = self
+            .new_game
+            .coordinates
+            .format_point((row, col), self.board_size);
+        // What is not on the display is not read out either
+        let shown = !self.stones_hidden() && !self.is_secret((row, col));
+        match self.board[row][col] {
+            stone if stone == Stone::Empty || !shown => trf("{} empty", &[&name]),
+            stone => {
+                let color = player_name(if stone == Stone::Black {
+                    Player::Black
+                } else {
+                    Player::White
+                });
+                // Liberties would give away hidden stones next to it
+                if self.hidden.is_some() && !self.game_ended() {
+                    return trf("{} {} stone", &[&name, &color]);
+                }
+                match self.board.liberties(row, col).len() {
+                    1 => trf("{} {} stone, 1 liberty", &[&name, &color]),
+                    count => trf("{} {} stone, {} liberties", &[&name, &color, &count]),
+                }
+            }
+        }
+    }
+
+    // A short summary for the window title
+    fn title(&self) -> String {
+        match self.state {
+            AppState::Game => trf(
+                "Move {}, {} - {}",
+                &[&self.moves.len(), &self.to_move_text(), &consts::TITLE],
+            ),
+            _ => consts::TITLE.to_string(),
+        }
+    }
+
+    fn show_status_bar(&mut self, ctx: &egui::Context) {
+        egui::TopBottomPanel::bottom("status_bar").show(ctx, |ui| {
+            ui.horizontal(|ui| {
+                ui.label(trf("Move {}", &[&self.moves.len()]));
+                ui.separator();
+                ui.label(self.to_move_text());
+                ui.separator();
+                ui.label(trf("Komi {}", &[&i18n::number(self.info.komi)]));
+                let last = self.moves.last().and_then(|&(_, point)| point);
+                let names: Vec<String> = [
+                    openings::fuseki(&self.board, self.moves.len()),
+                    last.and_then(|point| openings::joseki(&self.board, point)),
+                ]
+                .into_iter()
+                .flatten()
+                .map(tr)
+                .collect();
+                if !names.is_empty() {
+                    ui.separator();
+                    ui.label(names.join(", "));
+                }
+                if let Some(point) = self.hovered_point.or(self.board_cursor) {
+                    ui.separator();
+                    ui.label(trf(
+                        "Cursor {}",
+                        &[&self
+                            .new_game
+                            .coordinates
+                            .format_point(point, self.board_size)],
+                    ));
+                }
+                if let (Some(black), Some(white)) =
+                    (self.clock(Player::Black), self.clock(Player::White))
+                {
+                    ui.separator();
+                    ui.label(trf(
+                        "Time - Black {}, White {}",
+                        &[&black.label(), &white.label()],
+                    ));
+                    // Stopped clocks show the same time until something else happens
+                    if !self.game_over && self.paused.is_none() {
+                        ctx.request_repaint_after(std::time::Duration::from_secs(1));
+                    }
+                }
+                if !self.info.result.is_empty() {
+                    ui.separator();
+                    ui.label(trf("Result {}", &[&self.info.result]));
+                }
+            });
+        });
+    }
+
+    fn show_move_history(&mut self, ctx: &egui::Context) {
+        let mut jump = None;
+        let mut jump_node = None;
+        let mut panel = egui::SidePanel::right("move_history");
+        if let Some(width) = self.layout.history_width {
+            panel = panel.default_width(width);
+        }
+        let response = panel.show(ctx, |ui| {
+            ui.heading(tr("Moves"));
+            if self.guess_record.is_some() {
+                ui.label(tr("Hidden while guessing"));
+                return;
+            }
+            // The comment belongs to the node on the board, and is saved as its SGF C property
+            let node = self.tree.current();
+            let mut comment = self.tree.node(node).comment.clone();
+            if ui
+                .add(
+                    egui::TextEdit::multiline(&mut comment)
+                        .desired_rows(4)
+                        .hint_text(tr("Comment on this move")),
+                )
+                .changed()
+            {
+                self.tree.set_comment(node, comment);
+            }
+            if self.can_bookmark() {
+                self.show_bookmarks(ui, &mut jump_node);
+            }
+            ui.separator();
+            let current = self.moves.len();
+            let line = self.tree.line();
+
+            // Thinking time per move, Black's bars above the axis and White's below
+            let times: Vec<(Player, f32)> = line
+                .iter()
+                .filter_map(|&id| {
+                    let node = self.tree.node(id);
+                    Some((node.played?.0, node.seconds?))
+                })
+                .collect();
+            if !times.is_empty() {
+                let size = egui::Vec2::new(ui.available_width(), consts::TIME_CHART_HEIGHT);
+                let (response, painter) = ui.allocate_painter(size, egui::Sense::hover());
+                let rect = response.rect;
+                painter.rect_filled(rect, 0.0, egui::Color32::from_gray(30));
+                let longest = times
+                    .iter()
+                    .fold(1.0_f32, |max, &(_, seconds)| max.max(seconds));
+                let width = rect.width() / (line.len() - 1).max(1) as f32;
+                for (index, &id) in line.iter().skip(1).enumerate() {
+                    let node = self.tree.node(id);
+                    let (Some((player, _)), Some(seconds)) = (node.played, node.seconds) else {
+                        continue;
+                    };
+                    let height = rect.height() * 0.5 * seconds / longest;
+                    let left = rect.left() + index as f32 * width;
+                    let (top, bottom, color) = match player {
+                        Player::Black => (
+                            rect.center().y - height,
+                            rect.center().y,
+                            egui::Color32::LIGHT_GRAY,
+                        ),
+                        Player::White => (
+                            rect.center().y,
+                            rect.center().y + height,
+                            egui::Color32::WHITE,
+                        ),
+                    };
+                    painter.rect_filled(
+                        egui::Rect::from_x_y_ranges(left..=left + width.max(1.0), top..=bottom),
+                        0.0,
+                        color,
+                    );
+                }
+            }
+
+            egui::ScrollArea::vertical().show(ui, |ui| {
+                if ui.selectable_label(current == 0, tr("Start")).clicked() {
+                    jump = Some(0);
+                }
+                for (index, &id) in line.iter().skip(1).enumerate() {
+                    let Some((player, point)) = self.tree.node(id).played else {
+                        continue;
+                    };
+                    let vertex = point.map_or(tr("pass"), |point| {
+                        self.new_game
+                            .coordinates
+                            .format_point(point, self.board_size)
+                    });
+                    let mut text = format!("{}. {} {}", index + 1, gtp::color(player), vertex);
+                    if let Some(seconds) = self.tree.node(id).seconds {
+                        text.push_str(&format!("  {:.0}s", seconds));
+                    }
+                    // Mark moves that have alternatives
+                    let alternatives = self.tree.siblings(id).len() - 1;
+                    if alternatives > 0 {
+                        text.push_str(&format!("  (+{})", alternatives));
+                    }
+                    // Moves after the current position stay listed but dimmed
+                    let text = if index < current {
+                        egui::RichText::new(text)
+                    } else {
+                        egui::RichText::new(text).weak()
+                    };
+                    let response = ui.selectable_label(index + 1 == current, text);
+                    if response.clicked() {
+                        jump = Some(index + 1);
+                    }
+                }
+            });
+        });
+        self.layout.history_width = Some(response.response.rect.width());
+        if let Some(move_number) = jump {
+            self.go_to_move(move_number);
+        }
+        if let Some(id) = jump_node
+            && self.can_navigate()
+        {
+            self.go_to_node(id);
+        }
+    }
+
+    // The bookmarks of the game, each a click away, and the label for the position on the board
+    fn show_bookmarks(&mut self, ui: &mut egui::Ui, jump: &mut Option<usize>) {
+        let bookmarks = self.tree.bookmarks();
+        let current = self.tree.current();
+        egui::CollapsingHeader::new(trf("Bookmarks ({})", &[&bookmarks.len()]))
+            .id_salt("bookmarks")
+            .show(ui, |ui| {
+                let mut removed = None;
+                for &id in &bookmarks {
+                    let node = self.tree.node(id);
+                    let label = format!("{}. {}", self.tree.moves_to(id).len(), node.bookmark);
+                    ui.horizontal(|ui| {
+                        if ui.selectable_label(id == current, label).clicked() {
+                            *jump = Some(id);
+                        }
+                        if ui.small_button(tr("Remove")).clicked() {
+                            removed = Some(id);
+                        }
+                    });
+                }
+                if let Some(id) = removed {
+                    self.tree.set_bookmark(id, String::new());
+                }
+                ui.horizontal(|ui| {
+                    ui.add(
+                        egui::TextEdit::singleline(&mut self.bookmark_label)
+                            .hint_text(self.tree.node(current).bookmark.clone())
+                            .desired_width(120.0),
+                    );
+                    if ui
+                        .button(tr("Bookmark"))
+                        .on_hover_text(tr("Bookmark the position on the board under this label, or its move number without one"))
+                        .clicked()
+                    {
+                        self.bookmark();
+                    }
+                });
+            });
+    }
+
+    // The chat of a network game, left up after the connection closes while it has messages
+    fn show_chat(&mut self, ctx: &egui::Context) {
+        if self.lan.is_none() && self.chat.is_empty() {
+            return;
+        }
+        let mut panel = egui::SidePanel::right("chat");
+        if let Some(width) = self.layout.chat_width {
+            panel = panel.default_width(width);
+        }
+        let response = panel.show(ctx, |ui| {
+            ui.heading(tr("Chat"));
+            if self.lan_watching {
+                ui.horizontal(|ui| {
+                    ui.selectable_value(&mut self.chat_spectators, false, tr("Players"));
+                    ui.selectable_value(&mut self.chat_spectators, true, tr("Spectators"));
+                });
+            }
+            if self.lan_rengo.is_some() {
+                ui.horizontal(|ui| {
+                    ui.selectable_value(&mut self.chat_team, false, tr("Everyone"));
+                    ui.selectable_value(&mut self.chat_team, true, tr("Team"))
+                        .on_hover_text(tr("Only your partner reads this"));
+                });
+            }
+            let spectators = self.lan_watching && self.chat_spectators;
+            let team = self.lan_rengo.is_some() && self.chat_team;
+            // OGS games of others are watched with their chat read only
+            let watching_ogs =
+                self.lan_watching && matches!(self.lan_peer, Some(lan::Peer::Ogs { .. }));
+            let can_send = self.lan.is_some()
+                && if self.lan_watching {
+                    spectators && !watching_ogs
+                } else {
+                    self.lan_opponent.is_some()
+                };
+            ui.add_enabled_ui(can_send, |ui| {
+                let response = ui.add(
+                    egui::TextEdit::singleline(&mut self.chat_draft)
+                        .hint_text(tr("Say something"))
+                        .desired_width(f32::INFINITY),
+                );
+                if response.lost_focus() && ui.input(|i| i.key_pressed(egui::Key::Enter)) {
+                    self.send_chat();
+                    response.request_focus();
+                }
+            });
+            if self.lan_watching && !spectators {
+                ui.label(egui::RichText::new(tr("Only the players talk here")).weak());
+            }
+            ui.separator();
+            egui::ScrollArea::vertical()
+                .stick_to_bottom(true)
+                .show(ui, |ui| {
+                    for line in &self.chat {
+                        if line.from.is_none() != spectators || line.team != team {
+                            continue;
+                        }
+                        let name = line.from.map_or_else(|| tr("Spectator"), player_name);
+                        ui.horizontal_wrapped(|ui| {
+                            ui.label(egui::RichText::new(clock::format(line.at)).weak());
+                            ui.label(egui::RichText::new(format!("{}:", name)).strong());
+                            ui.label(&line.text);
+                        });
+                    }
+                });
+        });
+        self.layout.chat_width = Some(response.response.rect.width());
+    }
+
+    fn show_undo_prompt(&mut self, ctx: &egui::Context) {
+        let (Some(count), Some(opponent)) = (self.undo_offer, self.lan_opponent) else {
+            return;
+        };
+        let mut answer = None;
+        egui::Modal::new(egui::Id::new("undo_offer")).show(ctx, |ui| {
+            ui.heading(tr("Undo"));
+            ui.label(trf(
+                "{} asks to take back {}",
+                &[&player_name(opponent), &undo_moves(count)],
+            ));
+            ui.horizontal(|ui| {
+                if ui.button(tr("Accept")).clicked() {
+                    answer = Some(true);
+                }
+                if ui.button(tr("Decline")).clicked() {
+                    answer = Some(false);
+                }
+            });
+        });
+        let Some(accept) = answer else {
+            return;
+        };
+        self.undo_offer = None;
+        if accept {
+            self.send_lan(&protocol::Message::UndoAccept(count));
+            self.take_back(count, opponent);
+        } else {
+            self.send_lan(&protocol::Message::UndoDecline);
+            self.log_comment(&trf(
+                "{} asked to take back {}, declined by {}",
+                &[
+                    &player_name(opponent),
+                    &undo_moves(count),
+                    &player_name(opponent.other()),
+                ],
+            ));
+        }
+    }
+
+    fn show_self_atari_prompt(&mut self, ctx: &egui::Context) {
+        let Some(point) = self.self_atari_prompt else {
+            return;
+        };
+        let mut play = false;
+        let mut cancel = false;
+        let response = egui::Modal::new(egui::Id::new("self_atari")).show(ctx, |ui| {
+            ui.heading(tr("Self-atari"));
+            ui.label(trf(
+                "{} leaves your stones with a single liberty. Play it anyway?",
+                &[&self
+                    .new_game
+                    .coordinates
+                    .format_point(point, self.board_size)],
+            ));
+            ui.checkbox(&mut self.new_game.warn_self_atari, tr("Keep asking"));
+            ui.horizontal(|ui| {
+                play = ui.button(tr("Play")).clicked();
+                cancel = ui.button(tr("Cancel")).clicked();
+            });
+        });
+        if play {
+            self.self_atari_prompt = None;
+            self.play(Some(point));
+        } else if cancel || response.should_close() {
+            self.self_atari_prompt = None;
+        }
+    }
+
+    fn can_run(&self, command: Command) -> bool {
+        match command {
+            Command::SaveSgf => consts::DESKTOP && self.trial.is_none(),
+            Command::Undo => self.can_undo(),
+            Command::Redo => self.can_redo(),
+            Command::Pass => self.human_to_move() && self.presentation.is_none(),
+            Command::CountScore => {
+                self.game_ended()
+                    && !self.game_over
+                    && self.dead_stones.is_none()
+                    && self.guess_record.is_none()
+            }
+            Command::Rematch => self.game_ended() && self.guess_record.is_none(),
+            Command::EditPosition => {
+                self.edit_stone.is_some()
+                    || (self.can_edit_position()
+                        && self.presentation.is_none()
+                        && self.markup_tool.is_none())
+            }
+            Command::AnalyzeGame => self.analysis_pending.is_none(),
+            Command::TryItOut => {
+                self.guess_record.is_none()
+                    && self.edit_stone.is_none()
+                    && self.presentation.is_none()
+            }
+            Command::AttachEngine => consts::DESKTOP && self.engine.is_none(),
+            Command::Presentation => self.can_present(),
+            Command::Markup => self.can_mark_up(),
+            Command::ExportImage => consts::DESKTOP && !self.export_requested,
+            Command::Scripts | Command::Broadcast | Command::Tournament | Command::Wall => {
+                consts::DESKTOP
+            }
+            Command::PauseClocks => self.can_pause(),
+            Command::Debug => self.can_debug(),
+            Command::Share => self.can_share(),
+            Command::Bookmark => self.can_bookmark(),
+            Command::Adjourn => consts::DESKTOP && self.can_pause() && self.trial.is_none(),
+            _ => true,
+        }
+    }
+
+    fn run_command(&mut self, command: Command, ctx: &egui::Context) {
+        match command {
+            Command::NewGame => self.reset(),
+            Command::SaveSgf => self.save_game(),
+            Command::Undo => self.undo(),
+            Command::Redo => self.redo(),
+            Command::Pass => self.pass_clicked(),
+            Command::CountScore => self.start_count(),
+            Command::Rematch => self.rematch(),
+            Command::GameInfo => self.layout.show_info = !self.layout.show_info,
+            Command::EditPosition => {
+                self.edit_stone = match self.edit_stone {
+                    Some(_) => None,
+                    None => Some(Stone::Black),
+                };
+            }
+            Command::AnalyzeGame => self.analyze_game(ctx),
+            Command::GameTree => self.layout.show_tree = !self.layout.show_tree,
+            Command::TryItOut => {
+                if self.trial.is_some() {
+                    self.end_trial();
+                } else {
+                    self.start_trial();
+                }
+            }
+            Command::Estimate => self.show_estimate = !self.show_estimate,
+            Command::Influence => self.show_influence = !self.show_influence,
+            Command::AtariWarnings => self.show_atari = !self.show_atari,
+            Command::AreaCount => self.show_area = !self.show_area,
+            Command::SelectRegion => self.selecting_region = !self.selecting_region,
+            Command::AttachEngine => {
+                if let Err(err) = self.start_engine() {
+                    self.file_status = Some(err.to_string());
+                }
+            }
+            Command::Fullscreen => toggle_fullscreen(ctx),
+            Command::Coordinates => self.layout.show_coordinates = !self.layout.show_coordinates,
+            Command::Preferences => self.preferences = Some(self.new_game.clone()),
+            Command::RotateBoard => self.view.rotate(),
+            Command::MirrorBoard => self.view.mirrored = !self.view.mirrored,
+            Command::Presentation => self.toggle_presentation(),
+            Command::Markup => {
+                self.markup_tool = match self.markup_tool {
+                    Some(_) => None,
+                    None => Some(markup::Tool::Triangle),
+                }
+            }
+            Command::Drawing => {
+                self.drawing_tool = match self.drawing_tool {
+                    Some(_) => None,
+                    None => Some(drawing::Tool::Pen),
+                }
+            }
+            Command::ExportImage => self.request_export(ctx),
+            Command::PauseClocks => self.toggle_pause(),
+            Command::Adjourn => self.adjourn(),
+            Command::GameLog => self.show_log = !self.show_log,
+            Command::Scripts => self.show_scripts = !self.show_scripts,
+            Command::Tournament => self.show_tournament = !self.show_tournament,
+            Command::Wall => self.wall_requested = true,
+            Command::Study => self.study_requested = true,
+            Command::Compare => self.show_compare = !self.show_compare,
+            Command::Debug => self.show_debug = !self.show_debug,
+            Command::Share => self.open_share(),
+            Command::Bookmark => self.bookmark(),
+            #[cfg(not(target_arch = "wasm32"))]
+            Command::Broadcast => self.toggle_broadcast(),
+            #[cfg(target_arch = "wasm32")]
+            Command::Broadcast => {}
+        }
+    }
+
+    fn show_command_palette(&mut self, ctx: &egui::Context) {
+        let Some(mut query) = self.palette_query.take() else {
+            return;
+        };
+        let commands: Vec<Command> = Command::ALL
+            .into_iter()
+            .filter(|&command| self.can_run(command) && fuzzy_match(&query, &tr(command.name())))
+            .collect();
+        let mut chosen = None;
+        let response = egui::Modal::new(egui::Id::new("command_palette")).show(ctx, |ui| {
+            ui.set_width(300.0);
+            let input = ui.add(
+                egui::TextEdit::singleline(&mut query)
+                    .hint_text(tr("Type a command"))
+                    .desired_width(f32::INFINITY),
+            );
+            input.request_focus();
+            if ui.input(|i| i.key_pressed(egui::Key::Enter)) {
+                chosen = commands.first().copied();
+            }
+            for (index, &command) in commands.iter().enumerate() {
+                if ui
+                    .selectable_label(index == 0, tr(command.name()))
+                    .clicked()
+                {
+                    chosen = Some(command);
+                }
+            }
+            if commands.is_empty() {
+                ui.label(tr("No matching commands"));
+            }
+        });
+        if let Some(command) = chosen {
+            self.run_command(command, ctx);
+        } else if !response.should_close() {
+            self.palette_query = Some(query);
+        }
+    }
+
+    fn show_game_tree(&mut self, ctx: &egui::Context) {
+        if !self.layout.show_tree || self.guess_record.is_some() {
+            return;
+        }
+        let mut jump = None;
+        let response = egui::TopBottomPanel::bottom("game_tree")
+            .resizable(true)
+            .default_height(self.layout.tree_height.unwrap_or(140.0))
+            .show(ctx, |ui| {
+                let layout = self.tree.layout();
+                let columns = layout.iter().map(|&(column, _)| column).max().unwrap_or(0) + 1;
+                let rows = layout.iter().map(|&(_, row)| row).max().unwrap_or(0) + 1;
+                let current = self.tree.current();
+                let scroll = self.tree_scrolled_to != Some(current);
+                self.tree_scrolled_to = Some(current);
+                egui::ScrollArea::both().show(ui, |ui| {
+                    let size =
+                        egui::Vec2::new(columns as f32, rows as f32) * consts::TREE_NODE_SPACING;
+                    let (response, painter) = ui.allocate_painter(size, egui::Sense::click());
+                    let center = |id: usize| {
+                        let (column, row) = layout[id];
+                        response.rect.min
+                            + egui::Vec2::new(column as f32 + 0.5, row as f32 + 0.5)
+                                * consts::TREE_NODE_SPACING
+                    };
+                    let line_color = egui::Color32::GRAY;
+                    for (id, &(_, row)) in layout.iter().enumerate() {
+                        let Some(parent) = self.tree.node(id).parent else {
+                            continue;
+                        };
+                        // Branches drop down to their row just before the move
+                        let bend = egui::Pos2::new(
+                            center(id).x - consts::TREE_NODE_SPACING,
+                            response.rect.min.y + (row as f32 + 0.5) * consts::TREE_NODE_SPACING,
+                        );
+                        painter.line_segment(
+                            [center(parent), bend],
+                            egui::Stroke::new(1.0, line_color),
+                        );
+                        painter
+                            .line_segment([bend, center(id)], egui::Stroke::new(1.0, line_color));
+                    }
+                    let line = self.tree.line();
+                    for id in 0..layout.len() {
+                        let pos = center(id);
+                        let fill = match self.tree.node(id).played {
+                            Some((Player::Black, _)) => egui::Color32::BLACK,
+                            Some((Player::White, _)) => egui::Color32::WHITE,
+                            None => egui::Color32::GRAY,
+                        };
+                        painter.circle_filled(pos, consts::TREE_NODE_RADIUS, fill);
+                        // Nodes off the current line are drawn with a lighter outline, and the
+                        // lines of a problem in the colors of how they end
+                        let outline = if let Some(verdict) = self.tree.node(id).verdict {
+                            match verdict {
+                                Verdict::Correct => egui::Color32::GREEN,
+                                Verdict::Wrong => egui::Color32::RED,
+                            }
+                        } else if line.contains(&id) {
+                            egui::Color32::DARK_GRAY
+                        } else {
+                            egui::Color32::LIGHT_GRAY
+                        };
+                        painter.circle_stroke(
+                            pos,
+                            consts::TREE_NODE_RADIUS,
+                            egui::Stroke::new(1.0, outline),
+                        );
+                        // Bookmarked positions have a gold dot by their corner
+                        if !self.tree.node(id).bookmark.is_empty() {
+                            painter.circle_filled(
+                                pos - egui::Vec2::splat(consts::TREE_NODE_RADIUS),
+                                3.0,
+                                egui::Color32::GOLD,
+                            );
+                        }
+                        let name = &self.tree.node(id).name;
+                        if !name.is_empty() {
+                            painter.text(
+                                pos + egui::Vec2::new(
+                                    consts::TREE_NODE_RADIUS,
+                                    -consts::TREE_NODE_RADIUS,
+                                ),
+                                egui::Align2::LEFT_BOTTOM,
+                                name,
+                                egui::FontId::proportional(11.0),
+                                ui.visuals().text_color(),
+                            );
+                        }
+                        if id == current {
+                            painter.circle_stroke(
+                                pos,
+                                consts::TREE_NODE_RADIUS + 3.0,
+                                egui::Stroke::new(2.0, egui::Color32::RED),
+                            );
+                            if scroll {
+                                let rect = egui::Rect::from_center_size(
+                                    pos,
+                                    egui::Vec2::splat(consts::TREE_NODE_SPACING),
+                                );
+                                ui.scroll_to_rect(rect, None);
+                            }
+                        }
+                    }
+                    if response.clicked()
+                        && let Some(pos) = response.interact_pointer_pos()
+                    {
+                        let rel_pos = (pos - response.rect.min) / consts::TREE_NODE_SPACING;
+                        let clicked = (rel_pos.x as usize, rel_pos.y as usize);
+                        jump = layout.iter().position(|&position| position == clicked);
+                    }
+                });
+            });
+        self.layout.tree_height = Some(response.response.rect.height());
+        if let Some(id) = jump
+            && self.can_navigate()
+        {
+            self.go_to_node(id);
+        }
+    }
+
+    fn point_at(&self, pos: egui::Pos2, top_left: egui::Pos2, cell: f32) -> Option<(usize, usize)> {
+        let rel_pos = (pos - top_left) / cell + egui::Vec2::splat(0.5);
+        if rel_pos.x < 0.0 || rel_pos.y < 0.0 {
+            return None;
+        }
+        let (row, col) = (rel_pos.y as usize, rel_pos.x as usize);
+        if row < self.board_size && col < self.board_size {
+            Some(self.view.to_board((row, col), self.board_size))
+        } else {
+            None
+        }
+    }
+
+    fn navigate_with_keys(&mut self, ctx: &egui::Context) {
+        let current = self.moves.len();
+        let navigation = ctx.input(|i| {
+            if i.key_pressed(egui::Key::ArrowLeft) {
+                Some(current.saturating_sub(1))
+            } else if i.key_pressed(egui::Key::ArrowRight) {
+                Some(current + 1)
+            } else if i.key_pressed(egui::Key::Home) {
+                Some(0)
+            } else if i.key_pressed(egui::Key::End) {
+                Some(usize::MAX)
+            } else {
+                None
+            }
+        });
+        if let Some(move_number) = navigation {
+            self.go_to_move(move_number);
+        }
+        if let Some(point) = self.staged_move {
+            if ctx.input(|i| i.key_pressed(egui::Key::Enter)) {
+                self.click_point(point);
+            } else if ctx.input(|i| i.key_pressed(egui::Key::Escape)) {
+                self.staged_move = None;
+            }
+        } else if ctx.input(|i| i.key_pressed(egui::Key::Enter)) {
+            // Bring up the board cursor for playing without the mouse
+            let center = self.board_size / 2;
+            self.board_cursor = Some(self.last_move.unwrap_or((center, center)));
+        }
+        if ctx.input(|i| i.key_pressed(egui::Key::ArrowUp)) {
+            self.switch_variation(false);
+        }
+        if ctx.input(|i| i.key_pressed(egui::Key::ArrowDown)) {
+            self.switch_variation(true);
+        }
+    }
+
+    // While the board cursor is shown the arrow keys move it and Enter plays where it is
+    fn move_board_cursor(&mut self, ctx: &egui::Context, cursor: (usize, usize)) {
+        let last = self.board_size - 1;
+        // The arrows move across the screen, whichever way the board is turned
+        let (row, col) = self.view.to_screen(cursor, self.board_size);
+        let (row, col) = ctx.input(|i| {
+            if i.key_pressed(egui::Key::ArrowUp) {
+                (row.saturating_sub(1), col)
+            } else if i.key_pressed(egui::Key::ArrowDown) {
+                ((row + 1).min(last), col)
+            } else if i.key_pressed(egui::Key::ArrowLeft) {
+                (row, col.saturating_sub(1))
+            } else if i.key_pressed(egui::Key::ArrowRight) {
+                (row, (col + 1).min(last))
+            } else {
+                (row, col)
+            }
+        });
+        let (row, col) = self.view.to_board((row, col), self.board_size);
+        self.board_cursor = Some((row, col));
+        if ctx.input(|i| i.key_pressed(egui::Key::Enter)) {
+            match self.staged_move {
+                Some(point) => self.click_point(point),
+                None if self.presentation.is_some() => self.present_point((row, col)),
+                None if self.human_to_move() => self.click_point((row, col)),
+                None => {}
+            }
+        } else if ctx.input(|i| i.key_pressed(egui::Key::Escape)) {
+            if self.staged_move.is_some() {
+                self.staged_move = None;
+            } else {
+                self.board_cursor = None;
+            }
+        }
+    }
+
+    // The d-pad moves the board cursor, A plays where it is, B passes and the shoulder buttons
+    // step through the game
+    fn gamepad_action(&mut self, action: gamepad::Action) {
+        let last = self.board_size - 1;
+        let center = self.board_size / 2;
+        let cursor = self
+            .board_cursor
+            .or(self.last_move)
+            .unwrap_or((center, center));
+        let (row, col) = self.view.to_screen(cursor, self.board_size);
+        let step = |screen| Some(self.view.to_board(screen, self.board_size));
+        match action {
+            gamepad::Action::Up => self.board_cursor = step((row.saturating_sub(1), col)),
+            gamepad::Action::Down => self.board_cursor = step(((row + 1).min(last), col)),
+            gamepad::Action::Left => self.board_cursor = step((row, col.saturating_sub(1))),
+            gamepad::Action::Right => self.board_cursor = step((row, (col + 1).min(last))),
+            gamepad::Action::Place => match self.staged_move {
+                Some(point) => self.click_point(point),
+                None if self.board_cursor.is_some() && self.presentation.is_some() => {
+                    self.present_point(cursor)
+                }
+                None if self.board_cursor.is_some() && self.human_to_move() => {
+                    self.click_point(cursor)
+                }
+                None => self.board_cursor = Some(cursor),
+            },
+            gamepad::Action::Pass => {
+                if self.human_to_move() && self.presentation.is_none() {
+                    self.pass_clicked();
+                }
+            }
+            gamepad::Action::Back => self.go_to_move(self.moves.len().saturating_sub(1)),
+            gamepad::Action::Forward => self.go_to_move(self.moves.len() + 1),
+        }
+    }
+
+    fn show_game(&mut self, ctx: &egui::Context) {
+        self.check_flag(ctx);
+        if self.drawings_node != self.tree.current() {
+            self.drawings.clear();
+            self.drawings_node = self.tree.current();
+        }
+        if self.export_requested
+            && let Some(screenshot) = ctx.input(|i| {
+                i.raw.events.iter().find_map(|event| match event {
+                    egui::Event::Screenshot { image, .. } => Some(image.clone()),
+                    _ => None,
+                })
+            })
+        {
+            self.export_requested = false;
+            self.export_image(&screenshot, ctx.pixels_per_point());
+        }
+        // Leave the shortcuts to text fields while one of them is focused
+        if !ctx.wants_keyboard_input() {
+            if ctx.input_mut(|i| i.consume_shortcut(&UNDO_SHORTCUT)) {
+                self.undo();
+            }
+            if ctx.input_mut(|i| i.consume_shortcut(&REDO_SHORTCUT)) {
+                self.redo();
+            }
+            if ctx.input_mut(|i| i.consume_shortcut(&PALETTE_SHORTCUT)) {
+                self.palette_query = Some(String::new());
+            }
+            if ctx.input_mut(|i| i.consume_shortcut(&DEBUG_SHORTCUT)) {
+                self.show_debug = !self.show_debug;
+            }
+            if let Some(cursor) = self.board_cursor {
+                self.move_board_cursor(ctx, cursor);
+            } else {
+                self.navigate_with_keys(ctx);
+            }
+        }
+        // Fullscreen leaves the board and the prompts that need an answer
+        let fullscreen = ctx.input(|i| i.viewport().fullscreen.unwrap_or(false));
+        if !fullscreen {
+            self.show_status_bar(ctx);
+        }
+        self.show_game_info(ctx);
+        self.show_pattern_result(ctx);
+        self.show_yose(ctx);
+        self.show_fuseki_explorer(ctx);
+        self.show_score_graph(ctx);
+        self.show_game_log(ctx);
+        #[cfg(not(target_arch = "wasm32"))]
+        self.show_scripts_window(ctx);
+        #[cfg(not(target_arch = "wasm32"))]
+        self.show_tournament_window(ctx);
+        self.show_compare_window(ctx);
+        self.show_counting(ctx);
+        self.show_share(ctx);
+        if !fullscreen {
+            self.show_chat(ctx);
+            self.show_move_history(ctx);
+            self.show_prisoners(ctx);
+            self.show_game_tree(ctx);
+        }
+        self.show_self_atari_prompt(ctx);
+        self.show_undo_prompt(ctx);
+        self.show_command_palette(ctx);
+        if self.show_area {
+            self.update_area_count();
+        }
+        let score = self.score();
+        let estimate = self.show_estimate.then(|| estimate::estimate(&self.board));
+        if self.skin.as_ref().map(|skin| &skin.name) != self.new_game.skin.as_ref() {
+            self.skin = self
+                .new_game
+                .skin
+                .as_ref()
+                .map(|name| skin::Skin::load(ctx, name));
+        }
+        egui::CentralPanel::default().show(ctx, |ui| {
+            if let Some(mut presentation) = self.presentation {
+                // Stays up in fullscreen, where presenting usually happens
+                let mut stop = false;
+                ui.horizontal(|ui| {
+                    ui.heading(tr("Presenting"));
+                    ui.separator();
+                    ui.label(tr("Place:"));
+                    ui.radio_value(&mut presentation.color, Player::Black, tr("Black"));
+                    ui.radio_value(&mut presentation.color, Player::White, tr("White"));
+                    ui.checkbox(&mut presentation.alternate, tr("Alternate"));
+                    ui.separator();
+                    if ui
+                        .add_enabled(
+                            !self.moves.is_empty() && self.can_navigate(),
+                            egui::Button::new(tr("Back")),
+                        )
+                        .clicked()
+                    {
+                        self.go_to_move(self.moves.len().saturating_sub(1));
+                    }
+                    if ui
+                        .add_enabled(self.can_redo(), egui::Button::new(tr("Next")))
+                        .clicked()
+                    {
+                        self.go_to_move(self.moves.len() + 1);
+                    }
+                    if ui
+                        .button(tr("Clear Stones"))
+                        .on_hover_text(tr("Go back to the position of the game record"))
+                        .clicked()
+                    {
+                        self.go_to_node(self.tree.current());
+                    }
+                    stop = ui.button(tr("Stop Presenting")).clicked();
+                });
+                self.presentation = Some(presentation);
+                if stop {
+                    self.toggle_presentation();
+                }
+            } else if !fullscreen {
+                ui.heading(tr("Go Game"));
+                if let Some(pair) = self.pair.clone()
+                    && !self.game_over
+                {
+                    let turn = self.pair_turn();
+                    let mut seat = self
+                        .pair_seat
+                        .filter(|&(moves, _)| moves == self.moves.len())
+                        .map_or(turn, |(_, seat)| seat);
+                    ui.horizontal(|ui| {
+                        ui.label(tr("At the board:"));
+                        for partner in [turn % 2, turn % 2 + 2] {
+                            ui.selectable_value(&mut seat, partner, &pair[partner]);
+                        }
+                    });
+                    self.pair_seat = Some((self.moves.len(), seat));
+                    if let Some(seat) = self.wrong_partner() {
+                        ui.colored_label(
+                            ui.visuals().warn_fg_color,
+                            self.wrong_partner_warning(seat),
+                        );
+                    }
+                }
+                ui.horizontal(|ui| {
+                    ui.label(trf(
+                        "Current Player: {}",
+                        &[&player_name(self.current_player)],
+                    ));
+
+                    if self.show_area
+                        && let Some((_, black, white)) = self.area_count
+                    {
+                        ui.separator();
+                        ui.label(trf("Area - Black: {}, White: {}", &[&black, &white]));
+                    }
+                    if let Some(estimate) = &estimate {
+                        ui.separator();
+                        ui.label(trf(
+                            "Estimate: {}",
+                            &[&estimate::format_lead(estimate.lead(self.info.komi))],
+                        ));
+                    }
+                    if ui
+                        .add_enabled(self.human_to_move(), egui::Button::new(tr("Pass")))
+                        .clicked()
+                    {
+                        self.pass_clicked();
+                    }
+                    if self.lan.is_some()
+                        && self.lan_opponent.is_some()
+                        && !self.game_over
+                        && ui.button(tr("Resign")).clicked()
+                    {
+                        self.resign();
+                    }
+                    if self.lan.is_some()
+                        && self.lan_opponent.is_some()
+                        && !self.game_over
+                        && ui
+                            .add_enabled(
+                                self.undo_asked.is_none()
+                                    && self.undo_offer.is_none()
+                                    && self.lan_rengo.is_none()
+                                    && self
+                                        .lan_opponent
+                                        .is_some_and(|opponent| {
+                                            self.can_take_back(opponent.other()).is_some()
+                                        }),
+                                egui::Button::new(tr("Request Undo")),
+                            )
+                            .on_hover_text(tr("Ask the opponent to let you take back your last move"))
+                            .clicked()
+                    {
+                        self.request_undo();
+                    }
+                    if self.lan_rengo.is_some_and(|turn| self.rengo_turn() != turn)
+                        && self.lan_opponent != Some(self.current_player)
+                        && !self.game_over
+                    {
+                        ui.label(tr("Your partner's move"));
+                    }
+                    if self.lan.is_some() && self.lan_spectators > 0 {
+                        ui.label(trf("{} watching", &[&self.lan_spectators]));
+                    }
+                    #[cfg(not(target_arch = "wasm32"))]
+                    if let Some(broadcast) = &self.broadcast {
+                        ui.hyperlink_to(
+                            trf("Broadcasting, {} watching", &[&broadcast.viewers()]),
+                            &broadcast.address,
+                        )
+                        .on_hover_text(&broadcast.address);
+                    }
+                    if self.can_pause() {
+                        let label = if self.paused.is_some() {
+                            tr("Resume")
+                        } else {
+                            tr("Pause")
+                        };
+                        if ui
+                            .button(label)
+                            .on_hover_text(tr("Stop both clocks"))
+                            .clicked()
+                        {
+                            self.toggle_pause();
+                        }
+                    }
+                    if ui
+                        .add_enabled(self.can_undo(), egui::Button::new(tr("Undo")))
+                        .on_hover_text(ctx.format_shortcut(&UNDO_SHORTCUT))
+                        .clicked()
+                    {
+                        self.undo();
+                    }
+                    if ui
+                        .add_enabled(self.can_redo(), egui::Button::new(tr("Redo")))
+                        .on_hover_text(ctx.format_shortcut(&REDO_SHORTCUT))
+                        .clicked()
+                    {
+                        self.redo();
+                    }
+                    if self.game_ended()
+                        && !self.game_over
+                        && self.dead_stones.is_none()
+                        && self.guess_record.is_none()
+                        && ui
+                            .button(tr("Count"))
+                            .on_hover_text(tr("Mark dead stones and count the game"))
+                            .clicked()
+                    {
+                        self.start_count();
+                    }
+                    if self.game_ended()
+                        && self.guess_record.is_none()
+                        && ui
+                            .button(tr("Rematch"))
+                            .on_hover_text(tr("Same settings, colors swapped"))
+                            .clicked()
+                    {
+                        self.rematch();
+                    }
+                    if self.can_share()
+                        && ui
+                            .button(tr("Share"))
+                            .on_hover_text(tr("A link and QR code that open the game on a phone"))
+                            .clicked()
+                    {
+                        self.open_share();
+                    }
+                    if ui.button(tr("Reset Game")).clicked() {
+                        self.reset();
+                    }
+                    if ui.button(tr("Game Info")).clicked() {
+                        self.layout.show_info = !self.layout.show_info;
+                    }
+                    let editing = self.edit_stone.is_some();
+                    if ui
+                        .add_enabled(
+                            editing || self.can_edit_position(),
+                            egui::Button::new(tr("Edit Position")).selected(editing),
+                        )
+                        .on_disabled_hover_text(tr("Only possible before the first move"))
+                        .clicked()
+                    {
+                        self.edit_stone = if editing { None } else { Some(Stone::Black) };
+                    }
+                    let marking = self.markup_tool.is_some();
+                    if ui
+                        .add_enabled(
+                            self.can_mark_up(),
+                            egui::Button::new(tr("Markup")).selected(marking),
+                        )
+                        .on_hover_text(tr("Mark points of this move with shapes, letters and numbers"))
+                        .clicked()
+                    {
+                        self.markup_tool = if marking {
+                            None
+                        } else {
+                            Some(markup::Tool::Triangle)
+                        };
+                    }
+                    if self.blind && !self.game_ended() {
+                        // Shown only while held, so a look stays a look
+                        self.blind_revealed = ui
+                            .button(tr("Show Stones"))
+                            .on_hover_text(tr("Hold down to see the position"))
+                            .is_pointer_button_down_on();
+                    }
+                    if consts::DESKTOP
+                        && ui
+                            .add_enabled(
+                                self.guess_record.is_none() && self.problem.is_none(),
+                                egui::Button::new(tr("Problem Editor")).selected(self.problem_editor),
+                            )
+                            .on_hover_text(tr("Set up a problem, play its solutions and refutations as variations, and export it"))
+                            .clicked()
+                    {
+                        self.problem_editor = !self.problem_editor;
+                    }
+                    if ui
+                        .add_enabled(
+                            self.analysis_pending.is_none(),
+                            egui::Button::new(tr("Analyze")),
+                        )
+                        .on_hover_text(tr("Estimate the score after every move"))
+                        .clicked()
+                    {
+                        self.analyze_game(ctx);
+                    }
+                    if ui
+                        .selectable_label(self.layout.show_tree, tr("Game Tree"))
+                        .clicked()
+                    {
+                        self.layout.show_tree = !self.layout.show_tree;
+                    }
+                    if ui
+                        .add_enabled(self.can_present(), egui::Button::new(tr("Present")))
+                        .on_hover_text(tr("Place stones of either color and step through the game for an audience"))
+                        .clicked()
+                    {
+                        self.toggle_presentation();
+                    }
+                    let drawing = self.drawing_tool.is_some();
+                    if ui
+                        .add(egui::Button::new(tr("Draw")).selected(drawing))
+                        .on_hover_text(tr("Draw lines and arrows over the board"))
+                        .clicked()
+                    {
+                        self.drawing_tool = if drawing {
+                            None
+                        } else {
+                            Some(drawing::Tool::Pen)
+                        };
+                    }
+                    let trying = self.trial.is_some();
+                    if ui
+                        .add_enabled(
+                            self.guess_record.is_none() && self.edit_stone.is_none(),
+                            egui::Button::new(tr("Try It Out")).selected(trying),
+                        )
+                        .on_hover_text(tr("Play moves for both sides without changing the game"))
+                        .clicked()
+                    {
+                        if trying {
+                            self.end_trial();
+                        } else {
+                            self.start_trial();
+                        }
+                    }
+                    if consts::DESKTOP
+                        && ui
+                            .add_enabled(!trying, egui::Button::new(tr("Save")))
+                            .clicked()
+                    {
+                        self.save_game();
+                    }
+                    if consts::DESKTOP
+                        && self.can_pause()
+                        && ui
+                            .add_enabled(!trying, egui::Button::new(tr("Adjourn")))
+                            .on_hover_text(tr("Pause the clocks and save the game to resume later"))
+                            .clicked()
+                    {
+                        self.adjourn();
+                    }
+                    if consts::DESKTOP
+                        && ui
+                            .add_enabled(!self.export_requested, egui::Button::new(tr("Export Image")))
+                            .on_hover_text(tr("Save the board as a PNG picture"))
+                            .clicked()
+                    {
+                        self.request_export(ctx);
+                    }
+                });
+            }
+            if let Some(mut tool) = self.markup_tool {
+                let mut done = false;
+                ui.horizontal(|ui| {
+                    ui.label(tr("Mark:"));
+                    for option in markup::Tool::ALL {
+                        ui.radio_value(&mut tool, option, tr(option.name()));
+                    }
+                    ui.separator();
+                    if ui.button(tr("Clear Marks")).clicked() {
+                        self.tree.clear_markup(self.tree.current());
+                    }
+                    done = ui.button(tr("Done")).clicked();
+                });
+                self.markup_tool = (!done).then_some(tool);
+            }
+            if let Some(mut tool) = self.drawing_tool {
+                let mut done = false;
+                ui.horizontal(|ui| {
+                    ui.label(tr("Draw:"));
+                    for option in drawing::Tool::ALL {
+                        ui.radio_value(&mut tool, option, tr(option.name()));
+                    }
+                    ui.color_edit_button_srgba(&mut self.drawing_color);
+                    ui.separator();
+                    ui.checkbox(&mut self.export_drawings, tr("Include in images"));
+                    if ui.button(tr("Clear Drawings")).clicked() {
+                        self.drawings.clear();
+                    }
+                    done = ui.button(tr("Done")).clicked();
+                });
+                self.drawing_tool = (!done).then_some(tool);
+            }
+            if let Some(mut edit_stone) = self.edit_stone {
+                let mut changed = false;
+                ui.horizontal(|ui| {
+                    ui.label(tr("Place:"));
+                    ui.radio_value(&mut edit_stone, Stone::Black, tr("Black"));
+                    ui.radio_value(&mut edit_stone, Stone::White, tr("White"));
+                    ui.radio_value(&mut edit_stone, Stone::Empty, tr("Erase"));
+                    ui.separator();
+                    ui.label(tr("To move:"));
+                    changed |= ui
+                        .radio_value(&mut self.start_player, Player::Black, tr("Black"))
+                        .changed();
+                    changed |= ui
+                        .radio_value(&mut self.start_player, Player::White, tr("White"))
+                        .changed();
+                    ui.separator();
+                    ui.label(tr("Captured black:"));
+                    changed |= ui
+                        .add(egui::DragValue::new(&mut self.setup_captures.0))
+                        .changed();
+                    ui.label(tr("white:"));
+                    changed |= ui
+                        .add(egui::DragValue::new(&mut self.setup_captures.1))
+                        .changed();
+                    if ui.button(tr("Clear Board")).clicked() {
+                        self.setup.clear();
+                        changed = true;
+                    }
+                    if ui.button(tr("Done")).clicked() {
+                        self.edit_stone = None;
+                    }
+                });
+                if self.edit_stone.is_some() {
+                    self.edit_stone = Some(edit_stone);
+                }
+                if changed {
+                    self.rebuild(&[]);
+                    self.resync_engine();
+                }
+            }
+            if let Some(score) = &score {
+                ui.horizontal(|ui| {
+                    ui.label(trf(
+                        "Counting ({} rules) - Black: {}, White: {} ({})",
+                        &[
+                            &tr(self.info.rules.name()),
+                            &i18n::number(score.black),
+                            &i18n::number(score.white),
+                            &score.result(),
+                        ],
+                    ));
+                    ui.label(tr("Click groups to mark them dead"));
+                    if self.can_mark_territory() {
+                        ui.label(tr("Click empty points to give them to Black, White or neither"));
+                        if !self.territory_marks.is_empty()
+                            && ui
+                                .button(tr("Reset Territory"))
+                                .on_hover_text(tr("Give every empty point the owner the count found"))
+                                .clicked()
+                        {
+                            self.territory_marks.clear();
+                        }
+                    }
+                    if self.info.rules == Rules::Japanese
+                        && let Some(dead) = &self.dead_stones
+                        && ui
+                            .button(tr("Show the Count"))
+                            .on_hover_text(tr("See the count done the traditional way: the prisoners fill the territories, which are then made into rectangles of ten"))
+                            .clicked()
+                    {
+                        self.counting = Some(counting::Counting::new(
+                            &self.board,
+                            dead,
+                            &score.territory,
+                            (self.captured_black, self.captured_white),
+                            self.info.komi,
+                        ));
+                    }
+                    if ui.button(tr("Accept")).clicked() {
+                        // The other side sees the marks; OGS and IGS count the game once both
+                        // accept
+                        if let Some(dead) = &self.dead_stones
+                            && self.lan.is_some()
+                        {
+                            let mut stones: Vec<_> = dead.iter().copied().collect();
+                            stones.sort_unstable();
+                            self.send_lan(&protocol::Message::Dead(stones));
+                        }
+                        if matches!(
+                            self.lan_peer,
+                            Some(lan::Peer::Ogs { .. } | lan::Peer::Igs { .. })
+                        ) {
+                            self.file_status =
+                                Some(tr("Waiting for the opponent to accept the count"));
+                        } else {
+                            self.info.result = score.result();
+                            self.game_over = true;
+                            self.dead_stones = None;
+                        }
+                    }
+                    if ui.button(tr("Resume")).clicked() {
+                        self.dead_stones = None;
+                    }
+                });
+            }
+            if let Some(tree) = &self.trial {
+                let tried = self.moves.len() as i64 - tree.moves_to(tree.current()).len() as i64;
+                // Replies are planned from the game's last move, with the opponent to play
+                let start = tree.current();
+                let to_move = tree
+                    .node(start)
+                    .played
+                    .map_or(self.start_player, |(player, _)| player.other());
+                let plannable = self.lan_correspondence
+                    && self.lan.is_some()
+                    && tree.node(start).children.is_empty()
+                    && self.lan_opponent == Some(to_move);
+                let branched = !tree.path(start).contains(&self.tree.current());
+                let mut plan = false;
+                let mut keep = false;
+                ui.horizontal(|ui| {
+                    ui.label(trf(
+                        "Trying out moves ({} from the game)",
+                        &[&format!("{:+}", tried)],
+                    ));
+                    if ui.button(tr("Discard")).clicked() {
+                        self.end_trial();
+                    }
+                    if self.lan.is_none() {
+                        ui.add(
+                            egui::TextEdit::singleline(&mut self.branch_name)
+                                .hint_text(tr("Branch name"))
+                                .desired_width(120.0),
+                        );
+                        if ui
+                            .add_enabled(
+                                branched && !self.branch_name.trim().is_empty(),
+                                egui::Button::new(tr("Keep as Branch")),
+                            )
+                            .on_hover_text(tr("Keep the moves tried out in the game record under this name, with their comments, and go back to the game"))
+                            .clicked()
+                        {
+                            keep = true;
+                        }
+                    }
+                    if plannable
+                        && ui
+                            .button(tr("Plan These Replies"))
+                            .on_hover_text(tr("The server plays the first reply you tried to each move of the opponent, as soon as they play it, and goes on down that line"))
+                            .clicked()
+                    {
+                        plan = true;
+                    }
+                });
+                if plan {
+                    self.send_plan(start);
+                }
+                if keep {
+                    self.keep_trial();
+                }
+            }
+            if self.lan.is_some() && !self.lan_plan.lines.is_empty() {
+                ui.horizontal(|ui| {
+                    ui.label(trf("{} replies planned", &[&self.lan_plan.replies()]));
+                    if ui.button(tr("Drop Plan")).clicked() {
+                        self.lan_plan = protocol::Plan::default();
+                        self.send_lan(&protocol::Message::Plan(protocol::Plan::default()));
+                    }
+                });
+            }
+            if let Some(point) = self.staged_move {
+                ui.horizontal(|ui| {
+                    ui.label(trf(
+                        "Play {}?",
+                        &[&self
+                            .new_game
+                            .coordinates
+                            .format_point(point, self.board_size)],
+                    ));
+                    if ui.button(tr("Confirm")).clicked() {
+                        self.click_point(point);
+                    }
+                    if ui.button(tr("Cancel")).clicked() {
+                        self.staged_move = None;
+                    }
+                });
+            }
+            if self.guess_record.is_some() {
+                ui.horizontal(|ui| {
+                    let session = &self.guess_session;
+                    ui.label(trf(
+                        "Guessing - exact: {}, close: {}, wrong: {} ({}%)",
+                        &[
+                            &session.exact,
+                            &session.nearby,
+                            &session.wrong,
+                            &format!("{:.0}", session.accuracy()),
+                        ],
+                    ));
+                    if let Some(feedback) = &self.guess_feedback {
+                        ui.separator();
+                        ui.label(feedback);
+                    }
+                    if ui.button(tr("Stop Guessing")).clicked() {
+                        self.guess_record = None;
+                        self.guess_feedback = None;
+                    }
+                });
+            }
+            if self.problem_editor {
+                self.show_problem_editor(ui);
+            }
+            if self.daily.is_some() {
+                let today = problems::today();
+                let streak = self
+                    .streak
+                    .get_or_insert_with(|| daily::Streak::load(&paths::daily_path()));
+                ui.horizontal(|ui| {
+                    ui.strong(tr("Puzzle of the day"));
+                    ui.label(trf(
+                        "{} days in a row, best {}",
+                        &[&streak.current(today), &streak.best],
+                    ));
+                });
+            } else if let Some(drill) = &self.drill {
+                let shape = drill.shape;
+                let mut next = false;
+                ui.horizontal(|ui| {
+                    ui.strong(trf("Shape drill: {}", &[&tr(shape.name())]));
+                    let (right, tried) = self.drill_score;
+                    if tried > 0 {
+                        ui.label(trf("{} of {} right", &[&right, &tried]));
+                    }
+                    let selected = self
+                        .drill_shape
+                        .map_or(tr("All shapes"), |shape| tr(shape.name()));
+                    egui::ComboBox::from_id_salt("drill_shape")
+                        .selected_text(selected)
+                        .show_ui(ui, |ui| {
+                            let all = tr("All shapes");
+                            next |= ui
+                                .selectable_value(&mut self.drill_shape, None, all)
+                                .clicked();
+                            for shape in shapes::Shape::ALL {
+                                let name = tr(shape.name());
+                                next |= ui
+                                    .selectable_value(&mut self.drill_shape, Some(shape), name)
+                                    .clicked();
+                            }
+                        });
+                    next |= ui.button(tr("Next Drill")).clicked();
+                });
+                if next {
+                    self.start_drill();
+                }
+            }
+            if let Some(problem) = &self.problem {
+                let solver = problem.solver;
+                if let Some(index) = self.lesson {
+                    ui.strong(trf(
+                        "Lesson {} of {}: {}",
+                        &[
+                            &(index + 1),
+                            &tutorial::LESSONS.len(),
+                            &tr(tutorial::LESSONS[index].title),
+                        ],
+                    ));
+                }
+                if !problem.description.is_empty() {
+                    ui.label(&problem.description);
+                }
+                ui.horizontal(|ui| {
+                    ui.label(trf("{} to play", &[&player_name(solver)]));
+                    if let Some(feedback) = &self.problem_feedback {
+                        ui.separator();
+                        ui.label(feedback);
+                    }
+                    if ui.button(tr("Try Again")).clicked() {
+                        self.restart_problem();
+                    }
+                    if ui.button(tr("Show Solution")).clicked() {
+                        self.show_solution();
+                    }
+                    if let Some(set) = self.reviewing
+                        && ui.button(tr("Next Problem")).clicked()
+                    {
+                        self.review_next(set);
+                    }
+                    if let Some(index) = self.lesson
+                        && index + 1 < tutorial::LESSONS.len()
+                        && ui.button(tr("Next Lesson")).clicked()
+                    {
+                        self.start_lesson(index + 1);
+                    }
+                    if ui.button(tr("Stop Solving")).clicked() {
+                        self.problem = None;
+                        self.problem_feedback = None;
+                        self.reviewing = None;
+                        self.lesson = None;
+                        self.drill = None;
+                        self.daily = None;
+                    }
+                });
+            }
+            if !fullscreen {
+                ui.horizontal(|ui| {
+                    ui.label(tr("Move:"));
+                    let input = ui.add(
+                        egui::TextEdit::singleline(&mut self.move_input)
+                            .desired_width(50.0)
+                            .hint_text("Q16"),
+                    );
+                    if input.lost_focus() && ui.input(|i| i.key_pressed(egui::Key::Enter)) {
+                        self.enter_typed_move();
+                        input.request_focus();
+                    }
+                    egui::ComboBox::from_id_salt("move_numbers")
+                        .selected_text(tr(self.move_numbers.name()))
+                        .show_ui(ui, |ui| {
+                            for numbers in MoveNumbers::ALL {
+                                ui.selectable_value(
+                                    &mut self.move_numbers,
+                                    numbers,
+                                    tr(numbers.name()),
+                                );
+                            }
+                        });
+                    ui.checkbox(&mut self.layout.show_coordinates, tr("Coordinates"));
+                    if ui
+                        .button(tr("Rotate"))
+                        .on_hover_text(tr("Turn the board a quarter clockwise"))
+                        .clicked()
+                    {
+                        self.view.rotate();
+                    }
+                    ui.checkbox(&mut self.view.mirrored, tr("Mirror"));
+                    ui.checkbox(&mut self.show_atari, tr("Atari warnings"));
+                    ui.checkbox(&mut self.show_estimate, tr("Estimate"));
+                    ui.checkbox(&mut self.show_influence, tr("Influence"));
+                    ui.checkbox(&mut self.show_area, tr("Area count"));
+                    if self.show_area {
+                        ui.label(tr("every"));
+                        ui.add(egui::DragValue::new(&mut self.area_interval).range(1..=50));
+                        ui.label(tr("moves"));
+                    }
+                    if self.zoom > 1.0 && ui.button(tr("Reset Zoom")).clicked() {
+                        self.zoom = 1.0;
+                        self.pan = egui::Vec2::ZERO;
+                    }
+                    ui.label(tr("Mark last"));
+                    ui.add(
+                        egui::DragValue::new(&mut self.marked_moves)
+                            .range(0..=consts::MAX_MARKED_MOVES),
+                    );
+                    if self.move_numbers == MoveNumbers::FromFigure
+                        && ui
+                            .button(tr("Start Figure Here"))
+                            .on_hover_text(tr("Number the moves after the current one from 1"))
+                            .clicked()
+                    {
+                        self.figure_start = self.moves.len();
+                    }
+                    if ui
+                        .selectable_label(self.selecting_region, tr("Select Region"))
+                        .clicked()
+                    {
+                        self.selecting_region = !self.selecting_region;
+                    }
+                    let can_search = self.region.is_some() && self.pattern_pending.is_none();
+                    if ui
+                        .add_enabled(can_search, egui::Button::new(tr("Search Pattern")))
+                        .clicked()
+                    {
+                        self.search_pattern(ui.ctx());
+                    }
+                    let can_count = self.region.is_some() && self.yose_pending.is_none();
+                    if ui
+                        .add_enabled(can_count, egui::Button::new(tr("Endgame Values")))
+                        .on_hover_text(tr("What each boundary play in the region is worth, and whether it is sente"))
+                        .clicked()
+                    {
+                        self.count_endgame(ui.ctx());
+                    }
+                    if let Some(status) = &self.file_status {
+                        ui.label(status);
+                    }
+                });
+            }
+            let current = self.tree.current();
+            let variations = self.tree.node(current).children.clone();
+            let on_main_line = self.tree.path(self.tree.main_line_end()).contains(&current);
+            if (variations.len() > 1 || !on_main_line) && self.guess_record.is_none() && !fullscreen
+            {
+                ui.horizontal(|ui| {
+                    if variations.len() > 1 {
+                        ui.label(tr("Variations:"));
+                        for (index, &child) in variations.iter().enumerate() {
+                            let Some((_, point)) = self.tree.node(child).played else {
+                                continue;
+                            };
+                            let vertex = point.map_or(tr("pass"), |point| {
+                                self.new_game
+                                    .coordinates
+                                    .format_point(point, self.board_size)
+                            });
+                            let selected = self.tree.next(current) == Some(child);
+                            if ui
+                                .selectable_label(
+                                    selected,
+                                    format!("{} {}", variation_letter(index), vertex),
+                                )
+                                .clicked()
+                                && self.can_navigate()
+                            {
+                                self.go_to_node(child);
+                            }
+                        }
+                    }
+                    if !on_main_line && ui.button(tr("Make Main Line")).clicked() {
+                        self.tree.promote(current);
+                    }
+                });
+            }
+            if !fullscreen {
+                ui.separator();
+            }
+
+            // Fit the board into the space left below the controls and center it there,
+            // keeping room for the move slider and replay controls underneath
+            let total = self.tree.line().len() - 1;
+            let scrubbing = total > 0 && self.guess_record.is_none();
+            let spacing = ui.spacing();
+            let reserved = if scrubbing {
+                2.0 * (spacing.interact_size.y + spacing.item_spacing.y)
+            } else {
+                0.0
+            };
+            let (response, painter) = ui.allocate_painter(
+                ui.available_size() - egui::vec2(0.0, reserved),
+                egui::Sense::click_and_drag(),
+            );
+            let lines = self.board_size as f32 + 1.0;
+            let fitted = (response.rect.width().min(response.rect.height()) / lines)
+                .max(consts::MIN_CELL_SIZE);
+
+            // Zoom with the wheel or a pinch, keeping the point under the cursor or the fingers in place
+            let touch = ui
+                .input(|i| i.multi_touch())
+                .filter(|_| response.contains_pointer());
+            if let Some(pointer) = touch.map(|touch| touch.center_pos).or(response.hover_pos()) {
+                let factor = ui.input(|i| i.zoom_delta() * (i.raw_scroll_delta.y / 200.0).exp());
+                let zoom = (self.zoom * factor).clamp(1.0, consts::MAX_ZOOM);
+                if zoom != self.zoom {
+                    let from_center = pointer - response.rect.center() - self.pan;
+                    self.pan += from_center * (1.0 - zoom / self.zoom);
+                    self.zoom = zoom;
+                }
+            }
+            // Pan with two fingers, the middle or right button, or by dragging when not selecting a region
+            if let Some(touch) = touch {
+                self.pan += touch.translation_delta;
+            } else if response.dragged_by(egui::PointerButton::Middle)
+                || response.dragged_by(egui::PointerButton::Secondary)
+                || !self.selecting_region
+                    && self.drawing_tool.is_none()
+                    && response.dragged_by(egui::PointerButton::Primary)
+            {
+                self.pan += response.drag_delta();
+            }
+            let cell = fitted * self.zoom;
+            let slack = (cell * lines - fitted * lines) * 0.5;
+            self.pan = self
+                .pan
+                .clamp(egui::Vec2::splat(-slack), egui::Vec2::splat(slack));
+
+            let radius = cell * consts::STONE_RADIUS_RATIO;
+            let board_rect = egui::Rect::from_center_size(
+                response.rect.center() + self.pan,
+                egui::Vec2::splat(cell * lines),
+            );
+            // The grid sits one cell in from every edge, which leaves room for the coordinates
+            let top_left = board_rect.min + egui::Vec2::splat(cell);
+            self.board_rect = board_rect.intersect(response.rect);
+            let (view, size) = (self.view, self.board_size);
+            let screen_pos = move |point: (usize, usize)| {
+                let (row, col) = view.to_screen(point, size);
+                top_left + egui::Vec2::new(col as f32 * cell, row as f32 * cell)
+            };
+            self.hovered_point = response
+                .hover_pos()
+                .and_then(|pos| self.point_at(pos, top_left, cell));
+
+            // Screen readers get the last move as a live region, plus the point under the cursor
+            let mut description = trf(
+                "Go board, {} by {}. {}",
+                &[
+                    &self.board_size,
+                    &self.board_size,
+                    &self.describe_position(),
+                ],
+            );
+            if let Some(point) = self.board_cursor.or(self.hovered_point) {
+                description += &format!(". {}", self.describe_point(point));
+            }
+            response.widget_info(|| {
+                egui::WidgetInfo::labeled(egui::WidgetType::Other, true, &description)
+            });
+            ctx.accesskit_node_builder(response.id, |node| {
+                node.set_live(egui::accesskit::Live::Polite);
+            });
+
+            // Draw grid lines
+            let board_theme = self.new_game.board_theme;
+            let high_contrast = self.new_game.high_contrast;
+            let marker_shape = self.new_game.marker_shape;
+            // Markup is drawn larger while presenting so it reads from the back of a room
+            let markup_scale = if self.presentation.is_some() {
+                consts::PRESENTATION_SCALE
+            } else {
+                1.0
+            };
+            let marker_width = markup_scale * if high_contrast { 3.0 } else { 2.0 };
+            let full_uv = egui::Rect::from_min_max(egui::pos2(0.0, 0.0), egui::pos2(1.0, 1.0));
+            let skin = self.skin.as_ref();
+            match skin.and_then(|skin| skin.board.as_ref()) {
+                Some(texture) => {
+                    painter.image(texture.id(), board_rect, full_uv, egui::Color32::WHITE);
+                }
+                None => {
+                    painter.rect_filled(board_rect, 4.0, board_theme.background());
+                }
+            }
+            let line_color = board_theme.lines();
+            // Star points (handicap points) go with the lines
+            let stars = self.star_points().iter().map(|&point| screen_pos(point)).collect();
+            self.grid
+                .paint(&painter, top_left, cell, self.board_size, stars, line_color);
+
+            // On a torus the lines run on over the rim, where the stones of the opposite edge
+            // show again, cut off and faded
+            if self.board.wraps() {
+                let rim = painter.with_clip_rect(board_rect.intersect(painter.clip_rect()));
+                let stroke = egui::Stroke::new(1.0, line_color);
+                let far = (size - 1) as f32 * cell;
+                for i in 0..size {
+                    let along = i as f32 * cell;
+                    for (from, to) in [
+                        (egui::vec2(-cell, along), egui::vec2(0.0, along)),
+                        (egui::vec2(far, along), egui::vec2(far + cell, along)),
+                        (egui::vec2(along, -cell), egui::vec2(along, 0.0)),
+                        (egui::vec2(along, far), egui::vec2(along, far + cell)),
+                    ] {
+                        rim.line_segment([top_left + from, top_left + to], stroke);
+                    }
+                }
+                let around = (-1..=size as i32).flat_map(|row| {
+                    (-1..=size as i32)
+                        .map(move |col| (row, col))
+                        .filter(|&(row, col)| row.min(col) < 0 || row.max(col) == size as i32)
+                });
+                let hidden = self.stones_hidden();
+                for (row, col) in around.filter(|_| !hidden) {
+                    let wrapped = (
+                        row.rem_euclid(size as i32) as usize,
+                        col.rem_euclid(size as i32) as usize,
+                    );
+                    let (board_row, board_col) = view.to_board(wrapped, size);
+                    if self.is_secret((board_row, board_col)) {
+                        continue;
+                    }
+                    let color = match self.board[board_row][board_col] {
+                        Stone::Black => egui::Color32::BLACK,
+                        Stone::White => egui::Color32::WHITE,
+                        Stone::Empty => continue,
+                    };
+                    let pos = top_left + egui::vec2(col as f32 * cell, row as f32 * cell);
+                    rim.circle_filled(pos, radius, color.gamma_multiply(0.5));
+                }
+            }
+
+            if self.layout.show_coordinates {
+                let style = self.new_game.coordinates;
+                let font = egui::FontId::proportional(radius * 0.9 * markup_scale);
+                let far = (self.board_size - 1) as f32 * cell;
+                let offset = cell * 0.65;
+                // Labels follow the board when it is turned, so the top can show row numbers
+                for i in 0..self.board_size {
+                    let along = i as f32 * cell;
+                    let (top, side) = (view.to_board((0, i), size), view.to_board((i, 0), size));
+                    let (column, row) = if view.is_sideways() {
+                        (
+                            style.row_label(top.0, size),
+                            style.column_label(side.1, size),
+                        )
+                    } else {
+                        (
+                            style.column_label(top.1, size),
+                            style.row_label(side.0, size),
+                        )
+                    };
+                    for (pos, text) in [
+                        (egui::vec2(along, -offset), &column),
+                        (egui::vec2(along, far + offset), &column),
+                        (egui::vec2(-offset, along), &row),
+                        (egui::vec2(far + offset, along), &row),
+                    ] {
+                        painter.text(
+                            top_left + pos,
+                            egui::Align2::CENTER_CENTER,
+                            text,
+                            font.clone(),
+                            line_color,
+                        );
+                    }
+                }
+            }
+
+            // Draw stones
+            let flashing = self.move_started.elapsed() < consts::BLIND_FLASH;
+            if self.stones_hidden() && flashing {
+                ui.ctx().request_repaint_after(consts::BLIND_FLASH);
+            }
+            for row in 0..self.board_size {
+                for col in 0..self.board_size {
+                    let stone = self.board[row][col];
+                    let hidden = self.stones_hidden()
+                        && !(flashing && self.last_move == Some((row, col)))
+                        || self.is_secret((row, col));
+                    if stone != Stone::Empty && !hidden {
+                        let pos = screen_pos((row, col));
+                        let (stone_color, texture) = match stone {
+                            Stone::Black => (
+                                egui::Color32::BLACK,
+                                skin.and_then(|skin| skin.black.as_ref()),
+                            ),
+                            Stone::White => (
+                                egui::Color32::WHITE,
+                                skin.and_then(|skin| skin.white.as_ref()),
+                            ),
+                            Stone::Empty => continue,
+                        };
+                        if let Some(texture) = texture {
+                            painter.image(
+                                texture.id(),
+                                egui::Rect::from_center_size(pos, egui::Vec2::splat(radius * 2.0)),
+                                full_uv,
+                                egui::Color32::WHITE,
+                            );
+                        } else {
+                            // Draw stone shadow
+                            painter.circle_filled(
+                                pos + egui::Vec2::new(1.0, 1.0),
+                                radius,
+                                egui::Color32::from_rgba_premultiplied(0, 0, 0, 100),
+                            );
+                            // Draw stone
+                            painter.circle_filled(pos, radius, stone_color);
+                            // Draw stone border
+                            painter.circle_stroke(
+                                pos,
+                                radius,
+                                egui::Stroke::new(1.0, egui::Color32::DARK_GRAY),
+                            );
+                        }
+                        // Highlight last move
+                        if let Some((last_row, last_col)) = self.last_move
+                            && row == last_row
+                            && col == last_col
+                            && self.marked_moves > 0
+                        {
+                            let stroke = egui::Stroke::new(
+                                marker_width,
+                                theme::marker_color(stone, high_contrast),
+                            );
+                            // The default ring goes around the stone, other markers sit on it
+                            if marker_shape == MarkerShape::Circle && !high_contrast {
+                                painter.circle_stroke(pos, radius + 3.0, stroke);
+                            } else {
+                                marker_shape.paint(&painter, pos, radius * 0.6, stroke);
+                            }
+                        }
+                    }
+                }
+            }
+
+            // Markup of the current node, in the opposite color of what is under it
+            for (point, mark) in &self.tree.node(self.tree.current()).markup {
+                let pos = screen_pos(*point);
+                let stone = self.board[point.0][point.1];
+                if stone == Stone::Empty && matches!(mark, markup::Mark::Label(_)) {
+                    // Labels cover the grid lines so they stay legible
+                    painter.circle_filled(pos, radius * 0.7, board_theme.background());
+                }
+                let color = theme::marker_color(stone, true);
+                mark.paint(
+                    &painter,
+                    pos,
+                    radius * markup_scale,
+                    egui::Stroke::new(marker_width, color),
+                );
+            }
+
+            // Heat map of Bouzy influence, stronger colors for stronger influence
+            if self.show_influence {
+                let influence = estimate::influence(&self.board);
+                for (row, values) in influence.iter().enumerate() {
+                    for (col, &value) in values.iter().enumerate() {
+                        let alpha = (value.unsigned_abs().min(64) * 2) as u8;
+                        let color = if value > 0 {
+                            egui::Color32::from_rgba_unmultiplied(40, 40, 200, alpha)
+                        } else {
+                            egui::Color32::from_rgba_unmultiplied(230, 120, 30, alpha)
+                        };
+                        let pos = screen_pos((row, col));
+                        painter.rect_filled(
+                            egui::Rect::from_center_size(pos, egui::Vec2::splat(cell)),
+                            0.0,
+                            color,
+                        );
+                    }
+                }
+            }
+
+            // Shade the empty points by their expected owner
+            if let Some(estimate) = &estimate {
+                for (row, owners) in estimate.owner.iter().enumerate() {
+                    for (col, &owner) in owners.iter().enumerate() {
+                        if self.board[row][col] != Stone::Empty {
+                            continue;
+                        }
+                        let Some(color) = theme::territory_color(owner, high_contrast) else {
+                            continue;
+                        };
+                        let pos = screen_pos((row, col));
+                        painter.rect_filled(
+                            egui::Rect::from_center_size(pos, egui::Vec2::splat(radius)),
+                            0.0,
+                            color,
+                        );
+                    }
+                }
+            }
+
+            // Tint the stones of the player to move whose groups are down to one liberty
+            if self.show_atari {
+                let friendly = self.current_player.to_stone();
+                for row in 0..self.board_size {
+                    for col in 0..self.board_size {
+                        if self.board[row][col] == friendly
+                            && self.board.liberties(row, col).len() == 1
+                        {
+                            let pos = screen_pos((row, col));
+                            painter.circle_filled(
+                                pos,
+                                radius,
+                                egui::Color32::from_rgba_unmultiplied(220, 40, 40, 90),
+                            );
+                        }
+                    }
+                }
+            }
+
+            // Mark the point that cannot be retaken this turn
+            if let Some((row, col)) = self.board.ko_point() {
+                let pos = screen_pos((row, col));
+                painter.rect_stroke(
+                    egui::Rect::from_center_size(pos, egui::Vec2::splat(radius)),
+                    0.0,
+                    egui::Stroke::new(2.0, line_color),
+                    egui::StrokeKind::Middle,
+                );
+            }
+
+            // Earlier moves get progressively fainter markers
+            for (age, &(_, point)) in self
+                .moves
+                .iter()
+                .rev()
+                .enumerate()
+                .take(self.marked_moves)
+                .skip(1)
+            {
+                let Some((row, col)) = point else {
+                    continue;
+                };
+                if self.board[row][col] == Stone::Empty {
+                    continue;
+                }
+                let pos = screen_pos((row, col));
+                let alpha = 255 - 200 * age / self.marked_moves;
+                let color = theme::marker_color(self.board[row][col], high_contrast);
+                marker_shape.paint(
+                    &painter,
+                    pos,
+                    radius * 0.5,
+                    egui::Stroke::new(marker_width, color.gamma_multiply(alpha as f32 / 255.0)),
+                );
+            }
+
+            // Number the stones still on the board by the move that placed them
+            let first_numbered = match self.move_numbers {
+                MoveNumbers::Off => None,
+                MoveNumbers::All => Some(0),
+                MoveNumbers::LastTen => Some(self.moves.len().saturating_sub(10)),
+                MoveNumbers::FromFigure => Some(self.figure_start),
+            };
+            if let Some(first_numbered) = first_numbered {
+                let mut numbers = std::collections::HashMap::new();
+                for (index, &(_, point)) in self.moves.iter().enumerate() {
+                    if let Some(point) = point {
+                        numbers.insert(point, index);
+                    }
+                }
+                for ((row, col), index) in numbers {
+                    let stone = self.board[row][col];
+                    if index < first_numbered || stone == Stone::Empty {
+                        continue;
+                    }
+                    let number = match self.move_numbers {
+                        MoveNumbers::FromFigure => index + 1 - self.figure_start,
+                        _ => index + 1,
+                    };
+                    let text_color = if stone == Stone::Black {
+                        egui::Color32::WHITE
+                    } else {
+                        egui::Color32::BLACK
+                    };
+                    let pos = screen_pos((row, col));
+                    painter.text(
+                        pos,
+                        egui::Align2::CENTER_CENTER,
+                        number.to_string(),
+                        egui::FontId::proportional(radius * 0.9),
+                        text_color,
+                    );
+                }
+            }
+
+            // Select a region by dragging across the board
+            if self.selecting_region {
+                let pointer_point = response
+                    .interact_pointer_pos()
+                    .and_then(|pos| self.point_at(pos, top_left, cell));
+                if response.drag_started() {
+                    self.region_start = pointer_point;
+                }
+                if let (Some(start), Some(point)) = (self.region_start, pointer_point)
+                    && response.dragged_by(egui::PointerButton::Primary)
+                {
+                    self.region = Some((start, point));
+                }
+            }
+            if let Some((from, to)) = self.region {
+                let corner = |(row, col): (usize, usize)| screen_pos((row, col));
+                let rect = egui::Rect::from_two_pos(corner(from), corner(to)).expand(cell * 0.5);
+                painter.rect_filled(
+                    rect,
+                    0.0,
+                    egui::Color32::from_rgba_unmultiplied(60, 120, 220, 40),
+                );
+            }
+
+            // Mark how often each candidate was played in the fuseki explorer
+            if self.show_fuseki
+                && let Some((_, stats)) = &self.fuseki_stats
+            {
+                for candidate in &stats.candidates {
+                    if let Some((row, col)) = candidate.point {
+                        let pos = screen_pos((row, col));
+                        painter.text(
+                            pos,
+                            egui::Align2::CENTER_CENTER,
+                            candidate.games.to_string(),
+                            egui::FontId::proportional(radius * 1.1),
+                            egui::Color32::from_rgb(30, 80, 200),
+                        );
+                    }
+                }
+            }
+
+            // Mark the value of each endgame play
+            if let Some((_, plays)) = &self.yose {
+                for play in plays {
+                    painter.text(
+                        screen_pos(play.point),
+                        egui::Align2::CENTER_CENTER,
+                        i18n::number(play.value),
+                        egui::FontId::proportional(radius * 1.1),
+                        egui::Color32::from_rgb(200, 60, 30),
+                    );
+                }
+            }
+
+            // Mark continuations found by the pattern search
+            if let Some(result) = &self.pattern_result {
+                for continuation in &result.continuations {
+                    if let Some((row, col)) = continuation.point {
+                        let pos = screen_pos((row, col));
+                        painter.text(
+                            pos,
+                            egui::Align2::CENTER_CENTER,
+                            continuation.count.to_string(),
+                            egui::FontId::proportional(radius * 1.1),
+                            egui::Color32::from_rgb(30, 80, 200),
+                        );
+                    }
+                }
+            }
+
+            // Label the moves that branch from here
+            let variations = &self.tree.node(self.tree.current()).children;
+            if variations.len() > 1 && self.guess_record.is_none() {
+                for (index, &child) in variations.iter().enumerate() {
+                    if let Some((_, Some((row, col)))) = self.tree.node(child).played {
+                        let pos = screen_pos((row, col));
+                        painter.text(
+                            pos,
+                            egui::Align2::CENTER_CENTER,
+                            variation_letter(index),
+                            egui::FontId::proportional(radius * 1.25 * markup_scale),
+                            egui::Color32::from_rgb(200, 40, 40),
+                        );
+                    }
+                }
+            }
+
+            // Paint territory, mark dame and cross out dead stones while counting
+            if let Some(score) = &score {
+                let dead = self.dead_stones.as_ref();
+                for (row, owners) in score.territory.iter().enumerate() {
+                    for (col, &owner) in owners.iter().enumerate() {
+                        let pos = screen_pos((row, col));
+                        let area = egui::Rect::from_center_size(pos, egui::Vec2::splat(cell));
+                        match owner {
+                            Stone::Black | Stone::White => {
+                                if let Some(color) = theme::territory_color(owner, high_contrast) {
+                                    painter.rect_filled(area, 0.0, color);
+                                }
+                            }
+                            Stone::Empty if self.board[row][col] == Stone::Empty => {
+                                painter.circle_filled(pos, 3.0, egui::Color32::GRAY);
+                            }
+                            Stone::Empty => {}
+                        }
+                        // Points given by hand are outlined
+                        if self.territory_marks.contains_key(&(row, col)) {
+                            painter.rect_stroke(
+                                area.shrink(cell * 0.2),
+                                0.0,
+                                egui::Stroke::new(1.0, egui::Color32::GRAY),
+                                egui::StrokeKind::Inside,
+                            );
+                        }
+                        if dead.is_some_and(|dead| dead.contains(&(row, col))) {
+                            let arm = radius * 0.6;
+                            let cross = egui::Stroke::new(2.0, egui::Color32::RED);
+                            painter.line_segment(
+                                [
+                                    pos + egui::Vec2::new(-arm, -arm),
+                                    pos + egui::Vec2::new(arm, arm),
+                                ],
+                                cross,
+                            );
+                            painter.line_segment(
+                                [
+                                    pos + egui::Vec2::new(-arm, arm),
+                                    pos + egui::Vec2::new(arm, -arm),
+                                ],
+                                cross,
+                            );
+                        }
+                    }
+                }
+            }
+
+            if self.show_debug && self.can_debug() {
+                let groups = debug::groups(&self.board);
+                let facts = debug::facts(&self.board, &self.moves, &groups);
+                debug::paint(&painter, response.rect, &groups, &facts, screen_pos, radius);
+            }
+
+            // Handle clicks; a long press shows the group under the finger instead
+            if response.long_touched()
+                && let Some(pos) = response.interact_pointer_pos()
+                && let Some((row, col)) = self.point_at(pos, top_left, cell)
+            {
+                self.group_info = (self.board[row][col] != Stone::Empty).then_some((row, col));
+                self.long_pressed = true;
+            }
+            // Drags draw while the drawing layer is on, and clicks are left alone
+            if let Some(tool) = self.drawing_tool
+                && let Some(pos) = response.interact_pointer_pos()
+            {
+                let point = ((pos - top_left) / cell).to_pos2();
+                if response.drag_started_by(egui::PointerButton::Primary) {
+                    self.drawings
+                        .push(drawing::Stroke::new(tool, self.drawing_color, point));
+                } else if response.dragged_by(egui::PointerButton::Primary)
+                    && let Some(stroke) = self.drawings.last_mut()
+                {
+                    stroke.extend(point);
+                }
+            }
+            let clicked = response.clicked() && !self.long_pressed && self.drawing_tool.is_none();
+            if clicked {
+                self.group_info = None;
+            }
+            if !ui.input(|i| i.pointer.any_down()) {
+                self.long_pressed = false;
+            }
+            // Touch screens have no hover preview, so a tap only stages the move
+            let confirm = self.new_game.confirm_moves || ui.input(|i| i.has_touch_screen());
+            if let Some(tool) = self.markup_tool {
+                if clicked
+                    && let Some(pos) = response.interact_pointer_pos()
+                    && let Some(point) = self.point_at(pos, top_left, cell)
+                {
+                    self.mark_point(point, tool);
+                }
+            } else if self.presentation.is_some() {
+                if clicked
+                    && let Some(pos) = response.interact_pointer_pos()
+                    && let Some(point) = self.point_at(pos, top_left, cell)
+                {
+                    self.present_point(point);
+                }
+            } else if let Some(stone) = self.edit_stone {
+                if clicked
+                    && let Some(pos) = response.interact_pointer_pos()
+                    && let Some(point) = self.point_at(pos, top_left, cell)
+                {
+                    self.edit_point(point, stone);
+                }
+            } else if self.dead_stones.is_some() {
+                if clicked
+                    && let Some(pos) = response.interact_pointer_pos()
+                    && let Some(point) = self.point_at(pos, top_left, cell)
+                {
+                    if self.board[point.0][point.1] == Stone::Empty {
+                        self.cycle_territory(point);
+                    } else {
+                        self.toggle_dead(point);
+                    }
+                }
+            } else if !self.selecting_region
+                && self.human_to_move()
+                && clicked
+                && let Some(pos) = response.interact_pointer_pos()
+                && let Some(point) = self.point_at(pos, top_left, cell)
+            {
+                if confirm && self.staged_move != Some(point) {
+                    if self.is_valid_move(point.0, point.1) || self.is_secret(point) {
+                        self.staged_move = Some(point);
+                    }
+                } else {
+                    self.click_point(point);
+                }
+            }
+
+            // Draw the staged move as a ghost stone
+            if let Some((row, col)) = self.staged_move {
+                let pos = screen_pos((row, col));
+                let ghost_color = match self.current_player {
+                    Player::Black => egui::Color32::from_rgba_premultiplied(0, 0, 0, 150),
+                    Player::White => egui::Color32::from_rgba_premultiplied(255, 255, 255, 180),
+                };
+                painter.circle_filled(pos, radius, ghost_color);
+                painter.circle_stroke(
+                    pos,
+                    radius,
+                    egui::Stroke::new(2.0, egui::Color32::from_rgb(30, 80, 200)),
+                );
+            }
+
+            if let Some((row, col)) = self.board_cursor {
+                let pos = screen_pos((row, col));
+                painter.rect_stroke(
+                    egui::Rect::from_center_size(pos, egui::Vec2::splat(cell * 0.9)),
+                    2.0,
+                    egui::Stroke::new(marker_width, egui::Color32::from_rgb(30, 80, 200)),
+                    egui::StrokeKind::Middle,
+                );
+            }
+
+            // Freehand drawings go over everything, and are left out of images if asked
+            if !self.export_requested || self.export_drawings {
+                for stroke in &self.drawings {
+                    stroke.paint(&painter, top_left, cell);
+                }
+            }
+
+            // Show the liberties of the hovered or long-pressed group
+            let group = self.group_info.or_else(|| {
+                response
+                    .hover_pos()
+                    .and_then(|pos| self.point_at(pos, top_left, cell))
+            });
+            if let Some((row, col)) = group
+                && self.board[row][col] != Stone::Empty
+            {
+                let liberties = self.board.liberties(row, col);
+                for &(row, col) in &liberties {
+                    let pos = screen_pos((row, col));
+                    painter.circle_stroke(
+                        pos,
+                        radius * 0.4,
+                        egui::Stroke::new(2.0, egui::Color32::from_rgb(30, 80, 200)),
+                    );
+                }
+                let text = match liberties.len() {
+                    1 => tr("1 liberty"),
+                    count => trf("{} liberties", &[&count]),
+                };
+                if self.group_info.is_some() {
+                    let stones = self.board.get_group(row, col, self.board[row][col]).len();
+                    let galley = painter.layout_no_wrap(
+                        trf("Group of {}, {}", &[&stones, &text]),
+                        egui::TextStyle::Body.resolve(ui.style()),
+                        egui::Color32::WHITE,
+                    );
+                    let pos = screen_pos((row, col));
+                    let rect = egui::Align2::CENTER_BOTTOM
+                        .anchor_size(pos - egui::vec2(0.0, radius * 1.5), galley.size())
+                        .expand(4.0);
+                    painter.rect_filled(rect, 4.0, egui::Color32::from_black_alpha(200));
+                    painter.galley(
+                        rect.min + egui::Vec2::splat(4.0),
+                        galley,
+                        egui::Color32::WHITE,
+                    );
+                } else {
+                    response.clone().on_hover_text_at_pointer(text);
+                }
+            }
+
+            // Show move validity hint, or why the hovered move cannot be played
+            if let Some(hover_pos) = response.hover_pos()
+                && let Some((row, col)) = self.point_at(hover_pos, top_left, cell)
+                && !self.game_over
+                && self.edit_stone.is_none()
+                && self.presentation.is_none()
+            {
+                match self.board.check_move(row, col, self.current_player) {
+                    Ok(()) => {
+                        let pos = screen_pos((row, col));
+                        let preview_color = match self.current_player {
+                            Player::Black => egui::Color32::from_rgba_premultiplied(0, 0, 0, 100),
+                            Player::White => {
+                                egui::Color32::from_rgba_premultiplied(255, 255, 255, 150)
+                            }
+                        };
+                        painter.circle_filled(pos, radius * 0.7, preview_color);
+                        if high_contrast {
+                            painter.circle_stroke(
+                                pos,
+                                radius * 0.7,
+                                egui::Stroke::new(
+                                    marker_width,
+                                    theme::marker_color(self.current_player.to_stone(), true),
+                                ),
+                            );
+                        }
+                        // Cross out the stones this move would capture
+                        let cross = egui::Stroke::new(2.0, egui::Color32::RED);
+                        let arm = radius * 0.6;
+                        for (row, col) in self.board.captured_by(row, col, self.current_player) {
+                            let pos = screen_pos((row, col));
+                            painter.line_segment(
+                                [
+                                    pos + egui::Vec2::new(-arm, -arm),
+                                    pos + egui::Vec2::new(arm, arm),
+                                ],
+                                cross,
+                            );
+                            painter.line_segment(
+                                [
+                                    pos + egui::Vec2::new(-arm, arm),
+                                    pos + egui::Vec2::new(arm, -arm),
+                                ],
+                                cross,
+                            );
+                        }
+                    }
+                    // Hovering a stone shows its liberties instead
+                    Err(board::MoveError::Occupied) => {}
+                    Err(err) => {
+                        response
+                            .clone()
+                            .on_hover_text_at_pointer(tr(&err.to_string()));
+                    }
+                }
+            }
+
+            // Scrub through the game
+            if scrubbing {
+                let mut move_number = self.moves.len();
+                ui.spacing_mut().slider_width = board_rect.width() - 80.0;
+                let slider = egui::Slider::new(&mut move_number, 0..=total).text(tr("Move"));
+                if ui
+                    .add_enabled(self.engine_pending.is_none(), slider)
+                    .changed()
+                {
+                    self.go_to_move(move_number);
+                }
+
+                // Replay the game on its own
+                ui.horizontal(|ui| {
+                    let label = tr(if self.auto_replay { "Pause" } else { "Play" });
+                    if ui.button(label).clicked() {
+                        self.auto_replay = !self.auto_replay;
+                        self.replay_step_at = ui.input(|i| i.time);
+                    }
+                    if ui.button(tr("Step")).clicked() {
+                        self.go_to_move(self.moves.len() + 1);
+                    }
+                    ui.label(tr("Seconds per move"));
+                    ui.add(
+                        egui::DragValue::new(&mut self.replay_interval)
+                            .range(0.1..=10.0)
+                            .speed(0.1),
+                    );
+                });
+            }
+        });
+        self.step_replay(ctx);
+    }
+
+    fn step_replay(&mut self, ctx: &egui::Context) {
+        if !self.auto_replay {
+            return;
+        }
+        if !self.can_redo() {
+            self.auto_replay = false;
+            return;
+        }
+        let now = ctx.input(|i| i.time);
+        let due = self.replay_step_at + self.replay_interval as f64;
+        if now >= due {
+            self.go_to_move(self.moves.len() + 1);
+            self.replay_step_at = now;
+        }
+        ctx.request_repaint_after(std::time::Duration::from_secs_f64((due - now).max(0.0)));
+    }
+}
+
+// Analysis windows float over the board, or get their own native window once popped out
+fn show_analysis_window(
+    ctx: &egui::Context,
+    title: &str,
+    detached: &mut bool,
+    open: &mut bool,
+    mut add_contents: impl FnMut(&mut egui::Ui),
+) {
+    if !*detached {
+        egui::Window::new(title).open(open).show(ctx, |ui| {
+            if ui.button(tr("Pop Out")).clicked() {
+                *detached = true;
+            }
+            add_contents(ui);
+        });
+        return;
+    }
+    let builder = egui::ViewportBuilder::default()
+        .with_title(title)
+        .with_inner_size(consts::ANALYSIS_WINDOW_SIZE);
+    ctx.show_viewport_immediate(
+        egui::ViewportId::from_hash_of(title),
+        builder,
+        |ctx, class| {
+            let mut contents = |ui: &mut egui::Ui| {
+                if ui.button(tr("Dock")).clicked() {
+                    *detached = false;
+                }
+                add_contents(ui);
+            };
+            // Backends without multiple viewports draw it inside the main window instead
+            if class == egui::ViewportClass::Embedded {
+                egui::Window::new(title).open(open).show(ctx, contents);
+            } else {
+                egui::CentralPanel::default().show(ctx, |ui| contents(ui));
+                if ctx.input(|i| i.viewport().close_requested()) {
+                    *open = false;
+                }
+            }
+        },
+    );
+}
+
+// What a game picked from a list is opened for
+enum Opening {
+    Review,
+    Guess,
+    Solve,
+}
+
+// The name of a player of pair go left unnamed in the new game form, such as "Black 2"
+fn seat_name(seat: usize) -> String {
+    let player = if seat.is_multiple_of(2) {
+        Player::Black
+    } else {
+        Player::White
+    };
+    format!("{} {}", player_name(player), seat / 2 + 1)
+}
+
+fn player_name(player: Player) -> String {
+    tr(match player {
+        Player::Black => "Black",
+        Player::White => "White",
+    })
+}
+
+// The moves an undo in a network game takes back
+fn undo_moves(count: usize) -> String {
+    if count == 1 {
+        tr("the last move")
+    } else {
+        tr("the last two moves")
+    }
+}
+
+// The SGF properties holding the time and the periods or stones `player` has left
+fn time_idents(player: Player) -> (&'static str, &'static str) {
+    match player {
+        Player::Black => ("BL", "OB"),
+        Player::White => ("WL", "OW"),
+    }
+}
+
+// Who leads the games between `first` and `second`, how the last went and the handicap of the
+// last one
+fn head_to_head_summary(first: &str, second: &str, record: &profiles::HeadToHead) -> String {
+    let mut parts = vec![if record.draws > 0 {
+        trf(
+            "{} {} – {} {}, {} draws",
+            &[
+                &first.trim(),
+                &record.wins,
+                &record.losses,
+                &second.trim(),
+                &record.draws,
+            ],
+        )
+    } else {
+        trf(
+            "{} {} – {} {}",
+            &[&first.trim(), &record.wins, &record.losses, &second.trim()],
+        )
+    }];
+    let last: Vec<String> = record
+        .meetings
+        .iter()
+        .rev()
+        .take(consts::HEAD_TO_HEAD_SHOWN)
+        .map(|meeting| match meeting.winner() {
+            Some(winner) => format!("{} ({})", winner, meeting.result),
+            None => tr("Draw"),
+        })
+        .collect();
+    parts.push(trf("latest {}", &[&last.join(", ")]));
+    if let Some(meeting) = record.meetings.last() {
+        parts.push(if meeting.handicap >= 2 {
+            trf(
+                "last game {} took Black with {} stones",
+                &[&meeting.black, &meeting.handicap],
+            )
+        } else {
+            trf(
+                "last game even with {} komi, {} as Black",
+                &[&meeting.komi, &meeting.black],
+            )
+        });
+    }
+    parts.join("; ")
+}
+
+// Picks one of `profiles` by name, or none; returns the profile picked this frame
+fn profile_picker<'a>(
+    ui: &mut egui::Ui,
+    id: &str,
+    chosen: &mut String,
+    profiles: &'a profiles::Profiles,
+) -> Option<&'a profiles::Profile> {
+    let mut picked = None;
+    let selected = match profiles.find(chosen) {
+        Some(profile) => profile.label(),
+        None => tr("None"),
+    };
+    egui::ComboBox::from_id_salt(id)
+        .selected_text(selected)
+        .show_ui(ui, |ui| {
+            if ui.selectable_label(chosen.is_empty(), tr("None")).clicked() {
+                chosen.clear();
+            }
+            for profile in &profiles.profiles {
+                if !profile.name.trim().is_empty()
+                    && ui
+                        .selectable_label(profile.name.trim() == chosen.trim(), profile.label())
+                        .clicked()
+                {
+                    *chosen = profile.name.trim().to_string();
+                    picked = Some(profile);
+                }
+            }
+        });
+    picked
+}
+
+// Picks the kind of overtime and its amounts
+fn overtime_editor(ui: &mut egui::Ui, overtime: &mut clock::Overtime) {
+    ui.horizontal(|ui| {
+        egui::ComboBox::from_id_salt("overtime")
+            .selected_text(tr(overtime.name()))
+            .show_ui(ui, |ui| {
+                for option in clock::Overtime::ALL {
+                    if ui
+                        .selectable_label(overtime.same_kind(option), tr(option.name()))
+                        .clicked()
+                        && !overtime.same_kind(option)
+                    {
+                        *overtime = option;
+                    }
+                }
+            });
+        match overtime {
+            clock::Overtime::None => {}
+            clock::Overtime::ByoYomi { periods, seconds } => {
+                ui.add(egui::DragValue::new(periods).range(1..=99));
+                ui.label(tr("periods of"));
+                ui.add(egui::DragValue::new(seconds).range(1.0..=3600.0));
+                ui.label(tr("s"));
+            }
+            clock::Overtime::Canadian { stones, seconds } => {
+                ui.add(egui::DragValue::new(stones).range(1..=99));
+                ui.label(tr("stones in"));
+                ui.add(egui::DragValue::new(seconds).range(1.0..=3600.0));
+                ui.label(tr("s"));
+            }
+            clock::Overtime::Fischer { increment } => {
+                ui.add(egui::DragValue::new(increment).range(1.0..=3600.0));
+                ui.label(tr("s added per move"));
+            }
+            clock::Overtime::PerMove { seconds, auto_pass } => {
+                ui.add(egui::DragValue::new(seconds).range(1.0..=3600.0));
+                ui.label(tr("s"));
+                ui.checkbox(auto_pass, tr("Pass when the time runs out"))
+                    .on_hover_text(tr("Otherwise the game is lost on time"));
+            }
+        }
+    });
+}
+
+// Seconds since the Unix epoch, for naming files
+fn timestamp() -> u64 {
+    web_time::SystemTime::now()
+        .duration_since(web_time::UNIX_EPOCH)
+        .map(|elapsed| elapsed.as_secs())
+        .unwrap_or_default()
+}
+
+fn toggle_fullscreen(ctx: &egui::Context) {
+    let fullscreen = ctx.input(|i| i.viewport().fullscreen.unwrap_or(false));
+    ctx.send_viewport_cmd(egui::ViewportCommand::Fullscreen(!fullscreen));
+}
+
+// Whether the letters of `query` appear in order in `text`, ignoring case and spaces
+fn fuzzy_match(query: &str, text: &str) -> bool {
+    let mut text = text.chars().flat_map(char::to_lowercase);
+    query
+        .chars()
+        .filter(|c| !c.is_whitespace())
+        .flat_map(char::to_lowercase)
+        .all(|q| text.any(|t| t == q))
+}
+
+// Variations are lettered A, B, C... in the order they are stored
+fn variation_letter(index: usize) -> String {
+    char::from(b'A' + (index % 26) as u8).to_string()
+}
+
+// Tab title: the players once known, otherwise what the tab is showing
+fn tab_title(game: &GoBoard) -> String {
+    let info = &game.info;
+    let title = if !info.black_name.is_empty() || !info.white_name.is_empty() {
+        format!("{} vs {}", info.black_label(), info.white_label())
+    } else {
+        match game.state {
+            AppState::Options => tr("New Game"),
+            AppState::Game => trf("{}x{} game", &[&game.board_size, &game.board_size]),
+            AppState::Database => tr("Database"),
+            AppState::Problems => tr("Problems"),
+        }
+    };
+    // The boards of a simultaneous game that wait for a move stand out
+    if game.simul_waiting() {
+        format!("● {}", title)
+    } else {
+        title
+    }
+}
+
+// Every open game, each with its own board, history and engine
+enum Closing {
+    Window,
+    Tab(usize),
+}
+
+struct Tabs {
+    games: Vec<GoBoard>,
+    active: usize,
+    // What is waiting on the unsaved changes prompt
+    closing: Option<Closing>,
+    allow_close: bool,
+    // The window title last sent to the viewport
+    title: String,
+    audio: sound::Audio,
+    speaker: speech::Speaker,
+    notifier: notifications::Notifier,
+    #[cfg(not(target_arch = "wasm32"))]
+    listener: voice::Listener,
+    gamepads: gamepad::Gamepads,
+    config: config::Config,
+    // The zoom factor last applied, to tell when egui's own zoom keys changed it
+    zoom: f32,
+    #[cfg(not(target_arch = "wasm32"))]
+    api: Option<api::Server>,
+    // Shown in place of the tab in front while it is up
+    #[cfg(not(target_arch = "wasm32"))]
+    wall: Option<wall::Wall>,
+    study: Option<study::Study>,
+}
+
+impl Tabs {
+    fn new(cc: &eframe::CreationContext, game: GoBoard, config: config::Config) -> Self {
+        theme::install_fallback_font(&cc.egui_ctx);
+        Self {
+            games: vec![game],
+            active: 0,
+            closing: None,
+            allow_close: false,
+            title: consts::TITLE.to_string(),
+            audio: sound::Audio::default(),
+            speaker: speech::Speaker::default(),
+            notifier: notifications::Notifier::default(),
+            #[cfg(not(target_arch = "wasm32"))]
+            listener: voice::Listener::default(),
+            gamepads: gamepad::Gamepads::default(),
+            config,
+            zoom: 1.0,
+            #[cfg(not(target_arch = "wasm32"))]
+            api: None,
+            #[cfg(not(target_arch = "wasm32"))]
+            wall: None,
+            study: None,
+        }
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    fn start_api(&mut self, port: u16, ctx: &egui::Context) {
+        match api::Server::start(port, ctx) {
+            Ok(server) => self.api = Some(server),
+            Err(err) => {
+                self.games[self.active].file_status =
+                    Some(trf("Could not start the API server: {}", &[&err]))
+            }
+        }
+    }
+
+    // Plays the moves sent to the API in the game in front, and shows the clients how it stands
+    #[cfg(not(target_arch = "wasm32"))]
+    fn serve_api(&mut self) {
+        let Some(api) = &mut self.api else {
+            return;
+        };
+        let game = &mut self.games[self.active];
+        for request in api.requests() {
+            let result = game.api_move(&request.point);
+            let state = game.api_state();
+            request.answer(result.map(|()| &state));
+        }
+        api.publish(game.api_state());
+    }
+
+    // The boards of the tabs and of the broadcasts followed, tiled in place of the tab in front;
+    // clicking a game in a tab goes back to it. Returns whether the wall is up.
+    #[cfg(not(target_arch = "wasm32"))]
+    fn show_wall(&mut self, ctx: &egui::Context) -> bool {
+        let game = &mut self.games[self.active];
+        if std::mem::take(&mut game.wall_requested) {
+            self.wall = Some(wall::Wall::new(&game.new_game.wall_addresses, ctx));
+        }
+        let Some(wall) = &mut self.wall else {
+            return false;
+        };
+        let fullscreen = ctx.input(|i| i.viewport().fullscreen.unwrap_or(false));
+        let mut leave = ctx.input(|i| i.key_pressed(egui::Key::Escape));
+        let mut add = false;
+        let mut remove = None;
+        egui::TopBottomPanel::top("wall_controls").show_animated(ctx, !fullscreen, |ui| {
+            ui.horizontal_wrapped(|ui| {
+                ui.checkbox(&mut wall.tabs, tr("Games in the tabs"));
+                for (index, feed) in wall.feeds.iter().enumerate() {
+                    ui.label(&feed.address);
+                    if ui
+                        .small_button("x")
+                        .on_hover_text(tr("Stop showing this broadcast"))
+                        .clicked()
+                    {
+                        remove = Some(index);
+                    }
+                }
+                let typed = ui.add(
+                    egui::TextEdit::singleline(&mut wall.address)
+                        .hint_text(tr("Broadcast address"))
+                        .desired_width(200.0),
+                );
+                let entered = typed.lost_focus() && ui.input(|i| i.key_pressed(egui::Key::Enter));
+                add = (ui
+                    .button(tr("Add"))
+                    .on_hover_text(tr("Follow the game another computer broadcasts, by the address its status line gives"))
+                    .clicked()
+                    || entered)
+                    && !wall.address.trim().is_empty();
+                leave |= ui
+                    .button(tr("Leave"))
+                    .on_hover_text(tr("Back to the tab in front, as with Escape"))
+                    .clicked();
+            });
+        });
+        let mut tiles = Vec::new();
+        let mut tabs = Vec::new();
+        if wall.tabs {
+            for (index, game) in self.games.iter().enumerate() {
+                if matches!(game.state, AppState::Game) {
+                    tiles.push(wall::Tile {
+                        state: Some(game.api_state()),
+                        ..Default::default()
+                    });
+                    tabs.push(Some(index));
+                }
+            }
+        }
+        for feed in &wall.feeds {
+            tiles.push(feed.tile());
+            tabs.push(None);
+        }
+        let settings = &mut self.games[self.active].new_game;
+        let theme = settings.board_theme;
+        let clicked = egui::CentralPanel::default()
+            .show(ctx, |ui| wall::show(ui, &tiles, theme))
+            .inner;
+        // The clocks count down between moves
+        ctx.request_repaint_after(consts::WALL_REFRESH);
+        if add {
+            let address = std::mem::take(&mut wall.address).trim().to_string();
+            wall.feeds.push(wall::Feed::start(&address, ctx));
+            settings.wall_addresses.push(address);
+        }
+        if let Some(index) = remove {
+            let feed = wall.feeds.remove(index);
+            settings
+                .wall_addresses
+                .retain(|address| address.trim() != feed.address);
+        }
+        if let Some(Some(index)) = clicked.map(|tile| tabs[tile]) {
+            self.active = index;
+            leave = true;
+        }
+        if leave {
+            self.wall = None;
+        }
+        true
+    }
+
+    // Two tabs side by side in place of the tab in front, to step through together or each on
+    // its own. Returns whether they are up.
+    fn show_study(&mut self, ctx: &egui::Context) -> bool {
+        if std::mem::take(&mut self.games[self.active].study_requested) {
+            match (0..self.games.len()).find(|&index| index != self.active) {
+                Some(other) => {
+                    self.study = Some(study::Study {
+                        tabs: [self.active, other],
+                        synced: true,
+                        differences: true,
+                    })
+                }
+                None => {
+                    self.games[self.active].file_status = Some(tr(
+                        "Open the other game in a new tab first, to study the two side by side",
+                    ))
+                }
+            }
+        }
+        let Some(study) = &mut self.study else {
+            return false;
+        };
+        // A tab was closed under it
+        if study.tabs.iter().any(|&index| index >= self.games.len()) {
+            self.study = None;
+            return false;
+        }
+        let fullscreen = ctx.input(|i| i.viewport().fullscreen.unwrap_or(false));
+        let mut leave = ctx.input(|i| i.key_pressed(egui::Key::Escape));
+        egui::TopBottomPanel::top("study_controls").show_animated(ctx, !fullscreen, |ui| {
+            ui.horizontal_wrapped(|ui| {
+                for (index, tab) in study.tabs.iter_mut().enumerate() {
+                    egui::ComboBox::from_id_salt(("study_tab", index))
+                        .selected_text(tab_title(&self.games[*tab]))
+                        .show_ui(ui, |ui| {
+                            for (other, game) in self.games.iter().enumerate() {
+                                ui.selectable_value(tab, other, tab_title(game));
+                            }
+                        });
+                }
+                ui.checkbox(&mut study.synced, tr("Step both together"))
+                    .on_hover_text(tr("Take both games to the same move number whenever either steps, and step both with the arrow keys"));
+                ui.checkbox(&mut study.differences, tr("Frame differences"));
+                leave |= ui
+                    .button(tr("Leave"))
+                    .on_hover_text(tr("Back to the tab in front, as with Escape"))
+                    .clicked();
+            });
+        });
+        let sides = study.tabs.map(|index| {
+            let game = &self.games[index];
+            study::Side {
+                title: tab_title(game),
+                board: game.board.clone(),
+                last_move: game.last_move,
+                moves: game.moves.len(),
+                length: game.tree.line().len() - 1,
+                comment: game.tree.node(game.tree.current()).comment.clone(),
+                can_step: game.can_navigate(),
+            }
+        });
+        let differences = if study.differences {
+            compare::differences(&sides[0].board, &sides[1].board)
+        } else {
+            Vec::new()
+        };
+        let theme = self.games[self.active].new_game.board_theme;
+        let mut stepped = egui::CentralPanel::default()
+            .show(ctx, |ui| study::show(ui, &sides, &differences, theme))
+            .inner;
+        // The keys step through the game on the left, and the other with it when in step
+        if stepped.is_none() && !ctx.wants_keyboard_input() {
+            stepped = ctx.input(|i| {
+                if i.key_pressed(egui::Key::ArrowLeft) {
+                    Some((0, study::Step::Back))
+                } else if i.key_pressed(egui::Key::ArrowRight) {
+                    Some((0, study::Step::Forward))
+                } else if i.key_pressed(egui::Key::Home) {
+                    Some((0, study::Step::Start))
+                } else if i.key_pressed(egui::Key::End) {
+                    Some((0, study::Step::End))
+                } else {
+                    None
+                }
+            });
+        }
+        if let Some((side, step)) = stepped {
+            let move_number = step.from(sides[side].moves);
+            let tabs = if study.synced {
+                &study.tabs[..]
+            } else {
+                &study.tabs[side..=side]
+            };
+            for &index in tabs {
+                self.games[index].go_to_move(move_number);
+            }
+        }
+        if leave {
+            self.study = None;
+        }
+        true
+    }
+
+    // Stores the active tab's preferences as the defaults for the next start
+    fn save_config(&mut self) {
+        let game = &mut self.games[self.active];
+        self.config.settings = game.new_game.clone();
+        self.config.layout = game.layout;
+        if consts::VALID_BOARD_SIZES.contains(&game.board_size) {
+            self.config.board_size = game.board_size;
+        }
+        #[cfg(not(target_arch = "wasm32"))]
+        if let Err(err) = self.config.save(&paths::config_path()) {
+            game.file_status = Some(trf("Could not save settings: {}", &[&err]));
+        }
+    }
+
+    fn close_tab(&mut self, index: usize) {
+        self.games.remove(index);
+        if self.active > index || self.active == self.games.len() {
+            self.active -= 1;
+        }
+    }
+
+    fn show_unsaved_prompt(&mut self, ctx: &egui::Context) {
+        let Some(closing) = &self.closing else {
+            return;
+        };
+        let indices: Vec<usize> = match closing {
+            Closing::Window => (0..self.games.len()).collect(),
+            Closing::Tab(index) => vec![*index],
+        };
+        let unsaved: Vec<usize> = indices
+            .into_iter()
+            .filter(|&index| self.games[index].is_dirty())
+            .collect();
+        let (mut save, mut discard, mut cancel) = (false, false, false);
+        egui::Modal::new(egui::Id::new("unsaved_changes")).show(ctx, |ui| {
+            ui.heading(tr("Unsaved changes"));
+            for &index in &unsaved {
+                ui.label(tab_title(&self.games[index]));
+            }
+            ui.horizontal(|ui| {
+                save = ui.button(tr("Save")).clicked();
+                discard = ui.button(tr("Discard")).clicked();
+                cancel = ui.button(tr("Cancel")).clicked();
+            });
+        });
+        if save {
+            for &index in &unsaved {
+                self.games[index].save_game();
+            }
+            // A failed save keeps the prompt open; the tab shows why
+            discard = unsaved.iter().all(|&index| !self.games[index].is_dirty());
+        }
+        if discard {
+            match self.closing.take() {
+                Some(Closing::Window) => {
+                    self.allow_close = true;
+                    ctx.send_viewport_cmd(egui::ViewportCommand::Close);
+                }
+                Some(Closing::Tab(index)) => self.close_tab(index),
+                None => {}
+            }
+        }
+        if cancel {
+            self.closing = None;
+        }
+    }
+}
+
+impl eframe::App for Tabs {
+    fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
+        i18n::set_language(&self.games[self.active].new_game.language);
+        // Background work keeps running in the tabs that are not shown
+        #[cfg(not(target_arch = "wasm32"))]
+        let mut kept = Vec::new();
+        for game in &mut self.games {
+            #[cfg(not(target_arch = "wasm32"))]
+            game.poll_ogs();
+            #[cfg(not(target_arch = "wasm32"))]
+            game.poll_correspondence(ctx);
+            game.poll_database_import();
+            #[cfg(not(target_arch = "wasm32"))]
+            game.poll_archive_update();
+            game.poll_pattern_search();
+            game.poll_yose();
+            game.poll_analysis();
+            game.poll_mistakes();
+            game.poll_report();
+            game.drive_engine(ctx);
+            game.drive_lan(ctx);
+            game.keep_result();
+            #[cfg(not(target_arch = "wasm32"))]
+            game.drive_broadcast();
+            #[cfg(not(target_arch = "wasm32"))]
+            game.run_end_hooks();
+            #[cfg(not(target_arch = "wasm32"))]
+            kept.extend(game.keep_tournament_result());
+            #[cfg(not(target_arch = "wasm32"))]
+            game.sync_to_folder();
+        }
+        // A position marked in one tab is compared with in all of them, such as the same move of
+        // two games
+        if let Some(index) = self.games.iter().position(|game| game.compare_shared) {
+            self.games[index].compare_shared = false;
+            let marked = self.games[index].compare_mark.clone();
+            for game in &mut self.games {
+                game.compare_mark = marked.clone();
+            }
+        }
+        // The director may have the tournament open in another tab
+        #[cfg(not(target_arch = "wasm32"))]
+        for path in kept {
+            for game in &mut self.games {
+                game.reload_tournament(&path);
+            }
+        }
+        #[cfg(not(target_arch = "wasm32"))]
+        self.serve_api();
+        if ctx.input(|i| i.key_pressed(egui::Key::F11)) {
+            toggle_fullscreen(ctx);
+        }
+        let mut close = None;
+        let fullscreen = ctx.input(|i| i.viewport().fullscreen.unwrap_or(false));
+        egui::TopBottomPanel::top("tabs").show_animated(ctx, !fullscreen, |ui| {
+            ui.horizontal_wrapped(|ui| {
+                for (index, game) in self.games.iter().enumerate() {
+                    if ui
+                        .selectable_label(index == self.active, tab_title(game))
+                        .clicked()
+                    {
+                        self.active = index;
+                    }
+                    if self.games.len() > 1 && ui.small_button("x").clicked() {
+                        close = Some(index);
+                    }
+                    ui.separator();
+                }
+                let waiting: Vec<usize> = (0..self.games.len())
+                    .filter(|&index| self.games[index].simul_waiting())
+                    .collect();
+                if self.games.iter().any(|game| game.lan_simul) {
+                    ui.label(trf("{} boards wait for your move", &[&waiting.len()]));
+                    // The next board round from this one, as a teacher walks along the tables
+                    let next = waiting
+                        .iter()
+                        .find(|&&index| index > self.active)
+                        .or(waiting.first())
+                        .filter(|&&index| index != self.active);
+                    if ui
+                        .add_enabled(next.is_some(), egui::Button::new(tr("Next Board")))
+                        .on_hover_text(tr("Go to the next board of the simultaneous game that waits for your move"))
+                        .clicked()
+                        && let Some(&index) = next
+                    {
+                        self.active = index;
+                    }
+                    ui.separator();
+                }
+                if ui.button("+").on_hover_text(tr("Open a new tab")).clicked() {
+                    // New tabs keep the preferences and layout of the current one
+                    let mut game = GoBoard::new();
+                    game.new_game = self.games[self.active].new_game.clone();
+                    game.layout = self.games[self.active].layout;
+                    self.games.push(game);
+                    self.active = self.games.len() - 1;
+                }
+            });
+        });
+        if let Some(index) = close {
+            if self.games[index].is_dirty() {
+                self.closing = Some(Closing::Tab(index));
+            } else {
+                self.close_tab(index);
+            }
+        }
+        if ctx.input(|i| i.viewport().close_requested())
+            && !self.allow_close
+            && self.games.iter().any(GoBoard::is_dirty)
+        {
+            ctx.send_viewport_cmd(egui::ViewportCommand::CancelClose);
+            self.closing = Some(Closing::Window);
+        }
+        self.show_unsaved_prompt(ctx);
+
+        // Each further board of a simultaneous game opens its own room in a tab of its own
+        let extra = std::mem::take(&mut self.games[self.active].simul_extra);
+        for _ in 0..extra {
+            let mut game = GoBoard::new();
+            game.new_game = self.games[self.active].new_game.clone();
+            game.layout = self.games[self.active].layout;
+            game.start_game();
+            self.games.push(game);
+        }
+        // Each board of a tournament started from the director gets a tab, leaving the director
+        // where they are
+        #[cfg(not(target_arch = "wasm32"))]
+        for link in std::mem::take(&mut self.games[self.active].tournament_boards) {
+            let director = &self.games[self.active];
+            let Some((_, event)) = &director.tournament else {
+                continue;
+            };
+            let mut game = GoBoard::new();
+            game.new_game = director.new_game.clone();
+            game.layout = director.layout;
+            game.start_tournament_game(event, link);
+            self.games.push(game);
+        }
+        #[cfg(not(target_arch = "wasm32"))]
+        let walled = self.show_wall(ctx);
+        #[cfg(target_arch = "wasm32")]
+        let walled = false;
+        let walled = walled || self.show_study(ctx);
+        let game = &mut self.games[self.active];
+        if matches!(game.state, AppState::Game) {
+            for action in self.gamepads.poll(ctx) {
+                game.gamepad_action(action);
+            }
+        }
+        // The tab in front takes what is said, and what could not be made out goes to its status
+        #[cfg(not(target_arch = "wasm32"))]
+        {
+            let settings = &game.new_game;
+            let command_line = if settings.voice_moves {
+                settings.voice_command.as_str()
+            } else {
+                ""
+            };
+            match self.listener.heard(command_line, ctx) {
+                Ok(heard) if matches!(game.state, AppState::Game) && !walled => {
+                    for phrase in heard {
+                        game.voice_command(&phrase);
+                    }
+                }
+                Ok(_) => {}
+                Err(err) => game.file_status = Some(trf("Could not listen: {}", &[&err])),
+            }
+        }
+        ctx.set_visuals(game.new_game.ui_theme.visuals());
+        // The interface scale from the settings, enlarged further while presenting. Zooming with
+        // the keyboard changes the setting.
+        let settings = &mut game.new_game;
+        let system = if settings.follow_system_scale {
+            1.0
+        } else {
+            ctx.native_pixels_per_point().unwrap_or(1.0)
+        };
+        let presenting = if game.presentation.is_some() {
+            consts::PRESENTATION_SCALE
+        } else {
+            1.0
+        };
+        if ctx.zoom_factor() != self.zoom {
+            settings.ui_scale = (ctx.zoom_factor() * system / presenting).clamp(
+                *consts::UI_SCALE_RANGE.start(),
+                *consts::UI_SCALE_RANGE.end(),
+            );
+        }
+        self.zoom = settings.ui_scale / system * presenting;
+        if ctx.zoom_factor() != self.zoom {
+            ctx.set_zoom_factor(self.zoom);
+        }
+        // Fingers need bigger targets than a mouse pointer
+        if ctx.input(|i| i.has_touch_screen()) {
+            ctx.style_mut(|style| {
+                style.spacing.interact_size.y = consts::TOUCH_TARGET_SIZE;
+                style.spacing.button_padding = egui::vec2(12.0, 8.0);
+            });
+        }
+        match game.state {
+            _ if walled => {}
+            AppState::Options => game.show_options(ctx, &self.config.recent_files),
+            AppState::Game => game.show_game(ctx),
+            AppState::Database => game.show_database(ctx),
+            AppState::Problems => game.show_problem_sets(ctx),
+        }
+        if !ctx.wants_keyboard_input()
+            && ctx.input_mut(|i| i.consume_shortcut(&PREFERENCES_SHORTCUT))
+        {
+            game.preferences = Some(game.new_game.clone());
+        }
+        game.show_preferences(ctx);
+        if std::mem::take(&mut game.preferences_applied) {
+            self.save_config();
+        }
+        // Notifications are for when the window is out of sight; otherwise the status line says it
+        let away = ctx
+            .input(|i| i.viewport().focused == Some(false) || i.viewport().minimized == Some(true));
+        for game in &mut self.games {
+            let mut notices = std::mem::take(&mut game.notices);
+            if game.sounds.contains(&Sound::GameEnd) && game.lan_peer.is_some() {
+                notices.push(trf("The game ended: {}", &[&game.info.result]));
+            }
+            if !away || !game.new_game.notify_when_away || notices.is_empty() {
+                continue;
+            }
+            ctx.send_viewport_cmd(egui::ViewportCommand::RequestUserAttention(
+                egui::UserAttentionType::Informational,
+            ));
+            for notice in notices {
+                if let Err(err) = self
+                    .notifier
+                    .show(&tab_title(game), &notice, game.tab_id, ctx)
+                {
+                    game.file_status = Some(trf("Could not notify: {}", &[&err]));
+                }
+            }
+        }
+        for tab in self.notifier.clicked() {
+            if let Some(index) = self.games.iter().position(|game| game.tab_id == tab) {
+                self.active = index;
+                ctx.send_viewport_cmd(egui::ViewportCommand::Minimized(false));
+                ctx.send_viewport_cmd(egui::ViewportCommand::Focus);
+            }
+        }
+        // Only the tab in front makes noise
+        for (index, game) in self.games.iter_mut().enumerate() {
+            let sounds = std::mem::take(&mut game.sounds);
+            let announcement = game.announcement.take();
+            let countdown = game.countdown.take();
+            let settings = &game.new_game;
+            if index != self.active {
+                continue;
+            }
+            if let Some(pack) = &settings.sound_pack {
+                for sound in sounds.into_iter().filter(|&sound| settings.plays(sound)) {
+                    let volume = if sound == Sound::TimeWarning {
+                        settings.time_warning_volume
+                    } else {
+                        1.0
+                    };
+                    self.audio.play(pack, sound, volume);
+                }
+            }
+            // The countdown is more urgent than the move before it
+            let text = match countdown {
+                Some(second) if settings.speak_countdown => {
+                    Some(settings.countdown_language.word(second))
+                }
+                _ => announcement.filter(|_| settings.speak_moves),
+            };
+            if let Some(text) = text
+                && let Err(err) = self.speaker.say(&settings.speech_command, &text)
+            {
+                game.file_status = Some(trf("Could not speak: {}", &[&err]));
+            }
+        }
+        if let Some(path) = &self.games[self.active].file_path {
+            self.config.add_recent(path);
+        }
+        // Fullscreen and maximized sizes are not worth restoring; a maximized window is reopened
+        // maximized over the size it had before
+        let (maximized, geometry) = ctx.input(|i| {
+            let viewport = i.viewport();
+            let maximized = viewport.maximized == Some(true);
+            let normal = viewport.fullscreen != Some(true) && !maximized;
+            let geometry =
+                viewport
+                    .inner_rect
+                    .filter(|_| normal)
+                    .map(|rect| config::WindowGeometry {
+                        position: viewport.outer_rect.map(|rect| [rect.min.x, rect.min.y]),
+                        size: [rect.width(), rect.height()],
+                        maximized: false,
+                    });
+            (maximized, geometry)
+        });
+        if geometry.is_some() {
+            self.config.window = geometry;
+        } else if maximized && let Some(window) = &mut self.config.window {
+            window.maximized = true;
+        }
+        let title = self.games[self.active].title();
+        if title != self.title {
+            ctx.send_viewport_cmd(egui::ViewportCommand::Title(title.clone()));
+            self.title = title;
+        }
+    }
+
+    fn save(&mut self, _storage: &mut dyn eframe::Storage) {
+        self.save_config();
+        // The browser has no config file, so the config goes to local storage
+        #[cfg(target_arch = "wasm32")]
+        if let Ok(text) = self.config.to_text() {
+            _storage.set_string(config::STORAGE_KEY, text);
+        }
+    }
+
+    // Native builds have no eframe storage, so `save` is never called and the config is written here
+    fn on_exit(&mut self, _gl: Option<&eframe::glow::Context>) {
+        #[cfg(not(target_arch = "wasm32"))]
+        self.save_config();
+    }
+
+    fn auto_save_interval(&self) -> std::time::Duration {
+        std::time::Duration::from_secs(30)
+    }
+
+    fn clear_color(&self, _visuals: &egui::Visuals) -> [f32; 4] {
+        // NOTE: a bright gray makes the shadows of the windows look weird.
+        // We use a bit of transparency so that if the user switches on the
+        // `transparent()` option they get immediate results.
+        egui::Color32::from_rgba_unmultiplied(12, 12, 12, 180).to_normalized_gamma_f32()
+
+        // _visuals.window_fill() would also be a natural choice
+    }
+
+    fn persist_egui_memory(&self) -> bool {
+        true
+    }
+
+    fn raw_input_hook(&mut self, _ctx: &egui::Context, _raw_input: &mut egui::RawInput) {}
+}
+
+// The board as text for --headless, followed by the captures and the score
+#[cfg(not(target_arch = "wasm32"))]
+fn print_position(game: &GoBoard) {
+    let size = game.board_size;
+    let style = game.new_game.coordinates;
+    let columns: String = (0..size)
+        .map(|col| format!("{:>3}", style.column_label(col, size)))
+        .collect();
+    println!("   {}", columns);
+    for row in 0..size {
+        let stones: String = (0..size)
+            .map(|col| match game.board[row][col] {
+                Stone::Black => "  X",
+                Stone::White => "  O",
+                Stone::Empty => "  .",
+            })
+            .collect();
+        println!("{:>3}{}", style.row_label(row, size), stones);
+    }
+    println!("Black stones captured: {}", game.captured_black);
+    println!("White stones captured: {}", game.captured_white);
+    let score = scoring::score(
+        &game.board,
+        &HashSet::new(),
+        game.info.rules,
+        game.info.komi,
+        game.captured_black,
+        game.captured_white,
+    );
+    println!("Score with every stone alive: {}", score.result());
+    if !game.info.result.is_empty() {
+        println!("Recorded result: {}", game.info.result);
+    }
+}
+
+// Clears the engine's board and puts the setup stones of a game on it
+fn send_setup(
+    engine: &mut gtp::Engine,
+    size: usize,
+    komi: f32,
+    setup: &[(Stone, (usize, usize))],
+) -> Result<(), gtp::Error> {
+    engine.send(&format!("boardsize {}", size))?;
+    engine.send("clear_board")?;
+    engine.send(&format!("komi {}", komi))?;
+    if setup.iter().all(|&(stone, _)| stone == Stone::Black) {
+        if !setup.is_empty() {
+            let vertices: Vec<String> = setup
+                .iter()
+                .map(|&(_, point)| notation::format_point(point, size))
+                .collect();
+            engine.send(&format!("set_free_handicap {}", vertices.join(" ")))?;
+        }
+    } else {
+        // GTP has no setup command, so edited positions are played in stone by stone
+        for &(stone, point) in setup {
+            let player = match stone {
+                Stone::Black => Player::Black,
+                Stone::White => Player::White,
+                Stone::Empty => continue,
+            };
+            engine.play((player, Some(point)), size)?;
+        }
+    }
+    Ok(())
+}
+
+// Black's lead as the engine scores the start and every move after, asking it only about the
+// positions the analysis cache does not have
+fn engine_analysis(
+    command: &str,
+    komi: f32,
+    rules: Rules,
+    setup: &[(Stone, (usize, usize))],
+    mut board: Board,
+    start_player: Player,
+    moves: &[Move],
+) -> Result<Vec<f32>, gtp::Error> {
+    let size = board.size();
+    let mut engine = gtp::Engine::start(command)?;
+    // Engines that know of no rule sets score by their own
+    let _ = engine.send(&format!("kgs-rules {}", rules.name().to_lowercase()));
+    let key = format!(
+        "{} | {} | komi {} | {}",
+        engine.identity(),
+        command,
+        komi,
+        rules.name()
+    );
+    send_setup(&mut engine, size, komi, setup)?;
+    let mut sent = 0;
+    let mut leads = Vec::with_capacity(moves.len() + 1);
+    for played in 0..=moves.len() {
+        if played > 0 {
+            match moves[played - 1] {
+                (player, Some((row, col))) => {
+                    board.play(row, col, player);
+                }
+                _ => board.pass(),
+            }
+        }
+        let next = match moves.get(played) {
+            Some(&(player, _)) => player,
+            None => moves
+                .last()
+                .map_or(start_player, |&(player, _)| player.other()),
+        };
+        leads.push(analysis_cache::lead(&key, &board, next, || {
+            for &unsent in &moves[sent..played] {
+                engine.play(unsent, size)?;
+            }
+            sent = played;
+            engine.final_score()
+        })?);
+    }
+    Ok(leads)
+}
+
+// Keeps the scores the engine worked out for the next review, which loses nothing but time without them
+fn save_analysis_cache(megabytes: u32) {
+    if let Err(err) = analysis_cache::save(megabytes) {
+        tracing::warn!(target: "analysis", "could not save the cache: {}", err);
+    }
+}
+
+// Rewrites a record as SGF, or as a review report when the output ends in .html or .md
+#[cfg(not(target_arch = "wasm32"))]
+fn convert(args: &cli::ConvertArgs) -> Result<(), String> {
+    let mut game = GoBoard::default();
+    game.open_file(args.input.clone())
+        .map_err(|err| format!("{}: {}", args.input.display(), err))?;
+    let extension = args
+        .output
+        .extension()
+        .and_then(|extension| extension.to_str())
+        .unwrap_or_default()
+        .to_ascii_lowercase();
+    let text = if extension == "sgf" {
+        sgf::to_string(&game.to_sgf())
+    } else if let Some(format) = report::Format::ALL
+        .into_iter()
+        .find(|format| format.extension() == extension)
+    {
+        report::Review::new(game.setup_board(), &game.moves, game.info.clone())
+            .render(format, game.new_game.coordinates)
+    } else {
+        return Err(format!(
+            "{}: expected a name ending in .sgf, .html or .md",
+            args.output.display()
+        ));
+    };
+    std::fs::write(&args.output, text).map_err(|err| format!("{}: {}", args.output.display(), err))
+}
+
+// Reads each record as the board would open it, failing if any of them is broken
+#[cfg(not(target_arch = "wasm32"))]
+fn validate(args: &cli::ValidateArgs) -> Result<(), String> {
+    let mut broken = 0;
+    for path in &args.files {
+        let mut game = GoBoard::default();
+        match game.open_file(path.clone()) {
+            Ok(()) => println!("{}: {} moves", path.display(), game.moves.len()),
+            Err(err) => {
+                println!("{}: {}", path.display(), err);
+                broken += 1;
+            }
+        }
+    }
+    match broken {
+        0 => Ok(()),
+        _ => Err(format!(
+            "{} of {} records could not be read",
+            broken,
+            args.files.len()
+        )),
+    }
+}
+
+// Writes the main line of each record that fits the board size and has a winner
+#[cfg(not(target_arch = "wasm32"))]
+fn export_dataset(args: &cli::DatasetArgs) -> Result<(), String> {
+    let failed = |err: std::io::Error| format!("{}: {}", args.out.display(), err);
+    let file = std::fs::File::create(&args.out).map_err(failed)?;
+    let mut writer =
+        dataset::Writer::new(std::io::BufWriter::new(file), args.size).map_err(failed)?;
+    let (mut games, mut skipped) = (0, 0);
+    for path in &args.records {
+        let mut game = GoBoard::default();
+        let reason = match game.open_file(path.clone()) {
+            Err(err) => Some(err),
+            Ok(()) if game.board_size != args.size => {
+                Some(format!("a {}x{} board", game.board_size, game.board_size))
+            }
+            Ok(()) => None,
+        };
+        let score = profiles::black_score(&game.info.result);
+        match (reason, score) {
+            (Some(reason), _) => {
+                println!("{}: left out, {}", path.display(), reason);
+                skipped += 1;
+            }
+            (None, None) => {
+                println!("{}: left out, no result", path.display());
+                skipped += 1;
+            }
+            (None, Some(score)) => {
+                writer
+                    .write_game(game.setup_board(), &game.moves, game.info.komi, score)
+                    .map_err(failed)?;
+                games += 1;
+            }
+        }
+    }
+    let records = writer.records;
+    writer.finish().map_err(failed)?;
+    println!(
+        "{} positions from {} games written to {}, {} records left out",
+        records,
+        games,
+        args.out.display(),
+        skipped
+    );
+    Ok(())
+}
+
+// Annotates each record with the script and prints what it says, or runs it on an empty board
+#[cfg(not(target_arch = "wasm32"))]
+fn run_script(args: &cli::ScriptArgs) -> Result<(), String> {
+    let script = script::Script::load(&args.script)?;
+    if args.records.is_empty() {
+        let game = GoBoard::default();
+        println!("{}", script.run(&game.script_game())?);
+        return Ok(());
+    }
+    if let Some(out) = &args.out {
+        std::fs::create_dir_all(out).map_err(|err| format!("{}: {}", out.display(), err))?;
+    }
+    for path in &args.records {
+        let mut game = GoBoard::default();
+        game.open_file(path.clone())
+            .map_err(|err| format!("{}: {}", path.display(), err))?;
+        let commented = game.annotate_with(&script)?;
+        println!("{}: {} moves commented on", path.display(), commented);
+        let game_state = game.script_game();
+        let ended = script.on_game_end(&game_state, &game.info.result)?;
+        let mut said = script.take_printed();
+        said.extend(ended);
+        if script.defines("run", 1) {
+            said.push(script.run(&game_state)?);
+        }
+        for line in said.iter().filter(|line| !line.is_empty()) {
+            println!("{}", line);
+        }
+        if let Some(out) = &args.out {
+            let target = out.join(path.file_name().unwrap_or_default());
+            std::fs::write(&target, sgf::to_string(&game.to_sgf()))
+                .map_err(|err| format!("{}: {}", target.display(), err))?;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn main() -> Result<(), eframe::Error> {
+    let args = cli::Args::parse();
+    let result = match args.command {
+        None => return run_window(args.game),
+        Some(cli::Command::Gui(game)) => return run_window(game),
+        Some(cli::Command::Gtp(args)) => commands::gtp(&args),
+        Some(cli::Command::Selfplay(args)) => commands::selfplay(&args),
+        Some(cli::Command::Bench(args)) => commands::bench(&args),
+        Some(cli::Command::Convert(args)) => convert(&args),
+        Some(cli::Command::Validate(args)) => validate(&args),
+        Some(cli::Command::Serve(args)) => commands::serve(&args.args),
+        Some(cli::Command::Dataset(args)) => export_dataset(&args),
+        Some(cli::Command::Script(args)) => run_script(&args),
+    };
+    if let Err(err) = result {
+        eprintln!("{}", err);
+        std::process::exit(1);
+    }
+    Ok(())
+}
+
+// The board, in a window or for --headless and --tui in the terminal
+#[cfg(not(target_arch = "wasm32"))]
+fn run_window(args: cli::GameArgs) -> Result<(), eframe::Error> {
+    if let Err(err) = game_log::install(&paths::log_path()) {
+        eprintln!("Could not open the log file: {}", err);
+    }
+    let config = config::Config::load(&paths::config_path());
+    let mut game = GoBoard::from_config(&config);
+    let opened = game.apply_args(&args);
+    if args.headless {
+        if let Err(err) = opened {
+            eprintln!("{}", err);
+            std::process::exit(1);
+        }
+        print_position(&game);
+        return Ok(());
+    }
+    if args.tui {
+        if let Err(err) = opened {
+            eprintln!("{}", err);
+            std::process::exit(1);
+        }
+        let tui_game = tui::Game::new(
+            game.board.clone(),
+            game.current_player,
+            (game.captured_black, game.captured_white),
+            game.info.rules,
+            game.info.komi,
+        );
+        if let Err(err) = tui::run(tui_game) {
+            eprintln!("{}", err);
+            std::process::exit(1);
+        }
+        return Ok(());
+    }
+    if let Err(err) = opened {
+        game.file_status = Some(err);
+    }
+    let mut viewport = egui::ViewportBuilder::default()
+        .with_inner_size(consts::WINDOW_SIZE)
+        .with_title(consts::TITLE);
+    if let Some(window) = config.window {
+        viewport = viewport.with_inner_size(window.size);
+        if let Some(position) = window.position {
+            viewport = viewport.with_position(position);
+        }
+        viewport = viewport.with_maximized(window.maximized);
+    }
+    let options = eframe::NativeOptions {
+        viewport,
+        ..Default::default()
+    };
+    eframe::run_native(
+        consts::TITLE,
+        options,
+        Box::new(move |cc| {
+            let mut tabs = Tabs::new(cc, game, config);
+            if let Some(port) = args.api_port {
+                tabs.start_api(port, &cc.egui_ctx);
+            }
+            Ok(Box::new(tabs))
+        }),
+    )
+}
+
+#[cfg(target_arch = "wasm32")]
+fn main() {
+    use eframe::wasm_bindgen::JsCast;
+
+    wasm_bindgen_futures::spawn_local(async {
+        // The canvas is declared in index.html
+        let canvas = web_sys::window()
+            .and_then(|window| window.document())
+            .and_then(|document| document.get_element_by_id("go_game_canvas"))
+            .and_then(|element| element.dyn_into::<web_sys::HtmlCanvasElement>().ok())
+            .expect("the page has no canvas for the game");
+        eframe::WebRunner::new()
+            .start(
+                canvas,
+                eframe::WebOptions::default(),
+                Box::new(|cc| {
+                    let config = cc
+                        .storage
+                        .and_then(|storage| storage.get_string(config::STORAGE_KEY))
+                        .map(|text| config::Config::parse(&text))
+                        .unwrap_or_default();
+                    let mut game = GoBoard::from_config(&config);
+                    // Opened from a shared link
+                    if let Some(text) = web_sys::window()
+                        .and_then(|window| window.location().hash().ok())
+                        .and_then(|fragment| share::from_fragment(&fragment))
+                    {
+                        match game.load_sgf(&text) {
+                            Ok(()) => game.state = AppState::Game,
+                            Err(err) => {
+                                game.file_status =
+                                    Some(trf("Could not open the shared game: {}", &[&err]))
+                            }
+                        }
+                    }
+                    Ok(Box::new(Tabs::new(cc, game, config)))
+                }),
+            )
+            .await
+            .expect("the game failed to start");
+    });
 }
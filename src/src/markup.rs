@@ -0,0 +1,190 @@
+use eframe::egui;
+
+use crate::sgf;
+use crate::theme::MarkerShape;
+
+/// A mark on an intersection of one game tree node, as in the SGF TR, SQ, CR, MA and LB
+/// properties.
+#[derive(Clone, PartialEq, Debug)]
+pub enum Mark {
+    Triangle,
+    Square,
+    Circle,
+    Cross,
+    Label(String),
+}
+
+impl Mark {
+    fn ident(&self) -> &'static str {
+        match self {
+            Mark::Triangle => "TR",
+            Mark::Square => "SQ",
+            Mark::Circle => "CR",
+            Mark::Cross => "MA",
+            Mark::Label(_) => "LB",
+        }
+    }
+
+    /// Whether `other` is made by the same tool, whatever a label says.
+    pub fn same_kind(&self, other: &Mark) -> bool {
+        self.ident() == other.ident()
+    }
+
+    /// Draws the mark over an intersection whose stones have `radius`.
+    pub fn paint(
+        &self,
+        painter: &egui::Painter,
+        center: egui::Pos2,
+        radius: f32,
+        stroke: egui::Stroke,
+    ) {
+        match self {
+            Mark::Triangle => MarkerShape::Triangle.paint(painter, center, radius * 0.6, stroke),
+            Mark::Square => MarkerShape::Square.paint(painter, center, radius * 0.6, stroke),
+            Mark::Circle => MarkerShape::Circle.paint(painter, center, radius * 0.5, stroke),
+            Mark::Cross => {
+                let arm = radius * 0.4;
+                painter.line_segment(
+                    [
+                        center + egui::vec2(-arm, -arm),
+                        center + egui::vec2(arm, arm),
+                    ],
+                    stroke,
+                );
+                painter.line_segment(
+                    [
+                        center + egui::vec2(-arm, arm),
+                        center + egui::vec2(arm, -arm),
+                    ],
+                    stroke,
+                );
+            }
+            Mark::Label(text) => {
+                painter.text(
+                    center,
+                    egui::Align2::CENTER_CENTER,
+                    text,
+                    egui::FontId::proportional(radius * 1.1),
+                    stroke.color,
+                );
+            }
+        }
+    }
+}
+
+/// What a click on the board puts down while marking up.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Tool {
+    Triangle,
+    Square,
+    Circle,
+    Cross,
+    Letter,
+    Number,
+}
+
+impl Tool {
+    pub const ALL: [Tool; 6] = [
+        Tool::Triangle,
+        Tool::Square,
+        Tool::Circle,
+        Tool::Cross,
+        Tool::Letter,
+        Tool::Number,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Tool::Triangle => "Triangle",
+            Tool::Square => "Square",
+            Tool::Circle => "Circle",
+            Tool::Cross => "Cross",
+            Tool::Letter => "Letter",
+            Tool::Number => "Number",
+        }
+    }
+
+    /// The mark this tool adds next; letters and numbers count up from the first one unused in
+    /// `markup`.
+    pub fn next_mark(self, markup: &[((usize, usize), Mark)]) -> Mark {
+        let unused = |label: &String| {
+            !markup
+                .iter()
+                .any(|(_, mark)| matches!(mark, Mark::Label(text) if text == label))
+        };
+        match self {
+            Tool::Triangle => Mark::Triangle,
+            Tool::Square => Mark::Square,
+            Tool::Circle => Mark::Circle,
+            Tool::Cross => Mark::Cross,
+            Tool::Letter => Mark::Label(
+                ('A'..='Z')
+                    .map(String::from)
+                    .find(unused)
+                    .unwrap_or_else(|| "A".to_string()),
+            ),
+            Tool::Number => Mark::Label(
+                (1..)
+                    .map(|number: usize| number.to_string())
+                    .find(unused)
+                    .unwrap_or_default(),
+            ),
+        }
+    }
+}
+
+// A point, or every point of a rectangle written compressed as `aa:cc`
+fn points(value: &str, board_size: usize) -> Vec<(usize, usize)> {
+    let Some((from, to)) = value.split_once(':') else {
+        return sgf::parse_point(value, board_size).into_iter().collect();
+    };
+    let (Some(from), Some(to)) = (
+        sgf::parse_point(from, board_size),
+        sgf::parse_point(to, board_size),
+    ) else {
+        return Vec::new();
+    };
+    (from.0.min(to.0)..=from.0.max(to.0))
+        .flat_map(|row| (from.1.min(to.1)..=from.1.max(to.1)).map(move |col| (row, col)))
+        .collect()
+}
+
+/// The markup stored on `node`.
+pub fn read(node: &sgf::Node, board_size: usize) -> Vec<((usize, usize), Mark)> {
+    let mut markup = Vec::new();
+    for mark in [Mark::Triangle, Mark::Square, Mark::Circle, Mark::Cross] {
+        for value in node.get_all(mark.ident()) {
+            for point in points(value, board_size) {
+                markup.push((point, mark.clone()));
+            }
+        }
+    }
+    for value in node.get_all("LB") {
+        if let Some((point, text)) = value.split_once(':')
+            && let Some(point) = sgf::parse_point(point, board_size)
+        {
+            markup.push((point, Mark::Label(text.to_string())));
+        }
+    }
+    markup
+}
+
+/// Stores `markup` on `node`, replacing any markup properties it had.
+pub fn write(markup: &[((usize, usize), Mark)], node: &mut sgf::Node) {
+    for ident in ["TR", "SQ", "CR", "MA"] {
+        let points = markup
+            .iter()
+            .filter(|(_, mark)| mark.ident() == ident)
+            .map(|&(point, _)| sgf::format_point(point))
+            .collect();
+        node.set(ident, points);
+    }
+    let labels = markup
+        .iter()
+        .filter_map(|(point, mark)| match mark {
+            Mark::Label(text) => Some(format!("{}:{}", sgf::format_point(*point), text)),
+            _ => None,
+        })
+        .collect();
+    node.set("LB", labels);
+}
@@ -0,0 +1,156 @@
+//! Problems made from the mistakes in one's own games: the position before a move the review
+//! found to lose points, with the moves it would rather have played as the answers.
+
+use std::path::Path;
+
+use crate::board::{Board, Move, Player, Stone};
+use crate::consts;
+use crate::estimate;
+use crate::gtp;
+use crate::sgf;
+
+pub struct Mistake {
+    // Moves played before it
+    pub move_number: usize,
+    pub player: Player,
+    pub played: (usize, usize),
+    pub better: Vec<(usize, usize)>,
+    // Points lost next to the best move found
+    pub loss: f32,
+    board: Board,
+}
+
+impl Mistake {
+    /// The position the move was played in.
+    pub fn board(&self) -> &Board {
+        &self.board
+    }
+
+    /// The mistake as a problem: the position before it, the better moves right and the move
+    /// played wrong.
+    pub fn problem(&self, comment: String) -> sgf::Node {
+        let mut root = sgf::Node::default();
+        root.set("SZ", vec![self.board.size().to_string()]);
+        for (ident, stone) in [("AB", Stone::Black), ("AW", Stone::White)] {
+            let mut points = Vec::new();
+            for row in 0..self.board.size() {
+                for col in 0..self.board.size() {
+                    if self.board[row][col] == stone {
+                        points.push(sgf::format_point((row, col)));
+                    }
+                }
+            }
+            root.set(ident, points);
+        }
+        root.set("PL", vec![gtp::color(self.player).to_string()]);
+        root.set("C", vec![comment]);
+        let answer = |point: (usize, usize), mark: &str, comment: &str| {
+            let mut node = sgf::Node::default();
+            node.set(gtp::color(self.player), vec![sgf::format_point(point)]);
+            node.set(mark, vec!["1".to_string()]);
+            node.set("C", vec![comment.to_string()]);
+            node
+        };
+        root.children = self
+            .better
+            .iter()
+            .map(|&point| answer(point, "TE", "RIGHT"))
+            .collect();
+        root.children.push(answer(
+            self.played,
+            "BM",
+            "Wrong: the move played in the game",
+        ));
+        root
+    }
+}
+
+/// The moves of `side` in the game that lose at least `consts::MISTAKE_POINTS` by the estimate,
+/// both next to the best move it finds and in the lead once the opponent has answered.
+pub fn find(mut board: Board, moves: &[Move], side: Player, komi: f32) -> Vec<Mistake> {
+    let leads = estimate::analyze(board.clone(), moves, komi);
+    // Black's lead seen from `player`
+    let lead_for = |lead: f32, player: Player| match player {
+        Player::Black => lead,
+        Player::White => -lead,
+    };
+    let mut mistakes = Vec::new();
+    for (index, &(player, point)) in moves.iter().enumerate() {
+        if player == side
+            && let Some(played) = point
+        {
+            let answered = leads[(index + 2).min(leads.len() - 1)];
+            let dropped = lead_for(leads[index] - answered, player);
+            if dropped >= consts::MISTAKE_POINTS
+                && let Some(mistake) = compare(&board, index, player, played, komi)
+            {
+                mistakes.push(mistake);
+            }
+        }
+        match point {
+            Some((row, col)) => {
+                board.play(row, col, player);
+            }
+            None => board.pass(),
+        }
+    }
+    mistakes
+}
+
+// Weighs `played` against every other move `player` had on `board`
+fn compare(
+    board: &Board,
+    move_number: usize,
+    player: Player,
+    played: (usize, usize),
+    komi: f32,
+) -> Option<Mistake> {
+    let lead_after = |(row, col): (usize, usize)| {
+        let mut after = board.clone();
+        after.play(row, col, player);
+        let lead = estimate::estimate(&after).lead(komi);
+        match player {
+            Player::Black => lead,
+            Player::White => -lead,
+        }
+    };
+    let played_lead = lead_after(played);
+    let mut candidates = Vec::new();
+    for row in 0..board.size() {
+        for col in 0..board.size() {
+            if (row, col) != played && board.is_legal(row, col, player) {
+                candidates.push(((row, col), lead_after((row, col))));
+            }
+        }
+    }
+    candidates.sort_by(|a, b| b.1.total_cmp(&a.1));
+    let &(_, best) = candidates.first()?;
+    let loss = best - played_lead;
+    if loss < consts::MISTAKE_POINTS {
+        return None;
+    }
+    let better = candidates
+        .iter()
+        .take_while(|&&(_, lead)| lead >= best - consts::MISTAKE_ANSWER_MARGIN)
+        .take(consts::MISTAKE_ANSWERS)
+        .map(|&(point, _)| point)
+        .collect();
+    Some(Mistake {
+        move_number,
+        player,
+        played,
+        better,
+        loss,
+        board: board.clone(),
+    })
+}
+
+/// Writes each of `problems` to a file of its own in `folder`, named after `game`.
+pub fn write(folder: &Path, game: &str, problems: &[(usize, sgf::Node)]) -> std::io::Result<()> {
+    std::fs::create_dir_all(folder)?;
+    for (move_number, problem) in problems {
+        let path = folder.join(format!("{}-move{}.sgf", game, move_number + 1));
+        std::fs::write(path, sgf::to_string(problem))?;
+    }
+    Ok(())
+}
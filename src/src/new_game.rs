@@ -0,0 +1,278 @@
+use serde::{Deserialize, Serialize};
+
+use crate::board::Player;
+use crate::clock::{CountdownLanguage, Overtime};
+use crate::consts;
+use crate::game_info::Rules;
+use crate::i18n;
+use crate::notation::CoordinateStyle;
+use crate::protocol::{AwayClock, DelayUnit, Speed};
+use crate::sound::Sound;
+use crate::theme::{BoardTheme, MarkerShape, UiTheme};
+
+#[derive(Clone, Copy, PartialEq, Debug, Serialize, Deserialize)]
+pub enum Opponent {
+    Human,
+    Engine,
+    // Another copy of the app on the network, which connects here or is connected to
+    Host,
+    Join,
+    // A player in a room of the game server
+    Room,
+    // The account's own game on online-go.com
+    Ogs,
+    // A game on an IGS server such as Pandanet
+    Igs,
+}
+
+// Everything chosen in the new game form apart from the board size
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Settings {
+    pub handicap: u32,
+    pub komi: f32,
+    pub rules: Rules,
+    // Stones to capture to win a game of Capture Go, 0 for a normal game
+    pub capture_goal: u32,
+    // Stones stay hidden while the game is played, for practising blindfold
+    pub blind: bool,
+    // Toroidal Go, with the edges of the board joined
+    pub toroidal: bool,
+    // Stones each player places unseen before Hidden-move Go, 0 for a normal game
+    pub hidden_stones: u32,
+    pub opponent: Opponent,
+    pub engine_command: String,
+    pub human_color: Player,
+    // The profiles of the players at this computer, by name: both sides of a game between two
+    // people here, or the one side played here against an engine or as a network host; empty for
+    // none
+    pub black_profile: String,
+    pub white_profile: String,
+    pub profile: String,
+    // A finished game between profiles, or between a profile and an engine, moves their ratings
+    pub rated: bool,
+    // Where to join a network game; hosting only uses the port
+    pub lan_address: String,
+    pub lan_port: u16,
+    // Both sides of a direct game need the same choice
+    pub lan_tls: bool,
+    // The game server, and the room to enter there; with no code a new room is opened
+    pub server: String,
+    pub room_code: String,
+    // The account logged in to on the server, with no token when logged out, and those invited
+    // to a new room, separated by commas
+    pub server_account: String,
+    pub server_token: String,
+    pub invited: String,
+    // A new room is kept on the server for a slow game its players come back to
+    pub correspondence: bool,
+    // Pair go, with two players a side: in a new room, or taking turns at this computer
+    pub rengo: bool,
+    // The players of pair go at this computer, in the order they play: Black, White, then their
+    // partners
+    pub pair_names: [String; 4],
+    // Rooms a new simultaneous game opens, one in a tab per opponent; 1 for a single game
+    pub simul_boards: u32,
+    // Filled in to log in to OGS again
+    pub ogs_username: String,
+    // The IGS server and account, empty for a guest, and a game number to watch or a player to
+    // challenge; with neither the first challenge that arrives is accepted
+    pub igs_server: String,
+    pub igs_account: String,
+    pub igs_target: String,
+    // Instead of a room, the server's queue finds an opponent for one of these
+    pub automatch: bool,
+    pub match_sizes: Vec<usize>,
+    pub match_speeds: Vec<Speed>,
+    // Seconds a network player whose connection drops has to come back, 0 for none, and what the
+    // clocks do meanwhile; chosen by the side that opens the game
+    pub reconnect_seconds: u32,
+    pub away_clock: AwayClock,
+    // How far behind the game the spectators of a new room are kept, 0 for not at all
+    pub spectator_delay: u32,
+    pub spectator_delay_unit: DelayUnit,
+    // Network chat is also written into the comment of the move it was sent at
+    pub chat_in_record: bool,
+    // Board clicks only stage a move until it is confirmed
+    pub confirm_moves: bool,
+    pub warn_self_atari: bool,
+    // Seconds of main time for each player, 0 for no clock
+    pub main_time: f32,
+    pub overtime: Overtime,
+    // A language code with a catalog in the locales folder
+    pub language: String,
+    pub coordinates: CoordinateStyle,
+    pub ui_theme: UiTheme,
+    // Magnification of the whole interface, on top of the display's own scaling unless that is
+    // ignored, in which case it is the number of pixels per point
+    pub ui_scale: f32,
+    pub follow_system_scale: bool,
+    pub board_theme: BoardTheme,
+    // A directory under the skins folder; `None` draws the board and stones with shapes
+    pub skin: Option<String>,
+    pub marker_shape: MarkerShape,
+    // Opposite-color markers and stronger shading in place of the red and faint overlays
+    pub high_contrast: bool,
+    // A directory under the sounds folder; `None` keeps the app silent
+    pub sound_pack: Option<String>,
+    pub stone_sound: bool,
+    pub capture_sound: bool,
+    pub game_end_sound: bool,
+    // Ticks every second once a clock is in its last seconds
+    pub time_warning_sound: bool,
+    // From 0 to 1
+    pub time_warning_volume: f32,
+    // Reads every move aloud with the speech program, which gets the text as its last argument
+    pub speak_moves: bool,
+    // Reads the last seconds of each overtime period aloud instead of ticking
+    pub speak_countdown: bool,
+    pub countdown_language: CountdownLanguage,
+    pub speech_command: String,
+    // Takes moves and commands from what the speech recognizer hears, a line for each phrase
+    pub voice_moves: bool,
+    // Shows desktop notifications of network games while the window is out of sight
+    pub notify_when_away: bool,
+    pub voice_command: String,
+    // Games played here are also kept in this folder, for a sync tool to share; empty for none
+    pub sync_folder: String,
+    // Scores the score graph with the engine instead of the quick estimate
+    pub review_engine: bool,
+    // How much disk the engine's scores kept from earlier reviews may take
+    pub analysis_cache_megabytes: u32,
+    // Where the broadcast page of a game is served
+    pub broadcast_port: u16,
+    // The broadcasts of other computers shown on the wall of boards
+    pub wall_addresses: Vec<String>,
+    // The web version of the app that shared links open; on the web, empty for this page
+    pub share_viewer: String,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            handicap: 0,
+            komi: consts::DEFAULT_KOMI,
+            rules: Rules::Japanese,
+            capture_goal: 0,
+            blind: false,
+            toroidal: false,
+            hidden_stones: 0,
+            opponent: Opponent::Human,
+            engine_command: consts::DEFAULT_ENGINE_COMMAND.to_string(),
+            human_color: Player::Black,
+            black_profile: String::new(),
+            white_profile: String::new(),
+            profile: String::new(),
+            rated: true,
+            lan_address: String::new(),
+            lan_port: consts::LAN_PORT,
+            lan_tls: true,
+            server: consts::DEFAULT_SERVER.to_string(),
+            room_code: String::new(),
+            server_account: String::new(),
+            server_token: String::new(),
+            invited: String::new(),
+            correspondence: false,
+            rengo: false,
+            pair_names: Default::default(),
+            simul_boards: 1,
+            ogs_username: String::new(),
+            igs_server: consts::IGS_SERVER.to_string(),
+            igs_account: String::new(),
+            igs_target: String::new(),
+            automatch: false,
+            match_sizes: vec![19],
+            match_speeds: vec![Speed::Live],
+            reconnect_seconds: consts::RECONNECT_SECONDS,
+            away_clock: AwayClock::Stops,
+            spectator_delay: 0,
+            spectator_delay_unit: DelayUnit::Moves,
+            chat_in_record: false,
+            confirm_moves: false,
+            warn_self_atari: true,
+            main_time: 0.0,
+            overtime: Overtime::None,
+            language: i18n::ENGLISH.to_string(),
+            coordinates: CoordinateStyle::Western,
+            ui_theme: UiTheme::Dark,
+            ui_scale: 1.0,
+            follow_system_scale: true,
+            board_theme: BoardTheme::Kaya,
+            skin: None,
+            marker_shape: MarkerShape::Circle,
+            high_contrast: false,
+            sound_pack: None,
+            stone_sound: true,
+            capture_sound: true,
+            game_end_sound: true,
+            time_warning_sound: true,
+            time_warning_volume: 1.0,
+            speak_moves: false,
+            speak_countdown: false,
+            countdown_language: CountdownLanguage::Interface,
+            speech_command: consts::DEFAULT_SPEECH_COMMAND.to_string(),
+            voice_moves: false,
+            notify_when_away: true,
+            voice_command: String::new(),
+            sync_folder: String::new(),
+            review_engine: false,
+            analysis_cache_megabytes: consts::ANALYSIS_CACHE_MEGABYTES,
+            broadcast_port: consts::BROADCAST_PORT,
+            wall_addresses: Vec::new(),
+            share_viewer: String::new(),
+        }
+    }
+}
+
+impl Settings {
+    pub fn plays(&self, sound: Sound) -> bool {
+        match sound {
+            Sound::Stone => self.stone_sound,
+            Sound::Capture => self.capture_sound,
+            Sound::GameEnd => self.game_end_sound,
+            Sound::TimeWarning => self.time_warning_sound,
+        }
+    }
+
+    /// Copies what the new game form chooses from `other`, leaving the preferences alone.
+    pub fn keep_game_choices(&mut self, other: &Settings) {
+        self.handicap = other.handicap;
+        self.komi = other.komi;
+        self.rules = other.rules;
+        self.capture_goal = other.capture_goal;
+        self.blind = other.blind;
+        self.toroidal = other.toroidal;
+        self.hidden_stones = other.hidden_stones;
+        self.main_time = other.main_time;
+        self.overtime = other.overtime;
+        self.opponent = other.opponent;
+        self.human_color = other.human_color;
+        self.black_profile = other.black_profile.clone();
+        self.white_profile = other.white_profile.clone();
+        self.profile = other.profile.clone();
+        self.rated = other.rated;
+        self.lan_address = other.lan_address.clone();
+        self.lan_port = other.lan_port;
+        self.lan_tls = other.lan_tls;
+        self.server = other.server.clone();
+        self.room_code = other.room_code.clone();
+        self.server_account = other.server_account.clone();
+        self.server_token = other.server_token.clone();
+        self.invited = other.invited.clone();
+        self.correspondence = other.correspondence;
+        self.rengo = other.rengo;
+        self.pair_names = other.pair_names.clone();
+        self.simul_boards = other.simul_boards;
+        self.ogs_username = other.ogs_username.clone();
+        self.igs_server = other.igs_server.clone();
+        self.igs_account = other.igs_account.clone();
+        self.igs_target = other.igs_target.clone();
+        self.automatch = other.automatch;
+        self.match_sizes = other.match_sizes.clone();
+        self.match_speeds = other.match_speeds.clone();
+        self.reconnect_seconds = other.reconnect_seconds;
+        self.away_clock = other.away_clock;
+        self.spectator_delay = other.spectator_delay;
+        self.spectator_delay_unit = other.spectator_delay_unit;
+    }
+}
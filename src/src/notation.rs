@@ -0,0 +1,95 @@
+use serde::{Deserialize, Serialize};
+
+const COLUMNS: &[u8] = b"ABCDEFGHJKLMNOPQRSTUVWXYZ";
+const KANJI_DIGITS: [&str; 10] = ["", "一", "二", "三", "四", "五", "六", "七", "八", "九"];
+
+#[derive(Clone, Copy, PartialEq, Debug, Serialize, Deserialize)]
+pub enum CoordinateStyle {
+    // Letters from the left and numbers from the bottom, e.g. Q16
+    Western,
+    // Column then row, both counted from the bottom left, e.g. 16-16
+    Numeric,
+    // Column from the right in digits and row from the top in kanji, e.g. 4の四
+    Japanese,
+}
+
+impl CoordinateStyle {
+    pub const ALL: [CoordinateStyle; 3] = [
+        CoordinateStyle::Western,
+        CoordinateStyle::Numeric,
+        CoordinateStyle::Japanese,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            CoordinateStyle::Western => "Western (Q16)",
+            CoordinateStyle::Numeric => "Numeric (16-16)",
+            CoordinateStyle::Japanese => "Japanese (4の四)",
+        }
+    }
+
+    /// The label of a column along the board edge.
+    pub fn column_label(self, col: usize, board_size: usize) -> String {
+        match self {
+            CoordinateStyle::Western => (COLUMNS[col] as char).to_string(),
+            CoordinateStyle::Numeric => (col + 1).to_string(),
+            CoordinateStyle::Japanese => (board_size - col).to_string(),
+        }
+    }
+
+    /// The label of a row along the board edge.
+    pub fn row_label(self, row: usize, board_size: usize) -> String {
+        match self {
+            CoordinateStyle::Western | CoordinateStyle::Numeric => (board_size - row).to_string(),
+            CoordinateStyle::Japanese => kanji(row + 1),
+        }
+    }
+
+    pub fn format_point(self, (row, col): (usize, usize), board_size: usize) -> String {
+        match self {
+            CoordinateStyle::Western => format_point((row, col), board_size),
+            CoordinateStyle::Numeric => format!(
+                "{}-{}",
+                self.column_label(col, board_size),
+                self.row_label(row, board_size)
+            ),
+            CoordinateStyle::Japanese => format!(
+                "{}の{}",
+                self.column_label(col, board_size),
+                self.row_label(row, board_size)
+            ),
+        }
+    }
+}
+
+// Kanji numerals up to 99, e.g. 十四
+fn kanji(number: usize) -> String {
+    let (tens, ones) = (number / 10, number % 10);
+    let tens = match tens {
+        0 => String::new(),
+        1 => "十".to_string(),
+        tens => format!("{}十", KANJI_DIGITS[tens]),
+    };
+    tens + KANJI_DIGITS[ones]
+}
+
+/// Formats a point in the usual Western style, e.g. `Q16`; the letter I is skipped.
+pub fn format_point((row, col): (usize, usize), board_size: usize) -> String {
+    format!("{}{}", COLUMNS[col] as char, board_size - row)
+}
+
+/// Parses a Western coordinate such as `q16` or `pass`; the outer `None` means invalid input.
+pub fn parse_point(text: &str, board_size: usize) -> Option<Option<(usize, usize)>> {
+    let text = text.trim().to_ascii_uppercase();
+    if text == "PASS" {
+        return Some(None);
+    }
+    let mut chars = text.chars();
+    let letter = chars.next()? as u8;
+    let col = COLUMNS.iter().position(|&c| c == letter)?;
+    let number: usize = chars.as_str().parse().ok()?;
+    if col >= board_size || number == 0 || number > board_size {
+        return None;
+    }
+    Some(Some((board_size - number, col)))
+}
@@ -0,0 +1,81 @@
+//! Desktop notifications of what happens in network games while the window is out of sight, such
+//! as the opponent's move. Clicking one, where the desktop tells, brings back the window on the
+//! tab of its game. Does nothing when built without the `notifications` feature.
+
+use std::sync::mpsc::{self, Receiver, Sender};
+
+use eframe::egui;
+
+pub struct Notifier {
+    // The tabs of the notifications clicked, sent by the threads waiting on them
+    #[cfg_attr(
+        not(all(feature = "notifications", unix, not(target_os = "macos"))),
+        allow(dead_code)
+    )]
+    sender: Sender<u64>,
+    clicked: Receiver<u64>,
+}
+
+impl Default for Notifier {
+    fn default() -> Self {
+        let (sender, clicked) = mpsc::channel();
+        Notifier { sender, clicked }
+    }
+}
+
+impl Notifier {
+    /// Shows `text` under `title`, as being about the tab `tab`.
+    #[cfg(feature = "notifications")]
+    pub fn show(
+        &self,
+        title: &str,
+        text: &str,
+        tab: u64,
+        ctx: &egui::Context,
+    ) -> Result<(), String> {
+        let mut notification = notify_rust::Notification::new();
+        notification
+            .appname(crate::consts::TITLE)
+            .summary(title)
+            .body(text);
+        // Only the freedesktop servers say which notification was clicked
+        #[cfg(all(unix, not(target_os = "macos")))]
+        {
+            let handle = notification
+                .action("default", &crate::i18n::tr("Show"))
+                .show()
+                .map_err(|err| err.to_string())?;
+            let (sender, ctx) = (self.sender.clone(), ctx.clone());
+            std::thread::spawn(move || {
+                handle.wait_for_action(|action| {
+                    if action == "default" {
+                        let _ = sender.send(tab);
+                        ctx.request_repaint();
+                    }
+                })
+            });
+        }
+        #[cfg(not(all(unix, not(target_os = "macos"))))]
+        {
+            let _ = (tab, ctx);
+            notification.show().map_err(|err| err.to_string())?;
+        }
+        Ok(())
+    }
+
+    #[cfg(not(feature = "notifications"))]
+    pub fn show(
+        &self,
+        _title: &str,
+        _text: &str,
+        _tab: u64,
+        _ctx: &egui::Context,
+    ) -> Result<(), String> {
+        Ok(())
+    }
+
+    /// The tabs of the notifications clicked since the last call.
+    pub fn clicked(&self) -> Vec<u64> {
+        self.clicked.try_iter().collect()
+    }
+}
@@ -0,0 +1,1014 @@
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc;
+use std::time::{Duration, Instant};
+
+use eframe::egui;
+use serde::Deserialize;
+use serde_json::{Value, json};
+
+use crate::board::{self, Move, Player};
+use crate::clock::Overtime;
+use crate::lan;
+use crate::protocol::Message;
+use crate::sgf;
+
+const SITE_URL: &str = "https://online-go.com";
+const API_URL: &str = "https://online-go.com/api/v1";
+const SOCKET_URL: &str = "wss://online-go.com/";
+// How long the game's data may take to arrive once connected
+const GAMEDATA_TIMEOUT: Duration = Duration::from_secs(15);
+// Reads give up after this, so the socket thread also gets to send what is queued
+const POLL_INTERVAL: Duration = Duration::from_millis(50);
+// OGS drops connections that stay quiet for long
+const PING_INTERVAL: Duration = Duration::from_secs(20);
+// How many of the live games with the strongest players are listed to watch
+const TOP_GAMES: usize = 20;
+// The number a command that OGS answers is sent with, which comes back with the answer
+const REPLY_ID: u64 = 1;
+
+#[derive(Debug)]
+pub enum Error {
+    Http(ureq::Error),
+    Io(std::io::Error),
+    UnknownPlayer(String),
+    LoginRefused,
+    // The realtime connection failed, or sent a game this app cannot play
+    Live(String),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Error::Http(err) => write!(f, "request failed: {}", err),
+            Error::Io(err) => write!(f, "could not save game: {}", err),
+            Error::UnknownPlayer(name) => write!(f, "no OGS player named \"{}\"", name),
+            Error::LoginRefused => write!(f, "wrong OGS username or password"),
+            Error::Live(reason) => write!(f, "{}", reason),
+        }
+    }
+}
+
+impl From<ureq::Error> for Error {
+    fn from(err: ureq::Error) -> Self {
+        Error::Http(err)
+    }
+}
+
+impl From<std::io::Error> for Error {
+    fn from(err: std::io::Error) -> Self {
+        Error::Io(err)
+    }
+}
+
+pub enum Response {
+    Game(Result<String, Error>),
+    PlayerGames(Result<Vec<PathBuf>, Error>),
+}
+
+/// A logged in OGS account: the cookies of the website's session for the REST API, and the
+/// token the realtime socket takes.
+#[derive(Clone)]
+pub struct Session {
+    pub username: String,
+    user_id: u64,
+    jwt: String,
+    cookie: String,
+    csrf: String,
+}
+
+/// The account's games in progress and the challenges waiting for an answer.
+#[derive(Default)]
+pub struct Lobby {
+    pub games: Vec<LiveGame>,
+    pub challenges: Vec<Challenge>,
+}
+
+pub struct LiveGame {
+    pub id: u64,
+    pub name: String,
+    pub board_size: usize,
+    pub black: String,
+    pub white: String,
+}
+
+pub struct Challenge {
+    pub id: u64,
+    pub challenger: String,
+    pub board_size: usize,
+    pub ranked: bool,
+}
+
+#[derive(Deserialize)]
+struct Page<T> {
+    results: Vec<T>,
+}
+
+#[derive(Deserialize)]
+struct Entry {
+    id: u64,
+}
+
+/// Accepts either a bare game ID or any `online-go.com/game/...` URL.
+pub fn parse_game_id(input: &str) -> Option<u64> {
+    let input = input.trim();
+    if let Ok(id) = input.parse() {
+        return Some(id);
+    }
+    let (_, rest) = input.split_once("online-go.com/game/")?;
+    let rest = rest.strip_prefix("view/").unwrap_or(rest);
+    let digits: String = rest.chars().take_while(|c| c.is_ascii_digit()).collect();
+    digits.parse().ok()
+}
+
+pub fn fetch_sgf(game_id: u64) -> Result<String, Error> {
+    let mut response = ureq::get(format!("{}/games/{}/sgf", API_URL, game_id)).call()?;
+    Ok(response.body_mut().read_to_string()?)
+}
+
+/// Downloads the most recent finished games of `username` into `dir`, returning the written files.
+pub fn fetch_player_games(username: &str, count: usize, dir: &Path) -> Result<Vec<PathBuf>, Error> {
+    let player = find_player(username)?;
+    let games: Page<Entry> = ureq::get(format!("{}/players/{}/games/", API_URL, player))
+        .query("ordering", "-ended")
+        .query("ended__isnull", "false")
+        .query("page_size", count.to_string())
+        .call()?
+        .body_mut()
+        .read_json()?;
+
+    std::fs::create_dir_all(dir)?;
+    let mut paths = Vec::new();
+    for game in games.results {
+        let path = dir.join(format!("ogs-{}.sgf", game.id));
+        std::fs::write(&path, fetch_sgf(game.id)?)?;
+        paths.push(path);
+    }
+    Ok(paths)
+}
+
+// The ID of the player named `username`
+fn find_player(username: &str) -> Result<u64, Error> {
+    let players: Page<Entry> = ureq::get(format!("{}/players/", API_URL))
+        .query("username", username)
+        .call()?
+        .body_mut()
+        .read_json()?;
+    players
+        .results
+        .first()
+        .map(|player| player.id)
+        .ok_or_else(|| Error::UnknownPlayer(username.to_string()))
+}
+
+/// The games `username` is playing now, for watching.
+pub fn player_games(username: &str) -> Result<Vec<LiveGame>, Error> {
+    let player = find_player(username)?;
+    let games: Page<GameEntry> = ureq::get(format!("{}/players/{}/games/", API_URL, player))
+        .query("ended__isnull", "true")
+        .query("page_size", "50")
+        .call()?
+        .body_mut()
+        .read_json()?;
+    Ok(games.results.into_iter().map(GameEntry::live).collect())
+}
+
+/// The live games with the strongest players, as the website's list of games to watch has them.
+pub fn top_games() -> Result<Vec<LiveGame>, Error> {
+    let mut socket = open_socket()?;
+    let query = json!({
+        "list": "live",
+        "sort_by": "rank",
+        "where": {},
+        "from": 0,
+        "limit": TOP_GAMES,
+    });
+    let reply = request(&mut socket, "gamelist/query", query)?;
+    let _ = socket.close(None);
+    let _ = socket.flush();
+    let listed: Page<ListedGame> = serde_json::from_value(reply)
+        .map_err(|err| Error::Live(format!("OGS sent a list it did not explain: {}", err)))?;
+    Ok(listed
+        .results
+        .into_iter()
+        .map(|game| LiveGame {
+            id: game.id,
+            name: game.name,
+            board_size: game.width,
+            black: game.black.label(),
+            white: game.white.label(),
+        })
+        .collect())
+}
+
+#[derive(Deserialize)]
+struct Config {
+    user_jwt: String,
+    #[serde(default)]
+    csrf_token: String,
+    user: User,
+}
+
+#[derive(Default, Deserialize)]
+#[serde(default)]
+struct User {
+    id: u64,
+    username: String,
+}
+
+#[derive(Default, Deserialize)]
+#[serde(default)]
+struct Players {
+    black: User,
+    white: User,
+}
+
+#[derive(Default, Deserialize)]
+#[serde(default)]
+struct GameEntry {
+    id: u64,
+    name: String,
+    width: usize,
+    players: Players,
+}
+
+impl GameEntry {
+    fn live(self) -> LiveGame {
+        LiveGame {
+            id: self.id,
+            name: self.name,
+            board_size: self.width,
+            black: self.players.black.username,
+            white: self.players.white.username,
+        }
+    }
+}
+
+// A game of the socket's lists of games, whose players come with their ranks
+#[derive(Default, Deserialize)]
+#[serde(default)]
+struct ListedGame {
+    id: u64,
+    name: String,
+    width: usize,
+    black: ListedPlayer,
+    white: ListedPlayer,
+}
+
+#[derive(Default, Deserialize)]
+#[serde(default)]
+struct ListedPlayer {
+    username: String,
+    // 30 is 1 dan, and 29 is 1 kyu
+    rank: Option<f64>,
+}
+
+impl ListedPlayer {
+    fn label(self) -> String {
+        match self.rank {
+            Some(rank) if rank >= 30.0 => format!("{} [{}d]", self.username, rank as u32 - 29),
+            Some(rank) => format!("{} [{}k]", self.username, 30 - rank.max(0.0) as u32),
+            None => self.username,
+        }
+    }
+}
+
+#[derive(Default, Deserialize)]
+#[serde(default)]
+struct ChallengeGame {
+    width: usize,
+    ranked: bool,
+}
+
+#[derive(Default, Deserialize)]
+#[serde(default)]
+struct ChallengeEntry {
+    id: u64,
+    challenger: User,
+    game: ChallengeGame,
+}
+
+// Makes a request as the website would in the session's browser
+fn with_session<B>(builder: ureq::RequestBuilder<B>, session: &Session) -> ureq::RequestBuilder<B> {
+    let builder = builder
+        .header("Cookie", &session.cookie)
+        .header("Referer", format!("{}/", SITE_URL));
+    if session.csrf.is_empty() {
+        builder
+    } else {
+        builder.header("X-CSRFToken", &session.csrf)
+    }
+}
+
+/// Logs in to online-go.com like its website, and looks up the games and challenges waiting.
+pub fn log_in(username: &str, password: &str) -> Result<(Session, Lobby), Error> {
+    let response = ureq::post(format!("{}/api/v0/login", SITE_URL))
+        .send_json(json!({ "username": username, "password": password }))
+        .map_err(|err| match err {
+            ureq::Error::StatusCode(400..=403) => Error::LoginRefused,
+            err => Error::Http(err),
+        })?;
+    // Later requests send back the session's cookies, of which the CSRF token is one
+    let cookies: Vec<String> = response
+        .headers()
+        .get_all("set-cookie")
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .filter_map(|value| value.split(';').next())
+        .map(|pair| pair.trim().to_string())
+        .collect();
+    let csrf = cookies
+        .iter()
+        .find_map(|pair| pair.strip_prefix("csrftoken="))
+        .unwrap_or_default()
+        .to_string();
+    let mut session = Session {
+        username: username.to_string(),
+        user_id: 0,
+        jwt: String::new(),
+        cookie: cookies.join("; "),
+        csrf,
+    };
+    let config: Config = with_session(ureq::get(format!("{}/ui/config", API_URL)), &session)
+        .call()?
+        .body_mut()
+        .read_json()?;
+    session.username = config.user.username;
+    session.user_id = config.user.id;
+    session.jwt = config.user_jwt;
+    if !config.csrf_token.is_empty() {
+        session.csrf = config.csrf_token;
+    }
+    let lobby = lobby(&session)?;
+    Ok((session, lobby))
+}
+
+/// The account's games that have not ended, and the challenges sent to it.
+pub fn lobby(session: &Session) -> Result<Lobby, Error> {
+    let games: Page<GameEntry> = with_session(
+        ureq::get(format!("{}/players/{}/games/", API_URL, session.user_id)),
+        session,
+    )
+    .query("ended__isnull", "true")
+    .query("page_size", "50")
+    .call()?
+    .body_mut()
+    .read_json()?;
+    let challenges: Page<ChallengeEntry> =
+        with_session(ureq::get(format!("{}/me/challenges/", API_URL)), session)
+            .call()?
+            .body_mut()
+            .read_json()?;
+    Ok(Lobby {
+        games: games.results.into_iter().map(GameEntry::live).collect(),
+        challenges: challenges
+            .results
+            .into_iter()
+            .map(|challenge| Challenge {
+                id: challenge.id,
+                challenger: challenge.challenger.username,
+                board_size: challenge.game.width,
+                ranked: challenge.game.ranked,
+            })
+            .collect(),
+    })
+}
+
+/// The lobby after answering a challenge, with the game it started if OGS said which.
+pub type Answered = Result<(Option<u64>, Lobby), Error>;
+
+/// Accepts a challenge, which starts its game.
+pub fn accept(session: &Session, challenge: u64) -> Answered {
+    let text = with_session(
+        ureq::post(format!("{}/me/challenges/{}/accept", API_URL, challenge)),
+        session,
+    )
+    .send_json(json!({}))?
+    .body_mut()
+    .read_to_string()?;
+    let game = serde_json::from_str::<Value>(&text)
+        .ok()
+        .and_then(|reply| reply.get("game").or(reply.get("game_id"))?.as_u64());
+    Ok((game, lobby(session)?))
+}
+
+pub fn decline(session: &Session, challenge: u64) -> Answered {
+    with_session(
+        ureq::delete(format!("{}/me/challenges/{}", API_URL, challenge)),
+        session,
+    )
+    .call()?;
+    Ok((None, lobby(session)?))
+}
+
+type Socket = tungstenite::WebSocket<tungstenite::stream::MaybeTlsStream<std::net::TcpStream>>;
+
+// Commands and events of the realtime socket are JSON arrays of a name and its data
+fn send_command(socket: &mut Socket, name: &str, data: Value) -> Result<(), Error> {
+    socket
+        .send(tungstenite::Message::text(json!([name, data]).to_string()))
+        .map_err(live_error)
+}
+
+// The next event, or none when a read gives up or the frame is something else
+fn read_event(socket: &mut Socket) -> Result<Option<(String, Value)>, Error> {
+    let mut parts = read_parts(socket)?.unwrap_or_default().into_iter();
+    Ok(match (parts.next(), parts.next()) {
+        (Some(Value::String(name)), data) => Some((name, data.unwrap_or(Value::Null))),
+        _ => None,
+    })
+}
+
+// Sends a command that OGS answers, and waits for the answer
+fn request(socket: &mut Socket, name: &str, data: Value) -> Result<Value, Error> {
+    socket
+        .send(tungstenite::Message::text(
+            json!([name, data, REPLY_ID]).to_string(),
+        ))
+        .map_err(live_error)?;
+    let started = Instant::now();
+    while started.elapsed() < GAMEDATA_TIMEOUT {
+        let mut parts = read_parts(socket)?.unwrap_or_default().into_iter();
+        if parts.next().and_then(|id| id.as_u64()) == Some(REPLY_ID) {
+            return Ok(parts.next().unwrap_or(Value::Null));
+        }
+    }
+    Err(Error::Live("OGS did not answer".to_string()))
+}
+
+// The parts of the next frame, or none when a read gives up or the frame is something else
+fn read_parts(socket: &mut Socket) -> Result<Option<Vec<Value>>, Error> {
+    match socket.read() {
+        Ok(tungstenite::Message::Text(text)) => match serde_json::from_str(&text) {
+            Ok(Value::Array(parts)) => Ok(Some(parts)),
+            _ => Ok(None),
+        },
+        Ok(tungstenite::Message::Close(_)) => Err(live_error(tungstenite::Error::ConnectionClosed)),
+        Ok(_) => Ok(None),
+        Err(tungstenite::Error::Io(err))
+            if matches!(
+                err.kind(),
+                std::io::ErrorKind::WouldBlock | std::io::ErrorKind::TimedOut
+            ) =>
+        {
+            Ok(None)
+        }
+        Err(err) => Err(live_error(err)),
+    }
+}
+
+fn live_error(err: tungstenite::Error) -> Error {
+    Error::Live(format!("OGS connection failed: {}", err))
+}
+
+// The realtime socket, whose reads give up after a while
+fn open_socket() -> Result<Socket, Error> {
+    let (socket, _) = tungstenite::connect(SOCKET_URL).map_err(live_error)?;
+    if let tungstenite::stream::MaybeTlsStream::Rustls(stream) = socket.get_ref() {
+        stream.get_ref().set_read_timeout(Some(POLL_INTERVAL))?;
+    }
+    Ok(socket)
+}
+
+/// Opens game `game` on OGS as a network game: the record so far comes first, and then the moves,
+/// chat, counting and result as they happen there, while the messages sent are played there.
+/// Without a session, or in a game of others, it is only watched, and nothing goes back.
+pub fn play(
+    session: Option<&Session>,
+    game: u64,
+    ctx: egui::Context,
+) -> std::io::Result<lan::Connection> {
+    connect_game(session, game, ctx).map_err(|err| std::io::Error::other(err.to_string()))
+}
+
+fn connect_game(
+    session: Option<&Session>,
+    game: u64,
+    ctx: egui::Context,
+) -> Result<lan::Connection, Error> {
+    let mut socket = open_socket()?;
+    if let Some(session) = session {
+        let hello = json!({
+            "jwt": session.jwt,
+            "device_id": "go-game-rust",
+            "user_agent": crate::consts::SGF_APPLICATION,
+        });
+        send_command(&mut socket, "authenticate", hello)?;
+    }
+    let watch = json!({ "game_id": game, "chat": true });
+    send_command(&mut socket, "game/connect", watch)?;
+    let started = Instant::now();
+    let gamedata = format!("game/{}/gamedata", game);
+    let data = loop {
+        if started.elapsed() > GAMEDATA_TIMEOUT {
+            return Err(Error::Live(format!("OGS did not send game {}", game)));
+        }
+        match read_event(&mut socket)? {
+            Some((name, data)) if name == gamedata => break data,
+            _ => {}
+        }
+    };
+    let user = session.map_or(0, |session| session.user_id);
+    let (mut live, opening) = Live::new(game, user, &data)?;
+
+    let (outgoing, queued) = mpsc::channel::<Message>();
+    let (sender, incoming) = mpsc::channel();
+    std::thread::spawn(move || {
+        for message in opening {
+            if sender.send(message).is_err() {
+                return;
+            }
+        }
+        ctx.request_repaint();
+        let mut pinged = Instant::now();
+        'connected: loop {
+            loop {
+                match queued.try_recv() {
+                    Ok(message) => {
+                        let mut replies = Vec::new();
+                        for (name, data) in live.outgoing(message, &mut replies) {
+                            if send_command(&mut socket, name, data).is_err() {
+                                break 'connected;
+                            }
+                        }
+                        for reply in replies {
+                            let _ = sender.send(reply);
+                            ctx.request_repaint();
+                        }
+                    }
+                    Err(mpsc::TryRecvError::Empty) => break,
+                    // The game was closed here, and stays on at OGS
+                    Err(mpsc::TryRecvError::Disconnected) => {
+                        let _ = send_command(
+                            &mut socket,
+                            "game/disconnect",
+                            json!({ "game_id": game }),
+                        );
+                        let _ = socket.close(None);
+                        let _ = socket.flush();
+                        return;
+                    }
+                }
+            }
+            if pinged.elapsed() >= PING_INTERVAL {
+                pinged = Instant::now();
+                let now = std::time::SystemTime::now()
+                    .duration_since(std::time::UNIX_EPOCH)
+                    .map_or(0, |since| since.as_millis() as u64);
+                let ping = json!({ "client": now, "drift": 0, "latency": 0 });
+                if send_command(&mut socket, "net/ping", ping).is_err() {
+                    break;
+                }
+            }
+            match read_event(&mut socket) {
+                Ok(Some((name, data))) => {
+                    for message in live.incoming(&name, &data) {
+                        if sender.send(message).is_err() {
+                            return;
+                        }
+                        ctx.request_repaint();
+                    }
+                }
+                Ok(None) => {}
+                Err(_) => break,
+            }
+        }
+        let _ = sender.send(Message::Bye);
+        ctx.request_repaint();
+    });
+    Ok(lan::Connection::relayed(outgoing, incoming))
+}
+
+// Milliseconds since the epoch, as OGS times its clocks
+fn now_millis() -> f64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map_or(0.0, |since| since.as_millis() as f64)
+}
+
+// A point of an OGS move given as `[x, y]`, where -1 passes
+fn ogs_point(x: &Value, y: &Value) -> Option<(usize, usize)> {
+    Some((y.as_u64()? as usize, x.as_u64()? as usize))
+}
+
+// Points written as SGF pairs one after the other, as OGS lists stones
+fn parse_points(text: &str, board_size: usize) -> Vec<(usize, usize)> {
+    text.as_bytes()
+        .chunks(2)
+        .filter_map(|pair| sgf::parse_point(std::str::from_utf8(pair).ok()?, board_size))
+        .collect()
+}
+
+fn format_points(points: &[(usize, usize)]) -> String {
+    points
+        .iter()
+        .map(|&point| sgf::format_point(point))
+        .collect()
+}
+
+// What goes on between this app and one OGS game, translated both ways
+struct Live {
+    game: u64,
+    user: u64,
+    black: u64,
+    white: u64,
+    board_size: usize,
+    // Handicap stones OGS has as moves, which the record here sets up instead
+    placed: usize,
+    first: Player,
+    // Moves after the setup, counting those sent from here that OGS has not echoed yet
+    moves: usize,
+    unconfirmed: usize,
+    // An undo accepted here, for which the echo is not passed on
+    accepting_undo: bool,
+    // The stones marked dead once both passed
+    removed: Vec<(usize, usize)>,
+    finished: bool,
+}
+
+impl Live {
+    // The record and the moves of the game data sent on connecting, as the messages that load it
+    fn new(game: u64, user: u64, data: &Value) -> Result<(Live, Vec<Message>), Error> {
+        let width = data["width"].as_u64().unwrap_or(19) as usize;
+        if data["height"]
+            .as_u64()
+            .is_some_and(|height| height as usize != width)
+        {
+            return Err(Error::Live(
+                "this app only plays OGS games on square boards".to_string(),
+            ));
+        }
+        let player_id = |color: &str| {
+            data["players"][color]["id"]
+                .as_u64()
+                .or(data[format!("{}_player_id", color)].as_u64())
+                .unwrap_or_default()
+        };
+        let mut live = Live {
+            game,
+            user,
+            black: player_id("black"),
+            white: player_id("white"),
+            board_size: width,
+            placed: 0,
+            first: if data["initial_player"] == "white" {
+                Player::White
+            } else {
+                Player::Black
+            },
+            moves: 0,
+            unconfirmed: 0,
+            accepting_undo: false,
+            removed: parse_points(data["removed"].as_str().unwrap_or_default(), width),
+            finished: false,
+        };
+        let moves: Vec<(Option<(usize, usize)>, f32)> = data["moves"]
+            .as_array()
+            .into_iter()
+            .flatten()
+            .map(|played| {
+                let seconds = played[2].as_f64().unwrap_or_default() / 1000.0;
+                (ogs_point(&played[0], &played[1]), seconds as f32)
+            })
+            .collect();
+
+        let mut root = sgf::Node::default();
+        root.set("GM", vec!["1".to_string()]);
+        root.set("FF", vec!["4".to_string()]);
+        root.set("CA", vec!["UTF-8".to_string()]);
+        root.set("SZ", vec![width.to_string()]);
+        for (ident, value) in [
+            ("PB", &data["players"]["black"]["username"]),
+            ("PW", &data["players"]["white"]["username"]),
+            ("GN", &data["game_name"]),
+            ("RU", &data["rules"]),
+        ] {
+            if let Some(text) = value.as_str() {
+                root.set(ident, vec![text.to_string()]);
+            }
+        }
+        root.set(
+            "KM",
+            vec![data["komi"].as_f64().unwrap_or_default().to_string()],
+        );
+        root.set("PC", vec![format!("{}/game/{}", SITE_URL, game)]);
+        let (time_limit, overtime) = time_control(&data["time_control"]);
+        if time_limit > 0.0 {
+            root.set("TM", vec![time_limit.to_string()]);
+        }
+        if let Some(overtime) = overtime.to_sgf() {
+            root.set("OT", vec![overtime]);
+        }
+
+        let mut black_stones = parse_points(
+            data["initial_state"]["black"].as_str().unwrap_or_default(),
+            width,
+        );
+        let white_stones = parse_points(
+            data["initial_state"]["white"].as_str().unwrap_or_default(),
+            width,
+        );
+        // Handicap stones are Black's first moves at OGS, or already on the board
+        let handicap = data["handicap"].as_u64().unwrap_or_default() as usize;
+        if handicap > 1 {
+            let fixed = board::handicap_points(width, handicap as u32);
+            let free = data["free_handicap_placement"]
+                .as_bool()
+                .unwrap_or_default();
+            let played: Vec<(usize, usize)> = moves
+                .iter()
+                .take(handicap)
+                .filter_map(|&(point, _)| point)
+                .collect();
+            if played.len() == handicap
+                && (free || fixed.iter().all(|point| played.contains(point)))
+            {
+                black_stones.extend(played);
+                live.placed = handicap;
+            } else if free {
+                return Err(Error::Live(
+                    "place the handicap stones on online-go.com, then open the game again"
+                        .to_string(),
+                ));
+            } else {
+                black_stones.extend(fixed);
+            }
+            live.first = Player::White;
+            root.set("HA", vec![handicap.to_string()]);
+        }
+        root.set(
+            "AB",
+            black_stones
+                .iter()
+                .map(|&point| sgf::format_point(point))
+                .collect(),
+        );
+        root.set(
+            "AW",
+            white_stones
+                .iter()
+                .map(|&point| sgf::format_point(point))
+                .collect(),
+        );
+        if !black_stones.is_empty() || !white_stones.is_empty() || live.first == Player::White {
+            root.set("PL", vec![crate::gtp::color(live.first).to_string()]);
+        }
+
+        let sgf = sgf::to_string(&root);
+        let mut opening = vec![match live.own_color() {
+            Some(color) => Message::Resume { color, sgf },
+            None => Message::Watch { sgf },
+        }];
+        for &(point, seconds) in &moves[live.placed..] {
+            let played: Move = (live.color(live.moves), point);
+            live.moves += 1;
+            opening.push(Message::Play { played, seconds });
+        }
+        let last_move = data["clock"]["last_move"]
+            .as_f64()
+            .unwrap_or_else(now_millis);
+        opening.push(Message::Thinking(
+            ((now_millis() - last_move) / 1000.0).max(0.0) as f32,
+        ));
+        opening.extend(live.phase(data));
+        Ok((live, opening))
+    }
+
+    fn player(&self, id: u64) -> Option<Player> {
+        if id == self.black {
+            Some(Player::Black)
+        } else if id == self.white {
+            Some(Player::White)
+        } else {
+            None
+        }
+    }
+
+    // The color of the account logged in here, none when only watching
+    fn own_color(&self) -> Option<Player> {
+        if self.user == 0 {
+            None
+        } else {
+            self.player(self.user)
+        }
+    }
+
+    // Who plays the move after `moves` of them
+    fn color(&self, moves: usize) -> Player {
+        if moves.is_multiple_of(2) {
+            self.first
+        } else {
+            self.first.other()
+        }
+    }
+
+    // Counting or the end, from game data that may have come again after a change
+    fn phase(&mut self, data: &Value) -> Vec<Message> {
+        match data["phase"].as_str() {
+            Some("stone removal") => {
+                self.removed = parse_points(
+                    data["removed"].as_str().unwrap_or_default(),
+                    self.board_size,
+                );
+                vec![Message::Dead(self.removed.clone())]
+            }
+            Some("finished") if !self.finished => {
+                self.finished = true;
+                let winner = data["winner"].as_u64().and_then(|id| self.player(id));
+                let outcome = data["outcome"].as_str().unwrap_or_default();
+                let result = match winner {
+                    Some(winner) => {
+                        let how = match outcome {
+                            "Resignation" => "R",
+                            "Timeout" => "T",
+                            _ => outcome
+                                .strip_suffix(" points")
+                                .filter(|points| points.parse::<f32>().is_ok())
+                                .unwrap_or("F"),
+                        };
+                        format!("{}+{}", crate::gtp::color(winner), how)
+                    }
+                    None => "Void".to_string(),
+                };
+                vec![Message::Result(result)]
+            }
+            _ => Vec::new(),
+        }
+    }
+
+    // An event of the socket as the messages for this app
+    fn incoming(&mut self, name: &str, data: &Value) -> Vec<Message> {
+        let Some(event) = name.strip_prefix(&format!("game/{}/", self.game)) else {
+            return Vec::new();
+        };
+        match event {
+            "move" => {
+                if self.unconfirmed > 0 {
+                    self.unconfirmed -= 1;
+                    return Vec::new();
+                }
+                let played = &data["move"];
+                let point = ogs_point(&played[0], &played[1]);
+                let seconds = played[2].as_f64().unwrap_or_default() / 1000.0;
+                let played: Move = (self.color(self.moves), point);
+                self.moves += 1;
+                vec![Message::Play {
+                    played,
+                    seconds: seconds as f32,
+                }]
+            }
+            "gamedata" => self.phase(data),
+            "phase" if data == "stone removal" => vec![Message::Dead(self.removed.clone())],
+            "removed_stones" => {
+                self.removed = parse_points(
+                    data["all_removed"].as_str().unwrap_or_default(),
+                    self.board_size,
+                );
+                vec![Message::Dead(self.removed.clone())]
+            }
+            "undo_requested" => vec![Message::UndoRequest(1)],
+            "undo_accepted" if std::mem::take(&mut self.accepting_undo) => Vec::new(),
+            "undo_accepted" => {
+                self.moves = self.moves.saturating_sub(1);
+                vec![Message::UndoAccept(1)]
+            }
+            "chat" => {
+                let line = &data["line"];
+                let from = line["player_id"].as_u64().unwrap_or_default();
+                match line["body"].as_str() {
+                    Some(text) if from != self.user => vec![Message::Chat {
+                        from: self.player(from),
+                        text: text.to_string(),
+                    }],
+                    _ => Vec::new(),
+                }
+            }
+            _ => Vec::new(),
+        }
+    }
+
+    // A message of this app as commands for the socket, with any answer it gets at once in
+    // `replies`
+    fn outgoing(
+        &mut self,
+        message: Message,
+        replies: &mut Vec<Message>,
+    ) -> Vec<(&'static str, Value)> {
+        let game_id = self.game;
+        // A game watched here is only followed, chat included
+        if self.own_color().is_none() {
+            return Vec::new();
+        }
+        match message {
+            Message::Play {
+                played: (_, point), ..
+            } => {
+                self.moves += 1;
+                self.unconfirmed += 1;
+                let played = point.map_or_else(|| "..".to_string(), sgf::format_point);
+                vec![("game/move", json!({ "game_id": game_id, "move": played }))]
+            }
+            Message::Resign(_) => vec![("game/resign", json!({ "game_id": game_id }))],
+            Message::Chat { text, .. } => vec![(
+                "game/chat",
+                json!({
+                    "game_id": game_id,
+                    "body": text,
+                    "type": "main",
+                    "move_number": self.placed + self.moves,
+                }),
+            )],
+            // OGS only takes back the last move, right after the player made it
+            Message::UndoRequest(1) => vec![(
+                "game/undo/request",
+                json!({ "game_id": game_id, "move_number": self.placed + self.moves }),
+            )],
+            Message::UndoRequest(_) => {
+                replies.push(Message::UndoDecline);
+                Vec::new()
+            }
+            Message::UndoAccept(_) => {
+                let move_number = self.placed + self.moves;
+                self.moves = self.moves.saturating_sub(1);
+                self.accepting_undo = true;
+                vec![(
+                    "game/undo/accept",
+                    json!({ "game_id": game_id, "move_number": move_number }),
+                )]
+            }
+            // Accepting the count: the marks made here replace those at OGS first
+            Message::Dead(mut stones) => {
+                stones.sort_unstable();
+                let revived: Vec<_> = self
+                    .removed
+                    .iter()
+                    .filter(|point| !stones.contains(point))
+                    .copied()
+                    .collect();
+                let killed: Vec<_> = stones
+                    .iter()
+                    .filter(|point| !self.removed.contains(point))
+                    .copied()
+                    .collect();
+                let mut commands = Vec::new();
+                for (removed, points) in [(false, revived), (true, killed)] {
+                    if !points.is_empty() {
+                        let stones = format_points(&points);
+                        commands.push((
+                            "game/removed_stones/set",
+                            json!({ "game_id": game_id, "removed": removed, "stones": stones }),
+                        ));
+                    }
+                }
+                let accepted = format_points(&stones);
+                commands.push((
+                    "game/removed_stones/accept",
+                    json!({ "game_id": game_id, "stones": accepted, "strict_seki_mode": false }),
+                ));
+                self.removed = stones;
+                commands
+            }
+            _ => Vec::new(),
+        }
+    }
+}
+
+// The main time in seconds and the overtime of an OGS time control
+fn time_control(control: &Value) -> (f32, Overtime) {
+    let seconds = |field: &str| control[field].as_f64().unwrap_or_default() as f32;
+    let count = |field: &str| control[field].as_u64().unwrap_or_default() as u32;
+    match control["system"]
+        .as_str()
+        .or(control["time_control"].as_str())
+    {
+        Some("byoyomi") => (
+            seconds("main_time"),
+            Overtime::ByoYomi {
+                periods: count("periods"),
+                seconds: seconds("period_time"),
+            },
+        ),
+        Some("canadian") => (
+            seconds("main_time"),
+            Overtime::Canadian {
+                stones: count("stones_per_period"),
+                seconds: seconds("period_time"),
+            },
+        ),
+        Some("fischer") => (
+            seconds("initial_time"),
+            Overtime::Fischer {
+                increment: seconds("time_increment"),
+            },
+        ),
+        Some("simple") => (
+            0.0,
+            Overtime::PerMove {
+                seconds: seconds("per_move"),
+                auto_pass: false,
+            },
+        ),
+        Some("absolute") => (seconds("total_time"), Overtime::None),
+        _ => (0.0, Overtime::None),
+    }
+}
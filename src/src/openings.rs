@@ -0,0 +1,135 @@
+//! Names for well-known openings and corner patterns. Each is kept as it looks in one corner or
+//! on one side, and found on the board in any of its turns and reflections, for either color.
+
+use crate::board::{Board, Player, Stone};
+use crate::consts;
+use crate::pattern;
+
+// A whole-board opening on 19x19, by the stones of the player who chose it
+struct Fuseki {
+    name: &'static str,
+    stones: &'static [(usize, usize)],
+}
+
+const FUSEKI: [Fuseki; 5] = [
+    Fuseki {
+        name: "Nirensei",
+        stones: &[(3, 15), (15, 15)],
+    },
+    Fuseki {
+        name: "Sanrensei",
+        stones: &[(3, 15), (9, 15), (15, 15)],
+    },
+    Fuseki {
+        name: "Chinese opening",
+        stones: &[(3, 15), (15, 16), (9, 16)],
+    },
+    Fuseki {
+        name: "High Chinese opening",
+        stones: &[(3, 15), (15, 16), (9, 15)],
+    },
+    Fuseki {
+        name: "Diagonal star points",
+        stones: &[(3, 3), (15, 15)],
+    },
+];
+
+// A corner pattern as seen in the top left corner, with `true` for the stones of the player who
+// played there first
+struct Joseki {
+    name: &'static str,
+    stones: &'static [((usize, usize), bool)],
+}
+
+const JOSEKI: [Joseki; 8] = [
+    Joseki {
+        name: "3-3 invasion joseki",
+        stones: &[((3, 3), true), ((2, 2), false)],
+    },
+    Joseki {
+        name: "Star point, low approach",
+        stones: &[((3, 3), true), ((2, 5), false)],
+    },
+    Joseki {
+        name: "Star point, high approach",
+        stones: &[((3, 3), true), ((3, 5), false)],
+    },
+    Joseki {
+        name: "Star point, small knight enclosure",
+        stones: &[((3, 3), true), ((2, 5), true)],
+    },
+    Joseki {
+        name: "3-4 point, small knight approach",
+        stones: &[((3, 2), true), ((2, 4), false)],
+    },
+    Joseki {
+        name: "3-4 point, one-space high approach",
+        stones: &[((3, 2), true), ((3, 4), false)],
+    },
+    Joseki {
+        name: "3-4 point, small knight enclosure",
+        stones: &[((3, 2), true), ((2, 4), true)],
+    },
+    Joseki {
+        name: "3-4 point, one-space enclosure",
+        stones: &[((3, 2), true), ((3, 4), true)],
+    },
+];
+
+/// The name of the opening on the board while the game is young, from the patterns kept for
+/// 19x19.
+pub fn fuseki(board: &Board, moves: usize) -> Option<&'static str> {
+    if board.size() != 19 || moves > consts::FUSEKI_MOVES {
+        return None;
+    }
+    FUSEKI
+        .iter()
+        .filter(|fuseki| {
+            (0..8).any(|transform| {
+                [Stone::Black, Stone::White].into_iter().any(|stone| {
+                    fuseki.stones.iter().all(|&point| {
+                        let (row, col) = pattern::transform(point, transform, 19);
+                        board[row][col] == stone
+                    })
+                })
+            })
+        })
+        // The first of the biggest
+        .rev()
+        .max_by_key(|fuseki| fuseki.stones.len())
+        .map(|fuseki| fuseki.name)
+}
+
+/// The name of the corner pattern in the corner that `point` was played in: the biggest of those
+/// kept that the corner holds.
+pub fn joseki(board: &Board, point: (usize, usize)) -> Option<&'static str> {
+    let size = board.size();
+    let reach = consts::JOSEKI_CORNER;
+    if size < 2 * reach {
+        return None;
+    }
+    let mut best: Option<&Joseki> = None;
+    // Each symmetry brings one corner to the top left, one way round or the other
+    for transform in 0..8 {
+        let (row, col) = pattern::transform(point, transform, size);
+        if row >= reach || col >= reach {
+            continue;
+        }
+        let stone_at = |point: (usize, usize)| {
+            let (row, col) = pattern::transform(point, pattern::inverse(transform), size);
+            board[row][col]
+        };
+        for first in [Player::Black, Player::White] {
+            for joseki in &JOSEKI {
+                let holds = joseki.stones.iter().all(|&(point, first_player)| {
+                    let player = if first_player { first } else { first.other() };
+                    stone_at(point) == player.to_stone()
+                });
+                if holds && best.is_none_or(|best| joseki.stones.len() > best.stones.len()) {
+                    best = Some(joseki);
+                }
+            }
+        }
+    }
+    best.map(|joseki| joseki.name)
+}
@@ -0,0 +1,88 @@
+use std::path::PathBuf;
+
+use crate::consts;
+
+pub fn data_dir() -> PathBuf {
+    dirs::data_dir()
+        .unwrap_or_else(|| PathBuf::from("."))
+        .join(consts::APP_DIR_NAME)
+}
+
+pub fn games_dir() -> PathBuf {
+    data_dir().join("games")
+}
+
+pub fn problems_dir() -> PathBuf {
+    data_dir().join("problems")
+}
+
+pub fn skins_dir() -> PathBuf {
+    data_dir().join("skins")
+}
+
+pub fn sounds_dir() -> PathBuf {
+    data_dir().join("sounds")
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+pub fn scripts_dir() -> PathBuf {
+    data_dir().join("scripts")
+}
+
+pub fn locales_dir() -> PathBuf {
+    data_dir().join("locales")
+}
+
+// Games downloaded from public archives, a folder for each
+#[cfg(not(target_arch = "wasm32"))]
+pub fn archives_dir() -> PathBuf {
+    data_dir().join("archives")
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+pub fn archives_path() -> PathBuf {
+    data_dir().join("archives.json")
+}
+
+pub fn analysis_cache_path() -> PathBuf {
+    data_dir().join("analysis_cache.bin")
+}
+
+pub fn database_path() -> PathBuf {
+    data_dir().join("database.json")
+}
+
+pub fn problem_sets_path() -> PathBuf {
+    data_dir().join("problem_sets.json")
+}
+
+pub fn daily_path() -> PathBuf {
+    data_dir().join("daily.json")
+}
+
+pub fn profiles_path() -> PathBuf {
+    data_dir().join("profiles.json")
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+pub fn log_path() -> PathBuf {
+    data_dir().join("go-game.log")
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+pub fn tournaments_dir() -> PathBuf {
+    data_dir().join("tournaments")
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+pub fn correspondence_path() -> PathBuf {
+    data_dir().join("correspondence.json")
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+pub fn config_path() -> PathBuf {
+    dirs::config_dir()
+        .unwrap_or_else(|| PathBuf::from("."))
+        .join(consts::APP_DIR_NAME)
+        .join("config.toml")
+}
@@ -0,0 +1,206 @@
+use std::collections::HashMap;
+use std::path::PathBuf;
+
+use crate::board::{Board, Player, Stone};
+use crate::database::Record;
+
+// One orientation of the selected region: its cells after a symmetry and an optional color swap
+struct Variant {
+    transform: usize,
+    cells: Vec<((usize, usize), Stone)>,
+    rows: (usize, usize),
+    cols: (usize, usize),
+}
+
+pub struct Pattern {
+    board_size: usize,
+    variants: Vec<Variant>,
+}
+
+pub struct Match {
+    pub path: PathBuf,
+    pub black: String,
+    pub white: String,
+    pub move_number: usize,
+}
+
+pub struct Continuation {
+    // `None` when the next move was a pass or outside the selected region
+    pub point: Option<(usize, usize)>,
+    pub count: usize,
+    pub wins: usize,
+}
+
+#[derive(Default)]
+pub struct SearchResult {
+    pub games_searched: usize,
+    pub matches: Vec<Match>,
+    pub continuations: Vec<Continuation>,
+}
+
+/// Maps a point through one of the eight board symmetries.
+pub fn transform((row, col): (usize, usize), transform: usize, size: usize) -> (usize, usize) {
+    let last = size - 1;
+    match transform {
+        0 => (row, col),
+        1 => (col, last - row),
+        2 => (last - row, last - col),
+        3 => (last - col, row),
+        4 => (row, last - col),
+        5 => (last - row, col),
+        6 => (col, row),
+        _ => (last - col, last - row),
+    }
+}
+
+/// The symmetry that undoes `transform`.
+pub fn inverse(transform: usize) -> usize {
+    match transform {
+        1 => 3,
+        3 => 1,
+        other => other,
+    }
+}
+
+fn swap(stone: Stone) -> Stone {
+    match stone {
+        Stone::Black => Stone::White,
+        Stone::White => Stone::Black,
+        Stone::Empty => Stone::Empty,
+    }
+}
+
+impl Pattern {
+    /// Captures the region between the two corners; `None` if the region holds no stones.
+    pub fn from_region(board: &Board, from: (usize, usize), to: (usize, usize)) -> Option<Self> {
+        let (top, bottom) = (from.0.min(to.0), from.0.max(to.0));
+        let (left, right) = (from.1.min(to.1), from.1.max(to.1));
+        let mut cells = Vec::new();
+        for row in top..=bottom {
+            for col in left..=right {
+                cells.push(((row, col), board[row][col]));
+            }
+        }
+        if cells.iter().all(|&(_, stone)| stone == Stone::Empty) {
+            return None;
+        }
+
+        let size = board.size();
+        let mut variants = Vec::new();
+        for swap_colors in [false, true] {
+            for t in 0..8 {
+                let cells: Vec<_> = cells
+                    .iter()
+                    .map(|&(point, stone)| {
+                        let stone = if swap_colors { swap(stone) } else { stone };
+                        (transform(point, t, size), stone)
+                    })
+                    .collect();
+                let rows = cells.iter().map(|&((row, _), _)| row);
+                let cols = cells.iter().map(|&((_, col), _)| col);
+                variants.push(Variant {
+                    transform: t,
+                    rows: (rows.clone().min()?, rows.max()?),
+                    cols: (cols.clone().min()?, cols.max()?),
+                    cells,
+                });
+            }
+        }
+        Some(Self {
+            board_size: size,
+            variants,
+        })
+    }
+
+    fn matching_variant(
+        &self,
+        board: &Board,
+        last_move: Option<(usize, usize)>,
+    ) -> Option<&Variant> {
+        self.variants.iter().find(|variant| {
+            let touched = last_move.is_none_or(|(row, col)| {
+                (variant.rows.0..=variant.rows.1).contains(&row)
+                    && (variant.cols.0..=variant.cols.1).contains(&col)
+            });
+            touched
+                && variant
+                    .cells
+                    .iter()
+                    .all(|&((row, col), stone)| board[row][col] == stone)
+        })
+    }
+
+    /// Replays every game of matching board size and records where the pattern first appears.
+    pub fn search(&self, records: &[Record]) -> SearchResult {
+        let mut result = SearchResult::default();
+        let mut continuations: HashMap<Option<(usize, usize)>, Continuation> = HashMap::new();
+        for record in records
+            .iter()
+            .filter(|record| record.board_size == self.board_size)
+        {
+            result.games_searched += 1;
+            let mut board = Board::new(self.board_size);
+            for &(row, col) in &record.setup_black {
+                board[row][col] = Stone::Black;
+            }
+            for &(row, col) in &record.setup_white {
+                board[row][col] = Stone::White;
+            }
+            let moves: Vec<_> = record.moves().collect();
+            let mut found = self
+                .matching_variant(&board, None)
+                .map(|variant| (0, variant));
+            for (number, &(player, point)) in moves.iter().enumerate() {
+                if found.is_some() {
+                    break;
+                }
+                if let Some((row, col)) = point {
+                    let captured = board.play(row, col, player);
+                    // Captures may change the region even when the move itself lies outside it.
+                    let last_move = if captured > 0 { None } else { Some((row, col)) };
+                    found = self
+                        .matching_variant(&board, last_move)
+                        .map(|variant| (number + 1, variant));
+                }
+            }
+            let Some((move_number, variant)) = found else {
+                continue;
+            };
+
+            result.matches.push(Match {
+                path: record.path.clone(),
+                black: record.black.clone(),
+                white: record.white.clone(),
+                move_number,
+            });
+            let Some(&(player, next)) = moves.get(move_number) else {
+                continue;
+            };
+            // Express the continuation in the orientation of the selected region.
+            let next = next
+                .filter(|&(row, col)| {
+                    (variant.rows.0..=variant.rows.1).contains(&row)
+                        && (variant.cols.0..=variant.cols.1).contains(&col)
+                })
+                .map(|point| transform(point, inverse(variant.transform), self.board_size));
+            let winner_prefix = match player {
+                Player::Black => "B+",
+                Player::White => "W+",
+            };
+            let entry = continuations.entry(next).or_insert(Continuation {
+                point: next,
+                count: 0,
+                wins: 0,
+            });
+            entry.count += 1;
+            if record.result.starts_with(winner_prefix) {
+                entry.wins += 1;
+            }
+        }
+        result.continuations = continuations.into_values().collect();
+        result
+            .continuations
+            .sort_by_key(|continuation| std::cmp::Reverse(continuation.count));
+        result
+    }
+}
@@ -0,0 +1,242 @@
+//! Just enough of PDF to print paperwork: A4 pages of text in the Helvetica every reader has
+//! built in, and lines, with tables laid out over as many pages as they take.
+
+use std::fmt::Write;
+
+// A4 in points, upright
+pub const PAGE_WIDTH: f32 = 595.0;
+pub const PAGE_HEIGHT: f32 = 842.0;
+pub const MARGIN: f32 = 40.0;
+const TABLE_FONT_SIZE: f32 = 10.0;
+// Smaller than this and a table is cut at the right edge instead
+const MIN_TABLE_FONT_SIZE: f32 = 6.0;
+
+// Widths of the printable ASCII characters in Helvetica, in thousandths of the font size
+const WIDTHS: [u16; 95] = [
+    278, 278, 355, 556, 556, 889, 667, 191, 333, 333, 389, 584, 278, 333, 278, 278, 556, 556, 556,
+    556, 556, 556, 556, 556, 556, 556, 278, 278, 584, 584, 584, 556, 1015, 667, 667, 722, 722, 667,
+    611, 778, 722, 278, 500, 667, 556, 833, 722, 778, 667, 778, 722, 667, 611, 722, 667, 944, 667,
+    667, 611, 278, 278, 278, 469, 556, 333, 556, 556, 500, 556, 556, 278, 556, 556, 222, 222, 500,
+    222, 833, 556, 556, 556, 556, 333, 500, 278, 556, 500, 722, 500, 500, 500, 334, 260, 334, 584,
+];
+// Helvetica-Bold runs this much wider on the whole
+const BOLD_WIDTH: f32 = 1.06;
+
+/// How wide `text` is at `size` points.
+pub fn width(text: &str, size: f32, bold: bool) -> f32 {
+    let thousandths: u32 = text
+        .chars()
+        .map(|c| match c as u32 {
+            code @ 32..=126 => u32::from(WIDTHS[code as usize - 32]),
+            _ => 556,
+        })
+        .sum();
+    let width = thousandths as f32 * size / 1000.0;
+    if bold { width * BOLD_WIDTH } else { width }
+}
+
+// The byte for `c` in WinAnsiEncoding, which is Latin-1 with a few extras
+fn win_ansi(c: char) -> u8 {
+    match c {
+        '\u{20}'..='\u{7e}' | '\u{a0}'..='\u{ff}' => c as u8,
+        '€' => 0x80,
+        '…' => 0x85,
+        '‘' => 0x91,
+        '’' => 0x92,
+        '“' => 0x93,
+        '”' => 0x94,
+        '•' => 0x95,
+        '–' => 0x96,
+        '—' => 0x97,
+        _ => b'?',
+    }
+}
+
+/// A page being drawn, measured in points from its top left corner.
+#[derive(Default)]
+pub struct Page {
+    content: Vec<u8>,
+}
+
+impl Page {
+    /// Writes `text` with its baseline at `y`.
+    pub fn text(&mut self, x: f32, y: f32, size: f32, bold: bool, text: &str) {
+        let font = if bold { "F2" } else { "F1" };
+        let _ = write!(
+            self,
+            "BT /{} {:.1} Tf {:.1} {:.1} Td (",
+            font,
+            size,
+            x,
+            PAGE_HEIGHT - y
+        );
+        for byte in text.chars().map(win_ansi) {
+            if matches!(byte, b'(' | b')' | b'\\') {
+                self.content.push(b'\\');
+            }
+            self.content.push(byte);
+        }
+        self.content.extend_from_slice(b") Tj ET\n");
+    }
+
+    pub fn line(&mut self, from: (f32, f32), to: (f32, f32), width: f32) {
+        let _ = writeln!(
+            self,
+            "{:.2} w {:.1} {:.1} m {:.1} {:.1} l S",
+            width,
+            from.0,
+            PAGE_HEIGHT - from.1,
+            to.0,
+            PAGE_HEIGHT - to.1
+        );
+    }
+
+    /// Outlines the box with its top left corner at `x`, `y`.
+    pub fn rect(&mut self, x: f32, y: f32, w: f32, h: f32, width: f32) {
+        let _ = writeln!(
+            self,
+            "{:.2} w {:.1} {:.1} {:.1} {:.1} re S",
+            width,
+            x,
+            PAGE_HEIGHT - y - h,
+            w,
+            h
+        );
+    }
+
+    /// Dashes the lines drawn from now on, or draws them solid again with `false`.
+    pub fn dashed(&mut self, dashed: bool) {
+        self.content
+            .extend_from_slice(if dashed { b"[4 3] 0 d\n" } else { b"[] 0 d\n" });
+    }
+}
+
+impl std::fmt::Write for Page {
+    fn write_str(&mut self, text: &str) -> std::fmt::Result {
+        self.content.extend_from_slice(text.as_bytes());
+        Ok(())
+    }
+}
+
+/// The file of `pages`, in order.
+pub fn document(pages: &[Page]) -> Vec<u8> {
+    let mut out: Vec<u8> = b"%PDF-1.4\n".to_vec();
+    let mut offsets = Vec::new();
+    let mut object = |out: &mut Vec<u8>, body: &[u8]| {
+        offsets.push(out.len());
+        out.extend_from_slice(format!("{} 0 obj\n", offsets.len()).as_bytes());
+        out.extend_from_slice(body);
+        out.extend_from_slice(b"\nendobj\n");
+    };
+    // The catalog, the page tree and the two fonts come first, then a page and its content
+    // for each page
+    let kids: Vec<String> = (0..pages.len())
+        .map(|index| format!("{} 0 R", 5 + 2 * index))
+        .collect();
+    object(&mut out, b"<< /Type /Catalog /Pages 2 0 R >>");
+    object(
+        &mut out,
+        format!(
+            "<< /Type /Pages /Kids [{}] /Count {} >>",
+            kids.join(" "),
+            pages.len()
+        )
+        .as_bytes(),
+    );
+    for font in ["Helvetica", "Helvetica-Bold"] {
+        object(
+            &mut out,
+            format!(
+                "<< /Type /Font /Subtype /Type1 /BaseFont /{} /Encoding /WinAnsiEncoding >>",
+                font
+            )
+            .as_bytes(),
+        );
+    }
+    for (index, page) in pages.iter().enumerate() {
+        object(
+            &mut out,
+            format!(
+                "<< /Type /Page /Parent 2 0 R /MediaBox [0 0 {} {}] /Resources << /Font << /F1 3 0 R /F2 4 0 R >> >> /Contents {} 0 R >>",
+                PAGE_WIDTH,
+                PAGE_HEIGHT,
+                6 + 2 * index
+            )
+            .as_bytes(),
+        );
+        let mut stream = format!("<< /Length {} >>\nstream\n", page.content.len()).into_bytes();
+        stream.extend_from_slice(&page.content);
+        stream.extend_from_slice(b"endstream");
+        object(&mut out, &stream);
+    }
+    let xref = out.len();
+    let mut table = format!("xref\n0 {}\n0000000000 65535 f \n", offsets.len() + 1);
+    for offset in &offsets {
+        let _ = writeln!(table, "{:010} 00000 n ", offset);
+    }
+    let _ = write!(
+        table,
+        "trailer\n<< /Size {} /Root 1 0 R >>\nstartxref\n{}\n%%EOF\n",
+        offsets.len() + 1,
+        xref
+    );
+    out.extend_from_slice(table.as_bytes());
+    out
+}
+
+/// A table under `title` and `facts` with the first row as its header, repeated on each page,
+/// in a font small enough for the widest row to fit across.
+pub fn table(title: &str, facts: &str, rows: &[Vec<String>]) -> Vec<u8> {
+    let padding = 4.0;
+    let columns = rows.iter().map(Vec::len).max().unwrap_or(0);
+    let widths_at = |size: f32| -> Vec<f32> {
+        (0..columns)
+            .map(|column| {
+                rows.iter()
+                    .enumerate()
+                    .filter_map(|(index, row)| {
+                        Some(width(row.get(column)?, size, index == 0) + 2.0 * padding)
+                    })
+                    .fold(0.0, f32::max)
+            })
+            .collect()
+    };
+    let room = PAGE_WIDTH - 2.0 * MARGIN;
+    let mut size = TABLE_FONT_SIZE;
+    while size > MIN_TABLE_FONT_SIZE && widths_at(size).iter().sum::<f32>() > room {
+        size -= 0.5;
+    }
+    let widths = widths_at(size);
+    let row_height = size * 1.8;
+
+    let mut pages = Vec::new();
+    let mut page = Page::default();
+    page.text(MARGIN, MARGIN + 16.0, 16.0, true, title);
+    page.text(MARGIN, MARGIN + 34.0, 10.0, false, facts);
+    let mut y = MARGIN + 48.0;
+    let draw_row = |page: &mut Page, y: f32, row: &[String], bold: bool| {
+        let mut x = MARGIN;
+        for (cell, &width) in row.iter().zip(&widths) {
+            page.rect(x, y, width, row_height, 0.5);
+            page.text(x + padding, y + row_height * 0.68, size, bold, cell);
+            x += width;
+        }
+    };
+    let Some((header, body)) = rows.split_first() else {
+        return document(&[page]);
+    };
+    draw_row(&mut page, y, header, true);
+    y += row_height;
+    for row in body {
+        if y + row_height > PAGE_HEIGHT - MARGIN {
+            pages.push(std::mem::take(&mut page));
+            y = MARGIN;
+            draw_row(&mut page, y, header, true);
+            y += row_height;
+        }
+        draw_row(&mut page, y, row, false);
+        y += row_height;
+    }
+    pages.push(page);
+    document(&pages)
+}
@@ -0,0 +1,187 @@
+//! Problem sets: folders of tsumego files, with how each problem went every time it was tried.
+//! A solved problem comes back for review after a number of days that doubles with each solve;
+//! a missed one comes back the next day.
+
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Copy, Serialize, Deserialize)]
+pub struct Attempt {
+    pub day: u32,
+    pub solved: bool,
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Problem {
+    pub path: PathBuf,
+    pub history: Vec<Attempt>,
+    // Days between the last attempt and the next review
+    interval: u32,
+    due: u32,
+}
+
+impl Problem {
+    pub fn solved(&self) -> bool {
+        self.history.last().is_some_and(|attempt| attempt.solved)
+    }
+
+    pub fn is_due(&self, today: u32) -> bool {
+        self.due <= today
+    }
+
+    pub fn days_until_due(&self, today: u32) -> u32 {
+        self.due.saturating_sub(today)
+    }
+
+    fn record(&mut self, solved: bool, today: u32) {
+        self.history.push(Attempt { day: today, solved });
+        self.interval = if solved {
+            (self.interval * 2).max(1)
+        } else {
+            0
+        };
+        self.due = today + self.interval.max(1);
+    }
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Set {
+    pub name: String,
+    pub folder: PathBuf,
+    pub problems: Vec<Problem>,
+}
+
+impl Set {
+    pub fn solved(&self) -> usize {
+        self.problems
+            .iter()
+            .filter(|problem| problem.solved())
+            .count()
+    }
+
+    pub fn due(&self, today: u32) -> usize {
+        self.problems
+            .iter()
+            .filter(|problem| problem.is_due(today))
+            .count()
+    }
+
+    /// The problem to review next: the one due the longest, or the first of those never tried.
+    pub fn next_due(&self, today: u32) -> Option<&Path> {
+        self.problems
+            .iter()
+            .filter(|problem| problem.is_due(today))
+            .min_by_key(|problem| (problem.history.is_empty(), problem.due))
+            .map(|problem| problem.path.as_path())
+    }
+
+    /// Adds the files put in the folder since, and drops those gone from it, keeping the
+    /// history of the rest.
+    pub fn refresh(&mut self) {
+        let mut problems = Vec::new();
+        for path in problem_files(&self.folder) {
+            let known = self
+                .problems
+                .iter()
+                .position(|problem| problem.path == path);
+            problems.push(match known {
+                Some(index) => self.problems.swap_remove(index),
+                None => Problem {
+                    path,
+                    history: Vec::new(),
+                    interval: 0,
+                    due: 0,
+                },
+            });
+        }
+        self.problems = problems;
+    }
+}
+
+#[derive(Default, Serialize, Deserialize)]
+pub struct Sets {
+    pub sets: Vec<Set>,
+}
+
+impl Sets {
+    pub fn load(path: &Path) -> Self {
+        std::fs::read_to_string(path)
+            .ok()
+            .and_then(|text| serde_json::from_str(&text).ok())
+            .unwrap_or_default()
+    }
+
+    pub fn save(&self, path: &Path) -> std::io::Result<()> {
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::write(path, serde_json::to_string(self)?)
+    }
+
+    /// Adds the problems in `folder` as a set named after it, and returns how many there are.
+    pub fn add(&mut self, folder: PathBuf) -> usize {
+        let name = folder
+            .file_name()
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or_else(|| folder.display().to_string());
+        let mut set = Set {
+            name,
+            folder,
+            problems: Vec::new(),
+        };
+        set.refresh();
+        let count = set.problems.len();
+        self.sets.push(set);
+        count
+    }
+
+    /// Keeps how the problem at `path` went, in every set that has it.
+    pub fn record(&mut self, path: &Path, solved: bool) -> bool {
+        let today = today();
+        let mut found = false;
+        for problem in self
+            .sets
+            .iter_mut()
+            .flat_map(|set| set.problems.iter_mut())
+            .filter(|problem| problem.path == path)
+        {
+            problem.record(solved, today);
+            found = true;
+        }
+        found
+    }
+}
+
+/// Days since the Unix epoch, in which reviews are scheduled.
+pub fn today() -> u32 {
+    let seconds = web_time::SystemTime::now()
+        .duration_since(web_time::UNIX_EPOCH)
+        .map(|elapsed| elapsed.as_secs())
+        .unwrap_or_default();
+    (seconds / (24 * 60 * 60)) as u32
+}
+
+// The SGF files in `dir` and its subfolders, in name order
+fn problem_files(dir: &Path) -> Vec<PathBuf> {
+    let mut files = Vec::new();
+    let mut pending = vec![dir.to_path_buf()];
+    while let Some(dir) = pending.pop() {
+        let Ok(read_dir) = std::fs::read_dir(&dir) else {
+            continue;
+        };
+        for entry in read_dir.flatten() {
+            let path = entry.path();
+            if path.is_dir() {
+                pending.push(path);
+            } else if path
+                .extension()
+                .is_some_and(|ext| ext.eq_ignore_ascii_case("sgf"))
+            {
+                files.push(path);
+            }
+        }
+    }
+    files.sort();
+    files
+}
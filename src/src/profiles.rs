@@ -0,0 +1,276 @@
+//! The players who use this computer, kept between games so their names and ranks go into the
+//! records without typing them again.
+
+use std::collections::BTreeMap;
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+
+use crate::board::Player;
+use crate::consts;
+
+#[derive(Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Profile {
+    pub name: String,
+    // As written in records, such as "5k" or "2d"
+    pub rank: String,
+    // Taken when the profile plays an engine or hosts a network game; `None` for no preference
+    pub color: Option<Player>,
+    // A short text, usually a single emoji, shown before the name
+    pub avatar: String,
+    pub rating: Rating,
+}
+
+impl Profile {
+    /// The name as shown beside the clocks.
+    pub fn label(&self) -> String {
+        [self.avatar.trim(), self.name.trim(), self.rank.trim()]
+            .into_iter()
+            .filter(|part| !part.is_empty())
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+}
+
+/// An Elo rating from the rated games played here.
+#[derive(Clone, Copy, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Rating {
+    pub value: f32,
+    pub games: u32,
+}
+
+impl Default for Rating {
+    fn default() -> Self {
+        Self {
+            value: consts::START_RATING,
+            games: 0,
+        }
+    }
+}
+
+impl Rating {
+    pub fn provisional(self) -> bool {
+        self.games < consts::PROVISIONAL_GAMES
+    }
+
+    // Moves the rating by a game scored `score`, from 0 for a loss to 1 for a win, that was
+    // expected to score `expected`
+    fn update(&mut self, score: f32, expected: f32) {
+        let k = if self.provisional() {
+            consts::PROVISIONAL_K
+        } else {
+            consts::RATING_K
+        };
+        self.value += k * (score - expected);
+        self.games += 1;
+    }
+}
+
+/// The score Black is expected to make against White, with each handicap stone counted as
+/// `consts::STONE_RATING` points of rating.
+pub fn expected(black: Rating, white: Rating, handicap: u32) -> f32 {
+    let stones = if handicap >= 2 { handicap as f32 } else { 0.0 };
+    let gap = white.value - black.value - stones * consts::STONE_RATING;
+    1.0 / (1.0 + 10f32.powf(gap / 400.0))
+}
+
+/// Black's score in a game with the SGF `result`: 1 for a win, 0 for a loss and a half for a
+/// draw; `None` for a game without a winner, such as one left unfinished.
+pub fn black_score(result: &str) -> Option<f32> {
+    let result = result.trim();
+    if result.starts_with("B+") {
+        Some(1.0)
+    } else if result.starts_with("W+") {
+        Some(0.0)
+    } else if result == "0" || result.eq_ignore_ascii_case("draw") {
+        Some(0.5)
+    } else {
+        None
+    }
+}
+
+/// The ranks as a ladder with a step for each: 1 for 1 dan, 0 for 1 kyu, -1 for 2 kyu and so on.
+/// Marks of an uncertain rank such as "5k?" are left out.
+pub fn rank_step(rank: &str) -> Option<i32> {
+    let rank = rank
+        .trim()
+        .trim_end_matches(['?', '*'])
+        .to_ascii_lowercase();
+    let unit = rank.trim_start_matches(|c: char| c.is_ascii_digit());
+    let number: i32 = rank[..rank.len() - unit.len()].parse().ok()?;
+    match unit.trim() {
+        "k" | "kyu" => Some(1 - number),
+        "d" | "dan" => Some(number),
+        _ => None,
+    }
+}
+
+/// How many stones stronger `first` is than `second`, negative when weaker: by their ratings
+/// once both have settled, and otherwise by their ranks.
+pub fn stones_stronger(first: &Profile, second: &Profile) -> Option<i32> {
+    if !first.rating.provisional() && !second.rating.provisional() {
+        let gap = (first.rating.value - second.rating.value) / consts::STONE_RATING;
+        return Some(gap.round() as i32);
+    }
+    Some(rank_step(&first.rank)? - rank_step(&second.rank)?)
+}
+
+/// The conventional handicap between players `stones` apart, with the weaker taking Black: an
+/// even game for none, no komi for one, and a stone a rank beyond that.
+pub fn handicap(stones: u32, even_komi: f32) -> (u32, f32) {
+    match stones {
+        0 => (0, even_komi),
+        1 => (0, consts::HANDICAP_KOMI),
+        _ => (stones.min(consts::MAX_HANDICAP), consts::HANDICAP_KOMI),
+    }
+}
+
+/// Komi for an even game with the weaker player as Black, where White pays for the gap in
+/// points instead of stones, to the nearest half point.
+pub fn reverse_komi(stones: u32, even_komi: f32) -> f32 {
+    let komi = even_komi - stones as f32 * consts::RANK_POINTS;
+    (komi - 0.5).round() + 0.5
+}
+
+/// A finished game between two profiles, for their head-to-head record.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Meeting {
+    pub black: String,
+    pub white: String,
+    pub result: String,
+    pub handicap: u32,
+    pub komi: f32,
+    // Days since 1970
+    pub day: u32,
+}
+
+impl Meeting {
+    /// The winner's name, `None` for a draw.
+    pub fn winner(&self) -> Option<&str> {
+        match black_score(&self.result)? {
+            score if score > 0.5 => Some(&self.black),
+            score if score < 0.5 => Some(&self.white),
+            _ => None,
+        }
+    }
+}
+
+/// How the games between two profiles went, counted for the first.
+pub struct HeadToHead<'a> {
+    pub wins: usize,
+    pub losses: usize,
+    pub draws: usize,
+    // Latest last
+    pub meetings: Vec<&'a Meeting>,
+}
+
+#[derive(Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Profiles {
+    pub profiles: Vec<Profile>,
+    // The engines the profiles played rated games against, by the name they gave
+    pub engines: BTreeMap<String, Rating>,
+    // Oldest first
+    pub meetings: Vec<Meeting>,
+}
+
+impl Profiles {
+    pub fn load(path: &Path) -> Self {
+        std::fs::read_to_string(path)
+            .ok()
+            .and_then(|text| serde_json::from_str(&text).ok())
+            .unwrap_or_default()
+    }
+
+    pub fn save(&self, path: &Path) -> std::io::Result<()> {
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::write(path, serde_json::to_string(self)?)
+    }
+
+    /// The profile called `name`, with no profile for an empty name.
+    pub fn find(&self, name: &str) -> Option<&Profile> {
+        let name = name.trim();
+        if name.is_empty() {
+            return None;
+        }
+        self.profiles
+            .iter()
+            .find(|profile| profile.name.trim() == name)
+    }
+
+    // The rating of the profile called `name`, or of the engine, which starts out new
+    fn rating(&self, name: &str, engine: bool) -> Option<Rating> {
+        if engine {
+            return Some(self.engines.get(name.trim()).copied().unwrap_or_default());
+        }
+        self.find(name).map(|profile| profile.rating)
+    }
+
+    fn rating_mut(&mut self, name: &str, engine: bool) -> Option<&mut Rating> {
+        let name = name.trim();
+        if engine {
+            return Some(self.engines.entry(name.to_string()).or_default());
+        }
+        self.profiles
+            .iter_mut()
+            .find(|profile| !name.is_empty() && profile.name.trim() == name)
+            .map(|profile| &mut profile.rating)
+    }
+
+    /// The games `first` and `second` played each other, and who won them.
+    pub fn head_to_head(&self, first: &str, second: &str) -> HeadToHead<'_> {
+        let (first, second) = (first.trim(), second.trim());
+        let meetings: Vec<&Meeting> = self
+            .meetings
+            .iter()
+            .filter(|meeting| {
+                let players = (meeting.black.as_str(), meeting.white.as_str());
+                players == (first, second) || players == (second, first)
+            })
+            .collect();
+        let won = |name: &str| {
+            meetings
+                .iter()
+                .filter(|meeting| meeting.winner() == Some(name))
+                .count()
+        };
+        let (wins, losses) = (won(first), won(second));
+        HeadToHead {
+            wins,
+            losses,
+            draws: meetings.len() - wins - losses,
+            meetings,
+        }
+    }
+
+    /// Rates a game between `black` and `white`, either of which may be an engine rather than
+    /// a profile. Returns whether both players were found.
+    pub fn rate(
+        &mut self,
+        (black, white): (&str, &str),
+        engine: Option<Player>,
+        handicap: u32,
+        score: f32,
+    ) -> bool {
+        let (Some(black_rating), Some(white_rating)) = (
+            self.rating(black, engine == Some(Player::Black)),
+            self.rating(white, engine == Some(Player::White)),
+        ) else {
+            return false;
+        };
+        let expected = expected(black_rating, white_rating, handicap);
+        for (name, player, score, expected) in [
+            (black, Player::Black, score, expected),
+            (white, Player::White, 1.0 - score, 1.0 - expected),
+        ] {
+            if let Some(rating) = self.rating_mut(name, engine == Some(player)) {
+                rating.update(score, expected);
+            }
+        }
+        true
+    }
+}
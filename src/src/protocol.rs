@@ -0,0 +1,547 @@
+use std::time::Duration;
+
+use bincode::Options;
+use serde::{Deserialize, Serialize};
+use web_time::Instant;
+
+use crate::board::{Board, Move, Player};
+
+/// One message of the protocol between two copies of the app playing each other directly, or
+/// through the game server. It goes over the wire as bincode, one WebSocket frame to a message
+/// or, on a direct connection, with its length in front; new messages go at the end, so that an
+/// older copy skips them instead of taking them for others.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub enum Message {
+    // The host's record to play on, and the color the joining side takes
+    Game {
+        color: Player,
+        sgf: String,
+    },
+    // A move and the seconds it took, which the receiver adds to the mover's clock
+    Play {
+        played: Move,
+        #[serde(with = "tenths")]
+        seconds: f32,
+    },
+    Resign(Player),
+    // The player's clock ran out
+    Timeout(Player),
+    // The record a spectator of a server room follows, with the moves so far sent after it
+    Watch {
+        sgf: String,
+    },
+    // How long the player to move has been thinking, once a spectator has seen every move so far
+    Thinking(#[serde(with = "tenths")] f32),
+    // How many are watching the game
+    Spectators(usize),
+    // From a player, or from a spectator to the others when `from` is `None`
+    Chat {
+        from: Option<Player>,
+        text: String,
+    },
+    // The sender asks to take back that many of the last moves, down to and including its own
+    UndoRequest(usize),
+    // The moves are taken back on both sides
+    UndoAccept(usize),
+    UndoDecline,
+    // The terms for a dropped connection, sent once the game starts
+    Reconnect(Reconnect),
+    // Like `Game`, for a player coming back, with the moves so far and then `Thinking` sent
+    // after it
+    Resume {
+        color: Player,
+        sgf: String,
+    },
+    // The player's connection dropped, and they may still come back
+    Away(Player),
+    Back(Player),
+    // The player did not come back in time
+    Forfeit(Player),
+    // Joins the server's automatch queue with the board sizes and speeds the sender accepts
+    Seek(Seek),
+    // The room the queue opened for the sender and its opponent
+    Matched(String),
+    // The stones the sender counts as dead once both players passed
+    Dead(Vec<(usize, usize)>),
+    // How the game ended, such as `W+6.5`, as decided by a server that counts it
+    Result(String),
+    // To the creator of a rated room once it ends, what looked suspicious about the timing of
+    // the player of that color
+    FairPlay {
+        player: Player,
+        note: String,
+    },
+    // The server keeps the game between visits of its players, sent after `Game` or `Resume`
+    Correspondence,
+    // How far behind the game a spectator is kept, sent after `Watch`
+    Delay(SpectatorDelay),
+    // The game is pair go, and the receiver is the second player of its side, who plays the
+    // side's second, fourth and later moves; 0 for the first player. Sent after `Game` or
+    // `Resume`
+    Rengo(usize),
+    // Chat between the two players of a pair go side, which the other side does not hear
+    TeamChat(String),
+    // Replies the server plays for the sender of a correspondence game when the opponent's move
+    // matches, or none to drop those planned before
+    Plan(Plan),
+    // The state of the game after that many moves, as `checksum` digests it, from the side that
+    // keeps the game
+    Sync {
+        moves: usize,
+        checksum: u64,
+    },
+    // The sender's game differs from a `Sync`, and it asks for all of it again
+    Resync,
+    // From the side that keeps the clocks, to be answered at once with `Pong` and the same number
+    Ping(u32),
+    Pong(u32),
+    // The seconds the side that keeps the clocks counts for the receiver's last move, in place of
+    // those it sent
+    Charged(#[serde(with = "tenths")] f32),
+    // Never sent: the other side closed the connection
+    Bye,
+}
+
+/// What the clocks do while a player's connection is lost.
+#[derive(Clone, Copy, PartialEq, Debug, Serialize, Deserialize)]
+pub enum AwayClock {
+    Runs,
+    Stops,
+}
+
+impl AwayClock {
+    pub const ALL: [AwayClock; 2] = [AwayClock::Runs, AwayClock::Stops];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            AwayClock::Runs => "Keeps running",
+            AwayClock::Stops => "Stops",
+        }
+    }
+}
+
+/// What the delay of the spectators of a server room is counted in.
+#[derive(Clone, Copy, PartialEq, Debug, Serialize, Deserialize)]
+pub enum DelayUnit {
+    Moves,
+    Minutes,
+}
+
+impl DelayUnit {
+    pub const ALL: [DelayUnit; 2] = [DelayUnit::Moves, DelayUnit::Minutes];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            DelayUnit::Moves => "Moves",
+            DelayUnit::Minutes => "Minutes",
+        }
+    }
+}
+
+/// How far the spectators of a server room are kept behind the game, so that what they see
+/// comes too late to help the players.
+#[derive(Clone, Copy, PartialEq, Debug, Serialize, Deserialize)]
+pub struct SpectatorDelay {
+    pub amount: u32,
+    pub unit: DelayUnit,
+}
+
+/// The clocks of an automatch game, from which each seeker picks those to accept.
+#[derive(Clone, Copy, PartialEq, Debug, Serialize, Deserialize)]
+pub enum Speed {
+    Blitz,
+    Live,
+    Slow,
+}
+
+impl Speed {
+    #[cfg(not(target_arch = "wasm32"))]
+    pub const ALL: [Speed; 3] = [Speed::Blitz, Speed::Live, Speed::Slow];
+
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn name(self) -> &'static str {
+        match self {
+            Speed::Blitz => "Blitz",
+            Speed::Live => "Live",
+            Speed::Slow => "Slow",
+        }
+    }
+
+    /// The main time in seconds and the SGF overtime of games at this speed.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn clock(self) -> (u32, &'static str) {
+        match self {
+            Speed::Blitz => (300, "3x10 byo-yomi"),
+            Speed::Live => (600, "5x30 byo-yomi"),
+            Speed::Slow => (1800, "5x60 byo-yomi"),
+        }
+    }
+}
+
+/// What a player waiting in the automatch queue accepts, most wanted first.
+#[derive(Clone, PartialEq, Debug, Serialize, Deserialize)]
+pub struct Seek {
+    pub sizes: Vec<usize>,
+    pub speeds: Vec<Speed>,
+}
+
+/// Rating points between neighbouring ranks, each worth a handicap stone.
+#[cfg(not(target_arch = "wasm32"))]
+pub const RATING_PER_RANK: f64 = 100.0;
+
+/// A rating deviation above which a rank is still a guess.
+#[cfg(not(target_arch = "wasm32"))]
+pub const SETTLED_DEVIATION: f64 = 110.0;
+
+/// The kyu or dan rank of a rating, on the European scale where 2100 is 1 dan, marked with a
+/// question mark while the deviation says too few games settled it.
+#[cfg(not(target_arch = "wasm32"))]
+pub fn rank_name(rating: f64, deviation: f64) -> String {
+    let ranks = ((rating - 2100.0) / RATING_PER_RANK).floor() as i32;
+    let rank = if ranks >= 0 {
+        format!("{}d", (ranks + 1).min(9))
+    } else {
+        format!("{}k", (-ranks).min(30))
+    };
+    if deviation > SETTLED_DEVIATION {
+        format!("{}?", rank)
+    } else {
+        rank
+    }
+}
+
+/// Replies planned ahead in a correspondence game, as lines of moves the opponent may play, each
+/// with the answer to play at once. Lines that start alike share those moves, so the plan stays
+/// flat however far ahead it goes.
+#[derive(Clone, PartialEq, Debug, Default, Serialize, Deserialize)]
+pub struct Plan {
+    pub lines: Vec<Vec<Exchange>>,
+}
+
+/// The most lines a plan may hold, and the most replies down one line, for the server to keep it.
+pub const MAX_PLAN_LINES: usize = 1000;
+pub const MAX_PLAN_LENGTH: usize = 100;
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug, Serialize, Deserialize)]
+pub struct Exchange {
+    pub theirs: (usize, usize),
+    pub reply: (usize, usize),
+}
+
+impl Plan {
+    /// The answer planned to the opponent playing `point`, and the plan after it.
+    pub fn answer(&self, point: (usize, usize)) -> Option<((usize, usize), Plan)> {
+        let first = self
+            .lines
+            .iter()
+            .find_map(|line| line.first().filter(|exchange| exchange.theirs == point))?;
+        let lines = self
+            .lines
+            .iter()
+            .filter(|line| line.first() == Some(first) && line.len() > 1)
+            .map(|line| line[1..].to_vec())
+            .collect();
+        Some((first.reply, Plan { lines }))
+    }
+
+    /// Whether the plan is within `MAX_PLAN_LINES` and `MAX_PLAN_LENGTH`.
+    pub fn fits(&self) -> bool {
+        self.lines.len() <= MAX_PLAN_LINES
+            && self.lines.iter().all(|line| line.len() <= MAX_PLAN_LENGTH)
+    }
+
+    /// How many replies are planned, on every line, counting those lines share once.
+    pub fn replies(&self) -> usize {
+        self.lines
+            .iter()
+            .flat_map(|line| (1..=line.len()).map(|length| &line[..length]))
+            .collect::<std::collections::HashSet<_>>()
+            .len()
+    }
+}
+
+/// How long a player whose connection dropped has to come back to the game, instead of
+/// forfeiting it.
+#[derive(Clone, Copy, PartialEq, Debug, Serialize, Deserialize)]
+pub struct Reconnect {
+    pub seconds: u32,
+    pub clock: AwayClock,
+}
+
+/// How many moves go by between two `Sync` messages.
+pub const SYNC_INTERVAL: usize = 10;
+
+/// How often the side that keeps the clocks measures the round trip to a player.
+pub const PING_INTERVAL: Duration = Duration::from_secs(5);
+// The most of a move's time put down to the connection, so a slow answer to `Ping` gains little
+const MAX_ROUND_TRIP: f32 = 2.0;
+// Leeway on top of the round trip, which varies from move to move
+const LATENCY_MARGIN: f32 = 0.2;
+
+/// The round trip to a player, which the side that keeps the clocks measures with `Ping` and
+/// takes off the time it saw each of their moves take.
+#[derive(Default)]
+pub struct Latency {
+    round_trip: Option<f32>,
+    // The unanswered ping, whose answer is dropped once the next one is sent
+    waiting: Option<(u32, Instant)>,
+    last_ping: Option<Instant>,
+    next_id: u32,
+}
+
+impl Latency {
+    /// The next `Ping` to send, once it is time for one.
+    pub fn ping(&mut self) -> Option<Message> {
+        if self
+            .last_ping
+            .is_some_and(|at| at.elapsed() < PING_INTERVAL)
+        {
+            return None;
+        }
+        let id = self.next_id;
+        self.next_id = id.wrapping_add(1);
+        let now = Instant::now();
+        self.waiting = Some((id, now));
+        self.last_ping = Some(now);
+        Some(Message::Ping(id))
+    }
+
+    /// Takes in the answer to a `Ping`, averaging it with the round trips before.
+    pub fn pong(&mut self, id: u32) {
+        let Some((_, sent)) = self.waiting.filter(|&(waiting, _)| waiting == id) else {
+            return;
+        };
+        self.waiting = None;
+        let sample = sent.elapsed().as_secs_f32().min(MAX_ROUND_TRIP);
+        self.round_trip = Some(
+            self.round_trip
+                .map_or(sample, |round_trip| round_trip * 0.75 + sample * 0.25),
+        );
+    }
+
+    /// The seconds to count for a move the player says took `claimed`, and which arrived
+    /// `elapsed` after the move before it was sent to them: what they say, but no more than that
+    /// and no less than it without the round trip.
+    pub fn charge(&self, claimed: f32, elapsed: f32) -> f32 {
+        let least = (elapsed - self.round_trip.unwrap_or(0.0) - LATENCY_MARGIN).max(0.0);
+        claimed.max(least).min(elapsed)
+    }
+}
+
+// The most one message may take on the wire; a longer one means the stream is broken
+const MAX_FRAME: usize = 16 << 20;
+
+fn bincode() -> impl bincode::Options {
+    // Variable-length integers, so that moves and counts mostly take a byte each
+    bincode::DefaultOptions::new().with_limit(MAX_FRAME as u64)
+}
+
+impl Message {
+    pub fn encode(&self) -> Vec<u8> {
+        bincode()
+            .serialize(self)
+            .expect("messages are within the frame limit")
+    }
+
+    /// The message in `bytes`, or `None` for one this version does not know.
+    pub fn decode(bytes: &[u8]) -> Option<Message> {
+        bincode().deserialize(bytes).ok()
+    }
+
+    /// The message with its length in front, as four little-endian bytes, for a byte stream.
+    pub fn frame(&self) -> Vec<u8> {
+        let bytes = self.encode();
+        let mut frame = (bytes.len() as u32).to_le_bytes().to_vec();
+        frame.extend(bytes);
+        frame
+    }
+}
+
+/// Takes the first frame off `buffer` once all of it has arrived. A length past
+/// `MAX_FRAME` is an error, since nothing after it can be read.
+pub fn take_frame(buffer: &mut Vec<u8>) -> std::io::Result<Option<Vec<u8>>> {
+    let Some(length) = buffer.first_chunk::<4>() else {
+        return Ok(None);
+    };
+    let length = u32::from_le_bytes(*length) as usize;
+    if length > MAX_FRAME {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::InvalidData,
+            "message too long",
+        ));
+    }
+    if buffer.len() < 4 + length {
+        return Ok(None);
+    }
+    let frame = buffer[4..4 + length].to_vec();
+    buffer.drain(..4 + length);
+    Ok(Some(frame))
+}
+
+// Clock times go over the wire in whole tenths of a second
+mod tenths {
+    use serde::{Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<S: Serializer>(seconds: &f32, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_u32(super::tenths(*seconds))
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<f32, D::Error> {
+        Ok(u32::deserialize(deserializer)? as f32 / 10.0)
+    }
+}
+
+/// The seconds of a clock as the wire carries them.
+pub fn tenths(seconds: f32) -> u32 {
+    (seconds.max(0.0) * 10.0).round() as u32
+}
+
+/// What a `Sync` carries of the game after `moves`, played from `board` with the seconds each
+/// took, with `to_move` to play: a digest of the stones, the point a ko forbids, the player to
+/// move, the stones each side took and the time each side's moves took, in tenths as the wire
+/// has them. Two copies of the game that agree on it see the same position and clocks.
+pub fn checksum(mut board: Board, to_move: Player, moves: &[(Move, f32)]) -> u64 {
+    // Black's first
+    let mut captured = [0u64; 2];
+    let mut spent = [0u64; 2];
+    for &((player, point), seconds) in moves {
+        let seat = (player == Player::White) as usize;
+        match point {
+            Some((row, col)) => captured[seat] += u64::from(board.play(row, col, player)),
+            None => board.pass(),
+        }
+        spent[seat] += u64::from(tenths(seconds));
+    }
+    let ko = board
+        .ko_point()
+        .map_or(0, |(row, col)| (row * board.size() + col + 1) as u64);
+    let state = [
+        ko,
+        (to_move == Player::White) as u64,
+        captured[0],
+        captured[1],
+        spent[0],
+        spent[1],
+    ];
+    // On from the FNV-1a digest of the stones
+    state
+        .iter()
+        .flat_map(|word| word.to_le_bytes())
+        .fold(board.checksum(), |hash, byte| {
+            (hash ^ u64::from(byte)).wrapping_mul(0x0100_0000_01b3)
+        })
+}
+
+/// What a client sends the game server to open a room: the record to play on and the color the
+/// creator takes.
+#[cfg(not(target_arch = "wasm32"))]
+#[derive(Serialize, Deserialize)]
+pub struct NewRoom {
+    pub sgf: String,
+    pub color: Player,
+    // `None` forfeits a player as soon as their connection drops
+    #[serde(default)]
+    pub reconnect: Option<Reconnect>,
+    // Accounts that may enter besides the creator, who then has to be logged in; none for a
+    // game open to all
+    #[serde(default)]
+    pub invited: Vec<String>,
+    // Kept on the server while its players come and go, which needs them logged in
+    #[serde(default)]
+    pub correspondence: bool,
+    // Pair go: two players a side, who take turns playing its moves
+    #[serde(default)]
+    pub rengo: bool,
+    // One of the boards of a simultaneous game the creator plays against several opponents
+    #[serde(default)]
+    pub simul: bool,
+    // `None` shows spectators every move as it is played
+    #[serde(default)]
+    pub spectator_delay: Option<SpectatorDelay>,
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+#[derive(Serialize, Deserialize)]
+pub struct RoomCreated {
+    pub code: String,
+}
+
+/// A room of the game server's lobby, waiting for its second player or open to spectators.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct RoomInfo {
+    pub code: String,
+    pub board_size: usize,
+    pub komi: String,
+    // The SGF main time and overtime, empty for untimed games
+    pub time: String,
+    pub open_color: Player,
+    // Both players are in, and anyone entering watches
+    #[serde(default)]
+    pub playing: bool,
+    // The creator's account, empty if they are not logged in
+    #[serde(default)]
+    pub host: String,
+    // Their rank as the server rates them
+    #[serde(default)]
+    pub host_rank: String,
+    #[serde(default)]
+    pub private: bool,
+    #[serde(default)]
+    pub correspondence: bool,
+    #[serde(default)]
+    pub rengo: bool,
+    #[serde(default)]
+    pub simul: bool,
+    #[serde(default)]
+    pub spectator_delay: Option<SpectatorDelay>,
+}
+
+/// A correspondence game of the account asking, as the game server lists them.
+#[cfg(not(target_arch = "wasm32"))]
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct OngoingGame {
+    pub code: String,
+    pub color: Player,
+    // Empty while the room waits for an opponent
+    pub opponent: String,
+    pub board_size: usize,
+    pub moves: usize,
+    pub your_turn: bool,
+    // Seconds since the Unix epoch
+    pub last_move: u64,
+}
+
+/// A name and password to register or log in to the game server with.
+#[cfg(not(target_arch = "wasm32"))]
+#[derive(Serialize, Deserialize)]
+pub struct Credentials {
+    pub name: String,
+    pub password: String,
+}
+
+/// What the game server answers a login with: the token the client sends with every request.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Session {
+    pub name: String,
+    pub token: String,
+}
+
+/// An account of the game server, with its rating and the games it played, latest first.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Profile {
+    pub name: String,
+    pub rating: f64,
+    // How far off the rating may still be
+    #[serde(default)]
+    pub deviation: f64,
+    pub games: Vec<PlayedGame>,
+}
+
+/// A stored record of the game server, fetched by `file`.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct PlayedGame {
+    pub file: String,
+    pub black: String,
+    pub white: String,
+    pub result: String,
+}
@@ -0,0 +1,514 @@
+//! Review reports: the score graph of a game, its biggest mistakes drawn with the moves the
+//! review would rather have played, and a few numbers for each side, in a single file to share.
+
+use std::fmt::Write;
+
+use crate::board::{Board, Move, Player, Stone};
+use crate::consts;
+use crate::estimate;
+use crate::game_info::GameInfo;
+use crate::i18n::{tr, trf};
+use crate::mistakes::{self, Mistake};
+use crate::notation::CoordinateStyle;
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum Format {
+    Html,
+    Markdown,
+}
+
+impl Format {
+    pub const ALL: [Format; 2] = [Format::Html, Format::Markdown];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Format::Html => "HTML",
+            Format::Markdown => "Markdown",
+        }
+    }
+
+    pub fn extension(self) -> &'static str {
+        match self {
+            Format::Html => "html",
+            Format::Markdown => "md",
+        }
+    }
+}
+
+pub struct Review {
+    info: GameInfo,
+    size: usize,
+    moves: Vec<Move>,
+    // Black's estimated lead before the first move and after each one
+    leads: Vec<f32>,
+    // The biggest of both sides, in the order they were played
+    mistakes: Vec<Mistake>,
+}
+
+// How one side played, by the estimate
+struct Stats {
+    moves: usize,
+    mistakes: usize,
+    // Summed over the moves that lost points by the time the opponent had answered
+    lost: f32,
+    // The move that lost most, counted from 1, and what it lost
+    worst: Option<(usize, f32)>,
+}
+
+impl Review {
+    /// Reviews the game of `moves` played from `board`, the slow part, which runs off the UI
+    /// thread.
+    pub fn new(board: Board, moves: &[Move], info: GameInfo) -> Self {
+        let leads = estimate::analyze(board.clone(), moves, info.komi);
+        let mut found: Vec<Mistake> = [Player::Black, Player::White]
+            .into_iter()
+            .flat_map(|side| mistakes::find(board.clone(), moves, side, info.komi))
+            .collect();
+        found.sort_by(|a, b| b.loss.total_cmp(&a.loss));
+        found.truncate(consts::REPORT_MISTAKES);
+        found.sort_by_key(|mistake| mistake.move_number);
+        Review {
+            size: board.size(),
+            info,
+            moves: moves.to_vec(),
+            leads,
+            mistakes: found,
+        }
+    }
+
+    fn stats(&self, side: Player) -> Stats {
+        let mut stats = Stats {
+            moves: 0,
+            mistakes: 0,
+            lost: 0.0,
+            worst: None,
+        };
+        for (index, &(player, _)) in self.moves.iter().enumerate() {
+            if player != side {
+                continue;
+            }
+            stats.moves += 1;
+            // As in finding the mistakes, a move is judged once the opponent has answered
+            let answered = self.leads[(index + 2).min(self.leads.len() - 1)];
+            let drop = self.leads[index] - answered;
+            let lost = match side {
+                Player::Black => drop,
+                Player::White => -drop,
+            };
+            if lost > 0.0 {
+                stats.lost += lost;
+                if stats.worst.is_none_or(|(_, worst)| lost > worst) {
+                    stats.worst = Some((index + 1, lost));
+                }
+            }
+        }
+        stats.mistakes = self
+            .mistakes
+            .iter()
+            .filter(|mistake| mistake.player == side)
+            .count();
+        stats
+    }
+
+    fn title(&self) -> String {
+        let name = |name: &str, rank: &str, player: Player| {
+            let name = if name.trim().is_empty() {
+                player_name(player)
+            } else {
+                name.trim().to_string()
+            };
+            format!("{} {}", name, rank.trim()).trim().to_string()
+        };
+        trf(
+            "{} vs {}",
+            &[
+                &name(&self.info.black_name, &self.info.black_rank, Player::Black),
+                &name(&self.info.white_name, &self.info.white_rank, Player::White),
+            ],
+        )
+    }
+
+    // The lines under the title: date, result, komi and handicap
+    fn facts(&self) -> Vec<String> {
+        let mut facts = Vec::new();
+        if !self.info.date.is_empty() {
+            facts.push(self.info.date.clone());
+        }
+        facts.push(trf(
+            "{} x {}, {} moves",
+            &[&self.size, &self.size, &self.moves.len()],
+        ));
+        facts.push(trf("Komi {}", &[&self.info.komi]));
+        if self.info.handicap >= 2 {
+            facts.push(trf("Handicap {}", &[&self.info.handicap]));
+        }
+        if !self.info.result.is_empty() {
+            facts.push(trf("Result {}", &[&self.info.result]));
+        }
+        facts
+    }
+
+    fn mistake_text(&self, mistake: &Mistake, coordinates: CoordinateStyle) -> (String, String) {
+        let point = |point| coordinates.format_point(point, self.size);
+        let heading = trf(
+            "Move {}: {} {}, about {} points lost",
+            &[
+                &(mistake.move_number + 1),
+                &player_name(mistake.player),
+                &point(mistake.played),
+                &mistake.loss.round(),
+            ],
+        );
+        let better: Vec<String> = mistake
+            .better
+            .iter()
+            .enumerate()
+            .map(|(index, &better)| format!("{} {}", letter(index), point(better)))
+            .collect();
+        (heading, trf("Better: {}", &[&better.join(", ")]))
+    }
+
+    pub fn render(&self, format: Format, coordinates: CoordinateStyle) -> String {
+        match format {
+            Format::Html => self.html(coordinates),
+            Format::Markdown => self.markdown(coordinates),
+        }
+    }
+
+    fn html(&self, coordinates: CoordinateStyle) -> String {
+        let mut out = String::new();
+        let title = escape(&self.title());
+        let _ = writeln!(
+            out,
+            "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<title>{}</title>\n<style>body {{ font-family: sans-serif; max-width: 50em; margin: auto; }} table {{ border-collapse: collapse; }} td, th {{ border: 1px solid #999; padding: 0.2em 0.6em; }}</style>\n</head>\n<body>\n<h1>{}</h1>",
+            title, title
+        );
+        let _ = writeln!(out, "<p>{}</p>", escape(&self.facts().join(" · ")));
+        let _ = writeln!(
+            out,
+            "<h2>{}</h2>\n{}",
+            escape(&tr("Estimated lead for Black by move")),
+            self.graph_svg()
+        );
+        let _ = writeln!(out, "<h2>{}</h2>\n<table>", escape(&tr("Summary")));
+        self.stats_rows(|row| {
+            let cells: Vec<String> = row.iter().map(|cell| escape(cell)).collect();
+            let _ = writeln!(out, "<tr><td>{}</td></tr>", cells.join("</td><td>"));
+        });
+        let _ = writeln!(out, "</table>");
+        if !self.mistakes.is_empty() {
+            let _ = writeln!(out, "<h2>{}</h2>", escape(&tr("Key mistakes")));
+        }
+        for mistake in &self.mistakes {
+            let (heading, better) = self.mistake_text(mistake, coordinates);
+            let _ = writeln!(
+                out,
+                "<h3>{}</h3>\n<p>{}</p>\n{}",
+                escape(&heading),
+                escape(&better),
+                self.board_svg(mistake, coordinates)
+            );
+        }
+        let _ = writeln!(out, "</body>\n</html>");
+        out
+    }
+
+    fn markdown(&self, coordinates: CoordinateStyle) -> String {
+        let mut out = String::new();
+        let _ = writeln!(out, "# {}\n\n{}\n", self.title(), self.facts().join(" · "));
+        let _ = writeln!(out, "## {}\n", tr("Estimated lead for Black by move"));
+        let _ = writeln!(out, "```\n{}\n```\n", self.graph_text());
+        let marks: Vec<String> = (consts::REPORT_LEAD_STEP..self.leads.len())
+            .step_by(consts::REPORT_LEAD_STEP)
+            .map(|index| format!("{}: {}", index, estimate::format_lead(self.leads[index])))
+            .collect();
+        if !marks.is_empty() {
+            let _ = writeln!(out, "{}\n", marks.join(", "));
+        }
+        let _ = writeln!(out, "## {}\n", tr("Summary"));
+        let mut first = true;
+        self.stats_rows(|row| {
+            let _ = writeln!(out, "| {} |", row.join(" | "));
+            if std::mem::take(&mut first) {
+                let _ = writeln!(out, "|{}", " --- |".repeat(row.len()));
+            }
+        });
+        if !self.mistakes.is_empty() {
+            let _ = writeln!(out, "\n## {}", tr("Key mistakes"));
+        }
+        for mistake in &self.mistakes {
+            let (heading, better) = self.mistake_text(mistake, coordinates);
+            let _ = writeln!(
+                out,
+                "\n### {}\n\n{}\n\n```\n{}```",
+                heading,
+                better,
+                self.board_text(mistake, coordinates)
+            );
+        }
+        out
+    }
+
+    // The header row and a row for each side
+    fn stats_rows(&self, mut row: impl FnMut(&[String])) {
+        row(&[String::new(), tr("Black"), tr("White")]);
+        let [black, white] = [Player::Black, Player::White].map(|side| self.stats(side));
+        let worst = |stats: &Stats| match stats.worst {
+            Some((number, lost)) => trf("move {}, {} points", &[&number, &lost.round()]),
+            None => "-".to_string(),
+        };
+        row(&[
+            tr("Moves"),
+            black.moves.to_string(),
+            white.moves.to_string(),
+        ]);
+        row(&[
+            tr("Key mistakes"),
+            black.mistakes.to_string(),
+            white.mistakes.to_string(),
+        ]);
+        row(&[
+            tr("Points lost"),
+            format!("{}", black.lost.round()),
+            format!("{}", white.lost.round()),
+        ]);
+        let average = |stats: &Stats| format!("{:.1}", stats.lost / stats.moves.max(1) as f32);
+        row(&[tr("Lost per move"), average(&black), average(&white)]);
+        row(&[tr("Worst move"), worst(&black), worst(&white)]);
+    }
+
+    fn graph_svg(&self) -> String {
+        let (width, height) = (600.0, 150.0);
+        let scale = self.scale();
+        let last = (self.leads.len() - 1).max(1) as f32;
+        let points: Vec<String> = self
+            .leads
+            .iter()
+            .enumerate()
+            .map(|(index, lead)| {
+                let x = width * index as f32 / last;
+                let y = height / 2.0 - height / 2.0 * lead / scale;
+                format!("{:.1},{:.1}", x, y)
+            })
+            .collect();
+        let mut svg = format!(
+            "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{w}\" height=\"{h}\" viewBox=\"0 0 {w} {h}\"><rect width=\"{w}\" height=\"{h}\" fill=\"#1e1e1e\"/><line x1=\"0\" y1=\"{m}\" x2=\"{w}\" y2=\"{m}\" stroke=\"gray\"/>",
+            w = width,
+            h = height,
+            m = height / 2.0
+        );
+        for mistake in &self.mistakes {
+            let x = width * (mistake.move_number + 1) as f32 / last;
+            let _ = write!(
+                svg,
+                "<line x1=\"{x:.1}\" y1=\"0\" x2=\"{x:.1}\" y2=\"{height}\" stroke=\"#c03030\"/>"
+            );
+        }
+        let _ = write!(
+            svg,
+            "<polyline points=\"{}\" fill=\"none\" stroke=\"#1e78dc\" stroke-width=\"2\"/></svg>",
+            points.join(" ")
+        );
+        svg
+    }
+
+    // A line of block characters, the middle height for an even game
+    fn graph_text(&self) -> String {
+        const LEVELS: [char; 8] = ['▁', '▂', '▃', '▄', '▅', '▆', '▇', '█'];
+        let scale = self.scale();
+        let width = self.leads.len().min(consts::REPORT_GRAPH_WIDTH);
+        (0..width)
+            .map(|column| {
+                let lead = self.leads[column * self.leads.len() / width];
+                let level = ((lead / scale + 1.0) / 2.0 * (LEVELS.len() - 1) as f32).round();
+                LEVELS[level.clamp(0.0, (LEVELS.len() - 1) as f32) as usize]
+            })
+            .collect()
+    }
+
+    fn scale(&self) -> f32 {
+        self.leads
+            .iter()
+            .fold(10.0_f32, |max, lead| max.max(lead.abs()))
+    }
+
+    // The position before the mistake, with the move played marked by an X and the better
+    // moves by letters
+    fn board_svg(&self, mistake: &Mistake, coordinates: CoordinateStyle) -> String {
+        let cell = 22.0;
+        let size = self.size;
+        let margin = cell * 1.2;
+        let full = margin * 2.0 + cell * (size - 1) as f32;
+        let at = |index: usize| margin + cell * index as f32;
+        let mut svg = format!(
+            "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{full}\" height=\"{full}\" viewBox=\"0 0 {full} {full}\" font-family=\"sans-serif\" font-size=\"11\" text-anchor=\"middle\"><rect width=\"{full}\" height=\"{full}\" fill=\"#dcb35c\"/>"
+        );
+        for index in 0..size {
+            let (start, end, line) = (at(0), at(size - 1), at(index));
+            let _ = write!(
+                svg,
+                "<line x1=\"{start}\" y1=\"{line}\" x2=\"{end}\" y2=\"{line}\" stroke=\"black\"/><line x1=\"{line}\" y1=\"{start}\" x2=\"{line}\" y2=\"{end}\" stroke=\"black\"/>"
+            );
+            let _ = write!(
+                svg,
+                "<text x=\"{line}\" y=\"{:.1}\">{}</text><text x=\"{:.1}\" y=\"{:.1}\">{}</text>",
+                margin * 0.45,
+                escape(&coordinates.column_label(index, size)),
+                margin * 0.45,
+                line + 4.0,
+                escape(&coordinates.row_label(index, size))
+            );
+        }
+        let board = mistake.board();
+        for row in 0..size {
+            for col in 0..size {
+                let (fill, stroke) = match board[row][col] {
+                    Stone::Black => ("black", "black"),
+                    Stone::White => ("white", "black"),
+                    Stone::Empty => continue,
+                };
+                let _ = write!(
+                    svg,
+                    "<circle cx=\"{}\" cy=\"{}\" r=\"{:.1}\" fill=\"{fill}\" stroke=\"{stroke}\"/>",
+                    at(col),
+                    at(row),
+                    cell * 0.47
+                );
+            }
+        }
+        let (row, col) = mistake.played;
+        let (x, y, arm) = (at(col), at(row), cell * 0.3);
+        let _ = write!(
+            svg,
+            "<path d=\"M{} {} L{} {} M{} {} L{} {}\" stroke=\"#c03030\" stroke-width=\"3\"/>",
+            x - arm,
+            y - arm,
+            x + arm,
+            y + arm,
+            x + arm,
+            y - arm,
+            x - arm,
+            y + arm
+        );
+        for (index, &(row, col)) in mistake.better.iter().enumerate() {
+            let _ = write!(
+                svg,
+                "<circle cx=\"{}\" cy=\"{}\" r=\"{:.1}\" fill=\"#2e8b3a\"/><text x=\"{}\" y=\"{}\" fill=\"white\" font-weight=\"bold\">{}</text>",
+                at(col),
+                at(row),
+                cell * 0.42,
+                at(col),
+                at(row) + 4.0,
+                letter(index)
+            );
+        }
+        svg.push_str("</svg>");
+        svg
+    }
+
+    // The same in text: X and O for the stones, # for the move played and letters for the
+    // better moves
+    fn board_text(&self, mistake: &Mistake, coordinates: CoordinateStyle) -> String {
+        let size = self.size;
+        let board = mistake.board();
+        let labels: Vec<String> = (0..size)
+            .map(|row| coordinates.row_label(row, size))
+            .collect();
+        let pad = labels
+            .iter()
+            .map(|label| label.chars().count())
+            .max()
+            .unwrap_or(0);
+        let columns: Vec<String> = (0..size)
+            .map(|col| coordinates.column_label(col, size))
+            .collect();
+        let width = columns
+            .iter()
+            .map(|label| label.chars().count())
+            .max()
+            .unwrap_or(1);
+        let mut out = format!("{:pad$} ", "");
+        for label in &columns {
+            let _ = write!(out, " {:>width$}", label);
+        }
+        out.push('\n');
+        for (row, label) in labels.iter().enumerate() {
+            let _ = write!(out, "{:>pad$} ", label);
+            for col in 0..size {
+                let mark = if (row, col) == mistake.played {
+                    '#'
+                } else if let Some(index) =
+                    mistake.better.iter().position(|&point| point == (row, col))
+                {
+                    letter(index)
+                } else {
+                    match board[row][col] {
+                        Stone::Black => 'X',
+                        Stone::White => 'O',
+                        Stone::Empty => '.',
+                    }
+                };
+                let _ = write!(out, " {:>width$}", mark);
+            }
+            out.push('\n');
+        }
+        out
+    }
+}
+
+fn player_name(player: Player) -> String {
+    tr(match player {
+        Player::Black => "Black",
+        Player::White => "White",
+    })
+}
+
+fn letter(index: usize) -> char {
+    (b'A' + index as u8) as char
+}
+
+/// A page with a title, a line under it and one table, such as the lists of a tournament.
+#[cfg(not(target_arch = "wasm32"))]
+pub fn table(format: Format, title: &str, facts: &str, rows: &[Vec<String>]) -> String {
+    let mut out = String::new();
+    match format {
+        Format::Html => {
+            let title = escape(title);
+            let _ = writeln!(
+                out,
+                "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<title>{}</title>\n<style>body {{ font-family: sans-serif; margin: 1em; }} table {{ border-collapse: collapse; }} td, th {{ border: 1px solid #999; padding: 0.2em 0.6em; }}</style>\n</head>\n<body>\n<h1>{}</h1>\n<p>{}</p>\n<table>",
+                title,
+                title,
+                escape(facts)
+            );
+            for (index, row) in rows.iter().enumerate() {
+                let cell = if index == 0 { "th" } else { "td" };
+                let cells: Vec<String> = row.iter().map(|text| escape(text)).collect();
+                let _ = writeln!(
+                    out,
+                    "<tr><{cell}>{}</{cell}></tr>",
+                    cells.join(&format!("</{cell}><{cell}>"))
+                );
+            }
+            let _ = writeln!(out, "</table>\n</body>\n</html>");
+        }
+        Format::Markdown => {
+            let _ = writeln!(out, "# {}\n\n{}\n", title, facts);
+            for (index, row) in rows.iter().enumerate() {
+                let cells: Vec<String> = row.iter().map(|text| text.replace('|', "\\|")).collect();
+                let _ = writeln!(out, "| {} |", cells.join(" | "));
+                if index == 0 {
+                    let _ = writeln!(out, "|{}", " --- |".repeat(row.len()));
+                }
+            }
+        }
+    }
+    out
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
@@ -0,0 +1,140 @@
+use std::collections::{HashMap, HashSet};
+
+use crate::board::{Board, Stone};
+use crate::game_info::Rules;
+
+pub struct Score {
+    // Who owns each empty point or dead stone; `Empty` for dame and living stones
+    pub territory: Vec<Vec<Stone>>,
+    pub black: f32,
+    pub white: f32,
+}
+
+impl Score {
+    /// The SGF result, e.g. "W+6.5".
+    pub fn result(&self) -> String {
+        let lead = self.black - self.white;
+        if lead > 0.0 {
+            format!("B+{}", lead)
+        } else if lead < 0.0 {
+            format!("W+{}", -lead)
+        } else {
+            "0".to_string()
+        }
+    }
+
+    /// Gives each point of `owners` to its owner, or to neither as dame, over the owner the count
+    /// found, for open boundaries the count got wrong. Every point must be empty on the board;
+    /// points off it are left out.
+    pub fn reassign(&mut self, owners: &HashMap<(usize, usize), Stone>) {
+        for (&(row, col), &owner) in owners {
+            let Some(point) = self
+                .territory
+                .get_mut(row)
+                .and_then(|line| line.get_mut(col))
+            else {
+                continue;
+            };
+            let previous = std::mem::replace(point, owner);
+            for (stone, change) in [(previous, -1.0), (owner, 1.0)] {
+                match stone {
+                    Stone::Black => self.black += change,
+                    Stone::White => self.white += change,
+                    Stone::Empty => {}
+                }
+            }
+        }
+    }
+}
+
+/// Counts the final position with `dead` stones removed. `captured_black` is the number of black stones
+/// captured during the game, `captured_white` likewise.
+pub fn score(
+    board: &Board,
+    dead: &HashSet<(usize, usize)>,
+    rules: Rules,
+    komi: f32,
+    captured_black: u32,
+    captured_white: u32,
+) -> Score {
+    let size = board.size();
+    let alive =
+        |row: usize, col: usize| board[row][col] != Stone::Empty && !dead.contains(&(row, col));
+    let mut territory = vec![vec![Stone::Empty; size]; size];
+    let mut visited = vec![vec![false; size]; size];
+    let (mut black, mut white) = (0, 0);
+
+    // Flood each region of empty points and dead stones and see whose living stones surround it
+    for row in 0..size {
+        for col in 0..size {
+            if visited[row][col] || alive(row, col) {
+                continue;
+            }
+            let mut region = Vec::new();
+            let (mut touches_black, mut touches_white) = (false, false);
+            let mut stack = vec![(row, col)];
+            visited[row][col] = true;
+            while let Some((r, c)) = stack.pop() {
+                region.push((r, c));
+                for (nr, nc) in board.get_neighbors(r, c) {
+                    if alive(nr, nc) {
+                        match board[nr][nc] {
+                            Stone::Black => touches_black = true,
+                            _ => touches_white = true,
+                        }
+                    } else if !visited[nr][nc] {
+                        visited[nr][nc] = true;
+                        stack.push((nr, nc));
+                    }
+                }
+            }
+            let owner = match (touches_black, touches_white) {
+                (true, false) => Stone::Black,
+                (false, true) => Stone::White,
+                _ => Stone::Empty,
+            };
+            for &(r, c) in &region {
+                territory[r][c] = owner;
+            }
+            match owner {
+                Stone::Black => black += region.len() as u32,
+                Stone::White => white += region.len() as u32,
+                Stone::Empty => {}
+            }
+        }
+    }
+
+    match rules {
+        // Territory plus prisoners, with dead stones counting as prisoners
+        Rules::Japanese => {
+            for &(row, col) in dead.iter().filter(|&&(row, col)| row < size && col < size) {
+                match board[row][col] {
+                    Stone::Black => white += 1,
+                    Stone::White => black += 1,
+                    Stone::Empty => {}
+                }
+            }
+            black += captured_white;
+            white += captured_black;
+        }
+        // Territory plus living stones
+        Rules::Chinese => {
+            for row in 0..size {
+                for col in 0..size {
+                    if alive(row, col) {
+                        match board[row][col] {
+                            Stone::Black => black += 1,
+                            Stone::White => white += 1,
+                            Stone::Empty => {}
+                        }
+                    }
+                }
+            }
+        }
+    }
+    Score {
+        territory,
+        black: black as f32,
+        white: white as f32 + komi,
+    }
+}
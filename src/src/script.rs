@@ -0,0 +1,309 @@
+//! Rhai scripts for home-made bots, annotators and automation. A script defines any of these
+//! functions, each given the game as a `Game` value:
+//!
+//! - `genmove(game)` returns the move to play, such as `"D4"`, `"pass"` or `"resign"`;
+//! - `on_move(game, vertex)` runs after each move, and a string it returns is added to the
+//!   move's comment;
+//! - `on_game_end(game, result)` runs once the game is over, and a string it returns is shown;
+//! - `run(game)` runs when asked to, and what it returns or prints is shown.
+
+use std::cell::RefCell;
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+
+use rhai::{AST, Array, Dynamic, Engine, EvalAltResult, Scope};
+
+use crate::board::{Board, Move, Player, Stone};
+use crate::bot;
+use crate::consts;
+use crate::estimate;
+use crate::game_info::Rules;
+use crate::gtp;
+use crate::notation;
+use crate::paths;
+use crate::scoring;
+
+/// The game as a script sees it. Points are vertices such as "D4", as in GTP.
+#[derive(Clone)]
+pub struct Game {
+    board: Board,
+    to_move: Player,
+    moves: Vec<Move>,
+    // Black stones and white stones captured so far
+    captures: (u32, u32),
+    komi: f32,
+    rules: Rules,
+}
+
+impl Game {
+    pub fn new(
+        board: Board,
+        to_move: Player,
+        moves: Vec<Move>,
+        captures: (u32, u32),
+        komi: f32,
+        rules: Rules,
+    ) -> Self {
+        Self {
+            board,
+            to_move,
+            moves,
+            captures,
+            komi,
+            rules,
+        }
+    }
+
+    fn vertex(&self, point: Option<(usize, usize)>) -> String {
+        point.map_or("pass".to_string(), |point| {
+            notation::format_point(point, self.board.size())
+        })
+    }
+
+    fn point(&self, vertex: &str) -> Result<(usize, usize), Box<EvalAltResult>> {
+        match notation::parse_point(vertex, self.board.size()) {
+            Some(Some(point)) => Ok(point),
+            _ => Err(format!("not a point on the board: {}", vertex).into()),
+        }
+    }
+
+    fn stone(&mut self, vertex: &str) -> Result<String, Box<EvalAltResult>> {
+        let (row, col) = self.point(vertex)?;
+        let stone = match self.board[row][col] {
+            Stone::Black => "X",
+            Stone::White => "O",
+            Stone::Empty => ".",
+        };
+        Ok(stone.to_string())
+    }
+
+    fn is_legal(&mut self, vertex: &str) -> bool {
+        match notation::parse_point(vertex, self.board.size()) {
+            Some(Some((row, col))) => self.board.is_legal(row, col, self.to_move),
+            Some(None) => true,
+            None => false,
+        }
+    }
+
+    fn legal_moves(&mut self) -> Array {
+        let size = self.board.size();
+        (0..size)
+            .flat_map(|row| (0..size).map(move |col| (row, col)))
+            .filter(|&(row, col)| self.board.is_legal(row, col, self.to_move))
+            .map(|point| self.vertex(Some(point)).into())
+            .collect()
+    }
+
+    fn is_eye(&mut self, vertex: &str) -> Result<bool, Box<EvalAltResult>> {
+        let point = self.point(vertex)?;
+        Ok(bot::is_own_eye(&self.board, point, self.to_move))
+    }
+
+    fn liberties(&mut self, vertex: &str) -> Result<i64, Box<EvalAltResult>> {
+        let (row, col) = self.point(vertex)?;
+        match self.board[row][col] {
+            Stone::Empty => Ok(0),
+            _ => Ok(self.board.liberties(row, col).len() as i64),
+        }
+    }
+
+    /// The game after `played`, leaving this one as it was.
+    pub fn after(&self, (player, point): Move) -> Result<Game, String> {
+        let mut after = self.clone();
+        if let Some((row, col)) = point {
+            after
+                .board
+                .check_move(row, col, player)
+                .map_err(|err| format!("{} {}: {}", gtp::color(player), self.vertex(point), err))?;
+            let captured = after.board.play(row, col, player);
+            match player {
+                Player::Black => after.captures.1 += captured,
+                Player::White => after.captures.0 += captured,
+            }
+        } else {
+            after.board.pass();
+        }
+        after.moves.push((player, point));
+        after.to_move = player.other();
+        Ok(after)
+    }
+
+    fn play(&mut self, vertex: &str) -> Result<Game, Box<EvalAltResult>> {
+        match notation::parse_point(vertex, self.board.size()) {
+            Some(point) => Ok(self.after((self.to_move, point))?),
+            None => Err(format!("not a move: {}", vertex).into()),
+        }
+    }
+
+    fn score(&mut self) -> String {
+        scoring::score(
+            &self.board,
+            &Default::default(),
+            self.rules,
+            self.komi,
+            self.captures.0,
+            self.captures.1,
+        )
+        .result()
+    }
+}
+
+fn register(engine: &mut Engine) {
+    engine
+        .register_type_with_name::<Game>("Game")
+        .register_get("size", |game: &mut Game| game.board.size() as i64)
+        .register_get("to_move", |game: &mut Game| {
+            gtp::color(game.to_move).to_string()
+        })
+        .register_get("moves", |game: &mut Game| -> Array {
+            game.moves
+                .iter()
+                .map(|&(_, point)| game.vertex(point).into())
+                .collect()
+        })
+        .register_get("komi", |game: &mut Game| f64::from(game.komi))
+        .register_get("captured_black", |game: &mut Game| {
+            i64::from(game.captures.0)
+        })
+        .register_get("captured_white", |game: &mut Game| {
+            i64::from(game.captures.1)
+        })
+        .register_get("lead", |game: &mut Game| {
+            f64::from(estimate::estimate(&game.board).lead(game.komi))
+        })
+        .register_fn("stone", Game::stone)
+        .register_fn("is_legal", Game::is_legal)
+        .register_fn("legal_moves", Game::legal_moves)
+        .register_fn("is_eye", Game::is_eye)
+        .register_fn("liberties", Game::liberties)
+        .register_fn("play", Game::play)
+        .register_fn("score", Game::score);
+}
+
+/// What a script's `genmove` chose.
+pub enum Choice {
+    Play(Option<(usize, usize)>),
+    Resign,
+}
+
+pub struct Script {
+    pub name: String,
+    engine: Engine,
+    ast: AST,
+    // What the script printed since it was last asked
+    printed: Rc<RefCell<Vec<String>>>,
+}
+
+impl Script {
+    pub fn load(path: &Path) -> Result<Self, String> {
+        let source =
+            std::fs::read_to_string(path).map_err(|err| format!("{}: {}", path.display(), err))?;
+        let mut engine = Engine::new();
+        // Stops a script that loops forever instead of hanging the board
+        engine.set_max_operations(consts::SCRIPT_MAX_OPERATIONS);
+        register(&mut engine);
+        let printed = Rc::new(RefCell::new(Vec::new()));
+        let sink = printed.clone();
+        engine.on_print(move |text| sink.borrow_mut().push(text.to_string()));
+        let sink = printed.clone();
+        engine.on_debug(move |text, _, _| sink.borrow_mut().push(text.to_string()));
+        let ast = engine
+            .compile(&source)
+            .map_err(|err| format!("{}: {}", path.display(), err))?;
+        let name = path
+            .file_stem()
+            .map(|stem| stem.to_string_lossy().into_owned())
+            .unwrap_or_default();
+        Ok(Script {
+            name,
+            engine,
+            ast,
+            printed,
+        })
+    }
+
+    /// Whether the script defines `function` with `params` parameters.
+    pub fn defines(&self, function: &str, params: usize) -> bool {
+        self.ast
+            .iter_functions()
+            .any(|def| def.name == function && def.params.len() == params)
+    }
+
+    fn call(&self, function: &str, args: impl rhai::FuncArgs) -> Result<Dynamic, String> {
+        self.engine
+            .call_fn(&mut Scope::new(), &self.ast, function, args)
+            .map_err(|err| format!("{}: {}", self.name, err))
+    }
+
+    /// The lines printed since the last call, emptied.
+    pub fn take_printed(&self) -> Vec<String> {
+        std::mem::take(&mut self.printed.borrow_mut())
+    }
+
+    pub fn genmove(&self, game: &Game) -> Result<Choice, String> {
+        let reply = self.call("genmove", (game.clone(),))?;
+        let text = reply
+            .into_string()
+            .map_err(|kind| format!("{}: genmove returned a {}, not a string", self.name, kind))?;
+        if text.trim().eq_ignore_ascii_case("resign") {
+            return Ok(Choice::Resign);
+        }
+        notation::parse_point(&text, game.board.size())
+            .map(Choice::Play)
+            .ok_or_else(|| format!("{}: genmove returned {:?}, not a move", self.name, text))
+    }
+
+    /// The comment `on_move` gives the last move of `game`, if the script defines it.
+    pub fn on_move(&self, game: &Game) -> Result<Option<String>, String> {
+        let Some(&(_, point)) = game.moves.last() else {
+            return Ok(None);
+        };
+        if !self.defines("on_move", 2) {
+            return Ok(None);
+        }
+        let vertex = game.vertex(point);
+        self.call("on_move", (game.clone(), vertex)).map(text)
+    }
+
+    pub fn on_game_end(&self, game: &Game, result: &str) -> Result<Option<String>, String> {
+        if !self.defines("on_game_end", 2) {
+            return Ok(None);
+        }
+        self.call("on_game_end", (game.clone(), result.to_string()))
+            .map(text)
+    }
+
+    /// What `run` printed, followed by what it returned.
+    pub fn run(&self, game: &Game) -> Result<String, String> {
+        let returned = self.call("run", (game.clone(),)).map(text);
+        let mut lines = self.take_printed();
+        lines.extend(returned?);
+        Ok(lines.join("\n"))
+    }
+}
+
+// A returned value worth showing: anything but unit and empty strings
+fn text(value: Dynamic) -> Option<String> {
+    if value.is_unit() {
+        return None;
+    }
+    let text = value.to_string();
+    (!text.trim().is_empty()).then_some(text)
+}
+
+/// The scripts in the scripts folder, each loaded or with the reason it could not be.
+pub fn load_all() -> Vec<Result<Script, String>> {
+    let Ok(entries) = std::fs::read_dir(paths::scripts_dir()) else {
+        return Vec::new();
+    };
+    let mut files: Vec<PathBuf> = entries
+        .flatten()
+        .map(|entry| entry.path())
+        .filter(|path| {
+            path.extension()
+                .is_some_and(|extension| extension == "rhai")
+        })
+        .collect();
+    files.sort();
+    files.iter().map(|path| Script::load(path)).collect()
+}
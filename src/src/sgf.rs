@@ -0,0 +1,314 @@
+use std::fmt;
+
+// Records from other players and servers are untrusted: past these a file is refused rather
+// than built into a tree that takes too long, or too deep a stack, to go through
+const MAX_NODES: usize = 1_000_000;
+const MAX_DEPTH: usize = 10_000;
+
+#[derive(Debug, Default)]
+pub struct Node {
+    pub properties: Vec<(String, Vec<String>)>,
+    pub children: Vec<Node>,
+}
+
+// Cloning and dropping go down the tree with a stack of their own, as the derived ones would
+// recurse once per node of a line
+
+impl Clone for Node {
+    fn clone(&self) -> Node {
+        // The nodes being copied, each with the copies of its children made so far
+        let mut open = vec![(self, Vec::with_capacity(self.children.len()))];
+        loop {
+            let next = open
+                .last()
+                .and_then(|(node, children)| node.children.get(children.len()));
+            if let Some(child) = next {
+                open.push((child, Vec::with_capacity(child.children.len())));
+                continue;
+            }
+            let (node, children) = open.pop().unwrap();
+            let copy = Node {
+                properties: node.properties.clone(),
+                children,
+            };
+            match open.last_mut() {
+                Some((_, siblings)) => siblings.push(copy),
+                None => return copy,
+            }
+        }
+    }
+}
+
+impl Drop for Node {
+    fn drop(&mut self) {
+        let mut children = std::mem::take(&mut self.children);
+        while let Some(mut child) = children.pop() {
+            children.append(&mut child.children);
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct ParseError {
+    pub message: &'static str,
+    pub offset: usize,
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "invalid SGF at byte {}: {}", self.offset, self.message)
+    }
+}
+
+impl Node {
+    pub fn get(&self, ident: &str) -> Option<&str> {
+        self.get_all(ident).first().map(|value| value.as_str())
+    }
+
+    pub fn get_all(&self, ident: &str) -> &[String] {
+        self.properties
+            .iter()
+            .find(|(id, _)| id == ident)
+            .map(|(_, values)| values.as_slice())
+            .unwrap_or(&[])
+    }
+
+    /// Replaces all values of `ident`; an empty `values` removes the property.
+    pub fn set(&mut self, ident: &str, values: Vec<String>) {
+        self.properties.retain(|(id, _)| id != ident);
+        if !values.is_empty() {
+            self.properties.push((ident.to_string(), values));
+        }
+    }
+}
+
+/// Iterates over the root and its first children, i.e. the main line of a game tree.
+pub fn main_line(root: &Node) -> impl Iterator<Item = &Node> {
+    std::iter::successors(Some(root), |node| node.children.first())
+}
+
+/// Parses an SGF collection and returns the root node of every game tree in it.
+pub fn parse(input: &str) -> Result<Vec<Node>, ParseError> {
+    let mut parser = Parser {
+        input: input.as_bytes(),
+        pos: 0,
+        nodes: 0,
+    };
+    let mut trees = Vec::new();
+    parser.skip_whitespace();
+    while parser.peek() == Some(b'(') {
+        trees.push(parser.game_tree()?);
+        parser.skip_whitespace();
+    }
+    if trees.is_empty() {
+        return Err(parser.error("expected '('"));
+    }
+    Ok(trees)
+}
+
+/// Reads an `SZ` value; rectangular boards are not supported and yield `None`.
+pub fn parse_size(value: &str) -> Option<usize> {
+    match value.split_once(':') {
+        Some((cols, rows)) if cols.trim() == rows.trim() => cols.trim().parse().ok(),
+        Some(_) => None,
+        None => value.trim().parse().ok(),
+    }
+}
+
+/// Converts an SGF point such as `pd` into `(row, col)`. Empty values and `tt`
+/// on boards up to 19x19 are passes and yield `None`.
+pub fn parse_point(value: &str, board_size: usize) -> Option<(usize, usize)> {
+    let bytes = value.as_bytes();
+    if bytes.len() != 2 {
+        return None;
+    }
+    let col = bytes[0].checked_sub(b'a')? as usize;
+    let row = bytes[1].checked_sub(b'a')? as usize;
+    if row >= board_size || col >= board_size {
+        return None;
+    }
+    Some((row, col))
+}
+
+pub fn format_point((row, col): (usize, usize)) -> String {
+    let mut value = String::new();
+    value.push((b'a' + col as u8) as char);
+    value.push((b'a' + row as u8) as char);
+    value
+}
+
+/// Serializes a game tree, writing single-child chains as one sequence.
+pub fn to_string(root: &Node) -> String {
+    let mut out = String::new();
+    write_tree(root, &mut out);
+    out.push('\n');
+    out
+}
+
+fn write_tree(node: &Node, out: &mut String) {
+    out.push('(');
+    let mut node = node;
+    loop {
+        out.push(';');
+        for (ident, values) in &node.properties {
+            out.push_str(ident);
+            for value in values {
+                out.push('[');
+                for c in value.chars() {
+                    if c == ']' || c == '\\' {
+                        out.push('\\');
+                    }
+                    out.push(c);
+                }
+                out.push(']');
+            }
+        }
+        match node.children.as_slice() {
+            [only] => node = only,
+            children => {
+                for child in children {
+                    write_tree(child, out);
+                }
+                break;
+            }
+        }
+    }
+    out.push(')');
+}
+
+struct Parser<'a> {
+    input: &'a [u8],
+    pos: usize,
+    nodes: usize,
+}
+
+impl Parser<'_> {
+    fn peek(&self) -> Option<u8> {
+        self.input.get(self.pos).copied()
+    }
+
+    fn error(&self, message: &'static str) -> ParseError {
+        ParseError {
+            message,
+            offset: self.pos,
+        }
+    }
+
+    fn skip_whitespace(&mut self) {
+        while self.peek().is_some_and(|b| b.is_ascii_whitespace()) {
+            self.pos += 1;
+        }
+    }
+
+    fn expect(&mut self, byte: u8, message: &'static str) -> Result<(), ParseError> {
+        self.skip_whitespace();
+        if self.peek() != Some(byte) {
+            return Err(self.error(message));
+        }
+        self.pos += 1;
+        Ok(())
+    }
+
+    // Reads the trees nested in one another with a stack rather than by recursion
+    fn game_tree(&mut self) -> Result<Node, ParseError> {
+        // The trees still open, innermost last: the sequence of each and its variations so far
+        let mut open: Vec<(Vec<Node>, Vec<Node>)> = Vec::new();
+        // How many nodes the open trees hold from the root down
+        let mut depth = 0;
+        loop {
+            self.expect(b'(', "expected '('")?;
+            let mut sequence = Vec::new();
+            self.skip_whitespace();
+            while self.peek() == Some(b';') {
+                self.pos += 1;
+                self.nodes += 1;
+                depth += 1;
+                if self.nodes > MAX_NODES {
+                    return Err(self.error("too many nodes"));
+                }
+                if depth > MAX_DEPTH {
+                    return Err(self.error("game tree too deep"));
+                }
+                sequence.push(self.node()?);
+                self.skip_whitespace();
+            }
+            if sequence.is_empty() {
+                return Err(self.error("game tree without nodes"));
+            }
+            open.push((sequence, Vec::new()));
+            // Close every tree that ends here, handing it to the one around it
+            while self.peek() != Some(b'(') {
+                self.expect(b')', "expected ')'")?;
+                let (mut sequence, variations) = open.pop().unwrap();
+                depth -= sequence.len();
+
+                // Fold the sequence back to front so every node owns its successor.
+                let mut last = sequence.pop().unwrap_or_default();
+                last.children = variations;
+                while let Some(mut node) = sequence.pop() {
+                    node.children.push(last);
+                    last = node;
+                }
+                match open.last_mut() {
+                    Some((_, variations)) => variations.push(last),
+                    None => return Ok(last),
+                }
+                self.skip_whitespace();
+            }
+        }
+    }
+
+    fn node(&mut self) -> Result<Node, ParseError> {
+        let mut node = Node::default();
+        loop {
+            self.skip_whitespace();
+            let start = self.pos;
+            while self.peek().is_some_and(|b| b.is_ascii_alphabetic()) {
+                self.pos += 1;
+            }
+            if start == self.pos {
+                return Ok(node);
+            }
+            // FF[3] allowed lowercase letters inside identifiers; only the capitals count.
+            let ident: String = self.input[start..self.pos]
+                .iter()
+                .filter(|b| b.is_ascii_uppercase())
+                .map(|&b| b as char)
+                .collect();
+            let mut values = Vec::new();
+            self.skip_whitespace();
+            while self.peek() == Some(b'[') {
+                self.pos += 1;
+                values.push(self.value()?);
+                self.skip_whitespace();
+            }
+            if values.is_empty() {
+                return Err(self.error("property without value"));
+            }
+            node.properties.push((ident, values));
+        }
+    }
+
+    fn value(&mut self) -> Result<String, ParseError> {
+        let mut bytes = Vec::new();
+        loop {
+            match self.peek() {
+                None => return Err(self.error("unterminated property value")),
+                Some(b']') => break,
+                Some(b'\\') => {
+                    self.pos += 1;
+                    match self.peek() {
+                        None => return Err(self.error("unterminated property value")),
+                        // An escaped line break is a soft break and is removed.
+                        Some(b'\n') => {}
+                        Some(b) => bytes.push(b),
+                    }
+                }
+                Some(b) => bytes.push(b),
+            }
+            self.pos += 1;
+        }
+        self.pos += 1;
+        Ok(String::from_utf8_lossy(&bytes).into_owned())
+    }
+}
@@ -0,0 +1,431 @@
+//! Shape drills: small problems on the shapes every player should know, made from templates
+//! turned and colored at random. A move is checked by reading out the position, so any move
+//! that does the job counts, not only the one of the template.
+
+use std::collections::HashSet;
+
+use crate::board::{Board, Player, Stone};
+use crate::consts;
+use crate::pattern;
+use crate::sgf;
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum Shape {
+    TigersMouth,
+    BambooJoint,
+    Net,
+    Snapback,
+}
+
+impl Shape {
+    pub const ALL: [Shape; 4] = [
+        Shape::TigersMouth,
+        Shape::BambooJoint,
+        Shape::Net,
+        Shape::Snapback,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Shape::TigersMouth => "Tiger's mouth",
+            Shape::BambooJoint => "Bamboo joint",
+            Shape::Net => "Net",
+            Shape::Snapback => "Snapback",
+        }
+    }
+
+    /// What the drill asks for, shown above the board.
+    pub fn task(self) -> &'static str {
+        match self {
+            Shape::TigersMouth => {
+                "Protect the cut between the marked stones with a tiger's mouth: three stones around an empty point, where a cutting stone would be caught at once."
+            }
+            Shape::BambooJoint => {
+                "Connect the marked stones with a bamboo joint: two pairs of stones side by side, with two points between them that cannot both be taken."
+            }
+            Shape::Net => {
+                "Capture the marked stones with a net: a loose move that leaves them no way out, where chasing them along their liberties would let them run."
+            }
+            Shape::Snapback => {
+                "Capture the marked stones with a snapback: throw a stone in where it is taken, and take back more than was given."
+            }
+        }
+    }
+
+    // Whether the drill keeps stones together rather than capturing
+    fn connects(self) -> bool {
+        matches!(self, Shape::TigersMouth | Shape::BambooJoint)
+    }
+}
+
+// A drill on a 9x9 board, for Black. `X` and `O` are stones of the solver and of the opponent,
+// `*` and `@` the same stones marked as the ones to connect or to capture, and the digits the
+// moves of the shape's line in order, starting with the solver's.
+struct Template {
+    shape: Shape,
+    diagram: [&'static str; 9],
+    // The line ends with the solver taking back on the point of their first move, as in a
+    // snapback
+    retake: bool,
+}
+
+const TEMPLATES: [Template; 8] = [
+    Template {
+        shape: Shape::TigersMouth,
+        diagram: [
+            ".........",
+            ".........",
+            "....O....",
+            "...O*....",
+            "...*.1...",
+            ".........",
+            ".........",
+            ".........",
+            ".........",
+        ],
+        retake: false,
+    },
+    Template {
+        shape: Shape::TigersMouth,
+        diagram: [
+            ".........",
+            ".........",
+            ".........",
+            ".........",
+            ".........",
+            "...O.O...",
+            "....1....",
+            "...*.*...",
+            ".........",
+        ],
+        retake: false,
+    },
+    Template {
+        shape: Shape::BambooJoint,
+        diagram: [
+            ".........",
+            ".........",
+            ".........",
+            ".........",
+            "..**.....",
+            ".O..O....",
+            "..*1.....",
+            ".........",
+            ".........",
+        ],
+        retake: false,
+    },
+    Template {
+        shape: Shape::BambooJoint,
+        diagram: [
+            ".........",
+            ".....O...",
+            "......*.*",
+            "......*.1",
+            ".......O.",
+            ".........",
+            ".........",
+            ".........",
+            ".........",
+        ],
+        retake: false,
+    },
+    Template {
+        shape: Shape::Net,
+        diagram: [
+            ".........",
+            ".........",
+            "......O..",
+            "...XX....",
+            "..X@.....",
+            "..X.1....",
+            ".........",
+            ".O.......",
+            ".........",
+        ],
+        retake: false,
+    },
+    Template {
+        shape: Shape::Net,
+        diagram: [
+            ".........",
+            ".......O.",
+            "..XXXX...",
+            "..X@@....",
+            "...X.1...",
+            ".........",
+            ".........",
+            "O........",
+            ".........",
+        ],
+        retake: false,
+    },
+    Template {
+        shape: Shape::Snapback,
+        diagram: [
+            ".........",
+            ".........",
+            ".........",
+            ".........",
+            ".........",
+            ".........",
+            "XXXXXX...",
+            "X@@@XXO..",
+            "X@21OOO..",
+        ],
+        retake: true,
+    },
+    Template {
+        shape: Shape::Snapback,
+        diagram: [
+            ".........",
+            ".........",
+            ".........",
+            ".........",
+            ".........",
+            ".........",
+            "XXXXO....",
+            "@@@XO....",
+            "@21OO....",
+        ],
+        retake: true,
+    },
+];
+
+const SIZE: usize = 9;
+
+pub struct Drill {
+    pub shape: Shape,
+    pub solver: Player,
+    stones: Vec<((usize, usize), Player)>,
+    marked: Vec<(usize, usize)>,
+    line: Vec<(usize, usize)>,
+}
+
+impl Drill {
+    /// A drill on `shape`, or on any shape, with the template, its turn and its colors picked
+    /// by `seed`.
+    pub fn generate(shape: Option<Shape>, seed: u64) -> Self {
+        // Seeds that differ only a little still pick unlike drills
+        let seed = seed.wrapping_mul(0x9e37_79b9_7f4a_7c15) >> 32;
+        let templates: Vec<&Template> = TEMPLATES
+            .iter()
+            .filter(|template| shape.is_none_or(|shape| template.shape == shape))
+            .collect();
+        let template = templates[(seed % templates.len() as u64) as usize];
+        let transform = (seed / 8 % 8) as usize;
+        let solver = if (seed / 64).is_multiple_of(2) {
+            Player::Black
+        } else {
+            Player::White
+        };
+        let mut drill = Drill {
+            shape: template.shape,
+            solver,
+            stones: Vec::new(),
+            marked: Vec::new(),
+            line: Vec::new(),
+        };
+        let mut line = Vec::new();
+        for (row, text) in template.diagram.iter().enumerate() {
+            for (col, cell) in text.chars().enumerate() {
+                let point = pattern::transform((row, col), transform, SIZE);
+                match cell {
+                    'X' | '*' => drill.stones.push((point, solver)),
+                    'O' | '@' => drill.stones.push((point, solver.other())),
+                    _ => {}
+                }
+                if matches!(cell, '*' | '@') {
+                    drill.marked.push(point);
+                }
+                if let Some(order) = cell.to_digit(10) {
+                    line.push((order, point));
+                }
+            }
+        }
+        line.sort();
+        drill.line = line.into_iter().map(|(_, point)| point).collect();
+        if template.retake {
+            drill.line.push(drill.line[0]);
+        }
+        drill
+    }
+
+    /// The drill as a problem record: the stones set up with the marked ones under triangles,
+    /// and the shape's line as the solution.
+    pub fn problem(&self) -> sgf::Node {
+        let mut root = sgf::Node::default();
+        root.set("SZ", vec![SIZE.to_string()]);
+        for (ident, player) in [("AB", Player::Black), ("AW", Player::White)] {
+            let points = self
+                .stones
+                .iter()
+                .filter(|&&(_, owner)| owner == player)
+                .map(|&(point, _)| sgf::format_point(point))
+                .collect();
+            root.set(ident, points);
+        }
+        root.set(
+            "TR",
+            self.marked
+                .iter()
+                .map(|&point| sgf::format_point(point))
+                .collect(),
+        );
+        let color = |player| match player {
+            Player::Black => "B",
+            Player::White => "W",
+        };
+        root.set("PL", vec![color(self.solver).to_string()]);
+        let mut player = self.solver;
+        let mut nodes = Vec::new();
+        for &point in &self.line {
+            let mut node = sgf::Node::default();
+            node.set(color(player), vec![sgf::format_point(point)]);
+            nodes.push(node);
+            player = player.other();
+        }
+        root.children = nodes
+            .into_iter()
+            .rev()
+            .fold(Vec::new(), |children, mut node| {
+                node.children = children;
+                vec![node]
+            });
+        root
+    }
+
+    /// The first move of the shape.
+    pub fn answer(&self) -> (usize, usize) {
+        self.line[0]
+    }
+
+    /// Whether the solver playing `point` on `board` does what the drill asks.
+    pub fn works(&self, board: &Board, (row, col): (usize, usize)) -> bool {
+        if !board.is_legal(row, col, self.solver) {
+            return false;
+        }
+        let mut board = board.clone();
+        board.play(row, col, self.solver);
+        if self.shape.connects() {
+            connected(&board, &self.marked, self.solver)
+        } else {
+            self.marked.iter().all(|&target| {
+                captured(
+                    &board,
+                    target,
+                    self.solver.other(),
+                    consts::SHAPE_READING_DEPTH,
+                )
+            })
+        }
+    }
+}
+
+fn owner(stone: Stone) -> Option<Player> {
+    match stone {
+        Stone::Black => Some(Player::Black),
+        Stone::White => Some(Player::White),
+        Stone::Empty => None,
+    }
+}
+
+// Whether the group at `target` is taken with `to_move` playing next, reading `depth` moves
+// ahead. Only moves on the group's liberties are read, and captures of stones next to it; a
+// group that reaches three liberties has got away.
+fn captured(board: &Board, target: (usize, usize), to_move: Player, depth: usize) -> bool {
+    let Some(defender) = owner(board[target.0][target.1]) else {
+        return true;
+    };
+    let liberties = board.liberties(target.0, target.1);
+    let after = |point: Option<(usize, usize)>, player: Player| {
+        let mut next = board.clone();
+        match point {
+            Some((row, col)) => {
+                next.play(row, col, player);
+            }
+            None => next.pass(),
+        }
+        captured(&next, target, player.other(), depth - 1)
+    };
+    if to_move != defender {
+        if liberties.len() == 1 {
+            return true;
+        }
+        return depth > 0
+            && liberties.len() == 2
+            && liberties.iter().any(|&(row, col)| {
+                board.is_legal(row, col, to_move) && after(Some((row, col)), to_move)
+            });
+    }
+    if liberties.len() > 2 || depth == 0 {
+        return false;
+    }
+    let mut escapes: HashSet<(usize, usize)> = liberties;
+    for (row, col) in board.get_group(target.0, target.1, defender.to_stone()) {
+        for (nr, nc) in board.get_neighbors(row, col) {
+            if board[nr][nc] == defender.other().to_stone() {
+                let outside = board.liberties(nr, nc);
+                if outside.len() == 1 {
+                    escapes.extend(outside);
+                }
+            }
+        }
+    }
+    escapes
+        .into_iter()
+        .filter(|&(row, col)| board.is_legal(row, col, defender))
+        .map(Some)
+        .chain([None])
+        .all(|point| after(point, defender))
+}
+
+// Whether the `marked` stones of `player` hold together: their groups are linked, directly or
+// through other groups of theirs, by two shared liberties or by one where a cutting stone
+// would be caught at once
+fn connected(board: &Board, marked: &[(usize, usize)], player: Player) -> bool {
+    let stone = player.to_stone();
+    let mut groups: Vec<HashSet<(usize, usize)>> = Vec::new();
+    for row in 0..board.size() {
+        for col in 0..board.size() {
+            if board[row][col] == stone && groups.iter().all(|group| !group.contains(&(row, col))) {
+                groups.push(board.get_group(row, col, stone));
+            }
+        }
+    }
+    let liberties: Vec<HashSet<(usize, usize)>> = groups
+        .iter()
+        .map(|group| {
+            let &(row, col) = group.iter().next().expect("groups are not empty");
+            board.liberties(row, col)
+        })
+        .collect();
+    let linked = |a: usize, b: usize| {
+        let shared: Vec<&(usize, usize)> = liberties[a].intersection(&liberties[b]).collect();
+        shared.len() >= 2
+            || shared.iter().any(|&&(row, col)| {
+                !board.is_legal(row, col, player.other())
+                    || board.would_be_self_atari(row, col, player.other())
+            })
+    };
+    // Spreads from the group of the first marked stone to every group linked to it
+    let Some(start) = marked
+        .first()
+        .and_then(|point| groups.iter().position(|group| group.contains(point)))
+    else {
+        return false;
+    };
+    let mut reached = vec![start];
+    let mut index = 0;
+    while let Some(&group) = reached.get(index) {
+        for other in 0..groups.len() {
+            if !reached.contains(&other) && linked(group, other) {
+                reached.push(other);
+            }
+        }
+        index += 1;
+    }
+    marked
+        .iter()
+        .all(|point| reached.iter().any(|&group| groups[group].contains(point)))
+}
@@ -0,0 +1,103 @@
+//! Links that open a game in the web version of the app, with the record carried in the link
+//! itself, and QR codes of them, for a phone at the club to pick the game up from the screen.
+
+use base64::Engine;
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use eframe::egui;
+use qrcode::{Color, EcLevel, QrCode};
+
+use crate::sgf;
+
+// What comes before the record in the part of a link after the #
+const FRAGMENT_KEY: &str = "sgf=";
+// Root properties a phone can do without, which would only make the code denser
+const DROPPED: &[&str] = &["C", "BL", "WL", "OB", "OW", "AP", "CA", "FF"];
+
+/// A link with its code, made when the share dialog opens so it stays the same while it shows.
+pub struct Shared {
+    // The address of the web version it was made for
+    pub viewer: String,
+    pub link: String,
+    // `None` when the game is too long to fit
+    pub code: Option<QrCode>,
+}
+
+impl Shared {
+    pub fn new(viewer: &str, root: &sgf::Node) -> Self {
+        let link = link(viewer, root);
+        Shared {
+            viewer: viewer.to_string(),
+            code: QrCode::with_error_correction_level(link.as_bytes(), EcLevel::L).ok(),
+            link,
+        }
+    }
+}
+
+// The main line of `root` with its setup, players and result, without comments, times or
+// variations
+fn record(root: &sgf::Node) -> sgf::Node {
+    let mut nodes = sgf::main_line(root).map(|node| sgf::Node {
+        properties: node
+            .properties
+            .iter()
+            .filter(|(ident, _)| !DROPPED.contains(&ident.as_str()))
+            .filter(|(ident, _)| {
+                std::ptr::eq(node, root) || ["B", "W", "AB", "AW", "AE"].contains(&ident.as_str())
+            })
+            .cloned()
+            .collect(),
+        children: Vec::new(),
+    });
+    let mut shared = nodes.next().unwrap_or_default();
+    let mut tail = &mut shared;
+    for node in nodes {
+        tail.children.push(node);
+        tail = &mut tail.children[0];
+    }
+    shared
+}
+
+/// The link to `viewer` that opens `root`.
+pub fn link(viewer: &str, root: &sgf::Node) -> String {
+    let page = viewer.trim().split('#').next().unwrap_or_default();
+    let text = sgf::to_string(&record(root));
+    format!(
+        "{}#{}{}",
+        page,
+        FRAGMENT_KEY,
+        URL_SAFE_NO_PAD.encode(text.as_bytes())
+    )
+}
+
+/// The record in the part of a link after the #, with or without the #.
+#[cfg(target_arch = "wasm32")]
+pub fn from_fragment(fragment: &str) -> Option<String> {
+    let encoded = fragment
+        .trim_start_matches('#')
+        .strip_prefix(FRAGMENT_KEY)?;
+    let bytes = URL_SAFE_NO_PAD.decode(encoded).ok()?;
+    String::from_utf8(bytes).ok()
+}
+
+/// Draws `code` `side` points across, dark on white with the margin scanners need.
+pub fn paint(ui: &mut egui::Ui, code: &QrCode, side: f32) {
+    let (response, painter) = ui.allocate_painter(egui::Vec2::splat(side), egui::Sense::hover());
+    let rect = response.rect;
+    painter.rect_filled(rect, 0.0, egui::Color32::WHITE);
+    // Four modules of white all round
+    let width = code.width();
+    let module = side / (width + 8) as f32;
+    for (index, color) in code.to_colors().into_iter().enumerate() {
+        if color == Color::Dark {
+            let (x, y) = ((index % width + 4) as f32, (index / width + 4) as f32);
+            painter.rect_filled(
+                egui::Rect::from_min_size(
+                    rect.min + egui::vec2(x, y) * module,
+                    egui::Vec2::splat(module),
+                ),
+                0.0,
+                egui::Color32::BLACK,
+            );
+        }
+    }
+}
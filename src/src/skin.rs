@@ -0,0 +1,48 @@
+use std::path::Path;
+
+use eframe::egui;
+
+use crate::paths;
+
+/// Images from one directory under the skins folder. Each one is optional; whatever is missing is
+/// drawn the usual way.
+pub struct Skin {
+    pub name: String,
+    pub board: Option<egui::TextureHandle>,
+    pub black: Option<egui::TextureHandle>,
+    pub white: Option<egui::TextureHandle>,
+}
+
+/// The names of the skin directories, sorted.
+pub fn available() -> Vec<String> {
+    let Ok(entries) = std::fs::read_dir(paths::skins_dir()) else {
+        return Vec::new();
+    };
+    let mut names: Vec<String> = entries
+        .filter_map(Result::ok)
+        .filter(|entry| entry.path().is_dir())
+        .filter_map(|entry| entry.file_name().into_string().ok())
+        .collect();
+    names.sort();
+    names
+}
+
+fn load_texture(ctx: &egui::Context, path: &Path) -> Option<egui::TextureHandle> {
+    let image = image::open(path).ok()?.into_rgba8();
+    let size = [image.width() as usize, image.height() as usize];
+    let pixels = egui::ColorImage::from_rgba_unmultiplied(size, image.as_raw());
+    Some(ctx.load_texture(path.to_string_lossy(), pixels, egui::TextureOptions::LINEAR))
+}
+
+impl Skin {
+    /// Loads `board.png`, `black.png` and `white.png` from the skin directory `name`.
+    pub fn load(ctx: &egui::Context, name: &str) -> Self {
+        let dir = paths::skins_dir().join(name);
+        Self {
+            name: name.to_string(),
+            board: load_texture(ctx, &dir.join("board.png")),
+            black: load_texture(ctx, &dir.join("black.png")),
+            white: load_texture(ctx, &dir.join("white.png")),
+        }
+    }
+}
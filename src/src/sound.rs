@@ -0,0 +1,67 @@
+use crate::paths;
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum Sound {
+    Stone,
+    Capture,
+    GameEnd,
+    TimeWarning,
+}
+
+impl Sound {
+    // Looked up in the sound pack directory as `<stem>.wav` or `<stem>.ogg`
+    #[cfg_attr(not(feature = "sound"), allow(dead_code))]
+    fn file_stem(self) -> &'static str {
+        match self {
+            Sound::Stone => "stone",
+            Sound::Capture => "capture",
+            Sound::GameEnd => "game_end",
+            Sound::TimeWarning => "time_warning",
+        }
+    }
+}
+
+/// The names of the sound pack directories, sorted.
+pub fn packs() -> Vec<String> {
+    let Ok(entries) = std::fs::read_dir(paths::sounds_dir()) else {
+        return Vec::new();
+    };
+    let mut names: Vec<String> = entries
+        .filter_map(Result::ok)
+        .filter(|entry| entry.path().is_dir())
+        .filter_map(|entry| entry.file_name().into_string().ok())
+        .collect();
+    names.sort();
+    names
+}
+
+/// The audio output, opened on the first sound. Stays silent when built without the `sound` feature
+/// or when there is no output device.
+#[derive(Default)]
+pub struct Audio {
+    #[cfg(feature = "sound")]
+    sink: Option<rodio::MixerDeviceSink>,
+}
+
+impl Audio {
+    #[cfg(feature = "sound")]
+    pub fn play(&mut self, pack: &str, sound: Sound, volume: f32) {
+        let dir = paths::sounds_dir().join(pack);
+        let Some(file) = ["wav", "ogg"].iter().find_map(|extension| {
+            std::fs::File::open(dir.join(sound.file_stem()).with_extension(extension)).ok()
+        }) else {
+            return;
+        };
+        if self.sink.is_none() {
+            self.sink = rodio::DeviceSinkBuilder::open_default_sink().ok();
+        }
+        if let Some(sink) = &self.sink
+            && let Ok(source) = rodio::Decoder::try_from(file)
+        {
+            sink.mixer().add(rodio::Source::amplify(source, volume));
+        }
+    }
+
+    #[cfg(not(feature = "sound"))]
+    pub fn play(&mut self, _pack: &str, _sound: Sound, _volume: f32) {}
+}
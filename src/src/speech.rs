@@ -0,0 +1,32 @@
+use std::process::{Child, Command, Stdio};
+
+/// Reads text aloud through an external program such as `espeak` or `say`, given the text as its
+/// last argument.
+#[derive(Default)]
+pub struct Speaker {
+    child: Option<Child>,
+}
+
+impl Speaker {
+    /// Starts speaking `text`, cutting off whatever is still being said.
+    pub fn say(&mut self, command_line: &str, text: &str) -> std::io::Result<()> {
+        if let Some(mut child) = self.child.take() {
+            let _ = child.kill();
+            let _ = child.wait();
+        }
+        let mut parts = command_line.split_whitespace();
+        let Some(program) = parts.next() else {
+            return Ok(());
+        };
+        self.child = Some(
+            Command::new(program)
+                .args(parts)
+                .arg(text)
+                .stdin(Stdio::null())
+                .stdout(Stdio::null())
+                .stderr(Stdio::null())
+                .spawn()?,
+        );
+        Ok(())
+    }
+}
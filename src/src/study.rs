@@ -0,0 +1,106 @@
+//! Two games side by side for study, such as a student's game beside the professional game it
+//! followed, stepped through together or each on its own.
+
+use eframe::egui;
+
+use crate::board::Board;
+use crate::compare;
+use crate::consts;
+use crate::i18n::{tr, trf};
+use crate::theme::BoardTheme;
+
+/// The split view while it is up.
+pub struct Study {
+    // The tabs on the left and on the right
+    pub tabs: [usize; 2],
+    // Whether stepping through one game takes the other to the same move
+    pub synced: bool,
+    // Whether the points where the two positions differ are framed
+    pub differences: bool,
+}
+
+/// One of the games as the split view shows it.
+pub struct Side {
+    pub title: String,
+    pub board: Board,
+    pub last_move: Option<(usize, usize)>,
+    // The move on the board, out of those in the line it is on
+    pub moves: usize,
+    pub length: usize,
+    pub comment: String,
+    // Whether the game can go to another move, which a game being played may not
+    pub can_step: bool,
+}
+
+#[derive(Clone, Copy)]
+pub enum Step {
+    Start,
+    Back,
+    Forward,
+    End,
+}
+
+impl Step {
+    const ALL: [Step; 4] = [Self::Start, Self::Back, Self::Forward, Self::End];
+
+    fn name(self) -> &'static str {
+        match self {
+            Self::Start => "Start",
+            Self::Back => "Previous",
+            Self::Forward => "Next",
+            Self::End => "End",
+        }
+    }
+
+    /// The move number the step leads to from move `moves`, past the end for the last one.
+    pub fn from(self, moves: usize) -> usize {
+        match self {
+            Self::Start => 0,
+            Self::Back => moves.saturating_sub(1),
+            Self::Forward => moves + 1,
+            Self::End => usize::MAX,
+        }
+    }
+}
+
+/// Draws the two games over `ui`, each with its board, its move and the comment on it, and the
+/// points of `differences` framed on both. Returns the side stepped through and how.
+pub fn show(
+    ui: &mut egui::Ui,
+    sides: &[Side; 2],
+    differences: &[(usize, usize)],
+    theme: BoardTheme,
+) -> Option<(usize, Step)> {
+    let mut stepped = None;
+    let side = (ui.available_width() / 2.0 - 16.0)
+        .min(ui.available_height() - consts::STUDY_TEXT_HEIGHT)
+        .max(consts::STUDY_MIN_BOARD_SIZE);
+    ui.columns(2, |columns| {
+        for (index, (ui, game)) in columns.iter_mut().zip(sides).enumerate() {
+            ui.strong(&game.title);
+            compare::paint(ui, &game.board, differences, game.last_move, theme, side);
+            ui.horizontal(|ui| {
+                for step in Step::ALL {
+                    let enabled = game.can_step
+                        && match step {
+                            Step::Start | Step::Back => game.moves > 0,
+                            Step::Forward | Step::End => game.moves < game.length,
+                        };
+                    if ui
+                        .add_enabled(enabled, egui::Button::new(tr(step.name())))
+                        .clicked()
+                    {
+                        stepped = Some((index, step));
+                    }
+                }
+                ui.label(trf("Move {} / {}", &[&game.moves, &game.length]));
+            });
+            if !game.comment.is_empty() {
+                egui::ScrollArea::vertical()
+                    .id_salt(("study_comment", index))
+                    .show(ui, |ui| ui.label(&game.comment));
+            }
+        }
+    });
+    stepped
+}
@@ -0,0 +1,92 @@
+//! Keeps the games played here in a folder that a file sync tool shares between computers. Every
+//! computer writes files of its own, named after the game and the computer, so the tool never
+//! sees two of them change one file; the newest file of each game is the one to play on.
+
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::atomic::{AtomicU32, Ordering};
+
+use crate::library::{self, Entry};
+
+static NEXT_GAME: AtomicU32 = AtomicU32::new(0);
+
+/// A name no other game gets, here or on another computer sharing the folder.
+pub fn new_game_id() -> String {
+    let millis = web_time::SystemTime::now()
+        .duration_since(web_time::UNIX_EPOCH)
+        .map(|elapsed| elapsed.as_millis())
+        .unwrap_or_default();
+    format!(
+        "game{}x{}",
+        millis,
+        NEXT_GAME.fetch_add(1, Ordering::Relaxed)
+    )
+}
+
+// This computer's name as it goes into file names: letters and digits only, so it cannot be
+// mistaken for part of the game's name
+fn device_name() -> String {
+    let name = std::env::var("COMPUTERNAME")
+        .or_else(|_| std::env::var("HOSTNAME"))
+        .ok()
+        .or_else(|| std::fs::read_to_string("/etc/hostname").ok())
+        .unwrap_or_default();
+    let name: String = name
+        .chars()
+        .filter(char::is_ascii_alphanumeric)
+        .collect::<String>()
+        .to_lowercase();
+    if name.is_empty() {
+        "computer".to_string()
+    } else {
+        name
+    }
+}
+
+/// The game a file in the folder belongs to. Copies the sync tool makes of a file when two
+/// computers changed it, such as `game1x0-laptop.sync-conflict-....sgf` or
+/// `game1x0-laptop (conflicted copy).sgf`, belong to the same game.
+pub fn game_id(path: &Path) -> Option<String> {
+    let name = path.file_name()?.to_str()?;
+    let end = name
+        .find(|c: char| !c.is_ascii_alphanumeric() && c != '-')
+        .unwrap_or(name.len());
+    let (id, _device) = name[..end].rsplit_once('-')?;
+    id.starts_with("game").then(|| id.to_string())
+}
+
+/// Writes the record of the game `id` to this computer's file for it, all at once so the sync
+/// tool never picks up half of it.
+pub fn write(folder: &Path, id: &str, sgf: &str) -> std::io::Result<()> {
+    std::fs::create_dir_all(folder)?;
+    let name = format!("{}-{}.sgf", id, device_name());
+    let part = folder.join(format!(".{}.part", name));
+    std::fs::write(&part, sgf)?;
+    std::fs::rename(part, folder.join(name))
+}
+
+/// The games in the folder, each only in its most recently changed file.
+pub fn games(folder: &Path) -> Vec<Entry> {
+    let modified = |entry: &Entry| {
+        std::fs::metadata(&entry.path)
+            .and_then(|metadata| metadata.modified())
+            .ok()
+    };
+    let mut newest: HashMap<String, Entry> = HashMap::new();
+    let mut others = Vec::new();
+    for entry in library::scan(folder) {
+        let Some(id) = game_id(&entry.path) else {
+            others.push(entry);
+            continue;
+        };
+        if newest
+            .get(&id)
+            .is_none_or(|known| modified(known) < modified(&entry))
+        {
+            newest.insert(id, entry);
+        }
+    }
+    others.extend(newest.into_values());
+    others.sort_by(|a, b| a.path.cmp(&b.path));
+    others
+}
@@ -0,0 +1,32 @@
+use std::sync::mpsc::{self, Receiver};
+
+use eframe::egui;
+
+/// Runs `job` on a background thread and wakes the UI once it has finished.
+#[cfg(not(target_arch = "wasm32"))]
+pub fn spawn<T, F>(ctx: &egui::Context, job: F) -> Receiver<T>
+where
+    T: Send + 'static,
+    F: FnOnce() -> T + Send + 'static,
+{
+    let (sender, receiver) = mpsc::channel();
+    let ctx = ctx.clone();
+    std::thread::spawn(move || {
+        let _ = sender.send(job());
+        ctx.request_repaint();
+    });
+    receiver
+}
+
+// Browsers have no threads, so the job runs before the next frame instead
+#[cfg(target_arch = "wasm32")]
+pub fn spawn<T, F>(ctx: &egui::Context, job: F) -> Receiver<T>
+where
+    T: Send + 'static,
+    F: FnOnce() -> T + Send + 'static,
+{
+    let (sender, receiver) = mpsc::channel();
+    let _ = sender.send(job());
+    ctx.request_repaint();
+    receiver
+}
@@ -0,0 +1,170 @@
+use eframe::egui::{self, Color32};
+use serde::{Deserialize, Serialize};
+
+use crate::board::Stone;
+use crate::consts;
+
+#[derive(Clone, Copy, PartialEq, Debug, Serialize, Deserialize)]
+pub enum UiTheme {
+    Light,
+    Dark,
+}
+
+impl UiTheme {
+    pub const ALL: [UiTheme; 2] = [UiTheme::Light, UiTheme::Dark];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            UiTheme::Light => "Light",
+            UiTheme::Dark => "Dark",
+        }
+    }
+
+    pub fn visuals(self) -> egui::Visuals {
+        match self {
+            UiTheme::Light => egui::Visuals::light(),
+            UiTheme::Dark => egui::Visuals::dark(),
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Debug, Serialize, Deserialize)]
+pub enum BoardTheme {
+    Kaya,
+    Walnut,
+    Slate,
+    Paper,
+}
+
+impl BoardTheme {
+    pub const ALL: [BoardTheme; 4] = [
+        BoardTheme::Kaya,
+        BoardTheme::Walnut,
+        BoardTheme::Slate,
+        BoardTheme::Paper,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            BoardTheme::Kaya => "Kaya",
+            BoardTheme::Walnut => "Walnut",
+            BoardTheme::Slate => "Slate",
+            BoardTheme::Paper => "Paper",
+        }
+    }
+
+    pub fn background(self) -> Color32 {
+        match self {
+            BoardTheme::Kaya => Color32::from_rgb(220, 179, 92),
+            BoardTheme::Walnut => Color32::from_rgb(150, 105, 60),
+            BoardTheme::Slate => Color32::from_rgb(120, 135, 140),
+            BoardTheme::Paper => Color32::from_rgb(245, 242, 232),
+        }
+    }
+
+    pub fn lines(self) -> Color32 {
+        match self {
+            BoardTheme::Kaya => Color32::from_rgb(101, 67, 33),
+            BoardTheme::Walnut => Color32::from_rgb(50, 30, 15),
+            BoardTheme::Slate => Color32::from_rgb(30, 35, 40),
+            BoardTheme::Paper => Color32::from_rgb(60, 60, 60),
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Debug, Serialize, Deserialize)]
+pub enum MarkerShape {
+    Circle,
+    Triangle,
+    Square,
+}
+
+impl MarkerShape {
+    pub const ALL: [MarkerShape; 3] = [
+        MarkerShape::Circle,
+        MarkerShape::Triangle,
+        MarkerShape::Square,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            MarkerShape::Circle => "Circle",
+            MarkerShape::Triangle => "Triangle",
+            MarkerShape::Square => "Square",
+        }
+    }
+
+    /// Outlines the shape inside a circle of `radius` around `center`.
+    pub fn paint(
+        self,
+        painter: &egui::Painter,
+        center: egui::Pos2,
+        radius: f32,
+        stroke: egui::Stroke,
+    ) {
+        match self {
+            MarkerShape::Circle => {
+                painter.circle_stroke(center, radius, stroke);
+            }
+            MarkerShape::Triangle => {
+                let points = [90.0f32, 210.0, 330.0]
+                    .iter()
+                    .map(|degrees| {
+                        let angle = degrees.to_radians();
+                        center + egui::vec2(angle.cos(), -angle.sin()) * radius
+                    })
+                    .collect();
+                painter.add(egui::Shape::closed_line(points, stroke));
+            }
+            MarkerShape::Square => {
+                let half = radius * std::f32::consts::FRAC_1_SQRT_2;
+                painter.rect_stroke(
+                    egui::Rect::from_center_size(center, egui::Vec2::splat(half * 2.0)),
+                    0.0,
+                    stroke,
+                    egui::StrokeKind::Middle,
+                );
+            }
+        }
+    }
+}
+
+/// The color for markers drawn on `stone`. In high contrast it is the opposite stone color, which
+/// stands out without relying on telling hues apart.
+pub fn marker_color(stone: Stone, high_contrast: bool) -> Color32 {
+    match (high_contrast, stone) {
+        (false, _) => Color32::RED,
+        (true, Stone::Black) => Color32::WHITE,
+        (true, _) => Color32::BLACK,
+    }
+}
+
+/// Translucent shading for points owned by `owner`, stronger in high contrast.
+pub fn territory_color(owner: Stone, high_contrast: bool) -> Option<Color32> {
+    let alpha = if high_contrast { 200 } else { 100 };
+    match owner {
+        Stone::Black => Some(Color32::from_rgba_unmultiplied(0, 0, 0, alpha)),
+        Stone::White => Some(Color32::from_rgba_unmultiplied(255, 255, 255, alpha + 40)),
+        Stone::Empty => None,
+    }
+}
+
+/// Adds the first CJK font found on the system behind the default fonts, so Japanese coordinates
+/// and translations render instead of showing boxes.
+pub fn install_fallback_font(ctx: &egui::Context) {
+    let Some(data) = consts::CJK_FONT_PATHS
+        .iter()
+        .find_map(|path| std::fs::read(path).ok())
+    else {
+        return;
+    };
+    let mut fonts = egui::FontDefinitions::default();
+    fonts.font_data.insert(
+        "cjk".to_string(),
+        std::sync::Arc::new(egui::FontData::from_owned(data)),
+    );
+    for family in fonts.families.values_mut() {
+        family.push("cjk".to_string());
+    }
+    ctx.set_fonts(fonts);
+}
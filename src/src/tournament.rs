@@ -0,0 +1,676 @@
+//! Tournaments played over the board at a real event: the players, the pairings of each round
+//! and their results, kept in a file of their own so the director can close the app between
+//! rounds. Pairings are Swiss, or McMahon with players starting on a score from their rank.
+
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+use crate::consts;
+use crate::game_info::Rules;
+use crate::i18n::{tr, trf};
+use crate::paths;
+use crate::pdf::{self, Page};
+use crate::profiles;
+use crate::report;
+
+#[derive(Clone, Copy, PartialEq, Debug, Serialize, Deserialize)]
+pub enum System {
+    Swiss,
+    McMahon,
+}
+
+impl System {
+    pub const ALL: [System; 2] = [System::Swiss, System::McMahon];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            System::Swiss => "Swiss",
+            System::McMahon => "McMahon",
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Debug, Serialize, Deserialize)]
+pub enum Outcome {
+    Pending,
+    BlackWins,
+    WhiteWins,
+    Jigo,
+}
+
+impl Outcome {
+    pub const ALL: [Outcome; 4] = [
+        Outcome::Pending,
+        Outcome::BlackWins,
+        Outcome::WhiteWins,
+        Outcome::Jigo,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Outcome::Pending => "Not played",
+            Outcome::BlackWins => "Black wins",
+            Outcome::WhiteWins => "White wins",
+            Outcome::Jigo => "Jigo",
+        }
+    }
+
+    /// The outcome of a game with the SGF `result`, pending when it has no winner.
+    pub fn from_result(result: &str) -> Outcome {
+        match profiles::black_score(result) {
+            Some(score) if score > 0.5 => Outcome::BlackWins,
+            Some(score) if score < 0.5 => Outcome::WhiteWins,
+            Some(_) => Outcome::Jigo,
+            None => Outcome::Pending,
+        }
+    }
+}
+
+#[derive(Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Entrant {
+    pub name: String,
+    // As written in records, such as "5k" or "2d"
+    pub rank: String,
+    pub club: String,
+    // Left out of the pairings from the next round on
+    pub withdrawn: bool,
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Pairing {
+    // Indices into the players
+    pub black: usize,
+    pub white: usize,
+    pub handicap: u32,
+    pub komi: f32,
+    pub outcome: Outcome,
+    // The record of the game when it was played here
+    #[serde(default)]
+    pub game: Option<PathBuf>,
+}
+
+#[derive(Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Round {
+    pub pairings: Vec<Pairing>,
+    // The player left over from an odd number, who scores a win
+    pub bye: Option<usize>,
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Tournament {
+    pub name: String,
+    pub system: System,
+    // Rounds planned; more can be paired
+    pub rounds: u32,
+    // In McMahon, players at this rank or above all start on the top score
+    pub bar: String,
+    // Handicap games by the gap in rank, with the weaker player as Black
+    pub handicap: bool,
+    pub board_size: usize,
+    pub komi: f32,
+    pub rules: Rules,
+    pub players: Vec<Entrant>,
+    pub played: Vec<Round>,
+}
+
+impl Default for Tournament {
+    fn default() -> Self {
+        Self {
+            name: String::new(),
+            system: System::McMahon,
+            rounds: consts::TOURNAMENT_ROUNDS,
+            bar: consts::MCMAHON_BAR.to_string(),
+            handicap: false,
+            board_size: consts::DEFAULT_BOARD_SIZE,
+            komi: consts::DEFAULT_KOMI,
+            rules: Rules::Japanese,
+            players: Vec::new(),
+            played: Vec::new(),
+        }
+    }
+}
+
+/// The paperwork printed for an event, with the round it is for counted from 0.
+#[derive(Clone, Copy)]
+pub enum Sheet {
+    WallList,
+    Pairings(usize),
+    // One slip a board, for the players to fill in with the result and sign
+    ResultSlips(usize),
+}
+
+// Result slips on each page, cut apart along dashed lines
+const SLIPS_PER_PAGE: usize = 4;
+
+/// A board of a round played here, to take its result back to the tournament once over.
+#[derive(Clone)]
+pub struct Link {
+    pub path: PathBuf,
+    pub round: usize,
+    pub board: usize,
+    // The players paired there, in case the round has been paired again since
+    pub black: usize,
+    pub white: usize,
+}
+
+/// A line of the standings, in order of place.
+pub struct Standing {
+    pub player: usize,
+    pub place: usize,
+    pub score: f32,
+    // Sum of the opponents' scores, then of the scores of the opponents beaten
+    pub sos: f32,
+    pub sodos: f32,
+}
+
+impl Tournament {
+    pub fn load(path: &Path) -> Result<Self, String> {
+        let text = std::fs::read_to_string(path).map_err(|err| err.to_string())?;
+        serde_json::from_str(&text).map_err(|err| err.to_string())
+    }
+
+    pub fn save(&self, path: &Path) -> std::io::Result<()> {
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::write(path, serde_json::to_string_pretty(self)?)
+    }
+
+    // The weakest rank entered, where players start on no score in McMahon
+    fn floor(&self) -> i32 {
+        self.players
+            .iter()
+            .filter_map(|entrant| profiles::rank_step(&entrant.rank))
+            .min()
+            .unwrap_or(0)
+    }
+
+    // A player without a readable rank counts as the weakest
+    fn step(&self, player: usize) -> i32 {
+        profiles::rank_step(&self.players[player].rank).unwrap_or_else(|| self.floor())
+    }
+
+    fn start_score(&self, player: usize) -> f32 {
+        if self.system == System::Swiss {
+            return 0.0;
+        }
+        let bar = profiles::rank_step(&self.bar).unwrap_or(i32::MAX);
+        (self.step(player).min(bar) - self.floor()) as f32
+    }
+
+    // Each game of `player` with a result: the opponent and the points scored
+    fn games(&self, player: usize) -> impl Iterator<Item = (usize, f32)> + '_ {
+        self.played
+            .iter()
+            .flat_map(|round| &round.pairings)
+            .filter_map(move |pairing| {
+                let black_score = match pairing.outcome {
+                    Outcome::Pending => return None,
+                    Outcome::BlackWins => 1.0,
+                    Outcome::WhiteWins => 0.0,
+                    Outcome::Jigo => 0.5,
+                };
+                if pairing.black == player {
+                    Some((pairing.white, black_score))
+                } else if pairing.white == player {
+                    Some((pairing.black, 1.0 - black_score))
+                } else {
+                    None
+                }
+            })
+    }
+
+    pub fn score(&self, player: usize) -> f32 {
+        let byes = self
+            .played
+            .iter()
+            .filter(|round| round.bye == Some(player))
+            .count();
+        self.start_score(player) + byes as f32 + self.games(player).map(|(_, won)| won).sum::<f32>()
+    }
+
+    pub fn standings(&self) -> Vec<Standing> {
+        let mut standings: Vec<Standing> = (0..self.players.len())
+            .map(|player| Standing {
+                player,
+                place: 0,
+                score: self.score(player),
+                sos: self
+                    .games(player)
+                    .map(|(opponent, _)| self.score(opponent))
+                    .sum(),
+                sodos: self
+                    .games(player)
+                    .map(|(opponent, won)| won * self.score(opponent))
+                    .sum(),
+            })
+            .collect();
+        standings.sort_by(|a, b| {
+            (b.score, b.sos, b.sodos)
+                .partial_cmp(&(a.score, a.sos, a.sodos))
+                .unwrap_or(std::cmp::Ordering::Equal)
+                .then(self.step(b.player).cmp(&self.step(a.player)))
+        });
+        for index in 0..standings.len() {
+            let tied = index > 0 && {
+                let (before, this) = (&standings[index - 1], &standings[index]);
+                (before.score, before.sos, before.sodos) == (this.score, this.sos, this.sodos)
+            };
+            standings[index].place = if tied {
+                standings[index - 1].place
+            } else {
+                index + 1
+            };
+        }
+        standings
+    }
+
+    /// Whether every game of the last round has a result, so the next can be paired.
+    pub fn round_finished(&self) -> bool {
+        self.played.last().is_none_or(|round| {
+            round
+                .pairings
+                .iter()
+                .all(|pairing| pairing.outcome != Outcome::Pending)
+        })
+    }
+
+    fn have_met(&self, first: usize, second: usize) -> bool {
+        self.played
+            .iter()
+            .flat_map(|round| &round.pairings)
+            .any(|pairing| {
+                (pairing.black, pairing.white) == (first, second)
+                    || (pairing.black, pairing.white) == (second, first)
+            })
+    }
+
+    fn blacks(&self, player: usize) -> usize {
+        self.played
+            .iter()
+            .flat_map(|round| &round.pairings)
+            .filter(|pairing| pairing.black == player)
+            .count()
+    }
+
+    /// Pairs the next round among the players still in: by score, then rank, with no two
+    /// players meeting again while that can be helped, and a bye for the lowest player without
+    /// one when their number is odd.
+    pub fn pair_round(&mut self) -> Result<(), String> {
+        let mut order: Vec<usize> = (0..self.players.len())
+            .filter(|&player| !self.players[player].withdrawn)
+            .collect();
+        if order.len() < 2 {
+            return Err(tr("At least two players are needed"));
+        }
+        order.sort_by(|&a, &b| {
+            self.score(b)
+                .partial_cmp(&self.score(a))
+                .unwrap_or(std::cmp::Ordering::Equal)
+                .then(self.step(b).cmp(&self.step(a)))
+        });
+        let mut bye = None;
+        if order.len() % 2 == 1 {
+            let had_bye = |player: usize| self.played.iter().any(|round| round.bye == Some(player));
+            let index = order
+                .iter()
+                .rposition(|&player| !had_bye(player))
+                .unwrap_or(order.len() - 1);
+            bye = Some(order.remove(index));
+        }
+        let pairs = self
+            .match_up(&order, false)
+            .or_else(|| self.match_up(&order, true))
+            .unwrap_or_default();
+        let pairings = pairs
+            .into_iter()
+            .map(|(first, second)| self.pairing(first, second))
+            .collect();
+        self.played.push(Round { pairings, bye });
+        Ok(())
+    }
+
+    // Pairs the players in `order` from the top, each with the highest one left they can meet;
+    // tries the next one down when that leaves the rest unpairable
+    fn match_up(&self, order: &[usize], repeats: bool) -> Option<Vec<(usize, usize)>> {
+        let Some((&first, rest)) = order.split_first() else {
+            return Some(Vec::new());
+        };
+        for (index, &second) in rest.iter().enumerate() {
+            if !repeats && self.have_met(first, second) {
+                continue;
+            }
+            let mut left = rest.to_vec();
+            left.remove(index);
+            if let Some(mut pairs) = self.match_up(&left, repeats) {
+                pairs.insert(0, (first, second));
+                return Some(pairs);
+            }
+        }
+        None
+    }
+
+    // Colors and handicap for a game between two players
+    fn pairing(&self, first: usize, second: usize) -> Pairing {
+        let gap = self.step(first) - self.step(second);
+        let (black, white) = if self.handicap && gap != 0 {
+            // The weaker player takes Black
+            if gap > 0 {
+                (second, first)
+            } else {
+                (first, second)
+            }
+        } else {
+            match self.blacks(first).cmp(&self.blacks(second)) {
+                std::cmp::Ordering::Less => (first, second),
+                std::cmp::Ordering::Greater => (second, first),
+                std::cmp::Ordering::Equal if gap > 0 => (second, first),
+                std::cmp::Ordering::Equal => (first, second),
+            }
+        };
+        let stones = if self.handicap { gap.unsigned_abs() } else { 0 };
+        let (handicap, komi) = profiles::handicap(stones, self.komi);
+        Pairing {
+            black,
+            white,
+            handicap,
+            komi,
+            outcome: Outcome::Pending,
+            game: None,
+        }
+    }
+
+    /// Where board `board` of round `round` is played here, for the tournament kept at `path`.
+    pub fn link(&self, path: &Path, round: usize, board: usize) -> Link {
+        let pairing = &self.played[round].pairings[board];
+        Link {
+            path: path.to_path_buf(),
+            round,
+            board,
+            black: pairing.black,
+            white: pairing.white,
+        }
+    }
+
+    /// Sets the outcome of the linked game, with the record it was saved to.
+    pub fn record(
+        &mut self,
+        link: &Link,
+        outcome: Outcome,
+        game: Option<PathBuf>,
+    ) -> Result<(), String> {
+        let pairing = self
+            .played
+            .get_mut(link.round)
+            .and_then(|round| round.pairings.get_mut(link.board))
+            .filter(|pairing| (pairing.black, pairing.white) == (link.black, link.white))
+            .ok_or_else(|| tr("The board has been paired again since the game started"))?;
+        pairing.outcome = outcome;
+        pairing.game = game;
+        Ok(())
+    }
+
+    pub fn label(&self, player: usize) -> String {
+        let entrant = &self.players[player];
+        [entrant.name.trim(), entrant.rank.trim()]
+            .into_iter()
+            .filter(|part| !part.is_empty())
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+
+    fn facts(&self) -> String {
+        let mut facts = vec![
+            tr(self.system.name()),
+            trf("{} players", &[&self.players.len()]),
+            trf("{} of {} rounds", &[&self.played.len(), &self.rounds]),
+        ];
+        if self.system == System::McMahon {
+            facts.push(trf("bar {}", &[&self.bar.trim()]));
+        }
+        facts.join(" · ")
+    }
+
+    /// The standings with each player's games, as hung on the wall: a game is the opponent's
+    /// place, + for a win, - for a loss or = for jigo, and the color played with its handicap.
+    pub fn wall_list(&self, format: report::Format) -> String {
+        report::table(format, &self.name, &self.facts(), &self.wall_rows())
+    }
+
+    fn wall_rows(&self) -> Vec<Vec<String>> {
+        let standings = self.standings();
+        let place_of = |player: usize| {
+            standings
+                .iter()
+                .find(|standing| standing.player == player)
+                .map_or(0, |standing| standing.place)
+        };
+        let mut header = vec![tr("Place"), tr("Name"), tr("Rank"), tr("Club")];
+        header.extend((1..=self.played.len()).map(|round| trf("R{}", &[&round])));
+        header.extend([tr("Score"), tr("SOS"), tr("SODOS")]);
+        let mut rows = vec![header];
+        for standing in &standings {
+            let entrant = &self.players[standing.player];
+            let mut row = vec![
+                standing.place.to_string(),
+                entrant.name.clone(),
+                entrant.rank.clone(),
+                entrant.club.clone(),
+            ];
+            for round in &self.played {
+                let game = round.pairings.iter().find_map(|pairing| {
+                    if pairing.black == standing.player {
+                        Some((pairing.white, 'b', pairing))
+                    } else if pairing.white == standing.player {
+                        Some((pairing.black, 'w', pairing))
+                    } else {
+                        None
+                    }
+                });
+                row.push(match game {
+                    Some((opponent, color, pairing)) => {
+                        let won = match (pairing.outcome, color) {
+                            (Outcome::Pending, _) => '?',
+                            (Outcome::Jigo, _) => '=',
+                            (Outcome::BlackWins, 'b') | (Outcome::WhiteWins, 'w') => '+',
+                            _ => '-',
+                        };
+                        let handicap = match pairing.handicap {
+                            0 => String::new(),
+                            stones => stones.to_string(),
+                        };
+                        format!("{}{}/{}{}", place_of(opponent), won, color, handicap)
+                    }
+                    None if round.bye == Some(standing.player) => "0+".to_string(),
+                    None => "-".to_string(),
+                });
+            }
+            row.extend([
+                format_score(standing.score),
+                format_score(standing.sos),
+                format_score(standing.sodos),
+            ]);
+            rows.push(row);
+        }
+        rows
+    }
+
+    /// The boards of round `round`, counted from 0, to hang up before it starts.
+    pub fn pairing_list(&self, round: usize, format: report::Format) -> String {
+        report::table(
+            format,
+            &self.round_title(round),
+            &self.facts(),
+            &self.pairing_rows(round),
+        )
+    }
+
+    fn round_title(&self, round: usize) -> String {
+        trf("{}, round {}", &[&self.name, &(round + 1)])
+    }
+
+    fn pairing_rows(&self, round: usize) -> Vec<Vec<String>> {
+        let mut rows = vec![vec![
+            tr("Board"),
+            tr("Black"),
+            tr("White"),
+            tr("Handicap"),
+            tr("Result"),
+        ]];
+        let played = &self.played[round];
+        for (board, pairing) in played.pairings.iter().enumerate() {
+            rows.push(vec![
+                (board + 1).to_string(),
+                self.label(pairing.black),
+                self.label(pairing.white),
+                handicap(pairing),
+                match pairing.outcome {
+                    Outcome::Pending => String::new(),
+                    outcome => tr(outcome.name()),
+                },
+            ]);
+        }
+        if let Some(player) = played.bye {
+            rows.push(vec![
+                String::new(),
+                self.label(player),
+                tr("Bye"),
+                String::new(),
+                String::new(),
+            ]);
+        }
+        rows
+    }
+
+    /// `sheet` as a PDF file to print.
+    pub fn print(&self, sheet: Sheet) -> Vec<u8> {
+        match sheet {
+            Sheet::WallList => pdf::table(&self.name, &self.facts(), &self.wall_rows()),
+            Sheet::Pairings(round) => pdf::table(
+                &self.round_title(round),
+                &self.facts(),
+                &self.pairing_rows(round),
+            ),
+            Sheet::ResultSlips(round) => self.result_slips(round),
+        }
+    }
+
+    fn result_slips(&self, round: usize) -> Vec<u8> {
+        let pairings = &self.played[round].pairings;
+        let height = (pdf::PAGE_HEIGHT - 2.0 * pdf::MARGIN) / SLIPS_PER_PAGE as f32;
+        let right = pdf::PAGE_WIDTH - pdf::MARGIN;
+        let mut pages = Vec::new();
+        for slips in pairings.chunks(SLIPS_PER_PAGE) {
+            let mut page = Page::default();
+            for (index, pairing) in slips.iter().enumerate() {
+                let board = pages.len() * SLIPS_PER_PAGE + index;
+                let top = pdf::MARGIN + index as f32 * height;
+                let x = pdf::MARGIN;
+                if index > 0 {
+                    page.dashed(true);
+                    page.line((x, top), (right, top), 0.5);
+                    page.dashed(false);
+                }
+                page.text(
+                    x,
+                    top + 24.0,
+                    13.0,
+                    true,
+                    &trf(
+                        "{} · Round {} · Board {}",
+                        &[&self.name, &(round + 1), &(board + 1)],
+                    ),
+                );
+                page.text(
+                    x,
+                    top + 46.0,
+                    11.0,
+                    false,
+                    &trf("Black: {}", &[&self.label(pairing.black)]),
+                );
+                page.text(
+                    x,
+                    top + 62.0,
+                    11.0,
+                    false,
+                    &trf("White: {}", &[&self.label(pairing.white)]),
+                );
+                page.text(x + 320.0, top + 46.0, 11.0, false, &handicap(pairing));
+                // A box to tick for each outcome
+                let mut box_x = x;
+                for outcome in [Outcome::BlackWins, Outcome::WhiteWins, Outcome::Jigo] {
+                    page.rect(box_x, top + 80.0, 11.0, 11.0, 0.8);
+                    let name = tr(outcome.name());
+                    page.text(box_x + 16.0, top + 90.0, 11.0, false, &name);
+                    box_x += 16.0 + pdf::width(&name, 11.0, false) + 28.0;
+                }
+                let result = tr("Result, such as B+3.5 or W+R:");
+                page.text(x, top + 120.0, 11.0, false, &result);
+                let after = x + pdf::width(&result, 11.0, false) + 6.0;
+                page.line((after, top + 122.0), (after + 125.0, top + 122.0), 0.5);
+                let signed = top + height - 22.0;
+                for (label, at) in [(tr("Black signs"), x), (tr("White signs"), x + 260.0)] {
+                    page.text(at, signed, 11.0, false, &label);
+                    page.line((at + 75.0, signed + 2.0), (at + 235.0, signed + 2.0), 0.5);
+                }
+            }
+            pages.push(page);
+        }
+        if pages.is_empty() {
+            pages.push(Page::default());
+        }
+        pdf::document(&pages)
+    }
+}
+
+fn handicap(pairing: &Pairing) -> String {
+    match pairing.handicap {
+        0 => trf("komi {}", &[&pairing.komi]),
+        stones => trf("{} stones", &[&stones]),
+    }
+}
+
+fn format_score(score: f32) -> String {
+    if score.fract() == 0.0 {
+        format!("{}", score)
+    } else {
+        format!("{:.1}", score)
+    }
+}
+
+/// The tournament files in the tournaments folder, by name.
+pub fn list() -> Vec<PathBuf> {
+    let Ok(entries) = std::fs::read_dir(paths::tournaments_dir()) else {
+        return Vec::new();
+    };
+    let mut files: Vec<PathBuf> = entries
+        .flatten()
+        .map(|entry| entry.path())
+        .filter(|path| {
+            path.extension()
+                .is_some_and(|extension| extension == "json")
+        })
+        .collect();
+    files.sort();
+    files
+}
+
+/// Where a new tournament called `name` is kept.
+pub fn path_for(name: &str) -> PathBuf {
+    let file: String = name
+        .trim()
+        .chars()
+        .map(|c| {
+            if c.is_alphanumeric() || c == '-' {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect();
+    paths::tournaments_dir().join(format!("{}.json", file))
+}
@@ -0,0 +1,196 @@
+use crate::board::{Move, Player};
+use crate::consts;
+use crate::sgf;
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Guess {
+    Exact,
+    Nearby,
+    Wrong,
+}
+
+#[derive(Default)]
+pub struct Session {
+    pub exact: u32,
+    pub nearby: u32,
+    pub wrong: u32,
+}
+
+impl Session {
+    pub fn record(&mut self, guess: Guess) {
+        match guess {
+            Guess::Exact => self.exact += 1,
+            Guess::Nearby => self.nearby += 1,
+            Guess::Wrong => self.wrong += 1,
+        }
+    }
+
+    // Nearby guesses count as half a hit
+    pub fn accuracy(&self) -> f32 {
+        let total = self.exact + self.nearby + self.wrong;
+        if total == 0 {
+            return 0.0;
+        }
+        100.0 * (self.exact as f32 + 0.5 * self.nearby as f32) / total as f32
+    }
+}
+
+pub fn grade(guess: Option<(usize, usize)>, actual: Option<(usize, usize)>) -> Guess {
+    match (guess, actual) {
+        (None, None) => Guess::Exact,
+        (Some(guess), Some(actual)) if guess == actual => Guess::Exact,
+        (Some((row, col)), Some((actual_row, actual_col)))
+            if row.abs_diff(actual_row).max(col.abs_diff(actual_col))
+                <= consts::GUESS_NEARBY_DISTANCE =>
+        {
+            Guess::Nearby
+        }
+        _ => Guess::Wrong,
+    }
+}
+
+/// A tsumego: the position on the root of an SGF record, and its variations as the solution.
+/// The solver plays the first move, and the opponent answers from the variations.
+pub struct Problem {
+    root: sgf::Node,
+    board_size: usize,
+    pub solver: Player,
+    // The comment on the root, which usually says what to do
+    pub description: String,
+    // Whether the record marks its correct lines; if not, every line that ends with the
+    // solver's move and is not marked wrong is correct
+    marks_right: bool,
+    // The moves followed from the root so far, and how the attempt ended
+    line: Vec<Move>,
+    pub solved: Option<bool>,
+}
+
+// The moves right after `node`, looking through nodes without one
+fn moves_after(node: &sgf::Node, board_size: usize) -> Vec<(&sgf::Node, Move)> {
+    let mut moves = Vec::new();
+    for child in &node.children {
+        match node_move(child, board_size) {
+            Some(played) => moves.push((child, played)),
+            None => moves.extend(moves_after(child, board_size)),
+        }
+    }
+    moves
+}
+
+fn node_move(node: &sgf::Node, board_size: usize) -> Option<Move> {
+    [("B", Player::Black), ("W", Player::White)]
+        .into_iter()
+        .find_map(|(ident, player)| Some((player, sgf::parse_point(node.get(ident)?, board_size))))
+}
+
+fn comment_says(node: &sgf::Node, words: &[&str]) -> bool {
+    let comment = node.get("C").unwrap_or_default().to_lowercase();
+    words.iter().any(|word| comment.contains(word))
+}
+
+// A bad move, or a comment saying the line fails; checked first, as "incorrect" has "correct"
+fn marked_wrong(node: &sgf::Node) -> bool {
+    node.get("BM").is_some() || comment_says(node, &["wrong", "incorrect", "fail"])
+}
+
+fn marked_right(node: &sgf::Node) -> bool {
+    node.get("TE").is_some() || comment_says(node, &["right", "correct"])
+}
+
+fn any_marked_right(node: &sgf::Node) -> bool {
+    (marked_right(node) && !marked_wrong(node)) || node.children.iter().any(any_marked_right)
+}
+
+impl Problem {
+    /// The problem on `root`, or none if it has no variations to solve it with.
+    pub fn new(root: sgf::Node, board_size: usize) -> Option<Problem> {
+        let (_, (solver, _)) = *moves_after(&root, board_size).first()?;
+        Some(Problem {
+            description: root.get("C").unwrap_or_default().trim().to_string(),
+            marks_right: any_marked_right(&root),
+            root,
+            board_size,
+            solver,
+            line: Vec::new(),
+            solved: None,
+        })
+    }
+
+    // The node the moves so far lead to
+    fn node(&self) -> &sgf::Node {
+        let mut node = &self.root;
+        for &played in &self.line {
+            if let Some((child, _)) = moves_after(node, self.board_size)
+                .into_iter()
+                .find(|&(_, other)| other == played)
+            {
+                node = child;
+            }
+        }
+        node
+    }
+
+    // Whether reaching the end of a line at `node` solves the problem
+    fn ends_right(&self, node: &sgf::Node, solver_moved: bool) -> bool {
+        if marked_wrong(node) {
+            false
+        } else if marked_right(node) {
+            true
+        } else {
+            solver_moved && !self.marks_right
+        }
+    }
+
+    /// Takes the solver's move, and gives the opponent's answer from the variations, if any.
+    /// `solved` is set once the attempt is over.
+    pub fn attempt(&mut self, point: Option<(usize, usize)>) -> Option<Move> {
+        let played = (self.solver, point);
+        let Some((node, _)) = moves_after(self.node(), self.board_size)
+            .into_iter()
+            .find(|&(_, other)| other == played)
+        else {
+            self.solved = Some(false);
+            return None;
+        };
+        let replies = moves_after(node, self.board_size);
+        let Some(&(reply_node, reply)) = replies.first() else {
+            self.solved = Some(self.ends_right(node, true));
+            self.line.push(played);
+            return None;
+        };
+        if marked_wrong(node) {
+            self.solved = Some(false);
+        } else if moves_after(reply_node, self.board_size).is_empty() {
+            self.solved = Some(self.ends_right(reply_node, false));
+        }
+        self.line.push(played);
+        self.line.push(reply);
+        Some(reply)
+    }
+
+    /// A line from the start that solves the problem.
+    pub fn solution(&self) -> Vec<Move> {
+        self.correct_line(&self.root, false).unwrap_or_default()
+    }
+
+    fn correct_line(&self, node: &sgf::Node, solver_moved: bool) -> Option<Vec<Move>> {
+        let next = moves_after(node, self.board_size);
+        if next.is_empty() {
+            return self.ends_right(node, solver_moved).then(Vec::new);
+        }
+        if marked_wrong(node) {
+            return None;
+        }
+        next.into_iter().find_map(|(child, played)| {
+            let mut line = self.correct_line(child, played.0 == self.solver)?;
+            line.insert(0, played);
+            Some(line)
+        })
+    }
+
+    /// Starts the attempt again from the problem's position.
+    pub fn restart(&mut self) {
+        self.line.clear();
+        self.solved = None;
+    }
+}
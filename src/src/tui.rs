@@ -0,0 +1,228 @@
+use std::collections::HashSet;
+use std::io;
+
+use ratatui::crossterm::event::{self, Event, KeyCode, KeyEventKind};
+use ratatui::layout::{Constraint, Layout};
+use ratatui::style::{Color, Style};
+use ratatui::text::{Line, Span};
+use ratatui::widgets::{Block, Paragraph};
+use ratatui::{DefaultTerminal, Frame};
+
+use crate::board::{Board, Move, Player, Stone};
+use crate::game_info::Rules;
+use crate::notation::{self, CoordinateStyle};
+use crate::scoring;
+
+/// A game played in the terminal by typing coordinates, with the same rules as the window.
+pub struct Game {
+    start: Board,
+    start_player: Player,
+    start_captures: (u32, u32),
+    rules: Rules,
+    komi: f32,
+    board: Board,
+    to_play: Player,
+    moves: Vec<Move>,
+    // Black stones and white stones captured so far
+    captures: (u32, u32),
+    input: String,
+    message: String,
+    game_over: bool,
+}
+
+impl Game {
+    pub fn new(
+        board: Board,
+        to_play: Player,
+        captures: (u32, u32),
+        rules: Rules,
+        komi: f32,
+    ) -> Self {
+        Self {
+            start: board.clone(),
+            start_player: to_play,
+            start_captures: captures,
+            rules,
+            komi,
+            board,
+            to_play,
+            moves: Vec::new(),
+            captures,
+            input: String::new(),
+            message: "Type a move such as D4, \"pass\", \"undo\" or \"quit\"".to_string(),
+            game_over: false,
+        }
+    }
+
+    fn play(&mut self, played: Move) -> Result<(), String> {
+        let (player, point) = played;
+        match point {
+            Some((row, col)) => {
+                self.board
+                    .check_move(row, col, player)
+                    .map_err(|err| err.to_string())?;
+                let captured = self.board.play(row, col, player);
+                match player {
+                    Player::Black => self.captures.1 += captured,
+                    Player::White => self.captures.0 += captured,
+                }
+            }
+            None => self.board.pass(),
+        }
+        self.moves.push(played);
+        self.to_play = player.other();
+        Ok(())
+    }
+
+    // Replays everything but the last move from the starting position
+    fn undo(&mut self) {
+        let mut moves = std::mem::take(&mut self.moves);
+        moves.pop();
+        self.board = self.start.clone();
+        self.to_play = self.start_player;
+        self.captures = self.start_captures;
+        self.game_over = false;
+        for played in moves {
+            let _ = self.play(played);
+        }
+    }
+
+    fn score(&self) -> scoring::Score {
+        scoring::score(
+            &self.board,
+            &HashSet::new(),
+            self.rules,
+            self.komi,
+            self.captures.0,
+            self.captures.1,
+        )
+    }
+
+    // Handles a line of input and returns whether to quit
+    fn submit(&mut self) -> bool {
+        let input = std::mem::take(&mut self.input);
+        let command = input.trim().to_lowercase();
+        match command.as_str() {
+            "quit" | "q" => return true,
+            "undo" | "u" => {
+                self.undo();
+                self.message = "Took back the last move".to_string();
+            }
+            _ if self.game_over => {
+                self.message = "The game is over; undo to continue".to_string();
+            }
+            _ => match notation::parse_point(&command, self.board.size()) {
+                Some(point) => {
+                    let player = self.to_play;
+                    if let Err(err) = self.play((player, point)) {
+                        self.message = format!("Illegal move: {}", err);
+                        return false;
+                    }
+                    self.message = match point {
+                        Some(point) => format!(
+                            "{:?} played {}",
+                            player,
+                            notation::format_point(point, self.board.size())
+                        ),
+                        None => format!("{:?} passed", player),
+                    };
+                    let mut passes = self.moves.iter().rev().take(2);
+                    if self.moves.len() >= 2 && passes.all(|(_, point)| point.is_none()) {
+                        self.game_over = true;
+                        self.message = format!(
+                            "Game over: {}, counting every stone as alive",
+                            self.score().result()
+                        );
+                    }
+                }
+                None => self.message = format!("Not a move: {}", input.trim()),
+            },
+        }
+        false
+    }
+
+    fn draw(&self, frame: &mut Frame) {
+        let size = self.board.size();
+        let [board_area, side_area] =
+            Layout::horizontal([Constraint::Length(size as u16 * 2 + 6), Constraint::Min(20)])
+                .areas(frame.area());
+        let [info_area, input_area] =
+            Layout::vertical([Constraint::Min(5), Constraint::Length(3)]).areas(side_area);
+
+        let style = CoordinateStyle::Western;
+        let last = self.moves.last().and_then(|&(_, point)| point);
+        let header: String = (0..size)
+            .map(|col| format!("{} ", style.column_label(col, size)))
+            .collect();
+        let mut lines = vec![Line::from(format!("   {}", header))];
+        for row in 0..size {
+            let mut spans = vec![Span::raw(format!("{:>2} ", style.row_label(row, size)))];
+            for col in 0..size {
+                let symbol = match self.board[row][col] {
+                    Stone::Black => "●",
+                    Stone::White => "○",
+                    Stone::Empty => "·",
+                };
+                let highlight = if last == Some((row, col)) {
+                    Style::new().fg(Color::Red)
+                } else {
+                    Style::new()
+                };
+                spans.push(Span::styled(symbol, highlight));
+                spans.push(Span::raw(" "));
+            }
+            lines.push(Line::from(spans));
+        }
+        frame.render_widget(
+            Paragraph::new(lines).block(Block::bordered().title(" Go ")),
+            board_area,
+        );
+
+        let info = vec![
+            Line::from(if self.game_over {
+                "Game over".to_string()
+            } else {
+                format!("{:?} to play", self.to_play)
+            }),
+            Line::from(format!("Black stones captured: {}", self.captures.0)),
+            Line::from(format!("White stones captured: {}", self.captures.1)),
+            Line::from(format!("Komi: {}", self.komi)),
+            Line::from(""),
+            Line::from(self.message.as_str()),
+        ];
+        frame.render_widget(Paragraph::new(info).block(Block::bordered()), info_area);
+        frame.render_widget(
+            Paragraph::new(format!("> {}", self.input)).block(Block::bordered().title(" Move ")),
+            input_area,
+        );
+    }
+}
+
+/// Runs `game` in the terminal until the player quits.
+pub fn run(mut game: Game) -> io::Result<()> {
+    let mut terminal = ratatui::init();
+    let result = event_loop(&mut terminal, &mut game);
+    ratatui::restore();
+    result
+}
+
+fn event_loop(terminal: &mut DefaultTerminal, game: &mut Game) -> io::Result<()> {
+    loop {
+        terminal.draw(|frame| game.draw(frame))?;
+        let Event::Key(key) = event::read()? else {
+            continue;
+        };
+        if key.kind != KeyEventKind::Press {
+            continue;
+        }
+        match key.code {
+            KeyCode::Esc => return Ok(()),
+            KeyCode::Enter if game.submit() => return Ok(()),
+            KeyCode::Backspace => {
+                game.input.pop();
+            }
+            KeyCode::Char(c) => game.input.push(c),
+            _ => {}
+        }
+    }
+}
@@ -0,0 +1,51 @@
+//! The lessons that teach the rules: a short text and a position to play the right move on,
+//! checked like any other problem.
+
+pub struct Lesson {
+    pub title: &'static str,
+    pub text: &'static str,
+    pub sgf: &'static str,
+}
+
+pub const LESSONS: [Lesson; 8] = [
+    Lesson {
+        title: "Liberties",
+        text: "Stones are played on the points where the lines cross. The empty points next to a stone along the lines are its liberties. A stone without liberties is captured and taken off the board. The white stone has one liberty left: play there to capture it.",
+        sgf: "(;SZ[9]AB[de][fe][ed]AW[ee];B[ef])",
+    },
+    Lesson {
+        title: "Capturing groups",
+        text: "Stones next to each other along the lines form a group and share their liberties. A group is captured when its last liberty is taken. Capture the two white stones.",
+        sgf: "(;SZ[9]AB[de][ge][ed][fd][ef]AW[ee][fe];B[ff])",
+    },
+    Lesson {
+        title: "Escaping",
+        text: "A stone with one liberty left is in atari. Playing next to it joins the new stone to it, and the group gets the new stone's liberties. Save the black stone.",
+        sgf: "(;SZ[9]AB[ee]AW[de][fe][ed];B[ef])",
+    },
+    Lesson {
+        title: "Edges and corners",
+        text: "Stones on the edge have fewer liberties, and in the corner fewest of all. Capture the two white stones in the corner.",
+        sgf: "(;SZ[9]AB[ca][ab]AW[aa][ba];B[bb])",
+    },
+    Lesson {
+        title: "Ko",
+        text: "Capturing this white stone leaves the black stone with one liberty, where White could capture it back, and so on forever. The ko rule stops this: White may not take back at once, but has to play elsewhere first. Take the ko.",
+        sgf: "(;SZ[9]AB[ed][de][ef]AW[ee][fd][ff][ge];B[fe])",
+    },
+    Lesson {
+        title: "Two eyes",
+        text: "A move with no liberties is not allowed, unless it captures. So a group around two separate empty points, its eyes, can never be captured: it lives. Give the black group two eyes.",
+        sgf: "(;SZ[9]AB[bi][fi][bh][ch][dh][eh][fh]AW[ag][bg][cg][dg][eg][fg][gg][ah][ai][gh][gi];B[di])",
+    },
+    Lesson {
+        title: "Killing",
+        text: "A group with room for only one eye dies: it is captured in the end. Now White plays first. Take the point Black needs for two eyes.",
+        sgf: "(;SZ[9]AB[bi][fi][bh][ch][dh][eh][fh]AW[ag][bg][cg][dg][eg][fg][gg][ah][ai][gh][gi]PL[W];W[di])",
+    },
+    Lesson {
+        title: "Counting",
+        text: "The game ends when both players pass. Each player then counts the empty points they surround, their territory, and adds the stones they captured; White also gets komi for playing second. Here both have 27 points. Play the last neutral point between the walls, and the game is ready to pass and count.",
+        sgf: "(;SZ[9]AB[da][db][dc][dd][de][df][dg][dh][di][ea][eb][ec][ed]AW[fa][fb][fc][fd][fe][ff][fg][fh][fi][ef][eg][eh][ei];B[ee])",
+    },
+];
@@ -0,0 +1,57 @@
+use crate::consts;
+
+/// The points marked on boards of `size`, none for the sizes without them.
+pub fn star_points(size: usize) -> &'static [(usize, usize)] {
+    match size {
+        9 => consts::STAR_POINTS_9X9,
+        13 => consts::STAR_POINTS_13X13,
+        19 => consts::STAR_POINTS_19X19,
+        _ => &[],
+    }
+}
+
+/// How the board is turned on screen. Only drawing and input go through it; the game keeps its
+/// own coordinates.
+#[derive(Clone, Copy, PartialEq, Debug, Default)]
+pub struct BoardView {
+    // Quarter turns clockwise, applied after mirroring
+    pub turns: u8,
+    // Left and right swapped
+    pub mirrored: bool,
+}
+
+impl BoardView {
+    pub fn rotate(&mut self) {
+        self.turns = (self.turns + 1) % 4;
+    }
+
+    /// Whether screen columns run along the board's rows.
+    pub fn is_sideways(self) -> bool {
+        self.turns % 2 == 1
+    }
+
+    /// The screen row and column where board `point` is drawn.
+    pub fn to_screen(self, (row, col): (usize, usize), size: usize) -> (usize, usize) {
+        let last = size - 1;
+        let col = if self.mirrored { last - col } else { col };
+        match self.turns % 4 {
+            0 => (row, col),
+            1 => (col, last - row),
+            2 => (last - row, last - col),
+            _ => (last - col, row),
+        }
+    }
+
+    /// The board point drawn at screen `(row, col)`.
+    pub fn to_board(self, (row, col): (usize, usize), size: usize) -> (usize, usize) {
+        let last = size - 1;
+        let (row, col) = match self.turns % 4 {
+            0 => (row, col),
+            1 => (last - col, row),
+            2 => (last - row, last - col),
+            _ => (col, last - row),
+        };
+        let col = if self.mirrored { last - col } else { col };
+        (row, col)
+    }
+}
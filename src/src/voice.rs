@@ -0,0 +1,279 @@
+//! Moves and a few commands given by voice, for a teacher at a demonstration board across the
+//! room or a player who cannot easily use a mouse. The listening is left to an outside speech
+//! recognizer that prints each phrase it hears as a line, such as "black Q sixteen" or "undo".
+
+use std::io::{BufRead, BufReader};
+use std::process::{Child, Command, Stdio};
+use std::sync::mpsc::{self, Receiver, TryRecvError};
+
+use eframe::egui;
+
+use crate::board::Player;
+use crate::i18n::tr;
+use crate::notation;
+
+/// What a phrase asks for.
+#[derive(Debug, PartialEq)]
+pub enum Said {
+    // A point such as "Q16", or "pass", with the color when one was named
+    Move(Option<Player>, String),
+    Undo,
+    Back,
+    Forward,
+}
+
+// Letters as they are spelled out or as recognizers tend to write them
+const LETTER_WORDS: &[(&str, char)] = &[
+    ("alpha", 'A'),
+    ("alfa", 'A'),
+    ("bravo", 'B'),
+    ("bee", 'B'),
+    ("be", 'B'),
+    ("charlie", 'C'),
+    ("see", 'C'),
+    ("sea", 'C'),
+    ("delta", 'D'),
+    ("dee", 'D'),
+    ("echo", 'E'),
+    ("foxtrot", 'F'),
+    ("golf", 'G'),
+    ("gee", 'G'),
+    ("hotel", 'H'),
+    ("juliet", 'J'),
+    ("juliett", 'J'),
+    ("jay", 'J'),
+    ("kilo", 'K'),
+    ("kay", 'K'),
+    ("lima", 'L'),
+    ("mike", 'M'),
+    ("november", 'N'),
+    ("oscar", 'O'),
+    ("papa", 'P'),
+    ("pee", 'P'),
+    ("quebec", 'Q'),
+    ("queue", 'Q'),
+    ("cue", 'Q'),
+    ("romeo", 'R'),
+    ("are", 'R'),
+    ("sierra", 'S'),
+    ("tango", 'T'),
+    ("tea", 'T'),
+    ("tee", 'T'),
+    ("uniform", 'U'),
+    ("you", 'U'),
+    ("victor", 'V'),
+    ("whiskey", 'W'),
+    ("xray", 'X'),
+    ("yankee", 'Y'),
+    ("zulu", 'Z'),
+];
+
+const UNITS: &[(&str, usize)] = &[
+    ("one", 1),
+    ("won", 1),
+    ("two", 2),
+    ("to", 2),
+    ("too", 2),
+    ("three", 3),
+    ("four", 4),
+    ("for", 4),
+    ("five", 5),
+    ("six", 6),
+    ("seven", 7),
+    ("eight", 8),
+    ("ate", 8),
+    ("nine", 9),
+    ("ten", 10),
+    ("eleven", 11),
+    ("twelve", 12),
+    ("thirteen", 13),
+    ("fourteen", 14),
+    ("fifteen", 15),
+    ("sixteen", 16),
+    ("seventeen", 17),
+    ("eighteen", 18),
+    ("nineteen", 19),
+];
+
+fn letter(word: &str) -> Option<char> {
+    let mut chars = word.chars();
+    match (chars.next(), chars.next()) {
+        (Some(c), None) if c.is_ascii_alphabetic() => Some(c.to_ascii_uppercase()),
+        _ => LETTER_WORDS
+            .iter()
+            .find(|(spelled, _)| *spelled == word)
+            .map(|&(_, c)| c),
+    }
+}
+
+// The number the words at the start of `words` make, with how many words it took
+fn number(words: &[String]) -> Option<(usize, usize)> {
+    let first = words.first()?;
+    if let Ok(number) = first.parse() {
+        return Some((number, 1));
+    }
+    if first == "twenty" {
+        let unit = words
+            .get(1)
+            .and_then(|word| UNITS.iter().find(|(spelled, _)| spelled == word))
+            .filter(|&&(_, unit)| unit < 10);
+        return Some(match unit {
+            Some(&(_, unit)) => (20 + unit, 2),
+            None => (20, 1),
+        });
+    }
+    UNITS
+        .iter()
+        .find(|(spelled, _)| spelled == first)
+        .map(|&(_, number)| (number, 1))
+}
+
+/// Reads a phrase heard on a board of `board_size`, or `None` when it asks for nothing known.
+pub fn parse(phrase: &str, board_size: usize) -> Option<Said> {
+    // "Q16" comes apart into "q" and "16", like "Q sixteen"
+    let mut words = Vec::new();
+    for word in phrase
+        .to_lowercase()
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|word| !word.is_empty())
+    {
+        match word.find(|c: char| c.is_ascii_digit()) {
+            Some(digits) if digits > 0 && word[digits..].chars().all(|c| c.is_ascii_digit()) => {
+                words.push(word[..digits].to_string());
+                words.push(word[digits..].to_string());
+            }
+            _ => words.push(word.to_string()),
+        }
+    }
+    let has = |wanted: &[&str]| words.iter().any(|word| wanted.contains(&word.as_str()));
+    if has(&["undo"]) {
+        return Some(Said::Undo);
+    }
+    if has(&["back", "previous"]) {
+        return Some(Said::Back);
+    }
+    if has(&["forward", "next"]) {
+        return Some(Said::Forward);
+    }
+    let player = words.iter().find_map(|word| match word.as_str() {
+        "black" => Some(Player::Black),
+        "white" => Some(Player::White),
+        _ => None,
+    });
+    if has(&["pass"]) {
+        return Some(Said::Move(player, "pass".to_string()));
+    }
+    // The first letter followed by a number that makes a point on the board
+    (0..words.len()).find_map(|index| {
+        let column = letter(&words[index])?;
+        let (row, _) = number(&words[index + 1..])?;
+        let vertex = format!("{}{}", column, row);
+        notation::parse_point(&vertex, board_size)?;
+        Some(Said::Move(player, vertex))
+    })
+}
+
+/// The speech recognizer, started while voice moves are on and given a program to run.
+#[derive(Default)]
+pub struct Listener {
+    // The command line it runs, the program and the lines it has printed
+    running: Option<(String, Child, Receiver<String>)>,
+    // A command line that failed, so it is not tried again every frame
+    failed: Option<String>,
+}
+
+impl Listener {
+    /// What was heard since the last call. Starts the program, or another after `command_line`
+    /// changed, and stops it for an empty one.
+    pub fn heard(
+        &mut self,
+        command_line: &str,
+        ctx: &egui::Context,
+    ) -> Result<Vec<String>, String> {
+        let command_line = command_line.trim();
+        if self
+            .running
+            .as_ref()
+            .is_some_and(|(running, _, _)| running != command_line)
+        {
+            self.stop();
+        }
+        if self
+            .failed
+            .as_deref()
+            .is_some_and(|failed| failed != command_line)
+        {
+            self.failed = None;
+        }
+        if command_line.is_empty() || self.failed.is_some() {
+            return Ok(Vec::new());
+        }
+        if self.running.is_none() {
+            match start(command_line, ctx) {
+                Ok((child, lines)) => {
+                    self.running = Some((command_line.to_string(), child, lines));
+                }
+                Err(err) => {
+                    self.failed = Some(command_line.to_string());
+                    return Err(err.to_string());
+                }
+            }
+        }
+        let Some((_, _, lines)) = &self.running else {
+            return Ok(Vec::new());
+        };
+        let mut heard = Vec::new();
+        loop {
+            match lines.try_recv() {
+                Ok(line) => heard.push(line),
+                Err(TryRecvError::Empty) => return Ok(heard),
+                // What it printed last still counts
+                Err(TryRecvError::Disconnected) if !heard.is_empty() => return Ok(heard),
+                Err(TryRecvError::Disconnected) => {
+                    self.stop();
+                    self.failed = Some(command_line.to_string());
+                    return Err(tr("the speech recognizer stopped"));
+                }
+            }
+        }
+    }
+
+    fn stop(&mut self) {
+        if let Some((_, mut child, _)) = self.running.take() {
+            let _ = child.kill();
+            let _ = child.wait();
+        }
+    }
+}
+
+impl Drop for Listener {
+    fn drop(&mut self) {
+        self.stop();
+    }
+}
+
+// Runs `command_line` with a thread passing on each line it prints
+fn start(command_line: &str, ctx: &egui::Context) -> std::io::Result<(Child, Receiver<String>)> {
+    let mut parts = command_line.split_whitespace();
+    let program = parts.next().unwrap_or_default();
+    let mut child = Command::new(program)
+        .args(parts)
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .spawn()?;
+    let stdout = child.stdout.take().expect("stdout is piped");
+    let (sender, receiver) = mpsc::channel();
+    let ctx = ctx.clone();
+    std::thread::spawn(move || {
+        for line in BufReader::new(stdout).lines().map_while(Result::ok) {
+            if !line.trim().is_empty() && sender.send(line).is_err() {
+                break;
+            }
+            ctx.request_repaint();
+        }
+        // The sender is gone with the thread, which tells the listener the program stopped
+        ctx.request_repaint();
+    });
+    Ok((child, receiver))
+}
@@ -0,0 +1,312 @@
+//! A wall of boards to project at a club night or a tournament: the games in the tabs here and
+//! those other computers broadcast, tiled on one screen and kept up to date as they are played.
+
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+
+use eframe::egui;
+use tungstenite::Message;
+use tungstenite::stream::MaybeTlsStream;
+
+use crate::api;
+use crate::clock;
+use crate::consts;
+use crate::i18n::{tr, trf};
+use crate::notation;
+use crate::theme::BoardTheme;
+use crate::view;
+
+// What the thread following a broadcast shares with the wall
+#[derive(Default)]
+struct Latest {
+    // The game as last sent, and when
+    state: Option<(api::State, web_time::Instant)>,
+    // Why the game may be out of date, such as a dropped connection
+    status: String,
+}
+
+/// The game another computer broadcasts, followed by a thread that connects again whenever the
+/// connection drops.
+pub struct Feed {
+    pub address: String,
+    latest: Arc<Mutex<Latest>>,
+    stop: Arc<AtomicBool>,
+}
+
+impl Feed {
+    pub fn start(address: &str, ctx: &egui::Context) -> Self {
+        let url = socket_url(address);
+        let latest = Arc::new(Mutex::new(Latest {
+            state: None,
+            status: tr("Connecting…"),
+        }));
+        let stop = Arc::new(AtomicBool::new(false));
+        let (thread_latest, thread_stop, ctx) = (latest.clone(), stop.clone(), ctx.clone());
+        std::thread::spawn(move || {
+            while !thread_stop.load(Ordering::Relaxed) {
+                let status = follow(&url, &thread_latest, &thread_stop, &ctx);
+                if let Ok(mut latest) = thread_latest.lock() {
+                    latest.status = trf("{}, trying again", &[&status]);
+                }
+                ctx.request_repaint();
+                std::thread::sleep(consts::RECONNECT_RETRY);
+            }
+        });
+        Feed {
+            address: address.trim().to_string(),
+            latest,
+            stop,
+        }
+    }
+
+    pub fn tile(&self) -> Tile {
+        let Ok(latest) = self.latest.lock() else {
+            return Tile::default();
+        };
+        let (state, elapsed) = match &latest.state {
+            Some((state, at)) => (Some(state.clone()), at.elapsed().as_secs_f32()),
+            None => (None, 0.0),
+        };
+        let status = match latest.status.as_str() {
+            "" => String::new(),
+            status => format!("{}: {}", self.address, status),
+        };
+        Tile {
+            state,
+            elapsed,
+            status,
+        }
+    }
+}
+
+impl Drop for Feed {
+    fn drop(&mut self) {
+        // The thread sees this by its next read timeout
+        self.stop.store(true, Ordering::Relaxed);
+    }
+}
+
+// The websocket of the broadcast at `address`, given as the page's address, such as
+// http://192.168.1.20:7652/, or as a host with or without its port
+fn socket_url(address: &str) -> String {
+    let address = address.trim();
+    let address = ["http://", "ws://"]
+        .iter()
+        .find_map(|scheme| address.strip_prefix(scheme))
+        .unwrap_or(address);
+    let host = address.split('/').next().unwrap_or(address);
+    if host.contains(':') {
+        format!("ws://{}/ws", host)
+    } else {
+        format!("ws://{}:{}/ws", host, consts::BROADCAST_PORT)
+    }
+}
+
+// Keeps `latest` up to date until the connection ends or the feed stops; returns why it ended
+fn follow(url: &str, latest: &Mutex<Latest>, stop: &AtomicBool, ctx: &egui::Context) -> String {
+    let mut socket = match tungstenite::connect(url) {
+        Ok((socket, _)) => socket,
+        Err(err) => return trf("Could not connect: {}", &[&err]),
+    };
+    // Reads give up after a moment so the thread notices when the feed is dropped
+    if let MaybeTlsStream::Plain(stream) = socket.get_ref() {
+        let _ = stream.set_read_timeout(Some(consts::WALL_POLL));
+    }
+    while !stop.load(Ordering::Relaxed) {
+        match socket.read() {
+            Ok(Message::Text(text)) => {
+                let state: api::State = match serde_json::from_str(&text) {
+                    Ok(state) => state,
+                    Err(err) => return trf("Not a broadcast of a game: {}", &[&err]),
+                };
+                if let Ok(mut latest) = latest.lock() {
+                    latest.state = Some((state, web_time::Instant::now()));
+                    latest.status.clear();
+                }
+                ctx.request_repaint();
+            }
+            Ok(Message::Close(_)) => break,
+            Ok(_) => {}
+            Err(tungstenite::Error::Io(err))
+                if matches!(
+                    err.kind(),
+                    std::io::ErrorKind::WouldBlock | std::io::ErrorKind::TimedOut
+                ) => {}
+            Err(err) => return trf("Connection lost: {}", &[&err]),
+        }
+    }
+    tr("Connection closed")
+}
+
+/// What the wall shows in a place of its own.
+#[derive(Default)]
+pub struct Tile {
+    pub state: Option<api::State>,
+    // Seconds since the state was taken, for the clock of the player to move
+    pub elapsed: f32,
+    // Shown in place of the move count when not empty
+    pub status: String,
+}
+
+/// The wall while it is up: the broadcasts it follows, besides the tabs.
+pub struct Wall {
+    pub feeds: Vec<Feed>,
+    // Whether the games in the tabs are on it
+    pub tabs: bool,
+    // A broadcast address being typed
+    pub address: String,
+}
+
+impl Wall {
+    pub fn new(addresses: &[String], ctx: &egui::Context) -> Self {
+        Wall {
+            feeds: addresses
+                .iter()
+                .map(|address| Feed::start(address, ctx))
+                .collect(),
+            tabs: true,
+            address: String::new(),
+        }
+    }
+}
+
+/// Tiles the boards over `ui` in near enough a square. Returns the tile clicked.
+pub fn show(ui: &mut egui::Ui, tiles: &[Tile], theme: BoardTheme) -> Option<usize> {
+    if tiles.is_empty() {
+        ui.centered_and_justified(|ui| {
+            ui.label(tr(
+                "No games to show yet. Start games in the tabs, or add the address of a broadcast.",
+            ))
+        });
+        return None;
+    }
+    let columns = (tiles.len() as f32).sqrt().ceil() as usize;
+    let rows = tiles.len().div_ceil(columns);
+    let area = ui.available_rect_before_wrap();
+    let cell = egui::vec2(area.width() / columns as f32, area.height() / rows as f32);
+    let mut clicked = None;
+    for (index, tile) in tiles.iter().enumerate() {
+        let min = area.min + egui::vec2((index % columns) as f32, (index / columns) as f32) * cell;
+        let rect = egui::Rect::from_min_size(min, cell).shrink(8.0);
+        let response = ui.interact(
+            rect,
+            ui.id().with(("wall_tile", index)),
+            egui::Sense::click(),
+        );
+        if response.clicked() {
+            clicked = Some(index);
+        }
+        paint(ui, rect, tile, theme);
+    }
+    ui.allocate_rect(area, egui::Sense::hover());
+    clicked
+}
+
+fn paint(ui: &egui::Ui, rect: egui::Rect, tile: &Tile, theme: BoardTheme) {
+    let painter = ui.painter_at(rect);
+    let text_color = ui.visuals().text_color();
+    let font = egui::FontId::proportional((rect.height() * 0.04).clamp(12.0, 28.0));
+    let line = font.size * 1.4;
+    let Some(state) = &tile.state else {
+        painter.text(
+            rect.center(),
+            egui::Align2::CENTER_CENTER,
+            &tile.status,
+            font,
+            text_color,
+        );
+        return;
+    };
+    // The players above the board, Black on the left
+    for (side, color, align, x) in [
+        (&state.black, "B", egui::Align2::LEFT_TOP, rect.left()),
+        (&state.white, "W", egui::Align2::RIGHT_TOP, rect.right()),
+    ] {
+        let to_move = state.to_move == color && !state.game_over;
+        let name = format!("{} {}", side.name, side.rank).trim().to_string();
+        let name = if name.is_empty() {
+            tr(if color == "B" { "Black" } else { "White" })
+        } else {
+            name
+        };
+        let left = side
+            .time_left
+            .map(|left| if to_move { left - tile.elapsed } else { left });
+        let mut text = trf("{} ({} captured)", &[&name, &side.captured]);
+        if let Some(left) = left {
+            text = format!("{}  {}", text, clock::format(left.max(0.0)));
+        }
+        let color = if left.is_some_and(|left| left < consts::LOW_TIME) {
+            egui::Color32::RED
+        } else if to_move {
+            ui.visuals().strong_text_color()
+        } else {
+            text_color
+        };
+        painter.text(egui::pos2(x, rect.top()), align, text, font.clone(), color);
+    }
+    let footer = if !tile.status.is_empty() {
+        tile.status.clone()
+    } else if let Some(result) = &state.result {
+        trf("Result: {}", &[result])
+    } else {
+        trf("Move {}", &[&state.moves.len()])
+    };
+    painter.text(
+        egui::pos2(rect.center().x, rect.bottom()),
+        egui::Align2::CENTER_BOTTOM,
+        footer,
+        font.clone(),
+        text_color,
+    );
+    let room = egui::Rect::from_min_max(
+        rect.min + egui::vec2(0.0, line),
+        rect.max - egui::vec2(0.0, line),
+    );
+    let side = room.width().min(room.height());
+    if side <= 0.0 || state.size < 2 {
+        return;
+    }
+    let board_rect = egui::Rect::from_center_size(room.center(), egui::Vec2::splat(side));
+    painter.rect_filled(board_rect, 2.0, theme.background());
+    let step = side / state.size as f32;
+    let at = |(row, col): (usize, usize)| {
+        board_rect.min + egui::vec2(col as f32 + 0.5, row as f32 + 0.5) * step
+    };
+    let stroke = egui::Stroke::new((step * 0.04).max(1.0), theme.lines());
+    for index in 0..state.size {
+        let last = state.size - 1;
+        painter.line_segment([at((index, 0)), at((index, last))], stroke);
+        painter.line_segment([at((0, index)), at((last, index))], stroke);
+    }
+    for &point in view::star_points(state.size) {
+        painter.circle_filled(at(point), step * 0.1, theme.lines());
+    }
+    let radius = step * 0.47;
+    for (row, line) in state.board.iter().enumerate() {
+        for (col, stone) in line.chars().enumerate() {
+            let color = match stone {
+                'X' => egui::Color32::BLACK,
+                'O' => egui::Color32::WHITE,
+                _ => continue,
+            };
+            painter.circle_filled(at((row, col)), radius, color);
+            painter.circle_stroke(
+                at((row, col)),
+                radius,
+                egui::Stroke::new(1.0, egui::Color32::DARK_GRAY),
+            );
+        }
+    }
+    if let Some(Some(point)) = state
+        .moves
+        .last()
+        .and_then(|vertex| notation::parse_point(vertex, state.size))
+    {
+        painter.circle_stroke(
+            at(point),
+            radius * 0.5,
+            egui::Stroke::new((step * 0.08).max(1.5), egui::Color32::RED),
+        );
+    }
+}
@@ -0,0 +1,273 @@
+//! Endgame values: what each play on the boundaries is worth, counted the miai way, and whether
+//! it is sente. Every play is read out locally for both players, and the empty points counted
+//! for the side whose stones are nearer.
+
+use std::collections::VecDeque;
+
+use crate::board::{Board, Player, Stone};
+use crate::consts;
+use crate::game_info::Rules;
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum Status {
+    Gote,
+    // Sente for this player, and reverse sente for the other
+    Sente(Player),
+    DoubleSente,
+}
+
+impl Status {
+    pub fn name(self) -> &'static str {
+        match self {
+            Status::Gote => "Gote",
+            Status::Sente(Player::Black) => "Sente for Black",
+            Status::Sente(Player::White) => "Sente for White",
+            Status::DoubleSente => "Double sente",
+        }
+    }
+}
+
+pub struct Play {
+    pub point: (usize, usize),
+    // In miai counting: half the swing for a gote play, the whole swing for a sente one
+    pub value: f32,
+    pub status: Status,
+}
+
+impl Play {
+    // A sente play is worth about as much as a gote play of twice its value
+    fn priority(&self) -> f32 {
+        match self.status {
+            Status::Gote => self.value,
+            _ => self.value * 2.0,
+        }
+    }
+}
+
+// A position reached by local play, with how many more stones Black played than White to get
+// there: under territory counting a stone played is a point of one's own filled in
+#[derive(Clone)]
+struct Line {
+    board: Board,
+    stones: i32,
+    // The points around the play being read, the only ones counted
+    window: Window,
+}
+
+// The first and last rows and columns of part of the board
+type Window = ((usize, usize), (usize, usize));
+
+fn around((row, col): (usize, usize), reach: usize, size: usize) -> Window {
+    (
+        (row.saturating_sub(reach), col.saturating_sub(reach)),
+        ((row + reach).min(size - 1), (col + reach).min(size - 1)),
+    )
+}
+
+impl Line {
+    // Black's points less White's
+    fn count(&self, rules: Rules) -> i32 {
+        let area: i32 = owners(&self.board, self.window)
+            .iter()
+            .flatten()
+            .map(|owner| match owner {
+                Stone::Black => 1,
+                Stone::White => -1,
+                Stone::Empty => 0,
+            })
+            .sum();
+        match rules {
+            Rules::Japanese => area - self.stones,
+            Rules::Chinese => area,
+        }
+    }
+
+    fn then(&self, (row, col): (usize, usize), player: Player) -> Option<Line> {
+        if !self.board.is_legal(row, col, player) {
+            return None;
+        }
+        let mut line = self.clone();
+        line.board.play(row, col, player);
+        line.stones += match player {
+            Player::Black => 1,
+            Player::White => -1,
+        };
+        Some(line)
+    }
+}
+
+/// The endgame plays between `from` and `to`, biggest first, in points of `rules`.
+pub fn plays(board: &Board, rules: Rules, from: (usize, usize), to: (usize, usize)) -> Vec<Play> {
+    let size = board.size();
+    let owner = owners(board, ((0, 0), (size - 1, size - 1)));
+    let mut plays = Vec::new();
+    for row in from.0.min(to.0)..=from.0.max(to.0) {
+        for col in from.1.min(to.1)..=from.1.max(to.1) {
+            let start = Line {
+                board: board.clone(),
+                stones: 0,
+                window: around((row, col), consts::YOSE_COUNTED, size),
+            };
+            if unsettled(board, &owner, (row, col))
+                && let Some(play) = read(&start, rules, (row, col))
+            {
+                plays.push(play);
+            }
+        }
+    }
+    plays.sort_by(|a, b| b.priority().total_cmp(&a.priority()));
+    plays
+}
+
+// Every stone in `window` belongs to its side, and every empty point to the side whose stones
+// there are fewer steps away through empty points; `Empty` when they are as near, and outside
+fn owners(board: &Board, ((top, left), (bottom, right)): Window) -> Vec<Vec<Stone>> {
+    let size = board.size();
+    let inside =
+        |(row, col): (usize, usize)| (top..=bottom).contains(&row) && (left..=right).contains(&col);
+    let distances = |stone: Stone| {
+        let mut distance = vec![vec![usize::MAX; size]; size];
+        let mut queue = VecDeque::new();
+        for row in top..=bottom {
+            for col in left..=right {
+                if board[row][col] == stone {
+                    distance[row][col] = 0;
+                    queue.push_back((row, col));
+                }
+            }
+        }
+        while let Some((row, col)) = queue.pop_front() {
+            for (r, c) in board.get_neighbors(row, col) {
+                if inside((r, c)) && board[r][c] == Stone::Empty && distance[r][c] == usize::MAX {
+                    distance[r][c] = distance[row][col] + 1;
+                    queue.push_back((r, c));
+                }
+            }
+        }
+        distance
+    };
+    let black = distances(Stone::Black);
+    let white = distances(Stone::White);
+    let mut owner = vec![vec![Stone::Empty; size]; size];
+    for row in top..=bottom {
+        for col in left..=right {
+            owner[row][col] = match board[row][col] {
+                Stone::Empty if black[row][col] < white[row][col] => Stone::Black,
+                Stone::Empty if white[row][col] < black[row][col] => Stone::White,
+                stone => stone,
+            };
+        }
+    }
+    owner
+}
+
+// An empty point next to a stone that neither side has settled: it is as near to both, or it
+// borders the other side's
+fn unsettled(board: &Board, owner: &[Vec<Stone>], (row, col): (usize, usize)) -> bool {
+    let neighbors = board.get_neighbors(row, col);
+    board[row][col] == Stone::Empty
+        && neighbors.iter().any(|&(r, c)| board[r][c] != Stone::Empty)
+        && (owner[row][col] == Stone::Empty
+            || neighbors
+                .iter()
+                .any(|&(r, c)| owner[r][c] != owner[row][col]))
+}
+
+// The count once both players have played out the unsettled points near `point`, `player`
+// first, each stopping when nothing there gains them more; read `depth` plays ahead
+fn settle(
+    line: &Line,
+    rules: Rules,
+    point: (usize, usize),
+    player: Player,
+    depth: usize,
+    passed: bool,
+    (mut alpha, mut beta): (i32, i32),
+) -> i32 {
+    let stop = line.count(rules);
+    if depth == 0 {
+        return stop;
+    }
+    // Stopping here counts as it stands, unless the other player plays on
+    let mut best = if passed {
+        stop
+    } else {
+        settle(
+            line,
+            rules,
+            point,
+            player.other(),
+            depth - 1,
+            true,
+            (alpha, beta),
+        )
+    };
+    let ((top, left), (bottom, right)) = around(point, consts::YOSE_REACH, line.board.size());
+    let owner = owners(&line.board, line.window);
+    for r in top..=bottom {
+        for c in left..=right {
+            match player {
+                Player::Black => alpha = alpha.max(best),
+                Player::White => beta = beta.min(best),
+            }
+            if alpha >= beta {
+                return best;
+            }
+            if !unsettled(&line.board, &owner, (r, c)) {
+                continue;
+            }
+            let Some(next) = line.then((r, c), player) else {
+                continue;
+            };
+            let value = settle(
+                &next,
+                rules,
+                point,
+                player.other(),
+                depth - 1,
+                false,
+                (alpha, beta),
+            );
+            best = match player {
+                Player::Black => best.max(value),
+                Player::White => best.min(value),
+            };
+        }
+    }
+    best
+}
+
+// Reads `point` for both players. After each player's play the other answers, or leaves it for
+// the player to play again; the play is sente when what it threatens that way is bigger than the
+// play itself.
+fn read(start: &Line, rules: Rules, point: (usize, usize)) -> Option<Play> {
+    let black = start.then(point, Player::Black)?;
+    let white = start.then(point, Player::White)?;
+    let depth = consts::YOSE_DEPTH;
+    let window = (i32::MIN, i32::MAX);
+    let after =
+        |line: &Line, player: Player| settle(line, rules, point, player, depth, false, window);
+    let black_answered = after(&black, Player::White);
+    let white_answered = after(&white, Player::Black);
+    let gote = (black_answered - white_answered) as f32 / 2.0;
+    if gote <= 0.0 {
+        return None;
+    }
+    let black_threat = (after(&black, Player::Black) - black_answered) as f32 / 2.0;
+    let white_threat = (white_answered - after(&white, Player::White)) as f32 / 2.0;
+    let status = match (black_threat > gote, white_threat > gote) {
+        (false, false) => Status::Gote,
+        (true, false) => Status::Sente(Player::Black),
+        (false, true) => Status::Sente(Player::White),
+        (true, true) => Status::DoubleSente,
+    };
+    let value = match status {
+        Status::Gote => gote,
+        _ => gote * 2.0,
+    };
+    Some(Play {
+        point,
+        value,
+        status,
+    })
+}